@@ -11,7 +11,8 @@
 //! - **Parameterized**: Actions with key-value parameters for context passing
 //! - **Conditional**: Actions that evaluate conditions before execution
 //! - **Multiple**: Collections of actions for parallel execution or choice points
-//! - **Prioritized**: Actions with explicit priority ordering
+//! - **Prioritized**: Actions with explicit priority ordering; flows take the
+//!   highest-priority branch of a `Multiple` action that has a route
 //! - **WithMetadata**: Actions carrying additional execution metadata
 //!
 //! ### Conditions
@@ -256,6 +257,26 @@ impl Action {
     pub fn is_multiple(&self) -> bool {
         matches!(self, Action::Multiple(_))
     }
+
+    /// Get the branches of this action, highest priority first
+    ///
+    /// `Multiple` actions are flattened; branches without a priority count as 0
+    /// and keep their original order among equal priorities. Any other action
+    /// is its own single branch.
+    pub fn branches(&self) -> Vec<&Action> {
+        fn collect<'a>(action: &'a Action, out: &mut Vec<&'a Action>) {
+            match action {
+                Action::Multiple(actions) => actions.iter().for_each(|a| collect(a, out)),
+                _ => out.push(action),
+            }
+        }
+
+        let mut branches = Vec::new();
+        collect(self, &mut branches);
+        // Stable sort keeps declaration order for ties
+        branches.sort_by_key(|branch| std::cmp::Reverse(branch.priority().unwrap_or(0)));
+        branches
+    }
 }
 
 impl ActionCondition {
@@ -587,6 +608,21 @@ mod tests {
         assert_eq!(action.to_string(), "important@10");
     }
 
+    #[test]
+    fn test_branches_ordered_by_priority() {
+        let action = Action::multiple(vec![
+            Action::simple("plain"),
+            Action::with_priority(Action::simple("low"), -1),
+            Action::multiple(vec![Action::with_priority(Action::simple("urgent"), 10)]),
+            Action::with_priority(Action::simple("high"), 5),
+            Action::with_priority(Action::simple("also_high"), 5),
+        ]);
+
+        let names: Vec<String> = action.branches().iter().map(|a| a.name()).collect();
+        assert_eq!(names, ["urgent", "high", "also_high", "plain", "low"]);
+        assert_eq!(Action::simple("solo").branches().len(), 1);
+    }
+
     #[test]
     fn test_action_with_metadata() {
         let mut metadata = HashMap::new();
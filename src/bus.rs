@@ -0,0 +1,161 @@
+//! In-process message bus for flows running concurrently
+//!
+//! [`AgentBus`] is a topic-based publish/subscribe hub. Every subscriber of a
+//! topic receives its own copy of each message published after it subscribed,
+//! so several agent flows can talk to each other while running as separate
+//! tasks. Clones of a bus share the same topics.
+//!
+//! ```rust
+//! # use pocketflow_rs::bus::AgentBus;
+//! # use serde_json::json;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let bus = AgentBus::new();
+//! let mut inbox = bus.subscribe("guesser");
+//!
+//! bus.publish_from("guesser", "hinter", json!("It's a fruit"));
+//!
+//! let message = inbox.recv().await.unwrap();
+//! assert_eq!(message.from.as_deref(), Some("hinter"));
+//! assert_eq!(message.payload, json!("It's a fruit"));
+//! # }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/// A message delivered over an [`AgentBus`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusMessage {
+    /// Topic the message was published on
+    pub topic: String,
+    /// Name of the sending agent, if it identified itself
+    pub from: Option<String>,
+    /// Message body
+    pub payload: Value,
+}
+
+/// Topic-based async pub/sub shared between flows
+#[derive(Debug, Clone, Default)]
+pub struct AgentBus {
+    topics: Arc<Mutex<HashMap<String, Vec<UnboundedSender<BusMessage>>>>>,
+}
+
+impl AgentBus {
+    /// Create an empty bus
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to a topic, receiving every message published from now on
+    pub fn subscribe(&self, topic: impl Into<String>) -> Subscription {
+        let topic = topic.into();
+        let (sender, receiver) = unbounded_channel();
+        self.lock().entry(topic.clone()).or_default().push(sender);
+        Subscription { topic, receiver }
+    }
+
+    /// Publish an anonymous message, returning the number of subscribers reached
+    pub fn publish(&self, topic: &str, payload: Value) -> usize {
+        self.send(BusMessage {
+            topic: topic.to_string(),
+            from: None,
+            payload,
+        })
+    }
+
+    /// Publish a message on behalf of a named agent
+    pub fn publish_from(&self, topic: &str, from: impl Into<String>, payload: Value) -> usize {
+        self.send(BusMessage {
+            topic: topic.to_string(),
+            from: Some(from.into()),
+            payload,
+        })
+    }
+
+    /// Deliver a message to all current subscribers of its topic
+    pub fn send(&self, message: BusMessage) -> usize {
+        let mut topics = self.lock();
+        let Some(subscribers) = topics.get_mut(&message.topic) else {
+            return 0;
+        };
+        // Dropped subscriptions are pruned lazily here
+        subscribers.retain(|subscriber| subscriber.send(message.clone()).is_ok());
+        let delivered = subscribers.len();
+        if delivered == 0 {
+            topics.remove(&message.topic);
+        }
+        delivered
+    }
+
+    /// Number of live subscribers of a topic
+    pub fn subscriber_count(&self, topic: &str) -> usize {
+        self.lock()
+            .get(topic)
+            .map(|subscribers| subscribers.iter().filter(|s| !s.is_closed()).count())
+            .unwrap_or(0)
+    }
+
+    /// Topics that currently have subscribers
+    pub fn topics(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Vec<UnboundedSender<BusMessage>>>> {
+        self.topics.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Receiving end of a topic subscription
+///
+/// Messages are queued from the moment of subscribing, so nothing is lost
+/// between subscribing and the first call to [`Subscription::recv`].
+#[derive(Debug)]
+pub struct Subscription {
+    topic: String,
+    receiver: UnboundedReceiver<BusMessage>,
+}
+
+impl Subscription {
+    /// Get the subscribed topic
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Wait for the next message; `None` once every bus handle has been dropped
+    pub async fn recv(&mut self) -> Option<BusMessage> {
+        self.receiver.recv().await
+    }
+
+    /// Take the next queued message without waiting
+    pub fn try_recv(&mut self) -> Option<BusMessage> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_bus_fans_out_per_topic() {
+        let bus = AgentBus::new();
+        let mut first = bus.subscribe("chat");
+        let mut second = bus.clone().subscribe("chat");
+        let mut other = bus.subscribe("other");
+
+        assert_eq!(bus.publish("chat", json!(1)), 2);
+        assert_eq!(first.recv().await.unwrap().payload, json!(1));
+        assert_eq!(second.recv().await.unwrap().payload, json!(1));
+        assert!(other.try_recv().is_none());
+
+        drop(second);
+        assert_eq!(bus.publish("chat", json!(2)), 1);
+        assert_eq!(bus.subscriber_count("chat"), 1);
+        assert_eq!(bus.publish("nobody", json!(3)), 0);
+    }
+}
@@ -0,0 +1,324 @@
+//! Injectable sources of time, randomness and IDs
+//!
+//! Nodes and flows read the clock, draw random numbers and generate execution
+//! IDs through the [`RuntimeEnv`] carried by
+//! [`ExecutionContext`](crate::node::ExecutionContext). The default
+//! environment uses the system clock, OS-seeded randomness and UUIDs;
+//! [`RuntimeEnv::deterministic`] swaps in a manual clock whose `sleep` returns
+//! immediately, a seeded generator and sequential IDs, so runs with retry
+//! delays, simulated failures and generated IDs can be compared against
+//! golden files.
+//!
+//! ```rust
+//! # use pocketflow_rs::env::RuntimeEnv;
+//! # use std::time::Duration;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let env = RuntimeEnv::deterministic(42);
+//! let start = env.now();
+//! env.sleep(Duration::from_secs(60)).await; // returns at once
+//! assert_eq!(env.now() - start, Duration::from_secs(60));
+//! assert_eq!(env.next_id(), "exec-1");
+//! assert_eq!(env.random_f64(), RuntimeEnv::deterministic(42).random_f64());
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Source of the current time and of delays
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// Monotonic time, used for deadlines and durations
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time, used for timestamps
+    fn system_time(&self) -> SystemTime;
+
+    /// Wait for `duration`
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The real clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// A clock that only moves when advanced; `sleep` advances it and returns
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    start_time: SystemTime,
+    elapsed: Mutex<Duration>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new(SystemTime::UNIX_EPOCH)
+    }
+}
+
+impl ManualClock {
+    /// Create a clock frozen at the given wall-clock time
+    pub fn new(start_time: SystemTime) -> Self {
+        Self {
+            start: Instant::now(),
+            start_time,
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    /// Time elapsed since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.start_time + self.elapsed()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/// Source of random numbers
+pub trait RandomSource: Send + Sync {
+    /// Next random `u64`
+    fn next_u64(&self) -> u64;
+
+    /// Next random number in `[0, 1)`
+    fn next_f64(&self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Pseudo-random generator (SplitMix64) with a fixed or random seed
+#[derive(Debug)]
+pub struct SeededRandom {
+    state: AtomicU64,
+}
+
+impl SeededRandom {
+    /// Create a generator producing the same sequence for the same seed
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    /// Create a generator with a random seed
+    pub fn from_entropy() -> Self {
+        let bytes = *uuid::Uuid::new_v4().as_bytes();
+        Self::new(u64::from_le_bytes(bytes[..8].try_into().unwrap()))
+    }
+}
+
+impl RandomSource for SeededRandom {
+    fn next_u64(&self) -> u64 {
+        let mut z = self
+            .state
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Source of unique IDs
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> String;
+}
+
+/// Random UUID v4 IDs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidGenerator;
+
+impl IdGenerator for UuidGenerator {
+    fn next_id(&self) -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+}
+
+/// IDs of the form `{prefix}-1`, `{prefix}-2`, ...
+#[derive(Debug)]
+pub struct SequentialIds {
+    prefix: String,
+    next: AtomicU64,
+}
+
+impl SequentialIds {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            next: AtomicU64::new(1),
+        }
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> String {
+        format!(
+            "{}-{}",
+            self.prefix,
+            self.next.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+}
+
+/// Clock, randomness and ID generation shared by a run
+///
+/// Cloning is cheap and clones share state, so a deterministic environment
+/// set on a flow produces one sequence across all of its nodes.
+#[derive(Clone)]
+pub struct RuntimeEnv {
+    clock: Arc<dyn Clock>,
+    rng: Arc<dyn RandomSource>,
+    ids: Arc<dyn IdGenerator>,
+}
+
+impl std::fmt::Debug for RuntimeEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RuntimeEnv").finish_non_exhaustive()
+    }
+}
+
+impl Default for RuntimeEnv {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            rng: Arc::new(SeededRandom::from_entropy()),
+            ids: Arc::new(UuidGenerator),
+        }
+    }
+}
+
+impl RuntimeEnv {
+    /// Environment backed by the system clock, random seeds and UUIDs
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Environment with a [`ManualClock`] at the Unix epoch, a [`SeededRandom`]
+    /// and [`SequentialIds`] prefixed with `exec`
+    pub fn deterministic(seed: u64) -> Self {
+        Self {
+            clock: Arc::new(ManualClock::default()),
+            rng: Arc::new(SeededRandom::new(seed)),
+            ids: Arc::new(SequentialIds::new("exec")),
+        }
+    }
+
+    /// Set the clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Set the random source
+    pub fn with_rng(mut self, rng: Arc<dyn RandomSource>) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Set the ID generator
+    pub fn with_ids(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Get the clock
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Current monotonic time
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    /// Current wall-clock time
+    pub fn system_time(&self) -> SystemTime {
+        self.clock.system_time()
+    }
+
+    /// Wait for `duration` on the environment's clock
+    pub async fn sleep(&self, duration: Duration) {
+        self.clock.sleep(duration).await;
+    }
+
+    /// Random number in `[0, 1)`
+    pub fn random_f64(&self) -> f64 {
+        self.rng.next_f64()
+    }
+
+    /// Random `u64`
+    pub fn random_u64(&self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    /// Generate a new ID
+    pub fn next_id(&self) -> String {
+        self.ids.next_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_random_is_reproducible() {
+        let a = SeededRandom::new(7);
+        let b = SeededRandom::new(7);
+        let first: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first[0], SeededRandom::new(8).next_u64());
+        assert!((0..1000).all(|_| (0.0..1.0).contains(&a.next_f64())));
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_env() {
+        let env = RuntimeEnv::deterministic(1);
+        let clone = env.clone();
+        assert_eq!(env.system_time(), SystemTime::UNIX_EPOCH);
+        clone.sleep(Duration::from_secs(5)).await;
+        assert_eq!(
+            env.system_time(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(5)
+        );
+        // Clones share the ID sequence
+        assert_eq!(env.next_id(), "exec-1");
+        assert_eq!(clone.next_id(), "exec-2");
+    }
+}
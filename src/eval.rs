@@ -0,0 +1,603 @@
+//! Regression testing of flows over datasets
+//!
+//! An [`EvalCase`] gives the store inputs for one run and the [`Check`]s its
+//! output must pass: exact matches, regular expressions, JSONPath lookups or
+//! an LLM judge. An [`Evaluator`] runs a fresh flow per case, several at once,
+//! and collects an [`EvalReport`] with per-case results and aggregate scores,
+//! so a prompt change can be compared against the previous pass rate.
+//!
+//! Cases serialize as JSON, so datasets can live in files next to the
+//! prompts they test:
+//!
+//! ```json
+//! {"name": "greeting", "inputs": {"text": "hi"},
+//!  "checks": [{"type": "matches", "key": "reply", "pattern": "(?i)hello"}]}
+//! ```
+//!
+//! [`Check::Judge`] runs a judge template, such as
+//! `flows::stdlib::judge_output`, with the checked value in `text` and the
+//! criteria as its `criteria` parameter. The judge writes `verdict`, either a
+//! boolean or `{"pass": bool, "reason": string}`.
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::eval::{Check, EvalCase, Evaluator};
+//! # use serde_json::json;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let evaluator = Evaluator::new(|| {
+//!     FlowBuilder::new()
+//!         .start_node("shout")
+//!         .node("shout", Node::new(FunctionNode::new(
+//!             "shout".to_string(),
+//!             |store: &SharedStore<InMemoryStorage>, _ctx| {
+//!                 store.get("text").ok().flatten().unwrap_or_default()
+//!             },
+//!             |text, _ctx| Ok(text.as_str().unwrap_or_default().to_uppercase()),
+//!             |store, _text, reply, _ctx| {
+//!                 store.set("reply".to_string(), json!(reply))?;
+//!                 Ok(Action::simple("complete"))
+//!             },
+//!         )))
+//!         .build()
+//! });
+//!
+//! let report = evaluator
+//!     .run(vec![
+//!         EvalCase::new("hello")
+//!             .with_input("text", json!("hello"))
+//!             .with_check(Check::equals("reply", json!("HELLO"))),
+//!         EvalCase::new("digits")
+//!             .with_input("text", json!("r2d2"))
+//!             .with_check(Check::matches("reply", "^[A-Z]+$")),
+//!     ])
+//!     .await;
+//! assert_eq!(report.passed_count(), 1);
+//! println!("{}", report);
+//! # }
+//! ```
+
+use crate::SharedStore;
+use crate::flow::{BasicFlow, Flow};
+use crate::flows::FlowTemplate;
+use crate::shared_store::JsonPath;
+use crate::storage::InMemoryStorage;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Store key the judge reads the checked value from
+pub const JUDGE_INPUT_KEY: &str = "text";
+/// Store key the judge writes its verdict to
+pub const JUDGE_VERDICT_KEY: &str = "verdict";
+
+/// A property a case's output must have
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Check {
+    /// The key holds exactly `expected`
+    Equals { key: String, expected: Value },
+    /// The key's text matches a regular expression
+    ///
+    /// Strings are matched as is and other values as JSON.
+    Matches { key: String, pattern: String },
+    /// A JSONPath over the store, whose first name is a store key, selects
+    /// `expected` (or an array of everything it selects, if not exactly one)
+    JsonField { path: String, expected: Value },
+    /// The judge decides whether the key's value meets the criteria
+    Judge { key: String, criteria: String },
+}
+
+impl Check {
+    /// Check that `key` holds exactly `expected`
+    pub fn equals(key: impl Into<String>, expected: Value) -> Self {
+        Check::Equals {
+            key: key.into(),
+            expected,
+        }
+    }
+
+    /// Check that the text of `key` matches `pattern`
+    pub fn matches(key: impl Into<String>, pattern: impl Into<String>) -> Self {
+        Check::Matches {
+            key: key.into(),
+            pattern: pattern.into(),
+        }
+    }
+
+    /// Check that the JSONPath `path` selects `expected`
+    pub fn json_field(path: impl Into<String>, expected: Value) -> Self {
+        Check::JsonField {
+            path: path.into(),
+            expected,
+        }
+    }
+
+    /// Ask the judge whether the value of `key` meets `criteria`
+    pub fn judge(key: impl Into<String>, criteria: impl Into<String>) -> Self {
+        Check::Judge {
+            key: key.into(),
+            criteria: criteria.into(),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::Equals { key, .. } => write!(f, "equals({})", key),
+            Check::Matches { key, pattern } => write!(f, "matches({}, /{}/)", key, pattern),
+            Check::JsonField { path, .. } => write!(f, "json_field({})", path),
+            Check::Judge { key, .. } => write!(f, "judge({})", key),
+        }
+    }
+}
+
+/// Inputs for one run and the checks its output must pass
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalCase {
+    pub name: String,
+    /// Values written to the store before the flow runs
+    #[serde(default)]
+    pub inputs: Map<String, Value>,
+    #[serde(default)]
+    pub checks: Vec<Check>,
+}
+
+impl EvalCase {
+    /// Create a case without inputs or checks
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            inputs: Map::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Write `value` to `key` before the run
+    pub fn with_input(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.inputs.insert(key.into(), value);
+        self
+    }
+
+    /// Add a check
+    pub fn with_check(mut self, check: Check) -> Self {
+        self.checks.push(check);
+        self
+    }
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    pub check: Check,
+    pub passed: bool,
+    /// Why the check failed, or the judge's reasoning
+    pub detail: Option<String>,
+}
+
+/// Outcome of one case
+#[derive(Debug)]
+pub struct CaseReport {
+    pub name: String,
+    /// Check results, empty if the flow failed
+    pub checks: Vec<CheckResult>,
+    /// The flow's error, if it failed
+    pub error: Option<String>,
+    /// The store after the run
+    pub store: SharedStore<InMemoryStorage>,
+    pub duration: Duration,
+}
+
+impl CaseReport {
+    /// Whether the flow completed and every check passed
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.checks.iter().all(|check| check.passed)
+    }
+
+    /// Fraction of checks passed, 0 if the flow failed
+    pub fn score(&self) -> f64 {
+        if self.error.is_some() {
+            return 0.0;
+        }
+        match self.checks.len() {
+            0 => 1.0,
+            count => self.checks.iter().filter(|check| check.passed).count() as f64 / count as f64,
+        }
+    }
+}
+
+/// Per-case results, in input order, and aggregate scores
+#[derive(Debug)]
+pub struct EvalReport {
+    pub cases: Vec<CaseReport>,
+    /// Wall-clock time for the whole evaluation
+    pub duration: Duration,
+}
+
+impl EvalReport {
+    /// Number of cases that passed every check
+    pub fn passed_count(&self) -> usize {
+        self.cases.iter().filter(|case| case.passed()).count()
+    }
+
+    /// Number of cases that failed a check or whose flow failed
+    pub fn failed_count(&self) -> usize {
+        self.cases.len() - self.passed_count()
+    }
+
+    /// Fraction of cases that passed, 0 if there are none
+    pub fn pass_rate(&self) -> f64 {
+        match self.cases.len() {
+            0 => 0.0,
+            count => self.passed_count() as f64 / count as f64,
+        }
+    }
+
+    /// Average case score, 0 if there are none
+    pub fn mean_score(&self) -> f64 {
+        match self.cases.len() {
+            0 => 0.0,
+            count => self.cases.iter().map(CaseReport::score).sum::<f64>() / count as f64,
+        }
+    }
+
+    /// Cases that didn't pass
+    pub fn failures(&self) -> impl Iterator<Item = &CaseReport> {
+        self.cases.iter().filter(|case| !case.passed())
+    }
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}/{} cases passed ({:.1}%), mean score {:.2}",
+            self.passed_count(),
+            self.cases.len(),
+            self.pass_rate() * 100.0,
+            self.mean_score()
+        )?;
+        for case in self.failures() {
+            if let Some(error) = &case.error {
+                writeln!(f, "FAIL {}: flow error: {}", case.name, error)?;
+            }
+            for check in case.checks.iter().filter(|check| !check.passed) {
+                let detail = check.detail.as_deref().unwrap_or("failed");
+                writeln!(f, "FAIL {}: {}: {}", case.name, check.check, detail)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+type FlowFactory = Arc<dyn Fn() -> BasicFlow<InMemoryStorage> + Send + Sync>;
+
+/// Runs a flow over eval cases and scores the results
+#[derive(Clone)]
+pub struct Evaluator {
+    factory: FlowFactory,
+    judge: Option<FlowTemplate<InMemoryStorage>>,
+    concurrency: usize,
+}
+
+impl fmt::Debug for Evaluator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Evaluator")
+            .field("judge", &self.judge.as_ref().map(FlowTemplate::name))
+            .field("concurrency", &self.concurrency)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Evaluator {
+    /// Create an evaluator from a factory producing a fresh flow per case
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn() -> BasicFlow<InMemoryStorage> + Send + Sync + 'static,
+    {
+        Self {
+            factory: Arc::new(factory),
+            judge: None,
+            concurrency: 8,
+        }
+    }
+
+    /// Set how many cases may run at once (default: 8)
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set the template [`Check::Judge`] runs, e.g.
+    /// `flows::stdlib::judge_output`
+    pub fn with_judge(mut self, judge: FlowTemplate<InMemoryStorage>) -> Self {
+        self.judge = Some(judge);
+        self
+    }
+
+    /// Run and score every case
+    ///
+    /// A failing case doesn't stop the others; its error is reported in its
+    /// [`CaseReport`].
+    pub async fn run(&self, cases: Vec<EvalCase>) -> EvalReport {
+        let started = Instant::now();
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let mut tasks = JoinSet::new();
+
+        for (index, case) in cases.into_iter().enumerate() {
+            let semaphore = semaphore.clone();
+            let evaluator = self.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore
+                    .acquire_owned()
+                    .await
+                    .expect("eval semaphore is never closed");
+                (index, evaluator.run_case(case).await)
+            });
+        }
+
+        let mut cases = Vec::with_capacity(tasks.len());
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok(case) => cases.push(case),
+                // Surface node panics to the caller, as a sequential run would
+                Err(error) => std::panic::resume_unwind(error.into_panic()),
+            }
+        }
+        cases.sort_by_key(|(index, _)| *index);
+
+        EvalReport {
+            cases: cases.into_iter().map(|(_, case)| case).collect(),
+            duration: started.elapsed(),
+        }
+    }
+
+    async fn run_case(&self, case: EvalCase) -> CaseReport {
+        let started = Instant::now();
+        let mut store = SharedStore::new();
+        store
+            .set_many(case.inputs.into_iter().collect())
+            .expect("in-memory storage does not fail");
+
+        let mut flow = (self.factory)();
+        let (checks, error) = match flow.execute(&mut store).await {
+            Ok(_) => {
+                let mut results = Vec::with_capacity(case.checks.len());
+                for check in case.checks {
+                    results.push(self.score(check, &store).await);
+                }
+                (results, None)
+            }
+            Err(error) => (Vec::new(), Some(error.to_string())),
+        };
+        CaseReport {
+            name: case.name,
+            checks,
+            error,
+            store,
+            duration: started.elapsed(),
+        }
+    }
+
+    async fn score(&self, check: Check, store: &SharedStore<InMemoryStorage>) -> CheckResult {
+        let outcome = match &check {
+            Check::Equals { key, expected } => lookup(store, key).map(|actual| {
+                verdict(
+                    actual == *expected,
+                    format!("expected {}, got {}", expected, actual),
+                )
+            }),
+            Check::Matches { key, pattern } => regex::Regex::new(pattern)
+                .map_err(|e| format!("invalid pattern: {}", e))
+                .and_then(|regex| {
+                    let text = text_of(&lookup(store, key)?);
+                    Ok(verdict(
+                        regex.is_match(&text),
+                        format!("{:?} does not match", text),
+                    ))
+                }),
+            Check::JsonField { path, expected } => JsonPath::parse(path)
+                .map_err(|e| format!("invalid path: {}", e))
+                .map(|path| {
+                    let root = Value::Object(
+                        store
+                            .iter()
+                            .expect("in-memory storage does not fail")
+                            .collect(),
+                    );
+                    let actual = match path.select(&root).as_slice() {
+                        [value] => (*value).clone(),
+                        values => Value::Array(values.iter().map(|v| (*v).clone()).collect()),
+                    };
+                    verdict(
+                        actual == *expected,
+                        format!("expected {}, got {}", expected, actual),
+                    )
+                }),
+            Check::Judge { key, criteria } => match lookup(store, key) {
+                Ok(value) => self.judge(criteria, value).await,
+                Err(e) => Err(e),
+            },
+        };
+        let (passed, detail) = match outcome {
+            Ok((passed, detail)) => (passed, detail),
+            Err(detail) => (false, Some(detail)),
+        };
+        CheckResult {
+            check,
+            passed,
+            detail,
+        }
+    }
+
+    async fn judge(&self, criteria: &str, value: Value) -> Result<(bool, Option<String>), String> {
+        let judge = self.judge.as_ref().ok_or("no judge configured")?;
+        let params = HashMap::from([("criteria".to_string(), json!(criteria))]);
+        let mut flow = judge.instantiate(params).map_err(|e| e.to_string())?;
+        let mut store = SharedStore::new();
+        store
+            .set(JUDGE_INPUT_KEY.to_string(), json!(text_of(&value)))
+            .expect("in-memory storage does not fail");
+        flow.execute(&mut store)
+            .await
+            .map_err(|e| format!("judge failed: {}", e))?;
+
+        match store.get(JUDGE_VERDICT_KEY).ok().flatten() {
+            Some(Value::Bool(pass)) => Ok((pass, None)),
+            Some(Value::Object(verdict)) => {
+                let pass = verdict.get("pass").and_then(Value::as_bool);
+                let reason = verdict.get("reason").and_then(Value::as_str);
+                pass.map(|pass| (pass, reason.map(str::to_string)))
+                    .ok_or_else(|| "judge verdict has no boolean 'pass'".to_string())
+            }
+            _ => Err(format!("judge wrote no '{}'", JUDGE_VERDICT_KEY)),
+        }
+    }
+}
+
+/// The value of `key`, or why it is missing
+fn lookup(store: &SharedStore<InMemoryStorage>, key: &str) -> Result<Value, String> {
+    store
+        .get(key)
+        .ok()
+        .flatten()
+        .ok_or_else(|| format!("key '{}' not found", key))
+}
+
+/// Strings as is, other values as JSON
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A check outcome, with `detail` kept only on failure
+fn verdict(passed: bool, detail: String) -> (bool, Option<String>) {
+    (passed, (!passed).then_some(detail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Action;
+    use crate::flow::FlowBuilder;
+    use crate::node::{FunctionNode, Node};
+
+    /// Replies "Hello, {name}!" to `name`, failing on an empty name
+    fn greeter() -> BasicFlow<InMemoryStorage> {
+        FlowBuilder::new()
+            .start_node("greet")
+            .node(
+                "greet",
+                Node::new(FunctionNode::new(
+                    "greet".to_string(),
+                    |store: &SharedStore<InMemoryStorage>, _ctx| {
+                        store.get("name").ok().flatten().unwrap_or_default()
+                    },
+                    |name, _ctx| match name.as_str() {
+                        Some(name) if !name.is_empty() => Ok(name.to_string()),
+                        _ => Err("no name".into()),
+                    },
+                    |store, _prep, name, _ctx| {
+                        store.set("reply".to_string(), json!(format!("Hello, {}!", name)))?;
+                        store.set("meta".to_string(), json!({"length": name.len()}))?;
+                        Ok(Action::simple("complete"))
+                    },
+                )),
+            )
+            .build()
+    }
+
+    /// Passes texts containing "Ada"
+    fn judge() -> FlowTemplate<InMemoryStorage> {
+        FlowTemplate::new("contains_ada", |_params| {
+            Ok(FlowBuilder::new()
+                .start_node("judge")
+                .node(
+                    "judge",
+                    Node::new(FunctionNode::new(
+                        "judge".to_string(),
+                        |store: &SharedStore<InMemoryStorage>, _ctx| {
+                            store.get(JUDGE_INPUT_KEY).unwrap().unwrap()
+                        },
+                        |text, _ctx| Ok(text.as_str().unwrap().contains("Ada")),
+                        |store, _prep, pass, _ctx| {
+                            let verdict = json!({"pass": pass, "reason": "looked for Ada"});
+                            store.set(JUDGE_VERDICT_KEY.to_string(), verdict)?;
+                            Ok(Action::simple("complete"))
+                        },
+                    )),
+                )
+                .build())
+        })
+        .param("criteria", "Ignored")
+    }
+
+    #[tokio::test]
+    async fn test_evaluator_scores_cases() {
+        let cases: Vec<EvalCase> = serde_json::from_value(json!([
+            {"name": "ada", "inputs": {"name": "Ada"}, "checks": [
+                {"type": "equals", "key": "reply", "expected": "Hello, Ada!"},
+                {"type": "matches", "key": "reply", "pattern": "^Hello"},
+                {"type": "json_field", "path": "$.meta.length", "expected": 3},
+                {"type": "judge", "key": "reply", "criteria": "mentions Ada"}
+            ]},
+            {"name": "bob", "inputs": {"name": "Bob"}, "checks": [
+                {"type": "equals", "key": "reply", "expected": "Hello, Bob!"},
+                {"type": "judge", "key": "reply", "criteria": "mentions Ada"},
+                {"type": "matches", "key": "missing", "pattern": "."}
+            ]},
+            {"name": "empty", "inputs": {"name": ""}, "checks": [
+                {"type": "equals", "key": "reply", "expected": "Hello!"}
+            ]}
+        ]))
+        .unwrap();
+
+        let report = Evaluator::new(greeter)
+            .with_judge(judge())
+            .with_concurrency(2)
+            .run(cases)
+            .await;
+
+        let names: Vec<_> = report.cases.iter().map(|case| case.name.as_str()).collect();
+        assert_eq!(names, ["ada", "bob", "empty"]);
+        assert!(report.cases[0].passed());
+        assert_eq!(report.passed_count(), 1);
+        assert!((report.cases[1].score() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(
+            report.cases[1].checks[1].detail.as_deref(),
+            Some("looked for Ada")
+        );
+        assert_eq!(
+            report.cases[1].checks[2].detail.as_deref(),
+            Some("key 'missing' not found")
+        );
+        assert!(report.cases[2].error.is_some());
+        assert_eq!(report.cases[2].score(), 0.0);
+
+        let summary = report.to_string();
+        assert!(summary.starts_with("1/3 cases passed (33.3%)"));
+        assert!(summary.contains("FAIL bob: judge(reply): looked for Ada"));
+        assert!(summary.contains("FAIL empty: flow error:"));
+
+        // Judge checks fail without a judge
+        let report = Evaluator::new(greeter)
+            .run(vec![
+                EvalCase::new("ada")
+                    .with_input("name", json!("Ada"))
+                    .with_check(Check::judge("reply", "mentions Ada")),
+            ])
+            .await;
+        assert_eq!(
+            report.cases[0].checks[0].detail.as_deref(),
+            Some("no judge configured")
+        );
+    }
+}
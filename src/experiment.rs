@@ -0,0 +1,455 @@
+//! Online A/B experiments over flow variants
+//!
+//! An [`Experiment`] holds weighted [`Variant`]s, each with the settings it
+//! tests (prompt version, model, temperature, ...). Pick a variant per user
+//! with [`Experiment::assign`] (sticky) or per request with
+//! [`Experiment::choose`], then execute the flow with [`Experiment::run`]:
+//! the variant is written to the store, where nodes read it with
+//! `SharedStore::experiment`, and the run's outcome, duration and token usage
+//! are recorded. Outcomes known later, such as a user rating, are attached
+//! with [`Experiment::record_metric`]. [`Experiment::summary`] then compares
+//! the variants.
+//!
+//! Clones share recorded runs, so one experiment can be cloned into every
+//! request handler.
+//!
+//! ```rust
+//! # use pocketflow_rs::experiment::{Experiment, Variant};
+//! # use pocketflow_rs::prelude::*;
+//! # use serde_json::json;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let experiment = Experiment::new("summary-prompt")
+//!     .with_variant(Variant::new("control").with_config("prompt_version", json!(3)))
+//!     .with_variant(
+//!         Variant::new("terse")
+//!             .with_weight(1)
+//!             .with_config("prompt_version", json!(4))
+//!             .with_config("temperature", json!(0.2)),
+//!     );
+//!
+//! let variant = experiment.assign("user-42").unwrap().name.clone();
+//! let mut flow = FlowBuilder::new()
+//!     .start_node("answer")
+//!     .node("answer", Node::new(LogNode::new("answering", Action::simple("complete"))))
+//!     .build();
+//! let mut store = SharedStore::new();
+//! let run = experiment.run(&variant, &mut flow, &mut store).await.unwrap();
+//! experiment.record_metric(&run.id, "rating", 4.0);
+//!
+//! assert_eq!(store.experiment().unwrap().unwrap().variant, variant);
+//! for summary in experiment.summary() {
+//!     println!("{}: {} runs, {:?}", summary.variant, summary.runs, summary.mean_metric("rating"));
+//! }
+//! # }
+//! ```
+
+use crate::env::RuntimeEnv;
+use crate::flow::{Flow, FlowError};
+use crate::shared_store::{ExperimentTag, SystemKeys, TokenUsage};
+use crate::{SharedStore, StorageBackend};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// One arm of an experiment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    /// Relative share of traffic
+    pub weight: u32,
+    /// Settings under test, passed to nodes in the [`ExperimentTag`]
+    pub config: Map<String, Value>,
+}
+
+impl Variant {
+    /// Create a variant with weight 1 and no settings
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            weight: 1,
+            config: Map::new(),
+        }
+    }
+
+    /// Set the relative share of traffic
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Add a setting
+    pub fn with_config(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.config.insert(key.into(), value);
+        self
+    }
+}
+
+/// A recorded experiment run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentRun {
+    pub id: String,
+    pub variant: String,
+    /// Whether the flow reached a terminal action
+    pub success: bool,
+    pub final_action: Option<String>,
+    pub error: Option<String>,
+    pub steps: usize,
+    pub duration: Duration,
+    /// LLM usage recorded in the store during the run
+    pub usage: TokenUsage,
+    /// Outcomes attached with [`Experiment::record_metric`]
+    pub metrics: HashMap<String, f64>,
+}
+
+/// Aggregate statistics of one variant
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantSummary {
+    pub variant: String,
+    pub runs: usize,
+    pub successes: usize,
+    /// Total time spent in runs
+    pub duration: Duration,
+    /// Total LLM usage
+    pub usage: TokenUsage,
+    /// Sum and count of each metric over the runs that recorded it
+    pub metrics: HashMap<String, (f64, usize)>,
+}
+
+impl VariantSummary {
+    /// Fraction of runs that succeeded, 0 without runs
+    pub fn success_rate(&self) -> f64 {
+        match self.runs {
+            0 => 0.0,
+            runs => self.successes as f64 / runs as f64,
+        }
+    }
+
+    /// Average run duration
+    pub fn mean_duration(&self) -> Duration {
+        match self.runs {
+            0 => Duration::ZERO,
+            runs => self.duration / runs as u32,
+        }
+    }
+
+    /// Average tokens per run, 0 without runs
+    pub fn mean_tokens(&self) -> f64 {
+        match self.runs {
+            0 => 0.0,
+            runs => self.usage.total_tokens() as f64 / runs as f64,
+        }
+    }
+
+    /// Average estimated cost per run in US dollars, 0 without runs
+    pub fn mean_cost(&self) -> f64 {
+        match self.runs {
+            0 => 0.0,
+            runs => self.usage.cost / runs as f64,
+        }
+    }
+
+    /// Average of a metric over the runs that recorded it
+    pub fn mean_metric(&self, name: &str) -> Option<f64> {
+        self.metrics
+            .get(name)
+            .map(|(sum, count)| sum / *count as f64)
+    }
+}
+
+/// Weighted variants and the runs recorded for them
+#[derive(Debug, Clone)]
+pub struct Experiment {
+    name: String,
+    variants: Vec<Variant>,
+    env: RuntimeEnv,
+    runs: Arc<Mutex<Vec<ExperimentRun>>>,
+}
+
+impl Experiment {
+    /// Create an experiment without variants
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            variants: Vec::new(),
+            env: RuntimeEnv::default(),
+            runs: Arc::default(),
+        }
+    }
+
+    /// Add a variant
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variants.push(variant);
+        self
+    }
+
+    /// Set the clock, randomness and run IDs, e.g. [`RuntimeEnv::deterministic`]
+    /// in tests
+    pub fn with_env(mut self, env: RuntimeEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// The experiment name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The variants, in the order they were added
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    /// The variant with the given name
+    pub fn variant(&self, name: &str) -> Option<&Variant> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+
+    /// Always the same variant for the same unit (user, session, ...),
+    /// weighted by share of traffic
+    ///
+    /// `None` if no variant has a weight.
+    pub fn assign(&self, unit: &str) -> Option<&Variant> {
+        let hash = format!("{}:{}", self.name, unit)
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        self.pick(hash)
+    }
+
+    /// A random variant, weighted by share of traffic
+    ///
+    /// `None` if no variant has a weight.
+    pub fn choose(&self) -> Option<&Variant> {
+        self.pick(self.env.random_u64())
+    }
+
+    fn pick(&self, roll: u64) -> Option<&Variant> {
+        let total: u64 = self.variants.iter().map(|v| u64::from(v.weight)).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = roll % total;
+        self.variants.iter().find(|variant| {
+            let weight = u64::from(variant.weight);
+            if roll < weight {
+                return true;
+            }
+            roll -= weight;
+            false
+        })
+    }
+
+    /// Execute `flow` as `variant` and record the run
+    ///
+    /// Failed flows are recorded too; the returned run carries their error.
+    /// Only an unknown variant or a store error fails the call.
+    pub async fn run<S, F>(
+        &self,
+        variant: &str,
+        flow: &mut F,
+        store: &mut SharedStore<S>,
+    ) -> Result<ExperimentRun, FlowError>
+    where
+        S: StorageBackend + Send + Sync,
+        F: Flow<S> + Send,
+    {
+        let variant = self.variant(variant).ok_or_else(|| {
+            FlowError::InvalidConfiguration(format!(
+                "Experiment '{}' has no variant '{}'",
+                self.name, variant
+            ))
+        })?;
+        let storage_error = |e: S::Error| FlowError::NodeError(e.to_string());
+        let tag = ExperimentTag {
+            experiment: self.name.clone(),
+            variant: variant.name.clone(),
+            config: variant.config.clone(),
+        };
+        let tag = serde_json::to_value(tag).expect("ExperimentTag serializes to JSON");
+        store
+            .set_system(SystemKeys::EXPERIMENT, tag)
+            .map_err(storage_error)?;
+
+        let usage_before = store.usage().map_err(storage_error)?;
+        let started = self.env.now();
+        let outcome = flow.execute(store).await;
+        let duration = self.env.now() - started;
+        let usage_after = store.usage().map_err(storage_error)?;
+
+        let run = ExperimentRun {
+            id: self.env.next_id(),
+            variant: variant.name.clone(),
+            success: outcome.is_ok(),
+            final_action: outcome.as_ref().ok().map(|r| r.final_action.name()),
+            error: outcome.as_ref().err().map(ToString::to_string),
+            steps: outcome.as_ref().map_or(0, |r| r.steps_executed),
+            duration,
+            usage: TokenUsage {
+                requests: usage_after.requests.saturating_sub(usage_before.requests),
+                input_tokens: usage_after
+                    .input_tokens
+                    .saturating_sub(usage_before.input_tokens),
+                output_tokens: usage_after
+                    .output_tokens
+                    .saturating_sub(usage_before.output_tokens),
+                cost: (usage_after.cost - usage_before.cost).max(0.0),
+            },
+            metrics: HashMap::new(),
+        };
+        self.lock().push(run.clone());
+        Ok(run)
+    }
+
+    /// Attach an outcome to a recorded run, replacing an earlier value
+    ///
+    /// Returns whether the run was found.
+    pub fn record_metric(&self, run_id: &str, name: impl Into<String>, value: f64) -> bool {
+        let mut runs = self.lock();
+        match runs.iter_mut().find(|run| run.id == run_id) {
+            Some(run) => {
+                run.metrics.insert(name.into(), value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Copy of all recorded runs, oldest first
+    pub fn runs(&self) -> Vec<ExperimentRun> {
+        self.lock().clone()
+    }
+
+    /// Statistics per variant, in the order the variants were added
+    pub fn summary(&self) -> Vec<VariantSummary> {
+        let runs = self.lock();
+        self.variants
+            .iter()
+            .map(|variant| {
+                let mut summary = VariantSummary {
+                    variant: variant.name.clone(),
+                    runs: 0,
+                    successes: 0,
+                    duration: Duration::ZERO,
+                    usage: TokenUsage::default(),
+                    metrics: HashMap::new(),
+                };
+                for run in runs.iter().filter(|run| run.variant == variant.name) {
+                    summary.runs += 1;
+                    summary.successes += usize::from(run.success);
+                    summary.duration += run.duration;
+                    summary.usage += run.usage;
+                    for (name, value) in &run.metrics {
+                        let (sum, count) = summary.metrics.entry(name.clone()).or_default();
+                        *sum += value;
+                        *count += 1;
+                    }
+                }
+                summary
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<ExperimentRun>> {
+        self.runs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(all(test, feature = "storage-memory"))]
+mod tests {
+    use super::*;
+    use crate::flow::FlowBuilder;
+    use crate::node::{FunctionNode, Node};
+    use crate::pricing::{ModelPrice, PricingCatalog};
+    use crate::storage::InMemoryStorage;
+    use crate::{Action, BasicFlow};
+    use serde_json::json;
+
+    /// Spends tokens depending on the variant's `verbose` setting and fails
+    /// when it is null
+    fn answer_flow() -> BasicFlow<InMemoryStorage> {
+        FlowBuilder::new()
+            .start_node("answer")
+            .node(
+                "answer",
+                Node::new(FunctionNode::new(
+                    "answer".to_string(),
+                    |store: &SharedStore<InMemoryStorage>, _ctx| {
+                        store.experiment().unwrap().unwrap().config["verbose"].clone()
+                    },
+                    |verbose, _ctx| verbose.as_bool().ok_or_else(|| "no setting".into()),
+                    |store, _prep, verbose, _ctx| {
+                        let output_tokens = if verbose { 100 } else { 10 };
+                        let catalog = PricingCatalog::new()
+                            .with_model_price("test-model", ModelPrice::new(200_000.0, 100_000.0));
+                        store.record_usage(catalog.priced(
+                            "test-model",
+                            TokenUsage {
+                                requests: 1,
+                                input_tokens: 5,
+                                output_tokens,
+                                ..TokenUsage::default()
+                            },
+                        ))?;
+                        Ok(Action::simple("complete"))
+                    },
+                )),
+            )
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_experiment_records_runs_per_variant() {
+        let experiment = Experiment::new("verbosity")
+            .with_env(RuntimeEnv::deterministic(7))
+            .with_variant(Variant::new("verbose").with_config("verbose", json!(true)))
+            .with_variant(
+                Variant::new("terse")
+                    .with_weight(3)
+                    .with_config("verbose", json!(false)),
+            )
+            .with_variant(Variant::new("broken").with_config("verbose", Value::Null));
+
+        // Assignment is sticky and follows the weights
+        assert_eq!(experiment.assign("user-1"), experiment.assign("user-1"));
+        let terse = (0..1000)
+            .filter(|_| experiment.choose().unwrap().name == "terse")
+            .count();
+        assert!((500..700).contains(&terse), "{}", terse);
+
+        let mut store = SharedStore::new();
+        for (variant, rating) in [("verbose", 2.0), ("terse", 5.0), ("terse", 4.0)] {
+            let run = experiment
+                .run(variant, &mut answer_flow(), &mut store)
+                .await
+                .unwrap();
+            assert!(run.success);
+            assert!(experiment.record_metric(&run.id, "rating", rating));
+        }
+        let failed = experiment
+            .run("broken", &mut answer_flow(), &mut store)
+            .await
+            .unwrap();
+        assert!(!failed.success && failed.error.is_some());
+        assert!(
+            experiment
+                .run("missing", &mut answer_flow(), &mut store)
+                .await
+                .is_err()
+        );
+
+        let summary = experiment.summary();
+        assert_eq!(summary[0].usage.output_tokens, 100);
+        assert_eq!(summary[1].runs, 2);
+        assert_eq!(summary[1].mean_tokens(), 15.0);
+        assert_eq!(summary[0].mean_cost(), 11.0);
+        assert_eq!(summary[1].mean_cost(), 2.0);
+        assert_eq!(summary[1].mean_metric("rating"), Some(4.5));
+        assert_eq!(summary[2].success_rate(), 0.0);
+        assert_eq!(summary[2].mean_metric("rating"), None);
+        assert_eq!(experiment.runs().len(), 4);
+    }
+}
@@ -14,7 +14,8 @@
 //! ### Three-Phase Execution Model
 //! 1. **Validation**: Ensure flow integrity (reachable nodes, valid routes)
 //! 2. **Runtime Execution**: Step-by-step node execution with action-based routing
-//! 3. **Result Collection**: Comprehensive execution results with path tracking
+//! 3. **Result Collection**: Comprehensive execution results with path tracking and
+//!    per-node timing and retry statistics
 //!
 //! ## Core Components
 //!
@@ -23,6 +24,14 @@
 //! - Dynamic node addition and route configuration
 //! - Configurable execution policies (max steps, cycle detection, terminal actions)
 //! - Rich execution context with retry logic and metadata
+//! - Error routing (`FlowBuilder::error_route`) to failure handlers, which find
+//!   the error details in [`SystemKeys::ERRORS`]
+//! - Run-scoped variables (`ExecutionContext::vars`) for loop counters and
+//!   cursors that shouldn't be persisted in the store
+//! - A write-ahead intent log (`FlowBuilder::intent_log`) recording what
+//!   side-effecting nodes are about to do, so a crashed run can be compensated
+//! - Store checkpoints after every step (`FlowBuilder::checkpoints`), to diff
+//!   steps and inspect the store as it was at any of them
 //! - Comprehensive error handling and recovery
 //!
 //! ### FlowBuilder
@@ -58,7 +67,10 @@
 //! ### Safety
 //! - **Cycle Detection**: Prevents infinite loops in flow execution
 //! - **Step Limiting**: Configurable maximum execution steps
+//! - **Deadlines**: Optional wall-clock limit per run (`max_duration`)
 //! - **Nesting Depth Control**: Prevents stack overflow in nested flows
+//! - **Concurrency Pools**: Named semaphores (`FlowConfig::pool`) limit how many
+//!   nodes assigned to a pool run at once, across every flow sharing the config
 //! - **Error Isolation**: Node failures don't crash entire flows
 //!
 //! ### Observability
@@ -66,6 +78,11 @@
 //! - **Step Counting**: Performance and complexity metrics
 //! - **Success/Failure Status**: Clear execution outcome indication
 //! - **Final Action Capture**: Last action taken before termination
+//! - **Event Stream**: [`FlowEvent`]s for node start/completion, sent live to a
+//!   channel set with `BasicFlow::subscribe` or `FlowBuilder::event_sender`
+//! - **Correlation IDs**: Each run carries one (`FlowBuilder::correlation_id`)
+//!   into node contexts, tracing spans, recorded failures and the
+//!   `X-Request-Id` header of builtin HTTP nodes
 //!
 //! ## Advanced Features
 //!
@@ -88,6 +105,21 @@
 //! // Use flow_node like any other node in a larger flow
 //! ```
 //!
+//! They can also be flattened into one graph with `BasicFlow::then`,
+//! `BasicFlow::branch` and `BasicFlow::merge`, which namespace node IDs
+//! (`0::fetch`, `then::summarize`) and route each flow's terminal actions to
+//! the start of the next:
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::flow::BasicFlow;
+//! # let (fetch, summarize, translate) = (BasicFlow::new(), BasicFlow::new(), BasicFlow::new());
+//! let pipeline: BasicFlow<InMemoryStorage> = fetch.then(BasicFlow::branch(
+//!     RouteCondition::KeyExists("needs_translation".to_string()),
+//!     translate,
+//!     summarize,
+//! ));
+//! ```
+//!
 //! ## Error Handling
 //!
 //! The flow system provides comprehensive error handling:
@@ -95,13 +127,28 @@
 //! - **NoRouteFound**: Invalid action routing
 //! - **CycleDetected**: Infinite loop prevention
 //! - **MaxStepsExceeded**: Runaway execution protection
+//! - **DeadlineExceeded**: Wall-clock limit reached, with partial results
+//!
+//! [`BasicFlow::execute_with_report`] returns a [`FlowReport`] that keeps the
+//! execution path, step count and last node alongside any error.
 //! - **InvalidConfiguration**: Setup validation errors
 
-use crate::node::{ExecutionContext, NodeBackend, NodeError};
+use crate::env::RuntimeEnv;
+use crate::node::keys::schema_mismatch;
+use crate::node::registry::merge_patch;
+use crate::node::{
+    ExecutionContext, FlowVars, KeySpec, NodeBackend, NodeError, NodePhase, NodeRunStats,
+};
+use crate::shared_store::{CheckpointManager, Intent, KeyAccessPolicy, NodeFailure, SystemKeys};
+use crate::tenant::TenantContext;
 use crate::{Action, SharedStore, StorageBackend};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Semaphore, mpsc};
 
 /// Errors that can occur during flow execution
 #[derive(Debug, Clone)]
@@ -118,6 +165,11 @@ pub enum FlowError {
     NodeError(String),
     /// Invalid flow configuration
     InvalidConfiguration(String),
+    /// The flow ran longer than its `max_duration`; carries what ran so far
+    DeadlineExceeded {
+        max_duration: Duration,
+        partial: Box<FlowExecutionResult>,
+    },
 }
 
 impl fmt::Display for FlowError {
@@ -141,6 +193,14 @@ impl fmt::Display for FlowError {
             FlowError::InvalidConfiguration(msg) => {
                 write!(f, "Invalid flow configuration: {}", msg)
             }
+            FlowError::DeadlineExceeded {
+                max_duration,
+                partial,
+            } => write!(
+                f,
+                "Flow deadline of {:?} exceeded after {} steps",
+                max_duration, partial.steps_executed
+            ),
         }
     }
 }
@@ -217,8 +277,243 @@ pub struct FlowExecutionResult {
     pub success: bool,
     /// Execution path (node IDs in order)
     pub execution_path: Vec<String>,
+    /// Statistics for each node run, in execution order
+    pub node_stats: Vec<NodeExecutionStat>,
+    /// Nodes a dry run stubbed instead of running, in execution order
+    pub dry_run_calls: Vec<DryRunCall>,
+    /// Node failures, in execution order
+    pub errors: Vec<NodeFailure>,
+    /// Intents a previous run left pending, found when an intent-logging run
+    /// starts; see [`FlowBuilder::intent_log`]
+    pub incomplete_intents: Vec<Intent>,
+    /// Correlation ID passed to every node of the run, see
+    /// [`FlowConfig::correlation_id`]
+    pub correlation_id: String,
+}
+
+/// Timing and retry statistics for one node run within a flow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeExecutionStat {
+    pub node_id: String,
+    /// Number of times `exec` was called, including retries
+    pub attempts: usize,
+    pub prep_duration: Duration,
+    pub exec_duration: Duration,
+    pub post_duration: Duration,
+    /// Wall-clock time of the whole node run
+    pub duration: Duration,
+    /// Action the node returned, or `None` if it failed
+    pub action: Option<String>,
+}
+
+impl FlowExecutionResult {
+    /// Result of a run that hasn't executed any node yet
+    fn pending() -> Self {
+        Self {
+            final_action: Action::simple("none"),
+            last_node_id: String::new(),
+            steps_executed: 0,
+            success: false,
+            execution_path: Vec::new(),
+            node_stats: Vec::new(),
+            dry_run_calls: Vec::new(),
+            errors: Vec::new(),
+            incomplete_intents: Vec::new(),
+            correlation_id: String::new(),
+        }
+    }
+
+    /// The most recent node failure, if any node failed
+    pub fn last_error(&self) -> Option<&NodeFailure> {
+        self.errors.last()
+    }
+
+    /// Failures of one node, in execution order
+    pub fn errors_for<'a>(&'a self, node_id: &'a str) -> impl Iterator<Item = &'a NodeFailure> {
+        self.errors
+            .iter()
+            .filter(move |failure| failure.node_id == node_id)
+    }
+
+    /// Export `node_stats` as folded stacks for inferno or speedscope
+    ///
+    /// Each line is `node;phase microseconds`, with the `prep`, `exec` and
+    /// `post` phases as leaves and the rest of a node's run (pool waits,
+    /// retry delays) as time on the node itself. Composed nodes are split on
+    /// [`NAMESPACE_SEPARATOR`], so each composed flow gets its own frame.
+    /// Repeated runs of a node are summed.
+    pub fn folded_stacks(&self) -> String {
+        let mut stacks: std::collections::BTreeMap<String, u128> = Default::default();
+        for stat in &self.node_stats {
+            let frames = stat
+                .node_id
+                .split(NAMESPACE_SEPARATOR)
+                .map(|frame| frame.replace(';', "_"))
+                .collect::<Vec<_>>()
+                .join(";");
+            let phases = [
+                ("prep", stat.prep_duration),
+                ("exec", stat.exec_duration),
+                ("post", stat.post_duration),
+            ];
+            let phased: Duration = phases.iter().map(|(_, duration)| *duration).sum();
+            for (phase, duration) in phases {
+                *stacks.entry(format!("{};{}", frames, phase)).or_default() += duration.as_micros();
+            }
+            *stacks.entry(frames).or_default() += stat.duration.saturating_sub(phased).as_micros();
+        }
+        stacks
+            .into_iter()
+            .filter(|(_, micros)| *micros > 0)
+            .map(|(stack, micros)| format!("{} {}\n", stack, micros))
+            .collect()
+    }
+}
+
+/// Outcome of [`BasicFlow::execute_with_report`]
+///
+/// `result` describes what ran, up to and including the failing node when
+/// `error` is set; in that case `result.success` is false and
+/// `result.final_action` is the last action a node returned (`"none"` if no
+/// node completed).
+#[derive(Debug, Clone)]
+pub struct FlowReport {
+    pub result: FlowExecutionResult,
+    pub error: Option<FlowError>,
+    /// Wall-clock duration of the run
+    pub duration: Duration,
+}
+
+impl FlowReport {
+    /// Whether the flow reached a terminal action
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Convert into the result [`Flow::execute`] would have returned
+    pub fn into_result(self) -> Result<FlowExecutionResult, FlowError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.result),
+        }
+    }
+}
+
+/// Progress event emitted while a flow executes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FlowEvent {
+    /// A node is about to run
+    NodeStarted { node_id: String, step: usize },
+    /// A node finished and returned an action
+    NodeCompleted {
+        node_id: String,
+        action: String,
+        duration_ms: u64,
+    },
+    /// A node failed after exhausting its retries
+    NodeFailed { node_id: String, error: String },
+    /// Incremental output from a node, such as an LLM token
+    TokenDelta { text: String },
+    /// The flow reached a terminal action
+    FlowCompleted { final_action: String, steps: usize },
+    /// The flow stopped with an error
+    FlowFailed { error: String },
+}
+
+/// Sending half of a flow event stream
+pub type FlowEventSender = mpsc::UnboundedSender<FlowEvent>;
+
+/// Receiving half of a flow event stream
+pub type FlowEventReceiver = mpsc::UnboundedReceiver<FlowEvent>;
+
+/// Output a dry run substitutes for a stubbed node
+#[derive(Debug, Clone, PartialEq)]
+pub struct DryRunStub {
+    /// Action the stubbed node returns
+    pub action: Action,
+    /// Store writes the stubbed node makes
+    pub writes: Vec<(String, serde_json::Value)>,
+}
+
+impl DryRunStub {
+    /// Create a stub returning `action` without writing anything
+    pub fn new(action: Action) -> Self {
+        Self {
+            action,
+            writes: Vec::new(),
+        }
+    }
+
+    /// Write `value` to `key` when the stub runs
+    pub fn with_write(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.writes.push((key.into(), value));
+        self
+    }
+}
+
+/// Dry-run settings: which nodes are stubbed and what the stubs return
+///
+/// Side-effecting nodes (see `NodeBackend::is_side_effecting`) and nodes with
+/// an explicit stub are not run; every other node runs normally.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DryRun {
+    /// Stubs for specific node IDs
+    pub stubs: HashMap<String, DryRunStub>,
+    /// Stub for side-effecting nodes without their own
+    pub default_stub: DryRunStub,
+}
+
+impl Default for DryRun {
+    fn default() -> Self {
+        Self {
+            stubs: HashMap::new(),
+            default_stub: DryRunStub::new(Action::simple("default")),
+        }
+    }
+}
+
+impl DryRun {
+    /// Stub side-effecting nodes with the `"default"` action
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stub a node with `stub`, whether or not it is side-effecting
+    pub fn with_stub(mut self, node_id: impl Into<String>, stub: DryRunStub) -> Self {
+        self.stubs.insert(node_id.into(), stub);
+        self
+    }
+
+    /// Use `stub` for side-effecting nodes without their own
+    pub fn with_default_stub(mut self, stub: DryRunStub) -> Self {
+        self.default_stub = stub;
+        self
+    }
+
+    /// The stub replacing a node, or `None` if it runs normally
+    pub fn stub_for(&self, node_id: &str, side_effecting: bool) -> Option<&DryRunStub> {
+        self.stubs
+            .get(node_id)
+            .or_else(|| side_effecting.then_some(&self.default_stub))
+    }
 }
 
+/// A node a dry run stubbed instead of running
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DryRunCall {
+    pub node_id: String,
+    /// Step the node would have run at
+    pub step: usize,
+    /// Parameters the node would have received
+    pub params: HashMap<String, serde_json::Value>,
+    /// Action the stub returned
+    pub action: String,
+}
+
+/// Action a failed node is routed as when error routing is enabled
+pub const ERROR_ACTION: &str = "error";
+
 /// Configuration for flow execution
 #[derive(Debug, Clone)]
 pub struct FlowConfig {
@@ -230,6 +525,75 @@ pub struct FlowConfig {
     pub start_node_id: String,
     /// Actions that terminate the flow
     pub terminal_actions: Vec<String>,
+    /// Named concurrency pools; clones of the config share the same permits
+    pub pools: HashMap<String, Arc<Semaphore>>,
+    /// Pool assignment for each node ID
+    pub node_pools: HashMap<String, String>,
+    /// Write permissions of each node ID; nodes without one may write any key
+    pub key_access: HashMap<String, Arc<KeyAccessPolicy>>,
+    /// Wall-clock limit for a run, checked between steps and passed to nodes
+    /// as a deadline
+    pub max_duration: Option<Duration>,
+    /// Clock, randomness and ID generation passed to every node
+    pub env: RuntimeEnv,
+    /// Tenant passed to every node
+    pub tenant: Option<TenantContext>,
+    /// Stub side-effecting nodes instead of running them
+    pub dry_run: Option<DryRun>,
+    /// Keys the caller writes before running the flow, see [`KeySpec`]
+    pub inputs: Vec<KeySpec>,
+    /// Route node failures as the [`ERROR_ACTION`] action, recording each in
+    /// [`SystemKeys::ERRORS`]
+    pub route_errors: bool,
+    /// Record what side-effecting nodes are about to do in
+    /// [`SystemKeys::INTENTS`] before they run
+    pub log_intents: bool,
+    /// Correlation ID for runs, e.g. the ID of the request being served;
+    /// when unset each run uses the execution ID of its first node
+    pub correlation_id: Option<String>,
+    /// Snapshots the store after every step
+    pub checkpoints: Option<CheckpointManager>,
+}
+
+impl FlowConfig {
+    /// Add a named pool allowing `permits` assigned nodes to run at once
+    pub fn pool(mut self, name: impl Into<String>, permits: usize) -> Self {
+        self.pools
+            .insert(name.into(), Arc::new(Semaphore::new(permits)));
+        self
+    }
+
+    /// Assign a node to a pool
+    pub fn node_pool(mut self, node_id: impl Into<String>, pool: impl Into<String>) -> Self {
+        self.node_pools.insert(node_id.into(), pool.into());
+        self
+    }
+
+    /// Restrict the keys a node may write
+    pub fn key_access(mut self, node_id: impl Into<String>, policy: KeyAccessPolicy) -> Self {
+        self.key_access.insert(node_id.into(), Arc::new(policy));
+        self
+    }
+
+    /// Number of permits currently free in a pool
+    pub fn available_permits(&self, pool: &str) -> Option<usize> {
+        self.pools
+            .get(pool)
+            .map(|semaphore| semaphore.available_permits())
+    }
+
+    /// Get the semaphore limiting a node, if it is assigned to a pool
+    fn semaphore_for(&self, node_id: &str) -> Result<Option<Arc<Semaphore>>, FlowError> {
+        let Some(pool) = self.node_pools.get(node_id) else {
+            return Ok(None);
+        };
+        self.pools.get(pool).cloned().map(Some).ok_or_else(|| {
+            FlowError::InvalidConfiguration(format!(
+                "Node '{}' is assigned to unknown pool '{}'",
+                node_id, pool
+            ))
+        })
+    }
 }
 
 impl Default for FlowConfig {
@@ -243,6 +607,18 @@ impl Default for FlowConfig {
                 "complete".to_string(),
                 "finish".to_string(),
             ],
+            pools: HashMap::new(),
+            node_pools: HashMap::new(),
+            key_access: HashMap::new(),
+            max_duration: None,
+            env: RuntimeEnv::default(),
+            tenant: None,
+            dry_run: None,
+            inputs: Vec::new(),
+            route_errors: false,
+            log_intents: false,
+            correlation_id: None,
+            checkpoints: None,
         }
     }
 }
@@ -251,6 +627,60 @@ impl Default for FlowConfig {
 #[async_trait]
 pub trait NodeRunner<S: StorageBackend>: Send + Sync {
     async fn run(&mut self, store: &mut SharedStore<S>) -> Result<Action, NodeError>;
+
+    /// Run with per-execution parameters. Runners that don't support parameters
+    /// ignore them and fall back to `run`.
+    async fn run_with_params(
+        &mut self,
+        store: &mut SharedStore<S>,
+        _params: HashMap<String, serde_json::Value>,
+    ) -> Result<Action, NodeError> {
+        self.run(store).await
+    }
+
+    /// Run with a context prepared by the flow (parameters, deadline and
+    /// runtime environment). Runners that don't support contexts fall back to
+    /// `run_with_params`.
+    async fn run_with_context(
+        &mut self,
+        store: &mut SharedStore<S>,
+        context: ExecutionContext,
+    ) -> Result<Action, NodeError> {
+        self.run_with_params(store, context.params).await
+    }
+
+    /// Statistics of the most recent run, if the runner records them
+    fn last_run_stats(&self) -> Option<NodeRunStats> {
+        None
+    }
+
+    /// Whether running this node has effects outside the store
+    fn is_side_effecting(&self) -> bool {
+        false
+    }
+
+    /// Store keys the runner reads
+    fn reads(&self) -> Vec<KeySpec> {
+        Vec::new()
+    }
+
+    /// Store keys the runner writes, or `None` if it may write any key
+    fn writes(&self) -> Option<Vec<KeySpec>> {
+        None
+    }
+
+    /// Serializable config the runner was built from, for runners created by
+    /// [`NodeRegistry::register_configurable`](crate::node::NodeRegistry::register_configurable)
+    fn config(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Rebuild the runner from a new config
+    fn configure(&mut self, _config: serde_json::Value) -> Result<(), NodeError> {
+        Err(NodeError::ValidationError(
+            "Node has no serializable config".to_string(),
+        ))
+    }
 }
 
 /// Implementation of NodeRunner for any Node
@@ -267,6 +697,44 @@ where
             Err(err) => Err(NodeError::ExecutionError(err.to_string())),
         }
     }
+
+    async fn run_with_params(
+        &mut self,
+        store: &mut SharedStore<S>,
+        params: HashMap<String, serde_json::Value>,
+    ) -> Result<Action, NodeError> {
+        match crate::node::Node::run_with_params(self, store, params).await {
+            Ok(action) => Ok(action),
+            Err(err) => Err(NodeError::ExecutionError(err.to_string())),
+        }
+    }
+
+    async fn run_with_context(
+        &mut self,
+        store: &mut SharedStore<S>,
+        context: ExecutionContext,
+    ) -> Result<Action, NodeError> {
+        match crate::node::Node::run_with_context(self, store, context).await {
+            Ok(action) => Ok(action),
+            Err(err) => Err(NodeError::ExecutionError(err.to_string())),
+        }
+    }
+
+    fn last_run_stats(&self) -> Option<NodeRunStats> {
+        crate::node::Node::last_run_stats(self).cloned()
+    }
+
+    fn is_side_effecting(&self) -> bool {
+        self.backend().is_side_effecting()
+    }
+
+    fn reads(&self) -> Vec<KeySpec> {
+        self.backend().reads()
+    }
+
+    fn writes(&self) -> Option<Vec<KeySpec>> {
+        self.backend().writes()
+    }
 }
 
 /// Trait for implementing flow execution logic
@@ -305,7 +773,9 @@ pub trait Flow<S: StorageBackend> {
 pub struct FlowBuilder<S: StorageBackend> {
     nodes: HashMap<String, Box<dyn NodeRunner<S>>>,
     routes: HashMap<String, Vec<Route>>,
+    node_params: HashMap<String, HashMap<String, serde_json::Value>>,
     config: FlowConfig,
+    event_sender: Option<FlowEventSender>,
 }
 
 impl<S: StorageBackend + 'static> Default for FlowBuilder<S> {
@@ -320,10 +790,46 @@ impl<S: StorageBackend + 'static> FlowBuilder<S> {
         Self {
             nodes: HashMap::new(),
             routes: HashMap::new(),
+            node_params: HashMap::new(),
             config: FlowConfig::default(),
+            event_sender: None,
         }
     }
 
+    /// Create a flow builder starting from an existing configuration
+    ///
+    /// Reusing one config for several flows makes them share its pools.
+    pub fn with_config(config: FlowConfig) -> Self {
+        Self {
+            config,
+            ..Self::new()
+        }
+    }
+
+    /// Add a named concurrency pool
+    pub fn pool(mut self, name: impl Into<String>, permits: usize) -> Self {
+        self.config = self.config.pool(name, permits);
+        self
+    }
+
+    /// Assign a node to a concurrency pool
+    pub fn node_pool(mut self, node_id: impl Into<String>, pool: impl Into<String>) -> Self {
+        self.config = self.config.node_pool(node_id, pool);
+        self
+    }
+
+    /// Restrict the keys a node may write, see [`KeyAccessPolicy`]
+    pub fn key_access(mut self, node_id: impl Into<String>, policy: KeyAccessPolicy) -> Self {
+        self.config = self.config.key_access(node_id, policy);
+        self
+    }
+
+    /// Send execution events to the given channel
+    pub fn event_sender(mut self, sender: FlowEventSender) -> Self {
+        self.event_sender = Some(sender);
+        self
+    }
+
     /// Set the starting node ID
     pub fn start_node(mut self, node_id: impl Into<String>) -> Self {
         self.config.start_node_id = node_id.into();
@@ -336,6 +842,68 @@ impl<S: StorageBackend + 'static> FlowBuilder<S> {
         self
     }
 
+    /// Limit the wall-clock duration of each run
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.config.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Set the runtime environment, e.g. [`RuntimeEnv::deterministic`] in tests
+    pub fn env(mut self, env: RuntimeEnv) -> Self {
+        self.config.env = env;
+        self
+    }
+
+    /// Run every node on behalf of a tenant, see [`TenantContext`]
+    pub fn tenant(mut self, tenant: TenantContext) -> Self {
+        self.config.tenant = Some(tenant);
+        self
+    }
+
+    /// Stub side-effecting nodes instead of running them, see [`DryRun`]
+    pub fn dry_run(mut self, dry_run: DryRun) -> Self {
+        self.config.dry_run = Some(dry_run);
+        self
+    }
+
+    /// Log an [`Intent`] before each side-effecting node runs and mark it
+    /// completed or failed afterwards
+    ///
+    /// An intent still pending when a run starts belongs to a run that
+    /// crashed mid-operation. Runs report these in
+    /// [`FlowExecutionResult::incomplete_intents`] for compensation or
+    /// review, until [`SharedStore::resolve_intent`] clears them.
+    pub fn intent_log(mut self) -> Self {
+        self.config.log_intents = true;
+        self
+    }
+
+    /// Tag runs with a caller-supplied correlation ID, e.g. the `X-Request-Id`
+    /// of the request that triggered them
+    ///
+    /// Nodes see it as [`ExecutionContext::correlation_id`]; it's recorded in
+    /// failures, intents and node tracing spans, and builtin nodes send it
+    /// with their HTTP requests.
+    pub fn correlation_id(mut self, correlation_id: impl Into<String>) -> Self {
+        self.config.correlation_id = Some(correlation_id.into());
+        self
+    }
+
+    /// Snapshot the store after every step, labelled with the node that ran
+    ///
+    /// Keep a clone of the manager to diff steps or travel back to them.
+    pub fn checkpoints(mut self, checkpoints: CheckpointManager) -> Self {
+        self.config.checkpoints = Some(checkpoints);
+        self
+    }
+
+    /// Declare a key the caller writes before running the flow, so nodes may
+    /// read it without an upstream writer
+    pub fn input(mut self, key: impl Into<KeySpec>) -> Self {
+        self.config.inputs.push(key.into());
+        self
+    }
+
     /// Add a terminal action
     pub fn terminal_action(mut self, action: impl Into<String>) -> Self {
         self.config.terminal_actions.push(action.into());
@@ -352,6 +920,16 @@ impl<S: StorageBackend + 'static> FlowBuilder<S> {
         self
     }
 
+    /// Set static parameters for a node, exposed through `ExecutionContext::params`
+    pub fn node_params(
+        mut self,
+        id: impl Into<String>,
+        params: HashMap<String, serde_json::Value>,
+    ) -> Self {
+        self.node_params.insert(id.into(), params);
+        self
+    }
+
     /// Add a simple route (action -> target node)
     pub fn route(
         mut self,
@@ -370,6 +948,17 @@ impl<S: StorageBackend + 'static> FlowBuilder<S> {
         self
     }
 
+    /// Route failures of `from` to `to`
+    ///
+    /// Enables error routing for the flow: a failed node is recorded in
+    /// [`SystemKeys::ERRORS`] and, if it has a route for [`ERROR_ACTION`],
+    /// the flow continues there instead of failing.
+    pub fn error_route(self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        let mut builder = self.route(from, ERROR_ACTION, to);
+        builder.config.route_errors = true;
+        builder
+    }
+
     /// Add a conditional route
     pub fn conditional_route(
         mut self,
@@ -394,17 +983,78 @@ impl<S: StorageBackend + 'static> FlowBuilder<S> {
 pub struct BasicFlow<S: StorageBackend> {
     nodes: HashMap<String, Box<dyn NodeRunner<S>>>,
     routes: HashMap<String, Vec<Route>>,
+    node_params: HashMap<String, HashMap<String, serde_json::Value>>,
     config: FlowConfig,
+    event_sender: Option<FlowEventSender>,
+    exits: HashMap<String, NodeExit>,
+}
+
+/// How a node taken over from another flow by composition finishes
+///
+/// The node keeps the terminal actions of the flow it came from; `next` is
+/// where the composed flow continues instead of stopping on one of them.
+#[derive(Debug, Clone)]
+struct NodeExit {
+    terminal_actions: Vec<String>,
+    next: Option<String>,
+}
+
+/// Keys written on every path to a node, with the schemas their last writer
+/// may have used; `None` once a node that doesn't declare its writes may have
+/// run
+type KeyState = Option<HashMap<String, Vec<serde_json::Value>>>;
+
+/// Keys available after a node with the given declared writes runs
+fn after_writes(state: &KeyState, writes: Option<Vec<KeySpec>>) -> KeyState {
+    let mut state = state.clone()?;
+    for spec in writes? {
+        state.insert(spec.key, spec.schema.into_iter().collect());
+    }
+    Some(state)
+}
+
+/// Keys available whichever of two paths was taken
+fn meet(a: &KeyState, b: &KeyState) -> KeyState {
+    let (Some(a), Some(b)) = (a, b) else {
+        return a.clone().or_else(|| b.clone());
+    };
+    let merged = a.iter().filter_map(|(key, schemas)| {
+        let mut schemas = schemas.clone();
+        for schema in b.get(key)? {
+            if !schemas.contains(schema) {
+                schemas.push(schema.clone());
+            }
+        }
+        Some((key.clone(), schemas))
+    });
+    Some(merged.collect())
+}
+
+/// Separator between the namespace and the original ID of a composed node
+pub const NAMESPACE_SEPARATOR: &str = "::";
+
+/// Action emitted by the router node of [`BasicFlow::branch`]
+const BRANCH_ACTION: &str = "branch";
+
+/// Router node inserted by [`BasicFlow::branch`]; the choice is made by the
+/// conditions on its outgoing routes
+struct BranchRouter;
+
+#[async_trait]
+impl<S: StorageBackend + Send + Sync> NodeRunner<S> for BranchRouter {
+    async fn run(&mut self, _store: &mut SharedStore<S>) -> Result<Action, NodeError> {
+        Ok(Action::simple(BRANCH_ACTION))
+    }
+
+    fn writes(&self) -> Option<Vec<KeySpec>> {
+        Some(Vec::new())
+    }
 }
 
 impl<S: StorageBackend> BasicFlow<S> {
     /// Create a new basic flow
     pub fn new() -> Self {
-        Self {
-            nodes: HashMap::new(),
-            routes: HashMap::new(),
-            config: FlowConfig::default(),
-        }
+        Self::with_config(FlowConfig::default())
     }
 
     /// Create a new basic flow with custom configuration
@@ -412,48 +1062,228 @@ impl<S: StorageBackend> BasicFlow<S> {
         Self {
             nodes: HashMap::new(),
             routes: HashMap::new(),
+            node_params: HashMap::new(),
             config,
+            event_sender: None,
+            exits: HashMap::new(),
+        }
+    }
+
+    /// Send execution events to the given channel, replacing any previous one
+    pub fn set_event_sender(&mut self, sender: FlowEventSender) {
+        self.event_sender = Some(sender);
+    }
+
+    /// Create a new event channel for this flow and return its receiving half
+    ///
+    /// Replaces any previously configured event sender.
+    pub fn subscribe(&mut self) -> FlowEventReceiver {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.event_sender = Some(sender);
+        receiver
+    }
+
+    /// Send an event to the subscriber, if any
+    fn emit(&self, event: FlowEvent) {
+        if let Some(sender) = &self.event_sender {
+            // A dropped receiver just means nobody is watching anymore
+            let _ = sender.send(event);
+        }
+    }
+
+    /// Set static parameters for a node, replacing any existing ones
+    pub fn set_node_params(
+        &mut self,
+        node_id: impl Into<String>,
+        params: HashMap<String, serde_json::Value>,
+    ) {
+        self.node_params.insert(node_id.into(), params);
+    }
+
+    /// Get the static parameters configured for a node
+    pub fn node_params(&self, node_id: &str) -> Option<&HashMap<String, serde_json::Value>> {
+        self.node_params.get(node_id)
+    }
+
+    /// Merge a JSON patch onto the config of a registry-built node and
+    /// rebuild it, e.g. `{"model": "gpt-4o-mini", "max_retries": 5}`
+    ///
+    /// The patch follows JSON merge patch rules: objects merge field by field
+    /// and `null` removes a field. Only nodes registered with
+    /// [`NodeRegistry::register_configurable`](crate::node::NodeRegistry::register_configurable)
+    /// have a config; the node is left unchanged if the patched config is
+    /// invalid.
+    pub fn override_node_config(
+        &mut self,
+        node_id: &str,
+        patch: serde_json::Value,
+    ) -> Result<(), FlowError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| FlowError::NodeNotFound(node_id.to_string()))?;
+        let mut config = node.config().ok_or_else(|| {
+            FlowError::InvalidConfiguration(format!(
+                "Node '{}' has no serializable config",
+                node_id
+            ))
+        })?;
+        merge_patch(&mut config, &patch);
+        node.configure(config)
+            .map_err(|e| FlowError::InvalidConfiguration(format!("Node '{}': {}", node_id, e)))
+    }
+
+    /// Apply [`BasicFlow::override_node_config`] for each node ID in an
+    /// object such as `{"summarize": {"temperature": 0.2}}`
+    pub fn override_config(&mut self, overlay: serde_json::Value) -> Result<(), FlowError> {
+        let serde_json::Value::Object(overlay) = overlay else {
+            return Err(FlowError::InvalidConfiguration(
+                "Config overlay must be an object keyed by node ID".to_string(),
+            ));
+        };
+        for (node_id, patch) in overlay {
+            self.override_node_config(&node_id, patch)?;
         }
+        Ok(())
+    }
+
+    /// Resolve the parameters for a node execution: static node params overridden
+    /// by the params carried on the action that routed to the node
+    fn resolve_params(
+        &self,
+        node_id: &str,
+        incoming_action: Option<&Action>,
+    ) -> HashMap<String, serde_json::Value> {
+        let mut params = self.node_params.get(node_id).cloned().unwrap_or_default();
+        if let Some(action_params) = incoming_action.and_then(|a| a.params()) {
+            params.extend(action_params.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        params
     }
 
     /// Find the next node ID based on the current action
+    ///
+    /// For actions with several branches, the highest-priority branch that is
+    /// terminal or has a matching route is taken. Returns the chosen branch
+    /// alongside the next node (or `None` for a terminal action).
     fn find_next_node(
         &self,
         current_node_id: &str,
         action: &Action,
         store: &SharedStore<S>,
-    ) -> Result<Option<String>, FlowError> {
-        let action_str = action.to_string();
+    ) -> Result<(Action, Option<String>), FlowError> {
+        let routes = self.routes.get(current_node_id);
+        let (terminal_actions, handoff) = match self.exits.get(current_node_id) {
+            Some(exit) => (&exit.terminal_actions, exit.next.as_ref()),
+            None => (&self.config.terminal_actions, None),
+        };
 
-        // Check if this is a terminal action
-        if self.config.terminal_actions.contains(&action_str) {
-            return Ok(None);
-        }
+        for branch in action.branches() {
+            let action_str = branch.name();
 
-        // Get routes for the current node
-        let routes = self.routes.get(current_node_id).ok_or_else(|| {
-            FlowError::NoRouteFound(current_node_id.to_string(), action_str.clone())
-        })?;
+            // Check if this is a terminal action; composed nodes may hand off
+            // to the next flow instead
+            if terminal_actions.contains(&action_str) {
+                return Ok((branch.clone(), handoff.cloned()));
+            }
 
-        // Find matching route
-        for route in routes {
-            if route.action == action_str {
-                // Check condition if present
-                if let Some(condition) = &route.condition {
-                    if !condition.evaluate(store) {
+            // Find matching route
+            for route in routes.into_iter().flatten() {
+                if route.action == action_str {
+                    // Check condition if present
+                    if let Some(condition) = &route.condition
+                        && !condition.evaluate(store)
+                    {
                         continue;
                     }
+                    return Ok((branch.clone(), Some(route.target_node_id.clone())));
                 }
-                return Ok(Some(route.target_node_id.clone()));
             }
         }
 
+        let action_str = action
+            .branches()
+            .first()
+            .map(|branch| branch.name())
+            .unwrap_or_else(|| action.name());
         Err(FlowError::NoRouteFound(
             current_node_id.to_string(),
             action_str,
         ))
     }
 
+    /// Nodes that may run right after `node_id`
+    fn successors(&self, node_id: &str) -> Vec<&str> {
+        let routes = self.routes.get(node_id).into_iter().flatten();
+        let handoff = self
+            .exits
+            .get(node_id)
+            .and_then(|exit| exit.next.as_deref());
+        routes
+            .map(|route| route.target_node_id.as_str())
+            .chain(handoff)
+            .collect()
+    }
+
+    /// Check that every declared read is preceded by a write on every path,
+    /// with a compatible schema
+    fn check_keys(&self) -> Result<(), FlowError> {
+        let inputs = self
+            .config
+            .inputs
+            .iter()
+            .map(|spec| (spec.key.clone(), spec.schema.iter().cloned().collect()))
+            .collect();
+        let start = self.config.start_node_id.as_str();
+        let mut reached: HashMap<&str, KeyState> = HashMap::from([(start, Some(inputs))]);
+        let mut pending = vec![start];
+        while let Some(node_id) = pending.pop() {
+            let Some(node) = self.nodes.get(node_id) else {
+                continue;
+            };
+            let written = after_writes(&reached[node_id], node.writes());
+            for next in self.successors(node_id) {
+                let merged = match reached.get(next) {
+                    Some(existing) => meet(existing, &written),
+                    None => written.clone(),
+                };
+                if reached.get(next) != Some(&merged) {
+                    reached.insert(next, merged);
+                    pending.push(next);
+                }
+            }
+        }
+
+        let mut node_ids: Vec<&str> = reached.keys().copied().collect();
+        node_ids.sort_unstable();
+        for node_id in node_ids {
+            let (Some(node), Some(available)) = (self.nodes.get(node_id), &reached[node_id]) else {
+                continue;
+            };
+            for spec in node.reads() {
+                let Some(schemas) = available.get(&spec.key) else {
+                    return Err(FlowError::InvalidConfiguration(format!(
+                        "Node '{}' reads key '{}', which is not written on every path to it",
+                        node_id, spec.key
+                    )));
+                };
+                let Some(read) = &spec.schema else {
+                    continue;
+                };
+                if let Some(mismatch) = schemas
+                    .iter()
+                    .find_map(|written| schema_mismatch(written, read))
+                {
+                    return Err(FlowError::InvalidConfiguration(format!(
+                        "Node '{}' reads key '{}' with an incompatible schema: {}",
+                        node_id, spec.key, mismatch
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Check for cycles in the execution path
     fn check_cycle(&self, path: &[String], next_node_id: &str) -> Result<(), FlowError> {
         if !self.config.detect_cycles {
@@ -470,49 +1300,215 @@ impl<S: StorageBackend> BasicFlow<S> {
     }
 }
 
-#[async_trait]
-impl<S: StorageBackend + Send + Sync> Flow<S> for BasicFlow<S>
-where
-    S::Error: Send + Sync + 'static,
-{
-    fn add_node(&mut self, id: String, node: Box<dyn NodeRunner<S>>) -> Result<(), FlowError> {
-        self.nodes.insert(id, node);
-        Ok(())
+impl<S: StorageBackend + Send + Sync + 'static> BasicFlow<S> {
+    /// Run `next` after this flow
+    ///
+    /// Wherever this flow would terminate, the composed flow continues at the
+    /// start of `next`. Node IDs are namespaced as `0::<id>` and `1::<id>`;
+    /// see [`BasicFlow::merge`].
+    pub fn then(self, next: BasicFlow<S>) -> Self {
+        Self::merge(vec![self, next])
     }
 
-    fn add_route(&mut self, from_node_id: String, route: Route) -> Result<(), FlowError> {
-        self.routes.entry(from_node_id).or_default().push(route);
-        Ok(())
+    /// Run `flows` one after another
+    ///
+    /// Node IDs are prefixed with the flow's position (`2::<id>` for a node of
+    /// the third flow), and every terminal action of a flow except the last is
+    /// routed to the start of the following one. The composed flow uses the
+    /// first flow's configuration, with a step budget covering all of them.
+    /// Merging no flows gives an empty flow.
+    pub fn merge(flows: Vec<BasicFlow<S>>) -> Self {
+        let starts: Vec<String> = flows
+            .iter()
+            .enumerate()
+            .map(|(index, flow)| namespaced(&index.to_string(), &flow.config.start_node_id))
+            .collect();
+        let Some(first) = flows.first() else {
+            return Self::new();
+        };
+
+        let mut composed = Self::composed_from(first, starts[0].clone());
+        for (index, flow) in flows.into_iter().enumerate() {
+            composed.absorb(flow, &index.to_string(), starts.get(index + 1).cloned());
+        }
+        composed
     }
 
-    async fn execute(
-        &mut self,
-        store: &mut SharedStore<S>,
-    ) -> Result<FlowExecutionResult, FlowError> {
-        let start_node_id = self.config.start_node_id.clone();
-        self.execute_from(store, start_node_id).await
+    /// Run `if_true` when `condition` holds, and `if_false` otherwise
+    ///
+    /// The composed flow starts at a router node with ID `branch`; the two
+    /// flows' nodes are namespaced as `then::<id>` and `else::<id>`.
+    pub fn branch(
+        condition: RouteCondition,
+        if_true: BasicFlow<S>,
+        if_false: BasicFlow<S>,
+    ) -> Self {
+        let mut composed = Self::composed_from(&if_true, BRANCH_ACTION.to_string());
+        composed.config.max_steps = 1;
+        composed
+            .nodes
+            .insert(BRANCH_ACTION.to_string(), Box::new(BranchRouter));
+        composed.routes.insert(
+            BRANCH_ACTION.to_string(),
+            vec![
+                Route {
+                    action: BRANCH_ACTION.to_string(),
+                    target_node_id: namespaced("then", &if_true.config.start_node_id),
+                    condition: Some(condition),
+                },
+                Route {
+                    action: BRANCH_ACTION.to_string(),
+                    target_node_id: namespaced("else", &if_false.config.start_node_id),
+                    condition: None,
+                },
+            ],
+        );
+        composed.absorb(if_true, "then", None);
+        composed.absorb(if_false, "else", None);
+        composed
     }
 
-    async fn execute_from(
+    /// An empty flow with `template`'s settings, to absorb other flows into
+    fn composed_from(template: &BasicFlow<S>, start_node_id: String) -> Self {
+        let mut composed = Self::with_config(FlowConfig {
+            start_node_id,
+            max_steps: 0,
+            node_pools: HashMap::new(),
+            key_access: HashMap::new(),
+            dry_run: template.config.dry_run.clone().map(|dry_run| DryRun {
+                stubs: HashMap::new(),
+                ..dry_run
+            }),
+            ..template.config.clone()
+        });
+        composed.event_sender = template.event_sender.clone();
+        composed
+    }
+
+    /// Move `other`'s nodes, routes and parameters into this flow under
+    /// `namespace`, continuing at `next` wherever `other` would terminate
+    fn absorb(&mut self, other: BasicFlow<S>, namespace: &str, next: Option<String>) {
+        let rename = |id: &str| namespaced(namespace, id);
+
+        for (id, node) in other.nodes {
+            let exit = match other.exits.get(&id) {
+                Some(exit) => NodeExit {
+                    terminal_actions: exit.terminal_actions.clone(),
+                    next: exit.next.as_deref().map(rename).or_else(|| next.clone()),
+                },
+                None => NodeExit {
+                    terminal_actions: other.config.terminal_actions.clone(),
+                    next: next.clone(),
+                },
+            };
+            self.exits.insert(rename(&id), exit);
+            self.nodes.insert(rename(&id), node);
+        }
+        for (from, routes) in other.routes {
+            let routes = routes
+                .into_iter()
+                .map(|route| Route {
+                    target_node_id: rename(&route.target_node_id),
+                    ..route
+                })
+                .collect();
+            self.routes.insert(rename(&from), routes);
+        }
+        for (id, params) in other.node_params {
+            self.node_params.insert(rename(&id), params);
+        }
+        for (id, pool) in other.config.node_pools {
+            self.config.node_pools.insert(rename(&id), pool);
+        }
+        for (id, policy) in other.config.key_access {
+            self.config.key_access.insert(rename(&id), policy);
+        }
+        for input in other.config.inputs {
+            if !self.config.inputs.iter().any(|spec| spec.key == input.key) {
+                self.config.inputs.push(input);
+            }
+        }
+        if let (Some(dry_run), Some(other_dry_run)) =
+            (&mut self.config.dry_run, other.config.dry_run)
+        {
+            for (id, stub) in other_dry_run.stubs {
+                dry_run.stubs.insert(rename(&id), stub);
+            }
+        }
+        for (name, pool) in other.config.pools {
+            self.config.pools.entry(name).or_insert(pool);
+        }
+        self.config.max_steps = self.config.max_steps.saturating_add(other.config.max_steps);
+        // A flow that loops on purpose keeps doing so once composed
+        self.config.detect_cycles &= other.config.detect_cycles;
+        self.config.route_errors |= other.config.route_errors;
+        self.config.log_intents |= other.config.log_intents;
+    }
+}
+
+/// The ID of a composed node
+fn namespaced(namespace: &str, id: &str) -> String {
+    format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, id)
+}
+
+impl<S: StorageBackend + Send + Sync> BasicFlow<S>
+where
+    S::Error: Send + Sync + 'static,
+{
+    /// Run nodes from `start_node_id` until a terminal action is reached,
+    /// recording progress in `report` as it goes
+    async fn run_steps(
         &mut self,
         store: &mut SharedStore<S>,
         start_node_id: String,
-    ) -> Result<FlowExecutionResult, FlowError> {
+        report: &mut FlowExecutionResult,
+    ) -> Result<(), FlowError> {
         let mut current_node_id = start_node_id;
-        let mut execution_path = Vec::new();
-        let mut steps_executed = 0;
+        let mut incoming_action: Option<Action> = None;
+        let vars = FlowVars::new();
+        if self.config.log_intents {
+            report.incomplete_intents = store
+                .incomplete_intents()
+                .map_err(|e| FlowError::NodeError(e.to_string()))?;
+        }
+        let deadline = self
+            .config
+            .max_duration
+            .map(|max_duration| (max_duration, self.config.env.now() + max_duration));
 
         loop {
             // Check step limit
-            if steps_executed >= self.config.max_steps {
+            if report.steps_executed >= self.config.max_steps {
                 return Err(FlowError::MaxStepsExceeded(self.config.max_steps));
             }
 
+            // Check the wall-clock limit
+            if let Some((max_duration, deadline)) = deadline
+                && self.config.env.now() >= deadline
+            {
+                return Err(FlowError::DeadlineExceeded {
+                    max_duration,
+                    partial: Box::new(report.clone()),
+                });
+            }
+
             // Check for cycles
-            self.check_cycle(&execution_path, &current_node_id)?;
+            self.check_cycle(&report.execution_path, &current_node_id)?;
 
             // Add current node to execution path
-            execution_path.push(current_node_id.clone());
+            report.execution_path.push(current_node_id.clone());
+            report.last_node_id = current_node_id.clone();
+
+            let params = self.resolve_params(&current_node_id, incoming_action.as_ref());
+
+            // Wait for a slot if the node belongs to a concurrency pool
+            let _permit =
+                match self.config.semaphore_for(&current_node_id)? {
+                    Some(semaphore) => Some(semaphore.acquire_owned().await.map_err(|e| {
+                        FlowError::InvalidConfiguration(format!("Pool closed: {}", e))
+                    })?),
+                    None => None,
+                };
 
             // Get the current node
             let node = self
@@ -521,28 +1517,242 @@ where
                 .ok_or_else(|| FlowError::NodeNotFound(current_node_id.clone()))?;
 
             // Execute the node
-            let action = node.run(store).await.map_err(FlowError::from)?;
-            steps_executed += 1;
+            let env = self.config.env.clone();
+            let context = ExecutionContext::new(0, Duration::ZERO)
+                .with_params(params)
+                .with_deadline(deadline.map(|(_, deadline)| deadline))
+                .with_env(env.clone())
+                .with_tenant(self.config.tenant.clone())
+                .with_vars(vars.clone())
+                .with_node_id(current_node_id.clone())
+                .with_intent_log(self.config.log_intents);
+            if report.correlation_id.is_empty() {
+                // Runs without a caller-supplied ID use their first execution ID
+                report.correlation_id = match &self.config.correlation_id {
+                    Some(id) => id.clone(),
+                    None => context.execution_id.clone(),
+                };
+            }
+            let context = context.with_correlation_id(Some(report.correlation_id.clone()));
+            let started = env.now();
+            // `node` borrows `self.nodes`, so use the sender field rather than `emit`
+            if let Some(sender) = &self.event_sender {
+                let _ = sender.send(FlowEvent::NodeStarted {
+                    node_id: current_node_id.clone(),
+                    step: report.steps_executed + 1,
+                });
+            }
+            let stub =
+                self.config.dry_run.as_ref().and_then(|dry_run| {
+                    dry_run.stub_for(&current_node_id, node.is_side_effecting())
+                });
+            let (outcome, stats) = match stub.cloned() {
+                Some(stub) => {
+                    for (key, value) in stub.writes {
+                        store
+                            .set(key, value)
+                            .map_err(|e| FlowError::NodeError(e.to_string()))?;
+                    }
+                    report.dry_run_calls.push(DryRunCall {
+                        node_id: current_node_id.clone(),
+                        step: report.steps_executed + 1,
+                        params: context.params,
+                        action: stub.action.name(),
+                    });
+                    (Ok(stub.action), NodeRunStats::default())
+                }
+                None => {
+                    let policy = self.config.key_access.get(&current_node_id).cloned();
+                    if let Some(policy) = policy {
+                        store.begin_access_scope(&current_node_id, policy);
+                    }
+                    let run = node.run_with_context(store, context);
+                    #[cfg(feature = "builtin-nodes")]
+                    let run = tracing::Instrument::instrument(
+                        run,
+                        tracing::info_span!(
+                            "node",
+                            node_id = %current_node_id,
+                            correlation_id = %report.correlation_id
+                        ),
+                    );
+                    let mut outcome = run.await;
+                    // A refused write fails the node even if it ignored the error
+                    if let Some(key) = store.end_access_scope() {
+                        outcome = Err(NodeError::PermissionDenied(format!(
+                            "node '{}' may not write key '{}'",
+                            current_node_id, key
+                        )));
+                    }
+                    (outcome, node.last_run_stats().unwrap_or_default())
+                }
+            };
+            // Node boundary: persist what the node wrote before moving on
+            store
+                .flush()
+                .map_err(|e| FlowError::NodeError(e.to_string()))?;
+            report.node_stats.push(NodeExecutionStat {
+                node_id: current_node_id.clone(),
+                attempts: stats.attempts,
+                prep_duration: stats.prep_duration,
+                exec_duration: stats.exec_duration,
+                post_duration: stats.post_duration,
+                duration: env.now() - started,
+                action: outcome.as_ref().ok().map(Action::name),
+            });
+            let action = match outcome {
+                Ok(action) => action,
+                Err(e) => {
+                    self.emit(FlowEvent::NodeFailed {
+                        node_id: current_node_id.clone(),
+                        error: e.to_string(),
+                    });
+                    let failure = NodeFailure {
+                        node_id: current_node_id.clone(),
+                        // Refused writes are detected after post
+                        phase: stats.failed_phase.unwrap_or(NodePhase::Post),
+                        message: e.to_string(),
+                        retries: stats.attempts.saturating_sub(1),
+                        timestamp: env
+                            .system_time()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .map_or(0, |elapsed| elapsed.as_millis() as u64),
+                        correlation_id: Some(report.correlation_id.clone()),
+                    };
+                    report.errors.push(failure.clone());
+                    if !self.config.route_errors {
+                        return Err(FlowError::from(e));
+                    }
+                    store
+                        .record_error(failure)
+                        .map_err(|e| FlowError::NodeError(e.to_string()))?;
+                    let error_action = Action::simple(ERROR_ACTION);
+                    match self.find_next_node(&current_node_id, &error_action, store) {
+                        Ok((_, Some(_))) => error_action,
+                        _ => return Err(FlowError::from(e)),
+                    }
+                }
+            };
+            report.steps_executed += 1;
+            if let Some(checkpoints) = &self.config.checkpoints {
+                checkpoints
+                    .record(current_node_id.clone(), Some(report.steps_executed), store)
+                    .map_err(|e| FlowError::NodeError(e.to_string()))?;
+            }
+            self.emit(FlowEvent::NodeCompleted {
+                node_id: current_node_id.clone(),
+                action: action.name(),
+                duration_ms: (env.now() - started).as_millis() as u64,
+            });
 
             // Find next node
-            match self.find_next_node(&current_node_id, &action, store)? {
+            report.final_action = action.clone();
+            let (action, next_node_id) = self.find_next_node(&current_node_id, &action, store)?;
+            report.final_action = action.clone();
+            match next_node_id {
                 Some(next_node_id) => {
                     current_node_id = next_node_id;
+                    incoming_action = Some(action);
                 }
                 None => {
                     // Terminal action reached
-                    return Ok(FlowExecutionResult {
-                        final_action: action,
-                        last_node_id: current_node_id,
-                        steps_executed,
-                        success: true,
-                        execution_path,
-                    });
+                    report.success = true;
+                    return Ok(());
                 }
             }
         }
     }
 
+    /// Run the flow, returning what was executed even if it failed
+    ///
+    /// Unlike [`Flow::execute`], the path, step count and last node are kept
+    /// when a node fails or a limit is hit, which helps with diagnostics and
+    /// compensation logic.
+    pub async fn execute_with_report(&mut self, store: &mut SharedStore<S>) -> FlowReport {
+        let started = self.config.env.now();
+        let start_node_id = self.config.start_node_id.clone();
+        let mut result = FlowExecutionResult::pending();
+        let outcome = self.run_steps(store, start_node_id, &mut result).await;
+        let recorded = Self::record_run(store, &result, outcome.as_ref().err());
+        self.emit_outcome(&result, outcome.as_ref().err());
+        FlowReport {
+            result,
+            error: outcome.and(recorded).err(),
+            duration: self.config.env.now() - started,
+        }
+    }
+
+    /// Write the run's execution path, and its error if it failed, to the
+    /// reserved [`SystemKeys`]
+    fn record_run(
+        store: &mut SharedStore<S>,
+        result: &FlowExecutionResult,
+        error: Option<&FlowError>,
+    ) -> Result<(), FlowError> {
+        let storage_error = |e: S::Error| FlowError::NodeError(e.to_string());
+        store
+            .set_system(
+                SystemKeys::EXECUTION_PATH,
+                serde_json::json!(result.execution_path),
+            )
+            .map_err(storage_error)?;
+        if let Some(error) = error {
+            store
+                .set_system(SystemKeys::LAST_ERROR, serde_json::json!(error.to_string()))
+                .map_err(storage_error)?;
+        }
+        store.flush().map_err(storage_error)
+    }
+
+    /// Emit the final event of a run
+    fn emit_outcome(&self, result: &FlowExecutionResult, error: Option<&FlowError>) {
+        match error {
+            None => self.emit(FlowEvent::FlowCompleted {
+                final_action: result.final_action.name(),
+                steps: result.steps_executed,
+            }),
+            Some(e) => self.emit(FlowEvent::FlowFailed {
+                error: e.to_string(),
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: StorageBackend + Send + Sync> Flow<S> for BasicFlow<S>
+where
+    S::Error: Send + Sync + 'static,
+{
+    fn add_node(&mut self, id: String, node: Box<dyn NodeRunner<S>>) -> Result<(), FlowError> {
+        self.nodes.insert(id, node);
+        Ok(())
+    }
+
+    fn add_route(&mut self, from_node_id: String, route: Route) -> Result<(), FlowError> {
+        self.routes.entry(from_node_id).or_default().push(route);
+        Ok(())
+    }
+
+    async fn execute(
+        &mut self,
+        store: &mut SharedStore<S>,
+    ) -> Result<FlowExecutionResult, FlowError> {
+        let start_node_id = self.config.start_node_id.clone();
+        self.execute_from(store, start_node_id).await
+    }
+
+    async fn execute_from(
+        &mut self,
+        store: &mut SharedStore<S>,
+        start_node_id: String,
+    ) -> Result<FlowExecutionResult, FlowError> {
+        let mut result = FlowExecutionResult::pending();
+        let outcome = self.run_steps(store, start_node_id, &mut result).await;
+        let recorded = Self::record_run(store, &result, outcome.as_ref().err());
+        self.emit_outcome(&result, outcome.as_ref().err());
+        outcome.and(recorded).map(|_| result)
+    }
+
     fn config(&self) -> &FlowConfig {
         &self.config
     }
@@ -579,7 +1789,7 @@ where
             }
         }
 
-        Ok(())
+        self.check_keys()
     }
 }
 
@@ -589,6 +1799,25 @@ impl<S: StorageBackend + 'static> Default for BasicFlow<S> {
     }
 }
 
+/// Record a nested flow's result under [`SystemKeys::NESTED_FLOW_RESULT`]
+fn store_nested_result<S: StorageBackend>(
+    store: &mut SharedStore<S>,
+    result: &FlowExecutionResult,
+) -> Result<(), FlowError> {
+    store
+        .set_system(
+            SystemKeys::NESTED_FLOW_RESULT,
+            serde_json::json!({
+                "final_action": result.final_action.to_string(),
+                "last_node_id": result.last_node_id,
+                "steps_executed": result.steps_executed,
+                "success": result.success,
+                "execution_path": result.execution_path
+            }),
+        )
+        .map_err(|e| FlowError::NodeError(e.to_string()))
+}
+
 /// Implementation of NodeBackend for BasicFlow, allowing flows to be nested
 #[async_trait]
 impl<S: StorageBackend + Send + Sync + 'static> NodeBackend<S> for BasicFlow<S>
@@ -621,6 +1850,11 @@ where
             steps_executed: 0,
             success: true,
             execution_path: vec![],
+            node_stats: vec![],
+            dry_run_calls: vec![],
+            errors: vec![],
+            incomplete_intents: vec![],
+            correlation_id: String::new(),
         })
     }
 
@@ -644,26 +1878,24 @@ where
             ));
         }
 
-        // Execute the nested flow
-        let result = self.execute(store).await?;
+        // Execute the nested flow as part of the caller's request
+        let configured = self.config.correlation_id.clone();
+        if configured.is_none() {
+            self.config.correlation_id = context.correlation_id.clone();
+        }
+        let result = self.execute(store).await;
+        self.config.correlation_id = configured;
+        let result = result?;
 
-        // Store the nested flow result in the shared store
-        store
-            .set(
-                "nested_flow_result".to_string(),
-                serde_json::json!({
-                    "final_action": result.final_action.to_string(),
-                    "last_node_id": result.last_node_id,
-                    "steps_executed": result.steps_executed,
-                    "success": result.success,
-                    "execution_path": result.execution_path
-                }),
-            )
-            .map_err(|e| FlowError::NodeError(e.to_string()))?;
+        store_nested_result(store, &result)?;
 
         // Return the final action from the nested flow
         Ok(result.final_action)
     }
+
+    fn is_side_effecting(&self) -> bool {
+        self.nodes.values().any(|node| node.is_side_effecting())
+    }
 }
 
 /// A wrapper to make any Flow usable as a Node
@@ -733,6 +1965,11 @@ where
             steps_executed: 0,
             success: true,
             execution_path: vec![],
+            node_stats: vec![],
+            dry_run_calls: vec![],
+            errors: vec![],
+            incomplete_intents: vec![],
+            correlation_id: String::new(),
         })
     }
 
@@ -756,23 +1993,18 @@ where
             ));
         }
 
-        // Execute the nested flow
-        let result = self.flow.execute(store).await?;
+        // Execute the nested flow as part of the caller's request
+        let configured = self.flow.config().clone();
+        if configured.correlation_id.is_none() {
+            let mut config = configured.clone();
+            config.correlation_id = context.correlation_id.clone();
+            self.flow.set_config(config);
+        }
+        let result = self.flow.execute(store).await;
+        self.flow.set_config(configured);
+        let result = result?;
 
-        // Store the nested flow result in the shared store with a unique key
-        let result_key = format!("nested_flow_result_{}", context.execution_id());
-        store
-            .set(
-                result_key,
-                serde_json::json!({
-                    "final_action": result.final_action.to_string(),
-                    "last_node_id": result.last_node_id,
-                    "steps_executed": result.steps_executed,
-                    "success": result.success,
-                    "execution_path": result.execution_path
-                }),
-            )
-            .map_err(|e| FlowError::NodeError(e.to_string()))?;
+        store_nested_result(store, &result)?;
 
         // Return the final action from the nested flow
         Ok(result.final_action)
@@ -783,12 +2015,18 @@ impl<S: StorageBackend + 'static> FlowBuilder<S> {
     /// Build the flow
     pub fn build(self) -> BasicFlow<S> {
         let mut flow = BasicFlow::with_config(self.config);
+        flow.event_sender = self.event_sender;
 
         // Add all nodes
         for (id, node) in self.nodes {
             flow.add_node(id, node).expect("Failed to add node");
         }
 
+        // Add static node parameters
+        for (id, params) in self.node_params {
+            flow.set_node_params(id, params);
+        }
+
         // Add all routes
         for (from_id, routes) in self.routes {
             for route in routes {
@@ -975,4 +2213,1133 @@ mod tests {
         // println!("Result: {:?}", result);
         assert!(matches!(result, Err(FlowError::MaxStepsExceeded(5))));
     }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_max_duration_returns_partial_result() {
+        use crate::FunctionNode;
+
+        // Each step sleeps for 20ms and records the deadline it was given
+        let slow_node = || {
+            Node::new(FunctionNode::new(
+                "slow".to_string(),
+                |_store: &SharedStore<InMemoryStorage>, ctx| ctx.remaining(),
+                |remaining, _ctx| {
+                    std::thread::sleep(Duration::from_millis(20));
+                    Ok(remaining)
+                },
+                |store, _prep, remaining, _ctx| {
+                    store.set("had_deadline".to_string(), json!(remaining.is_some()))?;
+                    Ok(Action::simple("next"))
+                },
+            ))
+        };
+        let mut flow = FlowBuilder::new()
+            .start_node("a")
+            .max_duration(Duration::from_millis(30))
+            .node("a", slow_node())
+            .node("b", slow_node())
+            .node("c", slow_node())
+            .route("a", "next", "b")
+            .route("b", "next", "c")
+            .build();
+
+        let mut store = SharedStore::new();
+        let error = flow.execute(&mut store).await.unwrap_err();
+        let FlowError::DeadlineExceeded {
+            max_duration,
+            partial,
+        } = error
+        else {
+            panic!("expected DeadlineExceeded, got {:?}", error);
+        };
+        assert_eq!(max_duration, Duration::from_millis(30));
+        assert_eq!(partial.steps_executed, 2);
+        assert_eq!(partial.execution_path, vec!["a", "b"]);
+        assert_eq!(partial.last_node_id, "b");
+        assert_eq!(partial.final_action, Action::simple("next"));
+        assert!(!partial.success);
+        assert_eq!(store.get("had_deadline").unwrap(), Some(json!(true)));
+    }
+
+    #[cfg(all(feature = "storage-memory", feature = "builtin-nodes"))]
+    #[tokio::test]
+    async fn test_execute_with_report_keeps_partial_results() {
+        let failing = Node::new(crate::FunctionNode::new(
+            "failing".to_string(),
+            |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+            |_prep, _ctx| -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+                Err("boom".into())
+            },
+            |_store, _prep, _exec, _ctx| Ok(Action::simple("complete")),
+        ));
+        let mut flow = FlowBuilder::new()
+            .start_node("first")
+            .node(
+                "first",
+                Node::new(SetValueNode::new(
+                    "first".to_string(),
+                    json!(true),
+                    Action::simple("next"),
+                )),
+            )
+            .node("failing", failing)
+            .route("first", "next", "failing")
+            .build();
+
+        let mut store = SharedStore::new();
+        let report = flow.execute_with_report(&mut store).await;
+        assert!(!report.is_success());
+        assert!(report.error.as_ref().unwrap().to_string().contains("boom"));
+        assert_eq!(report.result.execution_path, vec!["first", "failing"]);
+        assert_eq!(report.result.last_node_id, "failing");
+        assert_eq!(report.result.steps_executed, 1);
+        assert_eq!(report.result.final_action, Action::simple("next"));
+        assert_eq!(report.result.node_stats.len(), 2);
+        assert_eq!(report.result.node_stats[1].action, None);
+        assert!(report.into_result().is_err());
+
+        // A successful run reports the same result as `execute`
+        let mut flow = FlowBuilder::new()
+            .start_node("only")
+            .node(
+                "only",
+                Node::new(SetValueNode::new(
+                    "only".to_string(),
+                    json!(1),
+                    Action::simple("complete"),
+                )),
+            )
+            .build();
+        let report = flow.execute_with_report(&mut store).await;
+        assert!(report.is_success());
+        let result = report.into_result().unwrap();
+        assert!(result.success);
+        assert_eq!(result.execution_path, vec!["only"]);
+    }
+
+    #[cfg(all(feature = "storage-memory", feature = "builtin-nodes"))]
+    #[tokio::test]
+    async fn test_engine_writes_reserved_system_keys() {
+        let inner = FlowBuilder::new()
+            .start_node("inner")
+            .terminal_action("next")
+            .node(
+                "inner",
+                Node::new(SetValueNode::new(
+                    "inner".to_string(),
+                    json!(true),
+                    Action::simple("next"),
+                )),
+            )
+            .build();
+        let mut flow = FlowBuilder::new()
+            .start_node("nested")
+            .node("nested", Node::new(FlowNode::new(inner)))
+            .route("nested", "next", "missing")
+            .build();
+
+        let mut store = SharedStore::new();
+        let err = flow.execute(&mut store).await.unwrap_err();
+        assert_eq!(store.execution_path().unwrap(), vec!["nested", "missing"]);
+        assert_eq!(store.last_error().unwrap(), Some(err.to_string()));
+        assert_eq!(
+            store.get(SystemKeys::NESTED_FLOW_RESULT).unwrap().unwrap()["execution_path"],
+            json!(["inner"])
+        );
+
+        // Users can read the reserved keys but not write them
+        assert!(matches!(
+            store.set(SystemKeys::LAST_ERROR.to_string(), json!("spoofed")),
+            Err(crate::SharedStoreError::ReservedKey(_))
+        ));
+    }
+
+    #[cfg(all(feature = "storage-memory", feature = "builtin-nodes"))]
+    #[tokio::test]
+    async fn test_flow_composition_operators() {
+        fn set_flow(key: &str, action: &str) -> BasicFlow<InMemoryStorage> {
+            FlowBuilder::new()
+                .start_node("set")
+                .node(
+                    "set",
+                    Node::new(SetValueNode::new(
+                        key.to_string(),
+                        json!(true),
+                        Action::simple(action),
+                    )),
+                )
+                .build()
+        }
+
+        let mut flow = set_flow("fetched", "complete").then(BasicFlow::branch(
+            RouteCondition::KeyExists("fetched".to_string()),
+            set_flow("summarized", "end"),
+            set_flow("skipped", "end"),
+        ));
+        let mut store = SharedStore::new();
+        let result = flow.execute(&mut store).await.unwrap();
+        assert_eq!(
+            result.execution_path,
+            vec!["0::set", "1::branch", "1::then::set"]
+        );
+        assert_eq!(result.final_action, Action::simple("end"));
+        assert_eq!(store.get("summarized").unwrap(), Some(json!(true)));
+        assert_eq!(store.get("skipped").unwrap(), None);
+
+        // Any terminal action of an earlier flow hands off to the next one
+        let mut flow = BasicFlow::merge(vec![
+            set_flow("a", "end"),
+            set_flow("b", "finish"),
+            set_flow("c", "complete"),
+        ]);
+        let result = flow.execute(&mut store).await.unwrap();
+        assert_eq!(result.execution_path, vec!["0::set", "1::set", "2::set"]);
+        assert_eq!(result.final_action, Action::simple("complete"));
+    }
+
+    #[cfg(all(feature = "storage-memory", feature = "builtin-nodes"))]
+    #[tokio::test]
+    async fn test_node_stats_record_attempts_and_actions() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let flaky_calls = calls.clone();
+        let flaky = crate::FunctionNode::new(
+            "flaky".to_string(),
+            |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+            move |_prep, _ctx| {
+                if flaky_calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err("not yet".into())
+                } else {
+                    Ok(())
+                }
+            },
+            |_store, _prep, _exec, _ctx| Ok(Action::simple("complete")),
+        )
+        .with_retries(3)
+        .with_retry_delay(Duration::from_millis(5));
+
+        let mut flow = FlowBuilder::new()
+            .start_node("set")
+            .node(
+                "set",
+                Node::new(SetValueNode::new(
+                    "key".to_string(),
+                    json!(1),
+                    Action::simple("next"),
+                )),
+            )
+            .node("flaky", Node::new(flaky))
+            .route("set", "next", "flaky")
+            .build();
+
+        let mut store = SharedStore::new();
+        let result = flow.execute(&mut store).await.unwrap();
+        let stats = &result.node_stats;
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].node_id, "set");
+        assert_eq!(stats[0].attempts, 1);
+        assert_eq!(stats[0].action.as_deref(), Some("next"));
+        assert_eq!(stats[1].attempts, 3);
+        assert!(stats[1].exec_duration >= Duration::from_millis(10));
+        assert!(stats[1].duration >= stats[1].exec_duration);
+        assert_eq!(stats[1].action.as_deref(), Some("complete"));
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_deterministic_env_drives_ids_and_retry_delays() {
+        use std::sync::Mutex;
+
+        let run = || async {
+            let seen = Arc::new(Mutex::new(Vec::new()));
+            let seen_in = seen.clone();
+            let flaky = crate::FunctionNode::new(
+                "flaky".to_string(),
+                |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+                move |_prep, ctx| {
+                    let roll = ctx.env().random_f64();
+                    seen_in
+                        .lock()
+                        .unwrap()
+                        .push((ctx.execution_id().to_string(), roll));
+                    if roll < 0.9 {
+                        Err("unlucky".into())
+                    } else {
+                        Ok(())
+                    }
+                },
+                |_store, _prep, _exec, _ctx| Ok(Action::simple("complete")),
+            )
+            .with_retries(50)
+            .with_retry_delay(Duration::from_secs(3600));
+
+            let mut flow = FlowBuilder::new()
+                .start_node("flaky")
+                .node("flaky", Node::new(flaky))
+                .env(RuntimeEnv::deterministic(42))
+                .build();
+            let result = flow.execute(&mut SharedStore::new()).await;
+            (result.map(|r| r.node_stats), seen.lock().unwrap().clone())
+        };
+
+        let started = std::time::Instant::now();
+        let (first_stats, first_seen) = run().await;
+        let (_, second_seen) = run().await;
+        // Hour-long retry delays pass on the manual clock, not in real time
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(first_seen, second_seen);
+        assert!(first_seen.iter().all(|(id, _)| id == "exec-1"));
+
+        let stats = first_stats.unwrap();
+        assert_eq!(stats[0].attempts, first_seen.len());
+        assert_eq!(
+            stats[0].exec_duration,
+            Duration::from_secs(3600) * (first_seen.len() as u32 - 1)
+        );
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_node_params_from_builder_and_action() {
+        use crate::FunctionNode;
+        use std::collections::HashMap;
+
+        let echo_params = |output_key: &'static str| {
+            FunctionNode::new(
+                "echo_params".to_string(),
+                |_store: &SharedStore<InMemoryStorage>, ctx| ctx.params().clone(),
+                |params, _ctx| Ok(params),
+                move |store, _prep, params, _ctx| {
+                    store.set(output_key.to_string(), json!(params))?;
+                    Ok(Action::simple("complete"))
+                },
+            )
+        };
+
+        let router = FunctionNode::new(
+            "router".to_string(),
+            |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+            |_, _ctx| Ok(()),
+            |_store, _prep, _result, _ctx| {
+                let mut params = HashMap::new();
+                params.insert("language".to_string(), json!("fr"));
+                Ok(Action::with_params("translate", params))
+            },
+        );
+
+        let mut static_params = HashMap::new();
+        static_params.insert("language".to_string(), json!("en"));
+        static_params.insert("style".to_string(), json!("formal"));
+
+        let mut flow = FlowBuilder::new()
+            .start_node("router")
+            .node("router", Node::new(router))
+            .node("translator", Node::new(echo_params("translator_params")))
+            .node_params("translator", static_params)
+            .route("router", "translate", "translator")
+            .build();
+
+        let mut store = SharedStore::new();
+        let result = flow.execute(&mut store).await.unwrap();
+
+        assert!(result.success);
+        assert_eq!(
+            store.get("translator_params").unwrap().unwrap(),
+            json!({"language": "fr", "style": "formal"})
+        );
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_flow_emits_execution_events() {
+        use crate::FunctionNode;
+
+        let step = |action: &'static str| {
+            FunctionNode::new(
+                "step".to_string(),
+                |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+                |_, _ctx| Ok(()),
+                move |_store, _prep, _result, _ctx| Ok(Action::simple(action)),
+            )
+        };
+
+        let mut flow = FlowBuilder::new()
+            .start_node("first")
+            .node("first", Node::new(step("next")))
+            .node("second", Node::new(step("complete")))
+            .route("first", "next", "second")
+            .build();
+        let mut events = flow.subscribe();
+
+        let mut store = SharedStore::new();
+        flow.execute(&mut store).await.unwrap();
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(received.len(), 5);
+        assert_eq!(
+            received[0],
+            FlowEvent::NodeStarted {
+                node_id: "first".to_string(),
+                step: 1
+            }
+        );
+        assert!(matches!(
+            &received[1],
+            FlowEvent::NodeCompleted { node_id, action, .. } if node_id == "first" && action == "next"
+        ));
+        assert_eq!(
+            received[4],
+            FlowEvent::FlowCompleted {
+                final_action: "complete".to_string(),
+                steps: 2
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&received[4]).unwrap(),
+            json!({"type": "flow_completed", "final_action": "complete", "steps": 2})
+        );
+
+        // Failures are reported before the error is returned
+        let mut flow = FlowBuilder::<InMemoryStorage>::new()
+            .start_node("missing")
+            .build();
+        let mut events = flow.subscribe();
+        assert!(flow.execute(&mut store).await.is_err());
+        assert!(matches!(
+            events.try_recv(),
+            Ok(FlowEvent::FlowFailed { .. })
+        ));
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_multiple_action_takes_highest_priority_branch() {
+        use crate::FunctionNode;
+
+        let branch = |action: Action| {
+            FunctionNode::new(
+                "branch".to_string(),
+                |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+                |_, _ctx| Ok(()),
+                move |_store, _prep, _result, _ctx| Ok(action.clone()),
+            )
+        };
+        let record = |name: &'static str| {
+            FunctionNode::new(
+                name.to_string(),
+                |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+                |_, _ctx| Ok(()),
+                move |store: &mut SharedStore<InMemoryStorage>, _prep, _result, _ctx| {
+                    store.set("taken".to_string(), json!(name)).unwrap();
+                    Ok(Action::simple("complete"))
+                },
+            )
+        };
+
+        let fan_out = Action::multiple(vec![
+            Action::with_priority(Action::simple("low"), 1),
+            Action::with_priority(Action::simple("unrouted"), 100),
+            Action::with_priority(Action::simple("high"), 10),
+        ]);
+        let mut flow = FlowBuilder::new()
+            .start_node("start")
+            .node("start", Node::new(branch(fan_out)))
+            .node("low_node", Node::new(record("low")))
+            .node("high_node", Node::new(record("high")))
+            .route("start", "low", "low_node")
+            .route("start", "high", "high_node")
+            .build();
+
+        let mut store = SharedStore::new();
+        let result = flow.execute(&mut store).await.unwrap();
+        assert_eq!(store.get("taken").unwrap(), Some(json!("high")));
+        assert_eq!(result.execution_path, vec!["start", "high_node"]);
+
+        // A higher-priority terminal branch ends the flow
+        let mut flow = FlowBuilder::new()
+            .start_node("start")
+            .node(
+                "start",
+                Node::new(branch(Action::multiple(vec![
+                    Action::simple("low"),
+                    Action::with_priority(Action::simple("complete"), 1),
+                ]))),
+            )
+            .node("low_node", Node::new(record("low")))
+            .route("start", "low", "low_node")
+            .build();
+        let result = flow.execute(&mut store).await.unwrap();
+        assert_eq!(result.final_action.name(), "complete");
+        assert_eq!(result.steps_executed, 1);
+    }
+
+    #[cfg(all(feature = "storage-memory", feature = "builtin-nodes"))]
+    #[tokio::test]
+    async fn test_pool_limits_concurrent_nodes_across_flows() {
+        use crate::node::builtin::DelayNode;
+        use std::time::{Duration, Instant};
+
+        let config = FlowConfig::default()
+            .pool("api", 1)
+            .node_pool("call", "api");
+        let build = || {
+            FlowBuilder::<InMemoryStorage>::with_config(config.clone())
+                .start_node("call")
+                .node(
+                    "call",
+                    Node::new(DelayNode::new(
+                        Duration::from_millis(50),
+                        Action::simple("complete"),
+                    )),
+                )
+                .build()
+        };
+        let (mut first, mut second) = (build(), build());
+        let (mut first_store, mut second_store) = (SharedStore::new(), SharedStore::new());
+
+        let started = Instant::now();
+        let (a, b) = tokio::join!(
+            first.execute(&mut first_store),
+            second.execute(&mut second_store)
+        );
+        a.unwrap();
+        b.unwrap();
+        // A single permit serializes the two delays
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert_eq!(config.available_permits("api"), Some(1));
+
+        let mut misconfigured = FlowBuilder::<InMemoryStorage>::new()
+            .start_node("call")
+            .node_pool("call", "missing")
+            .node(
+                "call",
+                Node::new(DelayNode::new(Duration::ZERO, Action::simple("complete"))),
+            )
+            .build();
+        assert!(matches!(
+            misconfigured.execute(&mut first_store).await,
+            Err(FlowError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_key_access_policy_denies_protected_writes() {
+        use crate::FunctionNode;
+        use crate::shared_store::SharedStoreError;
+
+        // The tool writes its result, then tries to rewrite the history and
+        // ignores the refusal
+        let tool = Node::new(FunctionNode::new(
+            "tool".to_string(),
+            |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+            |_, _ctx| Ok(()),
+            |store, _prep, _exec, _ctx| {
+                store.set("tool.result".to_string(), json!(42))?;
+                let refused = store.set("history".to_string(), json!([]));
+                assert!(matches!(
+                    refused,
+                    Err(SharedStoreError::PermissionDenied { ref key, .. }) if key == "history"
+                ));
+                Ok(Action::simple("complete"))
+            },
+        ));
+        let mut flow = FlowBuilder::new()
+            .start_node("tool")
+            .key_access("tool", KeyAccessPolicy::new().read_write("tool."))
+            .node("tool", tool)
+            .build();
+
+        let mut store = SharedStore::new();
+        store.set("history".to_string(), json!(["hi"])).unwrap();
+        let error = flow.execute(&mut store).await.unwrap_err();
+        assert!(error.to_string().contains("Permission denied"));
+        assert!(error.to_string().contains("'history'"));
+        assert_eq!(store.get("history").unwrap(), Some(json!(["hi"])));
+        assert_eq!(store.get("tool.result").unwrap(), Some(json!(42)));
+
+        // Outside the flow the store is unrestricted again
+        store.set("history".to_string(), json!([])).unwrap();
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_error_route_records_failure() {
+        use crate::FunctionNode;
+        use crate::node::NodePhase;
+
+        let fetch = || {
+            Node::new(
+                FunctionNode::new(
+                    "fetch".to_string(),
+                    |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+                    |_, _ctx| -> Result<(), _> { Err("service unavailable".into()) },
+                    |_store, _prep, _exec, _ctx| Ok(Action::simple("complete")),
+                )
+                .with_retries(2),
+            )
+        };
+        let handler = Node::new(FunctionNode::new(
+            "handler".to_string(),
+            |store: &SharedStore<InMemoryStorage>, _ctx| store.errors().unwrap(),
+            |errors, _ctx| Ok(errors),
+            |store, _prep, errors, _ctx| {
+                store.set("handled".to_string(), json!(errors[0].message))?;
+                Ok(Action::simple("complete"))
+            },
+        ));
+        let mut flow = FlowBuilder::new()
+            .start_node("fetch")
+            .node("fetch", fetch())
+            .node("handler", handler)
+            .error_route("fetch", "handler")
+            .build();
+
+        let mut store = SharedStore::new();
+        let result = flow.execute(&mut store).await.unwrap();
+        assert_eq!(result.execution_path, vec!["fetch", "handler"]);
+        let failure = result.last_error().unwrap();
+        assert_eq!(failure.node_id, "fetch");
+        assert_eq!(failure.phase, NodePhase::Exec);
+        assert_eq!(failure.retries, 2);
+        assert!(failure.message.contains("service unavailable"));
+        assert_eq!(result.errors_for("fetch").count(), 1);
+        assert_eq!(store.errors().unwrap(), result.errors);
+        assert_eq!(
+            store.get("handled").unwrap(),
+            Some(json!(failure.message.clone()))
+        );
+
+        // Without error routing the failure ends the run and isn't stored
+        let mut flow = FlowBuilder::new()
+            .start_node("fetch")
+            .node("fetch", fetch())
+            .build();
+        let mut store = SharedStore::new();
+        let report = flow.execute_with_report(&mut store).await;
+        assert!(report.error.is_some());
+        assert_eq!(report.result.errors.len(), 1);
+        assert!(store.errors().unwrap().is_empty());
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_flow_vars_last_one_run() {
+        use crate::FunctionNode;
+
+        // Loops three times on a counter kept outside the store
+        let counter = Node::new(FunctionNode::new(
+            "counter".to_string(),
+            |_store: &SharedStore<InMemoryStorage>, ctx| {
+                ctx.vars()
+                    .update("count", |count| json!(count.as_u64().unwrap_or(0) + 1))
+            },
+            |count, _ctx| Ok(count),
+            |_store, _prep, count, _ctx| match count.as_u64() {
+                Some(3) => Ok(Action::simple("complete")),
+                _ => Ok(Action::simple("again")),
+            },
+        ));
+        let mut flow = FlowBuilder::new()
+            .start_node("counter")
+            .node("counter", counter)
+            .route("counter", "again", "counter")
+            .build();
+        flow.set_config(FlowConfig {
+            detect_cycles: false,
+            ..flow.config().clone()
+        });
+
+        let mut store = SharedStore::new();
+        let result = flow.execute(&mut store).await.unwrap();
+        assert_eq!(result.steps_executed, 3);
+        assert!(store.get("count").unwrap().is_none());
+
+        // The next run starts counting again
+        let result = flow.execute(&mut store).await.unwrap();
+        assert_eq!(result.steps_executed, 3);
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_dry_run_stubs_side_effecting_nodes() {
+        use crate::FunctionNode;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let sent = Arc::new(AtomicUsize::new(0));
+        let draft = Node::new(FunctionNode::new(
+            "draft".to_string(),
+            |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+            |_, _ctx| Ok(()),
+            |store, _prep, _exec, _ctx| {
+                store.set("draft".to_string(), json!("Hello"))?;
+                Ok(Action::simple("send"))
+            },
+        ));
+        let send = {
+            let sent = sent.clone();
+            Node::new(
+                FunctionNode::new(
+                    "send".to_string(),
+                    |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+                    move |_, _ctx| {
+                        sent.fetch_add(1, Ordering::SeqCst);
+                        Ok(())
+                    },
+                    |store, _prep, _exec, _ctx| {
+                        store.set("receipt".to_string(), json!("sent"))?;
+                        Ok(Action::simple("complete"))
+                    },
+                )
+                .side_effecting(),
+            )
+        };
+        let mut flow = FlowBuilder::new()
+            .start_node("draft")
+            .node("draft", draft)
+            .node("send", send)
+            .route("draft", "send", "send")
+            .dry_run(DryRun::new().with_default_stub(
+                DryRunStub::new(Action::simple("complete")).with_write("receipt", json!("stub")),
+            ))
+            .build();
+
+        let mut store = SharedStore::new();
+        let result = flow.execute(&mut store).await.unwrap();
+        assert_eq!(sent.load(Ordering::SeqCst), 0);
+        assert_eq!(store.get("draft").unwrap(), Some(json!("Hello")));
+        assert_eq!(store.get("receipt").unwrap(), Some(json!("stub")));
+        assert_eq!(result.final_action.name(), "complete");
+        assert_eq!(result.dry_run_calls.len(), 1);
+        assert_eq!(result.dry_run_calls[0].node_id, "send");
+        assert_eq!(result.dry_run_calls[0].step, 2);
+        assert_eq!(result.node_stats[1].attempts, 0);
+    }
+
+    #[cfg(all(feature = "storage-memory", feature = "builtin-nodes"))]
+    #[tokio::test]
+    async fn test_override_node_config() {
+        use crate::node::NodeRegistry;
+
+        #[derive(Serialize, Deserialize)]
+        struct Greeting {
+            text: String,
+            punctuation: Option<String>,
+        }
+
+        let mut registry = NodeRegistry::<InMemoryStorage>::new();
+        registry.register_configurable(
+            "greet",
+            Greeting {
+                text: "hello".to_string(),
+                punctuation: Some("!".to_string()),
+            },
+            |config: Greeting| {
+                let text = config.text + config.punctuation.as_deref().unwrap_or("");
+                Node::new(SetValueNode::new(
+                    "greeting",
+                    json!(text),
+                    Action::simple("end"),
+                ))
+            },
+        );
+        registry.register("log", || {
+            Node::new(LogNode::new("done", Action::simple("end")))
+        });
+        let mut flow = BasicFlow::new();
+        flow.add_node("start".to_string(), registry.create("greet").unwrap())
+            .unwrap();
+        flow.add_node("log".to_string(), registry.create("log").unwrap())
+            .unwrap();
+
+        let mut store = SharedStore::new();
+        flow.execute(&mut store).await.unwrap();
+        assert_eq!(store.get("greeting").unwrap(), Some(json!("hello!")));
+
+        flow.override_config(json!({"start": {"text": "hi", "punctuation": null}}))
+            .unwrap();
+        flow.execute(&mut store).await.unwrap();
+        assert_eq!(store.get("greeting").unwrap(), Some(json!("hi")));
+
+        // Invalid patches and nodes without a config leave the flow unchanged
+        assert!(matches!(
+            flow.override_node_config("start", json!({"text": 42})),
+            Err(FlowError::InvalidConfiguration(_))
+        ));
+        assert!(matches!(
+            flow.override_node_config("log", json!({})),
+            Err(FlowError::InvalidConfiguration(_))
+        ));
+        assert!(matches!(
+            flow.override_node_config("missing", json!({})),
+            Err(FlowError::NodeNotFound(_))
+        ));
+        flow.execute(&mut store).await.unwrap();
+        assert_eq!(store.get("greeting").unwrap(), Some(json!("hi")));
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_correlation_id_reaches_nodes_and_journal() {
+        use crate::FunctionNode;
+
+        let record = |id: &str| {
+            Node::new(FunctionNode::new(
+                id.to_string(),
+                |_store: &SharedStore<InMemoryStorage>, ctx: &ExecutionContext| {
+                    ctx.correlation_id.clone()
+                },
+                |correlation_id, _ctx| Ok(correlation_id),
+                |store, _prep, correlation_id, _ctx| {
+                    store.set("seen".to_string(), json!(correlation_id))?;
+                    Ok(Action::simple("next"))
+                },
+            ))
+        };
+        let fail = Node::new(FunctionNode::new(
+            "fail".to_string(),
+            |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+            |_, _ctx| Err("boom".into()),
+            |_store, _prep, _exec: (), _ctx| Ok(Action::simple("end")),
+        ));
+        let inner = FlowBuilder::new()
+            .start_node("record")
+            .node("record", record("record"))
+            .terminal_action("next")
+            .build();
+        let mut flow = FlowBuilder::new()
+            .start_node("nested")
+            .node("nested", Node::new(inner))
+            .node("fail", fail)
+            .route("nested", "next", "fail")
+            .error_route("fail", "nested")
+            .max_steps(3)
+            .correlation_id("req-42")
+            .build();
+
+        let mut store = SharedStore::new();
+        let report = flow.execute_with_report(&mut store).await;
+        assert_eq!(report.result.correlation_id, "req-42");
+        assert_eq!(store.get("seen").unwrap(), Some(json!("req-42")));
+        let errors = store.errors().unwrap();
+        assert_eq!(errors[0].correlation_id.as_deref(), Some("req-42"));
+
+        // Without a caller-supplied ID each run gets its own
+        let mut flow = FlowBuilder::new()
+            .start_node("record")
+            .node("record", record("record"))
+            .terminal_action("next")
+            .build();
+        let first = flow.execute(&mut store).await.unwrap().correlation_id;
+        assert_eq!(store.get("seen").unwrap(), Some(json!(first)));
+        let second = flow.execute(&mut store).await.unwrap().correlation_id;
+        assert_ne!(first, second);
+    }
+
+    #[cfg(feature = "storage-file")]
+    #[tokio::test]
+    async fn test_write_behind_storage_is_flushed_after_each_node() {
+        use crate::FunctionNode;
+        use crate::storage::FileStorage;
+        use std::sync::{Arc, Mutex};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("store.json");
+        // Keys on disk as each node starts
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let write = |id: &str, key: &'static str, action: &'static str| {
+            let (path, seen) = (path.clone(), seen.clone());
+            Node::new(FunctionNode::new(
+                id.to_string(),
+                move |_store: &SharedStore<FileStorage>, _ctx| {
+                    let mut keys = FileStorage::new(&path).unwrap().keys().unwrap();
+                    keys.sort();
+                    seen.lock().unwrap().push(keys);
+                },
+                |_, _ctx| Ok(()),
+                move |store, _prep, _exec, _ctx| {
+                    store.set(key.to_string(), json!(true))?;
+                    assert!(store.storage().has_pending_writes());
+                    Ok(Action::simple(action))
+                },
+            ))
+        };
+        let mut flow = FlowBuilder::new()
+            .start_node("first")
+            .node("first", write("first", "a", "next"))
+            .node("second", write("second", "b", "complete"))
+            .route("first", "next", "second")
+            .build();
+        let storage = FileStorage::new(&path).unwrap().with_write_behind(true);
+        let mut store = SharedStore::with_storage(storage);
+        flow.execute(&mut store).await.unwrap();
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![Vec::<String>::new(), vec!["a".to_string()]]
+        );
+        assert!(!store.storage().has_pending_writes());
+        let on_disk = FileStorage::new(&path).unwrap();
+        assert_eq!(on_disk.get("b").unwrap(), Some(json!(true)));
+        assert!(on_disk.get(SystemKeys::EXECUTION_PATH).unwrap().is_some());
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_checkpoints_and_time_travel() {
+        use crate::FunctionNode;
+        use crate::shared_store::{CheckpointError, KeyChange};
+
+        let write = |id: &str,
+                     key: &'static str,
+                     value: Option<serde_json::Value>,
+                     action: &'static str| {
+            Node::new(FunctionNode::new(
+                id.to_string(),
+                |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+                |_, _ctx| Ok(()),
+                move |store, _prep, _exec, _ctx| {
+                    match &value {
+                        Some(value) => store.set(key.to_string(), value.clone())?,
+                        None => {
+                            store.remove(key)?;
+                        }
+                    }
+                    Ok(Action::simple(action))
+                },
+            ))
+        };
+        let checkpoints = CheckpointManager::new();
+        let mut flow = FlowBuilder::new()
+            .start_node("draft")
+            .node(
+                "draft",
+                write("draft", "plan", Some(json!("draft")), "next"),
+            )
+            .node(
+                "note",
+                write("note", "note", Some(json!("check dates")), "next"),
+            )
+            .node(
+                "revise",
+                write("revise", "plan", Some(json!("final")), "next"),
+            )
+            .node("clean", write("clean", "note", None, "complete"))
+            .route("draft", "next", "note")
+            .route("note", "next", "revise")
+            .route("revise", "next", "clean")
+            .checkpoints(checkpoints.clone())
+            .build();
+        let mut store = SharedStore::new();
+        flow.execute(&mut store).await.unwrap();
+
+        assert_eq!(checkpoints.checkpoints().len(), 4);
+        let step = |n| checkpoints.at_step(n).unwrap();
+        assert_eq!(step(3).label, "revise");
+        assert_eq!(
+            checkpoints.diff(&step(1).id, &step(3).id).unwrap(),
+            vec![
+                KeyChange::Added {
+                    key: "note".to_string(),
+                    value: json!("check dates"),
+                },
+                KeyChange::Changed {
+                    key: "plan".to_string(),
+                    before: json!("draft"),
+                    after: json!("final"),
+                },
+            ]
+        );
+        assert_eq!(
+            checkpoints.diff(&step(3).id, &step(4).id).unwrap()[0],
+            KeyChange::Removed {
+                key: "note".to_string(),
+                value: json!("check dates"),
+            }
+        );
+
+        // What the flow knew after its second step
+        let past = checkpoints.time_travel(&step(2).id).unwrap();
+        assert_eq!(past.get("plan").unwrap(), Some(json!("draft")));
+        assert_eq!(past.get("note").unwrap(), Some(json!("check dates")));
+        assert_eq!(past.checkpoint().step, Some(2));
+        assert_eq!(store.get("note").unwrap(), None);
+        assert_eq!(
+            checkpoints.diff("checkpoint-0", &step(1).id),
+            Err(CheckpointError::NotFound("checkpoint-0".to_string()))
+        );
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_intent_log_surfaces_incomplete_intents() {
+        use crate::FunctionNode;
+        use crate::shared_store::{Intent, IntentStatus};
+
+        let send = Node::new(
+            FunctionNode::new(
+                "send".to_string(),
+                |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+                |_, _ctx| Ok(()),
+                |_store, _prep, _exec, _ctx| Ok(Action::simple("complete")),
+            )
+            .side_effecting(),
+        );
+        let mut flow = FlowBuilder::new()
+            .start_node("send")
+            .node("send", send)
+            .intent_log()
+            .build();
+
+        let mut store = SharedStore::new();
+        let result = flow.execute(&mut store).await.unwrap();
+        assert!(result.incomplete_intents.is_empty());
+        let intents = store.intents().unwrap();
+        assert_eq!(intents.len(), 1);
+        assert_eq!(intents[0].node_id, "send");
+        assert_eq!(intents[0].status, IntentStatus::Completed);
+        assert!(intents[0].finished_at.is_some());
+
+        // A run that crashed mid-send leaves its intent pending
+        store
+            .record_intent(Intent {
+                id: "crashed".to_string(),
+                node_id: "send".to_string(),
+                operation: json!({"to": "ops@example.com"}),
+                status: IntentStatus::Pending,
+                started_at: 0,
+                finished_at: None,
+                correlation_id: None,
+            })
+            .unwrap();
+        let result = flow.execute(&mut store).await.unwrap();
+        assert_eq!(result.incomplete_intents.len(), 1);
+        assert_eq!(
+            result.incomplete_intents[0].operation["to"],
+            "ops@example.com"
+        );
+
+        let clock = crate::env::ManualClock::new(std::time::UNIX_EPOCH + Duration::from_secs(5));
+        let mut store = store.with_env(RuntimeEnv::new().with_clock(Arc::new(clock)));
+        assert!(store.resolve_intent("crashed").unwrap());
+        assert!(!store.resolve_intent("crashed").unwrap());
+        let resolved = store.intents().unwrap();
+        assert_eq!(resolved[1].status, IntentStatus::Resolved);
+        assert_eq!(resolved[1].finished_at, Some(5000));
+        let result = flow.execute(&mut store).await.unwrap();
+        assert!(result.incomplete_intents.is_empty());
+        store.prune_intents().unwrap();
+        assert!(store.intents().unwrap().is_empty());
+    }
+
+    #[cfg(feature = "storage-memory")]
+    #[test]
+    fn test_validate_checks_declared_keys() {
+        use crate::FunctionNode;
+
+        let node = |reads: &[&str], writes: Option<Vec<KeySpec>>| {
+            let node = FunctionNode::new(
+                "node".to_string(),
+                |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+                |_, _ctx| Ok(()),
+                |_store, _prep, _exec, _ctx| Ok(Action::simple("next")),
+            )
+            .with_reads(reads.iter().copied());
+            Node::new(match writes {
+                Some(writes) => node.with_writes(writes),
+                None => node,
+            })
+        };
+        let text = |key: &str| KeySpec::new(key).with_schema(json!({"type": "string"}));
+        let flow = |reader: &[&str], draft_writes: Vec<KeySpec>| {
+            FlowBuilder::new()
+                .start_node("draft")
+                .input("topic")
+                .node("draft", node(&["topic"], Some(draft_writes)))
+                .node("review", node(reader, Some(vec![])))
+                .route("draft", "next", "review")
+        };
+
+        assert!(
+            flow(&["summary"], vec![text("summary")])
+                .build()
+                .validate()
+                .is_ok()
+        );
+        let error = flow(&["sumary"], vec![text("summary")])
+            .build()
+            .validate()
+            .unwrap_err();
+        assert!(error.to_string().contains("reads key 'sumary'"));
+
+        // The reader expects a number
+        let mut numeric = flow(&[], vec![text("summary")]).build();
+        let reader = FunctionNode::new(
+            "reader".to_string(),
+            |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+            |_, _ctx| Ok(()),
+            |_store, _prep, _exec, _ctx| Ok(Action::simple("complete")),
+        )
+        .with_reads([KeySpec::new("summary").with_schema(json!({"type": "number"}))]);
+        numeric
+            .add_node("review".to_string(), Box::new(Node::new(reader)))
+            .unwrap();
+        assert!(
+            numeric
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("incompatible schema")
+        );
+
+        // Only one branch writes the key
+        let branchy = FlowBuilder::new()
+            .start_node("draft")
+            .node("draft", node(&[], Some(vec![])))
+            .node("write", node(&[], Some(vec![text("summary")])))
+            .node("review", node(&["summary"], Some(vec![])))
+            .route("draft", "next", "write")
+            .route("draft", "skip", "review")
+            .route("write", "next", "review");
+        assert!(branchy.build().validate().is_err());
+
+        // Undeclared writers may have written anything
+        let opaque = FlowBuilder::new()
+            .start_node("draft")
+            .node("draft", node(&[], None))
+            .node("review", node(&["summary"], Some(vec![])))
+            .route("draft", "next", "review");
+        assert!(opaque.build().validate().is_ok());
+    }
+
+    #[test]
+    fn test_folded_stacks() {
+        let ms = Duration::from_millis;
+        let stat = |node_id: &str, prep, exec, post, duration| NodeExecutionStat {
+            node_id: node_id.to_string(),
+            attempts: 1,
+            prep_duration: ms(prep),
+            exec_duration: ms(exec),
+            post_duration: ms(post),
+            duration: ms(duration),
+            action: Some("next".to_string()),
+        };
+        let mut result = FlowExecutionResult::pending();
+        result.node_stats = vec![
+            stat("plan", 1, 0, 2, 3),
+            stat("research::search", 0, 40, 1, 50),
+            stat("plan", 1, 0, 0, 1),
+        ];
+
+        assert_eq!(
+            result.folded_stacks(),
+            "plan;post 2000\nplan;prep 2000\nresearch;search 9000\n\
+             research;search;exec 40000\nresearch;search;post 1000\n"
+        );
+    }
 }
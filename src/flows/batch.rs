@@ -0,0 +1,275 @@
+//! Running one flow over many independent stores
+//!
+//! [`BatchFlow`] builds a fresh flow from its factory for every store and runs
+//! them concurrently, up to a configurable limit. The returned
+//! [`BatchResult`] keeps the per-item stores and results in input order along
+//! with aggregate statistics, which makes it suitable for dataset processing.
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::flows::BatchFlow;
+//! # use serde_json::json;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let batch = BatchFlow::new(|| {
+//!     FlowBuilder::new()
+//!         .start_node("double")
+//!         .node("double", Node::new(FunctionNode::new(
+//!             "double".to_string(),
+//!             |store: &SharedStore<InMemoryStorage>, _ctx| {
+//!                 store.get("n").ok().flatten().and_then(|n| n.as_i64()).unwrap_or(0)
+//!             },
+//!             |n, _ctx| Ok(n * 2),
+//!             |store, _n, doubled, _ctx| {
+//!                 store.set("doubled".to_string(), json!(doubled))?;
+//!                 Ok(Action::simple("complete"))
+//!             },
+//!         )))
+//!         .build()
+//! })
+//! .with_concurrency(4);
+//!
+//! let stores = (1..=10)
+//!     .map(|n| {
+//!         let mut store = SharedStore::new();
+//!         store.set("n".to_string(), json!(n)).unwrap();
+//!         store
+//!     })
+//!     .collect();
+//! let result = batch.execute(stores).await;
+//! assert_eq!(result.success_count(), 10);
+//! assert_eq!(result.items[2].store.get("doubled").unwrap(), Some(json!(6)));
+//! # }
+//! ```
+
+use crate::flow::{BasicFlow, Flow, FlowError, FlowExecutionResult};
+use crate::{SharedStore, StorageBackend};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+type BatchFactory<S> = Arc<dyn Fn() -> BasicFlow<S> + Send + Sync>;
+
+/// Runs the same flow over many stores concurrently
+pub struct BatchFlow<S: StorageBackend> {
+    factory: BatchFactory<S>,
+    concurrency: usize,
+}
+
+impl<S: StorageBackend> Clone for BatchFlow<S> {
+    fn clone(&self) -> Self {
+        Self {
+            factory: self.factory.clone(),
+            concurrency: self.concurrency,
+        }
+    }
+}
+
+impl<S: StorageBackend> std::fmt::Debug for BatchFlow<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchFlow")
+            .field("concurrency", &self.concurrency)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Outcome of running the flow on one store
+#[derive(Debug)]
+pub struct BatchItemResult<S: StorageBackend> {
+    /// Position of the store in the input
+    pub index: usize,
+    /// The store after the flow ran
+    pub store: SharedStore<S>,
+    pub result: Result<FlowExecutionResult, FlowError>,
+    pub duration: Duration,
+}
+
+/// Per-item results, in input order, and aggregate statistics
+#[derive(Debug)]
+pub struct BatchResult<S: StorageBackend> {
+    pub items: Vec<BatchItemResult<S>>,
+    /// Wall-clock time for the whole batch
+    pub duration: Duration,
+}
+
+impl<S: StorageBackend> BatchResult<S> {
+    /// Number of items whose flow completed
+    pub fn success_count(&self) -> usize {
+        self.items.iter().filter(|item| item.result.is_ok()).count()
+    }
+
+    /// Number of items whose flow failed
+    pub fn failure_count(&self) -> usize {
+        self.items.len() - self.success_count()
+    }
+
+    /// Steps executed across all successful items
+    pub fn total_steps(&self) -> usize {
+        self.items
+            .iter()
+            .filter_map(|item| item.result.as_ref().ok())
+            .map(|result| result.steps_executed)
+            .sum()
+    }
+
+    /// Sum of the per-item durations
+    pub fn total_item_duration(&self) -> Duration {
+        self.items.iter().map(|item| item.duration).sum()
+    }
+
+    /// Average per-item duration
+    pub fn average_duration(&self) -> Duration {
+        match self.items.len() {
+            0 => Duration::ZERO,
+            count => self.total_item_duration() / count as u32,
+        }
+    }
+
+    /// Items whose flow failed
+    pub fn failures(&self) -> impl Iterator<Item = &BatchItemResult<S>> {
+        self.items.iter().filter(|item| item.result.is_err())
+    }
+}
+
+impl<S> BatchFlow<S>
+where
+    S: StorageBackend + Send + Sync + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    /// Create a batch from a factory producing a fresh flow per store
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn() -> BasicFlow<S> + Send + Sync + 'static,
+    {
+        Self {
+            factory: Arc::new(factory),
+            concurrency: 8,
+        }
+    }
+
+    /// Set how many flows may run at once (default: 8)
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Get the concurrency limit
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Run the flow over every store
+    ///
+    /// A failing item doesn't stop the others; its error is reported in its
+    /// [`BatchItemResult`].
+    pub async fn execute(&self, stores: Vec<SharedStore<S>>) -> BatchResult<S> {
+        let started = Instant::now();
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let mut tasks = JoinSet::new();
+
+        for (index, mut store) in stores.into_iter().enumerate() {
+            let semaphore = semaphore.clone();
+            let factory = self.factory.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore
+                    .acquire_owned()
+                    .await
+                    .expect("batch semaphore is never closed");
+                let item_started = Instant::now();
+                let mut flow = factory();
+                let result = flow.execute(&mut store).await;
+                BatchItemResult {
+                    index,
+                    store,
+                    result,
+                    duration: item_started.elapsed(),
+                }
+            });
+        }
+
+        let mut items = Vec::with_capacity(tasks.len());
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok(item) => items.push(item),
+                // Surface node panics to the caller, as a sequential run would
+                Err(error) => std::panic::resume_unwind(error.into_panic()),
+            }
+        }
+        items.sort_by_key(|item| item.index);
+
+        BatchResult {
+            items,
+            duration: started.elapsed(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "storage-memory"))]
+mod tests {
+    use super::*;
+    use crate::Action;
+    use crate::flow::FlowBuilder;
+    use crate::node::{FunctionNode, Node};
+    use crate::storage::InMemoryStorage;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_batch_flow_limits_concurrency() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (running_in, peak_in) = (running.clone(), peak.clone());
+
+        let batch = BatchFlow::new(move || {
+            let (running, peak) = (running_in.clone(), peak_in.clone());
+            FlowBuilder::new()
+                .start_node("check")
+                .node(
+                    "check",
+                    Node::new(FunctionNode::new(
+                        "check".to_string(),
+                        |store: &SharedStore<InMemoryStorage>, _ctx| {
+                            store.get("n").ok().flatten().and_then(|n| n.as_i64())
+                        },
+                        move |n, _ctx| {
+                            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                            peak.fetch_max(now, Ordering::SeqCst);
+                            std::thread::sleep(Duration::from_millis(20));
+                            running.fetch_sub(1, Ordering::SeqCst);
+                            n.ok_or_else(|| "missing n".into())
+                        },
+                        |store, _prep, n, _ctx| {
+                            store.set("square".to_string(), json!(n * n))?;
+                            Ok(Action::simple("complete"))
+                        },
+                    )),
+                )
+                .build()
+        })
+        .with_concurrency(2);
+
+        let stores = (0..6)
+            .map(|n| {
+                let mut store = SharedStore::new();
+                if n != 3 {
+                    store.set("n".to_string(), json!(n)).unwrap();
+                }
+                store
+            })
+            .collect();
+        let result = batch.execute(stores).await;
+
+        assert_eq!(result.items.len(), 6);
+        assert_eq!(result.success_count(), 5);
+        assert_eq!(result.failure_count(), 1);
+        assert_eq!(result.failures().next().unwrap().index, 3);
+        assert_eq!(result.total_steps(), 5);
+        assert_eq!(
+            result.items[4].store.get("square").unwrap(),
+            Some(json!(16))
+        );
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+        assert!(result.average_duration() >= Duration::from_millis(20));
+    }
+}
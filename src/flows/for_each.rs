@@ -0,0 +1,302 @@
+//! Running a sub-flow once per element of a store array
+
+use crate::flow::{Flow, FlowError};
+use crate::node::{ExecutionContext, NodeBackend};
+use crate::{Action, SharedStore, StorageBackend};
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// Node that runs a sub-flow for every element of an array in the store
+///
+/// For each element of the array under the items key, the element is written
+/// to the item key (and its position to the index key, if set), the sub-flow
+/// runs on the store, and the value it left under the output key is collected.
+/// Elements without an output collect `null`. The collected array is stored
+/// under the results key.
+///
+/// The item, index and output keys are scoped to the iteration: their previous
+/// values are restored once all elements are done. Other keys written by the
+/// sub-flow are kept. A failing iteration fails the node.
+///
+/// ```rust
+/// # use pocketflow_rs::prelude::*;
+/// # use pocketflow_rs::flows::ForEachFlowNode;
+/// # use serde_json::json;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let square = FlowBuilder::new()
+///     .start_node("square")
+///     .node("square", Node::new(FunctionNode::new(
+///         "square".to_string(),
+///         |store: &SharedStore<InMemoryStorage>, _ctx| {
+///             store.get("item").ok().flatten().and_then(|n| n.as_i64()).unwrap_or(0)
+///         },
+///         |n, _ctx| Ok(n * n),
+///         |store, _n, squared, _ctx| {
+///             store.set("result".to_string(), json!(squared))?;
+///             Ok(Action::simple("complete"))
+///         },
+///     )))
+///     .build();
+///
+/// let mut node = Node::new(ForEachFlowNode::new(square, "numbers", "squares", Action::simple("next")));
+/// let mut store = SharedStore::new();
+/// store.set("numbers".to_string(), json!([1, 2, 3])).unwrap();
+/// node.run(&mut store).await.unwrap();
+/// assert_eq!(store.get("squares").unwrap(), Some(json!([1, 4, 9])));
+/// # }
+/// ```
+pub struct ForEachFlowNode<F, S>
+where
+    F: Flow<S>,
+    S: StorageBackend,
+{
+    flow: F,
+    items_key: String,
+    results_key: String,
+    item_key: String,
+    output_key: String,
+    index_key: Option<String>,
+    action: Action,
+    _phantom: std::marker::PhantomData<S>,
+}
+
+impl<F, S> ForEachFlowNode<F, S>
+where
+    F: Flow<S>,
+    S: StorageBackend,
+{
+    /// Create a node running `flow` over the array under `items_key`
+    ///
+    /// Elements are passed under `"item"` and outputs read from `"result"`
+    /// unless configured otherwise.
+    pub fn new<K: Into<String>>(flow: F, items_key: K, results_key: K, action: Action) -> Self {
+        Self {
+            flow,
+            items_key: items_key.into(),
+            results_key: results_key.into(),
+            item_key: "item".to_string(),
+            output_key: "result".to_string(),
+            index_key: None,
+            action,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Set the key each element is written to
+    pub fn with_item_key(mut self, key: impl Into<String>) -> Self {
+        self.item_key = key.into();
+        self
+    }
+
+    /// Set the key the sub-flow writes its output to
+    pub fn with_output_key(mut self, key: impl Into<String>) -> Self {
+        self.output_key = key.into();
+        self
+    }
+
+    /// Also write each element's position to the given key
+    pub fn with_index_key(mut self, key: impl Into<String>) -> Self {
+        self.index_key = Some(key.into());
+        self
+    }
+
+    /// Get a reference to the inner flow
+    pub fn flow(&self) -> &F {
+        &self.flow
+    }
+
+    /// Get a mutable reference to the inner flow
+    pub fn flow_mut(&mut self) -> &mut F {
+        &mut self.flow
+    }
+
+    fn scoped_keys(&self) -> Vec<String> {
+        let mut keys = vec![self.item_key.clone(), self.output_key.clone()];
+        keys.extend(self.index_key.clone());
+        keys
+    }
+}
+
+fn storage_error(error: impl std::fmt::Display) -> FlowError {
+    FlowError::NodeError(error.to_string())
+}
+
+#[async_trait]
+impl<F, S> NodeBackend<S> for ForEachFlowNode<F, S>
+where
+    F: Flow<S> + Send + Sync,
+    S: StorageBackend + Send + Sync + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    type PrepResult = Vec<Value>;
+    type ExecResult = ();
+    type Error = FlowError;
+
+    async fn prep(
+        &mut self,
+        store: &SharedStore<S>,
+        _context: &ExecutionContext,
+    ) -> Result<Self::PrepResult, Self::Error> {
+        self.flow.validate()?;
+        match store.get(&self.items_key).map_err(storage_error)? {
+            Some(Value::Array(items)) => Ok(items),
+            Some(_) => Err(FlowError::InvalidConfiguration(format!(
+                "Key '{}' must hold an array",
+                self.items_key
+            ))),
+            None => Err(FlowError::InvalidConfiguration(format!(
+                "Key '{}' not found in store",
+                self.items_key
+            ))),
+        }
+    }
+
+    async fn exec(
+        &mut self,
+        _prep_result: Self::PrepResult,
+        _context: &ExecutionContext,
+    ) -> Result<Self::ExecResult, Self::Error> {
+        // The sub-flow needs the store, so it runs in post
+        Ok(())
+    }
+
+    async fn post(
+        &mut self,
+        store: &mut SharedStore<S>,
+        items: Self::PrepResult,
+        _exec_result: Self::ExecResult,
+        _context: &ExecutionContext,
+    ) -> Result<Action, Self::Error> {
+        let scoped = self.scoped_keys();
+        let mut saved = Vec::with_capacity(scoped.len());
+        for key in &scoped {
+            saved.push((key.clone(), store.get(key).map_err(storage_error)?));
+        }
+
+        let mut results = Vec::with_capacity(items.len());
+        let mut outcome = Ok(());
+        for (index, item) in items.into_iter().enumerate() {
+            store.remove(&self.output_key).map_err(storage_error)?;
+            store
+                .set(self.item_key.clone(), item)
+                .map_err(storage_error)?;
+            if let Some(index_key) = &self.index_key {
+                store
+                    .set(index_key.clone(), Value::from(index))
+                    .map_err(storage_error)?;
+            }
+            if let Err(error) = self.flow.execute(store).await {
+                outcome = Err(FlowError::NodeError(format!(
+                    "Iteration {} failed: {}",
+                    index, error
+                )));
+                break;
+            }
+            results.push(
+                store
+                    .get(&self.output_key)
+                    .map_err(storage_error)?
+                    .unwrap_or(Value::Null),
+            );
+        }
+
+        // Restore the scoped keys even if an iteration failed
+        for (key, value) in saved {
+            match value {
+                Some(value) => store.set(key, value).map_err(storage_error)?,
+                None => {
+                    store.remove(&key).map_err(storage_error)?;
+                }
+            }
+        }
+        outcome?;
+
+        store
+            .set(self.results_key.clone(), Value::Array(results))
+            .map_err(storage_error)?;
+        Ok(self.action.clone())
+    }
+
+    fn name(&self) -> &str {
+        "ForEachFlowNode"
+    }
+}
+
+#[cfg(all(test, feature = "storage-memory"))]
+mod tests {
+    use super::*;
+    use crate::flow::FlowBuilder;
+    use crate::node::{FunctionNode, Node};
+    use crate::storage::InMemoryStorage;
+    use serde_json::json;
+
+    fn label_flow() -> crate::flow::BasicFlow<InMemoryStorage> {
+        FlowBuilder::new()
+            .start_node("label")
+            .node(
+                "label",
+                Node::new(FunctionNode::new(
+                    "label".to_string(),
+                    |store: &SharedStore<InMemoryStorage>, _ctx| {
+                        let name = store.get("name").ok().flatten().unwrap_or_default();
+                        let index = store.get("i").ok().flatten().unwrap_or_default();
+                        (name, index)
+                    },
+                    |(name, index), _ctx| match name.as_str() {
+                        Some("skip") => Ok(None),
+                        Some(name) => Ok(Some(format!("{}:{}", index, name))),
+                        None => Err("name must be a string".into()),
+                    },
+                    |store, _prep, label, _ctx| {
+                        if let Some(label) = label {
+                            store.set("label".to_string(), json!(label))?;
+                        }
+                        Ok(Action::simple("complete"))
+                    },
+                )),
+            )
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_for_each_flow_node() {
+        let mut node = Node::new(
+            ForEachFlowNode::new(label_flow(), "names", "labels", Action::simple("next"))
+                .with_item_key("name")
+                .with_output_key("label")
+                .with_index_key("i"),
+        );
+        let mut store = SharedStore::new();
+        store
+            .set("names".to_string(), json!(["ada", "skip", "alan"]))
+            .unwrap();
+        store.set("name".to_string(), json!("outer")).unwrap();
+
+        let action = node.run(&mut store).await.unwrap();
+        assert_eq!(action, Action::simple("next"));
+        assert_eq!(
+            store.get("labels").unwrap(),
+            Some(json!(["0:ada", null, "2:alan"]))
+        );
+        // Scoped keys are restored
+        assert_eq!(store.get("name").unwrap(), Some(json!("outer")));
+        assert_eq!(store.get("label").unwrap(), None);
+        assert_eq!(store.get("i").unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_for_each_flow_node_errors() {
+        let mut node = Node::new(
+            ForEachFlowNode::new(label_flow(), "names", "labels", Action::simple("next"))
+                .with_item_key("name"),
+        );
+        let mut store = SharedStore::new();
+        assert!(node.run(&mut store).await.is_err());
+
+        store.set("names".to_string(), json!(["ada", 7])).unwrap();
+        assert!(node.run(&mut store).await.is_err());
+        assert_eq!(store.get("labels").unwrap(), None);
+        assert_eq!(store.get("name").unwrap(), None);
+    }
+}
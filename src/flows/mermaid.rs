@@ -0,0 +1,392 @@
+//! Build flows from Mermaid flowchart text
+//!
+//! Teams can sketch a workflow in Mermaid and load it with
+//! [`BasicFlow::from_mermaid`]. Node IDs become flow node IDs, node labels
+//! name nodes in a [`NodeRegistry`] (a node without a label uses its ID), and
+//! edge labels become actions. Unlabelled edges use [`DEFAULT_ACTION`]. The
+//! first node mentioned is the start node.
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::flow::BasicFlow;
+//! # use pocketflow_rs::node::NodeRegistry;
+//! # use pocketflow_rs::node::builtin::LogNode;
+//! let mut registry = NodeRegistry::<InMemoryStorage>::new();
+//! registry.register("fetch", || Node::new(LogNode::new("fetching", Action::simple("ok"))));
+//! registry.register("report", || Node::new(LogNode::new("done", Action::simple("end"))));
+//!
+//! let flow = BasicFlow::from_mermaid(
+//!     r#"
+//!     flowchart TD
+//!         start[fetch] -->|ok| summary[report]
+//!         start -->|retry| start
+//!     "#,
+//!     &registry,
+//! )
+//! .unwrap();
+//! assert_eq!(flow.config().start_node_id, "start");
+//! ```
+//!
+//! Supported syntax: a `flowchart`/`graph` header, node shapes (`[..]`,
+//! `(..)`, `((..))`, `{..}`, ...), `-->`, `==>` and `-.->` arrows with
+//! `|label|` or inline (`-- label -->`) labels, chained edges and `;`
+//! separators. Styling statements, `subgraph`/`end` lines and `%%` comments
+//! are ignored.
+
+use crate::StorageBackend;
+use crate::flow::{BasicFlow, Flow, FlowConfig, Route};
+use crate::node::NodeRegistry;
+
+/// Action used for edges without a label
+pub const DEFAULT_ACTION: &str = "default";
+
+/// Errors raised while loading a flow from Mermaid text
+#[derive(Debug, thiserror::Error)]
+pub enum MermaidError {
+    #[error("Expected a `flowchart` or `graph` header")]
+    MissingHeader,
+    #[error("Line {line}: {message}")]
+    Syntax { line: usize, message: String },
+    #[error("Node '{id}' is labelled both '{first}' and '{second}'")]
+    ConflictingLabel {
+        id: String,
+        first: String,
+        second: String,
+    },
+    #[error("Node '{id}' refers to unregistered node '{name}'")]
+    UnknownNode { id: String, name: String },
+    #[error("The diagram has no nodes")]
+    Empty,
+}
+
+/// Nodes and edges of a parsed diagram
+#[derive(Debug, Default, PartialEq)]
+struct Diagram {
+    /// Node IDs in order of first mention, with their label if any
+    nodes: Vec<(String, Option<String>)>,
+    /// `(from, action, to)`
+    edges: Vec<(String, String, String)>,
+}
+
+impl Diagram {
+    fn add_node(&mut self, id: &str, label: Option<String>) -> Result<(), MermaidError> {
+        match self.nodes.iter_mut().find(|(known, _)| known == id) {
+            None => self.nodes.push((id.to_string(), label)),
+            Some((_, existing)) => match (existing.as_ref(), label) {
+                (Some(first), Some(second)) if *first != second => {
+                    return Err(MermaidError::ConflictingLabel {
+                        id: id.to_string(),
+                        first: first.clone(),
+                        second,
+                    });
+                }
+                (None, Some(label)) => *existing = Some(label),
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Statements that only affect rendering
+const IGNORED_KEYWORDS: &[&str] = &[
+    "classDef",
+    "class",
+    "style",
+    "linkStyle",
+    "click",
+    "direction",
+    "subgraph",
+];
+
+/// Arrows, longest first
+const ARROWS: &[&str] = &["-.->", "-->", "==>"];
+
+/// Openings of inline-labelled arrows and the text closing their label
+const INLINE_OPENERS: &[(&str, &str)] = &[("-.", ".->"), ("--", "-->"), ("==", "==>")];
+
+fn parse(text: &str) -> Result<Diagram, MermaidError> {
+    let mut diagram = Diagram::default();
+    let mut seen_header = false;
+
+    for (index, line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let line = match line.find("%%") {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        for statement in line.split(';').map(str::trim) {
+            if statement.is_empty() {
+                continue;
+            }
+            if !seen_header {
+                let keyword = statement.split_whitespace().next().unwrap_or_default();
+                if keyword != "flowchart" && keyword != "graph" {
+                    return Err(MermaidError::MissingHeader);
+                }
+                seen_header = true;
+                continue;
+            }
+            let keyword = statement.split_whitespace().next().unwrap_or_default();
+            if statement == "end" || IGNORED_KEYWORDS.contains(&keyword) {
+                continue;
+            }
+            parse_statement(statement, line_no, &mut diagram)?;
+        }
+    }
+
+    if !seen_header {
+        return Err(MermaidError::MissingHeader);
+    }
+    if diagram.nodes.is_empty() {
+        return Err(MermaidError::Empty);
+    }
+    Ok(diagram)
+}
+
+/// Parse a node or a chain of edges
+fn parse_statement(
+    statement: &str,
+    line: usize,
+    diagram: &mut Diagram,
+) -> Result<(), MermaidError> {
+    let syntax = |message| MermaidError::Syntax { line, message };
+
+    let ((mut from, label), mut rest) = parse_node(statement).map_err(syntax)?;
+    diagram.add_node(&from, label)?;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(());
+        }
+        let (action, after_arrow) = parse_arrow(rest).map_err(syntax)?;
+        let ((to, label), after_node) = parse_node(after_arrow.trim_start()).map_err(syntax)?;
+        diagram.add_node(&to, label)?;
+        let action = action.unwrap_or_else(|| DEFAULT_ACTION.to_string());
+        diagram.edges.push((from, action, to.clone()));
+        from = to;
+        rest = after_node;
+    }
+}
+
+/// Trim a label and strip surrounding double quotes
+fn unquote(label: &str) -> String {
+    let label = label.trim();
+    label
+        .strip_prefix('"')
+        .and_then(|label| label.strip_suffix('"'))
+        .unwrap_or(label)
+        .to_string()
+}
+
+/// Parse `id`, `id[label]`, `id((label))`, ... returning the ID, the label and
+/// the remaining text
+fn parse_node(text: &str) -> Result<((String, Option<String>), &str), String> {
+    let id_len = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .unwrap_or(text.len());
+    if id_len == 0 {
+        return Err(format!("expected a node ID at '{}'", text));
+    }
+    let (id, rest) = text.split_at(id_len);
+
+    let opener_len = rest
+        .find(|c: char| !matches!(c, '[' | '(' | '{' | '>' | '/' | '\\'))
+        .unwrap_or(rest.len());
+    let (label, rest) = if opener_len == 0 {
+        (None, rest)
+    } else {
+        let (opener, body) = rest.split_at(opener_len);
+        let closer: String = opener
+            .chars()
+            .rev()
+            .map(|c| match c {
+                '[' | '>' => ']',
+                '(' => ')',
+                '{' => '}',
+                other => other,
+            })
+            .collect();
+        let end = body
+            .find(&closer)
+            .ok_or_else(|| format!("unclosed shape for node '{}'", id))?;
+        (Some(unquote(&body[..end])), &body[end + closer.len()..])
+    };
+
+    // Skip a `:::class` suffix
+    let rest = match rest.strip_prefix(":::") {
+        Some(class) => {
+            class.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_' || c == '-')
+        }
+        None => rest,
+    };
+    Ok(((id.to_string(), label), rest))
+}
+
+/// Parse an arrow and its optional label, returning the label and the
+/// remaining text
+fn parse_arrow(text: &str) -> Result<(Option<String>, &str), String> {
+    for arrow in ARROWS {
+        if let Some(rest) = text.strip_prefix(arrow) {
+            let rest = rest.trim_start();
+            if let Some(labelled) = rest.strip_prefix('|') {
+                let end = labelled
+                    .find('|')
+                    .ok_or_else(|| "unclosed edge label".to_string())?;
+                return Ok((Some(unquote(&labelled[..end])), &labelled[end + 1..]));
+            }
+            return Ok((None, rest));
+        }
+    }
+    for (opener, closer) in INLINE_OPENERS {
+        if let Some(rest) = text.strip_prefix(opener) {
+            let end = rest
+                .find(closer)
+                .ok_or_else(|| format!("expected '{}' after edge label", closer))?;
+            return Ok((Some(unquote(&rest[..end])), &rest[end + closer.len()..]));
+        }
+    }
+    Err(format!("expected an arrow at '{}'", text))
+}
+
+impl<S> BasicFlow<S>
+where
+    S: StorageBackend + Send + Sync + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    /// Build a flow from a Mermaid flowchart, creating nodes from `registry`
+    ///
+    /// See the [module documentation](crate::flows::mermaid) for the
+    /// supported syntax.
+    pub fn from_mermaid(text: &str, registry: &NodeRegistry<S>) -> Result<Self, MermaidError> {
+        let diagram = parse(text)?;
+
+        let mut flow = BasicFlow::with_config(FlowConfig {
+            start_node_id: diagram.nodes[0].0.clone(),
+            ..FlowConfig::default()
+        });
+        for (id, label) in diagram.nodes {
+            let name = label.unwrap_or_else(|| id.clone());
+            let node = registry
+                .create(&name)
+                .ok_or_else(|| MermaidError::UnknownNode {
+                    id: id.clone(),
+                    name,
+                })?;
+            flow.add_node(id, node)
+                .expect("BasicFlow::add_node is infallible");
+        }
+
+        for (from, action, to) in diagram.edges {
+            let route = Route {
+                action,
+                target_node_id: to,
+                condition: None,
+            };
+            flow.add_route(from, route)
+                .expect("BasicFlow::add_route is infallible");
+        }
+        Ok(flow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(from: &str, action: &str, to: &str) -> (String, String, String) {
+        (from.to_string(), action.to_string(), to.to_string())
+    }
+
+    #[test]
+    fn test_parse_mermaid_syntax() {
+        let diagram = parse(
+            r#"
+            %% order pipeline
+            flowchart LR
+                a[fetch] -->|ok| b(("transform")) --> c{check}
+                c -- "yes" --> d; c -.->|no| a
+                c == retry ==> b:::hot
+                classDef hot fill:#f96
+                subgraph tail
+                    d
+                end
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            diagram.nodes,
+            vec![
+                ("a".to_string(), Some("fetch".to_string())),
+                ("b".to_string(), Some("transform".to_string())),
+                ("c".to_string(), Some("check".to_string())),
+                ("d".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            diagram.edges,
+            vec![
+                edge("a", "ok", "b"),
+                edge("b", DEFAULT_ACTION, "c"),
+                edge("c", "yes", "d"),
+                edge("c", "no", "a"),
+                edge("c", "retry", "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_mermaid_errors() {
+        assert!(matches!(parse("a --> b"), Err(MermaidError::MissingHeader)));
+        assert!(matches!(parse("graph TD"), Err(MermaidError::Empty)));
+        assert!(matches!(
+            parse("graph TD\n  a[x] --> a[y]"),
+            Err(MermaidError::ConflictingLabel { .. })
+        ));
+        assert!(matches!(
+            parse("graph TD\n  a --> "),
+            Err(MermaidError::Syntax { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse("graph TD\n  a[open --> b"),
+            Err(MermaidError::Syntax { line: 2, .. })
+        ));
+    }
+
+    #[cfg(all(feature = "storage-memory", feature = "builtin-nodes"))]
+    #[tokio::test]
+    async fn test_flow_from_mermaid() {
+        use crate::node::builtin::SetValueNode;
+        use crate::{Action, InMemoryStorage, Node, SharedStore};
+        use serde_json::json;
+
+        let mut registry = NodeRegistry::<InMemoryStorage>::new();
+        registry.register("fetch", || {
+            Node::new(SetValueNode::new(
+                "fetched".to_string(),
+                json!(true),
+                Action::simple("ok"),
+            ))
+        });
+        registry.register("store", || {
+            Node::new(SetValueNode::new(
+                "stored".to_string(),
+                json!(true),
+                Action::simple("end"),
+            ))
+        });
+
+        let text = "graph TD\n  load[fetch] -->|ok| store";
+        let mut flow = BasicFlow::from_mermaid(text, &registry).unwrap();
+        let mut store = SharedStore::new();
+        let result = flow.execute(&mut store).await.unwrap();
+        assert_eq!(result.execution_path, vec!["load", "store"]);
+        assert_eq!(store.get("stored").unwrap(), Some(json!(true)));
+
+        let loaded = BasicFlow::from_mermaid("graph TD\n  a[missing]", &registry);
+        assert!(
+            matches!(loaded, Err(MermaidError::UnknownNode { ref name, .. }) if name == "missing")
+        );
+    }
+}
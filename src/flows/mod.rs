@@ -0,0 +1,27 @@
+//! Reusable flow building blocks
+//!
+//! - [`batch`]: run one flow over many independent stores concurrently
+//! - [`for_each`]: run a sub-flow once per element of a store array, as a node
+//! - [`mermaid`]: build flows from Mermaid flowchart text, with nodes taken
+//!   from a [`NodeRegistry`](crate::node::NodeRegistry)
+//! - [`race`]: run alternative sub-flows concurrently, keeping the first to
+//!   reach a success action and canceling the others
+//! - [`template`]: parameterized flow definitions with declared input and
+//!   output keys, instantiated as often as needed and embedded with
+//!   [`FlowNode`](crate::flow::FlowNode)
+//! - [`stdlib`]: ready-made LLM flows (summarize, translate, extract entities,
+//!   classify intent) built on templates (feature: `builtin-llm`)
+
+pub mod batch;
+pub mod for_each;
+pub mod mermaid;
+pub mod race;
+#[cfg(feature = "builtin-llm")]
+pub mod stdlib;
+pub mod template;
+
+pub use batch::{BatchFlow, BatchItemResult, BatchResult};
+pub use for_each::ForEachFlowNode;
+pub use mermaid::MermaidError;
+pub use race::RaceFlowNode;
+pub use template::{FlowTemplate, TemplateFlow, TemplateParams};
@@ -0,0 +1,419 @@
+//! Running alternative sub-flows concurrently, keeping the first to succeed
+
+use crate::flow::{BasicFlow, Flow, FlowError};
+use crate::node::{ExecutionContext, NodeBackend};
+use crate::shared_store::SystemKeys;
+use crate::{Action, SharedStore, StorageBackend};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::future::Future;
+use std::task::Poll;
+
+/// Node that races several sub-flows and keeps the first to succeed
+///
+/// Every branch starts at once on its own copy of the store. The first branch
+/// to finish with the success action wins: the other branches are canceled,
+/// the winner's changes to the store are applied, and the node returns the
+/// success action. Branches that fail or finish with another action drop out;
+/// if none succeeds the node fails. Token usage recorded by every branch,
+/// winning or not, is added to the store's usage.
+///
+/// Branch stores start empty from `S::default()`, so the node suits in-memory
+/// backends. Useful to query a cheap and an expensive model at once, or
+/// several retrieval sources.
+///
+/// ```rust
+/// # use pocketflow_rs::prelude::*;
+/// # use pocketflow_rs::flows::RaceFlowNode;
+/// # use serde_json::json;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let lookup = |source: &'static str, found: bool| {
+///     FlowBuilder::new()
+///         .start_node("lookup")
+///         .terminal_action("found")
+///         .terminal_action("miss")
+///         .node("lookup", Node::new(FunctionNode::new(
+///             source.to_string(),
+///             |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+///             move |_, _ctx| Ok(found),
+///             move |store, _, found, _ctx| {
+///                 if !found {
+///                     return Ok(Action::simple("miss"));
+///                 }
+///                 store.set("answer".to_string(), json!(source))?;
+///                 Ok(Action::simple("found"))
+///             },
+///         )))
+///         .build()
+/// };
+/// let mut node = Node::new(
+///     RaceFlowNode::new("found")
+///         .with_branch("cache", lookup("cache", false))
+///         .with_branch("index", lookup("index", true))
+///         .with_winner_key("source"),
+/// );
+/// let mut store = SharedStore::new();
+/// assert_eq!(node.run(&mut store).await.unwrap(), Action::simple("found"));
+/// assert_eq!(store.get("answer").unwrap(), Some(json!("index")));
+/// assert_eq!(store.get("source").unwrap(), Some(json!("index")));
+/// # }
+/// ```
+pub struct RaceFlowNode<S: StorageBackend> {
+    branches: Vec<(String, BasicFlow<S>)>,
+    success_action: String,
+    winner_key: Option<String>,
+}
+
+impl<S: StorageBackend> std::fmt::Debug for RaceFlowNode<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RaceFlowNode")
+            .field(
+                "branches",
+                &self
+                    .branches
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .field("success_action", &self.success_action)
+            .field("winner_key", &self.winner_key)
+            .finish()
+    }
+}
+
+impl<S: StorageBackend> RaceFlowNode<S> {
+    /// Create a race won by the first branch finishing with `success_action`
+    pub fn new(success_action: impl Into<String>) -> Self {
+        Self {
+            branches: Vec::new(),
+            success_action: success_action.into(),
+            winner_key: None,
+        }
+    }
+
+    /// Add a branch
+    pub fn with_branch(mut self, name: impl Into<String>, flow: BasicFlow<S>) -> Self {
+        self.branches.push((name.into(), flow));
+        self
+    }
+
+    /// Write the name of the winning branch to `key`
+    pub fn with_winner_key(mut self, key: impl Into<String>) -> Self {
+        self.winner_key = Some(key.into());
+        self
+    }
+
+    /// Names of the branches, in the order they were added
+    pub fn branch_names(&self) -> Vec<&str> {
+        self.branches
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+fn storage_error(error: impl std::fmt::Display) -> FlowError {
+    FlowError::NodeError(error.to_string())
+}
+
+/// Wait for the first of `running` to finish, removing it; `None` once all
+/// have finished
+async fn next_finished<F: Future + Unpin>(running: &mut Vec<F>) -> Option<F::Output> {
+    if running.is_empty() {
+        return None;
+    }
+    std::future::poll_fn(|cx| {
+        for index in 0..running.len() {
+            if let Poll::Ready(output) = std::pin::Pin::new(&mut running[index]).poll(cx) {
+                running.swap_remove(index);
+                return Poll::Ready(Some(output));
+            }
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+/// Entries of `store` outside the reserved namespace
+fn user_entries<S: StorageBackend>(
+    store: &SharedStore<S>,
+) -> Result<Vec<(String, Value)>, FlowError> {
+    Ok(store
+        .iter()
+        .map_err(storage_error)?
+        .filter(|(key, _)| !SystemKeys::is_reserved(key))
+        .collect())
+}
+
+#[async_trait]
+impl<S> NodeBackend<S> for RaceFlowNode<S>
+where
+    S: StorageBackend + Default + Send + Sync + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    type PrepResult = Vec<(String, Value)>;
+    type ExecResult = ();
+    type Error = FlowError;
+
+    async fn prep(
+        &mut self,
+        store: &SharedStore<S>,
+        _context: &ExecutionContext,
+    ) -> Result<Self::PrepResult, Self::Error> {
+        if self.branches.is_empty() {
+            return Err(FlowError::InvalidConfiguration(
+                "Race has no branches".to_string(),
+            ));
+        }
+        for (_, flow) in &self.branches {
+            flow.validate()?;
+        }
+        user_entries(store)
+    }
+
+    async fn exec(
+        &mut self,
+        _prep_result: Self::PrepResult,
+        _context: &ExecutionContext,
+    ) -> Result<Self::ExecResult, Self::Error> {
+        // The branches need the store, so they run in post
+        Ok(())
+    }
+
+    async fn post(
+        &mut self,
+        store: &mut SharedStore<S>,
+        entries: Self::PrepResult,
+        _exec_result: Self::ExecResult,
+        context: &ExecutionContext,
+    ) -> Result<Action, Self::Error> {
+        let mut stores = Vec::with_capacity(self.branches.len());
+        for _ in &self.branches {
+            let mut branch_store = SharedStore::with_storage(S::default());
+            branch_store
+                .set_many(entries.clone())
+                .map_err(storage_error)?;
+            stores.push(branch_store);
+        }
+
+        let names: Vec<String> = self.branches.iter().map(|(name, _)| name.clone()).collect();
+        let success_action = self.success_action.as_str();
+        let mut winner = None;
+        let mut outcomes = Vec::new();
+        // Branches run as part of the caller's request
+        let configured: Vec<Option<String>> = self
+            .branches
+            .iter_mut()
+            .map(|(_, flow)| {
+                let configured = flow.config().correlation_id.clone();
+                if configured.is_none() {
+                    let mut config = flow.config().clone();
+                    config.correlation_id = context.correlation_id.clone();
+                    flow.set_config(config);
+                }
+                configured
+            })
+            .collect();
+        {
+            let mut running: Vec<_> = self
+                .branches
+                .iter_mut()
+                .zip(stores.iter_mut())
+                .enumerate()
+                .map(|(index, ((_, flow), branch_store))| {
+                    Box::pin(async move { (index, flow.execute(branch_store).await) })
+                })
+                .collect();
+            while let Some((index, result)) = next_finished(&mut running).await {
+                match result {
+                    Ok(result) if result.final_action.name() == success_action => {
+                        winner = Some(index);
+                        break;
+                    }
+                    Ok(result) => outcomes.push(format!(
+                        "{} finished with '{}'",
+                        names[index],
+                        result.final_action.name()
+                    )),
+                    Err(error) => outcomes.push(format!("{} failed: {}", names[index], error)),
+                }
+            }
+            // Dropping the remaining branches cancels them
+        }
+        for ((_, flow), correlation_id) in self.branches.iter_mut().zip(configured) {
+            let mut config = flow.config().clone();
+            config.correlation_id = correlation_id;
+            flow.set_config(config);
+        }
+
+        for branch_store in &stores {
+            let usage = branch_store.usage().map_err(storage_error)?;
+            if usage.requests > 0 {
+                store.record_usage(usage).map_err(storage_error)?;
+            }
+        }
+
+        let Some(winner) = winner else {
+            return Err(FlowError::NodeError(format!(
+                "No branch of the race finished with '{}': {}",
+                self.success_action,
+                outcomes.join("; ")
+            )));
+        };
+
+        let changed = user_entries(&stores[winner])?;
+        for (key, _) in &entries {
+            if !changed.iter().any(|(changed_key, _)| changed_key == key) {
+                store.remove(key).map_err(storage_error)?;
+            }
+        }
+        for (key, value) in changed {
+            if !entries.contains(&(key.clone(), value.clone())) {
+                store.set(key, value).map_err(storage_error)?;
+            }
+        }
+        if let Some(key) = &self.winner_key {
+            store
+                .set(key.clone(), Value::from(names[winner].clone()))
+                .map_err(storage_error)?;
+        }
+        Ok(Action::simple(success_action))
+    }
+
+    fn name(&self) -> &str {
+        "RaceFlowNode"
+    }
+
+    fn is_side_effecting(&self) -> bool {
+        self.branches
+            .iter()
+            .any(|(_, flow)| NodeBackend::<S>::is_side_effecting(flow))
+    }
+}
+
+#[cfg(all(test, feature = "storage-memory"))]
+mod tests {
+    use super::*;
+    use crate::flow::FlowBuilder;
+    use crate::node::{Node, NodeError};
+    use crate::shared_store::TokenUsage;
+    use crate::storage::InMemoryStorage;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    /// Waits, then answers with `action` and records one LLM request
+    struct Model {
+        latency: Duration,
+        action: &'static str,
+        finished: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl NodeBackend<InMemoryStorage> for Model {
+        type PrepResult = ();
+        type ExecResult = ();
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            _store: &SharedStore<InMemoryStorage>,
+            _context: &ExecutionContext,
+        ) -> Result<(), NodeError> {
+            Ok(())
+        }
+
+        async fn exec(&mut self, _: (), _context: &ExecutionContext) -> Result<(), NodeError> {
+            tokio::time::sleep(self.latency).await;
+            Ok(())
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<InMemoryStorage>,
+            _: (),
+            _: (),
+            _context: &ExecutionContext,
+        ) -> Result<Action, NodeError> {
+            self.finished.store(true, Ordering::SeqCst);
+            let storage_error = |e: String| NodeError::StorageError(e);
+            store
+                .set("answer".to_string(), json!(self.action))
+                .map_err(|e| storage_error(e.to_string()))?;
+            store
+                .remove("draft")
+                .map_err(|e| storage_error(e.to_string()))?;
+            store
+                .record_usage(TokenUsage {
+                    requests: 1,
+                    input_tokens: 10,
+                    output_tokens: 5,
+                    ..TokenUsage::default()
+                })
+                .map_err(|e| storage_error(e.to_string()))?;
+            Ok(Action::simple(self.action))
+        }
+    }
+
+    fn model(
+        latency_ms: u64,
+        action: &'static str,
+    ) -> (BasicFlow<InMemoryStorage>, Arc<AtomicBool>) {
+        let finished = Arc::new(AtomicBool::new(false));
+        let flow = FlowBuilder::new()
+            .start_node("model")
+            .terminal_action(action)
+            .node(
+                "model",
+                Node::new(Model {
+                    latency: Duration::from_millis(latency_ms),
+                    action,
+                    finished: finished.clone(),
+                }),
+            )
+            .build();
+        (flow, finished)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_race_flow_node() {
+        let (rejected, _) = model(10, "unsure");
+        let (cheap, _) = model(50, "answered");
+        let (expensive, expensive_finished) = model(500, "answered");
+        let mut node = Node::new(
+            RaceFlowNode::new("answered")
+                .with_branch("rejected", rejected)
+                .with_branch("cheap", cheap)
+                .with_branch("expensive", expensive)
+                .with_winner_key("winner"),
+        );
+        let mut store = SharedStore::new();
+        store.set("draft".to_string(), json!("...")).unwrap();
+        store.set("question".to_string(), json!("?")).unwrap();
+
+        assert_eq!(
+            node.run(&mut store).await.unwrap(),
+            Action::simple("answered")
+        );
+        assert_eq!(store.get("winner").unwrap(), Some(json!("cheap")));
+        assert_eq!(store.get("answer").unwrap(), Some(json!("answered")));
+        assert_eq!(store.get("draft").unwrap(), None);
+        assert_eq!(store.get("question").unwrap(), Some(json!("?")));
+        // The losing branch's request is counted too
+        assert_eq!(store.usage().unwrap().requests, 2);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(!expensive_finished.load(Ordering::SeqCst));
+
+        // Without a winner the node fails
+        let (rejected, _) = model(10, "unsure");
+        let mut node = Node::new(RaceFlowNode::new("answered").with_branch("rejected", rejected));
+        let error = node.run(&mut SharedStore::new()).await.unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("rejected finished with 'unsure'")
+        );
+    }
+}
@@ -0,0 +1,581 @@
+//! Ready-made LLM flows
+//!
+//! Each function returns a [`FlowTemplate`] driven by the given
+//! [`LlmProvider`], so the same flows run against OpenAI, Claude, Gemini,
+//! Ollama or an [`LlmRouter`](crate::llm::LlmRouter). All of them read the
+//! text to process from [`TEXT_KEY`] and finish with the `complete` action.
+//!
+//! | Flow | Input | Output | Parameters |
+//! |------|-------|--------|------------|
+//! | [`summarize_text`] | `text` | `summary`: string | `max_words` (default 100) |
+//! | [`translate_text`] | `text` | `translation`: string | `target_language`, `source_language` (optional) |
+//! | [`extract_structured_entities`] | `text` | `entities`: object of entity type to string array | `entity_types` |
+//! | [`classify_intent`] | `text` | `intent`: one of the labels | `labels`, `fallback` (default `"unknown"`) |
+//! | [`judge_output`] | `text` | `verdict`: `{"pass": bool, "reason": string}` | `criteria` |
+//!
+//! Use [`TemplateFlow::bind_input`](super::TemplateFlow::bind_input) and
+//! [`TemplateFlow::bind_output`](super::TemplateFlow::bind_output) to work on
+//! other store keys.
+//!
+//! ```rust,no_run
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::flows::stdlib;
+//! # use pocketflow_rs::llm::OllamaProvider;
+//! # use serde_json::json;
+//! # use std::collections::HashMap;
+//! # use std::sync::Arc;
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let provider: Arc<dyn LlmProvider> = Arc::new(OllamaProvider::new());
+//! let mut flow = stdlib::translate_text::<InMemoryStorage>(provider).instantiate(
+//!     HashMap::from([("target_language".to_string(), json!("French"))]),
+//! )?;
+//!
+//! let mut store = SharedStore::new();
+//! store.set("text".to_string(), json!("Good morning"))?;
+//! flow.execute(&mut store).await?;
+//! println!("{}", store.get("translation")?.unwrap());
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{FlowTemplate, TemplateParams};
+use crate::flow::{FlowBuilder, FlowError};
+use crate::llm::{ChatMessage, ChatRequest, LlmProvider};
+use crate::node::{ExecutionContext, Node, NodeBackend, NodeError};
+use crate::{Action, SharedStore, StorageBackend};
+use async_trait::async_trait;
+use serde_json::{Map, Value, json};
+use std::sync::Arc;
+
+/// Store key all stdlib flows read their input text from
+pub const TEXT_KEY: &str = "text";
+/// Output key of [`summarize_text`]
+pub const SUMMARY_KEY: &str = "summary";
+/// Output key of [`translate_text`]
+pub const TRANSLATION_KEY: &str = "translation";
+/// Output key of [`extract_structured_entities`]
+pub const ENTITIES_KEY: &str = "entities";
+/// Output key of [`classify_intent`]
+pub const INTENT_KEY: &str = "intent";
+/// Output key of [`judge_output`]
+pub const VERDICT_KEY: &str = "verdict";
+
+type ParseFn = dyn Fn(&str) -> Result<Value, NodeError> + Send + Sync;
+
+/// Sends the input text with a fixed system prompt and stores the parsed reply
+struct PromptNode {
+    name: &'static str,
+    provider: Arc<dyn LlmProvider>,
+    system: String,
+    output_key: &'static str,
+    parse: Arc<ParseFn>,
+}
+
+impl PromptNode {
+    fn new(
+        name: &'static str,
+        provider: Arc<dyn LlmProvider>,
+        system: String,
+        output_key: &'static str,
+        parse: impl Fn(&str) -> Result<Value, NodeError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name,
+            provider,
+            system,
+            output_key,
+            parse: Arc::new(parse),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: StorageBackend + Send + Sync> NodeBackend<S> for PromptNode {
+    type PrepResult = String;
+    type ExecResult = Value;
+    type Error = NodeError;
+
+    async fn prep(
+        &mut self,
+        store: &SharedStore<S>,
+        _context: &ExecutionContext,
+    ) -> Result<Self::PrepResult, Self::Error> {
+        match store.get(TEXT_KEY) {
+            Ok(Some(Value::String(text))) => Ok(text),
+            Ok(Some(other)) => Ok(other.to_string()),
+            Ok(None) => Err(NodeError::PrepError(format!(
+                "Input key '{}' not found in store",
+                TEXT_KEY
+            ))),
+            Err(e) => Err(NodeError::StorageError(e.to_string())),
+        }
+    }
+
+    async fn exec(
+        &mut self,
+        prep_result: Self::PrepResult,
+        _context: &ExecutionContext,
+    ) -> Result<Self::ExecResult, Self::Error> {
+        let request = ChatRequest::new(vec![
+            ChatMessage::system(self.system.clone()),
+            ChatMessage::user(prep_result),
+        ]);
+        let response = self.provider.chat(request).await?;
+        (self.parse)(response.content.trim())
+    }
+
+    async fn post(
+        &mut self,
+        store: &mut SharedStore<S>,
+        _prep_result: Self::PrepResult,
+        exec_result: Self::ExecResult,
+        _context: &ExecutionContext,
+    ) -> Result<Action, Self::Error> {
+        store
+            .set(self.output_key.to_string(), exec_result)
+            .map_err(|e| NodeError::StorageError(e.to_string()))?;
+        Ok(Action::simple("complete"))
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn is_side_effecting(&self) -> bool {
+        true
+    }
+
+    fn max_retries(&self) -> usize {
+        2
+    }
+}
+
+/// Build a single-node flow around a [`PromptNode`]
+fn prompt_flow<S>(node: PromptNode) -> crate::flow::BasicFlow<S>
+where
+    S: StorageBackend + Send + Sync + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    let id = node.name;
+    FlowBuilder::new()
+        .start_node(id)
+        .node(id, Node::new(node))
+        .build()
+}
+
+/// Read a non-empty list of strings parameter
+fn string_list(params: &TemplateParams, name: &str) -> Result<Vec<String>, FlowError> {
+    let values = params
+        .get(name)
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect::<Vec<_>>()
+        })
+        .filter(|values| !values.is_empty())
+        .ok_or_else(|| {
+            FlowError::InvalidConfiguration(format!(
+                "Parameter '{}' must be a non-empty array of strings",
+                name
+            ))
+        })?;
+    Ok(values)
+}
+
+/// Take the JSON object out of a reply, ignoring code fences and chatter
+fn parse_json_object(reply: &str) -> Result<Map<String, Value>, NodeError> {
+    let start = reply.find('{');
+    let end = reply.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => reply,
+    };
+    match serde_json::from_str(json) {
+        Ok(Value::Object(object)) => Ok(object),
+        _ => Err(NodeError::ExecutionError(format!(
+            "Expected a JSON object, got: {}",
+            reply
+        ))),
+    }
+}
+
+/// Summarize [`TEXT_KEY`] into [`SUMMARY_KEY`]
+///
+/// Parameters:
+/// - `max_words` (default 100): upper bound on the summary length
+pub fn summarize_text<S>(provider: Arc<dyn LlmProvider>) -> FlowTemplate<S>
+where
+    S: StorageBackend + Send + Sync + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    FlowTemplate::new("summarize_text", move |params| {
+        let max_words = params
+            .get("max_words")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| {
+                FlowError::InvalidConfiguration(
+                    "Parameter 'max_words' must be a positive integer".to_string(),
+                )
+            })?;
+        let system = format!(
+            "Summarize the user's text in at most {} words. \
+             Reply with the summary only.",
+            max_words
+        );
+        Ok(prompt_flow(PromptNode::new(
+            "summarize",
+            provider.clone(),
+            system,
+            SUMMARY_KEY,
+            |reply| Ok(json!(reply)),
+        )))
+    })
+    .description("Summarize a text")
+    .input(TEXT_KEY)
+    .output(SUMMARY_KEY)
+    .optional_param("max_words", json!(100), "Maximum summary length in words")
+}
+
+/// Translate [`TEXT_KEY`] into [`TRANSLATION_KEY`]
+///
+/// Parameters:
+/// - `target_language` (required): language to translate into
+/// - `source_language` (optional): language of the input, detected if omitted
+pub fn translate_text<S>(provider: Arc<dyn LlmProvider>) -> FlowTemplate<S>
+where
+    S: StorageBackend + Send + Sync + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    FlowTemplate::new("translate_text", move |params| {
+        let target = params.get_str("target_language")?;
+        let source = match params.get("source_language") {
+            Some(Value::String(source)) => format!(" from {}", source),
+            _ => String::new(),
+        };
+        let system = format!(
+            "Translate the user's text{} into {}. \
+             Reply with the translation only.",
+            source, target
+        );
+        Ok(prompt_flow(PromptNode::new(
+            "translate",
+            provider.clone(),
+            system,
+            TRANSLATION_KEY,
+            |reply| Ok(json!(reply)),
+        )))
+    })
+    .description("Translate a text into another language")
+    .input(TEXT_KEY)
+    .output(TRANSLATION_KEY)
+    .param("target_language", "Language to translate into")
+    .optional_param(
+        "source_language",
+        Value::Null,
+        "Language of the input text; detected when null",
+    )
+}
+
+/// Extract named entities from [`TEXT_KEY`] into [`ENTITIES_KEY`]
+///
+/// The output is an object with one array of strings per entity type, e.g.
+/// `{"person": ["Ada Lovelace"], "place": []}`.
+///
+/// Parameters:
+/// - `entity_types` (required): array of entity type names
+pub fn extract_structured_entities<S>(provider: Arc<dyn LlmProvider>) -> FlowTemplate<S>
+where
+    S: StorageBackend + Send + Sync + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    FlowTemplate::new("extract_structured_entities", move |params| {
+        let entity_types = string_list(params, "entity_types")?;
+        let system = format!(
+            "Extract entities of these types from the user's text: {}. \
+             Reply with a JSON object only, mapping each type to an array of \
+             the entities found as strings.",
+            entity_types.join(", ")
+        );
+        Ok(prompt_flow(PromptNode::new(
+            "extract_entities",
+            provider.clone(),
+            system,
+            ENTITIES_KEY,
+            move |reply| {
+                let found = parse_json_object(reply)?;
+                // Keep only the requested types, each as an array of strings
+                let entities = entity_types
+                    .iter()
+                    .map(|entity_type| {
+                        let values = match found.get(entity_type) {
+                            Some(Value::Array(values)) => values
+                                .iter()
+                                .map(|v| match v {
+                                    Value::String(s) => json!(s),
+                                    other => json!(other.to_string()),
+                                })
+                                .collect(),
+                            _ => Vec::new(),
+                        };
+                        (entity_type.clone(), Value::Array(values))
+                    })
+                    .collect::<Map<_, _>>();
+                Ok(Value::Object(entities))
+            },
+        )))
+    })
+    .description("Extract named entities as structured JSON")
+    .input(TEXT_KEY)
+    .output(ENTITIES_KEY)
+    .param(
+        "entity_types",
+        "Entity types to extract, e.g. [\"person\", \"place\"]",
+    )
+}
+
+/// Classify the intent of [`TEXT_KEY`] into [`INTENT_KEY`]
+///
+/// Parameters:
+/// - `labels` (required): array of allowed intent labels
+/// - `fallback` (default `"unknown"`): stored when the reply matches no label
+pub fn classify_intent<S>(provider: Arc<dyn LlmProvider>) -> FlowTemplate<S>
+where
+    S: StorageBackend + Send + Sync + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    FlowTemplate::new("classify_intent", move |params| {
+        let labels = string_list(params, "labels")?;
+        let fallback = params.get_str("fallback")?.to_string();
+        let system = format!(
+            "Classify the intent of the user's text as exactly one of: {}. \
+             Reply with the label only.",
+            labels.join(", ")
+        );
+        Ok(prompt_flow(PromptNode::new(
+            "classify_intent",
+            provider.clone(),
+            system,
+            INTENT_KEY,
+            move |reply| {
+                let reply = reply.trim_matches(|c: char| !c.is_alphanumeric());
+                let intent = labels
+                    .iter()
+                    .find(|label| label.eq_ignore_ascii_case(reply))
+                    .unwrap_or(&fallback);
+                Ok(json!(intent))
+            },
+        )))
+    })
+    .description("Classify a text into one of a fixed set of intents")
+    .input(TEXT_KEY)
+    .output(INTENT_KEY)
+    .param("labels", "Allowed intent labels")
+    .optional_param(
+        "fallback",
+        json!("unknown"),
+        "Label used when the reply matches none",
+    )
+}
+
+/// Judge whether [`TEXT_KEY`] meets some criteria, into [`VERDICT_KEY`]
+///
+/// The verdict is `{"pass": bool, "reason": string}`, the shape expected
+/// from the judge of `eval::Evaluator` (feature `eval`).
+///
+/// Parameters:
+/// - `criteria` (required): what a passing text must satisfy
+pub fn judge_output<S>(provider: Arc<dyn LlmProvider>) -> FlowTemplate<S>
+where
+    S: StorageBackend + Send + Sync + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    FlowTemplate::new("judge_output", move |params| {
+        let system = format!(
+            "You are a strict evaluator. Decide whether the user's text meets \
+             these criteria: {}. Reply with a JSON object only, of the form \
+             {{\"pass\": true or false, \"reason\": \"one sentence\"}}.",
+            params.get_str("criteria")?
+        );
+        Ok(prompt_flow(PromptNode::new(
+            "judge_output",
+            provider.clone(),
+            system,
+            VERDICT_KEY,
+            |reply| {
+                let verdict = parse_json_object(reply)?;
+                let pass = verdict
+                    .get("pass")
+                    .and_then(Value::as_bool)
+                    .ok_or_else(|| {
+                        NodeError::ExecutionError(format!(
+                            "Verdict has no boolean 'pass': {}",
+                            reply
+                        ))
+                    })?;
+                let reason = verdict.get("reason").and_then(Value::as_str).unwrap_or("");
+                Ok(json!({"pass": pass, "reason": reason}))
+            },
+        )))
+    })
+    .description("Judge whether a text meets the given criteria")
+    .input(TEXT_KEY)
+    .output(VERDICT_KEY)
+    .param("criteria", "What a passing text must satisfy")
+}
+
+#[cfg(all(test, feature = "storage-memory"))]
+mod tests {
+    use super::*;
+    use crate::flow::Flow;
+    use crate::llm::{ChatResponse, LlmError};
+    use crate::storage::InMemoryStorage;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Replies with canned answers and records the system prompts it saw
+    struct ScriptedProvider {
+        replies: Mutex<Vec<&'static str>>,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(replies: Vec<&'static str>) -> Arc<Self> {
+            Arc::new(Self {
+                replies: Mutex::new(replies),
+                prompts: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+            self.prompts
+                .lock()
+                .unwrap()
+                .push(request.messages[0].content.clone());
+            Ok(ChatResponse::new(
+                self.replies.lock().unwrap().remove(0).to_string(),
+                "scripted",
+            ))
+        }
+    }
+
+    async fn run(
+        template: FlowTemplate<InMemoryStorage>,
+        params: Value,
+        text: &str,
+    ) -> Result<SharedStore<InMemoryStorage>, FlowError> {
+        let params: HashMap<String, Value> = serde_json::from_value(params).unwrap();
+        let mut flow = template.instantiate(params)?;
+        let mut store = SharedStore::new();
+        store.set(TEXT_KEY.to_string(), json!(text)).unwrap();
+        flow.execute(&mut store).await?;
+        Ok(store)
+    }
+
+    #[tokio::test]
+    async fn test_text_flows() {
+        let provider = ScriptedProvider::new(vec![" A short summary. ", "Bonjour"]);
+
+        let store = run(summarize_text(provider.clone()), json!({}), "A long text")
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get(SUMMARY_KEY).unwrap(),
+            Some(json!("A short summary."))
+        );
+
+        let store = run(
+            translate_text(provider.clone()),
+            json!({"target_language": "French"}),
+            "Hello",
+        )
+        .await
+        .unwrap();
+        assert_eq!(store.get(TRANSLATION_KEY).unwrap(), Some(json!("Bonjour")));
+
+        {
+            let prompts = provider.prompts.lock().unwrap();
+            assert!(prompts[0].contains("at most 100 words"));
+            assert!(prompts[1].contains("into French"));
+        }
+
+        // A required parameter is missing
+        assert!(
+            run(translate_text(provider.clone()), json!({}), "Hello")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_structured_flows() {
+        let provider = ScriptedProvider::new(vec![
+            "```json\n{\"person\": [\"Ada Lovelace\"], \"year\": [1843]}\n```",
+            "Refund.",
+            "weather",
+        ]);
+
+        let store = run(
+            extract_structured_entities(provider.clone()),
+            json!({"entity_types": ["person", "place"]}),
+            "Ada Lovelace published her notes in 1843.",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            store.get(ENTITIES_KEY).unwrap(),
+            Some(json!({"person": ["Ada Lovelace"], "place": []}))
+        );
+
+        let labels = json!({"labels": ["refund", "support"]});
+        let store = run(
+            classify_intent(provider.clone()),
+            labels.clone(),
+            "Money back",
+        )
+        .await
+        .unwrap();
+        assert_eq!(store.get(INTENT_KEY).unwrap(), Some(json!("refund")));
+        let store = run(classify_intent(provider.clone()), labels, "Rain?")
+            .await
+            .unwrap();
+        assert_eq!(store.get(INTENT_KEY).unwrap(), Some(json!("unknown")));
+
+        assert!(
+            run(classify_intent(provider), json!({"labels": []}), "Anything")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_judge_output() {
+        let provider = ScriptedProvider::new(vec![
+            "{\"pass\": false, \"reason\": \"Not polite.\"}",
+            "maybe",
+            "maybe",
+            "maybe",
+        ]);
+        let criteria = json!({"criteria": "is polite"});
+
+        let store = run(judge_output(provider.clone()), criteria.clone(), "Go away")
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get(VERDICT_KEY).unwrap(),
+            Some(json!({"pass": false, "reason": "Not polite."}))
+        );
+        assert!(provider.prompts.lock().unwrap()[0].contains("is polite"));
+        // A reply without a verdict fails once the retries are used up
+        assert!(run(judge_output(provider), criteria, "Hi").await.is_err());
+    }
+}
@@ -0,0 +1,479 @@
+//! Parameterized, reusable sub-flows
+//!
+//! A [`FlowTemplate`] pairs a flow factory with a declared interface: the store
+//! keys it reads, the keys it writes and the parameters it needs. Each call to
+//! [`FlowTemplate::instantiate`] checks the parameters and builds a fresh
+//! [`TemplateFlow`], which verifies its inputs before running and its outputs
+//! afterwards. Bindings let an instance read and write store keys other than
+//! the template's own, so the same fragment can be reused on different data.
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use serde_json::json;
+//! # use std::collections::HashMap;
+//! let shout: FlowTemplate<InMemoryStorage> = FlowTemplate::new("shout", |params| {
+//!     let suffix = params.get_str("suffix")?.to_string();
+//!     Ok(FlowBuilder::new()
+//!         .start_node("shout")
+//!         .node("shout", Node::new(FunctionNode::new(
+//!             "shout".to_string(),
+//!             |store: &SharedStore<InMemoryStorage>, _ctx| store.get("text").ok().flatten(),
+//!             |text, _ctx| Ok(text),
+//!             move |store, _prep, text, _ctx| {
+//!                 let text = text.and_then(|t| t.as_str().map(str::to_uppercase)).unwrap_or_default();
+//!                 store.set("shouted".to_string(), json!(text + &suffix))?;
+//!                 Ok(Action::simple("complete"))
+//!             },
+//!         )))
+//!         .build())
+//! })
+//! .input("text")
+//! .output("shouted")
+//! .optional_param("suffix", json!("!"), "Appended to the result");
+//!
+//! let greeting = shout
+//!     .instantiate(HashMap::new())
+//!     .unwrap()
+//!     .bind_input("text", "greeting")
+//!     .bind_output("shouted", "loud_greeting");
+//! let node = Node::new(pocketflow_rs::flow::FlowNode::new(greeting));
+//! ```
+
+use crate::flow::{BasicFlow, Flow, FlowConfig, FlowError, FlowExecutionResult, NodeRunner, Route};
+use crate::{SharedStore, StorageBackend};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+type TemplateFactory<S> = dyn Fn(&TemplateParams) -> Result<BasicFlow<S>, FlowError> + Send + Sync;
+
+/// A parameter declared by a [`FlowTemplate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateParam {
+    pub name: String,
+    pub description: Option<String>,
+    /// Value used when the parameter isn't given; `None` makes it required
+    pub default: Option<Value>,
+}
+
+/// Parameter values passed to a template's factory
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateParams {
+    values: HashMap<String, Value>,
+}
+
+impl TemplateParams {
+    /// Get a parameter value
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    /// Get a string parameter, failing if it is missing or not a string
+    pub fn get_str(&self, name: &str) -> Result<&str, FlowError> {
+        self.get(name).and_then(Value::as_str).ok_or_else(|| {
+            FlowError::InvalidConfiguration(format!("Parameter '{}' must be a string", name))
+        })
+    }
+
+    /// Get all parameter values
+    pub fn values(&self) -> &HashMap<String, Value> {
+        &self.values
+    }
+}
+
+/// A reusable flow definition with declared inputs, outputs and parameters
+pub struct FlowTemplate<S: StorageBackend> {
+    name: String,
+    description: Option<String>,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    params: Vec<TemplateParam>,
+    factory: Arc<TemplateFactory<S>>,
+}
+
+impl<S: StorageBackend> Clone for FlowTemplate<S> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            params: self.params.clone(),
+            factory: self.factory.clone(),
+        }
+    }
+}
+
+impl<S: StorageBackend> std::fmt::Debug for FlowTemplate<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlowTemplate")
+            .field("name", &self.name)
+            .field("inputs", &self.inputs)
+            .field("outputs", &self.outputs)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+impl<S: StorageBackend> FlowTemplate<S> {
+    /// Create a template from a factory building the flow for given parameters
+    pub fn new<F>(name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&TemplateParams) -> Result<BasicFlow<S>, FlowError> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            description: None,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            params: Vec::new(),
+            factory: Arc::new(factory),
+        }
+    }
+
+    /// Set a human-readable description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Declare a store key the flow reads; it must be present before running
+    pub fn input(mut self, key: impl Into<String>) -> Self {
+        self.inputs.push(key.into());
+        self
+    }
+
+    /// Declare a store key the flow writes; it must be present after running
+    pub fn output(mut self, key: impl Into<String>) -> Self {
+        self.outputs.push(key.into());
+        self
+    }
+
+    /// Declare a required parameter
+    pub fn param(mut self, name: impl Into<String>, description: impl Into<String>) -> Self {
+        self.params.push(TemplateParam {
+            name: name.into(),
+            description: Some(description.into()),
+            default: None,
+        });
+        self
+    }
+
+    /// Declare an optional parameter with a default value
+    pub fn optional_param(
+        mut self,
+        name: impl Into<String>,
+        default: Value,
+        description: impl Into<String>,
+    ) -> Self {
+        self.params.push(TemplateParam {
+            name: name.into(),
+            description: Some(description.into()),
+            default: Some(default),
+        });
+        self
+    }
+
+    /// Get the template name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the description
+    pub fn get_description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Get the declared input keys
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    /// Get the declared output keys
+    pub fn outputs(&self) -> &[String] {
+        &self.outputs
+    }
+
+    /// Get the declared parameters
+    pub fn params(&self) -> &[TemplateParam] {
+        &self.params
+    }
+
+    /// Build a new flow instance
+    ///
+    /// Fails if a required parameter is missing or an undeclared one is given.
+    pub fn instantiate(
+        &self,
+        params: HashMap<String, Value>,
+    ) -> Result<TemplateFlow<S>, FlowError> {
+        if let Some(unknown) = params
+            .keys()
+            .find(|name| !self.params.iter().any(|p| &p.name == *name))
+        {
+            return Err(FlowError::InvalidConfiguration(format!(
+                "Template '{}' has no parameter '{}'",
+                self.name, unknown
+            )));
+        }
+
+        let mut values = params;
+        for param in &self.params {
+            if values.contains_key(&param.name) {
+                continue;
+            }
+            match &param.default {
+                Some(default) => {
+                    values.insert(param.name.clone(), default.clone());
+                }
+                None => {
+                    return Err(FlowError::InvalidConfiguration(format!(
+                        "Template '{}' requires parameter '{}'",
+                        self.name, param.name
+                    )));
+                }
+            }
+        }
+
+        let params = TemplateParams { values };
+        let flow = (self.factory)(&params)?;
+        Ok(TemplateFlow {
+            template: self.name.clone(),
+            flow,
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            input_bindings: HashMap::new(),
+            output_bindings: HashMap::new(),
+            params,
+        })
+    }
+}
+
+/// A flow instantiated from a [`FlowTemplate`]
+pub struct TemplateFlow<S: StorageBackend> {
+    template: String,
+    flow: BasicFlow<S>,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    input_bindings: HashMap<String, String>,
+    output_bindings: HashMap<String, String>,
+    params: TemplateParams,
+}
+
+impl<S: StorageBackend> TemplateFlow<S> {
+    /// Read the template input `key` from `store_key` instead
+    ///
+    /// The value is copied to `key` before the flow runs.
+    pub fn bind_input(mut self, key: impl Into<String>, store_key: impl Into<String>) -> Self {
+        self.input_bindings.insert(key.into(), store_key.into());
+        self
+    }
+
+    /// Also write the template output `key` to `store_key`
+    ///
+    /// The value is copied from `key` after the flow runs.
+    pub fn bind_output(mut self, key: impl Into<String>, store_key: impl Into<String>) -> Self {
+        self.output_bindings.insert(key.into(), store_key.into());
+        self
+    }
+
+    /// Get the name of the template this flow was built from
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Get the parameters this instance was built with
+    pub fn params(&self) -> &TemplateParams {
+        &self.params
+    }
+
+    /// Get the underlying flow
+    pub fn flow(&self) -> &BasicFlow<S> {
+        &self.flow
+    }
+
+    /// Get the underlying flow mutably
+    pub fn flow_mut(&mut self) -> &mut BasicFlow<S> {
+        &mut self.flow
+    }
+
+    fn copy(store: &mut SharedStore<S>, from: &str, to: &str) -> Result<bool, FlowError> {
+        let value = store
+            .get(from)
+            .map_err(|e| FlowError::NodeError(e.to_string()))?;
+        match value {
+            Some(value) => {
+                if from != to {
+                    store
+                        .set(to.to_string(), value)
+                        .map_err(|e| FlowError::NodeError(e.to_string()))?;
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn load_inputs(&self, store: &mut SharedStore<S>) -> Result<(), FlowError> {
+        for key in &self.inputs {
+            let source = self.input_bindings.get(key).unwrap_or(key);
+            if !Self::copy(store, source, key)? {
+                return Err(FlowError::InvalidConfiguration(format!(
+                    "Template '{}' input '{}' not found in store",
+                    self.template, source
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn store_outputs(&self, store: &mut SharedStore<S>) -> Result<(), FlowError> {
+        for key in &self.outputs {
+            let target = self.output_bindings.get(key).unwrap_or(key);
+            if !Self::copy(store, key, target)? {
+                return Err(FlowError::NodeError(format!(
+                    "Template '{}' did not produce output '{}'",
+                    self.template, key
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S: StorageBackend + Send + Sync> Flow<S> for TemplateFlow<S>
+where
+    S::Error: Send + Sync + 'static,
+{
+    fn add_node(&mut self, id: String, node: Box<dyn NodeRunner<S>>) -> Result<(), FlowError> {
+        self.flow.add_node(id, node)
+    }
+
+    fn add_route(&mut self, from_node_id: String, route: Route) -> Result<(), FlowError> {
+        self.flow.add_route(from_node_id, route)
+    }
+
+    async fn execute(
+        &mut self,
+        store: &mut SharedStore<S>,
+    ) -> Result<FlowExecutionResult, FlowError> {
+        self.load_inputs(store)?;
+        let result = self.flow.execute(store).await?;
+        self.store_outputs(store)?;
+        Ok(result)
+    }
+
+    async fn execute_from(
+        &mut self,
+        store: &mut SharedStore<S>,
+        start_node_id: String,
+    ) -> Result<FlowExecutionResult, FlowError> {
+        self.load_inputs(store)?;
+        let result = self.flow.execute_from(store, start_node_id).await?;
+        self.store_outputs(store)?;
+        Ok(result)
+    }
+
+    fn config(&self) -> &FlowConfig {
+        self.flow.config()
+    }
+
+    fn set_config(&mut self, config: FlowConfig) {
+        self.flow.set_config(config);
+    }
+
+    fn validate(&self) -> Result<(), FlowError> {
+        self.flow.validate()
+    }
+}
+
+#[cfg(all(test, feature = "storage-memory"))]
+mod tests {
+    use super::*;
+    use crate::Action;
+    use crate::flow::{FlowBuilder, FlowNode};
+    use crate::node::{FunctionNode, Node};
+    use crate::storage::InMemoryStorage;
+    use serde_json::json;
+
+    fn translate() -> FlowTemplate<InMemoryStorage> {
+        FlowTemplate::new("translate", |params| {
+            let language = params.get_str("language")?.to_string();
+            Ok(FlowBuilder::new()
+                .start_node("translate")
+                .terminal_action("translated")
+                .node(
+                    "translate",
+                    Node::new(FunctionNode::new(
+                        "translate".to_string(),
+                        |store: &SharedStore<InMemoryStorage>, _ctx| {
+                            store.get("text").ok().flatten().unwrap_or_default()
+                        },
+                        |text, _ctx| Ok(text),
+                        move |store, _prep, text, _ctx| {
+                            let text = format!("[{}] {}", language, text.as_str().unwrap_or(""));
+                            store.set("translation".to_string(), json!(text))?;
+                            Ok(Action::simple("translated"))
+                        },
+                    )),
+                )
+                .build())
+        })
+        .description("Translate text")
+        .input("text")
+        .output("translation")
+        .param("language", "Target language")
+    }
+
+    #[tokio::test]
+    async fn test_template_instances_in_parent_flow() {
+        let template = translate();
+        let params = |language: &str| HashMap::from([("language".to_string(), json!(language))]);
+        let french = template
+            .instantiate(params("fr"))
+            .unwrap()
+            .bind_input("text", "title")
+            .bind_output("translation", "title_fr");
+        let mut german = template
+            .instantiate(params("de"))
+            .unwrap()
+            .bind_input("text", "body")
+            .bind_output("translation", "body_de");
+
+        let mut flow = FlowBuilder::new()
+            .start_node("title")
+            .terminal_action("translated")
+            .node("title", Node::new(FlowNode::new(french)))
+            .build();
+        let mut store = SharedStore::new();
+        store.set("title".to_string(), json!("Hello")).unwrap();
+        store.set("body".to_string(), json!("Goodbye")).unwrap();
+
+        flow.execute(&mut store).await.unwrap();
+        german.execute(&mut store).await.unwrap();
+        assert_eq!(store.get("title_fr").unwrap(), Some(json!("[fr] Hello")));
+        assert_eq!(store.get("body_de").unwrap(), Some(json!("[de] Goodbye")));
+    }
+
+    #[tokio::test]
+    async fn test_template_validation() {
+        let template = translate();
+        assert!(matches!(
+            template.instantiate(HashMap::new()),
+            Err(FlowError::InvalidConfiguration(_))
+        ));
+        let unknown = HashMap::from([
+            ("language".to_string(), json!("fr")),
+            ("tone".to_string(), json!("formal")),
+        ]);
+        assert!(template.instantiate(unknown).is_err());
+
+        let mut instance = template
+            .instantiate(HashMap::from([("language".to_string(), json!("fr"))]))
+            .unwrap();
+        let mut store = SharedStore::new();
+        let error = instance.execute(&mut store).await.unwrap_err();
+        assert!(error.to_string().contains("input 'text' not found"));
+    }
+}
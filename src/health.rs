@@ -0,0 +1,265 @@
+//! Health checks for readiness probes
+//!
+//! A [`RuntimeHealth`] runs named checks concurrently, each bounded by a
+//! timeout, and reports whether the process is ready to take new flows.
+//! Checks can be storage backends ([`StorageBackend::health_check`]), LLM
+//! providers (`LlmProvider::ping`, feature `builtin-llm`), a runtime's
+//! shutdown signal or any async closure. With the `server` feature,
+//! `server::serve_health` exposes the report as `/livez` and `/readyz`
+//! endpoints for Kubernetes probes.
+//!
+//! ```rust
+//! # use pocketflow_rs::health::RuntimeHealth;
+//! # use pocketflow_rs::runtime::PocketFlowRuntime;
+//! # use pocketflow_rs::storage::InMemoryStorage;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let runtime = PocketFlowRuntime::new();
+//! let health = RuntimeHealth::new()
+//!     .with_storage("store", InMemoryStorage::new())
+//!     .with_shutdown_signal(runtime.shutdown_signal())
+//!     .with_check("queue", || async { Err::<(), _>("no broker connection") });
+//!
+//! let report = health.check().await;
+//! assert!(!report.ready);
+//! assert_eq!(report.failing().next().unwrap().name, "queue");
+//! # }
+//! ```
+
+use crate::runtime::ShutdownSignal;
+use crate::storage::{AsyncStorageBackend, StorageBackend};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+/// Time a check may take before it counts as failed, unless overridden
+pub const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+type CheckFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+type Check = Arc<dyn Fn() -> CheckFuture + Send + Sync>;
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub healthy: bool,
+    /// Why the check failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub latency_ms: u64,
+}
+
+/// Outcome of every check, in the order they were added
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// Whether every check passed
+    pub ready: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+impl HealthReport {
+    /// Checks that failed
+    pub fn failing(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|check| !check.healthy)
+    }
+}
+
+/// Named checks of the backends a process depends on
+#[derive(Clone)]
+pub struct RuntimeHealth {
+    checks: Vec<(String, Check)>,
+    timeout: Duration,
+}
+
+impl std::fmt::Debug for RuntimeHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RuntimeHealth")
+            .field("checks", &self.names())
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl Default for RuntimeHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuntimeHealth {
+    /// Create an aggregator without checks, which is always ready
+    pub fn new() -> Self {
+        Self {
+            checks: Vec::new(),
+            timeout: DEFAULT_CHECK_TIMEOUT,
+        }
+    }
+
+    /// Fail checks that take longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Add a check run by an async closure
+    pub fn with_check<F, Fut, E>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Display,
+    {
+        let check: Check = Arc::new(move || {
+            let check = check();
+            Box::pin(async move { check.await.map_err(|e| e.to_string()) })
+        });
+        self.checks.push((name.into(), check));
+        self
+    }
+
+    /// Add the [`StorageBackend::health_check`] of `storage`, run on a
+    /// blocking thread
+    pub fn with_storage<S>(self, name: impl Into<String>, storage: S) -> Self
+    where
+        S: StorageBackend + 'static,
+    {
+        let storage = Arc::new(storage);
+        self.with_check(name, move || {
+            let storage = storage.clone();
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    storage.health_check().map_err(|e| e.to_string())
+                })
+                .await
+                .map_err(|e| e.to_string())?
+            }
+        })
+    }
+
+    /// Add the [`AsyncStorageBackend::health_check`] of `storage`
+    pub fn with_async_storage<S>(self, name: impl Into<String>, storage: S) -> Self
+    where
+        S: AsyncStorageBackend + 'static,
+    {
+        let storage = Arc::new(storage);
+        self.with_check(name, move || {
+            let storage = storage.clone();
+            async move { storage.health_check().await }
+        })
+    }
+
+    /// Add the `ping` of an LLM provider, named after it
+    #[cfg(feature = "builtin-llm")]
+    pub fn with_provider(self, provider: Arc<dyn crate::llm::LlmProvider>) -> Self {
+        let name = provider.name().to_string();
+        self.with_check(name, move || {
+            let provider = provider.clone();
+            async move { provider.ping().await }
+        })
+    }
+
+    /// Fail once the runtime starts shutting down, so no new flows are
+    /// routed to it
+    pub fn with_shutdown_signal(self, signal: ShutdownSignal) -> Self {
+        self.with_check("runtime", move || {
+            let shutting_down = signal.is_triggered();
+            async move {
+                if shutting_down {
+                    Err("shutting down")
+                } else {
+                    Ok(())
+                }
+            }
+        })
+    }
+
+    /// Names of the checks, in the order they were added
+    pub fn names(&self) -> Vec<&str> {
+        self.checks.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Run every check concurrently
+    pub async fn check(&self) -> HealthReport {
+        let mut tasks = JoinSet::new();
+        for (index, (_, check)) in self.checks.iter().enumerate() {
+            let (check, timeout) = (check.clone(), self.timeout);
+            tasks.spawn(async move {
+                let started = Instant::now();
+                let outcome = tokio::time::timeout(timeout, check())
+                    .await
+                    .unwrap_or_else(|_| Err(format!("timed out after {:?}", timeout)));
+                (index, outcome, started.elapsed())
+            });
+        }
+
+        let mut outcomes = vec![None; self.checks.len()];
+        while let Some(joined) = tasks.join_next().await {
+            // A panicking check stays `None` and is reported below
+            if let Ok((index, outcome, elapsed)) = joined {
+                outcomes[index] = Some((outcome, elapsed));
+            }
+        }
+        let checks: Vec<CheckResult> = self
+            .checks
+            .iter()
+            .zip(outcomes)
+            .map(|((name, _), outcome)| {
+                let (outcome, elapsed) =
+                    outcome.unwrap_or((Err("check panicked".to_string()), Duration::ZERO));
+                CheckResult {
+                    name: name.clone(),
+                    healthy: outcome.is_ok(),
+                    error: outcome.err(),
+                    latency_ms: elapsed.as_millis() as u64,
+                }
+            })
+            .collect();
+        HealthReport {
+            ready: checks.iter().all(|check| check.healthy),
+            checks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::PocketFlowRuntime;
+    use crate::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn test_runtime_health_reports_each_check() {
+        let runtime = PocketFlowRuntime::new();
+        let health = RuntimeHealth::new()
+            .with_timeout(Duration::from_millis(20))
+            .with_storage("store", InMemoryStorage::new())
+            .with_shutdown_signal(runtime.shutdown_signal())
+            .with_check("slow", || async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok::<(), String>(())
+            });
+
+        let report = health.check().await;
+        assert_eq!(health.names(), vec!["store", "runtime", "slow"]);
+        assert!(!report.ready);
+        assert!(report.checks[0].healthy && report.checks[1].healthy);
+        assert_eq!(
+            report.checks[2].error.as_deref(),
+            Some("timed out after 20ms")
+        );
+
+        runtime.shutdown(Duration::ZERO).await;
+        let report = RuntimeHealth::new()
+            .with_shutdown_signal(runtime.shutdown_signal())
+            .check()
+            .await;
+        assert_eq!(
+            report.failing().next().unwrap().error.as_deref(),
+            Some("shutting down")
+        );
+        assert!(RuntimeHealth::new().check().await.ready);
+    }
+}
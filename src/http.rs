@@ -0,0 +1,544 @@
+//! Shared outbound HTTP client
+//!
+//! Builtin integrations calling HTTP APIs (the Claude, Gemini and Ollama
+//! providers and the webhook notification nodes) send their requests through
+//! an [`HttpClient`] rather than configuring reqwest themselves. The client
+//! pools connections, identifies itself with a user agent, goes through a
+//! proxy if configured (or the `HTTPS_PROXY`/`HTTP_PROXY` environment
+//! variables otherwise), can trust extra CA certificates such as a corporate
+//! root, and retries rate-limited (429) and server-error (5xx) responses,
+//! waiting as long as their `Retry-After` header asks.
+//!
+//! The client also reads the rate-limit headers OpenAI-style and Anthropic
+//! APIs send with every response (see [`RateLimitStatus`]). Once a host's
+//! remaining requests or tokens drop below the configured headroom, further
+//! requests to it are spaced out over the rest of the window, and held until
+//! the reset once nothing is left, instead of running into 429s.
+//!
+//! ```rust
+//! # use pocketflow_rs::http::{HttpClient, HttpConfig};
+//! # use std::time::Duration;
+//! let client = HttpClient::new(
+//!     HttpConfig::default()
+//!         .with_proxy("http://proxy.internal:3128")
+//!         .with_max_retries(5)
+//!         .with_max_backoff(Duration::from_secs(60)),
+//! )
+//! .unwrap();
+//! # let _ = client;
+//! ```
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Certificate, Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// User agent sent by default
+pub const DEFAULT_USER_AGENT: &str = concat!("pocketflow-rs/", env!("CARGO_PKG_VERSION"));
+
+/// Error building an [`HttpClient`]
+#[derive(Debug, thiserror::Error)]
+pub enum HttpError {
+    #[error("Cannot load CA certificate {}: {message}", .path.display())]
+    Certificate { path: PathBuf, message: String },
+    #[error("Invalid HTTP client settings: {0}")]
+    Client(#[from] reqwest::Error),
+}
+
+/// Settings of an [`HttpClient`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpConfig {
+    pub user_agent: String,
+    /// Proxy for all requests; the environment's proxy is used when unset
+    pub proxy: Option<String>,
+    /// PEM files of CA certificates trusted in addition to the system roots
+    pub ca_certificates: Vec<PathBuf>,
+    /// Skip TLS certificate verification; only for testing against servers
+    /// with self-signed certificates
+    pub accept_invalid_certs: bool,
+    /// Default timeout of a request, which providers may override per request
+    pub timeout: Option<Duration>,
+    /// Retries of 429 and 5xx responses and connection failures
+    pub max_retries: usize,
+    /// Wait before the first retry, doubled for each further one
+    pub backoff: Duration,
+    /// Longest wait between attempts; a `Retry-After` asking for more is
+    /// not waited for and the response is returned as is
+    pub max_backoff: Duration,
+    /// Fraction of a host's rate limit below which requests are spaced out
+    /// over the rest of its window; 0 only holds requests once nothing is
+    /// left
+    pub rate_limit_headroom: f64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: None,
+            ca_certificates: Vec::new(),
+            accept_invalid_certs: false,
+            timeout: None,
+            max_retries: 2,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            rate_limit_headroom: 0.1,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Set the user agent
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Send every request through a proxy, e.g. `http://proxy:3128`
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Trust the CA certificates in a PEM file, e.g. a corporate root
+    pub fn with_ca_certificate(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_certificates.push(path.into());
+        self
+    }
+
+    /// Turn TLS certificate verification off or back on
+    pub fn with_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Set the default request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the number of retries, 0 to disable them
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the wait before the first retry
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set the longest wait between attempts
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the fraction of a rate limit below which requests are paced
+    pub fn with_rate_limit_headroom(mut self, headroom: f64) -> Self {
+        self.rate_limit_headroom = headroom;
+        self
+    }
+}
+
+/// One limit a provider reports, such as requests or tokens per minute
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// Time until the limit resets
+    pub reset_after: Option<Duration>,
+}
+
+impl RateLimit {
+    /// Wait before the next request to stay within the limit, if it is
+    /// running low
+    ///
+    /// Below `headroom` of the limit, the remaining requests are spread over
+    /// the time until the reset; with nothing left, the wait is until the
+    /// reset.
+    pub fn delay(&self, headroom: f64) -> Option<Duration> {
+        let (remaining, reset_after) = (self.remaining?, self.reset_after?);
+        let low = match self.limit {
+            Some(limit) => remaining as f64 <= limit as f64 * headroom,
+            None => remaining == 0,
+        };
+        low.then(|| reset_after / (remaining.min(u64::from(u32::MAX)) as u32 + 1))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.limit.is_none() && self.remaining.is_none() && self.reset_after.is_none()
+    }
+
+    /// The limit `elapsed` after it was reported
+    fn aged(mut self, elapsed: Duration) -> Self {
+        self.reset_after = self.reset_after.map(|reset| reset.saturating_sub(elapsed));
+        self
+    }
+}
+
+/// Rate limits a provider reported with its latest response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitStatus {
+    pub requests: RateLimit,
+    pub tokens: RateLimit,
+}
+
+impl RateLimitStatus {
+    /// Read the `x-ratelimit-*` (OpenAI, Groq, ...) or
+    /// `anthropic-ratelimit-*` headers, `None` if there are none
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: String| headers.get(name)?.to_str().ok().map(str::trim);
+        let limit = |resource: &str| {
+            let openai = |field: &str| header(format!("x-ratelimit-{}-{}", field, resource));
+            let anthropic =
+                |field: &str| header(format!("anthropic-ratelimit-{}-{}", resource, field));
+            let number = |field: &str| {
+                openai(field)
+                    .or_else(|| anthropic(field))
+                    .and_then(|value| value.parse().ok())
+            };
+            RateLimit {
+                limit: number("limit"),
+                remaining: number("remaining"),
+                reset_after: openai("reset")
+                    .and_then(parse_reset_duration)
+                    .or_else(|| anthropic("reset").and_then(until_date)),
+            }
+        };
+        let status = Self {
+            requests: limit("requests"),
+            tokens: limit("tokens"),
+        };
+        (!status.requests.is_empty() || !status.tokens.is_empty()).then_some(status)
+    }
+
+    /// Wait before the next request, if either limit is running low
+    pub fn delay(&self, headroom: f64) -> Option<Duration> {
+        self.requests
+            .delay(headroom)
+            .max(self.tokens.delay(headroom))
+    }
+
+    fn aged(self, elapsed: Duration) -> Self {
+        Self {
+            requests: self.requests.aged(elapsed),
+            tokens: self.tokens.aged(elapsed),
+        }
+    }
+}
+
+/// A reset given as a duration, such as `20ms`, `1s` or `6m0s`
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(seconds).ok();
+    }
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let unit_end = rest[number_end..]
+            .find(|c: char| c.is_ascii_digit())
+            .map_or(rest.len(), |end| number_end + end);
+        let number: f64 = rest[..number_end].parse().ok()?;
+        total += number
+            * match &rest[number_end..unit_end] {
+                "h" => 3600.0,
+                "m" => 60.0,
+                "s" => 1.0,
+                "ms" => 0.001,
+                _ => return None,
+            };
+        rest = &rest[unit_end..];
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+/// Time until an RFC 3339 date, zero if it has passed
+fn until_date(value: &str) -> Option<Duration> {
+    let date = chrono::DateTime::parse_from_rfc3339(value).ok()?;
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// A pooled HTTP client retrying 429 and 5xx responses
+///
+/// Clones share connections and rate-limit state.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    config: HttpConfig,
+    /// Latest rate limits per host, with when they were received
+    rate_limits: Arc<Mutex<HashMap<String, (Instant, RateLimitStatus)>>>,
+}
+
+impl HttpClient {
+    /// Create a client with its own connection pool
+    pub fn new(config: HttpConfig) -> Result<Self, HttpError> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(&config.user_agent)
+            .danger_accept_invalid_certs(config.accept_invalid_certs);
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        for path in &config.ca_certificates {
+            let invalid = |message: String| HttpError::Certificate {
+                path: path.clone(),
+                message,
+            };
+            let pem = std::fs::read(path).map_err(|e| invalid(e.to_string()))?;
+            for certificate in
+                Certificate::from_pem_bundle(&pem).map_err(|e| invalid(e.to_string()))?
+            {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if let Some(timeout) = config.timeout {
+            builder = builder.timeout(timeout);
+        }
+        Ok(Self {
+            client: builder.build()?,
+            config,
+            rate_limits: Arc::default(),
+        })
+    }
+
+    /// The client with default settings, shared by every integration that
+    /// wasn't given one
+    pub fn shared() -> Self {
+        static SHARED: OnceLock<HttpClient> = OnceLock::new();
+        SHARED
+            .get_or_init(|| {
+                HttpClient::new(HttpConfig::default()).expect("Default HTTP client builds")
+            })
+            .clone()
+    }
+
+    /// Get the settings
+    pub fn config(&self) -> &HttpConfig {
+        &self.config
+    }
+
+    /// The underlying reqwest client, for libraries that take one
+    #[cfg(feature = "builtin-llm")]
+    pub(crate) fn reqwest_client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Rate limits a host (such as `api.openai.com`) reported most recently,
+    /// with resets counted down since
+    pub fn rate_limit(&self, host: &str) -> Option<RateLimitStatus> {
+        let rate_limits = self.rate_limits.lock().unwrap_or_else(|e| e.into_inner());
+        let (received, status) = rate_limits.get(host)?;
+        Some(status.aged(received.elapsed()))
+    }
+
+    /// Start a request
+    pub fn request(&self, method: Method, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// Start a POST request
+    pub fn post(&self, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    /// Send a request, retrying 429 and 5xx responses and connection failures
+    ///
+    /// The last response is returned whatever its status, for the caller to
+    /// classify. Requests with a streaming body can't be repeated and are sent
+    /// once. Each attempt first waits as long as the host's rate limits ask,
+    /// up to `max_backoff`.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        let request = request.build()?;
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        let mut attempt = 0;
+        loop {
+            if let Some(wait) = self
+                .rate_limit(&host)
+                .and_then(|status| status.delay(self.config.rate_limit_headroom))
+            {
+                tokio::time::sleep(wait.min(self.config.max_backoff)).await;
+            }
+            let Some(retry) = request
+                .try_clone()
+                .filter(|_| attempt < self.config.max_retries)
+            else {
+                return self.observe(&host, self.client.execute(request).await);
+            };
+            let wait = match self.observe(&host, self.client.execute(retry).await) {
+                Ok(response) if !is_retryable(response.status()) => return Ok(response),
+                Ok(response) => match self.delay(attempt, retry_after(&response)) {
+                    Some(wait) => wait,
+                    None => return Ok(response),
+                },
+                Err(_) => self.backoff(attempt),
+            };
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+
+    /// Remember the rate limits a response reports
+    fn observe(
+        &self,
+        host: &str,
+        response: Result<Response, reqwest::Error>,
+    ) -> Result<Response, reqwest::Error> {
+        if let Ok(response) = &response
+            && let Some(status) = RateLimitStatus::from_headers(response.headers())
+        {
+            self.rate_limits
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(host.to_string(), (Instant::now(), status));
+        }
+        response
+    }
+
+    /// Wait before retry `attempt + 1`, or `None` if the server asked for
+    /// longer than `max_backoff`
+    fn delay(&self, attempt: usize, retry_after: Option<Duration>) -> Option<Duration> {
+        match retry_after {
+            Some(wait) if wait > self.config.max_backoff => None,
+            Some(wait) => Some(wait),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    fn backoff(&self, attempt: usize) -> Duration {
+        self.config
+            .backoff
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.config.max_backoff)
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// The wait a `Retry-After` header asks for, in seconds or as an HTTP date
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answer one connection per canned response, returning the number of
+    /// requests served
+    async fn serve(responses: Vec<&'static str>) -> (String, tokio::task::JoinHandle<usize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            for (served, response) in responses.iter().enumerate() {
+                let Ok(Ok((mut socket, _))) =
+                    tokio::time::timeout(Duration::from_millis(500), listener.accept()).await
+                else {
+                    return served;
+                };
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await.unwrap();
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            responses.len()
+        });
+        (url, handle)
+    }
+
+    const RATE_LIMITED: &str = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const SLOW_DOWN: &str = "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 3600\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+
+    #[tokio::test]
+    async fn test_retries_rate_limited_requests() {
+        let client = HttpClient::new(HttpConfig::default().with_backoff(Duration::ZERO)).unwrap();
+
+        let (url, server) = serve(vec![RATE_LIMITED, RATE_LIMITED, OK]).await;
+        let response = client.send(client.post(&url).body("{}")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.await.unwrap(), 3);
+
+        // Out of retries, the last response is returned
+        let (url, server) = serve(vec![RATE_LIMITED; 4]).await;
+        let response = client.send(client.post(&url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(server.await.unwrap(), 3);
+
+        // A Retry-After beyond max_backoff isn't waited for
+        let (url, server) = serve(vec![SLOW_DOWN, OK]).await;
+        let response = client.send(client.post(&url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(server.await.unwrap(), 1);
+    }
+
+    const EXHAUSTED: &str = "HTTP/1.1 200 OK\r\nx-ratelimit-limit-requests: 10\r\nx-ratelimit-remaining-requests: 0\r\nx-ratelimit-reset-requests: 300ms\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+
+    #[tokio::test]
+    async fn test_paces_requests_by_rate_limit_headers() {
+        assert_eq!(
+            parse_reset_duration("1m30.5s"),
+            Some(Duration::from_millis(90_500))
+        );
+        assert_eq!(
+            parse_reset_duration("20ms"),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(parse_reset_duration("soon"), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("anthropic-ratelimit-tokens-limit", "1000".parse().unwrap());
+        headers.insert(
+            "anthropic-ratelimit-tokens-remaining",
+            "50".parse().unwrap(),
+        );
+        let reset = chrono::Utc::now() + chrono::Duration::seconds(60);
+        headers.insert(
+            "anthropic-ratelimit-tokens-reset",
+            reset.to_rfc3339().parse().unwrap(),
+        );
+        let status = RateLimitStatus::from_headers(&headers).unwrap();
+        assert_eq!(status.tokens.remaining, Some(50));
+        assert!(status.requests.remaining.is_none());
+        // 5% left is below a 10% headroom: 50 tokens spread over a minute
+        let delay = status.delay(0.1).unwrap();
+        assert!(delay > Duration::from_millis(1100) && delay <= Duration::from_millis(1200));
+        assert_eq!(status.delay(0.01), None);
+        assert_eq!(RateLimitStatus::from_headers(&HeaderMap::new()), None);
+
+        // Nothing left: the next request waits for the reset
+        let client = HttpClient::new(HttpConfig::default()).unwrap();
+        let (url, server) = serve(vec![EXHAUSTED, OK]).await;
+        client.send(client.post(&url)).await.unwrap();
+        assert_eq!(
+            client.rate_limit("127.0.0.1").unwrap().requests.remaining,
+            Some(0)
+        );
+        let started = std::time::Instant::now();
+        let response = client.send(client.post(&url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(started.elapsed() >= Duration::from_millis(250));
+        assert_eq!(server.await.unwrap(), 2);
+    }
+}
@@ -16,21 +16,68 @@
 //! - `async`: Async support (AsyncSharedStore)
 //!
 //! ### Built-in Components  
-//! - `builtin-nodes`: Basic nodes (LogNode, SetValueNode, etc.)
-//! - `builtin-llm`: LLM-related nodes (MockLlmNode, ApiRequestNode)
-//! - `builtin-flows`: Advanced flow components (FlowNode)
+//! - `builtin-nodes`: Basic nodes (LogNode, StructuredLogNode, SetValueNode, etc.), bus messaging
+//!   nodes, RenderReportNode, TransformRecordsNode, SampleNode, DedupeNode, task queue
+//!   nodes (EnqueueTaskNode, DequeueTaskNode), graph memory nodes (UpsertFactNode,
+//!   QueryGraphNode) and JSON Patch nodes (JsonDiffNode, JsonPatchNode)
+//! - `builtin-llm`: LLM-related nodes (MockLlmNode, ApiRequestNode, GeminiRequestNode,
+//!   ReasoningNode, EnsembleLlmNode, CascadeLlmNode, TreeSearchNode) and
+//!   LLM providers (OpenAI, Claude, Gemini, Ollama, LlmRouter)
+//! - `local-inference`: GGUF models run in-process with candle (LocalLlmProvider,
+//!   LocalLlmNode), for offline flows
+//! - `cron`: Cron-aligned delays (`DelayNode::cron`)
+//! - `tiktoken`: Exact token counts in the [`tokens`] module and for fitting prompts
+//!   into a model's context window
+//! - `prompt-library`: Prompt files with YAML front matter (PromptLibrary,
+//!   PromptTemplateNode), with hot reload
+//! - `notify`: Notification nodes (SendEmailNode, SlackWebhookNode, WebhookNotifyNode)
+//!   (both `builtin-llm` and `notify` send HTTP requests through the retrying
+//!   `http::HttpClient`)
+//! - `regex-nodes`: Regular expression nodes (RegexExtractNode, RegexValidateNode)
+//! - `normalize-text`: Markdown and HTML to plain text (NormalizeTextNode)
+//! - `builtin-flows`: Advanced flow components (FlowNode, ForEachFlowNode)
 //! - `builtin`: All built-in components
 //!
+//! Reusable, parameterized sub-flows ([`flows::FlowTemplate`]) and batch
+//! execution over many stores ([`flows::BatchFlow`]) are available in every
+//! configuration. Clocks, randomness and execution IDs come from a
+//! [`RuntimeEnv`], which can be made deterministic for reproducible runs.
+//! API keys are [`Secret`]s, resolved from the environment, files or custom
+//! providers when a request is made and redacted in `Debug` output.
+//! A [`TenantContext`] scopes store keys, credentials and quotas to one
+//! customer when a service runs flows for many.
+//! A [`Redactor`] scrubs sensitive keys and text from store values before
+//! log nodes, the JSONL journal, the LLM journal or the dashboard write them out.
+//!
 //! ### Storage Backends
 //! - `storage-memory`: In-memory storage (included in core)
 //! - `storage-file`: File-based storage
+//! - `storage-jsonl`: Append-only JSONL event log
 //! - `storage-redis`: Redis backend
+//! - `storage-s3`: S3 and other object stores via `object_store`
 //! - `storage-database`: SQL databases via SeaORM
 //! - `storage-sqlite`: SQLite support
 //! - `storage-postgres`: PostgreSQL support  
 //! - `storage-mysql`: MySQL support
+//! - `storage-encrypted`: AES-256-GCM encryption at rest wrapping any backend
 //! - `storage-all`: All storage backends
 //!
+//! ### Runtime
+//! - `work-queue`: Distributed work queue for running flows on worker processes
+//! - `server`: Serve flows as HTTP endpoints with axum
+//! - `mcp`: Expose flows as Model Context Protocol tools
+//! - `tui`: Terminal dashboard showing live flow progress
+//! - `grpc`: Run nodes in remote services over gRPC
+//!
+//! ### Validation
+//! - `schema-validation`: JSON Schema validation of store keys
+//!
+//! ### Redaction
+//! - `redaction`: Regular expression patterns in [`Redactor`]
+//!
+//! ### Evaluation
+//! - `eval`: Score flows against datasets of cases for regression testing
+//!
 //! ### Convenience Features
 //! - `default`: Core + async + builtin-nodes + storage-memory
 //! - `full`: Complete feature set
@@ -73,10 +120,38 @@
 // ============================================================================
 
 pub mod action;
+pub mod bus;
+pub mod env;
+#[cfg(feature = "eval")]
+pub mod eval;
+pub mod experiment;
 pub mod flow;
+pub mod flows;
+pub mod health;
+#[cfg(any(feature = "builtin-llm", feature = "notify"))]
+pub mod http;
+#[cfg(feature = "builtin-llm")]
+pub mod llm;
+#[cfg(feature = "mcp")]
+pub mod mcp;
 pub mod node;
+pub mod pricing;
+#[cfg(feature = "work-queue")]
+pub mod queue;
+pub mod redact;
+#[cfg(feature = "grpc")]
+pub mod remote;
+pub mod runtime;
+pub mod secrets;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod shared_store;
 pub mod storage;
+pub mod supervisor;
+pub mod tenant;
+pub mod tokens;
+#[cfg(feature = "tui")]
+pub mod tui;
 
 // ============================================================================
 // CORE RE-EXPORTS
@@ -86,54 +161,153 @@ pub mod storage;
 pub use action::{Action, ActionBuilder, ActionCondition, ComparisonOperator};
 
 // SharedStore - always available
-pub use shared_store::{AsyncSharedStore, InMemorySharedStore, SharedStore};
+pub use shared_store::{
+    AsyncSharedStore, CheckpointManager, ExperimentTag, InMemorySharedStore, Intent, IntentStatus,
+    JsonPath, JsonPathError, KeyAccess, KeyAccessPolicy, KnowledgeGraph, NodeFailure, Session,
+    SessionManager, SharedStore, SharedStoreError, SharedStoreHandle, SystemKeys, Task, TaskQueue,
+    TaskStatus, TokenUsage,
+};
 
 // Storage traits - always available
 pub use storage::StorageBackend;
 
 // Node system - always available
-pub use node::{ExecutionContext, FunctionNode, InMemoryNode, Node, NodeBackend, NodeBuilder};
+pub use node::{
+    CircuitBreaker, CircuitBreakerNode, EffectLedger, ExecutionContext, FlowVars, FunctionNode,
+    InMemoryNode, KeySpec, MemoTable, MemoizedNode, Node, NodeBackend, NodeBuilder, NodePhase,
+    NodeRegistry, NodeRunStats, Outputs,
+};
+
+// Runtime environment - always available
+pub use env::RuntimeEnv;
+
+// Credentials - always available
+pub use secrets::Secret;
+
+// Redaction - always available
+pub use redact::Redactor;
+
+// Agent bus - always available
+pub use bus::{AgentBus, BusMessage};
+
+// Runtime facade - always available
+pub use runtime::PocketFlowRuntime;
+
+// Readiness checks - always available
+pub use health::{HealthReport, RuntimeHealth};
+
+// Agent supervision - always available
+pub use supervisor::{AgentHandle, AgentSpec, AgentStatus, FlowSupervisor, RestartPolicy};
+
+// Multi-tenancy - always available
+pub use tenant::{QuotaError, TenantContext, TenantQuota, TenantStorage};
+
+// Model prices for cost estimates - always available
+pub use pricing::{ModelPrice, PricingCatalog};
+
+// A/B experiments - always available
+pub use experiment::{Experiment, ExperimentRun, Variant, VariantSummary};
 
 // Flow system - always available
 pub use flow::{
-    BasicFlow, Flow, FlowBuilder, FlowConfig, FlowError, FlowExecutionResult, Route, RouteCondition,
+    BasicFlow, DryRun, DryRunCall, DryRunStub, Flow, FlowBuilder, FlowConfig, FlowError,
+    FlowExecutionResult, FlowReport, NodeExecutionStat, Route, RouteCondition,
 };
 
+// Reusable flow templates - always available
+pub use flows::{BatchFlow, BatchResult, FlowTemplate, TemplateFlow};
+
 // ============================================================================
 // STORAGE BACKEND RE-EXPORTS (feature-gated)
 // ============================================================================
 
 /// Memory storage (included with core)
 #[cfg(feature = "storage-memory")]
-pub use storage::{InMemoryStorage, InMemoryStorageError};
+pub use storage::{ConcurrentInMemoryStorage, InMemoryStorage, InMemoryStorageError};
 
 /// File storage
 #[cfg(feature = "storage-file")]
 pub use storage::FileStorage;
 
+/// Append-only JSONL storage
+#[cfg(feature = "storage-jsonl")]
+pub use storage::JsonlStorage;
+
 /// Redis storage
 #[cfg(feature = "storage-redis")]
 pub use storage::RedisStorage;
 
+/// S3 / object store storage
+#[cfg(feature = "storage-s3")]
+pub use storage::ObjectStoreStorage;
+
 /// Database storage  
 #[cfg(feature = "storage-database")]
 pub use storage::DatabaseStorage;
 
+#[cfg(feature = "storage-encrypted")]
+pub use storage::EncryptedStorage;
+
+/// Flow evaluation
+#[cfg(feature = "eval")]
+pub use eval::{Check, EvalCase, EvalReport, Evaluator};
+
+/// Distributed work queue
+#[cfg(feature = "work-queue")]
+pub use queue::{FlowJob, FlowRegistry, FlowWorkQueue, FlowWorker, JobQueue, JobResult};
+
 // ============================================================================
 // BUILTIN COMPONENTS RE-EXPORTS (feature-gated)
 // ============================================================================
 
 /// Basic builtin nodes
 #[cfg(feature = "builtin-nodes")]
-pub use node::builtin::{ConditionalNode, DelayNode, GetValueNode, LogNode, SetValueNode};
+pub use node::builtin::{
+    AccumulateNode, AsyncConditionalNode, AwaitKeysNode, ConditionalNode, CounterNode, DedupeNode,
+    DelayNode, DequeueTaskNode, EnqueueTaskNode, GetValueNode, JsonDiffNode, JsonPatchNode,
+    LogNode, QueryGraphNode, QueryNode, ReceiveMessageNode, RenderReportNode, ReportFormat,
+    SampleNode, SendMessageNode, SetValueNode, StructuredLogNode, SwitchNode, TransformRecordsNode,
+    UpsertFactNode,
+};
 
 /// LLM-related nodes
 #[cfg(feature = "builtin-llm")]
-pub use node::builtin::{ApiConfig, ApiRequestNode, MockLlmNode};
+pub use node::builtin::{
+    ApiConfig, ApiRequestNode, CascadeLlmNode, EnsembleLlmNode, GeminiConfig, GeminiRequestNode,
+    MockLlmNode, Provider, ReasoningNode, TreeSearchNode,
+};
+
+/// Local inference node
+#[cfg(feature = "local-inference")]
+pub use node::builtin::LocalLlmNode;
+
+/// Regular expression nodes
+#[cfg(feature = "regex-nodes")]
+pub use node::builtin::{RegexExtractNode, RegexValidateNode};
+
+/// Text normalization nodes
+#[cfg(feature = "normalize-text")]
+pub use node::builtin::{NormalizeTextNode, TextFormat};
+
+/// Notification nodes
+#[cfg(feature = "notify")]
+pub use node::builtin::{SendEmailNode, SlackWebhookNode, WebhookNotifyNode};
+
+/// LLM providers
+#[cfg(feature = "builtin-llm")]
+pub use llm::{LlmProvider, LlmRouter};
+
+/// Prompt library
+#[cfg(feature = "prompt-library")]
+pub use llm::{Prompt, PromptLibrary};
+#[cfg(feature = "prompt-library")]
+pub use node::builtin::PromptTemplateNode;
 
 /// Flow components
 #[cfg(feature = "builtin-flows")]
 pub use flow::FlowNode;
+#[cfg(feature = "builtin-flows")]
+pub use flows::ForEachFlowNode;
 
 // ============================================================================
 // CONVENIENCE RE-EXPORTS
@@ -146,40 +320,69 @@ pub use serde_json::Value as JsonValue;
 pub mod prelude {
     // Core types - always available
     pub use crate::{
-        Action, ActionBuilder, ActionCondition, ComparisonOperator, ExecutionContext, Flow,
-        FlowBuilder, FlowError, FunctionNode, Node, NodeBackend, NodeBuilder, PocketFlowError,
-        PocketFlowResult, RouteCondition, SharedStore, StorageBackend,
+        Action, ActionBuilder, ActionCondition, AgentBus, BatchFlow, ComparisonOperator,
+        ExecutionContext, Flow, FlowBuilder, FlowError, FlowTemplate, FunctionNode, Node,
+        NodeBackend, NodeBuilder, PocketFlowError, PocketFlowResult, RouteCondition, RuntimeEnv,
+        SharedStore, StorageBackend,
     };
 
     // Storage backends - feature-gated
     #[cfg(feature = "storage-memory")]
-    pub use crate::storage::{InMemoryStorage, InMemoryStorageError};
+    pub use crate::storage::{ConcurrentInMemoryStorage, InMemoryStorage, InMemoryStorageError};
 
     #[cfg(feature = "storage-file")]
     pub use crate::storage::FileStorage;
 
+    #[cfg(feature = "storage-jsonl")]
+    pub use crate::storage::JsonlStorage;
+
     #[cfg(feature = "storage-redis")]
     pub use crate::storage::RedisStorage;
 
+    #[cfg(feature = "storage-s3")]
+    pub use crate::storage::ObjectStoreStorage;
+
     #[cfg(feature = "storage-database")]
     pub use crate::storage::DatabaseStorage;
 
+    #[cfg(feature = "storage-encrypted")]
+    pub use crate::storage::EncryptedStorage;
+
     // Async support - always available
-    pub use crate::shared_store::AsyncSharedStore;
+    pub use crate::shared_store::{AsyncSharedStore, SharedStoreHandle};
 
     // Builtin nodes - feature-gated
     #[cfg(feature = "builtin-nodes")]
     pub use crate::node::builtin::{
-        ConditionalNode, DelayNode, GetValueNode, LogNode, SetValueNode,
+        AccumulateNode, AsyncConditionalNode, AwaitKeysNode, ConditionalNode, CounterNode,
+        DedupeNode, DelayNode, DequeueTaskNode, EnqueueTaskNode, GetValueNode, JsonDiffNode,
+        JsonPatchNode, LogNode, QueryGraphNode, QueryNode, ReceiveMessageNode, RenderReportNode,
+        ReportFormat, SampleNode, SendMessageNode, SetValueNode, StructuredLogNode, SwitchNode,
+        TransformRecordsNode, UpsertFactNode,
     };
 
+    #[cfg(feature = "normalize-text")]
+    pub use crate::node::builtin::{NormalizeTextNode, TextFormat};
+    #[cfg(feature = "regex-nodes")]
+    pub use crate::node::builtin::{RegexExtractNode, RegexValidateNode};
+
     // LLM nodes - feature-gated
     #[cfg(feature = "builtin-llm")]
-    pub use crate::node::builtin::{ApiConfig, ApiRequestNode, MockLlmNode};
+    pub use crate::node::builtin::{
+        ApiConfig, ApiRequestNode, CascadeLlmNode, EnsembleLlmNode, GeminiConfig,
+        GeminiRequestNode, MockLlmNode, Provider, ReasoningNode, TreeSearchNode,
+    };
+
+    #[cfg(feature = "builtin-llm")]
+    pub use crate::llm::{ChatMessage, ChatRequest, LlmProvider, LlmRouter};
+    #[cfg(feature = "prompt-library")]
+    pub use crate::{PromptLibrary, PromptTemplateNode};
 
     // Flow components - feature-gated
     #[cfg(feature = "builtin-flows")]
     pub use crate::flow::FlowNode;
+    #[cfg(feature = "builtin-flows")]
+    pub use crate::flows::ForEachFlowNode;
 
     // Commonly used external types
     pub use serde_json::Value as JsonValue;
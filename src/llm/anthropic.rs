@@ -0,0 +1,241 @@
+//! Anthropic Messages API backend
+
+use super::{
+    ChatRequest, ChatResponse, LlmError, LlmProvider, Role, TokenStream, line_stream,
+    request_usage, send_checked, send_json, sse_data, tag_request,
+};
+use crate::http::{HttpClient, RateLimitStatus};
+use crate::secrets::Secret;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// Default Anthropic API base URL
+pub const ANTHROPIC_API_BASE: &str = "https://api.anthropic.com/v1";
+
+/// `anthropic-version` header sent with every request
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// [`LlmProvider`] for Claude models
+///
+/// System messages are sent as the top-level `system` prompt. Anthropic has
+/// no embeddings API, so [`LlmProvider::embeddings`] is unsupported.
+#[derive(Debug, Clone)]
+pub struct ClaudeProvider {
+    api_key: Secret,
+    base_url: String,
+    model: String,
+    max_tokens: u32,
+    temperature: Option<f32>,
+    timeout: Option<u64>,
+    client: HttpClient,
+}
+
+impl ClaudeProvider {
+    /// Create a provider with an API key
+    pub fn new(api_key: impl Into<Secret>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: ANTHROPIC_API_BASE.to_string(),
+            model: "claude-3-5-haiku-latest".to_string(),
+            max_tokens: 1024,
+            temperature: None,
+            timeout: Some(60),
+            client: HttpClient::shared(),
+        }
+    }
+
+    /// Create a provider reading the key from `ANTHROPIC_API_KEY`
+    ///
+    /// The variable must be set now and is re-read for every request.
+    pub fn from_env() -> Result<Self, LlmError> {
+        let api_key = Secret::env("ANTHROPIC_API_KEY");
+        api_key
+            .expose()
+            .map_err(|_| LlmError::InvalidRequest("ANTHROPIC_API_KEY is not set".to_string()))?;
+        Ok(Self::new(api_key))
+    }
+
+    /// Set the model to use
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Set the API base URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Set the default maximum tokens to generate (required by the API)
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Set the default temperature
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set timeout in seconds
+    pub fn with_timeout(mut self, timeout: u64) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests through `client` instead of the shared one, e.g. to use
+    /// a proxy or different retry settings
+    pub fn with_http_client(mut self, client: HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Build the Messages API request body
+    pub fn request_body(&self, request: &ChatRequest, stream: bool) -> Value {
+        let messages: Vec<Value> = request
+            .messages
+            .iter()
+            .filter(|m| m.role != Role::System)
+            .map(|m| json!({"role": m.role, "content": m.content}))
+            .collect();
+        let mut body = json!({
+            "model": request.model.as_deref().unwrap_or(&self.model),
+            "max_tokens": request.max_tokens.unwrap_or(self.max_tokens),
+            "messages": messages,
+        });
+        if let Some(system) = request.system_instruction() {
+            body["system"] = json!(system);
+        }
+        if let Some(temperature) = request.temperature.or(self.temperature) {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(top_p) = request.top_p {
+            body["top_p"] = json!(top_p);
+        }
+        if stream {
+            body["stream"] = json!(true);
+        }
+        body
+    }
+
+    fn post(&self) -> Result<reqwest::RequestBuilder, LlmError> {
+        self.request(reqwest::Method::POST, "messages")
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+    ) -> Result<reqwest::RequestBuilder, LlmError> {
+        let mut request = self
+            .client
+            .request(
+                method,
+                format!("{}/{}", self.base_url.trim_end_matches('/'), path),
+            )
+            .header("x-api-key", self.api_key.expose()?)
+            .header("anthropic-version", ANTHROPIC_VERSION);
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(Duration::from_secs(timeout));
+        }
+        Ok(request)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ClaudeProvider {
+    fn name(&self) -> &str {
+        "claude"
+    }
+
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+        let response = send_json(
+            &self.client,
+            tag_request(self.post()?, &request),
+            &self.request_body(&request, false),
+        )
+        .await?;
+        let rate_limit = RateLimitStatus::from_headers(response.headers());
+        let body: Value = response.json().await.map_err(LlmError::transport)?;
+        let content: String = body
+            .get("content")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|block| block.get("text").and_then(Value::as_str))
+            .collect();
+        let tool_calls: Vec<Value> = body
+            .get("content")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|block| block.get("type").and_then(Value::as_str) == Some("tool_use"))
+            .cloned()
+            .collect();
+        if content.is_empty() && tool_calls.is_empty() {
+            return Err(LlmError::Request(
+                "No response content received".to_string(),
+            ));
+        }
+        Ok(ChatResponse {
+            content,
+            provider: self.name().to_string(),
+            model: body
+                .get("model")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            finish_reason: body
+                .get("stop_reason")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            usage: request_usage(
+                body.pointer("/usage/input_tokens").and_then(Value::as_u64),
+                body.pointer("/usage/output_tokens").and_then(Value::as_u64),
+            ),
+            rate_limit,
+            tool_calls,
+            raw: Some(body),
+        })
+    }
+
+    async fn chat_stream(&self, request: ChatRequest) -> Result<TokenStream, LlmError> {
+        let response = send_json(
+            &self.client,
+            tag_request(self.post()?, &request),
+            &self.request_body(&request, true),
+        )
+        .await?;
+        Ok(line_stream(response, |line| {
+            let Some(event) = sse_data(line)? else {
+                return Ok(None);
+            };
+            match event.get("type").and_then(Value::as_str) {
+                Some("content_block_delta") => Ok(event
+                    .pointer("/delta/text")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)),
+                Some("error") => Err(LlmError::Request(
+                    event
+                        .pointer("/error/message")
+                        .and_then(Value::as_str)
+                        .unwrap_or("stream error")
+                        .to_string(),
+                )),
+                _ => Ok(None),
+            }
+        }))
+    }
+
+    /// Lists one model, which checks the API key without generating
+    async fn ping(&self) -> Result<(), LlmError> {
+        send_checked(
+            &self.client,
+            self.request(reqwest::Method::GET, "models?limit=1")?,
+        )
+        .await
+        .map(|_| ())
+    }
+}
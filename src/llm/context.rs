@@ -0,0 +1,366 @@
+//! Keeping prompts inside a model's context window
+//!
+//! A [`ContextWindow`] counts the tokens of a conversation and, when it would
+//! not fit the model's context (less the room reserved for the reply),
+//! shortens it according to its [`ContextStrategy`]: drop the oldest turns,
+//! replace them with a summary written by the provider, or fail before
+//! sending. System messages and the latest message are always kept.
+//!
+//! Tokens are counted by a [`TokenCounter`] from the [`tokens`](crate::tokens)
+//! module; [`ContextWindow::for_model`] picks the model's tokenizer when the
+//! `tiktoken` feature is enabled.
+//!
+//! ```rust
+//! # use pocketflow_rs::llm::ChatMessage;
+//! # use pocketflow_rs::llm::context::{ContextStrategy, ContextWindow};
+//! let window = ContextWindow::new(40).with_strategy(ContextStrategy::DropOldest);
+//! let messages = vec![
+//!     ChatMessage::system("Be brief."),
+//!     ChatMessage::user("a".repeat(80)),
+//!     ChatMessage::assistant("ok"),
+//!     ChatMessage::user("Next question?"),
+//! ];
+//! assert!(window.count(&messages) > window.budget());
+//! ```
+
+use super::{ChatMessage, ChatRequest, LlmError, LlmProvider, Role};
+use std::sync::Arc;
+
+#[cfg(feature = "tiktoken")]
+pub use crate::tokens::TiktokenCounter;
+pub use crate::tokens::{EstimateCounter, TokenCounter};
+
+/// Tokens each message costs beyond its content (role and delimiters)
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Tokens priming the reply
+const REPLY_OVERHEAD: usize = 3;
+
+/// Context size assumed for models missing from [`context_size`]
+pub const DEFAULT_CONTEXT_SIZE: usize = 8_192;
+
+/// Context size of a known model, in tokens
+pub fn context_size(model: &str) -> Option<usize> {
+    let model = model.to_lowercase();
+    let model = model.rsplit('/').next().unwrap_or_default();
+    const SIZES: &[(&str, usize)] = &[
+        ("gpt-4.1", 1_047_576),
+        ("gpt-4o", 128_000),
+        ("gpt-4-turbo", 128_000),
+        ("gpt-4-32k", 32_768),
+        ("gpt-4", 8_192),
+        ("gpt-3.5-turbo-instruct", 4_096),
+        ("gpt-3.5-turbo", 16_385),
+        ("o1", 200_000),
+        ("o3", 200_000),
+        ("o4", 200_000),
+        ("claude", 200_000),
+        ("gemini-1.5-pro", 2_097_152),
+        ("gemini", 1_048_576),
+        ("llama3.1", 131_072),
+        ("llama3.2", 131_072),
+        ("llama3", 8_192),
+        ("mistral", 32_768),
+    ];
+    SIZES
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, size)| *size)
+}
+
+/// What to do when a conversation doesn't fit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContextStrategy {
+    /// Drop the oldest turns until it fits
+    #[default]
+    DropOldest,
+    /// Replace the oldest turns with a summary written by the provider
+    Summarize,
+    /// Fail with [`LlmError::ContextOverflow`]
+    Error,
+}
+
+/// Token budget for the messages sent to a model
+#[derive(Clone)]
+pub struct ContextWindow {
+    max_tokens: usize,
+    reserved: usize,
+    strategy: ContextStrategy,
+    counter: Arc<dyn TokenCounter>,
+}
+
+impl std::fmt::Debug for ContextWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContextWindow")
+            .field("max_tokens", &self.max_tokens)
+            .field("reserved", &self.reserved)
+            .field("strategy", &self.strategy)
+            .finish()
+    }
+}
+
+impl ContextWindow {
+    /// A window of `max_tokens`, counted with [`EstimateCounter`]
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            reserved: 0,
+            strategy: ContextStrategy::default(),
+            counter: Arc::new(EstimateCounter),
+        }
+    }
+
+    /// The window of a model, counted with its tokenizer when the `tiktoken`
+    /// feature is enabled
+    pub fn for_model(model: &str) -> Self {
+        Self {
+            counter: crate::tokens::for_model(model),
+            ..Self::new(context_size(model).unwrap_or(DEFAULT_CONTEXT_SIZE))
+        }
+    }
+
+    /// Keep `tokens` free for the reply
+    pub fn with_reserved(mut self, tokens: usize) -> Self {
+        self.reserved = tokens;
+        self
+    }
+
+    /// Set what happens when the conversation doesn't fit
+    pub fn with_strategy(mut self, strategy: ContextStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Count tokens with a different counter
+    pub fn with_counter(mut self, counter: impl TokenCounter + 'static) -> Self {
+        self.counter = Arc::new(counter);
+        self
+    }
+
+    /// Tokens available for the prompt
+    pub fn budget(&self) -> usize {
+        self.max_tokens.saturating_sub(self.reserved)
+    }
+
+    /// Tokens a conversation takes up
+    pub fn count(&self, messages: &[ChatMessage]) -> usize {
+        messages
+            .iter()
+            .map(|m| self.message_tokens(m))
+            .sum::<usize>()
+            + REPLY_OVERHEAD
+    }
+
+    fn message_tokens(&self, message: &ChatMessage) -> usize {
+        self.counter.count(&message.content) + MESSAGE_OVERHEAD
+    }
+
+    /// Shorten a conversation to fit the budget
+    ///
+    /// `provider` writes the summary for [`ContextStrategy::Summarize`].
+    pub async fn fit(
+        &self,
+        messages: Vec<ChatMessage>,
+        provider: &dyn LlmProvider,
+    ) -> Result<Vec<ChatMessage>, LlmError> {
+        let budget = self.budget();
+        let tokens = self.count(&messages);
+        if tokens <= budget {
+            return Ok(messages);
+        }
+        let overflow = || LlmError::ContextOverflow {
+            tokens,
+            limit: budget,
+        };
+        if self.strategy == ContextStrategy::Error {
+            return Err(overflow());
+        }
+
+        // System messages and the latest message stay; the turns between them
+        // are dropped oldest first
+        let mut messages = messages;
+        let Some(latest) = messages.pop() else {
+            return Err(overflow());
+        };
+        let (system, mut turns): (Vec<_>, Vec<_>) =
+            messages.into_iter().partition(|m| m.role == Role::System);
+        turns.reverse();
+        let kept_tokens = |turns: &[ChatMessage], extra: usize| {
+            self.count(&system)
+                + self.message_tokens(&latest)
+                + extra
+                + turns.iter().map(|m| self.message_tokens(m)).sum::<usize>()
+        };
+
+        // Leave a quarter of the budget for the summary
+        let summary_budget = match self.strategy {
+            ContextStrategy::Summarize => budget / 4,
+            _ => 0,
+        };
+        let mut dropped = Vec::new();
+        while kept_tokens(&turns, summary_budget) > budget {
+            match turns.pop() {
+                Some(turn) => dropped.push(turn),
+                None => break,
+            }
+        }
+
+        let mut summary = None;
+        if self.strategy == ContextStrategy::Summarize && !dropped.is_empty() {
+            let text = self.summarize(&dropped, provider, summary_budget).await?;
+            summary = Some(ChatMessage::system(format!(
+                "Summary of the earlier conversation: {}",
+                text
+            )));
+        }
+        let summary_tokens = summary.as_ref().map_or(0, |m| self.message_tokens(m));
+        // The summary may run over its share
+        while kept_tokens(&turns, summary_tokens) > budget && turns.pop().is_some() {}
+        if kept_tokens(&turns, summary_tokens) > budget {
+            return Err(overflow());
+        }
+
+        turns.reverse();
+        Ok(system
+            .into_iter()
+            .chain(summary)
+            .chain(turns)
+            .chain(std::iter::once(latest))
+            .collect())
+    }
+
+    /// Ask the provider to summarize dropped turns, most recent ones first if
+    /// they don't all fit
+    async fn summarize(
+        &self,
+        dropped: &[ChatMessage],
+        provider: &dyn LlmProvider,
+        max_tokens: usize,
+    ) -> Result<String, LlmError> {
+        let instruction = ChatMessage::system(
+            "Summarize the conversation below in a few sentences. Keep the facts, \
+             names and decisions needed to continue it.",
+        );
+        let mut lines = Vec::new();
+        let mut tokens = self.message_tokens(&instruction) + max_tokens + REPLY_OVERHEAD;
+        // `dropped` is oldest first; keep the most recent turns that fit
+        for turn in dropped.iter().rev() {
+            let line = format!("{:?}: {}", turn.role, turn.content);
+            tokens += self.counter.count(&line) + 1;
+            if tokens > self.budget() {
+                break;
+            }
+            lines.push(line);
+        }
+        lines.reverse();
+
+        let mut request = ChatRequest::new(vec![instruction, ChatMessage::user(lines.join("\n"))]);
+        request.max_tokens = u32::try_from(max_tokens).ok().filter(|&t| t > 0);
+        Ok(provider.chat(request).await?.content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ChatResponse;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Replies with a fixed summary, recording requests
+    #[derive(Default)]
+    struct Summarizer {
+        requests: Mutex<Vec<ChatRequest>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for Summarizer {
+        fn name(&self) -> &str {
+            "summarizer"
+        }
+
+        async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+            self.requests.lock().unwrap().push(request);
+            Ok(ChatResponse::new("they said hi", "summarizer"))
+        }
+    }
+
+    fn conversation() -> Vec<ChatMessage> {
+        vec![
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("a".repeat(400)),
+            ChatMessage::assistant("b".repeat(400)),
+            ChatMessage::user("c".repeat(40)),
+            ChatMessage::assistant("d".repeat(40)),
+            ChatMessage::user("Next?"),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_context_window_strategies() {
+        let provider = Summarizer::default();
+        let window = ContextWindow::new(200);
+        assert_eq!(window.count(&[ChatMessage::user("abcdefgh")]), 2 + 4 + 3);
+
+        let fitted = window.fit(conversation(), &provider).await.unwrap();
+        assert!(window.count(&fitted) <= 200);
+        let contents: Vec<&str> = fitted.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec![
+                "Be brief.",
+                &"b".repeat(400),
+                &"c".repeat(40),
+                &"d".repeat(40),
+                "Next?"
+            ]
+        );
+
+        let summarized = window
+            .clone()
+            .with_strategy(ContextStrategy::Summarize)
+            .fit(conversation(), &provider)
+            .await
+            .unwrap();
+        assert!(window.count(&summarized) <= 200);
+        assert_eq!(summarized.len(), 6);
+        assert_eq!(
+            summarized[1].content,
+            "Summary of the earlier conversation: they said hi"
+        );
+        assert_eq!(summarized[2].content, "b".repeat(400));
+        let request = provider.requests.lock().unwrap()[0].clone();
+        assert!(request.messages[1].content.starts_with("User: aaa"));
+
+        let error = window
+            .with_strategy(ContextStrategy::Error)
+            .fit(conversation(), &provider)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            LlmError::ContextOverflow { limit: 200, .. }
+        ));
+
+        // Nothing left to drop
+        let too_long = vec![ChatMessage::user("x".repeat(1000))];
+        assert!(
+            ContextWindow::new(200)
+                .fit(too_long, &provider)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_context_size() {
+        assert_eq!(context_size("gpt-4o-mini"), Some(128_000));
+        assert_eq!(context_size("gpt-4-0613"), Some(8_192));
+        assert_eq!(context_size("anthropic/claude-3-5-sonnet"), Some(200_000));
+        assert_eq!(context_size("my-model"), None);
+        assert_eq!(
+            ContextWindow::for_model("my-model").budget(),
+            DEFAULT_CONTEXT_SIZE
+        );
+    }
+}
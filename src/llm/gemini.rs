@@ -0,0 +1,272 @@
+//! Google Generative Language (Gemini) backend
+
+use super::{
+    ChatRequest, ChatResponse, EmbeddingRequest, LlmError, LlmProvider, Role, TokenStream,
+    line_stream, request_usage, send_checked, send_json, sse_data, tag_request,
+};
+use crate::http::HttpClient;
+use crate::node::builtin::gemini::GeminiConfig;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// Default model for [`GeminiProvider::embeddings`]
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-004";
+
+/// [`LlmProvider`] for the Gemini API
+///
+/// System messages become the system instruction and assistant turns are sent
+/// with the `model` role.
+#[derive(Debug, Clone)]
+pub struct GeminiProvider {
+    config: GeminiConfig,
+    client: HttpClient,
+    embedding_model: String,
+}
+
+impl GeminiProvider {
+    /// Create a provider from a Gemini configuration
+    pub fn new(config: GeminiConfig) -> Self {
+        Self {
+            config,
+            client: HttpClient::shared(),
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
+        }
+    }
+
+    /// Set the default embeddings model
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = model.into();
+        self
+    }
+
+    /// Send requests through `client` instead of the shared one, e.g. to use
+    /// a proxy or different retry settings
+    pub fn with_http_client(mut self, client: HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &GeminiConfig {
+        &self.config
+    }
+
+    /// Build the `generateContent` request body
+    pub fn request_body(&self, request: &ChatRequest) -> Value {
+        let contents: Vec<Value> = request
+            .messages
+            .iter()
+            .filter(|m| m.role != Role::System)
+            .map(|m| {
+                let role = if m.role == Role::Assistant {
+                    "model"
+                } else {
+                    "user"
+                };
+                json!({"role": role, "parts": [{"text": m.content}]})
+            })
+            .collect();
+        let mut body = json!({ "contents": contents });
+        if let Some(instruction) = request.system_instruction() {
+            body["systemInstruction"] = json!({"parts": [{"text": instruction}]});
+        }
+
+        let mut generation = serde_json::Map::new();
+        if let Some(max_output_tokens) = request.max_tokens.or(self.config.max_output_tokens) {
+            generation.insert("maxOutputTokens".into(), json!(max_output_tokens));
+        }
+        if let Some(temperature) = request.temperature.or(self.config.temperature) {
+            generation.insert("temperature".into(), json!(temperature));
+        }
+        if let Some(top_p) = request.top_p.or(self.config.top_p) {
+            generation.insert("topP".into(), json!(top_p));
+        }
+        if let Some(top_k) = self.config.top_k {
+            generation.insert("topK".into(), json!(top_k));
+        }
+        if let Some(seed) = request.seed {
+            generation.insert("seed".into(), json!(seed));
+        }
+        if let Some(format) = request.response_format.as_ref().filter(|f| f.is_json()) {
+            generation.insert("responseMimeType".into(), json!("application/json"));
+            if let Some(schema) = format.schema() {
+                generation.insert("responseSchema".into(), schema.clone());
+            }
+        }
+        if !generation.is_empty() {
+            body["generationConfig"] = Value::Object(generation);
+        }
+        if !self.config.safety_settings.is_empty() {
+            body["safetySettings"] = json!(self.config.safety_settings);
+        }
+        body
+    }
+
+    fn post(&self, model: &str, method: &str) -> Result<reqwest::RequestBuilder, LlmError> {
+        self.request(reqwest::Method::POST, &format!("{}:{}", model, method))
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        model_path: &str,
+    ) -> Result<reqwest::RequestBuilder, LlmError> {
+        let url = format!(
+            "{}/models/{}",
+            self.config.base_url.trim_end_matches('/'),
+            model_path
+        );
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-goog-api-key", self.config.api_key.expose()?);
+        if let Some(timeout) = self.config.timeout {
+            request = request.timeout(Duration::from_secs(timeout));
+        }
+        Ok(request)
+    }
+}
+
+/// Extract the text of the first candidate, failing on blocked prompts
+fn response_text(body: &Value) -> Result<String, LlmError> {
+    if let Some(reason) = body
+        .pointer("/promptFeedback/blockReason")
+        .and_then(Value::as_str)
+    {
+        return Err(LlmError::Rejected(format!("Prompt blocked: {}", reason)));
+    }
+    let Some(candidate) = body.pointer("/candidates/0") else {
+        return Ok(String::new());
+    };
+    let text: String = candidate
+        .pointer("/content/parts")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|part| part.get("text").and_then(Value::as_str))
+        .collect();
+    if text.is_empty() && candidate.get("finishReason").and_then(Value::as_str) == Some("SAFETY") {
+        return Err(LlmError::Rejected(
+            "Response blocked by safety settings".to_string(),
+        ));
+    }
+    Ok(text)
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+        let model = request
+            .model
+            .clone()
+            .unwrap_or_else(|| self.config.model.clone());
+        let response = send_json(
+            &self.client,
+            tag_request(self.post(&model, "generateContent")?, &request),
+            &self.request_body(&request),
+        )
+        .await?;
+        let body: Value = response.json().await.map_err(LlmError::transport)?;
+        let content = response_text(&body)?;
+        let tool_calls: Vec<Value> = body
+            .pointer("/candidates/0/content/parts")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|part| part.get("functionCall").cloned())
+            .collect();
+        if content.is_empty() && tool_calls.is_empty() {
+            return Err(LlmError::Request(
+                "No response content received".to_string(),
+            ));
+        }
+        Ok(ChatResponse {
+            content,
+            provider: self.name().to_string(),
+            model: body
+                .get("modelVersion")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .or(Some(model)),
+            finish_reason: body
+                .pointer("/candidates/0/finishReason")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            usage: request_usage(
+                body.pointer("/usageMetadata/promptTokenCount")
+                    .and_then(Value::as_u64),
+                body.pointer("/usageMetadata/candidatesTokenCount")
+                    .and_then(Value::as_u64),
+            ),
+            rate_limit: None,
+            tool_calls,
+            raw: Some(body),
+        })
+    }
+
+    async fn chat_stream(&self, request: ChatRequest) -> Result<TokenStream, LlmError> {
+        let model = request
+            .model
+            .clone()
+            .unwrap_or_else(|| self.config.model.clone());
+        let response = send_json(
+            &self.client,
+            tag_request(
+                self.post(&model, "streamGenerateContent?alt=sse")?,
+                &request,
+            ),
+            &self.request_body(&request),
+        )
+        .await?;
+        Ok(line_stream(response, |line| {
+            let Some(chunk) = sse_data(line)? else {
+                return Ok(None);
+            };
+            let text = response_text(&chunk)?;
+            Ok((!text.is_empty()).then_some(text))
+        }))
+    }
+
+    async fn embeddings(&self, request: EmbeddingRequest) -> Result<Vec<Vec<f32>>, LlmError> {
+        let model = request
+            .model
+            .unwrap_or_else(|| self.embedding_model.clone());
+        let requests: Vec<Value> = request
+            .inputs
+            .iter()
+            .map(|input| {
+                json!({
+                    "model": format!("models/{}", model),
+                    "content": {"parts": [{"text": input}]}
+                })
+            })
+            .collect();
+        let response = send_json(
+            &self.client,
+            self.post(&model, "batchEmbedContents")?,
+            &json!({ "requests": requests }),
+        )
+        .await?;
+        let body: Value = response.json().await.map_err(LlmError::transport)?;
+        body.get("embeddings")
+            .and_then(Value::as_array)
+            .ok_or_else(|| LlmError::Request("No embeddings received".to_string()))?
+            .iter()
+            .map(|embedding| {
+                serde_json::from_value(embedding["values"].clone())
+                    .map_err(|e| LlmError::Request(format!("Invalid embedding: {}", e)))
+            })
+            .collect()
+    }
+
+    /// Fetches the configured model's metadata
+    async fn ping(&self) -> Result<(), LlmError> {
+        let request = self.request(reqwest::Method::GET, &self.config.model)?;
+        send_checked(&self.client, request).await.map(|_| ())
+    }
+}
@@ -0,0 +1,200 @@
+//! Request hedging to cut tail latency
+//!
+//! A [`HedgedProvider`] sends a request to its provider and, if no answer has
+//! arrived after a delay, sends the same request again, to the same provider
+//! or to a hedge provider. Whichever answers first is returned and the other
+//! request is dropped, which cancels it. A delay around the provider's 95th
+//! percentile latency hedges about one request in twenty while cutting off
+//! the slowest ones.
+//!
+//! ```rust
+//! # use pocketflow_rs::llm::{ClaudeProvider, HedgedProvider, OllamaProvider};
+//! # use std::time::Duration;
+//! let provider = HedgedProvider::new(ClaudeProvider::new("sk-ant-..."), Duration::from_secs(4))
+//!     .with_hedge_provider(OllamaProvider::new());
+//! ```
+
+use super::{ChatRequest, ChatResponse, EmbeddingRequest, LlmError, LlmProvider, TokenStream};
+use async_trait::async_trait;
+use futures::future::{Either, select};
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// [`LlmProvider`] sending a second request when the first is slow
+///
+/// A request failing before the delay is not hedged; put an
+/// [`LlmRouter`](super::LlmRouter) inside for fallback on errors. When both
+/// requests are in flight and one fails, the other is awaited.
+pub struct HedgedProvider {
+    primary: Arc<dyn LlmProvider>,
+    hedge: Option<Arc<dyn LlmProvider>>,
+    delay: Duration,
+    hedged: AtomicUsize,
+}
+
+impl std::fmt::Debug for HedgedProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HedgedProvider")
+            .field("primary", &self.primary.name())
+            .field("hedge", &self.hedge.as_ref().map(|p| p.name()))
+            .field("delay", &self.delay)
+            .finish()
+    }
+}
+
+impl HedgedProvider {
+    /// Hedge requests to a provider after `delay`
+    pub fn new(primary: impl LlmProvider + 'static, delay: Duration) -> Self {
+        Self::from_shared(Arc::new(primary), delay)
+    }
+
+    /// Hedge requests to a shared provider after `delay`
+    pub fn from_shared(primary: Arc<dyn LlmProvider>, delay: Duration) -> Self {
+        Self {
+            primary,
+            hedge: None,
+            delay,
+            hedged: AtomicUsize::new(0),
+        }
+    }
+
+    /// Send hedge requests to another provider instead of the primary
+    pub fn with_hedge_provider(self, hedge: impl LlmProvider + 'static) -> Self {
+        self.with_shared_hedge_provider(Arc::new(hedge))
+    }
+
+    /// Send hedge requests to another shared provider instead of the primary
+    pub fn with_shared_hedge_provider(mut self, hedge: Arc<dyn LlmProvider>) -> Self {
+        self.hedge = Some(hedge);
+        self
+    }
+
+    /// Number of hedge requests sent so far
+    pub fn hedged_requests(&self) -> usize {
+        self.hedged.load(Ordering::Relaxed)
+    }
+
+    /// Run `call` on the primary, and on the hedge provider too if the
+    /// primary hasn't answered after the delay
+    async fn hedge<T, F, Fut>(&self, call: F) -> Result<T, LlmError>
+    where
+        F: Fn(Arc<dyn LlmProvider>) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, LlmError>> + Send,
+    {
+        let mut primary = Box::pin(call(self.primary.clone()));
+        tokio::select! {
+            result = &mut primary => return result,
+            _ = tokio::time::sleep(self.delay) => {}
+        }
+
+        self.hedged.fetch_add(1, Ordering::Relaxed);
+        let hedge = Box::pin(call(
+            self.hedge.clone().unwrap_or_else(|| self.primary.clone()),
+        ));
+        let (result, other) = match select(primary, hedge).await {
+            Either::Left(first) | Either::Right(first) => first,
+        };
+        match result {
+            Ok(value) => Ok(value),
+            Err(_) => other.await,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for HedgedProvider {
+    fn name(&self) -> &str {
+        self.primary.name()
+    }
+
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+        self.hedge(|provider| {
+            let request = request.clone();
+            async move { provider.chat(request).await }
+        })
+        .await
+    }
+
+    /// Hedges opening the stream; the stream opened first is returned
+    async fn chat_stream(&self, request: ChatRequest) -> Result<TokenStream, LlmError> {
+        self.hedge(|provider| {
+            let request = request.clone();
+            async move { provider.chat_stream(request).await }
+        })
+        .await
+    }
+
+    async fn embeddings(&self, request: EmbeddingRequest) -> Result<Vec<Vec<f32>>, LlmError> {
+        self.hedge(|provider| {
+            let request = request.clone();
+            async move { provider.embeddings(request).await }
+        })
+        .await
+    }
+
+    async fn ping(&self) -> Result<(), LlmError> {
+        self.hedge(|provider| async move { provider.ping().await })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ChatMessage;
+
+    /// Answers after `latency`, counting calls that ran to completion
+    struct SlowProvider {
+        name: &'static str,
+        latency: Duration,
+        completed: AtomicUsize,
+    }
+
+    impl SlowProvider {
+        fn new(name: &'static str, latency_ms: u64) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                latency: Duration::from_millis(latency_ms),
+                completed: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for SlowProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn chat(&self, _request: ChatRequest) -> Result<ChatResponse, LlmError> {
+            tokio::time::sleep(self.latency).await;
+            self.completed.fetch_add(1, Ordering::SeqCst);
+            Ok(ChatResponse::new("hi", self.name))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hedged_provider_takes_first_answer() {
+        let request = || ChatRequest::new(vec![ChatMessage::user("Hi")]);
+        let primary = SlowProvider::new("primary", 1000);
+        let backup = SlowProvider::new("backup", 50);
+        let provider = HedgedProvider::from_shared(primary.clone(), Duration::from_millis(100))
+            .with_shared_hedge_provider(backup.clone());
+
+        // The hedge answers first and the primary request is canceled
+        assert_eq!(provider.chat(request()).await.unwrap().provider, "backup");
+        assert_eq!(provider.hedged_requests(), 1);
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(primary.completed.load(Ordering::SeqCst), 0);
+
+        // A fast primary isn't hedged
+        let fast = SlowProvider::new("fast", 10);
+        let provider = HedgedProvider::from_shared(fast.clone(), Duration::from_millis(100))
+            .with_shared_hedge_provider(backup.clone());
+        assert_eq!(provider.chat(request()).await.unwrap().provider, "fast");
+        assert_eq!(provider.hedged_requests(), 0);
+        assert_eq!(backup.completed.load(Ordering::SeqCst), 1);
+    }
+}
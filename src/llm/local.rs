@@ -0,0 +1,474 @@
+//! In-process inference on GGUF models with candle
+//!
+//! [`LocalLlmProvider`] loads a quantized llama-family model (Llama, Mistral,
+//! Qwen and others in GGUF format) and its `tokenizer.json` on first use and
+//! generates in the calling process, so flows run fully offline without an
+//! Ollama daemon. Generation runs on a blocking thread; requests to the same
+//! provider (and its clones) share the loaded model and are served one at a
+//! time.
+//!
+//! ```rust,no_run
+//! # use pocketflow_rs::llm::{ChatMessage, ChatRequest, ChatTemplate, LlmProvider, LocalLlmProvider};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let provider = LocalLlmProvider::new("models/qwen2.5-0.5b-instruct-q4_k_m.gguf", "models/tokenizer.json")
+//!     .with_template(ChatTemplate::ChatMl)
+//!     .with_stop("\n\n");
+//! let response = provider
+//!     .chat(ChatRequest::new(vec![ChatMessage::user("Name a prime number")]).with_max_tokens(16))
+//!     .await?;
+//! println!("{}", response.content);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{ChatMessage, ChatRequest, ChatResponse, LlmError, LlmProvider, Role, TokenStream};
+use async_trait::async_trait;
+use candle_core::quantized::gguf_file;
+use candle_core::{Device, Tensor};
+use candle_transformers::generation::LogitsProcessor;
+use candle_transformers::models::quantized_llama::ModelWeights;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokenizers::Tokenizer;
+use tokio::sync::mpsc;
+
+/// Tokens ending a reply in common chat formats
+const END_TOKENS: &[&str] = &[
+    "</s>",
+    "<|im_end|>",
+    "<|eot_id|>",
+    "<|end_of_text|>",
+    "<|endoftext|>",
+];
+
+/// How a conversation is laid out as a prompt for the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChatTemplate {
+    /// `<|im_start|>role ... <|im_end|>`, used by Qwen and many fine-tunes
+    #[default]
+    ChatMl,
+    /// Llama 3 header tokens
+    Llama3,
+    /// `User: ...` lines, for base models
+    Plain,
+}
+
+impl ChatTemplate {
+    /// Lay out `messages`, ending where the assistant's reply starts
+    pub fn render(&self, messages: &[ChatMessage]) -> String {
+        let role = |role: Role| match role {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        };
+        let mut prompt = String::new();
+        for message in messages {
+            prompt.push_str(&match self {
+                ChatTemplate::ChatMl => format!(
+                    "<|im_start|>{}\n{}<|im_end|>\n",
+                    role(message.role),
+                    message.content
+                ),
+                ChatTemplate::Llama3 => format!(
+                    "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+                    role(message.role),
+                    message.content
+                ),
+                ChatTemplate::Plain => {
+                    let name = role(message.role);
+                    format!(
+                        "{}{}: {}\n",
+                        name[..1].to_uppercase(),
+                        &name[1..],
+                        message.content
+                    )
+                }
+            });
+        }
+        prompt.push_str(match self {
+            ChatTemplate::ChatMl => "<|im_start|>assistant\n",
+            ChatTemplate::Llama3 => "<|start_header_id|>assistant<|end_header_id|>\n\n",
+            ChatTemplate::Plain => "Assistant:",
+        });
+        prompt
+    }
+}
+
+/// Sampling settings of one generation
+#[derive(Debug, Clone)]
+struct Generation {
+    prompt: String,
+    max_tokens: usize,
+    temperature: Option<f64>,
+    top_p: Option<f64>,
+    seed: u64,
+    repeat_penalty: f32,
+    stop: Vec<String>,
+}
+
+/// What a generation produced
+struct Generated {
+    text: String,
+    prompt_tokens: usize,
+    tokens: usize,
+    finish_reason: &'static str,
+}
+
+struct LoadedModel {
+    weights: ModelWeights,
+    tokenizer: Tokenizer,
+    end_tokens: Vec<u32>,
+}
+
+/// [`LlmProvider`] running a GGUF model in-process
+///
+/// The request's model name and response format are ignored; embeddings are
+/// unsupported.
+#[derive(Clone)]
+pub struct LocalLlmProvider {
+    name: String,
+    model_path: PathBuf,
+    tokenizer_path: PathBuf,
+    device: Device,
+    template: ChatTemplate,
+    max_tokens: u32,
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    seed: u64,
+    repeat_penalty: f32,
+    stop: Vec<String>,
+    model: Arc<Mutex<Option<LoadedModel>>>,
+}
+
+impl std::fmt::Debug for LocalLlmProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalLlmProvider")
+            .field("name", &self.name)
+            .field("model_path", &self.model_path)
+            .field("tokenizer_path", &self.tokenizer_path)
+            .field("template", &self.template)
+            .field("stop", &self.stop)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LocalLlmProvider {
+    /// Create a provider for a GGUF model file and its `tokenizer.json`,
+    /// running on the CPU
+    pub fn new(model_path: impl Into<PathBuf>, tokenizer_path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: "local".to_string(),
+            model_path: model_path.into(),
+            tokenizer_path: tokenizer_path.into(),
+            device: Device::Cpu,
+            template: ChatTemplate::default(),
+            max_tokens: 512,
+            temperature: Some(0.8),
+            top_p: None,
+            seed: 42,
+            repeat_penalty: 1.1,
+            stop: Vec::new(),
+            model: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Set the provider name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Run on another device, e.g. a CUDA or Metal GPU when candle is built
+    /// with support for it
+    pub fn with_device(mut self, device: Device) -> Self {
+        self.device = device;
+        self
+    }
+
+    /// Set the chat template
+    pub fn with_template(mut self, template: ChatTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Set the default maximum tokens to generate
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Set the default temperature; 0 always picks the likeliest token
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set the default top-p
+    pub fn with_top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Set the default sampling seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Set the penalty for repeating recent tokens, 1.0 for none
+    pub fn with_repeat_penalty(mut self, penalty: f32) -> Self {
+        self.repeat_penalty = penalty;
+        self
+    }
+
+    /// End replies at `stop`, which is not included in them
+    pub fn with_stop(mut self, stop: impl Into<String>) -> Self {
+        self.stop.push(stop.into());
+        self
+    }
+
+    /// The model file
+    pub fn model_path(&self) -> &Path {
+        &self.model_path
+    }
+
+    fn generation(&self, request: &ChatRequest) -> Generation {
+        Generation {
+            prompt: self.template.render(&request.messages),
+            max_tokens: request.max_tokens.unwrap_or(self.max_tokens) as usize,
+            temperature: request.temperature.or(self.temperature).map(f64::from),
+            top_p: request.top_p.or(self.top_p).map(f64::from),
+            seed: request.seed.map_or(self.seed, |seed| seed as u64),
+            repeat_penalty: self.repeat_penalty,
+            stop: self.stop.clone(),
+        }
+    }
+
+    fn load(&self) -> Result<LoadedModel, LlmError> {
+        let invalid = |path: &Path, error: String| {
+            LlmError::InvalidRequest(format!("Cannot load {}: {}", path.display(), error))
+        };
+        let mut file = std::fs::File::open(&self.model_path)
+            .map_err(|e| invalid(&self.model_path, e.to_string()))?;
+        let content = gguf_file::Content::read(&mut file)
+            .map_err(|e| invalid(&self.model_path, e.to_string()))?;
+        let weights = ModelWeights::from_gguf(content, &mut file, &self.device)
+            .map_err(|e| invalid(&self.model_path, e.to_string()))?;
+        let tokenizer = Tokenizer::from_file(&self.tokenizer_path)
+            .map_err(|e| invalid(&self.tokenizer_path, e.to_string()))?;
+        let end_tokens = END_TOKENS
+            .iter()
+            .filter_map(|token| tokenizer.token_to_id(token))
+            .collect();
+        Ok(LoadedModel {
+            weights,
+            tokenizer,
+            end_tokens,
+        })
+    }
+
+    /// Generate on the current thread, passing each piece of text to
+    /// `on_text` until it returns `false`
+    fn generate(
+        &self,
+        generation: Generation,
+        mut on_text: impl FnMut(&str) -> bool,
+    ) -> Result<Generated, LlmError> {
+        let mut guard = self
+            .model
+            .lock()
+            .map_err(|_| LlmError::Request("Local model poisoned by a panic".to_string()))?;
+        if guard.is_none() {
+            *guard = Some(self.load()?);
+        }
+        let model = guard.as_mut().expect("model was just loaded");
+        let failed =
+            |error: candle_core::Error| LlmError::Request(format!("Inference failed: {}", error));
+
+        let prompt = model
+            .tokenizer
+            .encode(generation.prompt.as_str(), true)
+            .map_err(|e| LlmError::InvalidRequest(format!("Cannot tokenize prompt: {}", e)))?
+            .get_ids()
+            .to_vec();
+        let mut sampler =
+            LogitsProcessor::new(generation.seed, generation.temperature, generation.top_p);
+        let mut context = prompt.clone();
+        let mut generated: Vec<u32> = Vec::new();
+        let mut emitted = 0;
+        let mut text = String::new();
+        let mut finish_reason = "length";
+        while generated.len() < generation.max_tokens {
+            let input = Tensor::new(context.as_slice(), &self.device)
+                .and_then(|input| input.unsqueeze(0))
+                .map_err(failed)?;
+            let position = prompt.len() + generated.len() - context.len();
+            let mut logits = model
+                .weights
+                .forward(&input, position)
+                .and_then(|logits| logits.squeeze(0))
+                .map_err(failed)?;
+            if generation.repeat_penalty != 1.0 {
+                let recent = &generated[generated.len().saturating_sub(64)..];
+                logits = candle_transformers::utils::apply_repeat_penalty(
+                    &logits,
+                    generation.repeat_penalty,
+                    recent,
+                )
+                .map_err(failed)?;
+            }
+            let token = sampler.sample(&logits).map_err(failed)?;
+            if model.end_tokens.contains(&token) {
+                finish_reason = "stop";
+                break;
+            }
+            generated.push(token);
+            context = vec![token];
+
+            text = model
+                .tokenizer
+                .decode(&generated, true)
+                .map_err(|e| LlmError::Request(format!("Cannot decode tokens: {}", e)))?;
+            let (end, stopped) = split_stop(&text, &generation.stop);
+            if end > emitted && !on_text(&text[emitted..end]) {
+                break;
+            }
+            emitted = emitted.max(end);
+            if stopped {
+                finish_reason = "stop";
+                break;
+            }
+        }
+        // Pass on what was held back in case a stop sequence followed
+        let end = generation
+            .stop
+            .iter()
+            .filter_map(|stop| text.find(stop.as_str()))
+            .min()
+            .unwrap_or(text.len());
+        if end > emitted {
+            on_text(&text[emitted..end]);
+        }
+        text.truncate(end);
+        Ok(Generated {
+            text,
+            prompt_tokens: prompt.len(),
+            tokens: generated.len(),
+            finish_reason,
+        })
+    }
+}
+
+/// How much of `text` can be passed on, and whether it contains a stop
+/// sequence
+///
+/// Text from the first stop sequence on is cut, and so is an end that could
+/// still turn into one, as is an incomplete character.
+fn split_stop(text: &str, stop: &[String]) -> (usize, bool) {
+    if let Some(start) = stop
+        .iter()
+        .filter_map(|stop| text.find(stop.as_str()))
+        .min()
+    {
+        return (start, true);
+    }
+    let mut end = text.len();
+    for stop in stop {
+        for (index, _) in stop.char_indices().skip(1) {
+            if text.ends_with(&stop[..index]) {
+                end = end.min(text.len() - index);
+            }
+        }
+    }
+    if text[..end].ends_with(char::REPLACEMENT_CHARACTER) {
+        end -= char::REPLACEMENT_CHARACTER.len_utf8();
+    }
+    (end, false)
+}
+
+#[async_trait]
+impl LlmProvider for LocalLlmProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+        let generation = self.generation(&request);
+        let provider = self.clone();
+        let generated =
+            tokio::task::spawn_blocking(move || provider.generate(generation, |_| true))
+                .await
+                .map_err(|e| LlmError::Request(format!("Inference task failed: {}", e)))??;
+        Ok(ChatResponse {
+            content: generated.text,
+            provider: self.name.clone(),
+            model: self
+                .model_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned()),
+            finish_reason: Some(generated.finish_reason.to_string()),
+            usage: super::request_usage(
+                Some(generated.prompt_tokens as u64),
+                Some(generated.tokens as u64),
+            ),
+            ..ChatResponse::default()
+        })
+    }
+
+    /// Streams text as it is generated; dropping the stream stops generation
+    async fn chat_stream(&self, request: ChatRequest) -> Result<TokenStream, LlmError> {
+        let generation = self.generation(&request);
+        let provider = self.clone();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || {
+            let result =
+                provider.generate(generation, |text| sender.send(Ok(text.to_string())).is_ok());
+            if let Err(error) = result {
+                let _ = sender.send(Err(error));
+            }
+        });
+        Ok(Box::pin(futures::stream::unfold(
+            receiver,
+            |mut receiver| async move { receiver.recv().await.map(|item| (item, receiver)) },
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_local_provider() {
+        let stop = vec!["</answer>".to_string()];
+        assert_eq!(split_stop("42</answer> more", &stop), (2, true));
+        // A possible start of a stop sequence is held back
+        assert_eq!(split_stop("42</ans", &stop), (2, false));
+        assert_eq!(split_stop("42 <", &[]), (4, false));
+        assert_eq!(split_stop("caf\u{FFFD}", &[]), (3, false));
+
+        let messages = [ChatMessage::system("Be brief"), ChatMessage::user("Hi")];
+        assert_eq!(
+            ChatTemplate::ChatMl.render(&messages),
+            "<|im_start|>system\nBe brief<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n<|im_start|>assistant\n"
+        );
+        assert_eq!(
+            ChatTemplate::Plain.render(&messages),
+            "System: Be brief\nUser: Hi\nAssistant:"
+        );
+
+        let provider = LocalLlmProvider::new("missing/model.gguf", "missing/tokenizer.json");
+        let request = ChatRequest::new(vec![ChatMessage::user("Hi")]);
+        let error = provider.chat(request.clone()).await.unwrap_err();
+        assert!(
+            matches!(&error, LlmError::InvalidRequest(message) if message.contains("model.gguf"))
+        );
+        let mut stream = provider.chat_stream(request).await.unwrap();
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(LlmError::InvalidRequest(_)))
+        ));
+    }
+}
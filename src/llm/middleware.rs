@@ -0,0 +1,604 @@
+//! Request/response middleware for LLM calls
+//!
+//! A [`MiddlewareProvider`] wraps an [`LlmProvider`] with a chain of
+//! [`LlmMiddleware`] hooks. Requests pass through the chain in order before
+//! reaching the provider, and responses pass back through it in reverse, so
+//! the first middleware added sees the original request and the final
+//! response. A middleware may also answer a request itself, skipping the
+//! provider and every later middleware.
+//!
+//! Bundled middleware:
+//!
+//! - [`RewriteMiddleware`]: edit requests with a closure (e.g. inject tenant context)
+//! - [`RedactionMiddleware`]: mask emails, long numbers and custom terms before sending
+//! - [`JournalMiddleware`]: record request/response pairs in an [`LlmJournal`],
+//!   optionally scrubbed by a [`Redactor`]
+//! - [`CacheMiddleware`]: answer repeated requests from memory
+//! - [`SemanticCache`](super::SemanticCache): answer similar requests using embeddings
+//!
+//! ```rust
+//! # use pocketflow_rs::llm::middleware::{CacheMiddleware, JournalMiddleware, LlmJournal, MiddlewareProvider, RedactionMiddleware};
+//! # use pocketflow_rs::llm::OllamaProvider;
+//! let journal = LlmJournal::new();
+//! let provider = MiddlewareProvider::new(OllamaProvider::new())
+//!     .with_middleware(RedactionMiddleware::pii())
+//!     .with_middleware(JournalMiddleware::new(journal.clone()))
+//!     .with_middleware(CacheMiddleware::new());
+//! ```
+
+use super::{ChatRequest, ChatResponse, EmbeddingRequest, LlmError, LlmProvider, TokenStream};
+use crate::redact::Redactor;
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Hooks around a chat request
+///
+/// All hooks default to doing nothing.
+#[async_trait]
+pub trait LlmMiddleware: Send + Sync {
+    /// Inspect or rewrite a request before it is sent
+    ///
+    /// Returning a response short-circuits the call: the provider and later
+    /// middleware are skipped, and earlier middleware see the response in
+    /// [`LlmMiddleware::after_response`].
+    async fn before_request(
+        &self,
+        request: &mut ChatRequest,
+    ) -> Result<Option<ChatResponse>, LlmError> {
+        let _ = request;
+        Ok(None)
+    }
+
+    /// Inspect or rewrite a response
+    ///
+    /// For streamed calls this runs once the stream ends, on the accumulated
+    /// text; changes to the response are not seen by the consumer.
+    async fn after_response(
+        &self,
+        request: &ChatRequest,
+        response: &mut ChatResponse,
+    ) -> Result<(), LlmError> {
+        let _ = (request, response);
+        Ok(())
+    }
+
+    /// Observe a failed call
+    async fn on_error(&self, request: &ChatRequest, error: &LlmError) {
+        let _ = (request, error);
+    }
+}
+
+/// [`LlmProvider`] running a middleware chain around another provider
+///
+/// Embedding requests are passed straight to the inner provider.
+pub struct MiddlewareProvider {
+    inner: Arc<dyn LlmProvider>,
+    middleware: Vec<Arc<dyn LlmMiddleware>>,
+}
+
+impl std::fmt::Debug for MiddlewareProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MiddlewareProvider")
+            .field("inner", &self.inner.name())
+            .field("middleware", &self.middleware.len())
+            .finish()
+    }
+}
+
+impl MiddlewareProvider {
+    /// Wrap a provider
+    pub fn new(inner: impl LlmProvider + 'static) -> Self {
+        Self::from_shared(Arc::new(inner))
+    }
+
+    /// Wrap a shared provider
+    pub fn from_shared(inner: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            inner,
+            middleware: Vec::new(),
+        }
+    }
+
+    /// Append a middleware to the chain
+    pub fn with_middleware(mut self, middleware: impl LlmMiddleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Append a shared middleware to the chain
+    pub fn with_shared_middleware(mut self, middleware: Arc<dyn LlmMiddleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Run `before_request` hooks, returning how many ran and any short-circuit response
+    async fn before(
+        &self,
+        request: &mut ChatRequest,
+    ) -> Result<(usize, Option<ChatResponse>), LlmError> {
+        for (index, middleware) in self.middleware.iter().enumerate() {
+            match middleware.before_request(request).await {
+                Ok(None) => {}
+                Ok(Some(response)) => return Ok((index, Some(response))),
+                Err(error) => {
+                    on_error(&self.middleware[..index], request, &error).await;
+                    return Err(error);
+                }
+            }
+        }
+        Ok((self.middleware.len(), None))
+    }
+}
+
+/// Run `after_response` hooks in reverse order
+async fn after(
+    middleware: &[Arc<dyn LlmMiddleware>],
+    request: &ChatRequest,
+    response: &mut ChatResponse,
+) -> Result<(), LlmError> {
+    for middleware in middleware.iter().rev() {
+        middleware.after_response(request, response).await?;
+    }
+    Ok(())
+}
+
+/// Run `on_error` hooks in reverse order
+async fn on_error(middleware: &[Arc<dyn LlmMiddleware>], request: &ChatRequest, error: &LlmError) {
+    for middleware in middleware.iter().rev() {
+        middleware.on_error(request, error).await;
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MiddlewareProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn chat(&self, mut request: ChatRequest) -> Result<ChatResponse, LlmError> {
+        let (ran, short_circuit) = self.before(&mut request).await?;
+        let chain = &self.middleware[..ran];
+        let mut response = match short_circuit {
+            Some(response) => response,
+            None => match self.inner.chat(request.clone()).await {
+                Ok(response) => response,
+                Err(error) => {
+                    on_error(chain, &request, &error).await;
+                    return Err(error);
+                }
+            },
+        };
+        after(chain, &request, &mut response).await?;
+        Ok(response)
+    }
+
+    async fn chat_stream(&self, mut request: ChatRequest) -> Result<TokenStream, LlmError> {
+        let (ran, short_circuit) = self.before(&mut request).await?;
+        let chain = self.middleware[..ran].to_vec();
+        if let Some(mut response) = short_circuit {
+            after(&chain, &request, &mut response).await?;
+            return Ok(Box::pin(futures::stream::iter([Ok(response.content)])));
+        }
+
+        let stream = match self.inner.chat_stream(request.clone()).await {
+            Ok(stream) => stream,
+            Err(error) => {
+                on_error(&chain, &request, &error).await;
+                return Err(error);
+            }
+        };
+
+        struct State {
+            stream: Option<TokenStream>,
+            content: String,
+            chain: Vec<Arc<dyn LlmMiddleware>>,
+            request: ChatRequest,
+            provider: String,
+        }
+
+        let state = State {
+            stream: Some(stream),
+            content: String::new(),
+            chain,
+            request,
+            provider: self.inner.name().to_string(),
+        };
+        Ok(Box::pin(futures::stream::unfold(
+            state,
+            |mut state| async move {
+                let item = state.stream.as_mut()?.next().await;
+                match item {
+                    Some(Ok(delta)) => {
+                        state.content.push_str(&delta);
+                        Some((Ok(delta), state))
+                    }
+                    Some(Err(error)) => {
+                        state.stream = None;
+                        on_error(&state.chain, &state.request, &error).await;
+                        Some((Err(error), state))
+                    }
+                    None => {
+                        state.stream = None;
+                        let mut response = ChatResponse::new(
+                            std::mem::take(&mut state.content),
+                            state.provider.clone(),
+                        );
+                        match after(&state.chain, &state.request, &mut response).await {
+                            Ok(()) => None,
+                            Err(error) => Some((Err(error), state)),
+                        }
+                    }
+                }
+            },
+        )))
+    }
+
+    async fn embeddings(&self, request: EmbeddingRequest) -> Result<Vec<Vec<f32>>, LlmError> {
+        self.inner.embeddings(request).await
+    }
+
+    async fn ping(&self) -> Result<(), LlmError> {
+        self.inner.ping().await
+    }
+}
+
+/// Middleware rewriting requests with a closure
+pub struct RewriteMiddleware {
+    rewrite: Box<dyn Fn(&mut ChatRequest) + Send + Sync>,
+}
+
+impl RewriteMiddleware {
+    /// Create a middleware applying `rewrite` to every request
+    pub fn new<F>(rewrite: F) -> Self
+    where
+        F: Fn(&mut ChatRequest) + Send + Sync + 'static,
+    {
+        Self {
+            rewrite: Box::new(rewrite),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmMiddleware for RewriteMiddleware {
+    async fn before_request(
+        &self,
+        request: &mut ChatRequest,
+    ) -> Result<Option<ChatResponse>, LlmError> {
+        (self.rewrite)(request);
+        Ok(None)
+    }
+}
+
+/// Middleware masking sensitive text in message contents before sending
+#[derive(Debug, Clone, Default)]
+pub struct RedactionMiddleware {
+    redactor: Redactor,
+}
+
+impl RedactionMiddleware {
+    /// Create a middleware that redacts nothing until configured
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redact email addresses and numbers of 7 or more digits (phone, card and
+    /// account numbers)
+    pub fn pii() -> Self {
+        Redactor::pii().into()
+    }
+
+    /// Redact email addresses
+    pub fn with_emails(mut self) -> Self {
+        self.redactor = self.redactor.with_emails();
+        self
+    }
+
+    /// Redact runs of at least `min_digits` digits, allowing single spaces or
+    /// dashes between them
+    pub fn with_numbers(mut self, min_digits: usize) -> Self {
+        self.redactor = self.redactor.with_numbers(min_digits);
+        self
+    }
+
+    /// Redact every occurrence of a literal term
+    pub fn with_term(mut self, term: impl Into<String>) -> Self {
+        self.redactor = self.redactor.with_term(term);
+        self
+    }
+
+    /// Set the text substituted for redacted spans (default: `[REDACTED]`)
+    pub fn with_replacement(mut self, replacement: impl Into<String>) -> Self {
+        self.redactor = self.redactor.with_replacement(replacement);
+        self
+    }
+
+    /// Apply the configured redactions to a text
+    pub fn redact(&self, text: &str) -> String {
+        self.redactor.redact(text)
+    }
+}
+
+impl From<Redactor> for RedactionMiddleware {
+    fn from(redactor: Redactor) -> Self {
+        Self { redactor }
+    }
+}
+
+#[async_trait]
+impl LlmMiddleware for RedactionMiddleware {
+    async fn before_request(
+        &self,
+        request: &mut ChatRequest,
+    ) -> Result<Option<ChatResponse>, LlmError> {
+        for message in &mut request.messages {
+            message.content = self.redact(&message.content);
+        }
+        Ok(None)
+    }
+}
+
+/// One recorded LLM call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmExchange {
+    /// Request as sent (after earlier middleware ran)
+    pub request: ChatRequest,
+    /// Response, if the call succeeded
+    pub response: Option<ChatResponse>,
+    /// Error message, if the call failed
+    pub error: Option<String>,
+}
+
+/// Shared, append-only record of LLM calls
+///
+/// Clones share the same entries.
+#[derive(Debug, Clone, Default)]
+pub struct LlmJournal {
+    entries: Arc<Mutex<Vec<LlmExchange>>>,
+}
+
+impl LlmJournal {
+    /// Create an empty journal
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an exchange
+    pub fn record(&self, exchange: LlmExchange) {
+        self.lock().push(exchange);
+    }
+
+    /// Copy of all recorded exchanges, oldest first
+    pub fn entries(&self) -> Vec<LlmExchange> {
+        self.lock().clone()
+    }
+
+    /// Number of recorded exchanges
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether nothing has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Remove and return all recorded exchanges
+    pub fn drain(&self) -> Vec<LlmExchange> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<LlmExchange>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Middleware recording every request/response pair in an [`LlmJournal`]
+#[derive(Debug, Clone)]
+pub struct JournalMiddleware {
+    journal: LlmJournal,
+    redactor: Option<Redactor>,
+}
+
+impl JournalMiddleware {
+    /// Record calls into a journal
+    pub fn new(journal: LlmJournal) -> Self {
+        Self {
+            journal,
+            redactor: None,
+        }
+    }
+
+    /// Scrub message and response contents before they are recorded
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    fn record(&self, mut exchange: LlmExchange) {
+        if let Some(redactor) = &self.redactor {
+            for message in &mut exchange.request.messages {
+                message.content = redactor.redact(&message.content);
+            }
+            if let Some(response) = &mut exchange.response {
+                response.content = redactor.redact(&response.content);
+            }
+            exchange.error = exchange.error.map(|error| redactor.redact(&error));
+        }
+        self.journal.record(exchange);
+    }
+}
+
+#[async_trait]
+impl LlmMiddleware for JournalMiddleware {
+    async fn after_response(
+        &self,
+        request: &ChatRequest,
+        response: &mut ChatResponse,
+    ) -> Result<(), LlmError> {
+        self.record(LlmExchange {
+            request: request.clone(),
+            response: Some(response.clone()),
+            error: None,
+        });
+        Ok(())
+    }
+
+    async fn on_error(&self, request: &ChatRequest, error: &LlmError) {
+        self.record(LlmExchange {
+            request: request.clone(),
+            response: None,
+            error: Some(error.to_string()),
+        });
+    }
+}
+
+/// Middleware answering repeated requests from an in-memory cache
+///
+/// Requests are keyed by their full contents, including model and sampling
+/// options. Clones share the same cache.
+#[derive(Debug, Clone, Default)]
+pub struct CacheMiddleware {
+    entries: Arc<Mutex<HashMap<String, ChatResponse>>>,
+}
+
+impl CacheMiddleware {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of cached responses
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Remove all cached responses
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn key(request: &ChatRequest) -> String {
+        serde_json::to_string(request).unwrap_or_default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, ChatResponse>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl LlmMiddleware for CacheMiddleware {
+    async fn before_request(
+        &self,
+        request: &mut ChatRequest,
+    ) -> Result<Option<ChatResponse>, LlmError> {
+        Ok(self.lock().get(&Self::key(request)).cloned())
+    }
+
+    async fn after_response(
+        &self,
+        request: &ChatRequest,
+        response: &mut ChatResponse,
+    ) -> Result<(), LlmError> {
+        self.lock().insert(Self::key(request), response.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ChatMessage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Echoes the last message, counting calls
+    #[derive(Default)]
+    struct EchoProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmProvider for EchoProvider {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let last = request.messages.last().unwrap();
+            if last.content == "fail" {
+                return Err(LlmError::Request("boom".to_string()));
+            }
+            Ok(ChatResponse::new(last.content.clone(), "echo"))
+        }
+    }
+
+    fn request(text: &str) -> ChatRequest {
+        ChatRequest::new(vec![ChatMessage::user(text)])
+    }
+
+    #[tokio::test]
+    async fn test_middleware_chain() {
+        let echo = Arc::new(EchoProvider::default());
+        let journal = LlmJournal::new();
+        let cache = CacheMiddleware::new();
+        let provider = MiddlewareProvider::from_shared(echo.clone())
+            .with_middleware(RewriteMiddleware::new(|request| {
+                request
+                    .messages
+                    .insert(0, ChatMessage::system("tenant: acme"))
+            }))
+            .with_middleware(RedactionMiddleware::pii().with_term("Project X"))
+            .with_middleware(JournalMiddleware::new(journal.clone()))
+            .with_middleware(cache.clone());
+
+        let text = "Mail jane.doe@example.com or call 555-123-4567 about Project X.";
+        let response = provider.chat(request(text)).await.unwrap();
+        assert_eq!(
+            response.content,
+            "Mail [REDACTED] or call [REDACTED] about [REDACTED]."
+        );
+
+        // The second identical call is served from the cache
+        provider.chat(request(text)).await.unwrap();
+        assert_eq!(echo.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.len(), 1);
+
+        // Streams go through the same chain
+        let mut stream = provider.chat_stream(request("order 12")).await.unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap(), "order 12");
+        assert!(stream.next().await.is_none());
+
+        assert!(provider.chat(request("fail")).await.is_err());
+
+        let entries = journal.entries();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].request.messages[0].content, "tenant: acme");
+        assert_eq!(entries[2].response.as_ref().unwrap().content, "order 12");
+        assert_eq!(
+            entries[3].error.as_deref(),
+            Some("API request failed: boom")
+        );
+    }
+
+    #[test]
+    fn test_redaction() {
+        let redaction = RedactionMiddleware::pii();
+        assert_eq!(redaction.redact("a@b.co."), "[REDACTED].");
+        assert_eq!(redaction.redact("@home, a@b"), "@home, a@b");
+        assert_eq!(
+            redaction.redact("room 42, 1234 5678"),
+            "room 42, [REDACTED]"
+        );
+        assert_eq!(redaction.redact("no pii"), "no pii");
+    }
+}
@@ -0,0 +1,642 @@
+//! Provider-agnostic LLM access
+//!
+//! [`LlmProvider`] abstracts chat completion, streaming and embeddings over
+//! the supported backends:
+//!
+//! - [`OpenAiProvider`]: OpenAI and compatible APIs, including Azure OpenAI
+//! - [`ClaudeProvider`]: Anthropic Messages API
+//! - [`GeminiProvider`]: Google Generative Language API
+//! - [`OllamaProvider`]: local Ollama server
+//! - `LocalLlmProvider`: GGUF models run in-process with candle (feature:
+//!   `local-inference`)
+//!
+//! [`LlmRouter`] is itself a provider: it tries its providers in fallback
+//! order, skipping ones whose health check (a [`CircuitBreaker`] per
+//! provider) is open. LLM nodes take an `Arc<dyn LlmProvider>`, so a flow can
+//! switch backends without changing its nodes.
+//!
+//! [`HedgedProvider`] cuts tail latency by sending a second request when the
+//! first is slow and taking whichever answers first.
+//!
+//! [`MiddlewareProvider`] wraps any provider with a chain of [`LlmMiddleware`]
+//! hooks for prompt rewriting, redaction, journaling and caching;
+//! [`SemanticCache`] reuses responses to similar prompts using embeddings and
+//! a [`VectorStore`].
+//!
+//! With the `prompt-library` feature, [`prompts::PromptLibrary`] loads prompt
+//! templates from files with YAML front matter.
+//!
+//! ```rust,no_run
+//! # use pocketflow_rs::llm::{ChatMessage, ChatRequest, ClaudeProvider, LlmProvider, LlmRouter, OllamaProvider};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let router = LlmRouter::new()
+//!     .with_provider(ClaudeProvider::new("sk-ant-..."))
+//!     .with_provider(OllamaProvider::new());
+//!
+//! let response = router
+//!     .chat(ChatRequest::new(vec![ChatMessage::user("Hello!")]))
+//!     .await?;
+//! println!("{} answered: {}", response.provider, response.content);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`CircuitBreaker`]: crate::node::CircuitBreaker
+
+mod anthropic;
+pub mod context;
+mod gemini;
+mod hedge;
+#[cfg(feature = "local-inference")]
+mod local;
+pub mod middleware;
+mod ollama;
+mod openai;
+#[cfg(feature = "prompt-library")]
+pub mod prompts;
+mod router;
+pub mod semantic_cache;
+pub mod vector;
+
+pub use anthropic::ClaudeProvider;
+pub use gemini::GeminiProvider;
+pub use hedge::HedgedProvider;
+#[cfg(feature = "local-inference")]
+pub use local::{ChatTemplate, LocalLlmProvider};
+pub use middleware::{LlmMiddleware, MiddlewareProvider};
+pub use ollama::OllamaProvider;
+pub use openai::OpenAiProvider;
+#[cfg(feature = "prompt-library")]
+pub use prompts::{ModelHints, Prompt, PromptError, PromptLibrary};
+pub use router::LlmRouter;
+pub use semantic_cache::SemanticCache;
+pub use vector::{InMemoryVectorStore, VectorMatch, VectorStore, VectorStoreError};
+
+use crate::http::HttpClient;
+use crate::node::NodeError;
+use crate::shared_store::TokenUsage;
+use async_trait::async_trait;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::pin::Pin;
+
+/// Errors returned by [`LlmProvider`] implementations
+#[derive(Debug, thiserror::Error)]
+pub enum LlmError {
+    /// The request itself is invalid (bad input, unknown model, ...)
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    /// The provider refused the request (authentication, content policy, ...)
+    #[error("Request rejected: {0}")]
+    Rejected(String),
+    /// A transient failure (network, rate limit, server error)
+    #[error("API request failed: {0}")]
+    Request(String),
+    #[error("Request timeout")]
+    Timeout,
+    #[error("{provider} does not support {operation}")]
+    Unsupported {
+        provider: String,
+        operation: &'static str,
+    },
+    #[error("No LLM provider available: {0}")]
+    NoProviderAvailable(String),
+    /// The prompt doesn't fit the model's context window
+    #[error("Prompt of {tokens} tokens exceeds the context budget of {limit}")]
+    ContextOverflow { tokens: usize, limit: usize },
+}
+
+impl LlmError {
+    /// Whether retrying the same request may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            LlmError::Request(_) | LlmError::Timeout | LlmError::NoProviderAvailable(_)
+        )
+    }
+
+    /// Classify an HTTP error status; client errors other than timeouts and rate
+    /// limits are not retryable
+    pub(crate) fn from_status(status: u16, body: &str) -> Self {
+        let detail = serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|v| v.pointer("/error/message")?.as_str().map(str::to_string))
+            .unwrap_or_else(|| body.to_string());
+        let message = format!("({}) {}", status, detail);
+        match status {
+            400 | 404 | 413 | 422 => LlmError::InvalidRequest(message),
+            408 | 429 => LlmError::Request(message),
+            400..=499 => LlmError::Rejected(message),
+            _ => LlmError::Request(message),
+        }
+    }
+
+    pub(crate) fn transport(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            LlmError::Timeout
+        } else {
+            LlmError::Request(error.to_string())
+        }
+    }
+}
+
+impl From<crate::secrets::SecretError> for LlmError {
+    fn from(error: crate::secrets::SecretError) -> Self {
+        LlmError::Rejected(format!("API key unavailable: {}", error))
+    }
+}
+
+impl From<LlmError> for NodeError {
+    fn from(error: LlmError) -> Self {
+        match error {
+            LlmError::InvalidRequest(message) => NodeError::ValidationError(message),
+            error if error.is_retryable() => NodeError::ExecutionError(error.to_string()),
+            error => NodeError::Fatal(error.to_string()),
+        }
+    }
+}
+
+/// Author of a chat message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+/// A single chat message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+impl ChatMessage {
+    /// Create a message
+    pub fn new(role: Role, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+        }
+    }
+
+    /// Create a system message
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(Role::System, content)
+    }
+
+    /// Create a user message
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(Role::User, content)
+    }
+
+    /// Create an assistant message
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(Role::Assistant, content)
+    }
+
+    /// Parse the input convention used by LLM nodes: a prompt string, or an
+    /// array of `{"role", "content"}` objects (`model` is accepted for `assistant`)
+    pub fn from_value(input: &Value) -> Result<Vec<Self>, LlmError> {
+        let messages = match input {
+            Value::String(prompt) => vec![ChatMessage::user(prompt.clone())],
+            Value::Array(message_array) => message_array
+                .iter()
+                .map(|msg_value| {
+                    let role = msg_value
+                        .get("role")
+                        .and_then(|r| r.as_str())
+                        .ok_or_else(|| {
+                            LlmError::InvalidRequest("Message must have a 'role' field".to_string())
+                        })?;
+                    let content = msg_value
+                        .get("content")
+                        .and_then(|c| c.as_str())
+                        .ok_or_else(|| {
+                            LlmError::InvalidRequest(
+                                "Message must have a 'content' field".to_string(),
+                            )
+                        })?;
+                    let role = match role {
+                        "system" => Role::System,
+                        "user" => Role::User,
+                        "assistant" | "model" => Role::Assistant,
+                        _ => {
+                            return Err(LlmError::InvalidRequest(format!(
+                                "Unsupported message role: {}",
+                                role
+                            )));
+                        }
+                    };
+                    Ok(ChatMessage::new(role, content))
+                })
+                .collect::<Result<_, _>>()?,
+            _ => {
+                return Err(LlmError::InvalidRequest(
+                    "Input must be a string or array of messages".to_string(),
+                ));
+            }
+        };
+
+        if messages.is_empty() {
+            return Err(LlmError::InvalidRequest(
+                "No valid messages found in input".to_string(),
+            ));
+        }
+        Ok(messages)
+    }
+}
+
+/// Output format a chat request asks the model for
+///
+/// OpenAI-compatible APIs, Gemini and Ollama enforce it; Claude ignores it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Free text
+    Text,
+    /// Any JSON object
+    JsonObject,
+    /// JSON matching a schema
+    JsonSchema {
+        name: String,
+        schema: Value,
+        #[serde(default)]
+        strict: bool,
+    },
+}
+
+impl ResponseFormat {
+    /// Strict JSON output matching `schema`
+    pub fn json_schema(name: impl Into<String>, schema: Value) -> Self {
+        ResponseFormat::JsonSchema {
+            name: name.into(),
+            schema,
+            strict: true,
+        }
+    }
+
+    /// Whether replies are JSON
+    pub fn is_json(&self) -> bool {
+        !matches!(self, ResponseFormat::Text)
+    }
+
+    /// The schema replies must match, if any
+    pub fn schema(&self) -> Option<&Value> {
+        match self {
+            ResponseFormat::JsonSchema { schema, .. } => Some(schema),
+            _ => None,
+        }
+    }
+}
+
+/// A chat completion request
+///
+/// Unset sampling options fall back to the provider's configuration.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatRequest {
+    pub messages: Vec<ChatMessage>,
+    /// Model override
+    pub model: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    /// Output format to request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    /// Sampling seed, for providers that support reproducible sampling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// Preferred provider name, tried first by an [`LlmRouter`]
+    pub provider: Option<String>,
+    /// Correlation ID sent as the `X-Request-Id` header by the Anthropic,
+    /// Gemini and Ollama providers; ignored by caches
+    #[serde(skip)]
+    pub request_id: Option<String>,
+}
+
+impl ChatRequest {
+    /// Create a request for a conversation
+    pub fn new(messages: Vec<ChatMessage>) -> Self {
+        Self {
+            messages,
+            ..Default::default()
+        }
+    }
+
+    /// Set the model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Set maximum tokens to generate
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Set temperature
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set top-p
+    pub fn with_top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Ask for an output format
+    pub fn with_response_format(mut self, format: ResponseFormat) -> Self {
+        self.response_format = Some(format);
+        self
+    }
+
+    /// Set the sampling seed
+    pub fn with_seed(mut self, seed: i64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Prefer a provider when routing
+    pub fn with_provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
+    }
+
+    /// Tag the request with a correlation ID, see
+    /// [`ExecutionContext::correlation_id`](crate::node::ExecutionContext::correlation_id)
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// System messages joined into a single instruction, for APIs that take
+    /// it separately from the conversation
+    pub(crate) fn system_instruction(&self) -> Option<String> {
+        let system: Vec<&str> = self
+            .messages
+            .iter()
+            .filter(|m| m.role == Role::System)
+            .map(|m| m.content.as_str())
+            .collect();
+        (!system.is_empty()).then(|| system.join("\n\n"))
+    }
+}
+
+/// A chat completion
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatResponse {
+    /// Generated text
+    pub content: String,
+    /// Name of the provider that answered
+    pub provider: String,
+    /// Model that answered, if reported
+    pub model: Option<String>,
+    /// Why generation stopped as the provider reports it (e.g. `stop`, `length`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+    /// Tokens used by this request, if reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    /// Rate limits the provider reported with the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<crate::http::RateLimitStatus>,
+    /// Tool calls requested by the model, in the provider's format
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<Value>,
+    /// Response body as received, if the provider keeps it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Value>,
+}
+
+impl ChatResponse {
+    /// Create a response with just content and provider name
+    pub fn new(content: impl Into<String>, provider: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            provider: provider.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Usage for one request from its input and output token counts
+pub(crate) fn request_usage(
+    input_tokens: Option<u64>,
+    output_tokens: Option<u64>,
+) -> Option<TokenUsage> {
+    if input_tokens.is_none() && output_tokens.is_none() {
+        return None;
+    }
+    Some(TokenUsage {
+        requests: 1,
+        input_tokens: input_tokens.unwrap_or(0),
+        output_tokens: output_tokens.unwrap_or(0),
+        ..TokenUsage::default()
+    })
+}
+
+/// An embeddings request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmbeddingRequest {
+    pub inputs: Vec<String>,
+    /// Model override
+    pub model: Option<String>,
+}
+
+impl EmbeddingRequest {
+    /// Create a request embedding each input
+    pub fn new<I: Into<String>>(inputs: impl IntoIterator<Item = I>) -> Self {
+        Self {
+            inputs: inputs.into_iter().map(Into::into).collect(),
+            model: None,
+        }
+    }
+
+    /// Set the model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+}
+
+/// Stream of response text deltas
+pub type TokenStream = Pin<Box<dyn Stream<Item = Result<String, LlmError>> + Send>>;
+
+/// A chat/embeddings backend
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Provider name, used in responses and for routing
+    fn name(&self) -> &str;
+
+    /// Generate a complete response
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError>;
+
+    /// Generate a response as a stream of text deltas
+    ///
+    /// The default implementation yields the whole [`LlmProvider::chat`]
+    /// response as one item.
+    async fn chat_stream(&self, request: ChatRequest) -> Result<TokenStream, LlmError> {
+        let response = self.chat(request).await?;
+        Ok(Box::pin(futures::stream::iter([Ok(response.content)])))
+    }
+
+    /// Embed each input, in order
+    async fn embeddings(&self, request: EmbeddingRequest) -> Result<Vec<Vec<f32>>, LlmError> {
+        let _ = request;
+        Err(LlmError::Unsupported {
+            provider: self.name().to_string(),
+            operation: "embeddings",
+        })
+    }
+
+    /// Check that the provider is reachable and accepts its credentials,
+    /// without generating anything, e.g. for a readiness probe
+    ///
+    /// The default reports success without a request.
+    async fn ping(&self) -> Result<(), LlmError> {
+        Ok(())
+    }
+}
+
+/// Turn a line-oriented HTTP response (SSE or NDJSON) into a token stream
+///
+/// `parse_line` returns the text delta carried by a line, if any.
+pub(crate) fn line_stream<F>(response: reqwest::Response, parse_line: F) -> TokenStream
+where
+    F: Fn(&str) -> Result<Option<String>, LlmError> + Send + Sync + 'static,
+{
+    struct State<F> {
+        response: Option<reqwest::Response>,
+        buffer: String,
+        pending: VecDeque<Result<String, LlmError>>,
+        parse_line: F,
+    }
+
+    impl<F: Fn(&str) -> Result<Option<String>, LlmError>> State<F> {
+        fn push_line(&mut self, line: &str) {
+            if let Some(item) = (self.parse_line)(line.trim()).transpose() {
+                self.pending.push_back(item);
+            }
+        }
+    }
+
+    let state = State {
+        response: Some(response),
+        buffer: String::new(),
+        pending: VecDeque::new(),
+        parse_line,
+    };
+    Box::pin(futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.pending.pop_front() {
+                return Some((item, state));
+            }
+            let response = state.response.as_mut()?;
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    state.buffer.push_str(&String::from_utf8_lossy(&chunk));
+                    while let Some(end) = state.buffer.find('\n') {
+                        let line: String = state.buffer.drain(..=end).collect();
+                        state.push_line(&line);
+                    }
+                }
+                Ok(None) => {
+                    state.response = None;
+                    let rest = std::mem::take(&mut state.buffer);
+                    state.push_line(&rest);
+                }
+                Err(error) => {
+                    state.response = None;
+                    state.pending.push_back(Err(LlmError::transport(error)));
+                }
+            }
+        }
+    }))
+}
+
+/// Add the request's correlation ID header, if it has one
+pub(crate) fn tag_request(
+    builder: reqwest::RequestBuilder,
+    request: &ChatRequest,
+) -> reqwest::RequestBuilder {
+    match &request.request_id {
+        Some(id) => builder.header(crate::node::REQUEST_ID_HEADER, id),
+        None => builder,
+    }
+}
+
+/// Send a JSON request, returning the response once its status is checked
+pub(crate) async fn send_json(
+    client: &HttpClient,
+    request: reqwest::RequestBuilder,
+    body: &Value,
+) -> Result<reqwest::Response, LlmError> {
+    send_checked(client, request.json(body)).await
+}
+
+/// Send a request, returning the response once its status is checked
+pub(crate) async fn send_checked(
+    client: &HttpClient,
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, LlmError> {
+    let response = client.send(request).await.map_err(LlmError::transport)?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(LlmError::from_status(status.as_u16(), &body))
+    }
+}
+
+/// Extract the JSON payload of an SSE `data:` line
+pub(crate) fn sse_data(line: &str) -> Result<Option<Value>, LlmError> {
+    match line.strip_prefix("data:").map(str::trim) {
+        None | Some("") | Some("[DONE]") => Ok(None),
+        Some(data) => serde_json::from_str(data)
+            .map(Some)
+            .map_err(|e| LlmError::Request(format!("Stream processing error: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_messages_from_value() {
+        assert_eq!(
+            ChatMessage::from_value(&json!("Hi")).unwrap(),
+            vec![ChatMessage::user("Hi")]
+        );
+        let messages = ChatMessage::from_value(&json!([
+            {"role": "system", "content": "Be brief"},
+            {"role": "model", "content": "Hello"}
+        ]))
+        .unwrap();
+        assert_eq!(messages[1], ChatMessage::assistant("Hello"));
+
+        assert!(ChatMessage::from_value(&json!([])).is_err());
+        assert!(ChatMessage::from_value(&json!([{"role": "tool", "content": "x"}])).is_err());
+        assert!(ChatMessage::from_value(&json!(42)).is_err());
+    }
+
+    #[test]
+    fn test_error_classification() {
+        assert!(!LlmError::from_status(401, "").is_retryable());
+        assert!(LlmError::from_status(429, "").is_retryable());
+        assert!(LlmError::from_status(503, "").is_retryable());
+        let error = LlmError::from_status(400, r#"{"error": {"message": "bad model"}}"#);
+        assert!(error.to_string().contains("bad model"));
+        assert!(!NodeError::from(error).is_retryable());
+    }
+}
@@ -0,0 +1,217 @@
+//! Ollama backend for locally served models
+
+use super::{
+    ChatRequest, ChatResponse, EmbeddingRequest, LlmError, LlmProvider, ResponseFormat,
+    TokenStream, line_stream, request_usage, send_checked, send_json, tag_request,
+};
+use crate::http::HttpClient;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::time::Duration;
+
+/// Default Ollama server URL
+pub const OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+/// [`LlmProvider`] for an Ollama server
+#[derive(Debug, Clone)]
+pub struct OllamaProvider {
+    base_url: String,
+    model: String,
+    embedding_model: String,
+    temperature: Option<f32>,
+    timeout: Option<u64>,
+    client: HttpClient,
+}
+
+impl Default for OllamaProvider {
+    fn default() -> Self {
+        Self {
+            base_url: std::env::var("OLLAMA_HOST").unwrap_or_else(|_| OLLAMA_BASE_URL.to_string()),
+            model: "llama3.2".to_string(),
+            embedding_model: "nomic-embed-text".to_string(),
+            temperature: None,
+            timeout: Some(120),
+            client: HttpClient::shared(),
+        }
+    }
+}
+
+impl OllamaProvider {
+    /// Create a provider for the server at `OLLAMA_HOST` (default: localhost)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the server URL
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Set the chat model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Set the default embeddings model
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = model.into();
+        self
+    }
+
+    /// Set the default temperature
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set timeout in seconds
+    pub fn with_timeout(mut self, timeout: u64) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests through `client` instead of the shared one, e.g. to use
+    /// a proxy or different retry settings
+    pub fn with_http_client(mut self, client: HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn request_body(&self, request: &ChatRequest, stream: bool) -> Value {
+        let mut options = serde_json::Map::new();
+        if let Some(temperature) = request.temperature.or(self.temperature) {
+            options.insert("temperature".into(), json!(temperature));
+        }
+        if let Some(top_p) = request.top_p {
+            options.insert("top_p".into(), json!(top_p));
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            options.insert("num_predict".into(), json!(max_tokens));
+        }
+        if let Some(seed) = request.seed {
+            options.insert("seed".into(), json!(seed));
+        }
+        let mut body = json!({
+            "model": request.model.as_deref().unwrap_or(&self.model),
+            "messages": request.messages,
+            "stream": stream,
+            "options": options,
+        });
+        match &request.response_format {
+            Some(ResponseFormat::JsonObject) => body["format"] = json!("json"),
+            Some(ResponseFormat::JsonSchema { schema, .. }) => body["format"] = schema.clone(),
+            _ => {}
+        }
+        body
+    }
+
+    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        self.request(reqwest::Method::POST, path)
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let mut request = self.client.request(
+            method,
+            format!("{}{}", self.base_url.trim_end_matches('/'), path),
+        );
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(Duration::from_secs(timeout));
+        }
+        request
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+        let response = send_json(
+            &self.client,
+            tag_request(self.post("/api/chat"), &request),
+            &self.request_body(&request, false),
+        )
+        .await?;
+        let body: Value = response.json().await.map_err(LlmError::transport)?;
+        let content = body
+            .pointer("/message/content")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let tool_calls = body
+            .pointer("/message/tool_calls")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        if content.is_empty() && tool_calls.is_empty() {
+            return Err(LlmError::Request(
+                "No response content received".to_string(),
+            ));
+        }
+        Ok(ChatResponse {
+            content,
+            provider: self.name().to_string(),
+            model: body
+                .get("model")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            finish_reason: body
+                .get("done_reason")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            usage: request_usage(
+                body.get("prompt_eval_count").and_then(Value::as_u64),
+                body.get("eval_count").and_then(Value::as_u64),
+            ),
+            rate_limit: None,
+            tool_calls,
+            raw: Some(body),
+        })
+    }
+
+    async fn chat_stream(&self, request: ChatRequest) -> Result<TokenStream, LlmError> {
+        let response = send_json(
+            &self.client,
+            tag_request(self.post("/api/chat"), &request),
+            &self.request_body(&request, true),
+        )
+        .await?;
+        // Newline-delimited JSON, one object per chunk
+        Ok(line_stream(response, |line| {
+            if line.is_empty() {
+                return Ok(None);
+            }
+            let chunk: Value = serde_json::from_str(line)
+                .map_err(|e| LlmError::Request(format!("Stream processing error: {}", e)))?;
+            if let Some(error) = chunk.get("error").and_then(Value::as_str) {
+                return Err(LlmError::Request(error.to_string()));
+            }
+            Ok(chunk
+                .pointer("/message/content")
+                .and_then(Value::as_str)
+                .filter(|content| !content.is_empty())
+                .map(str::to_string))
+        }))
+    }
+
+    async fn embeddings(&self, request: EmbeddingRequest) -> Result<Vec<Vec<f32>>, LlmError> {
+        let body = json!({
+            "model": request.model.as_deref().unwrap_or(&self.embedding_model),
+            "input": request.inputs,
+        });
+        let response = send_json(&self.client, self.post("/api/embed"), &body).await?;
+        let body: Value = response.json().await.map_err(LlmError::transport)?;
+        serde_json::from_value(body["embeddings"].clone())
+            .map_err(|e| LlmError::Request(format!("Invalid embeddings: {}", e)))
+    }
+
+    /// Asks the server for its version
+    async fn ping(&self) -> Result<(), LlmError> {
+        let request = self.request(reqwest::Method::GET, "/api/version");
+        send_checked(&self.client, request).await.map(|_| ())
+    }
+}
@@ -0,0 +1,470 @@
+//! OpenAI-compatible backend built on async-openai
+
+use super::{
+    ChatMessage, ChatRequest, ChatResponse, EmbeddingRequest, LlmError, LlmProvider,
+    ResponseFormat, Role, TokenStream, request_usage,
+};
+use crate::http::HttpClient;
+use crate::node::builtin::llm::{ApiConfig, AuthStyle};
+use async_openai::{
+    Client,
+    config::{AzureConfig, OpenAIConfig},
+    error::OpenAIError,
+    types::{
+        ChatCompletionRequestAssistantMessage, ChatCompletionRequestMessage,
+        ChatCompletionRequestSystemMessage, ChatCompletionRequestUserMessage,
+        ChatCompletionResponseStream, CreateChatCompletionRequest, CreateChatCompletionRequestArgs,
+        CreateChatCompletionResponse, CreateEmbeddingRequestArgs, CreateEmbeddingResponse,
+        ResponseFormat as OpenAiResponseFormat, ResponseFormatJsonSchema,
+    },
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Default model for [`OpenAiProvider::embeddings`]
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+/// Client for the configured provider's auth and URL scheme
+#[derive(Debug, Clone)]
+enum ChatClient {
+    OpenAi(Client<OpenAIConfig>),
+    Azure(Client<AzureConfig>),
+}
+
+impl ChatClient {
+    async fn create(
+        &self,
+        request: CreateChatCompletionRequest,
+    ) -> Result<CreateChatCompletionResponse, OpenAIError> {
+        match self {
+            ChatClient::OpenAi(client) => client.chat().create(request).await,
+            ChatClient::Azure(client) => client.chat().create(request).await,
+        }
+    }
+
+    async fn create_stream(
+        &self,
+        request: CreateChatCompletionRequest,
+    ) -> Result<ChatCompletionResponseStream, OpenAIError> {
+        match self {
+            ChatClient::OpenAi(client) => client.chat().create_stream(request).await,
+            ChatClient::Azure(client) => client.chat().create_stream(request).await,
+        }
+    }
+
+    async fn list_models(&self) -> Result<(), OpenAIError> {
+        match self {
+            ChatClient::OpenAi(client) => client.models().list().await.map(|_| ()),
+            // Azure deployments have no model listing; resolving the key must do
+            ChatClient::Azure(_) => Ok(()),
+        }
+    }
+
+    async fn embeddings(
+        &self,
+        request: async_openai::types::CreateEmbeddingRequest,
+    ) -> Result<CreateEmbeddingResponse, OpenAIError> {
+        match self {
+            ChatClient::OpenAi(client) => client.embeddings().create(request).await,
+            ChatClient::Azure(client) => client.embeddings().create(request).await,
+        }
+    }
+}
+
+impl ChatClient {
+    fn build(config: &ApiConfig, api_key: &str) -> Result<Self, LlmError> {
+        let http = match config.http_config() {
+            Some(http) => Some(
+                HttpClient::new(http)
+                    .map_err(|e| LlmError::InvalidRequest(e.to_string()))?
+                    .reqwest_client()
+                    .clone(),
+            ),
+            None => None,
+        };
+        fn with_http<C: async_openai::config::Config>(
+            client: Client<C>,
+            http: &Option<reqwest::Client>,
+        ) -> Client<C> {
+            match http {
+                Some(http) => client.with_http_client(http.clone()),
+                None => client,
+            }
+        }
+        Ok(match config.provider.auth_style() {
+            AuthStyle::ApiKeyHeader => ChatClient::Azure(with_http(
+                Client::with_config(config.azure_config(api_key)?),
+                &http,
+            )),
+            AuthStyle::Bearer => {
+                let mut config_builder = OpenAIConfig::new().with_api_key(api_key);
+
+                if let Some(ref base_url) = config.base_url {
+                    config_builder = config_builder.with_api_base(base_url);
+                }
+
+                if let Some(ref org_id) = config.org_id {
+                    config_builder = config_builder.with_org_id(org_id);
+                }
+
+                ChatClient::OpenAi(with_http(Client::with_config(config_builder), &http))
+            }
+        })
+    }
+}
+
+/// Client built for the most recently resolved API key
+struct CachedClient {
+    api_key: String,
+    client: ChatClient,
+}
+
+impl std::fmt::Debug for CachedClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedClient")
+            .field("client", &self.client)
+            .finish_non_exhaustive()
+    }
+}
+
+/// [`LlmProvider`] for OpenAI and OpenAI-compatible APIs
+///
+/// Any [`ApiConfig`] preset works, including Azure OpenAI, where requests go
+/// to the configured deployment regardless of the requested model.
+///
+/// The API key is resolved for each request; the client is rebuilt only when
+/// the key changes.
+#[derive(Debug, Clone)]
+pub struct OpenAiProvider {
+    config: ApiConfig,
+    client: Arc<Mutex<Option<CachedClient>>>,
+    embedding_model: String,
+}
+
+impl OpenAiProvider {
+    /// Create a provider from an API configuration
+    pub fn new(config: ApiConfig) -> Result<Self, LlmError> {
+        if config.provider.auth_style() == AuthStyle::ApiKeyHeader {
+            config.azure_endpoint()?;
+        }
+        Ok(Self {
+            config,
+            client: Arc::new(Mutex::new(None)),
+            embedding_model: DEFAULT_EMBEDDING_MODEL.to_string(),
+        })
+    }
+
+    /// Get the client for the current API key
+    fn client(&self) -> Result<ChatClient, LlmError> {
+        let api_key = self.config.api_key.expose()?;
+        let mut cached = self.client.lock().unwrap();
+        match cached.as_ref() {
+            Some(cached) if cached.api_key == api_key => Ok(cached.client.clone()),
+            _ => {
+                let client = ChatClient::build(&self.config, &api_key)?;
+                *cached = Some(CachedClient {
+                    api_key,
+                    client: client.clone(),
+                });
+                Ok(client)
+            }
+        }
+    }
+
+    /// Set the default embeddings model
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = model.into();
+        self
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &ApiConfig {
+        &self.config
+    }
+
+    fn build_request(
+        &self,
+        request: ChatRequest,
+        stream: bool,
+    ) -> Result<CreateChatCompletionRequest, LlmError> {
+        let messages: Vec<ChatCompletionRequestMessage> = request
+            .messages
+            .into_iter()
+            .map(|ChatMessage { role, content }| match role {
+                Role::System => {
+                    ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
+                        content: content.into(),
+                        name: None,
+                    })
+                }
+                Role::User => {
+                    ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+                        content: content.into(),
+                        name: None,
+                    })
+                }
+                Role::Assistant => {
+                    ChatCompletionRequestMessage::Assistant(ChatCompletionRequestAssistantMessage {
+                        content: Some(content.into()),
+                        ..Default::default()
+                    })
+                }
+            })
+            .collect();
+
+        let mut request_builder = CreateChatCompletionRequestArgs::default();
+        request_builder.model(request.model.unwrap_or_else(|| self.config.model.clone()));
+        request_builder.messages(messages);
+        request_builder.stream(stream);
+
+        if let Some(max_tokens) = request.max_tokens.or(self.config.max_tokens.map(u32::from)) {
+            request_builder.max_tokens(max_tokens);
+        }
+
+        if let Some(temperature) = request.temperature.or(self.config.temperature) {
+            request_builder.temperature(temperature);
+        }
+
+        if let Some(top_p) = request.top_p.or(self.config.top_p) {
+            request_builder.top_p(top_p);
+        }
+
+        if let Some(frequency_penalty) = self.config.frequency_penalty {
+            request_builder.frequency_penalty(frequency_penalty);
+        }
+
+        if let Some(presence_penalty) = self.config.presence_penalty {
+            request_builder.presence_penalty(presence_penalty);
+        }
+
+        if let Some(seed) = request.seed.or(self.config.seed) {
+            request_builder.seed(seed);
+        }
+
+        if let Some(format) = request
+            .response_format
+            .or_else(|| self.config.response_format.clone())
+        {
+            request_builder.response_format(match format {
+                ResponseFormat::Text => OpenAiResponseFormat::Text,
+                ResponseFormat::JsonObject => OpenAiResponseFormat::JsonObject,
+                ResponseFormat::JsonSchema {
+                    name,
+                    schema,
+                    strict,
+                } => OpenAiResponseFormat::JsonSchema {
+                    json_schema: ResponseFormatJsonSchema {
+                        description: None,
+                        name,
+                        schema: Some(schema),
+                        strict: Some(strict),
+                    },
+                },
+            });
+        }
+
+        request_builder
+            .build()
+            .map_err(|e| LlmError::InvalidRequest(format!("Failed to build request: {}", e)))
+    }
+
+    /// Apply the configured timeout to a call
+    async fn timed<T>(
+        &self,
+        call: impl Future<Output = Result<T, OpenAIError>>,
+    ) -> Result<T, LlmError> {
+        match self.config.timeout {
+            Some(timeout_secs) => tokio::time::timeout(Duration::from_secs(timeout_secs), call)
+                .await
+                .map_err(|_| LlmError::Timeout)?
+                .map_err(request_error),
+            None => call.await.map_err(request_error),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> &str {
+        self.config.provider.name()
+    }
+
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+        let request = self.build_request(request, false)?;
+        let client = self.client()?;
+        let response = self.timed(client.create(request)).await?;
+
+        let raw = serde_json::to_value(&response).ok();
+        let choice = response
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| LlmError::Request("No response content received".to_string()))?;
+        let tool_calls: Vec<Value> = choice
+            .message
+            .tool_calls
+            .iter()
+            .flatten()
+            .filter_map(|call| serde_json::to_value(call).ok())
+            .collect();
+        // A reply made only of tool calls has no content
+        let content = match choice.message.content {
+            Some(content) => content,
+            None if !tool_calls.is_empty() => String::new(),
+            None => {
+                return Err(LlmError::Request(
+                    "No response content received".to_string(),
+                ));
+            }
+        };
+        Ok(ChatResponse {
+            content,
+            provider: self.name().to_string(),
+            model: Some(response.model),
+            finish_reason: choice
+                .finish_reason
+                .and_then(|reason| serde_json::to_value(reason).ok())
+                .and_then(|reason| reason.as_str().map(str::to_string)),
+            usage: response.usage.and_then(|usage| {
+                request_usage(
+                    Some(usage.prompt_tokens.into()),
+                    Some(usage.completion_tokens.into()),
+                )
+            }),
+            rate_limit: None,
+            tool_calls,
+            raw,
+        })
+    }
+
+    async fn chat_stream(&self, request: ChatRequest) -> Result<TokenStream, LlmError> {
+        let request = self.build_request(request, true)?;
+        let client = self.client()?;
+        let stream = self.timed(client.create_stream(request)).await?;
+
+        Ok(Box::pin(stream.filter_map(|result| async move {
+            match result {
+                Ok(response) => response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content)
+                    .map(Ok),
+                Err(e) => Some(Err(LlmError::Request(format!(
+                    "Stream processing error: {}",
+                    e
+                )))),
+            }
+        })))
+    }
+
+    async fn embeddings(&self, request: EmbeddingRequest) -> Result<Vec<Vec<f32>>, LlmError> {
+        let request = CreateEmbeddingRequestArgs::default()
+            .model(
+                request
+                    .model
+                    .unwrap_or_else(|| self.embedding_model.clone()),
+            )
+            .input(request.inputs)
+            .build()
+            .map_err(|e| LlmError::InvalidRequest(format!("Failed to build request: {}", e)))?;
+        let client = self.client()?;
+        let mut response = self.timed(client.embeddings(request)).await?;
+        response.data.sort_by_key(|embedding| embedding.index);
+        Ok(response
+            .data
+            .into_iter()
+            .map(|embedding| embedding.embedding)
+            .collect())
+    }
+
+    /// Lists the available models, which checks the API key without
+    /// generating
+    async fn ping(&self) -> Result<(), LlmError> {
+        let client = self.client()?;
+        self.timed(client.list_models()).await
+    }
+}
+
+/// Classify a failed API call
+///
+/// Errors the API reports about the request itself are not retryable:
+/// authentication and permission failures, exhausted quota, and invalid
+/// requests (unknown model, bad parameters, oversized prompt). Anything else
+/// it reports, such as rate limits, overload or server errors, and transport
+/// failures may succeed on retry.
+fn request_error(error: OpenAIError) -> LlmError {
+    let message = error.to_string();
+    match &error {
+        OpenAIError::ApiError(api_error) => {
+            let kinds = [api_error.r#type.as_deref(), api_error.code.as_deref()];
+            let is_kind = |fatal: &[&str]| {
+                kinds
+                    .into_iter()
+                    .flatten()
+                    .any(|kind| fatal.contains(&kind))
+            };
+            if is_kind(&[
+                "authentication_error",
+                "invalid_api_key",
+                "permission_error",
+                "insufficient_quota",
+            ]) {
+                LlmError::Rejected(message)
+            } else if is_kind(&[
+                "invalid_request_error",
+                "model_not_found",
+                "context_length_exceeded",
+                "not_found_error",
+            ]) {
+                LlmError::InvalidRequest(message)
+            } else {
+                LlmError::Request(message)
+            }
+        }
+        OpenAIError::InvalidArgument(_) => LlmError::InvalidRequest(message),
+        OpenAIError::Reqwest(e)
+            if e.status().is_some_and(|status| {
+                // Compared numerically: async-openai may use another `http` version
+                status.is_client_error() && !matches!(status.as_u16(), 408 | 429)
+            }) =>
+        {
+            LlmError::Rejected(message)
+        }
+        _ => LlmError::Request(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::error::ApiError;
+
+    fn api_error(kind: &str) -> OpenAIError {
+        OpenAIError::ApiError(ApiError {
+            message: "failed".to_string(),
+            r#type: Some(kind.to_string()),
+            param: None,
+            code: None,
+        })
+    }
+
+    #[test]
+    fn test_request_error_defaults_to_retryable() {
+        assert!(matches!(
+            request_error(api_error("invalid_api_key")),
+            LlmError::Rejected(_)
+        ));
+        assert!(matches!(
+            request_error(api_error("invalid_request_error")),
+            LlmError::InvalidRequest(_)
+        ));
+        // Kinds not known to be fatal, such as a new overload error, are retried
+        assert!(request_error(api_error("rate_limit_exceeded")).is_retryable());
+        assert!(request_error(api_error("overloaded_error")).is_retryable());
+    }
+}
@@ -0,0 +1,528 @@
+//! Prompt files with YAML front matter
+//!
+//! A [`PromptLibrary`] loads every `.prompt` and `.md` file in a directory.
+//! Each file starts with a YAML front matter block describing the prompt,
+//! followed by the template body:
+//!
+//! ```text
+//! ---
+//! name: summarize
+//! description: Summarize a document
+//! model: gpt-4o-mini
+//! temperature: 0.2
+//! system: You are a concise technical writer.
+//! variables: [text, max_words]
+//! ---
+//! Summarize the following text in at most {max_words} words:
+//!
+//! {text}
+//! ```
+//!
+//! `name` defaults to the file stem. Only declared variables are substituted,
+//! so other braces (JSON examples, code) are left as written. Model hints
+//! (`model`, `temperature`, `max_tokens`, `top_p`, `provider`) are applied to
+//! the [`ChatRequest`] built by [`Prompt::to_request`].
+//!
+//! With hot reload enabled, the library checks the directory for changes each
+//! time a prompt is looked up, so prompts can be edited while a flow is
+//! running. Prompts are usually run with
+//! [`PromptTemplateNode`](crate::node::builtin::PromptTemplateNode).
+//!
+//! ```rust,no_run
+//! # use pocketflow_rs::llm::PromptLibrary;
+//! # use serde_json::json;
+//! # use std::collections::HashMap;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let library = PromptLibrary::load_dir("prompts")?.with_hot_reload(cfg!(debug_assertions));
+//! let prompt = library.get("summarize")?;
+//! let vars = HashMap::from([
+//!     ("text".to_string(), json!("...")),
+//!     ("max_words".to_string(), json!(50)),
+//! ]);
+//! let request = prompt.to_request(&vars)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{ChatMessage, ChatRequest};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+/// File extensions loaded from a prompt directory
+const PROMPT_EXTENSIONS: &[&str] = &["prompt", "md"];
+
+/// Errors raised while loading or rendering prompts
+#[derive(Debug, thiserror::Error)]
+pub enum PromptError {
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid prompt file {path}: {message}")]
+    InvalidFile { path: PathBuf, message: String },
+    #[error("Prompt '{name}' is defined in both {first} and {second}")]
+    DuplicateName {
+        name: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+    #[error("Prompt '{0}' not found")]
+    NotFound(String),
+    #[error("Prompt '{prompt}' is missing variable '{variable}'")]
+    MissingVariable { prompt: String, variable: String },
+}
+
+/// Model settings suggested by a prompt file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelHints {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Preferred provider name, tried first by an [`LlmRouter`](super::LlmRouter)
+    #[serde(default)]
+    pub provider: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FrontMatter {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    system: Option<String>,
+    #[serde(default)]
+    variables: Vec<String>,
+    // Model hints, listed here because `deny_unknown_fields` can't be
+    // combined with `flatten`
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+    #[serde(default)]
+    top_p: Option<f32>,
+    #[serde(default)]
+    provider: Option<String>,
+}
+
+/// A named prompt template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    pub name: String,
+    pub description: Option<String>,
+    /// System message sent before the rendered template
+    pub system: Option<String>,
+    /// Variables substituted as `{variable}` in the template and system message
+    pub variables: Vec<String>,
+    pub hints: ModelHints,
+    pub template: String,
+    /// File the prompt was loaded from, if any
+    pub path: Option<PathBuf>,
+}
+
+impl Prompt {
+    /// Create a prompt without front matter
+    pub fn new(name: impl Into<String>, template: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            system: None,
+            variables: Vec::new(),
+            hints: ModelHints::default(),
+            template: template.into(),
+            path: None,
+        }
+    }
+
+    /// Declare a variable
+    pub fn with_variable(mut self, variable: impl Into<String>) -> Self {
+        self.variables.push(variable.into());
+        self
+    }
+
+    /// Set the system message
+    pub fn with_system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+
+    /// Set the model hints
+    pub fn with_hints(mut self, hints: ModelHints) -> Self {
+        self.hints = hints;
+        self
+    }
+
+    /// Parse a prompt file's contents
+    ///
+    /// `default_name` is used when the front matter has no `name`.
+    pub fn parse(text: &str, default_name: &str) -> Result<Self, String> {
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        let rest = text
+            .strip_prefix("---")
+            .and_then(|rest| rest.strip_prefix('\n').or(rest.strip_prefix("\r\n")))
+            .ok_or("missing front matter (the file must start with '---')")?;
+
+        let mut offset = 0;
+        let (yaml, body) = loop {
+            let line_end = rest[offset..]
+                .find('\n')
+                .map_or(rest.len(), |i| offset + i + 1);
+            if rest[offset..line_end].trim_end() == "---" {
+                break (&rest[..offset], &rest[line_end..]);
+            }
+            if line_end == rest.len() {
+                return Err("unterminated front matter".to_string());
+            }
+            offset = line_end;
+        };
+
+        let front: FrontMatter = if yaml.trim().is_empty() {
+            serde_yaml::from_str("{}")
+        } else {
+            serde_yaml::from_str(yaml)
+        }
+        .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            name: front.name.unwrap_or_else(|| default_name.to_string()),
+            description: front.description,
+            system: front.system,
+            variables: front.variables,
+            hints: ModelHints {
+                model: front.model,
+                temperature: front.temperature,
+                max_tokens: front.max_tokens,
+                top_p: front.top_p,
+                provider: front.provider,
+            },
+            template: body.to_string(),
+            path: None,
+        })
+    }
+
+    /// Load a prompt file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, PromptError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| PromptError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let mut prompt = Self::parse(&text, stem).map_err(|message| PromptError::InvalidFile {
+            path: path.to_path_buf(),
+            message,
+        })?;
+        prompt.path = Some(path.to_path_buf());
+        Ok(prompt)
+    }
+
+    /// Substitute the declared variables into `text`
+    fn substitute(&self, text: &str, vars: &HashMap<String, Value>) -> Result<String, PromptError> {
+        let mut rendered = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(open) = rest.find('{') {
+            rendered.push_str(&rest[..open]);
+            rest = &rest[open..];
+            let variable = rest[1..]
+                .find('}')
+                .map(|close| &rest[1..=close])
+                .filter(|name| self.variables.iter().any(|v| v == name));
+            let Some(variable) = variable else {
+                rendered.push('{');
+                rest = &rest[1..];
+                continue;
+            };
+            let value = vars
+                .get(variable)
+                .ok_or_else(|| PromptError::MissingVariable {
+                    prompt: self.name.clone(),
+                    variable: variable.to_string(),
+                })?;
+            match value {
+                Value::String(text) => rendered.push_str(text),
+                other => rendered.push_str(&other.to_string()),
+            }
+            rest = &rest[variable.len() + 2..];
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+
+    /// Render the template
+    pub fn render(&self, vars: &HashMap<String, Value>) -> Result<String, PromptError> {
+        self.substitute(&self.template, vars)
+    }
+
+    /// Build a chat request from the rendered system message and template,
+    /// applying the model hints
+    pub fn to_request(&self, vars: &HashMap<String, Value>) -> Result<ChatRequest, PromptError> {
+        let mut messages = Vec::with_capacity(2);
+        if let Some(system) = &self.system {
+            messages.push(ChatMessage::system(self.substitute(system, vars)?));
+        }
+        messages.push(ChatMessage::user(self.render(vars)?.trim()));
+
+        let mut request = ChatRequest::new(messages);
+        request.model = self.hints.model.clone();
+        request.temperature = self.hints.temperature;
+        request.max_tokens = self.hints.max_tokens;
+        request.top_p = self.hints.top_p;
+        request.provider = self.hints.provider.clone();
+        Ok(request)
+    }
+}
+
+#[derive(Debug, Default)]
+struct LibraryState {
+    /// Prompts loaded from the directory
+    loaded: HashMap<String, Arc<Prompt>>,
+    /// Fingerprints of the files they were loaded from
+    files: HashMap<PathBuf, FileStamp>,
+}
+
+/// A set of named prompts, optionally loaded from a directory
+#[derive(Debug, Default)]
+pub struct PromptLibrary {
+    dir: Option<PathBuf>,
+    hot_reload: bool,
+    /// Prompts added in code; directory prompts with the same name win
+    inline: HashMap<String, Arc<Prompt>>,
+    state: RwLock<LibraryState>,
+}
+
+impl PromptLibrary {
+    /// Create an empty library
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every prompt file in `dir`
+    pub fn load_dir(dir: impl Into<PathBuf>) -> Result<Self, PromptError> {
+        let library = Self {
+            dir: Some(dir.into()),
+            ..Self::default()
+        };
+        library.reload()?;
+        Ok(library)
+    }
+
+    /// Add a prompt defined in code
+    pub fn with_prompt(mut self, prompt: Prompt) -> Self {
+        self.inline.insert(prompt.name.clone(), Arc::new(prompt));
+        self
+    }
+
+    /// Re-read the directory whenever it changed before a lookup
+    ///
+    /// Meant for development; `cfg!(debug_assertions)` is a convenient switch.
+    pub fn with_hot_reload(mut self, enabled: bool) -> Self {
+        self.hot_reload = enabled;
+        self
+    }
+
+    /// Whether hot reload is enabled
+    pub fn hot_reload(&self) -> bool {
+        self.hot_reload
+    }
+
+    /// Get the directory prompts are loaded from
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Look up a prompt by name, reloading first if hot reload is enabled
+    pub fn get(&self, name: &str) -> Result<Arc<Prompt>, PromptError> {
+        if self.hot_reload && self.is_stale()? {
+            self.reload()?;
+        }
+        let state = self.state.read().unwrap();
+        state
+            .loaded
+            .get(name)
+            .or_else(|| self.inline.get(name))
+            .cloned()
+            .ok_or_else(|| PromptError::NotFound(name.to_string()))
+    }
+
+    /// Names of all prompts, sorted
+    pub fn names(&self) -> Vec<String> {
+        let state = self.state.read().unwrap();
+        let mut names: Vec<String> = state
+            .loaded
+            .keys()
+            .chain(
+                self.inline
+                    .keys()
+                    .filter(|name| !state.loaded.contains_key(*name)),
+            )
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Re-read all prompt files from the directory
+    ///
+    /// On error the previously loaded prompts are kept. With hot reload, the
+    /// error is returned from every lookup until the files are fixed.
+    pub fn reload(&self) -> Result<(), PromptError> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        let mut loaded: HashMap<String, Arc<Prompt>> = HashMap::new();
+        let mut files = HashMap::new();
+        for (path, stamp) in scan(dir)? {
+            let prompt = Prompt::from_file(&path)?;
+            if let Some(existing) = loaded.get(&prompt.name) {
+                return Err(PromptError::DuplicateName {
+                    name: prompt.name.clone(),
+                    first: existing.path.clone().unwrap_or_default(),
+                    second: path,
+                });
+            }
+            loaded.insert(prompt.name.clone(), Arc::new(prompt));
+            files.insert(path, stamp);
+        }
+        *self.state.write().unwrap() = LibraryState { loaded, files };
+        Ok(())
+    }
+
+    /// Whether files were added, removed or modified since the last load
+    fn is_stale(&self) -> Result<bool, PromptError> {
+        let Some(dir) = &self.dir else {
+            return Ok(false);
+        };
+        let current = scan(dir)?;
+        let state = self.state.read().unwrap();
+        Ok(current.len() != state.files.len()
+            || current
+                .iter()
+                .any(|(path, stamp)| state.files.get(path) != Some(stamp)))
+    }
+}
+
+/// Modification time and size of a prompt file
+type FileStamp = (Option<SystemTime>, u64);
+
+/// List prompt files in `dir`, sorted, with their stamps
+fn scan(dir: &Path) -> Result<Vec<(PathBuf, FileStamp)>, PromptError> {
+    let io_error = |source| PromptError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+        let path = entry.path();
+        let is_prompt = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| PROMPT_EXTENSIONS.contains(&ext));
+        let metadata = entry.metadata().map_err(io_error)?;
+        if is_prompt && metadata.is_file() {
+            files.push((path, (metadata.modified().ok(), metadata.len())));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    const SUMMARIZE: &str = "---\n\
+        name: summarize\n\
+        model: small-model\n\
+        temperature: 0.2\n\
+        system: Write for {audience}.\n\
+        variables: [text, audience]\n\
+        ---\n\
+        Summarize {text} as JSON like {\"summary\": ...}\n";
+
+    #[test]
+    fn test_parse_and_render_prompt() {
+        let prompt = Prompt::parse(SUMMARIZE, "ignored").unwrap();
+        assert_eq!(prompt.name, "summarize");
+        assert_eq!(prompt.variables, ["text", "audience"]);
+        assert_eq!(prompt.hints.model.as_deref(), Some("small-model"));
+
+        let vars = HashMap::from([
+            ("text".to_string(), json!("the report")),
+            ("audience".to_string(), json!("engineers")),
+        ]);
+        let request = prompt.to_request(&vars).unwrap();
+        assert_eq!(request.model.as_deref(), Some("small-model"));
+        assert_eq!(request.temperature, Some(0.2));
+        assert_eq!(
+            request.messages[0],
+            ChatMessage::system("Write for engineers.")
+        );
+        assert_eq!(
+            request.messages[1],
+            ChatMessage::user("Summarize the report as JSON like {\"summary\": ...}")
+        );
+
+        let missing = prompt.render(&HashMap::new()).unwrap_err();
+        assert!(matches!(missing, PromptError::MissingVariable { .. }));
+        assert!(Prompt::parse("no front matter", "x").is_err());
+        assert!(Prompt::parse("---\nunknown_field: 1\n---\nbody", "x").is_err());
+        assert_eq!(
+            Prompt::parse("---\n---\nbody", "stem").unwrap().name,
+            "stem"
+        );
+    }
+
+    #[test]
+    fn test_library_hot_reload() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("summarize.prompt"), SUMMARIZE).unwrap();
+        std::fs::write(dir.path().join("greet.md"), "---\n---\nHello!").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let library = PromptLibrary::load_dir(dir.path())
+            .unwrap()
+            .with_prompt(Prompt::new("inline", "Inline"))
+            .with_hot_reload(true);
+        assert_eq!(library.names(), ["greet", "inline", "summarize"]);
+        assert_eq!(library.get("greet").unwrap().template, "Hello!");
+
+        // Edits, additions and removals are picked up on the next lookup
+        std::fs::write(dir.path().join("greet.md"), "---\n---\nHi there!").unwrap();
+        std::fs::write(dir.path().join("bye.prompt"), "---\n---\nBye").unwrap();
+        std::fs::remove_file(dir.path().join("summarize.prompt")).unwrap();
+        assert_eq!(library.get("greet").unwrap().template, "Hi there!");
+        assert!(matches!(
+            library.get("summarize"),
+            Err(PromptError::NotFound(_))
+        ));
+        assert_eq!(library.names(), ["bye", "greet", "inline"]);
+
+        // A broken edit is reported until it is fixed
+        std::fs::write(dir.path().join("bye.prompt"), "Bye").unwrap();
+        assert!(matches!(
+            library.get("greet"),
+            Err(PromptError::InvalidFile { .. })
+        ));
+        std::fs::write(dir.path().join("bye.prompt"), "---\n---\nSee you").unwrap();
+        assert_eq!(library.get("bye").unwrap().template, "See you");
+    }
+}
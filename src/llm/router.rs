@@ -0,0 +1,271 @@
+//! Routing across several providers with fallback and health tracking
+
+use super::{ChatRequest, ChatResponse, EmbeddingRequest, LlmError, LlmProvider, TokenStream};
+use crate::node::circuit_breaker::{CircuitBreaker, CircuitState};
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+struct Route {
+    provider: Arc<dyn LlmProvider>,
+    health: CircuitBreaker,
+}
+
+/// [`LlmProvider`] that dispatches each request to one of several providers
+///
+/// Providers are tried in the order they were added; a request naming a
+/// provider (see [`ChatRequest::with_provider`]) tries that one first. When a
+/// provider fails, the next one is tried. Each provider's health is tracked
+/// with a [`CircuitBreaker`]: after repeated failures it is skipped until its
+/// reset timeout elapses. Invalid requests and unsupported operations don't
+/// count against a provider's health.
+pub struct LlmRouter {
+    name: String,
+    routes: Vec<Route>,
+    failure_threshold: usize,
+    reset_timeout: Duration,
+}
+
+impl std::fmt::Debug for LlmRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LlmRouter")
+            .field("name", &self.name)
+            .field("providers", &self.provider_names())
+            .finish()
+    }
+}
+
+impl Default for LlmRouter {
+    fn default() -> Self {
+        Self {
+            name: "router".to_string(),
+            routes: Vec::new(),
+            failure_threshold: 3,
+            reset_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl LlmRouter {
+    /// Create an empty router
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the router's own provider name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Add a provider at the end of the fallback order
+    pub fn with_provider(self, provider: impl LlmProvider + 'static) -> Self {
+        self.with_shared_provider(Arc::new(provider))
+    }
+
+    /// Add a shared provider at the end of the fallback order
+    pub fn with_shared_provider(mut self, provider: Arc<dyn LlmProvider>) -> Self {
+        self.routes.push(Route {
+            provider,
+            health: CircuitBreaker::new(self.failure_threshold, self.reset_timeout),
+        });
+        self
+    }
+
+    /// Mark a provider unhealthy after `failure_threshold` consecutive failures,
+    /// and try it again after `reset_timeout` (default: 3 failures, 30s)
+    pub fn with_health_check(mut self, failure_threshold: usize, reset_timeout: Duration) -> Self {
+        self.failure_threshold = failure_threshold;
+        self.reset_timeout = reset_timeout;
+        for route in &mut self.routes {
+            route.health = CircuitBreaker::new(failure_threshold, reset_timeout);
+        }
+        self
+    }
+
+    /// Names of the providers, in fallback order
+    pub fn provider_names(&self) -> Vec<&str> {
+        self.routes.iter().map(|r| r.provider.name()).collect()
+    }
+
+    /// Health of each provider, in fallback order
+    pub fn health(&self) -> Vec<(&str, CircuitState)> {
+        self.routes
+            .iter()
+            .map(|r| (r.provider.name(), r.health.state()))
+            .collect()
+    }
+
+    /// Try providers in order until one succeeds
+    async fn route<T, F, Fut>(&self, preferred: Option<&str>, call: F) -> Result<T, LlmError>
+    where
+        F: Fn(Arc<dyn LlmProvider>) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, LlmError>> + Send,
+    {
+        let preferred_route =
+            preferred.and_then(|name| self.routes.iter().position(|r| r.provider.name() == name));
+        let order = preferred_route
+            .into_iter()
+            .chain((0..self.routes.len()).filter(|&i| Some(i) != preferred_route));
+
+        let mut last_error = None;
+        for index in order {
+            let route = &self.routes[index];
+            if !route.health.allow_request() {
+                continue;
+            }
+            match call(route.provider.clone()).await {
+                Ok(result) => {
+                    route.health.record_success();
+                    return Ok(result);
+                }
+                Err(error) => {
+                    match error {
+                        LlmError::InvalidRequest(_) | LlmError::Unsupported { .. } => {
+                            route.health.record_success()
+                        }
+                        _ => route.health.record_failure(),
+                    }
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            LlmError::NoProviderAvailable(if self.routes.is_empty() {
+                "no providers configured".to_string()
+            } else {
+                "all providers are unhealthy".to_string()
+            })
+        }))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for LlmRouter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+        self.route(request.provider.as_deref(), |provider| {
+            let request = request.clone();
+            async move { provider.chat(request).await }
+        })
+        .await
+    }
+
+    /// Falls back only while opening the stream; errors mid-stream are returned
+    async fn chat_stream(&self, request: ChatRequest) -> Result<TokenStream, LlmError> {
+        self.route(request.provider.as_deref(), |provider| {
+            let request = request.clone();
+            async move { provider.chat_stream(request).await }
+        })
+        .await
+    }
+
+    /// Succeeds if any healthy provider answers
+    async fn ping(&self) -> Result<(), LlmError> {
+        self.route(None, |provider| async move { provider.ping().await })
+            .await
+    }
+
+    async fn embeddings(&self, request: EmbeddingRequest) -> Result<Vec<Vec<f32>>, LlmError> {
+        self.route(None, |provider| {
+            let request = request.clone();
+            async move { provider.embeddings(request).await }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ChatMessage;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    struct TestProvider {
+        name: &'static str,
+        healthy: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    impl TestProvider {
+        fn new(name: &'static str, healthy: bool) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                healthy: AtomicBool::new(healthy),
+                calls: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for TestProvider {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn chat(&self, _request: ChatRequest) -> Result<ChatResponse, LlmError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.healthy.load(Ordering::SeqCst) {
+                Ok(ChatResponse::new(format!("from {}", self.name), self.name))
+            } else {
+                Err(LlmError::Request("overloaded".to_string()))
+            }
+        }
+    }
+
+    fn request() -> ChatRequest {
+        ChatRequest::new(vec![ChatMessage::user("Hi")])
+    }
+
+    #[tokio::test]
+    async fn test_router_falls_back_and_tracks_health() {
+        let primary = TestProvider::new("primary", false);
+        let backup = TestProvider::new("backup", true);
+        let router = LlmRouter::new()
+            .with_shared_provider(primary.clone())
+            .with_shared_provider(backup.clone())
+            .with_health_check(2, Duration::from_millis(20));
+
+        for _ in 0..3 {
+            assert_eq!(router.chat(request()).await.unwrap().provider, "backup");
+        }
+        // The primary is skipped once unhealthy
+        assert_eq!(primary.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(router.health()[0], ("primary", CircuitState::Open));
+
+        // A request can prefer a provider
+        let response = router
+            .chat(request().with_provider("backup"))
+            .await
+            .unwrap();
+        assert_eq!(response.content, "from backup");
+
+        // After the reset timeout the recovered primary is used again
+        primary.healthy.store(true, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        assert_eq!(router.chat(request()).await.unwrap().provider, "primary");
+        assert_eq!(router.health()[0].1, CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_router_errors() {
+        let router = LlmRouter::new();
+        assert!(matches!(
+            router.chat(request()).await,
+            Err(LlmError::NoProviderAvailable(_))
+        ));
+
+        let router = LlmRouter::new().with_shared_provider(TestProvider::new("only", false));
+        assert!(router.chat(request()).await.unwrap_err().is_retryable());
+        // Embeddings are unsupported by the test provider
+        assert!(matches!(
+            router.embeddings(EmbeddingRequest::new(["text"])).await,
+            Err(LlmError::Unsupported { .. })
+        ));
+    }
+}
@@ -0,0 +1,276 @@
+//! Response cache keyed by prompt meaning
+//!
+//! [`SemanticCache`] is an [`LlmMiddleware`] that embeds each request and
+//! answers it from a [`VectorStore`] when a previous prompt is similar
+//! enough. Unlike [`CacheMiddleware`](super::middleware::CacheMiddleware),
+//! which needs an exact match, it also serves paraphrases, which pays off for
+//! FAQ-style workloads.
+
+use super::middleware::LlmMiddleware;
+use super::vector::{InMemoryVectorStore, VectorStore};
+use super::{ChatRequest, ChatResponse, EmbeddingRequest, LlmError, LlmProvider};
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Similarity above which a cached response is reused, by default
+pub const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.92;
+
+/// Middleware serving responses to semantically similar prompts
+///
+/// The prompt is the conversation's messages; cached responses are only
+/// reused for requests with the same model. Embedding or vector store
+/// failures never fail a request: the cache is skipped instead. Clones share
+/// the same store and counters.
+///
+/// ```rust,no_run
+/// # use pocketflow_rs::llm::{MiddlewareProvider, OllamaProvider, SemanticCache};
+/// # use std::sync::Arc;
+/// let cache = SemanticCache::in_memory(Arc::new(OllamaProvider::new())).with_threshold(0.95);
+/// let provider = MiddlewareProvider::new(OllamaProvider::new()).with_middleware(cache);
+/// ```
+#[derive(Clone)]
+pub struct SemanticCache {
+    embedder: Arc<dyn LlmProvider>,
+    store: Arc<dyn VectorStore>,
+    threshold: f32,
+    embedding_model: Option<String>,
+    /// Embeddings of requests that missed, awaiting their response
+    pending: Arc<Mutex<HashMap<String, Vec<f32>>>>,
+    hits: Arc<AtomicUsize>,
+    misses: Arc<AtomicUsize>,
+}
+
+impl std::fmt::Debug for SemanticCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SemanticCache")
+            .field("embedder", &self.embedder.name())
+            .field("threshold", &self.threshold)
+            .field("hits", &self.hits())
+            .field("misses", &self.misses())
+            .finish()
+    }
+}
+
+impl SemanticCache {
+    /// Create a cache embedding prompts with `embedder` and storing them in `store`
+    pub fn new(embedder: Arc<dyn LlmProvider>, store: Arc<dyn VectorStore>) -> Self {
+        Self {
+            embedder,
+            store,
+            threshold: DEFAULT_SIMILARITY_THRESHOLD,
+            embedding_model: None,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            hits: Arc::new(AtomicUsize::new(0)),
+            misses: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Create a cache backed by an [`InMemoryVectorStore`]
+    pub fn in_memory(embedder: Arc<dyn LlmProvider>) -> Self {
+        Self::new(embedder, Arc::new(InMemoryVectorStore::new()))
+    }
+
+    /// Set the cosine similarity a prior prompt must reach to be reused
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the embeddings model
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = Some(model.into());
+        self
+    }
+
+    /// Number of requests answered from the cache
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of requests that went to the provider
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    fn prompt(request: &ChatRequest) -> String {
+        request
+            .messages
+            .iter()
+            .map(|m| format!("{:?}: {}", m.role, m.content).to_lowercase())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn pending_key(request: &ChatRequest) -> String {
+        serde_json::to_string(request).unwrap_or_default()
+    }
+
+    async fn embed(&self, request: &ChatRequest) -> Result<Vec<f32>, LlmError> {
+        let mut embedding_request = EmbeddingRequest::new([Self::prompt(request)]);
+        embedding_request.model = self.embedding_model.clone();
+        self.embedder
+            .embeddings(embedding_request)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| LlmError::Request("Embeddings response was empty".to_string()))
+    }
+
+    async fn lookup(&self, request: &ChatRequest) -> Result<Option<ChatResponse>, LlmError> {
+        let vector = self.embed(request).await?;
+        let matches = self
+            .store
+            .search(&vector, 8)
+            .await
+            .map_err(|e| LlmError::Request(e.to_string()))?;
+        let model = json!(request.model);
+        let hit = matches
+            .into_iter()
+            .take_while(|m| m.score >= self.threshold)
+            .find(|m| m.payload["model"] == model)
+            .and_then(|m| serde_json::from_value(m.payload["response"].clone()).ok());
+        if hit.is_none() {
+            self.pending
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(Self::pending_key(request), vector);
+        }
+        Ok(hit)
+    }
+}
+
+#[async_trait]
+impl LlmMiddleware for SemanticCache {
+    async fn before_request(
+        &self,
+        request: &mut ChatRequest,
+    ) -> Result<Option<ChatResponse>, LlmError> {
+        match self.lookup(request).await {
+            Ok(Some(response)) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(Some(response))
+            }
+            Ok(None) => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+            Err(error) => {
+                tracing::warn!(%error, "semantic cache lookup failed");
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn after_response(
+        &self,
+        request: &ChatRequest,
+        response: &mut ChatResponse,
+    ) -> Result<(), LlmError> {
+        let vector = self
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&Self::pending_key(request));
+        if let Some(vector) = vector {
+            let payload = json!({ "model": request.model, "response": response });
+            if let Err(error) = self
+                .store
+                .upsert(uuid::Uuid::new_v4().to_string(), vector, payload)
+                .await
+            {
+                tracing::warn!(%error, "semantic cache insert failed");
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_error(&self, request: &ChatRequest, _error: &LlmError) {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&Self::pending_key(request));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ChatMessage;
+    use crate::llm::middleware::MiddlewareProvider;
+
+    /// Embeds text as counts of a few keywords and echoes chat requests
+    #[derive(Default)]
+    struct KeywordProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmProvider for KeywordProvider {
+        fn name(&self) -> &str {
+            "keywords"
+        }
+
+        async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, LlmError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(ChatResponse {
+                model: request.model,
+                ..ChatResponse::new(
+                    format!("answer to: {}", request.messages[0].content),
+                    "keywords",
+                )
+            })
+        }
+
+        async fn embeddings(&self, request: EmbeddingRequest) -> Result<Vec<Vec<f32>>, LlmError> {
+            let vocabulary = ["reset", "password", "refund", "policy", "how", "my"];
+            Ok(request
+                .inputs
+                .iter()
+                .map(|text| {
+                    vocabulary
+                        .iter()
+                        .map(|word| text.matches(word).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    fn ask(text: &str) -> ChatRequest {
+        ChatRequest::new(vec![ChatMessage::user(text)])
+    }
+
+    #[tokio::test]
+    async fn test_semantic_cache() {
+        let llm = Arc::new(KeywordProvider::default());
+        let cache = SemanticCache::in_memory(llm.clone()).with_threshold(0.9);
+        let provider = MiddlewareProvider::from_shared(llm.clone()).with_middleware(cache.clone());
+
+        let first = provider
+            .chat(ask("How do I reset my password?"))
+            .await
+            .unwrap();
+        let paraphrase = provider
+            .chat(ask("how can I reset my password"))
+            .await
+            .unwrap();
+        assert_eq!(paraphrase.content, first.content);
+        assert_eq!(llm.calls.load(Ordering::SeqCst), 1);
+
+        // Unrelated prompts and other models miss
+        provider
+            .chat(ask("What is the refund policy?"))
+            .await
+            .unwrap();
+        provider
+            .chat(ask("How do I reset my password?").with_model("other"))
+            .await
+            .unwrap();
+        assert_eq!(llm.calls.load(Ordering::SeqCst), 3);
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+    }
+}
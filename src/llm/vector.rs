@@ -0,0 +1,159 @@
+//! Vector storage for embedding similarity search
+//!
+//! [`VectorStore`] abstracts a nearest-neighbour index over embeddings with a
+//! JSON payload per vector. [`InMemoryVectorStore`] is an exact, brute-force
+//! implementation suited to small collections and tests; larger deployments
+//! can implement the trait over a dedicated vector database.
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Error reported by a vector store
+#[derive(Debug, thiserror::Error)]
+pub enum VectorStoreError {
+    /// The vector's length differs from the store's dimension
+    #[error("Expected a vector of dimension {expected}, got {actual}")]
+    DimensionMismatch { expected: usize, actual: usize },
+    #[error("Vector store error: {0}")]
+    Backend(String),
+}
+
+/// A search result
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorMatch {
+    pub id: String,
+    /// Cosine similarity to the query, from -1 to 1
+    pub score: f32,
+    pub payload: Value,
+}
+
+/// A similarity index over embeddings
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// Insert a vector, replacing any existing one with the same ID
+    async fn upsert(
+        &self,
+        id: String,
+        vector: Vec<f32>,
+        payload: Value,
+    ) -> Result<(), VectorStoreError>;
+
+    /// Find the most similar vectors, best first
+    async fn search(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<VectorMatch>, VectorStoreError>;
+
+    /// Remove a vector, returning whether it existed
+    async fn remove(&self, id: &str) -> Result<bool, VectorStoreError>;
+
+    /// Number of stored vectors
+    async fn len(&self) -> Result<usize, VectorStoreError>;
+
+    /// Whether the store is empty
+    async fn is_empty(&self) -> Result<bool, VectorStoreError> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// Remove every vector
+    async fn clear(&self) -> Result<(), VectorStoreError>;
+}
+
+/// Cosine similarity of two vectors; 0 if either is all zeros or their
+/// lengths differ
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Brute-force in-memory [`VectorStore`]
+///
+/// The dimension is fixed by the first vector inserted. Clones share the
+/// same vectors.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryVectorStore {
+    entries: Arc<RwLock<HashMap<String, StoredVector>>>,
+}
+
+#[derive(Debug)]
+struct StoredVector {
+    vector: Vec<f32>,
+    payload: Value,
+}
+
+impl InMemoryVectorStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl VectorStore for InMemoryVectorStore {
+    async fn upsert(
+        &self,
+        id: String,
+        vector: Vec<f32>,
+        payload: Value,
+    ) -> Result<(), VectorStoreError> {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = entries.values().next()
+            && existing.vector.len() != vector.len()
+        {
+            return Err(VectorStoreError::DimensionMismatch {
+                expected: existing.vector.len(),
+                actual: vector.len(),
+            });
+        }
+        entries.insert(id, StoredVector { vector, payload });
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        vector: &[f32],
+        limit: usize,
+    ) -> Result<Vec<VectorMatch>, VectorStoreError> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        let mut matches: Vec<VectorMatch> = entries
+            .iter()
+            .map(|(id, stored)| VectorMatch {
+                id: id.clone(),
+                score: cosine_similarity(vector, &stored.vector),
+                payload: stored.payload.clone(),
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
+    async fn remove(&self, id: &str) -> Result<bool, VectorStoreError> {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        Ok(entries.remove(id).is_some())
+    }
+
+    async fn len(&self) -> Result<usize, VectorStoreError> {
+        Ok(self.entries.read().unwrap_or_else(|e| e.into_inner()).len())
+    }
+
+    async fn clear(&self) -> Result<(), VectorStoreError> {
+        self.entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,473 @@
+//! Model Context Protocol server exposing flows as tools (feature `mcp`)
+//!
+//! Each [`FlowTool`] becomes an MCP tool. Its declared inputs form the tool's
+//! JSON Schema; when a client calls the tool, the arguments are written into a
+//! fresh shared store, the flow runs, and the output keys are returned as the
+//! tool result.
+//!
+//! [`McpServer::serve_stdio`] speaks newline-delimited JSON-RPC over stdin and
+//! stdout, which is what desktop MCP clients expect when launching a server
+//! binary:
+//!
+//! ```rust,no_run
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::BasicFlow;
+//! # use pocketflow_rs::mcp::{FlowTool, McpServer};
+//! # fn summarize_flow() -> BasicFlow<InMemoryStorage> { FlowBuilder::new().build() }
+//! # async fn run() -> std::io::Result<()> {
+//! McpServer::new("pocketflow", "0.1.0")
+//!     .tool(
+//!         FlowTool::new("summarize", summarize_flow)
+//!             .description("Summarize a document")
+//!             .input("text", "string", "The document to summarize")
+//!             .output_keys(["summary"]),
+//!     )
+//!     .serve_stdio()
+//!     .await
+//! # }
+//! ```
+
+use crate::flow::{BasicFlow, Flow};
+use crate::shared_store::SharedStore;
+use crate::storage::InMemoryStorage;
+use serde_json::{Map, Value, json};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// MCP protocol revision implemented by this server
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+type ToolFactory = Arc<dyn Fn() -> BasicFlow<InMemoryStorage> + Send + Sync>;
+
+/// A declared input of a flow tool
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolInput {
+    /// Store key the argument is written to
+    pub name: String,
+    /// JSON Schema of the argument (e.g. `{"type": "string"}`)
+    pub schema: Value,
+    /// Whether the argument must be provided
+    pub required: bool,
+}
+
+/// A flow exposed as an MCP tool
+#[derive(Clone)]
+pub struct FlowTool {
+    name: String,
+    description: Option<String>,
+    inputs: Vec<ToolInput>,
+    output_keys: Option<Vec<String>>,
+    factory: ToolFactory,
+}
+
+impl std::fmt::Debug for FlowTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlowTool")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("inputs", &self.inputs)
+            .field("output_keys", &self.output_keys)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FlowTool {
+    /// Create a tool from a factory producing a fresh flow per call
+    pub fn new<F>(name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn() -> BasicFlow<InMemoryStorage> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            description: None,
+            inputs: Vec::new(),
+            output_keys: None,
+            factory: Arc::new(factory),
+        }
+    }
+
+    /// Set the description shown to the client
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Declare a required input of a JSON type (`"string"`, `"number"`, `"object"`, ...)
+    pub fn input(self, name: impl Into<String>, json_type: &str, description: &str) -> Self {
+        self.input_schema(
+            name,
+            json!({ "type": json_type, "description": description }),
+            true,
+        )
+    }
+
+    /// Declare an optional input of a JSON type
+    pub fn optional_input(
+        self,
+        name: impl Into<String>,
+        json_type: &str,
+        description: &str,
+    ) -> Self {
+        self.input_schema(
+            name,
+            json!({ "type": json_type, "description": description }),
+            false,
+        )
+    }
+
+    /// Declare an input with a full JSON Schema
+    pub fn input_schema(mut self, name: impl Into<String>, schema: Value, required: bool) -> Self {
+        self.inputs.push(ToolInput {
+            name: name.into(),
+            schema,
+            required,
+        });
+        self
+    }
+
+    /// Only return these store keys as the tool result (default: the whole store)
+    pub fn output_keys<I, K>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.output_keys = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Get the tool name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the declared inputs
+    pub fn inputs(&self) -> &[ToolInput] {
+        &self.inputs
+    }
+
+    /// JSON Schema of the tool's arguments, derived from the declared inputs
+    pub fn schema(&self) -> Value {
+        let properties: Map<String, Value> = self
+            .inputs
+            .iter()
+            .map(|input| (input.name.clone(), input.schema.clone()))
+            .collect();
+        let required: Vec<&str> = self
+            .inputs
+            .iter()
+            .filter(|input| input.required)
+            .map(|input| input.name.as_str())
+            .collect();
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    }
+
+    fn descriptor(&self) -> Value {
+        let mut descriptor = json!({
+            "name": self.name,
+            "inputSchema": self.schema(),
+        });
+        if let Some(description) = &self.description {
+            descriptor["description"] = json!(description);
+        }
+        descriptor
+    }
+
+    /// Run the flow with the given arguments and collect its output
+    async fn call(&self, arguments: Map<String, Value>) -> Result<Value, String> {
+        if let Some(missing) = self
+            .inputs
+            .iter()
+            .find(|input| input.required && !arguments.contains_key(&input.name))
+        {
+            return Err(format!("Missing required input '{}'", missing.name));
+        }
+
+        let mut flow = (self.factory)();
+        let mut store = SharedStore::new();
+        for (key, value) in arguments {
+            store.set(key, value).map_err(|e| e.to_string())?;
+        }
+
+        flow.execute(&mut store).await.map_err(|e| e.to_string())?;
+
+        let keys = match &self.output_keys {
+            Some(keys) => keys.clone(),
+            None => store.keys().map_err(|e| e.to_string())?,
+        };
+        let mut output = Map::new();
+        for key in keys {
+            if let Some(value) = store.get(&key).map_err(|e| e.to_string())? {
+                output.insert(key, value);
+            }
+        }
+        Ok(Value::Object(output))
+    }
+}
+
+/// MCP server serving a set of flow tools
+#[derive(Debug, Clone)]
+pub struct McpServer {
+    name: String,
+    version: String,
+    tools: Vec<FlowTool>,
+}
+
+impl McpServer {
+    /// Create a server reporting the given name and version to clients
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            tools: Vec::new(),
+        }
+    }
+
+    /// Add a tool, replacing any existing tool with the same name
+    pub fn tool(mut self, tool: FlowTool) -> Self {
+        self.tools.retain(|existing| existing.name != tool.name);
+        self.tools.push(tool);
+        self
+    }
+
+    /// Get the registered tools
+    pub fn tools(&self) -> &[FlowTool] {
+        &self.tools
+    }
+
+    /// Serve over stdin/stdout until stdin closes
+    pub async fn serve_stdio(&self) -> std::io::Result<()> {
+        self.serve(BufReader::new(tokio::io::stdin()), tokio::io::stdout())
+            .await
+    }
+
+    /// Serve newline-delimited JSON-RPC messages from `reader`, writing responses to `writer`
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> std::io::Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle_message(message).await,
+                Err(e) => Some(error_response(
+                    Value::Null,
+                    PARSE_ERROR,
+                    &format!("Parse error: {}", e),
+                )),
+            };
+            if let Some(response) = response {
+                writer.write_all(response.to_string().as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle a single JSON-RPC message, returning the response for requests
+    /// and `None` for notifications
+    pub async fn handle_message(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return id.map(|id| error_response(id, INVALID_REQUEST, "Missing method"));
+        };
+        // Notifications (no id) never get a response
+        let id = id?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({
+                "tools": self.tools.iter().map(FlowTool::descriptor).collect::<Vec<_>>(),
+            })),
+            "tools/call" => self.call_tool(&params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let protocol_version = params
+            .get("protocolVersion")
+            .and_then(Value::as_str)
+            .unwrap_or(PROTOCOL_VERSION);
+        json!({
+            "protocolVersion": protocol_version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": { "name": self.name, "version": self.version },
+        })
+    }
+
+    async fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "Missing tool name".to_string()))?;
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.name == name)
+            .ok_or_else(|| (INVALID_PARAMS, format!("Unknown tool: {}", name)))?;
+        let arguments = match params.get("arguments") {
+            Some(Value::Object(arguments)) => arguments.clone(),
+            None | Some(Value::Null) => Map::new(),
+            Some(_) => return Err((INVALID_PARAMS, "Arguments must be an object".to_string())),
+        };
+
+        // Flow failures are tool errors the model can see, not protocol errors
+        Ok(match tool.call(arguments).await {
+            Ok(output) => json!({
+                "content": [{ "type": "text", "text": output.to_string() }],
+                "structuredContent": output,
+                "isError": false,
+            }),
+            Err(e) => json!({
+                "content": [{ "type": "text", "text": e }],
+                "isError": true,
+            }),
+        })
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+#[cfg(all(test, feature = "builtin-nodes"))]
+mod tests {
+    use super::*;
+    use crate::Action;
+    use crate::flow::FlowBuilder;
+    use crate::node::Node;
+    use crate::node::builtin::SetValueNode;
+
+    fn server() -> McpServer {
+        McpServer::new("test", "1.0").tool(
+            FlowTool::new("greet", || {
+                FlowBuilder::new()
+                    .start_node("set")
+                    .node(
+                        "set",
+                        Node::new(SetValueNode::new(
+                            "greeting",
+                            json!("hello"),
+                            Action::simple("complete"),
+                        )),
+                    )
+                    .build()
+            })
+            .description("Say hello")
+            .input("name", "string", "Who to greet")
+            .optional_input("excited", "boolean", "Add emphasis")
+            .output_keys(["greeting", "name"]),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_mcp_lists_and_calls_tools() {
+        let server = server();
+
+        let init = server
+            .handle_message(
+                json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(init["result"]["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(init["result"]["serverInfo"]["name"], "test");
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert!(server.handle_message(notification).await.is_none());
+
+        let list = server
+            .handle_message(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}))
+            .await
+            .unwrap();
+        let tool = &list["result"]["tools"][0];
+        assert_eq!(tool["name"], "greet");
+        assert_eq!(tool["inputSchema"]["required"], json!(["name"]));
+        assert_eq!(
+            tool["inputSchema"]["properties"]["excited"]["type"],
+            "boolean"
+        );
+
+        let call = server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "tools/call",
+                "params": {"name": "greet", "arguments": {"name": "Ada"}}
+            }))
+            .await
+            .unwrap();
+        assert_eq!(call["result"]["isError"], false);
+        assert_eq!(
+            call["result"]["structuredContent"],
+            json!({"greeting": "hello", "name": "Ada"})
+        );
+
+        let missing = server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 4,
+                "method": "tools/call",
+                "params": {"name": "greet", "arguments": {}}
+            }))
+            .await
+            .unwrap();
+        assert_eq!(missing["result"]["isError"], true);
+
+        let unknown = server
+            .handle_message(json!({"jsonrpc": "2.0", "id": 5, "method": "resources/list"}))
+            .await
+            .unwrap();
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_mcp_serves_newline_delimited_messages() {
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#,
+            "\n",
+            "not json\n",
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            "\n",
+        );
+        let mut output = Vec::new();
+        server()
+            .serve(BufReader::new(input.as_bytes()), &mut output)
+            .await
+            .unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["result"], json!({}));
+        assert_eq!(responses[1]["error"]["code"], PARSE_ERROR);
+    }
+}
@@ -3,7 +3,17 @@
 //! This module provides pre-built node implementations organized by feature:
 //!
 //! - Basic nodes (feature: `builtin-nodes`)
+//! - Messaging nodes (feature: `builtin-nodes`)
+//! - Report nodes (feature: `builtin-nodes`)
+//! - Record transform nodes (feature: `builtin-nodes`)
+//! - JSON Patch nodes (feature: `builtin-nodes`)
+//! - Task queue nodes (feature: `builtin-nodes`)
+//! - Graph memory nodes (feature: `builtin-nodes`)
 //! - LLM nodes (feature: `builtin-llm`)
+//! - Gemini nodes (feature: `builtin-llm`)
+//! - Regex nodes (feature: `regex-nodes`)
+//! - Text normalization nodes (feature: `normalize-text`)
+//! - Notification nodes (feature: `notify`)
 //!
 //! Each feature set can be enabled independently.
 
@@ -14,13 +24,21 @@
 /// Basic utility nodes for common operations
 #[cfg(feature = "builtin-nodes")]
 pub mod basic {
-    use crate::node::{ExecutionContext, NodeBackend, NodeError};
+    use crate::node::{ExecutionContext, KeySpec, NodeBackend, NodeError};
+    use crate::redact::Redactor;
+    use crate::shared_store::{JsonPath, JsonPathError};
     use crate::{Action, SharedStore, StorageBackend};
     use async_trait::async_trait;
-    use serde_json::Value;
+    use serde_json::{Value, json};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
     use std::time::Duration;
 
     /// A simple node that logs messages and passes through
+    ///
+    /// Prints to stdout; use [`StructuredLogNode`] for leveled `tracing`
+    /// output and store-backed logs.
     pub struct LogNode {
         message: String,
         action: Action,
@@ -101,6 +119,256 @@ pub mod basic {
         }
     }
 
+    /// Store key [`StructuredLogNode`] appends entries to by default
+    pub const LOG_KEY: &str = "__log";
+
+    /// A node that emits `tracing` events and optionally records them in the store
+    ///
+    /// The message may reference store values as `{key}` or `{key.nested.field}`;
+    /// string values are inserted as-is and other values as JSON. Placeholders
+    /// for missing keys are left unchanged. Store values added with
+    /// [`StructuredLogNode::with_field`] are attached to the event as a JSON
+    /// `fields` object.
+    ///
+    /// With [`StructuredLogNode::with_store_log`] each entry is also appended to
+    /// the `__log` array in the store as
+    /// `{"timestamp", "level", "node", "execution_id", "message", "fields"}`.
+    /// A [`Redactor`] set with [`StructuredLogNode::with_redactor`] scrubs the
+    /// message and fields before either is emitted.
+    pub struct StructuredLogNode {
+        name: String,
+        message: String,
+        level: tracing::Level,
+        fields: Vec<String>,
+        log_key: Option<String>,
+        redactor: Option<Redactor>,
+        action: Action,
+    }
+
+    impl StructuredLogNode {
+        /// Create a node logging `message` at INFO level
+        pub fn new<S: Into<String>>(message: S, action: Action) -> Self {
+            Self {
+                name: "StructuredLogNode".to_string(),
+                message: message.into(),
+                level: tracing::Level::INFO,
+                fields: Vec::new(),
+                log_key: None,
+                redactor: None,
+                action,
+            }
+        }
+
+        /// Set the node name reported with each entry
+        pub fn with_name(mut self, name: impl Into<String>) -> Self {
+            self.name = name.into();
+            self
+        }
+
+        /// Set the event level
+        pub fn with_level(mut self, level: tracing::Level) -> Self {
+            self.level = level;
+            self
+        }
+
+        /// Attach a store value to each entry
+        pub fn with_field(mut self, key: impl Into<String>) -> Self {
+            self.fields.push(key.into());
+            self
+        }
+
+        /// Also append entries to the `__log` array in the store
+        pub fn with_store_log(self) -> Self {
+            self.with_log_key(LOG_KEY)
+        }
+
+        /// Also append entries to an array under a custom store key
+        pub fn with_log_key(mut self, key: impl Into<String>) -> Self {
+            self.log_key = Some(key.into());
+            self
+        }
+
+        /// Scrub store values in the message and fields before logging them
+        pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+            self.redactor = Some(redactor);
+            self
+        }
+
+        /// Render a message template against the store
+        pub fn render<S: StorageBackend>(
+            template: &str,
+            store: &SharedStore<S>,
+        ) -> Result<String, NodeError> {
+            Self::render_redacted(template, store, None)
+        }
+
+        fn render_redacted<S: StorageBackend>(
+            template: &str,
+            store: &SharedStore<S>,
+            redactor: Option<&Redactor>,
+        ) -> Result<String, NodeError> {
+            let mut rendered = String::with_capacity(template.len());
+            let mut rest = template;
+            while let Some(open) = rest.find('{') {
+                let Some(close) = rest[open..].find('}').map(|i| open + i) else {
+                    break;
+                };
+                rendered.push_str(&rest[..open]);
+                let placeholder = &rest[open..=close];
+                let path = &rest[open + 1..close];
+                let value = lookup(store, path)?.map(|value| redact_path(redactor, path, value));
+                match value {
+                    Some(Value::String(text)) => rendered.push_str(&text),
+                    Some(value) => rendered.push_str(&value.to_string()),
+                    None => rendered.push_str(placeholder),
+                }
+                rest = &rest[close + 1..];
+            }
+            rendered.push_str(rest);
+            Ok(rendered)
+        }
+    }
+
+    /// Redact a value looked up at a dotted path
+    fn redact_path(redactor: Option<&Redactor>, path: &str, value: Value) -> Value {
+        let Some(redactor) = redactor else {
+            return value;
+        };
+        // A sensitive key anywhere along the path masks the whole value
+        match path
+            .trim()
+            .split('.')
+            .find(|key| redactor.is_sensitive_key(key))
+        {
+            Some(key) => redactor.redact_entry(key, &value),
+            None => redactor.redact_value(&value),
+        }
+    }
+
+    /// Look up a dotted path in the store
+    pub(super) fn lookup<S: StorageBackend>(
+        store: &SharedStore<S>,
+        path: &str,
+    ) -> Result<Option<Value>, NodeError> {
+        let mut segments = path.trim().split('.');
+        let Some(key) = segments.next().filter(|key| !key.is_empty()) else {
+            return Ok(None);
+        };
+        let Some(mut value) = store
+            .get(key)
+            .map_err(|e| NodeError::StorageError(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+        for segment in segments {
+            let next = match &value {
+                Value::Object(map) => map.get(segment).cloned(),
+                Value::Array(items) => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| items.get(i).cloned()),
+                _ => None,
+            };
+            match next {
+                Some(next) => value = next,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(value))
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for StructuredLogNode {
+        type PrepResult = (String, serde_json::Map<String, Value>);
+        type ExecResult = ();
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let redactor = self.redactor.as_ref();
+            let message = Self::render_redacted(&self.message, store, redactor)?;
+            let mut fields = serde_json::Map::new();
+            for key in &self.fields {
+                let value = lookup(store, key)?.unwrap_or(Value::Null);
+                fields.insert(key.clone(), redact_path(redactor, key, value));
+            }
+            Ok((message, fields))
+        }
+
+        async fn exec(
+            &mut self,
+            (message, fields): Self::PrepResult,
+            context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let fields = Value::Object(fields);
+            macro_rules! emit {
+                ($level:expr) => {
+                    tracing::event!(
+                        $level,
+                        node = %self.name,
+                        execution_id = %context.execution_id,
+                        correlation_id = context.correlation_id.as_deref(),
+                        fields = %fields,
+                        "{}",
+                        message
+                    )
+                };
+            }
+            match self.level {
+                tracing::Level::TRACE => emit!(tracing::Level::TRACE),
+                tracing::Level::DEBUG => emit!(tracing::Level::DEBUG),
+                tracing::Level::INFO => emit!(tracing::Level::INFO),
+                tracing::Level::WARN => emit!(tracing::Level::WARN),
+                _ => emit!(tracing::Level::ERROR),
+            }
+            Ok(())
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            (message, fields): Self::PrepResult,
+            _exec_result: Self::ExecResult,
+            context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            if let Some(ref log_key) = self.log_key {
+                let mut entries = match store
+                    .get(log_key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?
+                {
+                    Some(Value::Array(entries)) => entries,
+                    Some(_) => {
+                        return Err(NodeError::ValidationError(format!(
+                            "Log key '{}' does not hold an array",
+                            log_key
+                        )));
+                    }
+                    None => Vec::new(),
+                };
+                entries.push(serde_json::json!({
+                    "timestamp": chrono::DateTime::<chrono::Utc>::from(context.env.system_time())
+                        .to_rfc3339(),
+                    "level": self.level.as_str(),
+                    "node": self.name,
+                    "execution_id": context.execution_id,
+                    "message": message,
+                    "fields": fields,
+                }));
+                store
+                    .set(log_key.clone(), Value::Array(entries))
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
     /// A node that sets a value in the shared store
     pub struct SetValueNode {
         key: String,
@@ -167,6 +435,10 @@ pub mod basic {
             "SetValueNode"
         }
 
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(vec![KeySpec::new(self.key.clone())])
+        }
+
         fn max_retries(&self) -> usize {
             self.max_retries
         }
@@ -262,6 +534,99 @@ pub mod basic {
         }
     }
 
+    /// A node that extracts values from the store with a JSONPath expression
+    ///
+    /// Writes the first match to the output key, or every match as an array
+    /// with [`QueryNode::collect_all`]. When nothing matches, the default value
+    /// is written if one was set; otherwise the node fails.
+    pub struct QueryNode {
+        path: JsonPath,
+        output_key: String,
+        collect_all: bool,
+        default: Option<Value>,
+        action: Action,
+    }
+
+    impl QueryNode {
+        /// Create a query node, failing if the expression is malformed
+        pub fn new(
+            path: &str,
+            output_key: impl Into<String>,
+            action: Action,
+        ) -> Result<Self, JsonPathError> {
+            Ok(Self {
+                path: JsonPath::parse(path)?,
+                output_key: output_key.into(),
+                collect_all: false,
+                default: None,
+                action,
+            })
+        }
+
+        /// Write every match as an array instead of the first match
+        pub fn collect_all(mut self) -> Self {
+            self.collect_all = true;
+            self
+        }
+
+        /// Write this value when nothing matches
+        pub fn with_default(mut self, default: Value) -> Self {
+            self.default = Some(default);
+            self
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for QueryNode {
+        type PrepResult = Vec<Value>;
+        type ExecResult = Value;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            store
+                .query_path(&self.path)
+                .map_err(|e| NodeError::StorageError(e.to_string()))
+        }
+
+        async fn exec(
+            &mut self,
+            matches: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            if self.collect_all {
+                return Ok(Value::Array(matches));
+            }
+            matches
+                .into_iter()
+                .next()
+                .or_else(|| self.default.clone())
+                .ok_or_else(|| {
+                    NodeError::ValidationError(format!("Query '{}' matched nothing", self.path))
+                })
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            value: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            store
+                .set(self.output_key.clone(), value)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "QueryNode"
+        }
+    }
+
     /// A conditional node that chooses actions based on store content
     pub struct ConditionalNode<F, S>
     where
@@ -347,42 +712,113 @@ pub mod basic {
         }
     }
 
-    /// A delay node that waits for a specified duration
-    pub struct DelayNode {
-        duration: Duration,
-        action: Action,
+    type Selector<S> =
+        Box<dyn Fn(&SharedStore<S>) -> Result<Option<String>, NodeError> + Send + Sync>;
+
+    /// A node that picks one of several actions by the value of a store key or
+    /// a closure
+    ///
+    /// Values are matched against the cases in order; a missing value or one
+    /// without a case takes the default action. Strings match as they are and
+    /// other JSON values by their JSON text (`42`, `true`).
+    pub struct SwitchNode<S: StorageBackend> {
+        selector: Selector<S>,
+        key: Option<String>,
+        cases: Vec<(String, Action)>,
+        default: Action,
+        ignore_case: bool,
         max_retries: usize,
     }
 
-    impl DelayNode {
-        /// Create a new delay node
-        pub fn new(duration: Duration, action: Action) -> Self {
+    impl<S: StorageBackend> SwitchNode<S> {
+        /// Switch on the value of a store key
+        pub fn on_key(key: impl Into<String>, default: Action) -> Self {
+            let key = key.into();
+            let read = key.clone();
+            let selector = move |store: &SharedStore<S>| {
+                let value = store
+                    .get(&read)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+                Ok(value.map(|value| match value {
+                    Value::String(text) => text,
+                    other => other.to_string(),
+                }))
+            };
             Self {
-                duration,
-                action,
+                key: Some(key),
+                ..Self::new(selector, default)
+            }
+        }
+
+        /// Switch on a value computed from the store
+        pub fn on<F>(selector: F, default: Action) -> Self
+        where
+            F: Fn(&SharedStore<S>) -> Option<String> + Send + Sync + 'static,
+        {
+            Self::new(move |store: &SharedStore<S>| Ok(selector(store)), default)
+        }
+
+        fn new<F>(selector: F, default: Action) -> Self
+        where
+            F: Fn(&SharedStore<S>) -> Result<Option<String>, NodeError> + Send + Sync + 'static,
+        {
+            Self {
+                selector: Box::new(selector),
+                key: None,
+                cases: Vec::new(),
+                default,
+                ignore_case: false,
                 max_retries: 1,
             }
         }
 
+        /// Take `action` when the value is `value`
+        pub fn case(mut self, value: impl Into<String>, action: Action) -> Self {
+            self.cases.push((value.into(), action));
+            self
+        }
+
+        /// Match values ignoring case and surrounding whitespace, as in free-form
+        /// LLM labels like `" Billing\n"`
+        pub fn ignore_case(mut self) -> Self {
+            self.ignore_case = true;
+            self
+        }
+
         /// Set maximum retries
         pub fn with_retries(mut self, max_retries: usize) -> Self {
             self.max_retries = max_retries;
             self
         }
+
+        /// Action for a selected value
+        fn action_for(&self, value: Option<&str>) -> Action {
+            let normalize = |text: &str| {
+                if self.ignore_case {
+                    text.trim().to_lowercase()
+                } else {
+                    text.to_string()
+                }
+            };
+            value
+                .map(normalize)
+                .and_then(|value| self.cases.iter().find(|(case, _)| normalize(case) == value))
+                .map_or_else(|| self.default.clone(), |(_, action)| action.clone())
+        }
     }
 
     #[async_trait]
-    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for DelayNode {
-        type PrepResult = Duration;
-        type ExecResult = ();
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for SwitchNode<S> {
+        type PrepResult = Option<String>;
+        type ExecResult = Action;
         type Error = NodeError;
 
         async fn prep(
             &mut self,
-            _store: &SharedStore<S>,
+            store: &SharedStore<S>,
             _context: &ExecutionContext,
         ) -> Result<Self::PrepResult, Self::Error> {
-            Ok(self.duration)
+            (self.selector)(store)
         }
 
         async fn exec(
@@ -390,224 +826,477 @@ pub mod basic {
             prep_result: Self::PrepResult,
             _context: &ExecutionContext,
         ) -> Result<Self::ExecResult, Self::Error> {
-            tokio::time::sleep(prep_result).await;
-            Ok(())
+            Ok(self.action_for(prep_result.as_deref()))
         }
 
         async fn post(
             &mut self,
             _store: &mut SharedStore<S>,
             _prep_result: Self::PrepResult,
-            _exec_result: Self::ExecResult,
+            exec_result: Self::ExecResult,
             _context: &ExecutionContext,
         ) -> Result<Action, Self::Error> {
-            Ok(self.action.clone())
+            Ok(exec_result)
         }
 
         fn name(&self) -> &str {
-            "DelayNode"
+            "SwitchNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            self.key.iter().cloned().map(KeySpec::new).collect()
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(Vec::new())
         }
 
         fn max_retries(&self) -> usize {
             self.max_retries
         }
     }
-}
-
-// ============================================================================
-// LLM NODES (feature: builtin-llm)
-// ============================================================================
-
-/// LLM-related nodes for AI interactions
-#[cfg(feature = "builtin-llm")]
-pub mod llm {
-    use crate::node::{ExecutionContext, NodeBackend, NodeError};
-    use crate::{Action, SharedStore, StorageBackend};
-    use async_openai::{
-        Client,
-        config::OpenAIConfig,
-        types::{ChatCompletionRequestMessage, CreateChatCompletionRequestArgs},
-    };
-    use async_trait::async_trait;
-    use futures::StreamExt;
-    use serde_json::Value;
-    use std::time::Duration; // For stream processing
 
-    /// Configuration for API requests
-    #[derive(Debug, Clone)]
-    pub struct ApiConfig {
-        /// API key for authentication
-        pub api_key: String,
-        /// Base URL for the API (optional, defaults to OpenAI)
-        pub base_url: Option<String>,
-        /// Organization ID (optional)
-        pub org_id: Option<String>,
-        /// Model to use for requests
-        pub model: String,
-        /// Maximum tokens for response
-        pub max_tokens: Option<u16>,
-        /// Temperature for response generation
-        pub temperature: Option<f32>,
-        /// Request timeout in seconds
-        pub timeout: Option<u64>,
-        /// Top-p sampling parameter
-        pub top_p: Option<f32>,
-        /// Frequency penalty
-        pub frequency_penalty: Option<f32>,
-        /// Presence penalty
-        pub presence_penalty: Option<f32>,
-        /// Enable streaming response (default: false)
-        pub stream: bool,
+    /// Outcome of an [`AsyncConditionalNode`]'s condition
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Decision {
+        pub outcome: bool,
+        /// Why the condition decided as it did, for the audit record
+        pub rationale: Option<String>,
     }
 
-    impl Default for ApiConfig {
-        fn default() -> Self {
+    impl Decision {
+        /// Create a decision without a rationale
+        pub fn new(outcome: bool) -> Self {
             Self {
-                api_key: std::env::var("OPENAI_API_KEY").unwrap_or_default(),
-                base_url: None,
-                org_id: None,
-                model: "gpt-3.5-turbo".to_string(),
-                max_tokens: Some(1000),
-                temperature: Some(0.7),
-                timeout: Some(30),
-                top_p: None,
-                frequency_penalty: None,
-                presence_penalty: None,
-                stream: false,
+                outcome,
+                rationale: None,
             }
         }
-    }
 
-    impl ApiConfig {
-        /// Create a new ApiConfig with an API key
-        pub fn new(api_key: impl Into<String>) -> Self {
-            Self {
-                api_key: api_key.into(),
-                ..Default::default()
-            }
+        /// Explain the decision
+        pub fn with_rationale(mut self, rationale: impl Into<String>) -> Self {
+            self.rationale = Some(rationale.into());
+            self
         }
+    }
 
-        /// Set the model to use
-        pub fn with_model(mut self, model: impl Into<String>) -> Self {
-            self.model = model.into();
-            self
+    impl From<bool> for Decision {
+        fn from(outcome: bool) -> Self {
+            Self::new(outcome)
         }
+    }
 
-        /// Set the base URL for the API
-        pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
-            self.base_url = Some(base_url.into());
-            self
+    /// Boxed future returned by an async condition
+    pub type ConditionFuture = Pin<Box<dyn Future<Output = Result<Decision, NodeError>> + Send>>;
+
+    type AsyncCondition = Arc<dyn Fn(Value) -> ConditionFuture + Send + Sync>;
+
+    /// A conditional node whose condition is async, so it can query databases
+    /// or APIs
+    ///
+    /// The condition gets an object holding the values of the input keys
+    /// (`null` for missing ones) and runs in `exec`, so failures are retried.
+    /// With a rationale key, each decision is appended to an array there as
+    /// `{"outcome", "action", "rationale", "execution_id"}`.
+    #[derive(Clone)]
+    pub struct AsyncConditionalNode {
+        condition: AsyncCondition,
+        inputs: Vec<String>,
+        if_true: Action,
+        if_false: Action,
+        rationale_key: Option<String>,
+        max_retries: usize,
+        retry_delay: Duration,
+    }
+
+    impl AsyncConditionalNode {
+        /// Create a new async conditional node
+        pub fn new<F, Fut, D>(condition: F, if_true: Action, if_false: Action) -> Self
+        where
+            F: Fn(Value) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<D, NodeError>> + Send + 'static,
+            D: Into<Decision>,
+        {
+            Self {
+                condition: Arc::new(move |inputs| {
+                    let decision = condition(inputs);
+                    Box::pin(async move { decision.await.map(Into::into) })
+                }),
+                inputs: Vec::new(),
+                if_true,
+                if_false,
+                rationale_key: None,
+                max_retries: 1,
+                retry_delay: Duration::from_secs(0),
+            }
         }
 
-        /// Set the organization ID
-        pub fn with_org_id(mut self, org_id: impl Into<String>) -> Self {
-            self.org_id = Some(org_id.into());
+        /// Pass the values of these keys to the condition
+        pub fn with_inputs<K: Into<String>>(mut self, keys: impl IntoIterator<Item = K>) -> Self {
+            self.inputs = keys.into_iter().map(Into::into).collect();
             self
         }
 
-        /// Set maximum tokens for response
-        pub fn with_max_tokens(mut self, max_tokens: u16) -> Self {
-            self.max_tokens = Some(max_tokens);
+        /// Append each decision and its rationale to an array under `key`
+        pub fn with_rationale_key(mut self, key: impl Into<String>) -> Self {
+            self.rationale_key = Some(key.into());
             self
         }
 
-        /// Set temperature for response generation
-        pub fn with_temperature(mut self, temperature: f32) -> Self {
-            self.temperature = Some(temperature);
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
             self
         }
 
-        /// Set request timeout in seconds
-        pub fn with_timeout(mut self, timeout: u64) -> Self {
-            self.timeout = Some(timeout);
+        /// Set retry delay
+        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+            self.retry_delay = delay;
             self
         }
+    }
 
-        /// Set top-p sampling parameter
-        pub fn with_top_p(mut self, top_p: f32) -> Self {
-            self.top_p = Some(top_p);
-            self
+    impl std::fmt::Debug for AsyncConditionalNode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("AsyncConditionalNode")
+                .field("inputs", &self.inputs)
+                .field("if_true", &self.if_true)
+                .field("if_false", &self.if_false)
+                .field("rationale_key", &self.rationale_key)
+                .finish()
         }
+    }
 
-        /// Set frequency penalty
-        pub fn with_frequency_penalty(mut self, frequency_penalty: f32) -> Self {
-            self.frequency_penalty = Some(frequency_penalty);
-            self
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for AsyncConditionalNode {
+        type PrepResult = Value;
+        type ExecResult = Decision;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let mut inputs = serde_json::Map::new();
+            for key in &self.inputs {
+                let value = store
+                    .get(key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+                inputs.insert(key.clone(), value.unwrap_or(Value::Null));
+            }
+            Ok(Value::Object(inputs))
         }
 
-        /// Set presence penalty
-        pub fn with_presence_penalty(mut self, presence_penalty: f32) -> Self {
-            self.presence_penalty = Some(presence_penalty);
-            self
+        async fn exec(
+            &mut self,
+            prep_result: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            (self.condition)(prep_result).await
         }
 
-        /// Enable or disable streaming
-        pub fn with_stream(mut self, stream: bool) -> Self {
-            self.stream = stream;
-            self
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            decision: Self::ExecResult,
+            context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            let action = if decision.outcome {
+                self.if_true.clone()
+            } else {
+                self.if_false.clone()
+            };
+            if let Some(key) = &self.rationale_key {
+                let mut records = match store
+                    .get(key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?
+                {
+                    Some(Value::Array(records)) => records,
+                    Some(_) => {
+                        return Err(NodeError::ValidationError(format!(
+                            "Rationale key '{}' does not hold an array",
+                            key
+                        )));
+                    }
+                    None => Vec::new(),
+                };
+                records.push(json!({
+                    "outcome": decision.outcome,
+                    "action": action.name(),
+                    "rationale": decision.rationale,
+                    "execution_id": context.execution_id,
+                }));
+                store
+                    .set(key.clone(), Value::Array(records))
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            Ok(action)
+        }
+
+        fn name(&self) -> &str {
+            "AsyncConditionalNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            self.inputs.iter().cloned().map(KeySpec::new).collect()
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(
+                self.rationale_key
+                    .iter()
+                    .cloned()
+                    .map(KeySpec::new)
+                    .collect(),
+            )
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+
+        fn retry_delay(&self) -> Duration {
+            self.retry_delay
         }
     }
 
-    // LLM nodes implementation will be added here
+    /// What a [`DelayNode`] waits for
+    #[derive(Debug, Clone)]
+    enum DelayTarget {
+        Duration(Duration),
+        UntilKey(String),
+        #[cfg(feature = "cron")]
+        Cron(Box<cron::Schedule>),
+    }
 
-    /// A mock LLM node for testing and examples
-    pub struct MockLlmNode {
-        prompt_key: String,
-        output_key: String,
-        mock_response: String,
+    type Timestamp = chrono::DateTime<chrono::Utc>;
+
+    /// A delay node that waits for a duration, until a time stored in the
+    /// store or, with the `cron` feature, until the next cron-aligned time
+    ///
+    /// With a wake key the wake time is recorded before sleeping, so a flow
+    /// re-run after a crash (on a persistent store) only waits for what is
+    /// left. The wait happens in `post`, after the wake time is recorded.
+    pub struct DelayNode {
+        target: DelayTarget,
+        wake_key: Option<String>,
         action: Action,
         max_retries: usize,
-        retry_delay: Duration,
-        failure_rate: f64,
     }
 
-    impl MockLlmNode {
-        /// Create a new mock LLM node
-        pub fn new<S1, S2, S3>(
-            prompt_key: S1,
-            output_key: S2,
-            mock_response: S3,
-            action: Action,
-        ) -> Self
-        where
-            S1: Into<String>,
-            S2: Into<String>,
-            S3: Into<String>,
-        {
+    impl DelayNode {
+        /// Create a new delay node
+        pub fn new(duration: Duration, action: Action) -> Self {
+            Self::with_target(DelayTarget::Duration(duration), action)
+        }
+
+        /// Wait until the time stored under `key`, as an RFC 3339 string or
+        /// milliseconds since the Unix epoch; a past time doesn't wait
+        pub fn until_key(key: impl Into<String>, action: Action) -> Self {
+            Self::with_target(DelayTarget::UntilKey(key.into()), action)
+        }
+
+        /// Wait until the next time matching a cron expression, in UTC
+        ///
+        /// Expressions start with a seconds field: `sec min hour day month
+        /// weekday [year]`, e.g. `0 */15 * * * *` for every quarter hour.
+        #[cfg(feature = "cron")]
+        pub fn cron(expression: &str, action: Action) -> Result<Self, NodeError> {
+            let schedule = expression.parse::<cron::Schedule>().map_err(|e| {
+                NodeError::ValidationError(format!(
+                    "Invalid cron expression '{}': {}",
+                    expression, e
+                ))
+            })?;
+            Ok(Self::with_target(
+                DelayTarget::Cron(Box::new(schedule)),
+                action,
+            ))
+        }
+
+        fn with_target(target: DelayTarget, action: Action) -> Self {
             Self {
-                prompt_key: prompt_key.into(),
-                output_key: output_key.into(),
-                mock_response: mock_response.into(),
+                target,
+                wake_key: None,
                 action,
-                max_retries: 3,
-                retry_delay: Duration::from_secs(1),
-                failure_rate: 0.0,
+                max_retries: 1,
             }
         }
 
+        /// Record the wake time under `key` while waiting and reuse a recorded
+        /// one instead of starting over; the key is removed on waking
+        pub fn with_wake_key(mut self, key: impl Into<String>) -> Self {
+            self.wake_key = Some(key.into());
+            self
+        }
+
         /// Set maximum retries
         pub fn with_retries(mut self, max_retries: usize) -> Self {
             self.max_retries = max_retries;
             self
         }
+    }
 
-        /// Set retry delay
-        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
-            self.retry_delay = delay;
+    /// Parse an RFC 3339 string or milliseconds since the Unix epoch
+    fn parse_timestamp(value: &Value) -> Option<Timestamp> {
+        match value {
+            Value::String(text) => chrono::DateTime::parse_from_rfc3339(text)
+                .ok()
+                .map(|time| time.to_utc()),
+            other => chrono::DateTime::from_timestamp_millis(other.as_i64()?),
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for DelayNode {
+        type PrepResult = Timestamp; // The wake time
+        type ExecResult = Timestamp;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let get = |key: &str| {
+                store
+                    .get(key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))
+            };
+            if let Some(key) = &self.wake_key
+                && let Some(recorded) = get(key)?
+            {
+                return parse_timestamp(&recorded).ok_or_else(|| {
+                    NodeError::ValidationError(format!(
+                        "Wake key '{}' does not hold a timestamp",
+                        key
+                    ))
+                });
+            }
+
+            let now = Timestamp::from(context.env.system_time());
+            match &self.target {
+                DelayTarget::Duration(duration) => Ok(
+                    now + chrono::Duration::from_std(*duration).unwrap_or(chrono::Duration::MAX)
+                ),
+                DelayTarget::UntilKey(key) => {
+                    let value = get(key)?.ok_or_else(|| {
+                        NodeError::PrepError(format!("Input key '{}' not found in store", key))
+                    })?;
+                    parse_timestamp(&value).ok_or_else(|| {
+                        NodeError::ValidationError(format!(
+                            "Key '{}' does not hold a timestamp",
+                            key
+                        ))
+                    })
+                }
+                #[cfg(feature = "cron")]
+                DelayTarget::Cron(schedule) => schedule.after(&now).next().ok_or_else(|| {
+                    NodeError::ValidationError("Cron schedule has no upcoming time".to_string())
+                }),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            prep_result: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(prep_result)
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            wake: Self::ExecResult,
+            context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            if let Some(key) = &self.wake_key {
+                store
+                    .set(key.clone(), Value::String(wake.to_rfc3339()))
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            let now = Timestamp::from(context.env.system_time());
+            if let Ok(remaining) = (wake - now).to_std() {
+                context.env.sleep(remaining).await;
+            }
+            if let Some(key) = &self.wake_key {
+                store
+                    .remove(key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "DelayNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            match &self.target {
+                DelayTarget::UntilKey(key) => vec![KeySpec::new(key.clone())],
+                _ => Vec::new(),
+            }
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+    }
+
+    /// A node that increments a numeric key, routing differently once it
+    /// reaches a threshold
+    ///
+    /// A missing key counts from 0. This is the loop counter of agent flows:
+    /// route `continue` back into the loop and `done` out of it.
+    pub struct CounterNode {
+        key: String,
+        step: i64,
+        threshold: Option<(i64, Action)>,
+        reset: bool,
+        action: Action,
+    }
+
+    impl CounterNode {
+        /// Add 1 to `key` on every run and return `action`
+        pub fn new(key: impl Into<String>, action: Action) -> Self {
+            Self {
+                key: key.into(),
+                step: 1,
+                threshold: None,
+                reset: false,
+                action,
+            }
+        }
+
+        /// Set the amount added per run (may be negative)
+        pub fn with_step(mut self, step: i64) -> Self {
+            self.step = step;
             self
         }
 
-        /// Set failure rate for testing retry logic
-        pub fn with_failure_rate(mut self, rate: f64) -> Self {
-            self.failure_rate = rate.clamp(0.0, 1.0);
+        /// Return `action` instead once the count reaches `threshold` (or, with a
+        /// negative step, falls to it)
+        pub fn with_threshold(mut self, threshold: i64, action: Action) -> Self {
+            self.threshold = Some((threshold, action));
+            self
+        }
+
+        /// Remove the key when the threshold is reached, so the next loop
+        /// counts from 0 again
+        pub fn reset_on_threshold(mut self) -> Self {
+            self.reset = true;
             self
         }
     }
 
     #[async_trait]
-    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for MockLlmNode {
-        type PrepResult = String;
-        type ExecResult = String;
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for CounterNode {
+        type PrepResult = i64; // The current count
+        type ExecResult = i64; // The new count
         type Error = NodeError;
 
         async fn prep(
@@ -615,436 +1304,7027 @@ pub mod llm {
             store: &SharedStore<S>,
             _context: &ExecutionContext,
         ) -> Result<Self::PrepResult, Self::Error> {
-            let value = match store.get(&self.prompt_key) {
-                Ok(value) => value,
-                Err(e) => return Err(NodeError::StorageError(e.to_string())),
-            };
-
-            let prompt = value
-                .and_then(|v| v.as_str().map(|s| s.to_string()))
-                .ok_or_else(|| {
+            match store
+                .get(&self.key)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?
+            {
+                None => Ok(0),
+                Some(value) => value.as_i64().ok_or_else(|| {
                     NodeError::ValidationError(format!(
-                        "Prompt not found at key: {}",
-                        self.prompt_key
+                        "Counter key '{}' does not hold an integer",
+                        self.key
                     ))
-                })?;
-            Ok(prompt)
+                }),
+            }
         }
 
         async fn exec(
             &mut self,
-            prompt: Self::PrepResult,
-            context: &ExecutionContext,
+            prep_result: Self::PrepResult,
+            _context: &ExecutionContext,
         ) -> Result<Self::ExecResult, Self::Error> {
-            // Simulate API call delay
-            tokio::time::sleep(Duration::from_millis(100)).await;
-
-            // Simulate random failures for testing
-            if self.failure_rate > 0.0 && rand::random::<f64>() < self.failure_rate {
-                return Err(NodeError::ExecutionError(format!(
-                    "Mock LLM API failure (retry {})",
-                    context.current_retry
-                )));
-            }
-
-            // Generate mock response
-            let response = format!("{} (processed prompt: '{}')", self.mock_response, prompt);
-            Ok(response)
+            Ok(prep_result.saturating_add(self.step))
         }
 
         async fn post(
             &mut self,
             store: &mut SharedStore<S>,
             _prep_result: Self::PrepResult,
-            exec_result: Self::ExecResult,
+            count: Self::ExecResult,
             _context: &ExecutionContext,
         ) -> Result<Action, Self::Error> {
-            match store.set(
-                self.output_key.clone(),
-                serde_json::Value::String(exec_result),
-            ) {
-                Ok(_) => Ok(self.action.clone()),
+            let reached = self.threshold.as_ref().filter(|(threshold, _)| {
+                if self.step < 0 {
+                    count <= *threshold
+                } else {
+                    count >= *threshold
+                }
+            });
+            if reached.is_some() && self.reset {
+                store
+                    .remove(&self.key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            } else {
+                store
+                    .set(self.key.clone(), Value::from(count))
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            Ok(reached.map_or(&self.action, |(_, action)| action).clone())
+        }
+
+        fn name(&self) -> &str {
+            "CounterNode"
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(vec![
+                KeySpec::new(self.key.clone()).with_schema(json!({"type": "integer"})),
+            ])
+        }
+    }
+
+    /// A node that appends the value of one key to an array under another,
+    /// optionally keeping only the most recent entries
+    pub struct AccumulateNode {
+        source_key: String,
+        target_key: String,
+        max_len: Option<usize>,
+        action: Action,
+    }
+
+    impl AccumulateNode {
+        /// Append the value of `source_key` to the array under `target_key`
+        pub fn new(
+            source_key: impl Into<String>,
+            target_key: impl Into<String>,
+            action: Action,
+        ) -> Self {
+            Self {
+                source_key: source_key.into(),
+                target_key: target_key.into(),
+                max_len: None,
+                action,
+            }
+        }
+
+        /// Drop the oldest entries beyond `max_len`
+        pub fn with_max_len(mut self, max_len: usize) -> Self {
+            self.max_len = Some(max_len);
+            self
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for AccumulateNode {
+        type PrepResult = (Value, Vec<Value>); // The new entry and the existing ones
+        type ExecResult = Vec<Value>;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let get = |key: &str| {
+                store
+                    .get(key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))
+            };
+            let entry = get(&self.source_key)?.ok_or_else(|| {
+                NodeError::PrepError(format!(
+                    "Input key '{}' not found in store",
+                    self.source_key
+                ))
+            })?;
+            let entries = match get(&self.target_key)? {
+                Some(Value::Array(entries)) => entries,
+                Some(_) => {
+                    return Err(NodeError::ValidationError(format!(
+                        "Accumulator key '{}' does not hold an array",
+                        self.target_key
+                    )));
+                }
+                None => Vec::new(),
+            };
+            Ok((entry, entries))
+        }
+
+        async fn exec(
+            &mut self,
+            (entry, mut entries): Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            entries.push(entry);
+            if let Some(max_len) = self.max_len {
+                let excess = entries.len().saturating_sub(max_len);
+                entries.drain(..excess);
+            }
+            Ok(entries)
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            entries: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            store
+                .set(self.target_key.clone(), Value::Array(entries))
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "AccumulateNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            vec![KeySpec::new(self.source_key.clone())]
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(vec![
+                KeySpec::new(self.target_key.clone()).with_schema(json!({"type": "array"})),
+            ])
+        }
+    }
+
+    /// A node that waits until a set of store keys exist
+    ///
+    /// This joins parallel branches or external writers sharing the store's
+    /// backend (e.g. [`ConcurrentInMemoryStorage`] or Redis): the node polls
+    /// until every key is present, returning `ready`, or until the timeout (or
+    /// the flow's deadline) passes, returning `timeout`.
+    ///
+    /// [`ConcurrentInMemoryStorage`]: crate::storage::ConcurrentInMemoryStorage
+    pub struct AwaitKeysNode {
+        keys: Vec<String>,
+        timeout: Duration,
+        poll_interval: Duration,
+        ready: Action,
+        timed_out: Action,
+    }
+
+    impl AwaitKeysNode {
+        /// Wait up to `timeout` for all of `keys`
+        pub fn new<K: Into<String>>(keys: impl IntoIterator<Item = K>, timeout: Duration) -> Self {
+            Self {
+                keys: keys.into_iter().map(Into::into).collect(),
+                timeout,
+                poll_interval: Duration::from_millis(50),
+                ready: Action::simple("ready"),
+                timed_out: Action::simple("timeout"),
+            }
+        }
+
+        /// Set how often the store is checked (default: 50ms)
+        pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+            self.poll_interval = interval;
+            self
+        }
+
+        /// Set the action returned once every key exists
+        pub fn with_ready_action(mut self, action: Action) -> Self {
+            self.ready = action;
+            self
+        }
+
+        /// Set the action returned when the wait times out
+        pub fn with_timeout_action(mut self, action: Action) -> Self {
+            self.timed_out = action;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for AwaitKeysNode {
+        type PrepResult = bool; // Whether every key exists
+        type ExecResult = bool;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let mut deadline = context.env.now() + self.timeout;
+            if let Some(flow_deadline) = context.deadline {
+                deadline = deadline.min(flow_deadline);
+            }
+            loop {
+                let mut ready = true;
+                for key in &self.keys {
+                    if !store
+                        .contains_key(key)
+                        .map_err(|e| NodeError::StorageError(e.to_string()))?
+                    {
+                        ready = false;
+                        break;
+                    }
+                }
+                let now = context.env.now();
+                if ready || now >= deadline {
+                    return Ok(ready);
+                }
+                context
+                    .env
+                    .sleep(self.poll_interval.min(deadline - now))
+                    .await;
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            prep_result: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(prep_result)
+        }
+
+        async fn post(
+            &mut self,
+            _store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            ready: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            Ok(if ready {
+                self.ready.clone()
+            } else {
+                self.timed_out.clone()
+            })
+        }
+
+        fn name(&self) -> &str {
+            "AwaitKeysNode"
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(Vec::new())
+        }
+    }
+}
+
+// ============================================================================
+// MESSAGING NODES (feature: builtin-nodes)
+// ============================================================================
+
+/// Nodes for exchanging messages between flows over an [`AgentBus`](crate::bus::AgentBus)
+#[cfg(feature = "builtin-nodes")]
+pub mod messaging {
+    use crate::bus::{AgentBus, BusMessage, Subscription};
+    use crate::node::{ExecutionContext, NodeBackend, NodeError};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    /// A node that publishes a value from the shared store to a bus topic
+    pub struct SendMessageNode {
+        bus: AgentBus,
+        topic: String,
+        source_key: String,
+        sender: Option<String>,
+        action: Action,
+        max_retries: usize,
+    }
+
+    impl SendMessageNode {
+        /// Create a node publishing the value stored under `source_key` to `topic`
+        pub fn new<T: Into<String>, K: Into<String>>(
+            bus: &AgentBus,
+            topic: T,
+            source_key: K,
+            action: Action,
+        ) -> Self {
+            Self {
+                bus: bus.clone(),
+                topic: topic.into(),
+                source_key: source_key.into(),
+                sender: None,
+                action,
+                max_retries: 1,
+            }
+        }
+
+        /// Identify the sending agent in published messages
+        pub fn with_sender<S: Into<String>>(mut self, sender: S) -> Self {
+            self.sender = Some(sender.into());
+            self
+        }
+
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for SendMessageNode {
+        type PrepResult = Value;
+        type ExecResult = usize;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            store
+                .get(&self.source_key)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?
+                .ok_or_else(|| NodeError::PrepError(format!("Key '{}' not found", self.source_key)))
+        }
+
+        async fn exec(
+            &mut self,
+            prep_result: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(self.bus.send(BusMessage {
+                topic: self.topic.clone(),
+                from: self.sender.clone(),
+                payload: prep_result,
+            }))
+        }
+
+        async fn post(
+            &mut self,
+            _store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            _exec_result: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "SendMessageNode"
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+    }
+
+    /// A node that waits for the next message on a bus topic and stores its payload
+    ///
+    /// The node subscribes when it is created, so messages published before the
+    /// flow reaches it are queued rather than lost.
+    pub struct ReceiveMessageNode {
+        subscription: Subscription,
+        target_key: String,
+        timeout: Option<Duration>,
+        routes: Vec<(Value, Action)>,
+        action: Action,
+        max_retries: usize,
+    }
+
+    impl ReceiveMessageNode {
+        /// Create a node storing the next message on `topic` under `target_key`
+        pub fn new<T: Into<String>, K: Into<String>>(
+            bus: &AgentBus,
+            topic: T,
+            target_key: K,
+            action: Action,
+        ) -> Self {
+            Self {
+                subscription: bus.subscribe(topic),
+                target_key: target_key.into(),
+                timeout: None,
+                routes: Vec::new(),
+                action,
+                max_retries: 1,
+            }
+        }
+
+        /// Fail if no message arrives within `timeout`
+        pub fn with_timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = Some(timeout);
+            self
+        }
+
+        /// Return `action` instead of the default when the payload equals `payload`
+        pub fn with_route(mut self, payload: Value, action: Action) -> Self {
+            self.routes.push((payload, action));
+            self
+        }
+
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for ReceiveMessageNode {
+        type PrepResult = ();
+        type ExecResult = BusMessage;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            _store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            Ok(())
+        }
+
+        async fn exec(
+            &mut self,
+            _prep_result: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let message = match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, self.subscription.recv())
+                    .await
+                    .map_err(|_| {
+                        NodeError::ExecutionError(format!(
+                            "No message on topic '{}' within {:?}",
+                            self.subscription.topic(),
+                            timeout
+                        ))
+                    })?,
+                None => self.subscription.recv().await,
+            };
+            message.ok_or_else(|| {
+                NodeError::ExecutionError(format!(
+                    "Bus closed while waiting on topic '{}'",
+                    self.subscription.topic()
+                ))
+            })
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            exec_result: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            let action = self
+                .routes
+                .iter()
+                .find(|(payload, _)| *payload == exec_result.payload)
+                .map(|(_, action)| action.clone())
+                .unwrap_or_else(|| self.action.clone());
+            store
+                .set(self.target_key.clone(), exec_result.payload)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            Ok(action)
+        }
+
+        fn name(&self) -> &str {
+            "ReceiveMessageNode"
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+    }
+}
+
+// ============================================================================
+// REPORT NODES (feature: builtin-nodes)
+// ============================================================================
+
+/// Nodes rendering reports from store values
+#[cfg(feature = "builtin-nodes")]
+pub mod report {
+    use super::basic::lookup;
+    use crate::node::{ExecutionContext, NodeBackend, NodeError};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use serde_json::Value;
+    use std::path::PathBuf;
+
+    /// Output format of a [`RenderReportNode`]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum ReportFormat {
+        #[default]
+        Markdown,
+        /// HTML; inserted values are escaped
+        Html,
+    }
+
+    /// A node rendering a Markdown or HTML report from a template
+    ///
+    /// Placeholders refer to store values by key or dotted path:
+    ///
+    /// - `{key}` inserts the value, strings as-is and other values as JSON
+    /// - `{table:key}` renders an array as a table; arrays of objects get one
+    ///   column per field, other arrays a single `value` column
+    /// - `{chart:key}` renders an object of numbers as a Mermaid pie chart
+    ///
+    /// Placeholders for missing keys are left unchanged. The report is stored
+    /// under the output key (`"report"` by default) and optionally written to
+    /// a file.
+    pub struct RenderReportNode {
+        template: String,
+        format: ReportFormat,
+        output_key: String,
+        output_file: Option<PathBuf>,
+        action: Action,
+    }
+
+    impl RenderReportNode {
+        /// Create a node rendering a Markdown report
+        pub fn new(template: impl Into<String>, action: Action) -> Self {
+            Self {
+                template: template.into(),
+                format: ReportFormat::Markdown,
+                output_key: "report".to_string(),
+                output_file: None,
+                action,
+            }
+        }
+
+        /// Set the output format
+        pub fn with_format(mut self, format: ReportFormat) -> Self {
+            self.format = format;
+            self
+        }
+
+        /// Set the store key the report is written to
+        pub fn with_output_key(mut self, key: impl Into<String>) -> Self {
+            self.output_key = key.into();
+            self
+        }
+
+        /// Also write the report to a file, creating parent directories
+        pub fn with_output_file(mut self, path: impl Into<PathBuf>) -> Self {
+            self.output_file = Some(path.into());
+            self
+        }
+
+        /// Render a report template against the store
+        pub fn render<S: StorageBackend>(
+            template: &str,
+            format: ReportFormat,
+            store: &SharedStore<S>,
+        ) -> Result<String, NodeError> {
+            let mut rendered = String::with_capacity(template.len());
+            let mut rest = template;
+            while let Some(open) = rest.find('{') {
+                let Some(close) = rest[open..].find('}').map(|i| open + i) else {
+                    break;
+                };
+                rendered.push_str(&rest[..open]);
+                let placeholder = &rest[open..=close];
+                let inner = rest[open + 1..close].trim();
+                let (kind, path) = match inner.split_once(':') {
+                    Some((kind @ ("table" | "chart"), path)) => (kind, path),
+                    _ => ("value", inner),
+                };
+                match lookup(store, path)? {
+                    Some(value) => rendered.push_str(&match kind {
+                        "table" => table(path, &value, format)?,
+                        "chart" => chart(path, &value, format)?,
+                        _ => match value {
+                            Value::String(text) => escape(&text, format),
+                            value => escape(&value.to_string(), format),
+                        },
+                    }),
+                    None => rendered.push_str(placeholder),
+                }
+                rest = &rest[close + 1..];
+            }
+            rendered.push_str(rest);
+            Ok(rendered)
+        }
+    }
+
+    fn escape(text: &str, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => text.to_string(),
+            ReportFormat::Html => text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;"),
+        }
+    }
+
+    fn cell(value: Option<&Value>, format: ReportFormat) -> String {
+        let text = match value {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => value.to_string(),
+        };
+        match format {
+            ReportFormat::Markdown => text.replace('|', "\\|").replace('\n', " "),
+            ReportFormat::Html => escape(&text, format),
+        }
+    }
+
+    fn table(path: &str, value: &Value, format: ReportFormat) -> Result<String, NodeError> {
+        let Value::Array(rows) = value else {
+            return Err(NodeError::ValidationError(format!(
+                "Table source '{}' is not an array",
+                path
+            )));
+        };
+
+        let mut columns: Vec<String> = Vec::new();
+        for row in rows {
+            if let Value::Object(fields) = row {
+                for key in fields.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+        }
+        let cells: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| match row {
+                Value::Object(fields) => columns
+                    .iter()
+                    .map(|column| cell(fields.get(column), format))
+                    .collect(),
+                value => vec![cell(Some(value), format)],
+            })
+            .collect();
+        if columns.is_empty() {
+            columns.push("value".to_string());
+        }
+
+        let mut out = String::new();
+        match format {
+            ReportFormat::Markdown => {
+                let header: Vec<String> = columns
+                    .iter()
+                    .map(|c| cell(Some(&Value::String(c.clone())), format))
+                    .collect();
+                out.push_str(&format!("| {} |\n", header.join(" | ")));
+                out.push_str(&format!("|{}\n", " --- |".repeat(columns.len())));
+                for row in cells {
+                    out.push_str(&format!("| {} |\n", row.join(" | ")));
+                }
+            }
+            ReportFormat::Html => {
+                out.push_str("<table>\n<thead><tr>");
+                for column in &columns {
+                    out.push_str(&format!("<th>{}</th>", escape(column, format)));
+                }
+                out.push_str("</tr></thead>\n<tbody>\n");
+                for row in cells {
+                    out.push_str("<tr>");
+                    for value in row {
+                        out.push_str(&format!("<td>{}</td>", value));
+                    }
+                    out.push_str("</tr>\n");
+                }
+                out.push_str("</tbody>\n</table>\n");
+            }
+        }
+        Ok(out)
+    }
+
+    fn chart(path: &str, value: &Value, format: ReportFormat) -> Result<String, NodeError> {
+        let invalid = || {
+            NodeError::ValidationError(format!(
+                "Chart source '{}' is not an object of numbers",
+                path
+            ))
+        };
+        let Value::Object(slices) = value else {
+            return Err(invalid());
+        };
+
+        let mut chart = format!("pie title {}\n", path);
+        for (label, value) in slices {
+            let value = value.as_f64().ok_or_else(invalid)?;
+            chart.push_str(&format!(
+                "    \"{}\" : {}\n",
+                label.replace('"', "'"),
+                value
+            ));
+        }
+        Ok(match format {
+            ReportFormat::Markdown => format!("```mermaid\n{}```\n", chart),
+            ReportFormat::Html => format!(
+                "<pre class=\"mermaid\">\n{}</pre>\n",
+                escape(&chart, format)
+            ),
+        })
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for RenderReportNode {
+        type PrepResult = String;
+        type ExecResult = String;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            Self::render(&self.template, self.format, store)
+        }
+
+        async fn exec(
+            &mut self,
+            report: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            if let Some(path) = &self.output_file {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .map_err(|e| NodeError::ExecutionError(e.to_string()))?;
+                }
+                tokio::fs::write(path, &report).await.map_err(|e| {
+                    NodeError::ExecutionError(format!(
+                        "Failed to write report to {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+            }
+            Ok(report)
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            report: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            store
+                .set(self.output_key.clone(), Value::String(report))
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "RenderReportNode"
+        }
+    }
+}
+
+// ============================================================================
+// RECORD TRANSFORM NODES (feature: builtin-nodes)
+// ============================================================================
+
+/// Declarative transforms over arrays of JSON records
+#[cfg(feature = "builtin-nodes")]
+pub mod records {
+    use crate::env::{RandomSource, SeededRandom};
+    #[cfg(feature = "builtin-llm")]
+    use crate::llm::{EmbeddingRequest, LlmProvider, vector::cosine_similarity};
+    use crate::node::{ExecutionContext, KeySpec, NodeBackend, NodeError};
+    use crate::{Action, ComparisonOperator, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{Map, Value};
+    use std::cmp::Ordering;
+    use std::collections::{HashMap, HashSet};
+    #[cfg(feature = "builtin-llm")]
+    use std::sync::Arc;
+
+    /// A JSON object processed by [`TransformRecordsNode`]
+    pub type Record = Map<String, Value>;
+
+    /// Aggregate function of a group-by
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum AggregateFn {
+        /// Number of records in the group
+        Count,
+        Sum,
+        Avg,
+        Min,
+        Max,
+        /// Values of the field, in record order
+        Collect,
+    }
+
+    /// An aggregate column produced by a group-by
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Aggregate {
+        /// Field name in the output record
+        pub output: String,
+        pub function: AggregateFn,
+        /// Source field; ignored by `Count`
+        pub field: String,
+    }
+
+    impl Aggregate {
+        /// Count the records of each group
+        pub fn count(output: impl Into<String>) -> Self {
+            Self::new(output, AggregateFn::Count, "")
+        }
+
+        /// Sum a numeric field
+        pub fn sum(output: impl Into<String>, field: impl Into<String>) -> Self {
+            Self::new(output, AggregateFn::Sum, field)
+        }
+
+        /// Average a numeric field
+        pub fn avg(output: impl Into<String>, field: impl Into<String>) -> Self {
+            Self::new(output, AggregateFn::Avg, field)
+        }
+
+        /// Smallest value of a numeric field
+        pub fn min(output: impl Into<String>, field: impl Into<String>) -> Self {
+            Self::new(output, AggregateFn::Min, field)
+        }
+
+        /// Largest value of a numeric field
+        pub fn max(output: impl Into<String>, field: impl Into<String>) -> Self {
+            Self::new(output, AggregateFn::Max, field)
+        }
+
+        /// Collect the values of a field into an array
+        pub fn collect(output: impl Into<String>, field: impl Into<String>) -> Self {
+            Self::new(output, AggregateFn::Collect, field)
+        }
+
+        fn new(output: impl Into<String>, function: AggregateFn, field: impl Into<String>) -> Self {
+            Self {
+                output: output.into(),
+                function,
+                field: field.into(),
+            }
+        }
+
+        fn apply(&self, group: &[Record]) -> Value {
+            let values = group.iter().filter_map(|record| field(record, &self.field));
+            if self.function == AggregateFn::Count {
+                return Value::from(group.len());
+            }
+            if self.function == AggregateFn::Collect {
+                return Value::Array(values.cloned().collect());
+            }
+
+            let numbers: Vec<&serde_json::Number> = values
+                .filter_map(|value| match value {
+                    Value::Number(number) => Some(number),
+                    _ => None,
+                })
+                .collect();
+            let integers: Option<Vec<i64>> = numbers.iter().map(|n| n.as_i64()).collect();
+            let floats: Vec<f64> = numbers.iter().filter_map(|n| n.as_f64()).collect();
+            if floats.is_empty() {
+                return match self.function {
+                    AggregateFn::Sum => Value::from(0),
+                    _ => Value::Null,
+                };
+            }
+            match (self.function, integers) {
+                (AggregateFn::Sum, Some(integers)) => Value::from(integers.iter().sum::<i64>()),
+                (AggregateFn::Min, Some(integers)) => Value::from(integers.into_iter().min()),
+                (AggregateFn::Max, Some(integers)) => Value::from(integers.into_iter().max()),
+                (AggregateFn::Sum, None) => Value::from(floats.iter().sum::<f64>()),
+                (AggregateFn::Min, None) => {
+                    Value::from(floats.into_iter().fold(f64::INFINITY, f64::min))
+                }
+                (AggregateFn::Max, None) => {
+                    Value::from(floats.into_iter().fold(f64::NEG_INFINITY, f64::max))
+                }
+                _ => Value::from(floats.iter().sum::<f64>() / floats.len() as f64),
+            }
+        }
+    }
+
+    /// One step of a [`TransformRecordsNode`]
+    ///
+    /// Fields are read by name or dotted path (`customer.id`) and written at
+    /// the top level of each record.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub enum RecordOp {
+        /// Keep records whose field compares true against a value
+        Filter {
+            field: String,
+            operator: ComparisonOperator,
+            value: Value,
+        },
+        /// Keep only these fields
+        Select(Vec<String>),
+        /// Drop these fields
+        Drop(Vec<String>),
+        /// Rename a field
+        Rename { from: String, to: String },
+        /// Set a field to a constant
+        Set { field: String, value: Value },
+        /// Group by key fields, producing one record per group with the keys
+        /// and aggregates
+        GroupBy {
+            keys: Vec<String>,
+            aggregates: Vec<Aggregate>,
+        },
+        /// Merge in the records of another store key that share the `on`
+        /// field; unmatched records are dropped unless `keep_unmatched` is set
+        Join {
+            store_key: String,
+            on: String,
+            keep_unmatched: bool,
+        },
+        /// Sort by a field; missing values sort first
+        SortBy { field: String, descending: bool },
+        /// Keep the first records
+        Limit(usize),
+    }
+
+    fn field<'a>(record: &'a Record, path: &str) -> Option<&'a Value> {
+        let mut segments = path.split('.');
+        let mut value = record.get(segments.next()?)?;
+        for segment in segments {
+            value = match value {
+                Value::Object(map) => map.get(segment)?,
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+        match (left, right) {
+            (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::Null, Value::Null) => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
+
+    fn matches(value: Option<&Value>, operator: &ComparisonOperator, expected: &Value) -> bool {
+        let ordering = compare(value.unwrap_or(&Value::Null), expected);
+        match operator {
+            ComparisonOperator::Equal => ordering == Some(Ordering::Equal),
+            ComparisonOperator::NotEqual => ordering != Some(Ordering::Equal),
+            ComparisonOperator::GreaterThan => ordering == Some(Ordering::Greater),
+            ComparisonOperator::GreaterThanOrEqual => {
+                matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+            }
+            ComparisonOperator::LessThan => ordering == Some(Ordering::Less),
+            ComparisonOperator::LessThanOrEqual => {
+                matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+            }
+        }
+    }
+
+    fn into_records(key: &str, value: Value) -> Result<Vec<Record>, NodeError> {
+        let Value::Array(items) = value else {
+            return Err(NodeError::ValidationError(format!(
+                "Key '{}' does not hold an array of records",
+                key
+            )));
+        };
+        items
+            .into_iter()
+            .map(|item| match item {
+                Value::Object(record) => Ok(record),
+                other => Err(NodeError::ValidationError(format!(
+                    "Key '{}' holds a non-object record: {}",
+                    key, other
+                ))),
+            })
+            .collect()
+    }
+
+    impl RecordOp {
+        fn apply(
+            &self,
+            records: Vec<Record>,
+            sources: &HashMap<String, Vec<Record>>,
+        ) -> Vec<Record> {
+            match self {
+                RecordOp::Filter {
+                    field: path,
+                    operator,
+                    value,
+                } => records
+                    .into_iter()
+                    .filter(|record| matches(field(record, path), operator, value))
+                    .collect(),
+                RecordOp::Select(fields) => records
+                    .into_iter()
+                    .map(|mut record| {
+                        record.retain(|key, _| fields.contains(key));
+                        record
+                    })
+                    .collect(),
+                RecordOp::Drop(fields) => records
+                    .into_iter()
+                    .map(|mut record| {
+                        record.retain(|key, _| !fields.contains(key));
+                        record
+                    })
+                    .collect(),
+                RecordOp::Rename { from, to } => records
+                    .into_iter()
+                    .map(|mut record| {
+                        if let Some(value) = record.remove(from) {
+                            record.insert(to.clone(), value);
+                        }
+                        record
+                    })
+                    .collect(),
+                RecordOp::Set { field: name, value } => records
+                    .into_iter()
+                    .map(|mut record| {
+                        record.insert(name.clone(), value.clone());
+                        record
+                    })
+                    .collect(),
+                RecordOp::GroupBy { keys, aggregates } => {
+                    let mut groups: Vec<(Vec<Value>, Vec<Record>)> = Vec::new();
+                    for record in records {
+                        let group_key: Vec<Value> = keys
+                            .iter()
+                            .map(|key| field(&record, key).cloned().unwrap_or(Value::Null))
+                            .collect();
+                        match groups.iter_mut().find(|(k, _)| *k == group_key) {
+                            Some((_, members)) => members.push(record),
+                            None => groups.push((group_key, vec![record])),
+                        }
+                    }
+                    groups
+                        .into_iter()
+                        .map(|(group_key, members)| {
+                            let mut out: Record = keys.iter().cloned().zip(group_key).collect();
+                            for aggregate in aggregates {
+                                out.insert(aggregate.output.clone(), aggregate.apply(&members));
+                            }
+                            out
+                        })
+                        .collect()
+                }
+                RecordOp::Join {
+                    store_key,
+                    on,
+                    keep_unmatched,
+                } => {
+                    let right = sources.get(store_key).map(Vec::as_slice).unwrap_or(&[]);
+                    let mut joined = Vec::new();
+                    for record in records {
+                        let key = field(&record, on);
+                        let mut matched = false;
+                        for other in right {
+                            if key.is_some() && field(other, on) == key {
+                                let mut merged = record.clone();
+                                merged.extend(other.iter().map(|(k, v)| (k.clone(), v.clone())));
+                                joined.push(merged);
+                                matched = true;
+                            }
+                        }
+                        if !matched && *keep_unmatched {
+                            joined.push(record);
+                        }
+                    }
+                    joined
+                }
+                RecordOp::SortBy {
+                    field: path,
+                    descending,
+                } => {
+                    let mut records = records;
+                    records.sort_by(|a, b| {
+                        let ordering = match (field(a, path), field(b, path)) {
+                            (Some(a), Some(b)) => compare(a, b).unwrap_or(Ordering::Equal),
+                            (a, b) => a.is_some().cmp(&b.is_some()),
+                        };
+                        if *descending {
+                            ordering.reverse()
+                        } else {
+                            ordering
+                        }
+                    });
+                    records
+                }
+                RecordOp::Limit(limit) => records.into_iter().take(*limit).collect(),
+            }
+        }
+    }
+
+    /// A node transforming an array of records from the store
+    ///
+    /// The operations run in the order they were added, like an ETL
+    /// "Transform" stage:
+    ///
+    /// ```rust
+    /// # use pocketflow_rs::prelude::*;
+    /// # use pocketflow_rs::node::builtin::{Aggregate, TransformRecordsNode};
+    /// # use serde_json::json;
+    /// let node = TransformRecordsNode::new("orders", "revenue", Action::simple("next"))
+    ///     .filter("status", ComparisonOperator::Equal, json!("paid"))
+    ///     .join("customers", "customer_id")
+    ///     .group_by(["country"], [Aggregate::sum("revenue", "amount"), Aggregate::count("orders")])
+    ///     .sort_by("revenue", true);
+    /// ```
+    pub struct TransformRecordsNode {
+        input_key: String,
+        output_key: String,
+        ops: Vec<RecordOp>,
+        action: Action,
+    }
+
+    impl TransformRecordsNode {
+        /// Create a node reading records from `input_key` and writing the
+        /// result to `output_key`
+        pub fn new(
+            input_key: impl Into<String>,
+            output_key: impl Into<String>,
+            action: Action,
+        ) -> Self {
+            Self {
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                ops: Vec::new(),
+                action,
+            }
+        }
+
+        /// Append an operation
+        pub fn op(mut self, op: RecordOp) -> Self {
+            self.ops.push(op);
+            self
+        }
+
+        /// Keep records whose field compares true against a value
+        pub fn filter(
+            self,
+            field: impl Into<String>,
+            operator: ComparisonOperator,
+            value: Value,
+        ) -> Self {
+            self.op(RecordOp::Filter {
+                field: field.into(),
+                operator,
+                value,
+            })
+        }
+
+        /// Keep only these fields
+        pub fn select<I, F>(self, fields: I) -> Self
+        where
+            I: IntoIterator<Item = F>,
+            F: Into<String>,
+        {
+            self.op(RecordOp::Select(
+                fields.into_iter().map(Into::into).collect(),
+            ))
+        }
+
+        /// Drop these fields
+        pub fn drop_fields<I, F>(self, fields: I) -> Self
+        where
+            I: IntoIterator<Item = F>,
+            F: Into<String>,
+        {
+            self.op(RecordOp::Drop(fields.into_iter().map(Into::into).collect()))
+        }
+
+        /// Rename a field
+        pub fn rename(self, from: impl Into<String>, to: impl Into<String>) -> Self {
+            self.op(RecordOp::Rename {
+                from: from.into(),
+                to: to.into(),
+            })
+        }
+
+        /// Set a field to a constant
+        pub fn set(self, field: impl Into<String>, value: Value) -> Self {
+            self.op(RecordOp::Set {
+                field: field.into(),
+                value,
+            })
+        }
+
+        /// Group by key fields and aggregate each group
+        pub fn group_by<K, F, A>(self, keys: K, aggregates: A) -> Self
+        where
+            K: IntoIterator<Item = F>,
+            F: Into<String>,
+            A: IntoIterator<Item = Aggregate>,
+        {
+            self.op(RecordOp::GroupBy {
+                keys: keys.into_iter().map(Into::into).collect(),
+                aggregates: aggregates.into_iter().collect(),
+            })
+        }
+
+        /// Inner join with the records under another store key
+        pub fn join(self, store_key: impl Into<String>, on: impl Into<String>) -> Self {
+            self.op(RecordOp::Join {
+                store_key: store_key.into(),
+                on: on.into(),
+                keep_unmatched: false,
+            })
+        }
+
+        /// Left join with the records under another store key
+        pub fn left_join(self, store_key: impl Into<String>, on: impl Into<String>) -> Self {
+            self.op(RecordOp::Join {
+                store_key: store_key.into(),
+                on: on.into(),
+                keep_unmatched: true,
+            })
+        }
+
+        /// Sort by a field
+        pub fn sort_by(self, field: impl Into<String>, descending: bool) -> Self {
+            self.op(RecordOp::SortBy {
+                field: field.into(),
+                descending,
+            })
+        }
+
+        /// Keep the first records
+        pub fn limit(self, limit: usize) -> Self {
+            self.op(RecordOp::Limit(limit))
+        }
+
+        /// The operations, in order
+        pub fn ops(&self) -> &[RecordOp] {
+            &self.ops
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for TransformRecordsNode {
+        type PrepResult = (Vec<Record>, HashMap<String, Vec<Record>>);
+        type ExecResult = Vec<Record>;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let read = |key: &str| -> Result<Vec<Record>, NodeError> {
+                let value = store
+                    .get(key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?
+                    .ok_or_else(|| {
+                        NodeError::PrepError(format!("Key '{}' not found in store", key))
+                    })?;
+                into_records(key, value)
+            };
+
+            let records = read(&self.input_key)?;
+            let mut sources = HashMap::new();
+            for op in &self.ops {
+                if let RecordOp::Join { store_key, .. } = op
+                    && !sources.contains_key(store_key)
+                {
+                    sources.insert(store_key.clone(), read(store_key)?);
+                }
+            }
+            Ok((records, sources))
+        }
+
+        async fn exec(
+            &mut self,
+            (records, sources): Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(self
+                .ops
+                .iter()
+                .fold(records, |records, op| op.apply(records, &sources)))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            records: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            let records = records.into_iter().map(Value::Object).collect();
+            store
+                .set(self.output_key.clone(), Value::Array(records))
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "TransformRecordsNode"
+        }
+    }
+
+    /// A node that writes a random sample of an array's items, optionally
+    /// stratified by a field
+    ///
+    /// With stratification each value of the field gets a share of the sample
+    /// proportional to its share of the items (items without the field form
+    /// their own stratum). Sampled items keep their original order. The
+    /// flow's [`RuntimeEnv`](crate::RuntimeEnv) supplies the randomness unless
+    /// a seed is set, in which case every run draws the same sample.
+    pub struct SampleNode {
+        input_key: String,
+        output_key: String,
+        size: usize,
+        stratify_by: Option<String>,
+        seed: Option<u64>,
+        action: Action,
+    }
+
+    impl SampleNode {
+        /// Sample `size` items of the array under `input_key` into `output_key`
+        ///
+        /// Arrays with no more than `size` items are copied whole.
+        pub fn new(
+            input_key: impl Into<String>,
+            output_key: impl Into<String>,
+            size: usize,
+            action: Action,
+        ) -> Self {
+            Self {
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                size,
+                stratify_by: None,
+                seed: None,
+                action,
+            }
+        }
+
+        /// Sample each value of `field` in proportion to its frequency
+        pub fn stratify_by(mut self, field: impl Into<String>) -> Self {
+            self.stratify_by = Some(field.into());
+            self
+        }
+
+        /// Draw from a fixed seed instead of the flow's environment
+        pub fn with_seed(mut self, seed: u64) -> Self {
+            self.seed = Some(seed);
+            self
+        }
+
+        /// Indices of the items to keep, in order
+        fn sample(&self, items: &[Value], mut next_u64: impl FnMut() -> u64) -> Vec<usize> {
+            let mut strata: Vec<(Value, Vec<usize>)> = Vec::new();
+            for (index, item) in items.iter().enumerate() {
+                let stratum = match &self.stratify_by {
+                    Some(field) => item.get(field).cloned().unwrap_or(Value::Null),
+                    None => Value::Null,
+                };
+                match strata.iter_mut().find(|(value, _)| *value == stratum) {
+                    Some((_, indices)) => indices.push(index),
+                    None => strata.push((stratum, vec![index])),
+                }
+            }
+
+            // Largest remainder allocation of the sample across strata
+            let total = items.len();
+            let size = self.size.min(total);
+            let mut quotas: Vec<usize> = strata
+                .iter()
+                .map(|(_, indices)| size * indices.len() / total)
+                .collect();
+            let mut by_remainder: Vec<usize> = (0..strata.len()).collect();
+            by_remainder.sort_by_key(|&i| std::cmp::Reverse(size * strata[i].1.len() % total));
+            let allocated: usize = quotas.iter().sum();
+            for &i in by_remainder.iter().take(size - allocated) {
+                quotas[i] += 1;
+            }
+
+            let mut chosen = Vec::with_capacity(size);
+            for ((_, mut indices), quota) in strata.into_iter().zip(quotas) {
+                // Partial Fisher-Yates shuffle
+                for i in 0..quota {
+                    let j = i + (next_u64() % (indices.len() - i) as u64) as usize;
+                    indices.swap(i, j);
+                }
+                chosen.extend_from_slice(&indices[..quota]);
+            }
+            chosen.sort_unstable();
+            chosen
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for SampleNode {
+        type PrepResult = Vec<Value>;
+        type ExecResult = Vec<Value>;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match store
+                .get(&self.input_key)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?
+            {
+                Some(Value::Array(items)) => Ok(items),
+                Some(_) => Err(NodeError::ValidationError(format!(
+                    "Key '{}' does not hold an array",
+                    self.input_key
+                ))),
+                None => Err(NodeError::PrepError(format!(
+                    "Key '{}' not found in store",
+                    self.input_key
+                ))),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            items: Self::PrepResult,
+            context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let seeded = self.seed.map(SeededRandom::new);
+            let chosen = self.sample(&items, || match &seeded {
+                Some(rng) => rng.next_u64(),
+                None => context.env.random_u64(),
+            });
+            Ok(chosen
+                .into_iter()
+                .map(|index| items[index].clone())
+                .collect())
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            sample: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            store
+                .set(self.output_key.clone(), Value::Array(sample))
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "SampleNode"
+        }
+    }
+
+    /// A node that removes duplicate items from an array
+    ///
+    /// Items are compared by their text: strings as they are, other values
+    /// (or the field chosen with [`DedupeNode::by_field`]) as JSON. Exact
+    /// duplicates are found by hashing; with `builtin-llm`, near duplicates can
+    /// also be removed by embedding the remaining items and dropping any whose
+    /// cosine similarity to an earlier kept item reaches a threshold. The first
+    /// occurrence is kept and the order is preserved.
+    pub struct DedupeNode {
+        input_key: String,
+        output_key: String,
+        field: Option<String>,
+        normalize: bool,
+        duplicates_key: Option<String>,
+        #[cfg(feature = "builtin-llm")]
+        embedder: Option<(Arc<dyn LlmProvider>, f32)>,
+        #[cfg(feature = "builtin-llm")]
+        embedding_model: Option<String>,
+        action: Action,
+    }
+
+    impl DedupeNode {
+        /// Write the array under `input_key` without duplicates to `output_key`
+        pub fn new(
+            input_key: impl Into<String>,
+            output_key: impl Into<String>,
+            action: Action,
+        ) -> Self {
+            Self {
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                field: None,
+                normalize: false,
+                duplicates_key: None,
+                #[cfg(feature = "builtin-llm")]
+                embedder: None,
+                #[cfg(feature = "builtin-llm")]
+                embedding_model: None,
+                action,
+            }
+        }
+
+        /// Compare objects by one field instead of the whole item
+        pub fn by_field(mut self, field: impl Into<String>) -> Self {
+            self.field = Some(field.into());
+            self
+        }
+
+        /// Ignore case and differences in whitespace when comparing
+        pub fn normalized(mut self) -> Self {
+            self.normalize = true;
+            self
+        }
+
+        /// Also write the removed items to `key`
+        pub fn with_duplicates_key(mut self, key: impl Into<String>) -> Self {
+            self.duplicates_key = Some(key.into());
+            self
+        }
+
+        /// Remove items whose embedding's cosine similarity to a kept item is
+        /// at least `threshold`
+        #[cfg(feature = "builtin-llm")]
+        pub fn with_embeddings(mut self, embedder: Arc<dyn LlmProvider>, threshold: f32) -> Self {
+            self.embedder = Some((embedder, threshold));
+            self
+        }
+
+        /// Set the embeddings model
+        #[cfg(feature = "builtin-llm")]
+        pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+            self.embedding_model = Some(model.into());
+            self
+        }
+
+        /// Text an item is compared by
+        fn text(&self, item: &Value) -> String {
+            let value = match &self.field {
+                Some(field) => item.get(field).unwrap_or(&Value::Null),
+                None => item,
+            };
+            let text = match value {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            if self.normalize {
+                text.split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .to_lowercase()
+            } else {
+                text
+            }
+        }
+
+        /// Indices of the kept items among `texts`, which are already unique
+        #[cfg(feature = "builtin-llm")]
+        async fn dedupe_similar(&self, texts: Vec<String>) -> Result<Vec<usize>, NodeError> {
+            let Some((embedder, threshold)) = &self.embedder else {
+                return Ok((0..texts.len()).collect());
+            };
+            if texts.is_empty() {
+                return Ok(Vec::new());
+            }
+            let count = texts.len();
+            let mut request = EmbeddingRequest::new(texts);
+            request.model = self.embedding_model.clone();
+            let vectors = embedder
+                .embeddings(request)
+                .await
+                .map_err(|e| NodeError::ExecutionError(e.to_string()))?;
+            if vectors.len() != count {
+                return Err(NodeError::ExecutionError(format!(
+                    "Expected {} embeddings, got {}",
+                    count,
+                    vectors.len()
+                )));
+            }
+            let mut kept: Vec<usize> = Vec::new();
+            for (index, vector) in vectors.iter().enumerate() {
+                if kept
+                    .iter()
+                    .all(|&i| cosine_similarity(&vectors[i], vector) < *threshold)
+                {
+                    kept.push(index);
+                }
+            }
+            Ok(kept)
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for DedupeNode {
+        type PrepResult = Vec<Value>;
+        type ExecResult = (Vec<Value>, Vec<Value>); // (kept, removed)
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match store
+                .get(&self.input_key)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?
+            {
+                Some(Value::Array(items)) => Ok(items),
+                Some(_) => Err(NodeError::ValidationError(format!(
+                    "Key '{}' does not hold an array",
+                    self.input_key
+                ))),
+                None => Err(NodeError::PrepError(format!(
+                    "Key '{}' not found in store",
+                    self.input_key
+                ))),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            items: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let mut seen = HashSet::new();
+            let mut unique = Vec::new();
+            let mut texts = Vec::new();
+            for (index, item) in items.iter().enumerate() {
+                let text = self.text(item);
+                if seen.insert(text.clone()) {
+                    unique.push(index);
+                    texts.push(text);
+                }
+            }
+            #[cfg(feature = "builtin-llm")]
+            let unique: Vec<usize> = self
+                .dedupe_similar(texts)
+                .await?
+                .into_iter()
+                .map(|i| unique[i])
+                .collect();
+            #[cfg(not(feature = "builtin-llm"))]
+            drop(texts);
+
+            let mut kept = Vec::with_capacity(unique.len());
+            let mut removed = Vec::new();
+            let mut unique = unique.into_iter().peekable();
+            for (index, item) in items.into_iter().enumerate() {
+                if unique.next_if_eq(&index).is_some() {
+                    kept.push(item);
+                } else {
+                    removed.push(item);
+                }
+            }
+            Ok((kept, removed))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            (kept, removed): Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            store
+                .set(self.output_key.clone(), Value::Array(kept))
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            if let Some(key) = &self.duplicates_key {
+                store
+                    .set(key.clone(), Value::Array(removed))
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "DedupeNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            vec![KeySpec::new(self.input_key.clone())]
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(
+                std::iter::once(&self.output_key)
+                    .chain(&self.duplicates_key)
+                    .map(|key| KeySpec::new(key.clone()))
+                    .collect(),
+            )
+        }
+    }
+}
+
+// ============================================================================
+// JSON PATCH NODES (feature: builtin-nodes)
+// ============================================================================
+
+/// Diffing and patching JSON documents with RFC 6902 JSON Patch
+#[cfg(feature = "builtin-nodes")]
+pub mod patch {
+    use crate::node::{ExecutionContext, NodeBackend, NodeError};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    /// One operation of an RFC 6902 JSON Patch
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "op", rename_all = "lowercase")]
+    pub enum PatchOperation {
+        Add { path: String, value: Value },
+        Remove { path: String },
+        Replace { path: String, value: Value },
+        Move { from: String, path: String },
+        Copy { from: String, path: String },
+        Test { path: String, value: Value },
+    }
+
+    impl PatchOperation {
+        /// The JSON Pointer the operation writes to or tests
+        pub fn path(&self) -> &str {
+            match self {
+                PatchOperation::Add { path, .. }
+                | PatchOperation::Remove { path }
+                | PatchOperation::Replace { path, .. }
+                | PatchOperation::Move { path, .. }
+                | PatchOperation::Copy { path, .. }
+                | PatchOperation::Test { path, .. } => path,
+            }
+        }
+    }
+
+    /// Error raised when a patch cannot be applied
+    #[derive(Debug, Clone, PartialEq, thiserror::Error)]
+    pub enum PatchError {
+        #[error("Invalid JSON Pointer '{0}'")]
+        InvalidPointer(String),
+        #[error("Path '{0}' does not exist")]
+        PathNotFound(String),
+        #[error("Cannot move '{from}' into its own child '{path}'")]
+        MoveIntoChild { from: String, path: String },
+        #[error("Test failed at '{0}'")]
+        TestFailed(String),
+    }
+
+    fn escape(token: &str) -> String {
+        token.replace('~', "~0").replace('/', "~1")
+    }
+
+    fn tokens(pointer: &str) -> Result<Vec<String>, PatchError> {
+        if pointer.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(rest) = pointer.strip_prefix('/') else {
+            return Err(PatchError::InvalidPointer(pointer.to_string()));
+        };
+        Ok(rest
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect())
+    }
+
+    fn array_index(token: &str, len: usize, pointer: &str) -> Result<usize, PatchError> {
+        if token != "0" && token.starts_with('0') {
+            return Err(PatchError::InvalidPointer(pointer.to_string()));
+        }
+        token
+            .parse::<usize>()
+            .ok()
+            .filter(|&index| index < len)
+            .ok_or_else(|| PatchError::PathNotFound(pointer.to_string()))
+    }
+
+    fn get_mut<'a>(
+        document: &'a mut Value,
+        tokens: &[String],
+        pointer: &str,
+    ) -> Result<&'a mut Value, PatchError> {
+        let mut current = document;
+        for token in tokens {
+            current = match current {
+                Value::Object(map) => map.get_mut(token),
+                Value::Array(items) => {
+                    let index = array_index(token, items.len(), pointer)?;
+                    items.get_mut(index)
+                }
+                _ => None,
+            }
+            .ok_or_else(|| PatchError::PathNotFound(pointer.to_string()))?;
+        }
+        Ok(current)
+    }
+
+    fn add(document: &mut Value, pointer: &str, value: Value) -> Result<(), PatchError> {
+        let tokens = tokens(pointer)?;
+        let Some((last, parent)) = tokens.split_last() else {
+            *document = value;
+            return Ok(());
+        };
+        match get_mut(document, parent, pointer)? {
+            Value::Object(map) => {
+                map.insert(last.clone(), value);
+            }
+            Value::Array(items) if last == "-" => items.push(value),
+            Value::Array(items) => {
+                let index = array_index(last, items.len() + 1, pointer)?;
+                items.insert(index, value);
+            }
+            _ => return Err(PatchError::PathNotFound(pointer.to_string())),
+        }
+        Ok(())
+    }
+
+    fn remove(document: &mut Value, pointer: &str) -> Result<Value, PatchError> {
+        let tokens = tokens(pointer)?;
+        let Some((last, parent)) = tokens.split_last() else {
+            return Ok(std::mem::take(document));
+        };
+        match get_mut(document, parent, pointer)? {
+            Value::Object(map) => map
+                .remove(last)
+                .ok_or_else(|| PatchError::PathNotFound(pointer.to_string())),
+            Value::Array(items) => {
+                let index = array_index(last, items.len(), pointer)?;
+                Ok(items.remove(index))
+            }
+            _ => Err(PatchError::PathNotFound(pointer.to_string())),
+        }
+    }
+
+    /// Apply a patch, leaving the document unchanged if any operation fails
+    pub fn apply_patch(document: &mut Value, patch: &[PatchOperation]) -> Result<(), PatchError> {
+        let mut patched = document.clone();
+        for operation in patch {
+            match operation {
+                PatchOperation::Add { path, value } => add(&mut patched, path, value.clone())?,
+                PatchOperation::Remove { path } => {
+                    remove(&mut patched, path)?;
+                }
+                PatchOperation::Replace { path, value } => {
+                    *get_mut(&mut patched, &tokens(path)?, path)? = value.clone();
+                }
+                PatchOperation::Move { from, path } => {
+                    if path.starts_with(&format!("{}/", from)) {
+                        return Err(PatchError::MoveIntoChild {
+                            from: from.clone(),
+                            path: path.clone(),
+                        });
+                    }
+                    let value = remove(&mut patched, from)?;
+                    add(&mut patched, path, value)?;
+                }
+                PatchOperation::Copy { from, path } => {
+                    let value = get_mut(&mut patched, &tokens(from)?, from)?.clone();
+                    add(&mut patched, path, value)?;
+                }
+                PatchOperation::Test { path, value } => {
+                    if get_mut(&mut patched, &tokens(path)?, path)? != value {
+                        return Err(PatchError::TestFailed(path.clone()));
+                    }
+                }
+            }
+        }
+        *document = patched;
+        Ok(())
+    }
+
+    /// Compute a patch turning `from` into `to`
+    ///
+    /// Objects are compared key by key and arrays index by index, with
+    /// trailing items added or removed.
+    pub fn diff(from: &Value, to: &Value) -> Vec<PatchOperation> {
+        let mut patch = Vec::new();
+        diff_at(from, to, "", &mut patch);
+        patch
+    }
+
+    fn diff_at(from: &Value, to: &Value, path: &str, patch: &mut Vec<PatchOperation>) {
+        match (from, to) {
+            _ if from == to => {}
+            (Value::Object(a), Value::Object(b)) => {
+                for (key, old) in a {
+                    let child = format!("{}/{}", path, escape(key));
+                    match b.get(key) {
+                        Some(new) => diff_at(old, new, &child, patch),
+                        None => patch.push(PatchOperation::Remove { path: child }),
+                    }
+                }
+                for (key, new) in b {
+                    if !a.contains_key(key) {
+                        patch.push(PatchOperation::Add {
+                            path: format!("{}/{}", path, escape(key)),
+                            value: new.clone(),
+                        });
+                    }
+                }
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                for (index, (old, new)) in a.iter().zip(b).enumerate() {
+                    diff_at(old, new, &format!("{}/{}", path, index), patch);
+                }
+                for index in (b.len()..a.len()).rev() {
+                    patch.push(PatchOperation::Remove {
+                        path: format!("{}/{}", path, index),
+                    });
+                }
+                for (index, new) in b.iter().enumerate().skip(a.len()) {
+                    patch.push(PatchOperation::Add {
+                        path: format!("{}/{}", path, index),
+                        value: new.clone(),
+                    });
+                }
+            }
+            _ => patch.push(PatchOperation::Replace {
+                path: path.to_string(),
+                value: to.clone(),
+            }),
+        }
+    }
+
+    /// A node writing the JSON Patch between two store values
+    ///
+    /// Returns the unchanged action (the main action by default) when the
+    /// values are equal.
+    pub struct JsonDiffNode {
+        from_key: String,
+        to_key: String,
+        output_key: String,
+        action: Action,
+        unchanged_action: Option<Action>,
+    }
+
+    impl JsonDiffNode {
+        /// Create a node diffing `from_key` against `to_key`
+        pub fn new(
+            from_key: impl Into<String>,
+            to_key: impl Into<String>,
+            output_key: impl Into<String>,
+            action: Action,
+        ) -> Self {
+            Self {
+                from_key: from_key.into(),
+                to_key: to_key.into(),
+                output_key: output_key.into(),
+                action,
+                unchanged_action: None,
+            }
+        }
+
+        /// Set the action returned when the values are equal
+        pub fn with_unchanged_action(mut self, action: impl Into<Action>) -> Self {
+            self.unchanged_action = Some(action.into());
+            self
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for JsonDiffNode {
+        type PrepResult = (Value, Value);
+        type ExecResult = Vec<PatchOperation>;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let read = |key: &str| {
+                store
+                    .get(key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?
+                    .ok_or_else(|| {
+                        NodeError::PrepError(format!("Key '{}' not found in store", key))
+                    })
+            };
+            Ok((read(&self.from_key)?, read(&self.to_key)?))
+        }
+
+        async fn exec(
+            &mut self,
+            (from, to): Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(diff(&from, &to))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            patch: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            let unchanged = patch.is_empty();
+            let patch = serde_json::to_value(patch).expect("patch operations serialize to JSON");
+            store
+                .set(self.output_key.clone(), patch)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            match &self.unchanged_action {
+                Some(action) if unchanged => Ok(action.clone()),
+                _ => Ok(self.action.clone()),
+            }
+        }
+
+        fn name(&self) -> &str {
+            "JsonDiffNode"
+        }
+    }
+
+    /// Policy check run on every operation before a patch is applied
+    pub type PatchPolicy = Arc<dyn Fn(&PatchOperation) -> Result<(), String> + Send + Sync>;
+
+    /// A node applying a JSON Patch from the store to a document in the store
+    ///
+    /// The patch is checked against the policy first: every operation must
+    /// target an allowed path prefix (when any are set) and pass the custom
+    /// policy. The whole patch is then applied atomically. A rejected or
+    /// failing patch leaves the document untouched; with a rejected action
+    /// set, the node records the reason and returns that action instead of
+    /// failing.
+    pub struct JsonPatchNode {
+        document_key: String,
+        patch_key: String,
+        output_key: Option<String>,
+        allowed_paths: Vec<String>,
+        max_operations: Option<usize>,
+        policy: Option<PatchPolicy>,
+        action: Action,
+        rejected_action: Option<Action>,
+        error_key: Option<String>,
+    }
+
+    impl JsonPatchNode {
+        /// Create a node applying the patch under `patch_key` to `document_key`
+        pub fn new(
+            document_key: impl Into<String>,
+            patch_key: impl Into<String>,
+            action: Action,
+        ) -> Self {
+            Self {
+                document_key: document_key.into(),
+                patch_key: patch_key.into(),
+                output_key: None,
+                allowed_paths: Vec::new(),
+                max_operations: None,
+                policy: None,
+                action,
+                rejected_action: None,
+                error_key: None,
+            }
+        }
+
+        /// Write the patched document to another key instead of in place
+        pub fn with_output_key(mut self, key: impl Into<String>) -> Self {
+            self.output_key = Some(key.into());
+            self
+        }
+
+        /// Allow operations under a JSON Pointer prefix, such as `/settings`
+        pub fn allow_path(mut self, prefix: impl Into<String>) -> Self {
+            self.allowed_paths.push(prefix.into());
+            self
+        }
+
+        /// Reject patches with more operations than this
+        pub fn with_max_operations(mut self, max: usize) -> Self {
+            self.max_operations = Some(max);
+            self
+        }
+
+        /// Check every operation with a custom policy
+        pub fn with_policy<F>(mut self, policy: F) -> Self
+        where
+            F: Fn(&PatchOperation) -> Result<(), String> + Send + Sync + 'static,
+        {
+            self.policy = Some(Arc::new(policy));
+            self
+        }
+
+        /// Return this action instead of failing when a patch is rejected
+        pub fn with_rejected_action(mut self, action: impl Into<Action>) -> Self {
+            self.rejected_action = Some(action.into());
+            self
+        }
+
+        /// Store the reason a patch was rejected under this key
+        pub fn with_error_key(mut self, key: impl Into<String>) -> Self {
+            self.error_key = Some(key.into());
+            self
+        }
+
+        fn allowed(&self, pointer: &str) -> bool {
+            self.allowed_paths.is_empty()
+                || self
+                    .allowed_paths
+                    .iter()
+                    .any(|prefix| pointer == prefix || pointer.starts_with(&format!("{}/", prefix)))
+        }
+
+        fn check(&self, patch: &[PatchOperation]) -> Result<(), String> {
+            if let Some(max) = self.max_operations
+                && patch.len() > max
+            {
+                return Err(format!(
+                    "Patch has {} operations, more than the allowed {}",
+                    patch.len(),
+                    max
+                ));
+            }
+            for operation in patch {
+                let mut touched = vec![operation.path()];
+                if let PatchOperation::Move { from, .. } = operation {
+                    touched.push(from);
+                }
+                if let Some(path) = touched.into_iter().find(|path| !self.allowed(path)) {
+                    return Err(format!("Path '{}' is not allowed", path));
+                }
+                if let Some(policy) = &self.policy {
+                    policy(operation)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for JsonPatchNode {
+        type PrepResult = (Value, Value);
+        type ExecResult = Result<Value, String>;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let read = |key: &str| {
+                store
+                    .get(key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?
+                    .ok_or_else(|| {
+                        NodeError::PrepError(format!("Key '{}' not found in store", key))
+                    })
+            };
+            Ok((read(&self.document_key)?, read(&self.patch_key)?))
+        }
+
+        async fn exec(
+            &mut self,
+            (mut document, patch): Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let patch: Vec<PatchOperation> = match serde_json::from_value(patch) {
+                Ok(patch) => patch,
+                Err(e) => return Ok(Err(format!("Invalid JSON Patch: {}", e))),
+            };
+            if let Err(reason) = self.check(&patch) {
+                return Ok(Err(reason));
+            }
+            Ok(apply_patch(&mut document, &patch)
+                .map(|()| document)
+                .map_err(|e| e.to_string()))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            outcome: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            match outcome {
+                Ok(document) => {
+                    let key = self.output_key.as_ref().unwrap_or(&self.document_key);
+                    store
+                        .set(key.clone(), document)
+                        .map_err(|e| NodeError::StorageError(e.to_string()))?;
+                    Ok(self.action.clone())
+                }
+                Err(reason) => {
+                    if let Some(key) = &self.error_key {
+                        store
+                            .set(key.clone(), Value::String(reason.clone()))
+                            .map_err(|e| NodeError::StorageError(e.to_string()))?;
+                    }
+                    match &self.rejected_action {
+                        Some(action) => Ok(action.clone()),
+                        None => Err(NodeError::ValidationError(reason)),
+                    }
+                }
+            }
+        }
+
+        fn name(&self) -> &str {
+            "JsonPatchNode"
+        }
+    }
+}
+
+// ============================================================================
+// TASK QUEUE NODES (feature: builtin-nodes)
+// ============================================================================
+
+/// Nodes feeding and draining a [`TaskQueue`](crate::shared_store::TaskQueue)
+#[cfg(feature = "builtin-nodes")]
+pub mod tasks {
+    use crate::node::{ExecutionContext, KeySpec, NodeBackend, NodeError};
+    use crate::shared_store::TaskQueue;
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use serde_json::Value;
+
+    /// A node that pushes the value under a key onto a task queue
+    ///
+    /// With [`EnqueueTaskNode::each`] the value must be an array and every item
+    /// becomes a task, so a planner's list of steps can be queued at once.
+    pub struct EnqueueTaskNode {
+        queue: TaskQueue,
+        input_key: String,
+        each: bool,
+        priority: i64,
+        priority_field: Option<String>,
+        action: Action,
+    }
+
+    impl EnqueueTaskNode {
+        /// Push the value under `input_key` onto the queue under `queue_key`
+        pub fn new(
+            queue_key: impl Into<String>,
+            input_key: impl Into<String>,
+            action: Action,
+        ) -> Self {
+            Self {
+                queue: TaskQueue::new(queue_key),
+                input_key: input_key.into(),
+                each: false,
+                priority: 0,
+                priority_field: None,
+                action,
+            }
+        }
+
+        /// Push every item of the array under the input key
+        pub fn each(mut self) -> Self {
+            self.each = true;
+            self
+        }
+
+        /// Set the priority of pushed tasks (default: 0)
+        pub fn with_priority(mut self, priority: i64) -> Self {
+            self.priority = priority;
+            self
+        }
+
+        /// Take each task's priority from an integer field of it, if present
+        pub fn with_priority_field(mut self, field: impl Into<String>) -> Self {
+            self.priority_field = Some(field.into());
+            self
+        }
+
+        fn priority(&self, task: &Value) -> i64 {
+            self.priority_field
+                .as_ref()
+                .and_then(|field| task.get(field))
+                .and_then(Value::as_i64)
+                .unwrap_or(self.priority)
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for EnqueueTaskNode {
+        type PrepResult = Vec<Value>;
+        type ExecResult = ();
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let value = store
+                .get(&self.input_key)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?
+                .ok_or_else(|| {
+                    NodeError::PrepError(format!(
+                        "Input key '{}' not found in store",
+                        self.input_key
+                    ))
+                })?;
+            match value {
+                Value::Array(tasks) if self.each => Ok(tasks),
+                _ if self.each => Err(NodeError::ValidationError(format!(
+                    "Key '{}' does not hold an array",
+                    self.input_key
+                ))),
+                task => Ok(vec![task]),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            _tasks: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(())
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            tasks: Self::PrepResult,
+            _exec_result: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            for task in tasks {
+                let priority = self.priority(&task);
+                self.queue
+                    .push(store, task, priority)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "EnqueueTaskNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            vec![KeySpec::new(self.input_key.clone())]
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(vec![KeySpec::new(self.queue.key())])
+        }
+    }
+
+    /// A node that takes the next task off a task queue
+    ///
+    /// The task (`id`, `priority`, `status` and `payload`) is written to the
+    /// output key and the node returns `task`, or `empty` when no task is
+    /// pending. With [`DequeueTaskNode::complete_previous`] the task left in
+    /// the output key by the last run is marked done first, which closes an
+    /// executor loop without a separate node.
+    pub struct DequeueTaskNode {
+        queue: TaskQueue,
+        output_key: String,
+        complete_previous: bool,
+        task_action: Action,
+        empty_action: Action,
+    }
+
+    impl DequeueTaskNode {
+        /// Take the next task of the queue under `queue_key` into `output_key`
+        pub fn new(queue_key: impl Into<String>, output_key: impl Into<String>) -> Self {
+            Self {
+                queue: TaskQueue::new(queue_key),
+                output_key: output_key.into(),
+                complete_previous: false,
+                task_action: Action::simple("task"),
+                empty_action: Action::simple("empty"),
+            }
+        }
+
+        /// Mark the task in the output key done before taking the next one
+        pub fn complete_previous(mut self) -> Self {
+            self.complete_previous = true;
+            self
+        }
+
+        /// Set the actions returned when a task was taken and when none is pending
+        pub fn with_actions(mut self, task: Action, empty: Action) -> Self {
+            self.task_action = task;
+            self.empty_action = empty;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for DequeueTaskNode {
+        type PrepResult = ();
+        type ExecResult = ();
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            _store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            Ok(())
+        }
+
+        async fn exec(
+            &mut self,
+            _prep_result: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(())
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            _exec_result: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            let storage_error =
+                |e: crate::SharedStoreError<S::Error>| NodeError::StorageError(e.to_string());
+            if self.complete_previous {
+                let previous = store
+                    .get(&self.output_key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+                if let Some(id) = previous.as_ref().and_then(|task| task["id"].as_u64()) {
+                    self.queue.mark_done(store, id).map_err(storage_error)?;
+                }
+            }
+            match self.queue.pop(store).map_err(storage_error)? {
+                Some(task) => {
+                    let task = serde_json::to_value(task)
+                        .map_err(|e| NodeError::ExecutionError(e.to_string()))?;
+                    store
+                        .set(self.output_key.clone(), task)
+                        .map_err(storage_error)?;
+                    Ok(self.task_action.clone())
+                }
+                None => {
+                    store
+                        .remove(&self.output_key)
+                        .map_err(|e| NodeError::StorageError(e.to_string()))?;
+                    Ok(self.empty_action.clone())
+                }
+            }
+        }
+
+        fn name(&self) -> &str {
+            "DequeueTaskNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            vec![KeySpec::new(self.queue.key())]
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(vec![
+                KeySpec::new(self.queue.key()),
+                KeySpec::new(self.output_key.clone()),
+            ])
+        }
+    }
+}
+
+// ============================================================================
+// GRAPH MEMORY NODES (feature: builtin-nodes)
+// ============================================================================
+
+/// Nodes reading and writing a [`KnowledgeGraph`](crate::shared_store::KnowledgeGraph)
+#[cfg(feature = "builtin-nodes")]
+pub mod graph {
+    use crate::node::{ExecutionContext, KeySpec, NodeBackend, NodeError};
+    use crate::shared_store::{Direction, KnowledgeGraph, Subgraph};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use serde_json::Value;
+
+    /// A fact parsed by [`UpsertFactNode`]
+    enum Fact {
+        Entity {
+            id: String,
+            properties: Value,
+        },
+        Relation {
+            subject: String,
+            relation: String,
+            object: String,
+            properties: Value,
+        },
+    }
+
+    impl Fact {
+        fn parse(value: &Value) -> Result<Self, String> {
+            let field = |name: &str| match value.get(name) {
+                Some(Value::String(text)) => Ok(Some(text.clone())),
+                Some(_) => Err(format!("'{}' is not a string", name)),
+                None => Ok(None),
+            };
+            let subject = field("subject")?.ok_or("missing 'subject'")?;
+            let properties = value.get("properties").cloned().unwrap_or(Value::Null);
+            if !matches!(properties, Value::Null | Value::Object(_)) {
+                return Err("'properties' is not an object".to_string());
+            }
+            match (field("relation")?, field("object")?) {
+                (Some(relation), Some(object)) => Ok(Fact::Relation {
+                    subject,
+                    relation,
+                    object,
+                    properties,
+                }),
+                (None, None) => Ok(Fact::Entity {
+                    id: subject,
+                    properties,
+                }),
+                _ => Err("'relation' and 'object' must be given together".to_string()),
+            }
+        }
+    }
+
+    /// A node that records facts in a knowledge graph
+    ///
+    /// The input key holds a fact or an array of facts, typically extracted
+    /// by an LLM. A fact with `relation` and `object` adds a relation between
+    /// two entities, one with only a `subject` updates that entity; either may
+    /// carry `properties`, which are merged in:
+    ///
+    /// ```json
+    /// [
+    ///   {"subject": "ada", "relation": "works_at", "object": "acme", "properties": {"since": 2021}},
+    ///   {"subject": "ada", "properties": {"role": "engineer"}}
+    /// ]
+    /// ```
+    ///
+    /// All facts are checked before any is written.
+    pub struct UpsertFactNode {
+        graph: KnowledgeGraph,
+        input_key: String,
+        action: Action,
+    }
+
+    impl UpsertFactNode {
+        /// Record the facts under `input_key` in the graph under `graph_key`
+        pub fn new(
+            graph_key: impl Into<String>,
+            input_key: impl Into<String>,
+            action: Action,
+        ) -> Self {
+            Self {
+                graph: KnowledgeGraph::new(graph_key),
+                input_key: input_key.into(),
+                action,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for UpsertFactNode {
+        type PrepResult = Vec<Value>;
+        type ExecResult = ();
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match store
+                .get(&self.input_key)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?
+            {
+                Some(Value::Array(facts)) => Ok(facts),
+                Some(fact) => Ok(vec![fact]),
+                None => Err(NodeError::PrepError(format!(
+                    "Input key '{}' not found in store",
+                    self.input_key
+                ))),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            facts: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            for (index, fact) in facts.iter().enumerate() {
+                Fact::parse(fact).map_err(|message| {
+                    NodeError::ValidationError(format!("Invalid fact {}: {}", index, message))
+                })?;
+            }
+            Ok(())
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            facts: Self::PrepResult,
+            _exec_result: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            for fact in facts.iter().filter_map(|fact| Fact::parse(fact).ok()) {
+                match fact {
+                    Fact::Entity { id, properties } => {
+                        self.graph.upsert_node(store, &id, properties)
+                    }
+                    Fact::Relation {
+                        subject,
+                        relation,
+                        object,
+                        properties,
+                    } => self
+                        .graph
+                        .upsert_edge(store, &subject, &relation, &object, properties),
+                }
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "UpsertFactNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            vec![KeySpec::new(self.input_key.clone())]
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(vec![KeySpec::new(self.graph.key())])
+        }
+    }
+
+    /// A node that looks up the neighbourhood of an entity in a knowledge graph
+    ///
+    /// The entity id is read from a key and the [`Subgraph`] within the
+    /// configured depth (1 by default) is written to the output key as
+    /// `{"nodes": [...], "edges": [...]}`, ready to render into a prompt.
+    pub struct QueryGraphNode {
+        graph: KnowledgeGraph,
+        subject_key: String,
+        output_key: String,
+        relation: Option<String>,
+        direction: Direction,
+        depth: usize,
+        action: Action,
+        not_found_action: Option<Action>,
+    }
+
+    impl QueryGraphNode {
+        /// Query around the entity named under `subject_key`, writing the
+        /// result to `output_key`
+        pub fn new(
+            graph_key: impl Into<String>,
+            subject_key: impl Into<String>,
+            output_key: impl Into<String>,
+            action: Action,
+        ) -> Self {
+            Self {
+                graph: KnowledgeGraph::new(graph_key),
+                subject_key: subject_key.into(),
+                output_key: output_key.into(),
+                relation: None,
+                direction: Direction::Both,
+                depth: 1,
+                action,
+                not_found_action: None,
+            }
+        }
+
+        /// Follow only relations with this label
+        pub fn with_relation(mut self, relation: impl Into<String>) -> Self {
+            self.relation = Some(relation.into());
+            self
+        }
+
+        /// Set which relations of each entity are followed (default: both)
+        pub fn with_direction(mut self, direction: Direction) -> Self {
+            self.direction = direction;
+            self
+        }
+
+        /// Set how many relations away entities may be
+        pub fn with_depth(mut self, depth: usize) -> Self {
+            self.depth = depth;
+            self
+        }
+
+        /// Return `action` when the entity isn't in the graph
+        pub fn with_not_found_action(mut self, action: Action) -> Self {
+            self.not_found_action = Some(action);
+            self
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for QueryGraphNode {
+        type PrepResult = Subgraph;
+        type ExecResult = Value;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let subject = match store
+                .get(&self.subject_key)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?
+            {
+                Some(Value::String(subject)) => subject,
+                Some(_) => {
+                    return Err(NodeError::ValidationError(format!(
+                        "Key '{}' does not hold a string",
+                        self.subject_key
+                    )));
+                }
+                None => {
+                    return Err(NodeError::PrepError(format!(
+                        "Input key '{}' not found in store",
+                        self.subject_key
+                    )));
+                }
+            };
+            self.graph
+                .subgraph(
+                    store,
+                    &subject,
+                    self.relation.as_deref(),
+                    self.direction,
+                    self.depth,
+                )
+                .map_err(|e| NodeError::StorageError(e.to_string()))
+        }
+
+        async fn exec(
+            &mut self,
+            subgraph: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            serde_json::to_value(subgraph).map_err(|e| NodeError::ExecutionError(e.to_string()))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            subgraph: Self::PrepResult,
+            output: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            store
+                .set(self.output_key.clone(), output)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            match &self.not_found_action {
+                Some(action) if subgraph.nodes.is_empty() => Ok(action.clone()),
+                _ => Ok(self.action.clone()),
+            }
+        }
+
+        fn name(&self) -> &str {
+            "QueryGraphNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            vec![
+                KeySpec::new(self.graph.key()),
+                KeySpec::new(self.subject_key.clone()),
+            ]
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(vec![KeySpec::new(self.output_key.clone())])
+        }
+    }
+}
+
+// ============================================================================
+// LLM NODES (feature: builtin-llm)
+// ============================================================================
+
+/// LLM-related nodes for AI interactions
+#[cfg(feature = "builtin-llm")]
+pub mod llm {
+    use crate::http::HttpConfig;
+    use crate::llm::context::ContextWindow;
+    use crate::llm::{
+        ChatMessage, ChatRequest, ChatResponse, LlmError, LlmProvider, OpenAiProvider,
+        ResponseFormat,
+    };
+    use crate::node::{ExecutionContext, KeySpec, NodeBackend, NodeError, Outputs, TokenSender};
+    use crate::secrets::Secret;
+    use crate::tenant::TenantContext;
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_openai::config::AzureConfig;
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::Duration; // For stream processing
+
+    /// Errors from loading an [`ApiConfig`] from the environment
+    #[derive(Debug, thiserror::Error)]
+    pub enum ApiConfigError {
+        #[error("Unknown LLM provider: {0}")]
+        UnknownProvider(String),
+        #[error("Missing API key: set {0} or POCKETFLOW_API_KEY")]
+        MissingApiKey(&'static str),
+        #[error("Missing required variable: {0}")]
+        MissingVar(&'static str),
+        #[error("Invalid value for {key}: {value}")]
+        InvalidValue { key: &'static str, value: String },
+    }
+
+    /// How a provider expects the API key to be sent
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum AuthStyle {
+        /// `Authorization: Bearer <key>`
+        Bearer,
+        /// `api-key: <key>` (Azure OpenAI)
+        ApiKeyHeader,
+    }
+
+    /// OpenAI-compatible API providers with known defaults
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum Provider {
+        #[default]
+        OpenAi,
+        AzureOpenAi,
+        DeepSeek,
+        OpenRouter,
+        Groq,
+        Together,
+    }
+
+    impl Provider {
+        /// All presets
+        pub const ALL: [Provider; 6] = [
+            Provider::OpenAi,
+            Provider::AzureOpenAi,
+            Provider::DeepSeek,
+            Provider::OpenRouter,
+            Provider::Groq,
+            Provider::Together,
+        ];
+
+        /// Look up a preset by name (e.g. `"openai"`, `"azure-openai"`, `"groq"`)
+        pub fn from_name(name: &str) -> Option<Self> {
+            let name = name.trim().to_ascii_lowercase().replace('_', "-");
+            match name.as_str() {
+                "azure" => Some(Provider::AzureOpenAi),
+                _ => Self::ALL.into_iter().find(|p| p.name() == name),
+            }
+        }
+
+        /// Canonical preset name
+        pub fn name(&self) -> &'static str {
+            match self {
+                Provider::OpenAi => "openai",
+                Provider::AzureOpenAi => "azure-openai",
+                Provider::DeepSeek => "deepseek",
+                Provider::OpenRouter => "openrouter",
+                Provider::Groq => "groq",
+                Provider::Together => "together",
+            }
+        }
+
+        /// Base URL of the provider's OpenAI-compatible API
+        ///
+        /// Azure has no fixed URL; its endpoint is specific to each resource.
+        pub fn base_url(&self) -> Option<&'static str> {
+            match self {
+                Provider::OpenAi => Some("https://api.openai.com/v1"),
+                Provider::AzureOpenAi => None,
+                Provider::DeepSeek => Some("https://api.deepseek.com/v1"),
+                Provider::OpenRouter => Some("https://openrouter.ai/api/v1"),
+                Provider::Groq => Some("https://api.groq.com/openai/v1"),
+                Provider::Together => Some("https://api.together.xyz/v1"),
+            }
+        }
+
+        /// Model used when none is configured
+        pub fn default_model(&self) -> &'static str {
+            match self {
+                Provider::OpenAi | Provider::AzureOpenAi => "gpt-4o-mini",
+                Provider::DeepSeek => "deepseek-chat",
+                Provider::OpenRouter => "openai/gpt-4o-mini",
+                Provider::Groq => "llama-3.1-8b-instant",
+                Provider::Together => "meta-llama/Llama-3.3-70B-Instruct-Turbo",
+            }
+        }
+
+        /// Environment variable conventionally holding the provider's API key
+        pub fn api_key_env(&self) -> &'static str {
+            match self {
+                Provider::OpenAi => "OPENAI_API_KEY",
+                Provider::AzureOpenAi => "AZURE_OPENAI_API_KEY",
+                Provider::DeepSeek => "DEEPSEEK_API_KEY",
+                Provider::OpenRouter => "OPENROUTER_API_KEY",
+                Provider::Groq => "GROQ_API_KEY",
+                Provider::Together => "TOGETHER_API_KEY",
+            }
+        }
+
+        /// How the API key is sent
+        pub fn auth_style(&self) -> AuthStyle {
+            match self {
+                Provider::AzureOpenAi => AuthStyle::ApiKeyHeader,
+                _ => AuthStyle::Bearer,
+            }
+        }
+    }
+
+    /// Default Azure OpenAI `api-version` query parameter
+    pub const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
+
+    /// Configuration for API requests
+    ///
+    /// Start from [`ApiConfig::new`], [`ApiConfig::for_provider`] or
+    /// [`ApiConfig::from_env`] and adjust it with the `with_*` methods. A
+    /// struct literal should end in `..Default::default()`: fields are added
+    /// as providers gain options, each defaulting to the previous behaviour.
+    ///
+    /// ```rust
+    /// # use pocketflow_rs::node::builtin::llm::ApiConfig;
+    /// let config = ApiConfig {
+    ///     model: "gpt-4o-mini".to_string(),
+    ///     stream: true,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(config.max_tokens, ApiConfig::default().max_tokens);
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct ApiConfig {
+        /// API key for authentication, resolved for each client build
+        pub api_key: Secret,
+        /// Base URL for the API (optional, defaults to OpenAI)
+        pub base_url: Option<String>,
+        /// Organization ID (optional)
+        pub org_id: Option<String>,
+        /// Model to use for requests
+        pub model: String,
+        /// Maximum tokens for response
+        pub max_tokens: Option<u16>,
+        /// Temperature for response generation
+        pub temperature: Option<f32>,
+        /// Request timeout in seconds
+        pub timeout: Option<u64>,
+        /// Top-p sampling parameter
+        pub top_p: Option<f32>,
+        /// Frequency penalty
+        pub frequency_penalty: Option<f32>,
+        /// Presence penalty
+        pub presence_penalty: Option<f32>,
+        /// Enable streaming response (default: false)
+        pub stream: bool,
+        /// Provider preset the config targets
+        pub provider: Provider,
+        /// Azure OpenAI deployment name
+        pub deployment: Option<String>,
+        /// Azure OpenAI `api-version` query parameter
+        pub api_version: Option<String>,
+        /// Output format to request, e.g. JSON mode
+        pub response_format: Option<ResponseFormat>,
+        /// Sampling seed for reproducible outputs
+        pub seed: Option<i64>,
+        /// Proxy URL for API traffic; the environment's proxy is used when unset
+        pub proxy: Option<String>,
+        /// PEM files of extra CA certificates to trust, e.g. a corporate root
+        pub ca_certificates: Vec<PathBuf>,
+        /// Skip TLS certificate verification (default: false)
+        pub accept_invalid_certs: bool,
+    }
+
+    impl Default for ApiConfig {
+        fn default() -> Self {
+            Self {
+                api_key: Secret::env("OPENAI_API_KEY"),
+                base_url: None,
+                org_id: None,
+                model: "gpt-3.5-turbo".to_string(),
+                max_tokens: Some(1000),
+                temperature: Some(0.7),
+                timeout: Some(30),
+                top_p: None,
+                frequency_penalty: None,
+                presence_penalty: None,
+                stream: false,
+                provider: Provider::OpenAi,
+                deployment: None,
+                api_version: None,
+                response_format: None,
+                seed: None,
+                proxy: None,
+                ca_certificates: Vec::new(),
+                accept_invalid_certs: false,
+            }
+        }
+    }
+
+    impl ApiConfig {
+        /// Create a new ApiConfig with an API key
+        pub fn new(api_key: impl Into<Secret>) -> Self {
+            Self {
+                api_key: api_key.into(),
+                ..Default::default()
+            }
+        }
+
+        /// Create an Azure OpenAI config for a resource endpoint and deployment
+        ///
+        /// `endpoint` is the resource URL, e.g. `https://my-resource.openai.azure.com`.
+        pub fn azure(
+            endpoint: impl Into<String>,
+            api_key: impl Into<Secret>,
+            deployment: impl Into<String>,
+        ) -> Self {
+            Self {
+                api_key: api_key.into(),
+                base_url: Some(endpoint.into()),
+                provider: Provider::AzureOpenAi,
+                deployment: Some(deployment.into()),
+                api_version: Some(DEFAULT_AZURE_API_VERSION.to_string()),
+                ..Default::default()
+            }
+        }
+
+        /// Deployment requests are sent to on Azure OpenAI
+        ///
+        /// Falls back to the model name, matching the common setup where each
+        /// deployment is named after its model.
+        pub fn azure_deployment(&self) -> &str {
+            self.deployment.as_deref().unwrap_or(&self.model)
+        }
+
+        /// Resource endpoint requests are sent to on Azure OpenAI
+        pub(crate) fn azure_endpoint(&self) -> Result<&str, LlmError> {
+            self.base_url.as_deref().ok_or_else(|| {
+                LlmError::InvalidRequest(
+                    "Azure OpenAI requires the resource endpoint as base_url".to_string(),
+                )
+            })
+        }
+
+        /// Build the async-openai config for Azure OpenAI
+        pub(crate) fn azure_config(&self, api_key: &str) -> Result<AzureConfig, LlmError> {
+            Ok(AzureConfig::new()
+                .with_api_base(self.azure_endpoint()?.trim_end_matches('/'))
+                .with_api_key(api_key)
+                .with_deployment_id(self.azure_deployment())
+                .with_api_version(
+                    self.api_version
+                        .as_deref()
+                        .unwrap_or(DEFAULT_AZURE_API_VERSION),
+                ))
+        }
+
+        /// Create a config for a provider preset, reading the API key from the
+        /// provider's conventional environment variable
+        pub fn for_provider(provider: Provider) -> Self {
+            Self {
+                api_key: Secret::env(provider.api_key_env()),
+                base_url: provider.base_url().map(str::to_string),
+                model: provider.default_model().to_string(),
+                provider,
+                api_version: (provider == Provider::AzureOpenAi)
+                    .then(|| DEFAULT_AZURE_API_VERSION.to_string()),
+                ..Default::default()
+            }
+        }
+
+        /// Load configuration from environment variables, falling back to a
+        /// `.env` file in the current directory or its parents
+        ///
+        /// | Variable | Meaning |
+        /// |---|---|
+        /// | `POCKETFLOW_PROVIDER` | Preset name (default `openai`) |
+        /// | `POCKETFLOW_API_KEY` | API key (else the provider's own variable, e.g. `GROQ_API_KEY`) |
+        /// | `POCKETFLOW_BASE_URL` | Override the preset base URL |
+        /// | `POCKETFLOW_MODEL` | Model (default: preset model) |
+        /// | `POCKETFLOW_MAX_TOKENS`, `POCKETFLOW_TEMPERATURE`, `POCKETFLOW_TIMEOUT` | Request settings |
+        /// | `OPENAI_ORG_ID` | OpenAI organization |
+        /// | `AZURE_OPENAI_ENDPOINT` | Azure resource endpoint (required for Azure) |
+        /// | `AZURE_OPENAI_DEPLOYMENT` | Azure deployment (default: the model) |
+        /// | `AZURE_OPENAI_API_VERSION` | Azure API version |
+        /// | `POCKETFLOW_PROXY` | Proxy URL |
+        /// | `POCKETFLOW_CA_CERTS` | Extra CA certificate files, separated like `PATH` |
+        /// | `POCKETFLOW_ACCEPT_INVALID_CERTS` | `true` to skip TLS verification |
+        ///
+        /// Variables already set in the process take precedence over `.env`.
+        pub fn from_env() -> Result<Self, ApiConfigError> {
+            let dotenv: std::collections::HashMap<String, String> = dotenvy::dotenv_iter()
+                .map(|iter| iter.filter_map(Result::ok).collect())
+                .unwrap_or_default();
+            Self::from_vars(|key| std::env::var(key).ok().or_else(|| dotenv.get(key).cloned()))
+        }
+
+        /// Build a config from a variable lookup, as described in [`ApiConfig::from_env`]
+        pub fn from_vars<F>(lookup: F) -> Result<Self, ApiConfigError>
+        where
+            F: Fn(&str) -> Option<String>,
+        {
+            let var = |key: &str| lookup(key).filter(|value| !value.trim().is_empty());
+            fn parse<T: std::str::FromStr>(
+                key: &'static str,
+                value: Option<String>,
+            ) -> Result<Option<T>, ApiConfigError> {
+                value
+                    .map(|value| {
+                        value
+                            .trim()
+                            .parse()
+                            .map_err(|_| ApiConfigError::InvalidValue { key, value })
+                    })
+                    .transpose()
+            }
+
+            let provider = match var("POCKETFLOW_PROVIDER") {
+                Some(name) => {
+                    Provider::from_name(&name).ok_or(ApiConfigError::UnknownProvider(name))?
+                }
+                None => Provider::OpenAi,
+            };
+
+            let mut config = Self {
+                api_key: var("POCKETFLOW_API_KEY")
+                    .or_else(|| var(provider.api_key_env()))
+                    .map(Secret::new)
+                    .ok_or(ApiConfigError::MissingApiKey(provider.api_key_env()))?,
+                base_url: var("POCKETFLOW_BASE_URL")
+                    .or_else(|| provider.base_url().map(str::to_string)),
+                org_id: var("OPENAI_ORG_ID"),
+                model: var("POCKETFLOW_MODEL")
+                    .unwrap_or_else(|| provider.default_model().to_string()),
+                provider,
+                ..Default::default()
+            };
+            if let Some(max_tokens) = parse("POCKETFLOW_MAX_TOKENS", var("POCKETFLOW_MAX_TOKENS"))?
+            {
+                config.max_tokens = Some(max_tokens);
+            }
+            if let Some(temperature) =
+                parse("POCKETFLOW_TEMPERATURE", var("POCKETFLOW_TEMPERATURE"))?
+            {
+                config.temperature = Some(temperature);
+            }
+            if let Some(timeout) = parse("POCKETFLOW_TIMEOUT", var("POCKETFLOW_TIMEOUT"))? {
+                config.timeout = Some(timeout);
+            }
+            config.proxy = var("POCKETFLOW_PROXY");
+            if let Some(paths) = var("POCKETFLOW_CA_CERTS") {
+                config.ca_certificates = std::env::split_paths(&paths).collect();
+            }
+            config.accept_invalid_certs = parse(
+                "POCKETFLOW_ACCEPT_INVALID_CERTS",
+                var("POCKETFLOW_ACCEPT_INVALID_CERTS"),
+            )?
+            .unwrap_or(false);
+
+            if provider == Provider::AzureOpenAi {
+                if config.base_url.is_none() {
+                    config.base_url = Some(
+                        var("AZURE_OPENAI_ENDPOINT")
+                            .ok_or(ApiConfigError::MissingVar("AZURE_OPENAI_ENDPOINT"))?,
+                    );
+                }
+                config.deployment = var("AZURE_OPENAI_DEPLOYMENT");
+                config.api_version = Some(
+                    var("AZURE_OPENAI_API_VERSION")
+                        .unwrap_or_else(|| DEFAULT_AZURE_API_VERSION.to_string()),
+                );
+            }
+
+            Ok(config)
+        }
+
+        /// Set the API key, e.g. a [`Secret::file`] to follow key rotation
+        pub fn with_api_key(mut self, api_key: impl Into<Secret>) -> Self {
+            self.api_key = api_key.into();
+            self
+        }
+
+        /// Set the provider preset without changing other settings
+        pub fn with_provider(mut self, provider: Provider) -> Self {
+            self.provider = provider;
+            self
+        }
+
+        /// Set the Azure OpenAI deployment name
+        pub fn with_deployment(mut self, deployment: impl Into<String>) -> Self {
+            self.deployment = Some(deployment.into());
+            self
+        }
+
+        /// Set the Azure OpenAI `api-version`
+        pub fn with_api_version(mut self, api_version: impl Into<String>) -> Self {
+            self.api_version = Some(api_version.into());
+            self
+        }
+
+        /// Set the model to use
+        pub fn with_model(mut self, model: impl Into<String>) -> Self {
+            self.model = model.into();
+            self
+        }
+
+        /// Set the base URL for the API
+        pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+            self.base_url = Some(base_url.into());
+            self
+        }
+
+        /// Set the organization ID
+        pub fn with_org_id(mut self, org_id: impl Into<String>) -> Self {
+            self.org_id = Some(org_id.into());
+            self
+        }
+
+        /// Set maximum tokens for response
+        pub fn with_max_tokens(mut self, max_tokens: u16) -> Self {
+            self.max_tokens = Some(max_tokens);
+            self
+        }
+
+        /// Set temperature for response generation
+        pub fn with_temperature(mut self, temperature: f32) -> Self {
+            self.temperature = Some(temperature);
+            self
+        }
+
+        /// Set request timeout in seconds
+        pub fn with_timeout(mut self, timeout: u64) -> Self {
+            self.timeout = Some(timeout);
+            self
+        }
+
+        /// Set top-p sampling parameter
+        pub fn with_top_p(mut self, top_p: f32) -> Self {
+            self.top_p = Some(top_p);
+            self
+        }
+
+        /// Set frequency penalty
+        pub fn with_frequency_penalty(mut self, frequency_penalty: f32) -> Self {
+            self.frequency_penalty = Some(frequency_penalty);
+            self
+        }
+
+        /// Set presence penalty
+        pub fn with_presence_penalty(mut self, presence_penalty: f32) -> Self {
+            self.presence_penalty = Some(presence_penalty);
+            self
+        }
+
+        /// Ask for an output format
+        pub fn with_response_format(mut self, format: ResponseFormat) -> Self {
+            self.response_format = Some(format);
+            self
+        }
+
+        /// Ask for any JSON object (`response_format: json_object`)
+        pub fn with_json_mode(self) -> Self {
+            self.with_response_format(ResponseFormat::JsonObject)
+        }
+
+        /// Ask for strict JSON matching `schema` (`response_format: json_schema`)
+        pub fn with_json_schema(self, name: impl Into<String>, schema: Value) -> Self {
+            self.with_response_format(ResponseFormat::json_schema(name, schema))
+        }
+
+        /// Set the sampling seed
+        pub fn with_seed(mut self, seed: i64) -> Self {
+            self.seed = Some(seed);
+            self
+        }
+
+        /// Enable or disable streaming
+        pub fn with_stream(mut self, stream: bool) -> Self {
+            self.stream = stream;
+            self
+        }
+
+        /// Route API traffic through a proxy, e.g. `http://proxy.corp:3128`
+        pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+            self.proxy = Some(proxy.into());
+            self
+        }
+
+        /// Trust the CA certificates in a PEM file, e.g. for a proxy that
+        /// re-signs TLS traffic with an internal CA
+        pub fn with_ca_certificate(mut self, path: impl Into<PathBuf>) -> Self {
+            self.ca_certificates.push(path.into());
+            self
+        }
+
+        /// Turn TLS certificate verification off or back on
+        ///
+        /// Prefer [`ApiConfig::with_ca_certificate`]; this is meant for local
+        /// servers with self-signed certificates.
+        pub fn with_accept_invalid_certs(mut self, accept: bool) -> Self {
+            self.accept_invalid_certs = accept;
+            self
+        }
+
+        /// Settings of the HTTP client API requests need, or `None` if the
+        /// default client will do
+        pub fn http_config(&self) -> Option<HttpConfig> {
+            if self.proxy.is_none() && self.ca_certificates.is_empty() && !self.accept_invalid_certs
+            {
+                return None;
+            }
+            Some(HttpConfig {
+                proxy: self.proxy.clone(),
+                ca_certificates: self.ca_certificates.clone(),
+                accept_invalid_certs: self.accept_invalid_certs,
+                ..Default::default()
+            })
+        }
+    }
+
+    // LLM nodes implementation will be added here
+
+    /// A mock LLM node for testing and examples
+    pub struct MockLlmNode {
+        prompt_key: String,
+        output_key: String,
+        mock_response: String,
+        action: Action,
+        max_retries: usize,
+        retry_delay: Duration,
+        failure_rate: f64,
+        token_sender: Option<TokenSender>,
+    }
+
+    impl MockLlmNode {
+        /// Create a new mock LLM node
+        pub fn new<S1, S2, S3>(
+            prompt_key: S1,
+            output_key: S2,
+            mock_response: S3,
+            action: Action,
+        ) -> Self
+        where
+            S1: Into<String>,
+            S2: Into<String>,
+            S3: Into<String>,
+        {
+            Self {
+                prompt_key: prompt_key.into(),
+                output_key: output_key.into(),
+                mock_response: mock_response.into(),
+                action,
+                max_retries: 3,
+                retry_delay: Duration::from_secs(1),
+                failure_rate: 0.0,
+                token_sender: None,
+            }
+        }
+
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+
+        /// Set retry delay
+        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+            self.retry_delay = delay;
+            self
+        }
+
+        /// Set failure rate for testing retry logic
+        pub fn with_failure_rate(mut self, rate: f64) -> Self {
+            self.failure_rate = rate.clamp(0.0, 1.0);
+            self
+        }
+
+        /// Send the mock response word by word to a token channel, simulating streaming
+        pub fn with_token_sender(mut self, sender: TokenSender) -> Self {
+            self.token_sender = Some(sender);
+            self
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for MockLlmNode {
+        type PrepResult = String;
+        type ExecResult = String;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let value = match store.get(&self.prompt_key) {
+                Ok(value) => value,
+                Err(e) => return Err(NodeError::StorageError(e.to_string())),
+            };
+
+            let prompt = value
+                .and_then(|v| v.as_str().map(|s| s.to_string()))
+                .ok_or_else(|| {
+                    NodeError::ValidationError(format!(
+                        "Prompt not found at key: {}",
+                        self.prompt_key
+                    ))
+                })?;
+            Ok(prompt)
+        }
+
+        async fn exec(
+            &mut self,
+            prompt: Self::PrepResult,
+            context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            // Simulate API call delay
+            context.env.sleep(Duration::from_millis(100)).await;
+
+            // Simulate random failures for testing
+            if self.failure_rate > 0.0 && context.env.random_f64() < self.failure_rate {
+                return Err(NodeError::ExecutionError(format!(
+                    "Mock LLM API failure (retry {})",
+                    context.current_retry
+                )));
+            }
+
+            // Generate mock response
+            let response = format!("{} (processed prompt: '{}')", self.mock_response, prompt);
+            if let Some(sender) = &self.token_sender {
+                for token in response.split_inclusive(' ') {
+                    // A closed channel just means nobody is listening anymore
+                    let _ = sender.send(token.to_string());
+                }
+            }
+            Ok(response)
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            exec_result: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            match store.set(
+                self.output_key.clone(),
+                serde_json::Value::String(exec_result),
+            ) {
+                Ok(_) => Ok(self.action.clone()),
+                Err(e) => Err(NodeError::StorageError(e.to_string())),
+            }
+        }
+
+        async fn exec_fallback(
+            &mut self,
+            _prep_result: Self::PrepResult,
+            error: Self::Error,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(format!("Fallback response due to error: {}", error))
+        }
+
+        fn name(&self) -> &str {
+            "MockLlmNode"
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+
+        fn retry_delay(&self) -> Duration {
+            self.retry_delay
+        }
+    }
+
+    /// HTTP-based API request node for LLM interactions
+    ///
+    /// This node makes actual HTTP requests to LLM APIs (OpenAI, etc.)
+    /// It supports various configuration options including retries,
+    /// custom endpoints, message history, and error handling.
+    ///
+    /// By default requests go through an [`OpenAiProvider`] built from the
+    /// node's [`ApiConfig`]; with [`Provider::AzureOpenAi`] they go to the
+    /// deployment URL under `base_url` with an `api-key` header and
+    /// `api-version` query parameter. Any other [`LlmProvider`] (including an
+    /// [`LlmRouter`](crate::llm::LlmRouter)) can be set with
+    /// [`ApiRequestNode::with_provider`].
+    ///
+    /// When the flow runs for a [`TenantContext`](crate::tenant::TenantContext),
+    /// each request counts against the tenant's quota and the tenant's
+    /// `ApiConfig`, if it has one, replaces the node's (an explicit provider
+    /// still takes precedence).
+    ///
+    /// With a JSON [`ResponseFormat`] in the config the reply is parsed and
+    /// stored as JSON rather than text. A reply that doesn't parse (or, with
+    /// the `schema-validation` feature, doesn't match the schema) fails the
+    /// attempt, and the retry tells the model what was wrong. With
+    /// [`ApiRequestNode::with_outputs`] the fields of the reply are also written
+    /// to their own keys.
+    #[derive(Clone)]
+    pub struct ApiRequestNode {
+        /// Configuration for the API
+        config: ApiConfig,
+        /// Input key for the messages (can be a single prompt or array of messages)
+        input_key: String,
+        /// Output key for the response
+        output_key: String,
+        /// Key for the response with its metadata
+        response_key: Option<String>,
+        /// Fields of a JSON reply written to their own keys
+        outputs: Outputs,
+        /// Action to execute after successful completion
+        action: Action,
+        /// Maximum number of retries
+        max_retries: usize,
+        /// Delay between retries
+        retry_delay: Duration,
+        /// System message to prepend to conversations
+        system_message: Option<String>,
+        /// Provider used instead of the config's OpenAI-compatible API
+        provider: Option<Arc<dyn LlmProvider>>,
+        /// Cached provider built from the config
+        client: Option<Arc<OpenAiProvider>>,
+        /// Cached providers built from tenant configs, by tenant ID
+        tenant_clients: HashMap<String, Arc<OpenAiProvider>>,
+        /// Channel receiving response content as it arrives
+        token_sender: Option<TokenSender>,
+        /// Token budget the messages are fitted into before sending
+        context_window: Option<ContextWindow>,
+        /// Why the last reply was rejected, shown to the model on retry
+        format_error: Option<String>,
+    }
+
+    impl std::fmt::Debug for ApiRequestNode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("ApiRequestNode")
+                .field("config", &self.config)
+                .field("input_key", &self.input_key)
+                .field("output_key", &self.output_key)
+                .field("response_key", &self.response_key)
+                .field("outputs", &self.outputs)
+                .field("action", &self.action)
+                .field("max_retries", &self.max_retries)
+                .field("retry_delay", &self.retry_delay)
+                .field("system_message", &self.system_message)
+                .field("provider", &self.provider.as_ref().map(|p| p.name()))
+                .field("context_window", &self.context_window)
+                .finish()
+        }
+    }
+
+    impl ApiRequestNode {
+        /// Create a new API request node with default configuration
+        pub fn new<S: Into<String>>(input_key: S, output_key: S, action: Action) -> Self {
+            Self {
+                config: ApiConfig::default(),
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                response_key: None,
+                outputs: Outputs::new(),
+                action,
+                max_retries: 3,
+                retry_delay: Duration::from_millis(1000),
+                system_message: None,
+                provider: None,
+                client: None,
+                tenant_clients: HashMap::new(),
+                token_sender: None,
+                context_window: None,
+                format_error: None,
+            }
+        }
+
+        /// Create a new API request node with custom configuration
+        pub fn with_config(mut self, config: ApiConfig) -> Self {
+            self.config = config;
+            self.client = None;
+            self
+        }
+
+        /// Send requests through a provider instead of the configured API
+        ///
+        /// The config's `stream` flag still selects streaming; model and
+        /// sampling settings come from the provider.
+        pub fn with_provider(mut self, provider: Arc<dyn LlmProvider>) -> Self {
+            self.provider = Some(provider);
+            self
+        }
+
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+
+        /// Set retry delay
+        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+            self.retry_delay = delay;
+            self
+        }
+
+        /// Set a system message to prepend to conversations
+        pub fn with_system_message(mut self, message: impl Into<String>) -> Self {
+            self.system_message = Some(message.into());
+            self
+        }
+
+        /// Fit long conversations into a context window before sending
+        ///
+        /// Use [`ContextWindow::for_model`] with the configured model to get its
+        /// context size; the window's strategy decides whether old turns are
+        /// dropped, summarized or rejected.
+        pub fn with_context_window(mut self, window: ContextWindow) -> Self {
+            self.context_window = Some(window);
+            self
+        }
+
+        /// Also write the response with its metadata to `key`
+        ///
+        /// The value is `{content, provider, model, finish_reason, usage,
+        /// tool_calls, latency_ms, raw}`, with `null` for anything the provider
+        /// doesn't report (streamed responses only carry content), so later
+        /// nodes can branch on `finish_reason == "length"` or log usage. It is
+        /// not written when the fallback answers.
+        pub fn with_response_key(mut self, key: impl Into<String>) -> Self {
+            self.response_key = Some(key.into());
+            self
+        }
+
+        /// Send response content to a channel as it arrives
+        ///
+        /// With streaming enabled each token delta is sent separately; otherwise
+        /// the full response is sent once.
+        pub fn with_token_sender(mut self, sender: TokenSender) -> Self {
+            self.token_sender = Some(sender);
+            self
+        }
+
+        /// Also write fields of the reply to their own keys
+        ///
+        /// Needs a JSON [`ResponseFormat`]; a reply missing a field is rejected
+        /// and retried like one that isn't valid JSON. The whole reply is still
+        /// written to the output key.
+        pub fn with_outputs(mut self, outputs: Outputs) -> Self {
+            self.outputs = outputs;
+            self
+        }
+
+        /// Update the configuration
+        pub fn update_config(mut self, config: ApiConfig) -> Self {
+            self.config = config;
+            self.client = None; // Reset client to force recreation
+            self
+        }
+
+        /// Get the provider requests are sent to
+        fn get_provider(
+            &mut self,
+            tenant: Option<&TenantContext>,
+        ) -> Result<Arc<dyn LlmProvider>, NodeError> {
+            if let Some(provider) = &self.provider {
+                return Ok(provider.clone());
+            }
+            if let Some(tenant) = tenant
+                && let Some(config) = tenant.api_config()
+            {
+                if let Some(client) = self.tenant_clients.get(tenant.id()) {
+                    return Ok(client.clone());
+                }
+                let client = Arc::new(OpenAiProvider::new(config.clone())?);
+                self.tenant_clients
+                    .insert(tenant.id().to_string(), client.clone());
+                return Ok(client);
+            }
+            if self.client.is_none() {
+                self.client = Some(Arc::new(OpenAiProvider::new(self.config.clone())?));
+            }
+            Ok(self.client.clone().unwrap())
+        }
+
+        /// Convert input to messages array
+        fn parse_messages(&self, input: &Value) -> Result<Vec<ChatMessage>, NodeError> {
+            let mut messages = Vec::new();
+
+            // Add system message if provided
+            if let Some(ref system_msg) = self.system_message {
+                messages.push(ChatMessage::system(system_msg.clone()));
+            }
+
+            // Parse input as either a single prompt or array of messages
+            messages.extend(ChatMessage::from_value(input)?);
+            Ok(messages)
+        }
+
+        /// Make the API request, streaming if configured
+        async fn make_api_request(
+            &mut self,
+            messages: Vec<ChatMessage>,
+            context: &ExecutionContext,
+        ) -> Result<ChatResponse, NodeError> {
+            let tenant = context.tenant();
+            let provider = self.get_provider(tenant)?;
+            let messages = match &self.context_window {
+                Some(window) => window.fit(messages, provider.as_ref()).await?,
+                None => messages,
+            };
+            let config = tenant
+                .and_then(TenantContext::api_config)
+                .unwrap_or(&self.config);
+            let stream = config.stream;
+            let mut request =
+                ChatRequest::new(messages).with_request_id(context.correlation_id.clone());
+            request.response_format = config.response_format.clone();
+            request.seed = config.seed;
+
+            if !stream {
+                let response = provider.chat(request).await?;
+                if let Some(sender) = &self.token_sender {
+                    let _ = sender.send(response.content.clone());
+                }
+                return Ok(response);
+            }
+
+            // Process the stream and accumulate content
+            let mut stream = provider.chat_stream(request).await?;
+            let mut accumulated_content = String::new();
+            while let Some(delta) = stream.next().await {
+                let delta = delta?;
+                if let Some(sender) = &self.token_sender {
+                    let _ = sender.send(delta.clone());
+                }
+                accumulated_content.push_str(&delta);
+            }
+
+            if accumulated_content.is_empty() {
+                return Err(NodeError::ExecutionError(
+                    "No content received from streaming response".to_string(),
+                ));
+            }
+
+            Ok(ChatResponse::new(accumulated_content, provider.name()))
+        }
+    }
+
+    /// Parse a reply requested as JSON, checking it against the format's schema
+    /// when the `schema-validation` feature is enabled
+    fn parse_json_reply(format: &ResponseFormat, content: &str) -> Result<Value, String> {
+        // Some models wrap JSON in a Markdown code fence anyway
+        let json = content
+            .trim()
+            .strip_prefix("```json")
+            .or_else(|| content.trim().strip_prefix("```"))
+            .and_then(|rest| rest.trim_end().strip_suffix("```"))
+            .unwrap_or(content);
+        let value: Value = serde_json::from_str(json.trim()).map_err(|e| e.to_string())?;
+
+        #[cfg(feature = "schema-validation")]
+        if let Some(schema) = format.schema() {
+            let validator = jsonschema::validator_for(schema).map_err(|e| e.to_string())?;
+            let errors: Vec<String> = validator
+                .iter_errors(&value)
+                .map(|e| e.to_string())
+                .collect();
+            if !errors.is_empty() {
+                return Err(errors.join("; "));
+            }
+        }
+        #[cfg(not(feature = "schema-validation"))]
+        let _ = format;
+        Ok(value)
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for ApiRequestNode {
+        type PrepResult = Vec<ChatMessage>; // The messages to send
+        type ExecResult = (Value, Option<Value>); // The output value and response metadata
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match store.get(&self.input_key) {
+                Ok(Some(value)) => self.parse_messages(&value),
+                Ok(None) => Err(NodeError::PrepError(format!(
+                    "Input key '{}' not found in store",
+                    self.input_key
+                ))),
+                Err(e) => Err(NodeError::StorageError(e.to_string())),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            prep_result: Self::PrepResult,
+            context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let mut messages = prep_result;
+            // Check if this is a retry and log it
+            if context.current_retry > 0 {
+                eprintln!(
+                    "ApiRequestNode retry attempt {} for {} messages",
+                    context.current_retry,
+                    messages.len()
+                );
+                if let Some(error) = &self.format_error {
+                    messages.push(ChatMessage::user(format!(
+                        "Your previous reply was rejected: {}. Reply with JSON only.",
+                        error
+                    )));
+                }
+            } else {
+                self.format_error = None;
+            }
+
+            if let Some(tenant) = context.tenant() {
+                tenant.acquire(context).await?;
+            }
+
+            // Make the actual API request
+            let started = context.env.now();
+            let response = self.make_api_request(messages, context).await?;
+            let format = context
+                .tenant()
+                .and_then(TenantContext::api_config)
+                .unwrap_or(&self.config)
+                .response_format
+                .clone();
+            let output = match format.filter(ResponseFormat::is_json) {
+                Some(format) => match parse_json_reply(&format, &response.content)
+                    .and_then(|value| self.outputs.extract(&value).map(|_| value))
+                {
+                    Ok(value) => value,
+                    Err(error) => {
+                        self.format_error = Some(error.clone());
+                        return Err(NodeError::ExecutionError(format!(
+                            "Invalid JSON response: {}",
+                            error
+                        )));
+                    }
+                },
+                None => Value::String(response.content.clone()),
+            };
+            let metadata = self.response_key.as_ref().map(|_| {
+                json!({
+                    "content": response.content,
+                    "provider": response.provider,
+                    "model": response.model,
+                    "finish_reason": response.finish_reason,
+                    "usage": response.usage,
+                    "rate_limit": response.rate_limit,
+                    "tool_calls": response.tool_calls,
+                    "latency_ms": (context.env.now() - started).as_millis() as u64,
+                    "raw": response.raw,
+                })
+            });
+            Ok((output, metadata))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            (output, metadata): Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            // The fallback's error message has no fields to write
+            if !self.outputs.is_empty() && output.is_object() {
+                self.outputs.write(store, &output)?;
+            }
+            store
+                .set(self.output_key.clone(), output)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            if let (Some(key), Some(metadata)) = (&self.response_key, metadata) {
+                store
+                    .set(key.clone(), metadata)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            Ok(self.action.clone())
+        }
+
+        async fn exec_fallback(
+            &mut self,
+            _prep_result: Self::PrepResult,
+            error: Self::Error,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            // For API failures, return a user-friendly error message
+            Ok((
+                Value::String(format!(
+                    "API request failed: {}. Please check your configuration and try again.",
+                    error
+                )),
+                None,
+            ))
+        }
+
+        fn name(&self) -> &str {
+            "ApiRequestNode"
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            let keys = std::iter::once(&self.output_key).chain(&self.response_key);
+            Some(
+                keys.map(|key| KeySpec::new(key.clone()))
+                    .chain(self.outputs.keys())
+                    .collect(),
+            )
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+
+        fn retry_delay(&self) -> Duration {
+            self.retry_delay
+        }
+    }
+}
+
+// ============================================================================
+// GEMINI NODES (feature: builtin-llm)
+// ============================================================================
+
+/// Nodes for the Google Generative Language (Gemini) API
+#[cfg(feature = "builtin-llm")]
+pub mod gemini {
+    use crate::llm::{ChatMessage, ChatRequest, GeminiProvider, LlmProvider};
+    use crate::node::{ExecutionContext, NodeBackend, NodeError, TokenSender};
+    use crate::secrets::Secret;
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use std::time::Duration;
+
+    /// Default Generative Language API base URL
+    pub const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+    /// A safety setting sent with each request
+    ///
+    /// See the Gemini API docs for categories (e.g. `HARM_CATEGORY_HARASSMENT`)
+    /// and thresholds (e.g. `BLOCK_ONLY_HIGH`).
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct SafetySetting {
+        pub category: String,
+        pub threshold: String,
+    }
+
+    /// Configuration for Gemini requests
+    #[derive(Debug, Clone)]
+    pub struct GeminiConfig {
+        /// API key (default: `GEMINI_API_KEY`, then `GOOGLE_API_KEY`)
+        pub api_key: Secret,
+        /// API base URL (default: [`GEMINI_API_BASE`])
+        pub base_url: String,
+        /// Model to use (default: "gemini-2.0-flash")
+        pub model: String,
+        /// Maximum tokens to generate
+        pub max_output_tokens: Option<u32>,
+        /// Temperature for randomness (0.0 to 2.0)
+        pub temperature: Option<f32>,
+        /// Nucleus sampling probability
+        pub top_p: Option<f32>,
+        /// Top-k sampling
+        pub top_k: Option<u32>,
+        /// Request timeout in seconds
+        pub timeout: Option<u64>,
+        /// Use `streamGenerateContent` instead of `generateContent` (default: false)
+        pub stream: bool,
+        /// Safety settings applied to the request
+        pub safety_settings: Vec<SafetySetting>,
+    }
+
+    impl Default for GeminiConfig {
+        fn default() -> Self {
+            Self {
+                api_key: Secret::env_any(["GEMINI_API_KEY", "GOOGLE_API_KEY"]),
+                base_url: GEMINI_API_BASE.to_string(),
+                model: "gemini-2.0-flash".to_string(),
+                max_output_tokens: None,
+                temperature: Some(0.7),
+                top_p: None,
+                top_k: None,
+                timeout: Some(30),
+                stream: false,
+                safety_settings: Vec::new(),
+            }
+        }
+    }
+
+    impl GeminiConfig {
+        /// Create a new GeminiConfig with an API key
+        pub fn new(api_key: impl Into<Secret>) -> Self {
+            Self {
+                api_key: api_key.into(),
+                ..Default::default()
+            }
+        }
+
+        /// Set the model to use
+        pub fn with_model(mut self, model: impl Into<String>) -> Self {
+            self.model = model.into();
+            self
+        }
+
+        /// Set the API base URL
+        pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+            self.base_url = base_url.into();
+            self
+        }
+
+        /// Set maximum output tokens
+        pub fn with_max_output_tokens(mut self, max_output_tokens: u32) -> Self {
+            self.max_output_tokens = Some(max_output_tokens);
+            self
+        }
+
+        /// Set temperature
+        pub fn with_temperature(mut self, temperature: f32) -> Self {
+            self.temperature = Some(temperature);
+            self
+        }
+
+        /// Set top-p
+        pub fn with_top_p(mut self, top_p: f32) -> Self {
+            self.top_p = Some(top_p);
+            self
+        }
+
+        /// Set top-k
+        pub fn with_top_k(mut self, top_k: u32) -> Self {
+            self.top_k = Some(top_k);
+            self
+        }
+
+        /// Set timeout in seconds
+        pub fn with_timeout(mut self, timeout: u64) -> Self {
+            self.timeout = Some(timeout);
+            self
+        }
+
+        /// Enable streaming responses
+        pub fn with_stream(mut self, stream: bool) -> Self {
+            self.stream = stream;
+            self
+        }
+
+        /// Add a safety setting
+        pub fn with_safety_setting(
+            mut self,
+            category: impl Into<String>,
+            threshold: impl Into<String>,
+        ) -> Self {
+            self.safety_settings.push(SafetySetting {
+                category: category.into(),
+                threshold: threshold.into(),
+            });
+            self
+        }
+    }
+
+    /// API request node for Gemini models
+    ///
+    /// Follows the same conventions as
+    /// [`ApiRequestNode`](super::llm::ApiRequestNode): the input key holds a
+    /// prompt string or an array of `{"role", "content"}` messages, and the
+    /// response text is stored under the output key. Requests go through a
+    /// [`GeminiProvider`].
+    #[derive(Debug, Clone)]
+    pub struct GeminiRequestNode {
+        provider: GeminiProvider,
+        input_key: String,
+        output_key: String,
+        action: Action,
+        max_retries: usize,
+        retry_delay: Duration,
+        system_message: Option<String>,
+        token_sender: Option<TokenSender>,
+    }
+
+    impl GeminiRequestNode {
+        /// Create a new Gemini request node with default configuration
+        pub fn new<S: Into<String>>(input_key: S, output_key: S, action: Action) -> Self {
+            Self {
+                provider: GeminiProvider::new(GeminiConfig::default()),
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                action,
+                max_retries: 3,
+                retry_delay: Duration::from_millis(1000),
+                system_message: None,
+                token_sender: None,
+            }
+        }
+
+        /// Set the configuration
+        pub fn with_config(mut self, config: GeminiConfig) -> Self {
+            self.provider = GeminiProvider::new(config);
+            self
+        }
+
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+
+        /// Set retry delay
+        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+            self.retry_delay = delay;
+            self
+        }
+
+        /// Set a system instruction prepended to any from the input
+        pub fn with_system_message(mut self, message: impl Into<String>) -> Self {
+            self.system_message = Some(message.into());
+            self
+        }
+
+        /// Send response content to a channel as it arrives
+        pub fn with_token_sender(mut self, sender: TokenSender) -> Self {
+            self.token_sender = Some(sender);
+            self
+        }
+
+        /// Get the configuration
+        pub fn config(&self) -> &GeminiConfig {
+            self.provider.config()
+        }
+
+        /// Make the API request, streaming if configured
+        async fn make_api_request(
+            &self,
+            messages: Vec<ChatMessage>,
+            context: &ExecutionContext,
+        ) -> Result<String, NodeError> {
+            let request =
+                ChatRequest::new(messages).with_request_id(context.correlation_id.clone());
+            if !self.config().stream {
+                let content = self.provider.chat(request).await?.content;
+                if let Some(sender) = &self.token_sender {
+                    let _ = sender.send(content.clone());
+                }
+                return Ok(content);
+            }
+
+            let mut stream = self.provider.chat_stream(request).await?;
+            let mut content = String::new();
+            while let Some(delta) = stream.next().await {
+                let delta = delta?;
+                if let Some(sender) = &self.token_sender {
+                    let _ = sender.send(delta.clone());
+                }
+                content.push_str(&delta);
+            }
+            if content.is_empty() {
+                return Err(NodeError::ExecutionError(
+                    "No content received from streaming response".to_string(),
+                ));
+            }
+            Ok(content)
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for GeminiRequestNode {
+        type PrepResult = Vec<ChatMessage>;
+        type ExecResult = String;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match store.get(&self.input_key) {
+                Ok(Some(value)) => {
+                    let mut messages: Vec<ChatMessage> = self
+                        .system_message
+                        .iter()
+                        .map(ChatMessage::system)
+                        .collect();
+                    messages.extend(ChatMessage::from_value(&value)?);
+                    Ok(messages)
+                }
+                Ok(None) => Err(NodeError::PrepError(format!(
+                    "Input key '{}' not found in store",
+                    self.input_key
+                ))),
+                Err(e) => Err(NodeError::StorageError(e.to_string())),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            prep_result: Self::PrepResult,
+            context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            self.make_api_request(prep_result, context).await
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            exec_result: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            match store.set(self.output_key.clone(), Value::String(exec_result)) {
+                Ok(_) => Ok(self.action.clone()),
+                Err(e) => Err(NodeError::StorageError(e.to_string())),
+            }
+        }
+
+        async fn exec_fallback(
+            &mut self,
+            _prep_result: Self::PrepResult,
+            error: Self::Error,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(format!(
+                "API request failed: {}. Please check your configuration and try again.",
+                error
+            ))
+        }
+
+        fn name(&self) -> &str {
+            "GeminiRequestNode"
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+
+        fn retry_delay(&self) -> Duration {
+            self.retry_delay
+        }
+    }
+}
+
+// ============================================================================
+// LOCAL INFERENCE NODES (feature: local-inference)
+// ============================================================================
+
+/// Nodes running GGUF models in-process
+#[cfg(feature = "local-inference")]
+pub mod local {
+    use crate::llm::{ChatMessage, ChatRequest, LlmProvider, LocalLlmProvider};
+    use crate::node::{ExecutionContext, NodeBackend, NodeError, TokenSender};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use serde_json::Value;
+
+    /// Node generating a reply with a [`LocalLlmProvider`]
+    ///
+    /// Follows the conventions of
+    /// [`ApiRequestNode`](super::llm::ApiRequestNode): the input key holds a
+    /// prompt string or an array of `{"role", "content"}` messages, and the
+    /// reply is stored under the output key. With a token sender, text is
+    /// sent as it is generated.
+    ///
+    /// ```rust,no_run
+    /// # use pocketflow_rs::prelude::*;
+    /// # use pocketflow_rs::llm::LocalLlmProvider;
+    /// # use pocketflow_rs::node::builtin::LocalLlmNode;
+    /// let model = LocalLlmProvider::new("models/llama-3.2-1b-q4_k_m.gguf", "models/tokenizer.json");
+    /// let node = LocalLlmNode::new(model, "prompt", "reply", Action::simple("next"))
+    ///     .with_temperature(0.2)
+    ///     .with_max_tokens(128)
+    ///     .with_stop("\n\n");
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct LocalLlmNode {
+        provider: LocalLlmProvider,
+        input_key: String,
+        output_key: String,
+        action: Action,
+        system_message: Option<String>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        token_sender: Option<TokenSender>,
+    }
+
+    impl LocalLlmNode {
+        /// Create a node generating with `provider`
+        pub fn new<S: Into<String>>(
+            provider: LocalLlmProvider,
+            input_key: S,
+            output_key: S,
+            action: Action,
+        ) -> Self {
+            Self {
+                provider,
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                action,
+                system_message: None,
+                temperature: None,
+                max_tokens: None,
+                token_sender: None,
+            }
+        }
+
+        /// Set a system instruction prepended to any from the input
+        pub fn with_system_message(mut self, message: impl Into<String>) -> Self {
+            self.system_message = Some(message.into());
+            self
+        }
+
+        /// Set the temperature, overriding the provider's
+        pub fn with_temperature(mut self, temperature: f32) -> Self {
+            self.temperature = Some(temperature);
+            self
+        }
+
+        /// Set maximum tokens to generate, overriding the provider's
+        pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+            self.max_tokens = Some(max_tokens);
+            self
+        }
+
+        /// End replies at `stop`, in addition to the provider's stop sequences
+        pub fn with_stop(mut self, stop: impl Into<String>) -> Self {
+            self.provider = self.provider.with_stop(stop);
+            self
+        }
+
+        /// Send text to a channel as it is generated
+        pub fn with_token_sender(mut self, sender: TokenSender) -> Self {
+            self.token_sender = Some(sender);
+            self
+        }
+
+        /// Get the provider
+        pub fn provider(&self) -> &LocalLlmProvider {
+            &self.provider
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for LocalLlmNode {
+        type PrepResult = Vec<ChatMessage>;
+        type ExecResult = String;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match store.get(&self.input_key) {
+                Ok(Some(value)) => {
+                    let mut messages: Vec<ChatMessage> = self
+                        .system_message
+                        .iter()
+                        .map(ChatMessage::system)
+                        .collect();
+                    messages.extend(ChatMessage::from_value(&value)?);
+                    Ok(messages)
+                }
+                Ok(None) => Err(NodeError::PrepError(format!(
+                    "Input key '{}' not found in store",
+                    self.input_key
+                ))),
+                Err(e) => Err(NodeError::StorageError(e.to_string())),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            prep_result: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let mut request = ChatRequest::new(prep_result);
+            request.temperature = self.temperature;
+            request.max_tokens = self.max_tokens;
+            let Some(sender) = &self.token_sender else {
+                return Ok(self.provider.chat(request).await?.content);
+            };
+
+            let mut stream = self.provider.chat_stream(request).await?;
+            let mut content = String::new();
+            while let Some(delta) = stream.next().await {
+                let delta = delta?;
+                let _ = sender.send(delta.clone());
+                content.push_str(&delta);
+            }
+            Ok(content)
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            exec_result: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            match store.set(self.output_key.clone(), Value::String(exec_result)) {
+                Ok(_) => Ok(self.action.clone()),
+                Err(e) => Err(NodeError::StorageError(e.to_string())),
+            }
+        }
+
+        fn name(&self) -> &str {
+            "LocalLlmNode"
+        }
+    }
+}
+
+// ============================================================================
+// REASONING NODES (feature: builtin-llm)
+// ============================================================================
+
+/// Multi-step (chain-of-thought) reasoning
+#[cfg(feature = "builtin-llm")]
+pub mod reasoning {
+    use crate::llm::{ChatMessage, ChatRequest, LlmProvider};
+    use crate::node::{ExecutionContext, NodeBackend, NodeError};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Default marker the model uses to declare it is done
+    pub const FINAL_ANSWER_MARKER: &str = "FINAL ANSWER:";
+
+    const DEFAULT_SYSTEM_MESSAGE: &str = "You solve problems by thinking step by step. \
+        Each reply adds exactly one step: refine the plan, check earlier steps or work \
+        towards the solution. When the solution is complete, end your reply with a line \
+        starting with the final answer marker followed by the answer.";
+
+    /// Thoughts produced by one run of a [`ReasoningNode`]
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ReasoningOutcome {
+        /// Every step so far, including those loaded from the scratchpad
+        pub thoughts: Vec<String>,
+        /// The declared answer, or `None` if the step limit was reached first
+        pub answer: Option<String>,
+    }
+
+    /// Node that reasons about a problem one LLM call per step
+    ///
+    /// The problem is read from the input key. Each step sends the problem and
+    /// all previous thoughts, and the reply is appended to a scratchpad (an
+    /// array of strings stored under the scratchpad key, `"scratchpad"` by
+    /// default). Reasoning stops when a reply contains the final answer marker
+    /// or after `max_steps` new steps. The answer (or the last thought, if the
+    /// step limit was hit) is stored under the output key.
+    ///
+    /// A scratchpad already in the store is continued, so a flow can run the
+    /// node again to keep refining an unfinished solution.
+    #[derive(Clone)]
+    pub struct ReasoningNode {
+        provider: Arc<dyn LlmProvider>,
+        input_key: String,
+        output_key: String,
+        scratchpad_key: String,
+        action: Action,
+        exhausted_action: Option<Action>,
+        max_steps: usize,
+        final_marker: String,
+        system_message: String,
+        max_retries: usize,
+        retry_delay: Duration,
+    }
+
+    impl std::fmt::Debug for ReasoningNode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("ReasoningNode")
+                .field("provider", &self.provider.name())
+                .field("input_key", &self.input_key)
+                .field("output_key", &self.output_key)
+                .field("scratchpad_key", &self.scratchpad_key)
+                .field("max_steps", &self.max_steps)
+                .finish()
+        }
+    }
+
+    impl ReasoningNode {
+        /// Create a new reasoning node
+        pub fn new<S: Into<String>>(
+            provider: Arc<dyn LlmProvider>,
+            input_key: S,
+            output_key: S,
+            action: Action,
+        ) -> Self {
+            Self {
+                provider,
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                scratchpad_key: "scratchpad".to_string(),
+                action,
+                exhausted_action: None,
+                max_steps: 5,
+                final_marker: FINAL_ANSWER_MARKER.to_string(),
+                system_message: DEFAULT_SYSTEM_MESSAGE.to_string(),
+                max_retries: 3,
+                retry_delay: Duration::from_millis(1000),
+            }
+        }
+
+        /// Set the store key holding the thoughts so far
+        pub fn with_scratchpad_key(mut self, key: impl Into<String>) -> Self {
+            self.scratchpad_key = key.into();
+            self
+        }
+
+        /// Set the maximum number of LLM calls per run
+        pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+            self.max_steps = max_steps.max(1);
+            self
+        }
+
+        /// Set the marker that declares the final answer
+        pub fn with_final_marker(mut self, marker: impl Into<String>) -> Self {
+            self.final_marker = marker.into();
+            self
+        }
+
+        /// Replace the default system message
+        ///
+        /// The final answer marker is appended to it in each request.
+        pub fn with_system_message(mut self, message: impl Into<String>) -> Self {
+            self.system_message = message.into();
+            self
+        }
+
+        /// Return a different action when `max_steps` is reached without an answer
+        pub fn with_exhausted_action(mut self, action: Action) -> Self {
+            self.exhausted_action = Some(action);
+            self
+        }
+
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+
+        /// Set retry delay
+        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+            self.retry_delay = delay;
+            self
+        }
+
+        /// Build the conversation for the next step
+        fn messages(&self, problem: &str, thoughts: &[String]) -> Vec<ChatMessage> {
+            let mut messages = vec![
+                ChatMessage::system(format!(
+                    "{}\n\nFinal answer marker: {}",
+                    self.system_message, self.final_marker
+                )),
+                ChatMessage::user(problem),
+            ];
+            for thought in thoughts {
+                messages.push(ChatMessage::assistant(thought));
+                messages.push(ChatMessage::user("Continue with the next step."));
+            }
+            messages
+        }
+
+        /// Extract the answer following the final marker, if present
+        fn final_answer(&self, thought: &str) -> Option<String> {
+            thought.find(&self.final_marker).map(|index| {
+                thought[index + self.final_marker.len()..]
+                    .trim()
+                    .to_string()
+            })
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for ReasoningNode {
+        type PrepResult = (String, Vec<String>); // The problem and earlier thoughts
+        type ExecResult = ReasoningOutcome;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let problem = match store.get(&self.input_key) {
+                Ok(Some(Value::String(problem))) => problem,
+                Ok(Some(other)) => other.to_string(),
+                Ok(None) => {
+                    return Err(NodeError::PrepError(format!(
+                        "Input key '{}' not found in store",
+                        self.input_key
+                    )));
+                }
+                Err(e) => return Err(NodeError::StorageError(e.to_string())),
+            };
+            let thoughts = match store.get(&self.scratchpad_key) {
+                Ok(Some(value)) => serde_json::from_value(value).map_err(|_| {
+                    NodeError::ValidationError(format!(
+                        "Scratchpad '{}' must be an array of strings",
+                        self.scratchpad_key
+                    ))
+                })?,
+                Ok(None) => Vec::new(),
+                Err(e) => return Err(NodeError::StorageError(e.to_string())),
+            };
+            Ok((problem, thoughts))
+        }
+
+        async fn exec(
+            &mut self,
+            (problem, mut thoughts): Self::PrepResult,
+            context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            for _ in 0..self.max_steps {
+                let request = ChatRequest::new(self.messages(&problem, &thoughts))
+                    .with_request_id(context.correlation_id.clone());
+                let thought = self.provider.chat(request).await?.content;
+                let answer = self.final_answer(&thought);
+                thoughts.push(thought);
+                if answer.is_some() {
+                    return Ok(ReasoningOutcome { thoughts, answer });
+                }
+            }
+            Ok(ReasoningOutcome {
+                thoughts,
+                answer: None,
+            })
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            exec_result: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            let finished = exec_result.answer.is_some();
+            let output = exec_result
+                .answer
+                .or_else(|| exec_result.thoughts.last().cloned())
+                .unwrap_or_default();
+            store
+                .set(self.scratchpad_key.clone(), json!(exec_result.thoughts))
+                .and_then(|_| store.set(self.output_key.clone(), Value::String(output)))
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+
+            match (&self.exhausted_action, finished) {
+                (Some(action), false) => Ok(action.clone()),
+                _ => Ok(self.action.clone()),
+            }
+        }
+
+        fn name(&self) -> &str {
+            "ReasoningNode"
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+
+        fn retry_delay(&self) -> Duration {
+            self.retry_delay
+        }
+    }
+}
+
+// ============================================================================
+// ENSEMBLE NODES (feature: builtin-llm)
+// ============================================================================
+
+/// Self-consistency sampling across several LLM calls
+#[cfg(feature = "builtin-llm")]
+pub mod ensemble {
+    use crate::llm::{ChatMessage, ChatRequest, LlmProvider, Role};
+    use crate::node::{ExecutionContext, NodeBackend, NodeError};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use futures::future::join_all;
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    type Aggregator = dyn Fn(&[String]) -> Option<String> + Send + Sync;
+
+    /// How an [`EnsembleLlmNode`] picks one answer from its samples
+    #[derive(Clone)]
+    enum Aggregation {
+        MajorityVote,
+        Judge(Option<Arc<dyn LlmProvider>>),
+        Custom(Arc<Aggregator>),
+    }
+
+    /// Node that samples the same prompt several times and keeps the best answer
+    ///
+    /// All samples are requested concurrently, cycling through the configured
+    /// temperatures. By default the most frequent answer wins (compared
+    /// case-insensitively, ignoring surrounding whitespace and punctuation),
+    /// which suits classification-style prompts. Alternatively an LLM judge or
+    /// a closure can choose. Failed samples are dropped as long as one
+    /// succeeds.
+    ///
+    /// The input key holds a prompt string or an array of
+    /// `{"role", "content"}` messages, as for
+    /// [`ApiRequestNode`](super::llm::ApiRequestNode).
+    #[derive(Clone)]
+    pub struct EnsembleLlmNode {
+        provider: Arc<dyn LlmProvider>,
+        input_key: String,
+        output_key: String,
+        samples_key: Option<String>,
+        action: Action,
+        samples: usize,
+        temperatures: Vec<f32>,
+        aggregation: Aggregation,
+        max_retries: usize,
+        retry_delay: Duration,
+    }
+
+    impl std::fmt::Debug for EnsembleLlmNode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("EnsembleLlmNode")
+                .field("provider", &self.provider.name())
+                .field("input_key", &self.input_key)
+                .field("output_key", &self.output_key)
+                .field("samples", &self.samples)
+                .field("temperatures", &self.temperatures)
+                .finish()
+        }
+    }
+
+    impl EnsembleLlmNode {
+        /// Create a new ensemble node taking 5 samples with majority voting
+        pub fn new<S: Into<String>>(
+            provider: Arc<dyn LlmProvider>,
+            input_key: S,
+            output_key: S,
+            action: Action,
+        ) -> Self {
+            Self {
+                provider,
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                samples_key: None,
+                action,
+                samples: 5,
+                temperatures: vec![0.3, 0.7, 1.0],
+                aggregation: Aggregation::MajorityVote,
+                max_retries: 3,
+                retry_delay: Duration::from_millis(1000),
+            }
+        }
+
+        /// Set the number of samples
+        pub fn with_samples(mut self, samples: usize) -> Self {
+            self.samples = samples.max(1);
+            self
+        }
+
+        /// Set the temperatures used in turn by successive samples
+        pub fn with_temperatures(mut self, temperatures: Vec<f32>) -> Self {
+            self.temperatures = temperatures;
+            self
+        }
+
+        /// Also store every sample under the given key
+        pub fn with_samples_key(mut self, key: impl Into<String>) -> Self {
+            self.samples_key = Some(key.into());
+            self
+        }
+
+        /// Pick the most frequent answer (the default)
+        pub fn with_majority_vote(mut self) -> Self {
+            self.aggregation = Aggregation::MajorityVote;
+            self
+        }
+
+        /// Ask the LLM to pick the best sample
+        ///
+        /// Falls back to majority voting if the judge's reply names no sample.
+        pub fn with_llm_judge(mut self) -> Self {
+            self.aggregation = Aggregation::Judge(None);
+            self
+        }
+
+        /// Ask a different provider to pick the best sample
+        pub fn with_judge_provider(mut self, provider: Arc<dyn LlmProvider>) -> Self {
+            self.aggregation = Aggregation::Judge(Some(provider));
+            self
+        }
+
+        /// Pick the answer with a closure; `None` fails the node
+        pub fn with_aggregator<F>(mut self, aggregator: F) -> Self
+        where
+            F: Fn(&[String]) -> Option<String> + Send + Sync + 'static,
+        {
+            self.aggregation = Aggregation::Custom(Arc::new(aggregator));
+            self
+        }
+
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+
+        /// Set retry delay
+        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+            self.retry_delay = delay;
+            self
+        }
+
+        /// Request all samples concurrently
+        async fn sample(
+            &self,
+            messages: &[ChatMessage],
+            context: &ExecutionContext,
+        ) -> Result<Vec<String>, NodeError> {
+            let requests = (0..self.samples).map(|i| {
+                let mut request = ChatRequest::new(messages.to_vec())
+                    .with_request_id(context.correlation_id.clone());
+                if !self.temperatures.is_empty() {
+                    request =
+                        request.with_temperature(self.temperatures[i % self.temperatures.len()]);
+                }
+                self.provider.chat(request)
+            });
+
+            let mut samples = Vec::new();
+            let mut first_error = None;
+            for result in join_all(requests).await {
+                match result {
+                    Ok(response) => samples.push(response.content.trim().to_string()),
+                    Err(error) => {
+                        first_error.get_or_insert(error);
+                    }
+                }
+            }
+            match first_error {
+                Some(error) if samples.is_empty() => Err(error.into()),
+                _ => Ok(samples),
+            }
+        }
+
+        /// Ask the judge to pick a sample by number
+        async fn judge(
+            &self,
+            judge: &Arc<dyn LlmProvider>,
+            messages: &[ChatMessage],
+            samples: &[String],
+            context: &ExecutionContext,
+        ) -> Result<Option<String>, NodeError> {
+            let question = messages
+                .iter()
+                .rev()
+                .find(|m| m.role == Role::User)
+                .map(|m| m.content.as_str())
+                .unwrap_or_default();
+            let candidates = samples
+                .iter()
+                .enumerate()
+                .map(|(i, sample)| format!("[{}] {}", i + 1, sample))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            let request = ChatRequest::new(vec![
+                ChatMessage::system(
+                    "You judge candidate answers. Reply with the number of the best \
+                     candidate only.",
+                ),
+                ChatMessage::user(format!(
+                    "Question:\n{}\n\nCandidates:\n{}",
+                    question, candidates
+                )),
+            ])
+            .with_request_id(context.correlation_id.clone());
+            let reply = judge.chat(request).await?.content;
+            let choice = reply
+                .split(|c: char| !c.is_ascii_digit())
+                .find_map(|number| number.parse::<usize>().ok());
+            Ok(choice
+                .filter(|&n| (1..=samples.len()).contains(&n))
+                .map(|n| samples[n - 1].clone()))
+        }
+    }
+
+    /// Pick the most frequent sample, ties going to the earliest
+    pub fn majority_vote(samples: &[String]) -> Option<String> {
+        let normalize = |sample: &str| {
+            sample
+                .trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+                .to_lowercase()
+        };
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for sample in samples {
+            *counts.entry(normalize(sample)).or_default() += 1;
+        }
+        let mut best: Option<(&String, usize)> = None;
+        for sample in samples {
+            let count = counts[&normalize(sample)];
+            if best.is_none_or(|(_, best_count)| count > best_count) {
+                best = Some((sample, count));
+            }
+        }
+        best.map(|(sample, _)| sample.clone())
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for EnsembleLlmNode {
+        type PrepResult = Vec<ChatMessage>;
+        type ExecResult = (String, Vec<String>); // The chosen answer and all samples
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match store.get(&self.input_key) {
+                Ok(Some(value)) => Ok(ChatMessage::from_value(&value)?),
+                Ok(None) => Err(NodeError::PrepError(format!(
+                    "Input key '{}' not found in store",
+                    self.input_key
+                ))),
+                Err(e) => Err(NodeError::StorageError(e.to_string())),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            prep_result: Self::PrepResult,
+            context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let samples = self.sample(&prep_result, context).await?;
+            let answer = match &self.aggregation {
+                Aggregation::MajorityVote => majority_vote(&samples),
+                Aggregation::Judge(judge) => {
+                    let judge = judge.as_ref().unwrap_or(&self.provider);
+                    match self.judge(judge, &prep_result, &samples, context).await? {
+                        Some(answer) => Some(answer),
+                        None => majority_vote(&samples),
+                    }
+                }
+                Aggregation::Custom(aggregator) => aggregator(&samples),
+            };
+            let answer = answer.ok_or_else(|| {
+                NodeError::ExecutionError("No answer selected from samples".to_string())
+            })?;
+            Ok((answer, samples))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            (answer, samples): Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            if let Some(key) = &self.samples_key {
+                store
+                    .set(key.clone(), json!(samples))
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            match store.set(self.output_key.clone(), Value::String(answer)) {
+                Ok(_) => Ok(self.action.clone()),
+                Err(e) => Err(NodeError::StorageError(e.to_string())),
+            }
+        }
+
+        fn name(&self) -> &str {
+            "EnsembleLlmNode"
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+
+        fn retry_delay(&self) -> Duration {
+            self.retry_delay
+        }
+    }
+}
+
+// ============================================================================
+// CASCADE NODES (feature: builtin-llm)
+// ============================================================================
+
+/// Cheap-first escalation across models
+#[cfg(feature = "builtin-llm")]
+pub mod cascade {
+    use crate::llm::{ChatMessage, ChatRequest, LlmProvider, Role};
+    use crate::node::{ExecutionContext, NodeBackend, NodeError};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const DEFAULT_JUDGE_PROMPT: &str = "You grade answers. Rate how well the answer \
+        solves the question on a scale from 0 to 10. Reply with the number only.";
+
+    type Validator = dyn Fn(&str) -> f64 + Send + Sync;
+
+    /// How a [`CascadeLlmNode`] scores an answer
+    #[derive(Clone)]
+    enum Scorer {
+        Judge(Option<Arc<dyn LlmProvider>>),
+        Custom(Arc<Validator>),
+    }
+
+    /// A model the cascade may ask
+    #[derive(Clone)]
+    struct Tier {
+        provider: Arc<dyn LlmProvider>,
+        model: Option<String>,
+    }
+
+    /// Node that asks a cheap model first and escalates to stronger ones only
+    /// when its answer scores too low
+    ///
+    /// Tiers are tried in the order they were added. Each answer except the
+    /// last tier's is scored between 0 and 1, by an LLM judge (the last tier's
+    /// provider by default) or a closure, and accepted if the score reaches
+    /// the threshold. A tier whose request fails is escalated past. The answer
+    /// is stored under the output key and the tier that gave it under the
+    /// tier key (`"cascade_tier"` by default), as
+    /// `{"tier", "provider", "model", "scores"}` with the score of every tier
+    /// tried before it.
+    ///
+    /// The input key holds a prompt string or an array of
+    /// `{"role", "content"}` messages, as for
+    /// [`ApiRequestNode`](super::llm::ApiRequestNode).
+    #[derive(Clone)]
+    pub struct CascadeLlmNode {
+        tiers: Vec<Tier>,
+        input_key: String,
+        output_key: String,
+        tier_key: String,
+        action: Action,
+        threshold: f64,
+        scorer: Scorer,
+        judge_prompt: String,
+        max_retries: usize,
+        retry_delay: Duration,
+    }
+
+    impl std::fmt::Debug for CascadeLlmNode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let tiers: Vec<_> = self
+                .tiers
+                .iter()
+                .map(|tier| (tier.provider.name(), tier.model.as_deref()))
+                .collect();
+            f.debug_struct("CascadeLlmNode")
+                .field("tiers", &tiers)
+                .field("input_key", &self.input_key)
+                .field("output_key", &self.output_key)
+                .field("threshold", &self.threshold)
+                .finish()
+        }
+    }
+
+    impl CascadeLlmNode {
+        /// Create a cascade starting with `provider`, accepting scores of 0.7
+        /// and above
+        pub fn new<S: Into<String>>(
+            provider: Arc<dyn LlmProvider>,
+            input_key: S,
+            output_key: S,
+            action: Action,
+        ) -> Self {
+            Self {
+                tiers: vec![Tier {
+                    provider,
+                    model: None,
+                }],
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                tier_key: "cascade_tier".to_string(),
+                action,
+                threshold: 0.7,
+                scorer: Scorer::Judge(None),
+                judge_prompt: DEFAULT_JUDGE_PROMPT.to_string(),
+                max_retries: 3,
+                retry_delay: Duration::from_millis(1000),
+            }
+        }
+
+        /// Ask the first tier's provider for a specific model
+        pub fn with_model(mut self, model: impl Into<String>) -> Self {
+            self.tiers[0].model = Some(model.into());
+            self
+        }
+
+        /// Add a stronger tier, asked when the previous answers score too low
+        pub fn with_escalation(mut self, provider: Arc<dyn LlmProvider>) -> Self {
+            self.tiers.push(Tier {
+                provider,
+                model: None,
+            });
+            self
+        }
+
+        /// Add a stronger tier asking `provider` for a specific model
+        pub fn with_escalation_model(
+            mut self,
+            provider: Arc<dyn LlmProvider>,
+            model: impl Into<String>,
+        ) -> Self {
+            self.tiers.push(Tier {
+                provider,
+                model: Some(model.into()),
+            });
+            self
+        }
+
+        /// Set the lowest score accepted without escalating
+        pub fn with_threshold(mut self, threshold: f64) -> Self {
+            self.threshold = threshold;
+            self
+        }
+
+        /// Score answers with a closure returning 0 to 1
+        pub fn with_validator<F>(mut self, validator: F) -> Self
+        where
+            F: Fn(&str) -> f64 + Send + Sync + 'static,
+        {
+            self.scorer = Scorer::Custom(Arc::new(validator));
+            self
+        }
+
+        /// Score answers with a specific judge provider
+        pub fn with_judge_provider(mut self, provider: Arc<dyn LlmProvider>) -> Self {
+            self.scorer = Scorer::Judge(Some(provider));
+            self
+        }
+
+        /// Replace the judge's system message; its reply must start with a
+        /// grade from 0 to 10
+        pub fn with_judge_prompt(mut self, prompt: impl Into<String>) -> Self {
+            self.judge_prompt = prompt.into();
+            self
+        }
+
+        /// Set the store key recording which tier answered
+        pub fn with_tier_key(mut self, key: impl Into<String>) -> Self {
+            self.tier_key = key.into();
+            self
+        }
+
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+
+        /// Set retry delay
+        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+            self.retry_delay = delay;
+            self
+        }
+
+        /// Ask the judge to grade an answer, 0 if its reply has no grade
+        async fn judge(
+            &self,
+            judge: &Arc<dyn LlmProvider>,
+            messages: &[ChatMessage],
+            answer: &str,
+            context: &ExecutionContext,
+        ) -> Result<f64, NodeError> {
+            let question = messages
+                .iter()
+                .rev()
+                .find(|m| m.role == Role::User)
+                .map(|m| m.content.as_str())
+                .unwrap_or_default();
+            let request = ChatRequest::new(vec![
+                ChatMessage::system(self.judge_prompt.clone()),
+                ChatMessage::user(format!("Question:\n{}\n\nAnswer:\n{}", question, answer)),
+            ])
+            .with_request_id(context.correlation_id.clone());
+            let reply = judge.chat(request).await?.content;
+            let grade = reply
+                .split(|c: char| !c.is_ascii_digit() && c != '.')
+                .find_map(|number| number.parse::<f64>().ok())
+                .unwrap_or(0.0);
+            Ok((grade / 10.0).clamp(0.0, 1.0))
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for CascadeLlmNode {
+        type PrepResult = Vec<ChatMessage>;
+        type ExecResult = (String, Value); // The answer and the tier record
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match store.get(&self.input_key) {
+                Ok(Some(value)) => Ok(ChatMessage::from_value(&value)?),
+                Ok(None) => Err(NodeError::PrepError(format!(
+                    "Input key '{}' not found in store",
+                    self.input_key
+                ))),
+                Err(e) => Err(NodeError::StorageError(e.to_string())),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            prep_result: Self::PrepResult,
+            context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let last = self.tiers.len() - 1;
+            let mut scores = Vec::new();
+            for (index, tier) in self.tiers.iter().enumerate() {
+                let mut request = ChatRequest::new(prep_result.clone())
+                    .with_request_id(context.correlation_id.clone());
+                if let Some(model) = &tier.model {
+                    request = request.with_model(model.clone());
+                }
+                let answer = match tier.provider.chat(request).await {
+                    Ok(response) => response.content.trim().to_string(),
+                    Err(error) if index == last => return Err(error.into()),
+                    Err(_) => {
+                        scores.push(Value::Null);
+                        continue;
+                    }
+                };
+                if index < last {
+                    let score = match &self.scorer {
+                        Scorer::Custom(validator) => validator(&answer),
+                        Scorer::Judge(judge) => {
+                            let judge = judge.as_ref().unwrap_or(&self.tiers[last].provider);
+                            self.judge(judge, &prep_result, &answer, context).await?
+                        }
+                    };
+                    scores.push(json!(score));
+                    if score < self.threshold {
+                        continue;
+                    }
+                }
+                let record = json!({
+                    "tier": index,
+                    "provider": tier.provider.name(),
+                    "model": tier.model,
+                    "scores": scores,
+                });
+                return Ok((answer, record));
+            }
+            unreachable!("the last tier always answers or fails")
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            (answer, record): Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            store
+                .set(self.tier_key.clone(), record)
+                .and_then(|_| store.set(self.output_key.clone(), Value::String(answer)))
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "CascadeLlmNode"
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+
+        fn retry_delay(&self) -> Duration {
+            self.retry_delay
+        }
+    }
+}
+
+// ============================================================================
+// TREE SEARCH NODES (feature: builtin-llm)
+// ============================================================================
+
+/// Tree-of-thought search over LLM-generated reasoning steps
+#[cfg(feature = "builtin-llm")]
+pub mod search {
+    use super::reasoning::FINAL_ANSWER_MARKER;
+    use crate::llm::{ChatMessage, ChatRequest, LlmProvider};
+    use crate::node::{ExecutionContext, NodeBackend, NodeError};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use futures::future::join_all;
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const DEFAULT_SYSTEM_MESSAGE: &str = "You solve problems by thinking step by step. \
+        Each reply adds exactly one step towards the solution. When the solution is \
+        complete, end your reply with a line starting with the final answer marker \
+        followed by the answer.";
+
+    const JUDGE_PROMPT: &str = "You grade partial solutions. Rate how likely the steps \
+        so far lead to a correct solution of the problem on a scale from 0 to 10. Reply \
+        with the number only.";
+
+    type Heuristic = dyn Fn(&[String]) -> f64 + Send + Sync;
+
+    /// How a [`TreeSearchNode`] scores a path of steps
+    #[derive(Clone)]
+    enum Scorer {
+        Judge(Option<Arc<dyn LlmProvider>>),
+        Custom(Arc<Heuristic>),
+    }
+
+    /// A path through the tree and its score
+    #[derive(Debug, Clone)]
+    struct Candidate {
+        steps: Vec<String>,
+        score: f64,
+        finished: bool,
+    }
+
+    /// Node that searches a tree of reasoning steps with a beam
+    ///
+    /// Starting from the problem in the input key, every path in the beam is
+    /// extended by `branching` candidate next steps, each asked for
+    /// concurrently on its own copy of the path's conversation. Candidates are
+    /// scored by an LLM judge (the node's provider by default) or a heuristic
+    /// closure, and the best `beam_width` are kept; a beam as wide as the
+    /// tree makes this a breadth-first search. Paths whose last step declares
+    /// the final answer stop growing. The search ends when every path in the
+    /// beam is finished or after `max_depth` steps.
+    ///
+    /// The best path's answer (or last step, if unfinished) is stored under
+    /// the output key, and with [`with_path_key`](Self::with_path_key) the
+    /// path itself as `{"steps", "score"}`. Failed requests are dropped as
+    /// long as one candidate per level succeeds.
+    #[derive(Clone)]
+    pub struct TreeSearchNode {
+        provider: Arc<dyn LlmProvider>,
+        input_key: String,
+        output_key: String,
+        path_key: Option<String>,
+        action: Action,
+        branching: usize,
+        beam_width: usize,
+        max_depth: usize,
+        temperature: f32,
+        scorer: Scorer,
+        final_marker: String,
+        system_message: String,
+        max_retries: usize,
+        retry_delay: Duration,
+    }
+
+    impl std::fmt::Debug for TreeSearchNode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("TreeSearchNode")
+                .field("provider", &self.provider.name())
+                .field("input_key", &self.input_key)
+                .field("output_key", &self.output_key)
+                .field("branching", &self.branching)
+                .field("beam_width", &self.beam_width)
+                .field("max_depth", &self.max_depth)
+                .finish()
+        }
+    }
+
+    impl TreeSearchNode {
+        /// Create a search expanding 3 candidates per path with a beam of 2,
+        /// at most 3 steps deep
+        pub fn new<S: Into<String>>(
+            provider: Arc<dyn LlmProvider>,
+            input_key: S,
+            output_key: S,
+            action: Action,
+        ) -> Self {
+            Self {
+                provider,
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                path_key: None,
+                action,
+                branching: 3,
+                beam_width: 2,
+                max_depth: 3,
+                temperature: 0.8,
+                scorer: Scorer::Judge(None),
+                final_marker: FINAL_ANSWER_MARKER.to_string(),
+                system_message: DEFAULT_SYSTEM_MESSAGE.to_string(),
+                max_retries: 3,
+                retry_delay: Duration::from_millis(1000),
+            }
+        }
+
+        /// Set the number of candidate steps generated per path
+        pub fn with_branching(mut self, branching: usize) -> Self {
+            self.branching = branching.max(1);
+            self
+        }
+
+        /// Set the number of paths kept after each level
+        pub fn with_beam_width(mut self, beam_width: usize) -> Self {
+            self.beam_width = beam_width.max(1);
+            self
+        }
+
+        /// Set the maximum number of steps in a path
+        pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+            self.max_depth = max_depth.max(1);
+            self
+        }
+
+        /// Set the sampling temperature of candidate steps
+        pub fn with_temperature(mut self, temperature: f32) -> Self {
+            self.temperature = temperature;
+            self
+        }
+
+        /// Score paths with a closure over their steps; higher is better
+        pub fn with_heuristic<F>(mut self, heuristic: F) -> Self
+        where
+            F: Fn(&[String]) -> f64 + Send + Sync + 'static,
+        {
+            self.scorer = Scorer::Custom(Arc::new(heuristic));
+            self
+        }
+
+        /// Score paths with a different judge provider
+        pub fn with_judge_provider(mut self, provider: Arc<dyn LlmProvider>) -> Self {
+            self.scorer = Scorer::Judge(Some(provider));
+            self
+        }
+
+        /// Also store the best path under the given key
+        pub fn with_path_key(mut self, key: impl Into<String>) -> Self {
+            self.path_key = Some(key.into());
+            self
+        }
+
+        /// Set the marker that declares the final answer
+        pub fn with_final_marker(mut self, marker: impl Into<String>) -> Self {
+            self.final_marker = marker.into();
+            self
+        }
+
+        /// Replace the default system message
+        ///
+        /// The final answer marker is appended to it in each request.
+        pub fn with_system_message(mut self, message: impl Into<String>) -> Self {
+            self.system_message = message.into();
+            self
+        }
+
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+
+        /// Set retry delay
+        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+            self.retry_delay = delay;
+            self
+        }
+
+        /// The conversation leading to the next step of a path
+        fn messages(&self, problem: &str, steps: &[String]) -> Vec<ChatMessage> {
+            let mut messages = vec![
+                ChatMessage::system(format!(
+                    "{}\n\nFinal answer marker: {}",
+                    self.system_message, self.final_marker
+                )),
+                ChatMessage::user(problem),
+            ];
+            for step in steps {
+                messages.push(ChatMessage::assistant(step));
+                messages.push(ChatMessage::user("Continue with the next step."));
+            }
+            messages
+        }
+
+        /// Generate `branching` next steps for every unfinished path
+        async fn expand(
+            &self,
+            problem: &str,
+            beam: &[Candidate],
+            context: &ExecutionContext,
+        ) -> Result<Vec<Vec<String>>, NodeError> {
+            let parents: Vec<&Candidate> = beam.iter().filter(|c| !c.finished).collect();
+            let requests = parents.iter().flat_map(|parent| {
+                (0..self.branching).map(|_| {
+                    let request = ChatRequest::new(self.messages(problem, &parent.steps))
+                        .with_temperature(self.temperature)
+                        .with_request_id(context.correlation_id.clone());
+                    self.provider.chat(request)
+                })
+            });
+
+            let mut paths = Vec::new();
+            let mut first_error = None;
+            for (i, result) in join_all(requests).await.into_iter().enumerate() {
+                match result {
+                    Ok(response) => {
+                        let mut steps = parents[i / self.branching].steps.clone();
+                        steps.push(response.content.trim().to_string());
+                        paths.push(steps);
+                    }
+                    Err(error) => {
+                        first_error.get_or_insert(error);
+                    }
+                }
+            }
+            match first_error {
+                Some(error) if paths.is_empty() => Err(error.into()),
+                _ => Ok(paths),
+            }
+        }
+
+        /// Score paths concurrently
+        async fn score(
+            &self,
+            problem: &str,
+            paths: &[Vec<String>],
+            context: &ExecutionContext,
+        ) -> Result<Vec<f64>, NodeError> {
+            let judge = match &self.scorer {
+                Scorer::Custom(heuristic) => {
+                    return Ok(paths.iter().map(|steps| heuristic(steps)).collect());
+                }
+                Scorer::Judge(judge) => judge.as_ref().unwrap_or(&self.provider),
+            };
+            let requests = paths.iter().map(|steps| {
+                let numbered = steps
+                    .iter()
+                    .enumerate()
+                    .map(|(i, step)| format!("Step {}: {}", i + 1, step))
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                let request = ChatRequest::new(vec![
+                    ChatMessage::system(JUDGE_PROMPT),
+                    ChatMessage::user(format!("Problem:\n{}\n\nSteps:\n{}", problem, numbered)),
+                ])
+                .with_request_id(context.correlation_id.clone());
+                judge.chat(request)
+            });
+            let mut scores = Vec::new();
+            for result in join_all(requests).await {
+                let reply = result?.content;
+                let grade = reply
+                    .split(|c: char| !c.is_ascii_digit() && c != '.')
+                    .find_map(|number| number.parse::<f64>().ok())
+                    .unwrap_or(0.0);
+                scores.push(grade / 10.0);
+            }
+            Ok(scores)
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for TreeSearchNode {
+        type PrepResult = String;
+        type ExecResult = (Vec<String>, f64); // The best path and its score
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match store.get(&self.input_key) {
+                Ok(Some(Value::String(problem))) => Ok(problem),
+                Ok(Some(other)) => Ok(other.to_string()),
+                Ok(None) => Err(NodeError::PrepError(format!(
+                    "Input key '{}' not found in store",
+                    self.input_key
+                ))),
+                Err(e) => Err(NodeError::StorageError(e.to_string())),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            problem: Self::PrepResult,
+            context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let mut beam = vec![Candidate {
+                steps: Vec::new(),
+                score: 0.0,
+                finished: false,
+            }];
+            for _ in 0..self.max_depth {
+                if beam.iter().all(|candidate| candidate.finished) {
+                    break;
+                }
+                let paths = self.expand(&problem, &beam, context).await?;
+                let scores = self.score(&problem, &paths, context).await?;
+                let mut candidates: Vec<Candidate> = beam
+                    .into_iter()
+                    .filter(|candidate| candidate.finished)
+                    .collect();
+                candidates.extend(paths.into_iter().zip(scores).map(|(steps, score)| {
+                    let finished = steps
+                        .last()
+                        .is_some_and(|step| step.contains(&self.final_marker));
+                    Candidate {
+                        steps,
+                        score,
+                        finished,
+                    }
+                }));
+                // Stable, so ties keep the earlier candidate first
+                candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+                candidates.truncate(self.beam_width);
+                beam = candidates;
+            }
+            let best = beam.into_iter().next().expect("the beam is never empty");
+            Ok((best.steps, best.score))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            (steps, score): Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            let last = steps.last().cloned().unwrap_or_default();
+            let answer = match last.find(&self.final_marker) {
+                Some(index) => last[index + self.final_marker.len()..].trim().to_string(),
+                None => last,
+            };
+            if let Some(key) = &self.path_key {
+                store
+                    .set(key.clone(), json!({"steps": steps, "score": score}))
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            match store.set(self.output_key.clone(), Value::String(answer)) {
+                Ok(_) => Ok(self.action.clone()),
                 Err(e) => Err(NodeError::StorageError(e.to_string())),
             }
         }
 
+        fn name(&self) -> &str {
+            "TreeSearchNode"
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+
+        fn retry_delay(&self) -> Duration {
+            self.retry_delay
+        }
+    }
+}
+
+// ============================================================================
+// PROMPT TEMPLATE NODES (feature: prompt-library)
+// ============================================================================
+
+/// Nodes running prompts from a [`PromptLibrary`](crate::llm::PromptLibrary)
+#[cfg(feature = "prompt-library")]
+pub mod prompt {
+    use crate::llm::{LlmProvider, PromptLibrary};
+    use crate::node::{ExecutionContext, NodeBackend, NodeError};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Node that renders a named prompt from a library with store values
+    ///
+    /// Each variable the prompt declares is read from the store key of the
+    /// same name, unless mapped to another key with
+    /// [`PromptTemplateNode::with_variable`]. With a provider, the rendered
+    /// prompt is sent using the prompt's model hints and the reply is stored
+    /// under the output key; without one, the rendered template itself is
+    /// stored.
+    ///
+    /// The prompt is looked up on every run, so a library with hot reload
+    /// picks up edited prompt files without rebuilding the flow.
+    #[derive(Clone)]
+    pub struct PromptTemplateNode {
+        library: Arc<PromptLibrary>,
+        prompt: String,
+        output_key: String,
+        variables: HashMap<String, String>,
+        provider: Option<Arc<dyn LlmProvider>>,
+        action: Action,
+        max_retries: usize,
+        retry_delay: Duration,
+    }
+
+    impl std::fmt::Debug for PromptTemplateNode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("PromptTemplateNode")
+                .field("prompt", &self.prompt)
+                .field("output_key", &self.output_key)
+                .field("variables", &self.variables)
+                .field("provider", &self.provider.as_ref().map(|p| p.name()))
+                .finish()
+        }
+    }
+
+    impl PromptTemplateNode {
+        /// Create a node rendering the prompt named `prompt`
+        pub fn new<S: Into<String>>(
+            library: Arc<PromptLibrary>,
+            prompt: S,
+            output_key: S,
+            action: Action,
+        ) -> Self {
+            Self {
+                library,
+                prompt: prompt.into(),
+                output_key: output_key.into(),
+                variables: HashMap::new(),
+                provider: None,
+                action,
+                max_retries: 3,
+                retry_delay: Duration::from_millis(1000),
+            }
+        }
+
+        /// Read a prompt variable from a different store key
+        pub fn with_variable(
+            mut self,
+            variable: impl Into<String>,
+            store_key: impl Into<String>,
+        ) -> Self {
+            self.variables.insert(variable.into(), store_key.into());
+            self
+        }
+
+        /// Send the rendered prompt to a provider and store its reply
+        pub fn with_provider(mut self, provider: Arc<dyn LlmProvider>) -> Self {
+            self.provider = Some(provider);
+            self
+        }
+
+        /// Set maximum retries
+        pub fn with_retries(mut self, max_retries: usize) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+
+        /// Set retry delay
+        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+            self.retry_delay = delay;
+            self
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for PromptTemplateNode {
+        type PrepResult = crate::llm::ChatRequest;
+        type ExecResult = String;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let prompt = self
+                .library
+                .get(&self.prompt)
+                .map_err(|e| NodeError::PrepError(e.to_string()))?;
+            let mut vars = HashMap::new();
+            for variable in &prompt.variables {
+                let key = self.variables.get(variable).unwrap_or(variable);
+                if let Some(value) = store
+                    .get(key)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?
+                {
+                    vars.insert(variable.clone(), value);
+                }
+            }
+            prompt
+                .to_request(&vars)
+                .map_err(|e| NodeError::PrepError(e.to_string()))
+        }
+
+        async fn exec(
+            &mut self,
+            request: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            match &self.provider {
+                Some(provider) => Ok(provider.chat(request).await?.content),
+                None => Ok(request
+                    .messages
+                    .last()
+                    .map(|message| message.content.clone())
+                    .unwrap_or_default()),
+            }
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            exec_result: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            store
+                .set(self.output_key.clone(), Value::String(exec_result))
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "PromptTemplateNode"
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            self.provider.is_some()
+        }
+
+        fn max_retries(&self) -> usize {
+            self.max_retries
+        }
+
+        fn retry_delay(&self) -> Duration {
+            self.retry_delay
+        }
+    }
+}
+
+// ============================================================================
+// REGEX NODES (feature: regex-nodes)
+// ============================================================================
+
+/// Nodes extracting and validating text with regular expressions
+#[cfg(feature = "regex-nodes")]
+pub mod text {
+    use crate::node::{ExecutionContext, KeySpec, NodeBackend, NodeError};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use regex::{Captures, Regex};
+    use serde_json::{Map, Value};
+
+    /// Read the string under `key`
+    fn read_text<S: StorageBackend>(
+        store: &SharedStore<S>,
+        key: &str,
+    ) -> Result<Option<String>, NodeError> {
+        match store
+            .get(key)
+            .map_err(|e| NodeError::StorageError(e.to_string()))?
+        {
+            Some(Value::String(text)) => Ok(Some(text)),
+            Some(_) => Err(NodeError::ValidationError(format!(
+                "Key '{}' does not hold a string",
+                key
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// A node that extracts text matching a regular expression
+    ///
+    /// By default the named capture groups of the first match are written to
+    /// keys of the same name (`null` for groups that didn't participate). In
+    /// find-all mode every match is written to one key as an array: objects of
+    /// the named groups, or the matched text if the pattern has none.
+    ///
+    /// Input that doesn't match returns the no-match action if one is set and
+    /// fails otherwise; find-all mode writes an empty array instead.
+    pub struct RegexExtractNode {
+        input_key: String,
+        pattern: Regex,
+        all_key: Option<String>,
+        action: Action,
+        no_match: Option<Action>,
+    }
+
+    impl RegexExtractNode {
+        /// Extract the named groups of `pattern` from the string under `input_key`
+        pub fn new(input_key: impl Into<String>, pattern: Regex, action: Action) -> Self {
+            Self {
+                input_key: input_key.into(),
+                pattern,
+                all_key: None,
+                action,
+                no_match: None,
+            }
+        }
+
+        /// Write every match to `output_key` instead
+        pub fn find_all(mut self, output_key: impl Into<String>) -> Self {
+            self.all_key = Some(output_key.into());
+            self
+        }
+
+        /// Return `action` when the input doesn't match
+        pub fn with_no_match_action(mut self, action: Action) -> Self {
+            self.no_match = Some(action);
+            self
+        }
+
+        /// Names of the pattern's named groups
+        fn group_names(&self) -> impl Iterator<Item = &str> {
+            self.pattern.capture_names().flatten()
+        }
+
+        /// Named groups of one match
+        fn groups(&self, captures: &Captures) -> Map<String, Value> {
+            self.group_names()
+                .map(|name| {
+                    let value = captures
+                        .name(name)
+                        .map_or(Value::Null, |group| Value::String(group.as_str().into()));
+                    (name.to_string(), value)
+                })
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for RegexExtractNode {
+        type PrepResult = String;
+        type ExecResult = Option<Vec<(String, Value)>>; // Keys to write, `None` if no match
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            read_text(store, &self.input_key)?.ok_or_else(|| {
+                NodeError::PrepError(format!("Key '{}' not found in store", self.input_key))
+            })
+        }
+
+        async fn exec(
+            &mut self,
+            text: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            if let Some(key) = &self.all_key {
+                let named = self.group_names().next().is_some();
+                let matches = self
+                    .pattern
+                    .captures_iter(&text)
+                    .map(|captures| match named {
+                        true => Value::Object(self.groups(&captures)),
+                        false => Value::String(captures[0].to_string()),
+                    })
+                    .collect();
+                return Ok(Some(vec![(key.clone(), Value::Array(matches))]));
+            }
+            Ok(self
+                .pattern
+                .captures(&text)
+                .map(|captures| self.groups(&captures).into_iter().collect()))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            extracted: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            let Some(extracted) = extracted else {
+                return self.no_match.clone().ok_or_else(|| {
+                    NodeError::ValidationError(format!(
+                        "Key '{}' does not match /{}/",
+                        self.input_key, self.pattern
+                    ))
+                });
+            };
+            for (key, value) in extracted {
+                store
+                    .set(key, value)
+                    .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            }
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "RegexExtractNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            vec![KeySpec::new(self.input_key.clone())]
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(match &self.all_key {
+                Some(key) => vec![KeySpec::new(key.clone())],
+                None => self.group_names().map(KeySpec::from).collect(),
+            })
+        }
+    }
+
+    /// A node that routes on whether a string matches a regular expression
+    ///
+    /// Returns `valid` or `invalid`; a missing or non-string value is invalid.
+    pub struct RegexValidateNode {
+        input_key: String,
+        pattern: Regex,
+        valid: Action,
+        invalid: Action,
+    }
+
+    impl RegexValidateNode {
+        /// Check the string under `input_key` against `pattern`
+        pub fn new(input_key: impl Into<String>, pattern: Regex) -> Self {
+            Self {
+                input_key: input_key.into(),
+                pattern,
+                valid: Action::simple("valid"),
+                invalid: Action::simple("invalid"),
+            }
+        }
+
+        /// Require the pattern to match the whole string rather than part of it
+        pub fn full_match(mut self) -> Self {
+            self.pattern = Regex::new(&format!(r"\A(?:{})\z", self.pattern.as_str()))
+                .expect("anchoring a valid pattern keeps it valid");
+            self
+        }
+
+        /// Set the actions returned for valid and invalid input
+        pub fn with_actions(mut self, valid: Action, invalid: Action) -> Self {
+            self.valid = valid;
+            self.invalid = invalid;
+            self
+        }
+
+        /// Check a string against the pattern
+        pub fn is_valid(&self, text: &str) -> bool {
+            self.pattern.is_match(text)
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for RegexValidateNode {
+        type PrepResult = Option<String>;
+        type ExecResult = bool;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match read_text(store, &self.input_key) {
+                Err(NodeError::ValidationError(_)) => Ok(None),
+                other => other,
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            text: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(text.is_some_and(|text| self.is_valid(&text)))
+        }
+
+        async fn post(
+            &mut self,
+            _store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            valid: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            Ok(if valid {
+                self.valid.clone()
+            } else {
+                self.invalid.clone()
+            })
+        }
+
+        fn name(&self) -> &str {
+            "RegexValidateNode"
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(Vec::new())
+        }
+    }
+}
+
+// ============================================================================
+// TEXT NORMALIZATION NODES (feature: normalize-text)
+// ============================================================================
+
+/// Nodes converting Markdown and HTML to plain text
+#[cfg(feature = "normalize-text")]
+pub mod normalize {
+    use crate::node::{ExecutionContext, KeySpec, NodeBackend, NodeError};
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+    use serde_json::Value;
+
+    /// Markup of the text being normalized
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TextFormat {
+        /// CommonMark, with tables, strikethrough and task lists
+        Markdown,
+        /// An HTML document or fragment
+        Html,
+    }
+
+    /// A node that converts Markdown or HTML in the store to plain text
+    ///
+    /// Markup is dropped and blocks (paragraphs, list items, headings, table
+    /// rows) are separated by blank lines. Link text and image alt text are
+    /// kept, and `<script>`/`<style>` content is removed. Headings and code
+    /// blocks are kept by default; code blocks keep their whitespace even when
+    /// the rest of the text is collapsed.
+    pub struct NormalizeTextNode {
+        input_key: String,
+        output_key: String,
+        format: TextFormat,
+        headings: bool,
+        code_blocks: bool,
+        collapse_whitespace: bool,
+        action: Action,
+    }
+
+    impl NormalizeTextNode {
+        /// Convert the text under `input_key` and write it to `output_key`
+        pub fn new(
+            input_key: impl Into<String>,
+            output_key: impl Into<String>,
+            format: TextFormat,
+            action: Action,
+        ) -> Self {
+            Self {
+                input_key: input_key.into(),
+                output_key: output_key.into(),
+                format,
+                headings: true,
+                code_blocks: true,
+                collapse_whitespace: true,
+                action,
+            }
+        }
+
+        /// Keep or drop headings (default: kept)
+        pub fn with_headings(mut self, keep: bool) -> Self {
+            self.headings = keep;
+            self
+        }
+
+        /// Keep or drop code blocks (default: kept)
+        pub fn with_code_blocks(mut self, keep: bool) -> Self {
+            self.code_blocks = keep;
+            self
+        }
+
+        /// Collapse runs of whitespace inside each block to single spaces
+        /// (default: on)
+        pub fn with_collapse_whitespace(mut self, collapse: bool) -> Self {
+            self.collapse_whitespace = collapse;
+            self
+        }
+
+        /// Convert text to plain text with this node's settings
+        pub fn normalize(&self, text: &str) -> String {
+            let mut writer = TextWriter::default();
+            match self.format {
+                TextFormat::Markdown => self.write_markdown(text, &mut writer),
+                TextFormat::Html => self.write_html(text, &mut writer),
+            }
+            writer.finish(self.collapse_whitespace)
+        }
+
+        fn write_markdown(&self, text: &str, writer: &mut TextWriter) {
+            let options =
+                Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+            // End of the element being dropped
+            let mut skip_until: Option<TagEnd> = None;
+            let mut html = String::new();
+            for event in Parser::new_ext(text, options) {
+                if let Some(end) = &skip_until {
+                    if matches!(&event, Event::End(tag) if tag == end) {
+                        skip_until = None;
+                    }
+                    continue;
+                }
+                match event {
+                    Event::Start(Tag::Heading { level, .. }) if !self.headings => {
+                        skip_until = Some(TagEnd::Heading(level));
+                    }
+                    Event::Start(Tag::CodeBlock(_)) if !self.code_blocks => {
+                        skip_until = Some(TagEnd::CodeBlock);
+                    }
+                    Event::Start(Tag::CodeBlock(_)) => writer.start_verbatim(),
+                    Event::Start(
+                        Tag::Paragraph
+                        | Tag::Heading { .. }
+                        | Tag::BlockQuote(_)
+                        | Tag::Item
+                        | Tag::TableRow
+                        | Tag::TableHead,
+                    )
+                    | Event::End(
+                        TagEnd::Paragraph
+                        | TagEnd::Heading(_)
+                        | TagEnd::CodeBlock
+                        | TagEnd::BlockQuote(_)
+                        | TagEnd::Item
+                        | TagEnd::TableRow
+                        | TagEnd::TableHead,
+                    )
+                    | Event::Rule => writer.end_block(),
+                    Event::End(TagEnd::TableCell) => writer.push(" "),
+                    Event::End(TagEnd::HtmlBlock) => {
+                        writer.end_block();
+                        self.write_html(&std::mem::take(&mut html), writer);
+                    }
+                    Event::Html(fragment) => html.push_str(&fragment),
+                    Event::Text(text)
+                    | Event::Code(text)
+                    | Event::InlineMath(text)
+                    | Event::DisplayMath(text) => writer.push(&text),
+                    Event::SoftBreak | Event::HardBreak => writer.push("\n"),
+                    _ => {}
+                }
+            }
+        }
+
+        fn write_html(&self, html: &str, writer: &mut TextWriter) {
+            let mut rest = html;
+            while let Some(open) = rest.find('<') {
+                writer.push(&decode_entities(&rest[..open]));
+                rest = &rest[open..];
+                if let Some(comment) = rest.strip_prefix("<!--") {
+                    rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                    continue;
+                }
+                let Some(end) = rest.find('>') else {
+                    // A stray '<' rather than a tag
+                    writer.push(rest);
+                    return;
+                };
+                let tag = &rest[1..end];
+                rest = &rest[end + 1..];
+                let closing = tag.starts_with('/');
+                let name = tag
+                    .trim_start_matches('/')
+                    .split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+
+                let heading = matches!(name.as_str(), "h1" | "h2" | "h3" | "h4" | "h5" | "h6");
+                let dropped = matches!(name.as_str(), "script" | "style" | "head" | "noscript")
+                    || (heading && !self.headings)
+                    || (name == "pre" && !self.code_blocks);
+                if !closing && dropped {
+                    rest = skip_element(rest, &name);
+                    continue;
+                }
+                match name.as_str() {
+                    "br" => writer.push("\n"),
+                    "td" | "th" if closing => writer.push(" "),
+                    "pre" if !closing => writer.start_verbatim(),
+                    "p" | "div" | "li" | "ul" | "ol" | "dl" | "dt" | "dd" | "pre"
+                    | "blockquote" | "table" | "tr" | "hr" | "section" | "article" | "header"
+                    | "footer" | "nav" | "aside" | "main" | "figure" | "figcaption" | "form"
+                    | "title" | "address" => writer.end_block(),
+                    _ if heading => writer.end_block(),
+                    _ => {}
+                }
+            }
+            writer.push(&decode_entities(rest));
+        }
+    }
+
+    /// Skip past the closing tag of an element whose opening tag was consumed
+    fn skip_element<'a>(html: &'a str, name: &str) -> &'a str {
+        let closing = format!("</{}", name);
+        let Some(start) = html.to_ascii_lowercase().find(&closing) else {
+            return "";
+        };
+        let after = &html[start..];
+        after.find('>').map_or("", |end| &after[end + 1..])
+    }
+
+    /// Decode HTML character references
+    fn decode_entities(text: &str) -> String {
+        let mut decoded = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(amp) = rest.find('&') {
+            decoded.push_str(&rest[..amp]);
+            rest = &rest[amp..];
+            let entity = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+            let character = entity.and_then(|entity| match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => {
+                    let number = entity.strip_prefix('#')?;
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => number.parse().ok()?,
+                    };
+                    char::from_u32(code)
+                }
+            });
+            match (entity, character) {
+                (Some(entity), Some(character)) => {
+                    decoded.push(character);
+                    rest = &rest[entity.len() + 2..];
+                }
+                _ => {
+                    decoded.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        decoded.push_str(rest);
+        decoded
+    }
+
+    /// Plain text assembled block by block
+    #[derive(Default)]
+    struct TextWriter {
+        blocks: Vec<(String, bool)>, // (text, verbatim)
+        current: String,
+        verbatim: bool,
+    }
+
+    impl TextWriter {
+        fn push(&mut self, text: &str) {
+            self.current.push_str(text);
+        }
+
+        fn start_verbatim(&mut self) {
+            self.end_block();
+            self.verbatim = true;
+        }
+
+        fn end_block(&mut self) {
+            if !self.current.is_empty() {
+                let text = std::mem::take(&mut self.current);
+                self.blocks.push((text, self.verbatim));
+            }
+            self.verbatim = false;
+        }
+
+        fn finish(mut self, collapse_whitespace: bool) -> String {
+            self.end_block();
+            self.blocks
+                .into_iter()
+                .map(|(text, verbatim)| match (verbatim, collapse_whitespace) {
+                    (true, _) => text.trim_end().trim_start_matches('\n').to_string(),
+                    (false, true) => text.split_whitespace().collect::<Vec<_>>().join(" "),
+                    (false, false) => text.trim().to_string(),
+                })
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        }
+    }
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for NormalizeTextNode {
+        type PrepResult = String;
+        type ExecResult = String;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            match store
+                .get(&self.input_key)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?
+            {
+                Some(Value::String(text)) => Ok(text),
+                Some(_) => Err(NodeError::ValidationError(format!(
+                    "Key '{}' does not hold a string",
+                    self.input_key
+                ))),
+                None => Err(NodeError::PrepError(format!(
+                    "Input key '{}' not found in store",
+                    self.input_key
+                ))),
+            }
+        }
+
+        async fn exec(
+            &mut self,
+            text: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(self.normalize(&text))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _prep_result: Self::PrepResult,
+            text: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            store
+                .set(self.output_key.clone(), Value::String(text))
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+            Ok(self.action.clone())
+        }
+
+        fn name(&self) -> &str {
+            "NormalizeTextNode"
+        }
+
+        fn reads(&self) -> Vec<KeySpec> {
+            vec![KeySpec::new(self.input_key.clone())]
+        }
+
+        fn writes(&self) -> Option<Vec<KeySpec>> {
+            Some(vec![KeySpec::new(self.output_key.clone())])
+        }
+    }
+}
+
+// ============================================================================
+// NOTIFICATION NODES (feature: notify)
+// ============================================================================
+
+/// Nodes delivering notifications by email, Slack and webhooks
+///
+/// Message parts are templates rendered against the store like
+/// [`StructuredLogNode`](super::basic::StructuredLogNode) messages (`{key}` or
+/// `{key.nested.field}`). Transient failures are retried; once delivery has
+/// failed for good the node records the error and returns its failure action
+/// (`"failed"` by default) instead of failing the flow. Successful deliveries
+/// return `"sent"`.
+#[cfg(feature = "notify")]
+pub mod notify {
+    use super::basic::StructuredLogNode;
+    use crate::http::HttpClient;
+    use crate::node::{ExecutionContext, NodeBackend, NodeError};
+    use crate::secrets::Secret;
+    use crate::{Action, SharedStore, StorageBackend};
+    use async_trait::async_trait;
+    use lettre::message::header::ContentType;
+    use lettre::transport::smtp::authentication::Credentials;
+    use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+    use serde_json::{Value, json};
+    use std::time::Duration;
+
+    /// Outcome of a delivery: `Err` holds the message of the final failure
+    type Delivery = Result<(), String>;
+
+    /// Actions, error key and retry settings shared by the notification nodes
+    struct DeliveryOptions {
+        sent_action: Action,
+        failed_action: Action,
+        error_key: Option<String>,
+        max_retries: usize,
+        retry_delay: Duration,
+    }
+
+    impl Default for DeliveryOptions {
+        fn default() -> Self {
+            Self {
+                sent_action: Action::simple("sent"),
+                failed_action: Action::simple("failed"),
+                error_key: None,
+                max_retries: 1,
+                retry_delay: Duration::from_secs(0),
+            }
+        }
+    }
+
+    impl DeliveryOptions {
+        fn finish<S: StorageBackend>(
+            &self,
+            store: &mut SharedStore<S>,
+            node: &str,
+            delivery: Delivery,
+        ) -> Result<Action, NodeError> {
+            match delivery {
+                Ok(()) => Ok(self.sent_action.clone()),
+                Err(message) => {
+                    tracing::warn!(node, error = %message, "notification delivery failed");
+                    if let Some(key) = &self.error_key {
+                        store
+                            .set(key.clone(), Value::String(message))
+                            .map_err(|e| NodeError::StorageError(e.to_string()))?;
+                    }
+                    Ok(self.failed_action.clone())
+                }
+            }
+        }
+    }
+
+    macro_rules! delivery_builders {
+        ($node:ty) => {
+            impl $node {
+                /// Set the action returned after a successful delivery
+                pub fn with_sent_action(mut self, action: impl Into<Action>) -> Self {
+                    self.options.sent_action = action.into();
+                    self
+                }
+
+                /// Set the action returned once delivery has failed
+                pub fn with_failed_action(mut self, action: impl Into<Action>) -> Self {
+                    self.options.failed_action = action.into();
+                    self
+                }
+
+                /// Store the delivery error message under this key
+                pub fn with_error_key(mut self, key: impl Into<String>) -> Self {
+                    self.options.error_key = Some(key.into());
+                    self
+                }
+
+                /// Set maximum retries
+                pub fn with_retries(mut self, max_retries: usize) -> Self {
+                    self.options.max_retries = max_retries;
+                    self
+                }
+
+                /// Set retry delay
+                pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+                    self.options.retry_delay = delay;
+                    self
+                }
+            }
+        };
+    }
+
+    /// Render every string in a JSON template against the store
+    fn render_value<S: StorageBackend>(
+        template: &Value,
+        store: &SharedStore<S>,
+    ) -> Result<Value, NodeError> {
+        Ok(match template {
+            Value::String(text) => Value::String(StructuredLogNode::render(text, store)?),
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| render_value(item, store))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, value)| Ok((key.clone(), render_value(value, store)?)))
+                    .collect::<Result<_, NodeError>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+
+    fn secret(secret: &Secret) -> Result<String, NodeError> {
+        secret.expose().map_err(|e| NodeError::fatal(e.to_string()))
+    }
+
+    /// POST or PUT a JSON body tagged with the correlation ID, classifying
+    /// failures by retryability
+    async fn send_json(
+        client: &HttpClient,
+        mut request: reqwest::RequestBuilder,
+        body: &Value,
+        context: &ExecutionContext,
+    ) -> Result<(), NodeError> {
+        if let Some(id) = &context.correlation_id {
+            request = request.header(crate::node::REQUEST_ID_HEADER, id);
+        }
+        let response = client
+            .send(request.json(body))
+            .await
+            .map_err(|e| NodeError::retryable(format!("Request failed: {}", e)))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let text = response.text().await.unwrap_or_default();
+        let message = format!("HTTP {}: {}", status.as_u16(), text.trim());
+        if status.is_client_error() && !matches!(status.as_u16(), 408 | 429) {
+            Err(NodeError::fatal(message))
+        } else {
+            Err(NodeError::retryable(message))
+        }
+    }
+
+    /// How the SMTP connection is secured
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum SmtpSecurity {
+        /// TLS from the start of the connection (usually port 465)
+        Tls,
+        /// Plain connection upgraded with STARTTLS (usually port 587)
+        #[default]
+        StartTls,
+        /// No encryption, for local test servers
+        None,
+    }
+
+    /// Email rendered from a [`SendEmailNode`]'s templates
+    #[derive(Debug, Clone)]
+    pub struct RenderedEmail {
+        pub to: Vec<String>,
+        pub subject: String,
+        pub body: String,
+    }
+
+    /// A node sending email through an SMTP relay
+    ///
+    /// Recipients, subject and body are templates; a recipient rendering to
+    /// an empty string is skipped.
+    pub struct SendEmailNode {
+        relay: String,
+        port: Option<u16>,
+        security: SmtpSecurity,
+        credentials: Option<(String, Secret)>,
+        from: String,
+        to: Vec<String>,
+        subject: String,
+        body: String,
+        html: bool,
+        options: DeliveryOptions,
+    }
+
+    impl SendEmailNode {
+        /// Create a node sending from `from` through the SMTP server `relay`
+        pub fn new(
+            relay: impl Into<String>,
+            from: impl Into<String>,
+            subject: impl Into<String>,
+            body: impl Into<String>,
+        ) -> Self {
+            Self {
+                relay: relay.into(),
+                port: None,
+                security: SmtpSecurity::default(),
+                credentials: None,
+                from: from.into(),
+                to: Vec::new(),
+                subject: subject.into(),
+                body: body.into(),
+                html: false,
+                options: DeliveryOptions::default(),
+            }
+        }
+
+        /// Add a recipient
+        pub fn to(mut self, recipient: impl Into<String>) -> Self {
+            self.to.push(recipient.into());
+            self
+        }
+
+        /// Use a port other than the default for the security mode
+        pub fn with_port(mut self, port: u16) -> Self {
+            self.port = Some(port);
+            self
+        }
+
+        /// Set how the connection is secured
+        pub fn with_security(mut self, security: SmtpSecurity) -> Self {
+            self.security = security;
+            self
+        }
+
+        /// Authenticate with a username and password
+        pub fn with_credentials(mut self, username: impl Into<String>, password: Secret) -> Self {
+            self.credentials = Some((username.into(), password));
+            self
+        }
+
+        /// Send the body as HTML instead of plain text
+        pub fn as_html(mut self) -> Self {
+            self.html = true;
+            self
+        }
+
+        fn message(&self, email: &RenderedEmail) -> Result<Message, NodeError> {
+            let invalid = |e: &dyn std::fmt::Display| NodeError::ValidationError(e.to_string());
+            let mut builder = Message::builder()
+                .from(self.from.parse().map_err(|e| invalid(&e))?)
+                .subject(email.subject.clone())
+                .header(if self.html {
+                    ContentType::TEXT_HTML
+                } else {
+                    ContentType::TEXT_PLAIN
+                });
+            for recipient in &email.to {
+                builder = builder.to(recipient.parse().map_err(|e| invalid(&e))?);
+            }
+            builder.body(email.body.clone()).map_err(|e| invalid(&e))
+        }
+
+        fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, NodeError> {
+            type Transport = AsyncSmtpTransport<Tokio1Executor>;
+            let mut builder = match self.security {
+                SmtpSecurity::Tls => Transport::relay(&self.relay),
+                SmtpSecurity::StartTls => Transport::starttls_relay(&self.relay),
+                SmtpSecurity::None => Ok(Transport::builder_dangerous(&self.relay)),
+            }
+            .map_err(|e| NodeError::ValidationError(e.to_string()))?;
+            if let Some(port) = self.port {
+                builder = builder.port(port);
+            }
+            if let Some((username, password)) = &self.credentials {
+                builder =
+                    builder.credentials(Credentials::new(username.clone(), secret(password)?));
+            }
+            Ok(builder.build())
+        }
+    }
+
+    delivery_builders!(SendEmailNode);
+
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for SendEmailNode {
+        type PrepResult = RenderedEmail;
+        type ExecResult = Delivery;
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let mut to = Vec::new();
+            for recipient in &self.to {
+                let recipient = StructuredLogNode::render(recipient, store)?;
+                if !recipient.trim().is_empty() {
+                    to.push(recipient);
+                }
+            }
+            if to.is_empty() {
+                return Err(NodeError::ValidationError(
+                    "Email has no recipients".to_string(),
+                ));
+            }
+            Ok(RenderedEmail {
+                to,
+                subject: StructuredLogNode::render(&self.subject, store)?,
+                body: StructuredLogNode::render(&self.body, store)?,
+            })
+        }
+
+        async fn exec(
+            &mut self,
+            email: Self::PrepResult,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let message = self.message(&email)?;
+            self.transport()?.send(message).await.map_err(|e| {
+                if e.is_permanent() {
+                    NodeError::fatal(format!("SMTP error: {}", e))
+                } else {
+                    NodeError::retryable(format!("SMTP error: {}", e))
+                }
+            })?;
+            Ok(Ok(()))
+        }
+
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _email: Self::PrepResult,
+            delivery: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            self.options.finish(store, "SendEmailNode", delivery)
+        }
+
         async fn exec_fallback(
             &mut self,
-            _prep_result: Self::PrepResult,
+            _email: Self::PrepResult,
             error: Self::Error,
             _context: &ExecutionContext,
         ) -> Result<Self::ExecResult, Self::Error> {
-            Ok(format!("Fallback response due to error: {}", error))
+            Ok(Err(error.to_string()))
         }
 
         fn name(&self) -> &str {
-            "MockLlmNode"
+            "SendEmailNode"
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
+
+        fn intent(&self, email: &Self::PrepResult) -> Option<Value> {
+            Some(json!({ "to": email.to, "subject": email.subject }))
         }
 
         fn max_retries(&self) -> usize {
-            self.max_retries
+            self.options.max_retries
         }
 
         fn retry_delay(&self) -> Duration {
-            self.retry_delay
+            self.options.retry_delay
         }
     }
 
-    /// HTTP-based API request node for LLM interactions using async-openai SDK
-    ///
-    /// This node makes actual HTTP requests to LLM APIs (OpenAI, etc.)
-    /// It supports various configuration options including retries,
-    /// custom endpoints, message history, and error handling.
-    #[derive(Debug, Clone)]
-    pub struct ApiRequestNode {
-        /// Configuration for the API
-        config: ApiConfig,
-        /// Input key for the messages (can be a single prompt or array of messages)
-        input_key: String,
-        /// Output key for the response
-        output_key: String,
-        /// Action to execute after successful completion
-        action: Action,
-        /// Maximum number of retries
-        max_retries: usize,
-        /// Delay between retries
-        retry_delay: Duration,
-        /// System message to prepend to conversations
-        system_message: Option<String>,
-        /// Cached OpenAI client
-        client: Option<Client<OpenAIConfig>>,
+    /// A node posting a message to a Slack incoming webhook
+    pub struct SlackWebhookNode {
+        client: HttpClient,
+        webhook_url: Secret,
+        text: String,
+        username: Option<String>,
+        icon_emoji: Option<String>,
+        options: DeliveryOptions,
     }
 
-    impl ApiRequestNode {
-        /// Create a new API request node with default configuration
-        pub fn new<S: Into<String>>(input_key: S, output_key: S, action: Action) -> Self {
+    impl SlackWebhookNode {
+        /// Create a node posting `text` to a webhook URL, which is a secret
+        pub fn new(webhook_url: Secret, text: impl Into<String>) -> Self {
             Self {
-                config: ApiConfig::default(),
-                input_key: input_key.into(),
-                output_key: output_key.into(),
-                action,
-                max_retries: 3,
-                retry_delay: Duration::from_millis(1000),
-                system_message: None,
-                client: None,
+                client: HttpClient::shared(),
+                webhook_url,
+                text: text.into(),
+                username: None,
+                icon_emoji: None,
+                options: DeliveryOptions::default(),
             }
         }
 
-        /// Create a new API request node with custom configuration
-        pub fn with_config(mut self, config: ApiConfig) -> Self {
-            self.config = config;
-            self
-        }
-
-        /// Set maximum retries
-        pub fn with_retries(mut self, max_retries: usize) -> Self {
-            self.max_retries = max_retries;
-            self
-        }
-
-        /// Set retry delay
-        pub fn with_retry_delay(mut self, delay: Duration) -> Self {
-            self.retry_delay = delay;
+        /// Override the name the message is posted under
+        pub fn with_username(mut self, username: impl Into<String>) -> Self {
+            self.username = Some(username.into());
             self
         }
 
-        /// Set a system message to prepend to conversations
-        pub fn with_system_message(mut self, message: impl Into<String>) -> Self {
-            self.system_message = Some(message.into());
+        /// Override the icon, e.g. `:rotating_light:`
+        pub fn with_icon_emoji(mut self, emoji: impl Into<String>) -> Self {
+            self.icon_emoji = Some(emoji.into());
             self
         }
 
-        /// Update the configuration
-        pub fn update_config(mut self, config: ApiConfig) -> Self {
-            self.config = config;
-            self.client = None; // Reset client to force recreation
+        /// Send through `client` instead of the shared one
+        pub fn with_http_client(mut self, client: HttpClient) -> Self {
+            self.client = client;
             self
         }
+    }
 
-        /// Get or create an OpenAI client
-        fn get_client(&mut self) -> Result<&Client<OpenAIConfig>, NodeError> {
-            if self.client.is_none() {
-                let mut config_builder = OpenAIConfig::new().with_api_key(&self.config.api_key);
-
-                if let Some(ref base_url) = self.config.base_url {
-                    config_builder = config_builder.with_api_base(base_url);
-                }
-
-                if let Some(ref org_id) = self.config.org_id {
-                    config_builder = config_builder.with_org_id(org_id);
-                }
-
-                self.client = Some(Client::with_config(config_builder));
-            }
-
-            Ok(self.client.as_ref().unwrap())
-        }
-
-        /// Convert input to messages array
-        fn parse_messages(
-            &self,
-            input: &Value,
-        ) -> Result<Vec<ChatCompletionRequestMessage>, NodeError> {
-            let mut messages = Vec::new();
+    delivery_builders!(SlackWebhookNode);
 
-            // Add system message if provided
-            if let Some(ref system_msg) = self.system_message {
-                messages.push(ChatCompletionRequestMessage::System(
-                    async_openai::types::ChatCompletionRequestSystemMessage {
-                        content: system_msg.clone().into(),
-                        name: None,
-                    },
-                ));
-            }
+    #[async_trait]
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for SlackWebhookNode {
+        type PrepResult = Value;
+        type ExecResult = Delivery;
+        type Error = NodeError;
 
-            // Parse input as either a single prompt or array of messages
-            match input {
-                Value::String(prompt) => {
-                    // Single prompt string - create user message
-                    messages.push(ChatCompletionRequestMessage::User(
-                        async_openai::types::ChatCompletionRequestUserMessage {
-                            content: prompt.clone().into(),
-                            name: None,
-                        },
-                    ));
-                }
-                Value::Array(message_array) => {
-                    // Array of message objects
-                    for msg_value in message_array {
-                        let role =
-                            msg_value
-                                .get("role")
-                                .and_then(|r| r.as_str())
-                                .ok_or_else(|| {
-                                    NodeError::ValidationError(
-                                        "Message must have a 'role' field".to_string(),
-                                    )
-                                })?;
-
-                        let content = msg_value
-                            .get("content")
-                            .and_then(|c| c.as_str())
-                            .ok_or_else(|| {
-                                NodeError::ValidationError(
-                                    "Message must have a 'content' field".to_string(),
-                                )
-                            })?
-                            .to_string();
-
-                        match role {
-                            "system" => {
-                                messages.push(ChatCompletionRequestMessage::System(
-                                    async_openai::types::ChatCompletionRequestSystemMessage {
-                                        content: content.into(),
-                                        name: msg_value
-                                            .get("name")
-                                            .and_then(|n| n.as_str())
-                                            .map(|s| s.to_string()),
-                                    },
-                                ));
-                            }
-                            "user" => {
-                                messages.push(ChatCompletionRequestMessage::User(
-                                    async_openai::types::ChatCompletionRequestUserMessage {
-                                        content: content.into(),
-                                        name: msg_value
-                                            .get("name")
-                                            .and_then(|n| n.as_str())
-                                            .map(|s| s.to_string()),
-                                    },
-                                ));
-                            }
-                            "assistant" => {
-                                messages.push(ChatCompletionRequestMessage::Assistant(
-                                    async_openai::types::ChatCompletionRequestAssistantMessage {
-                                        content: Some(content.into()),
-                                        name: msg_value
-                                            .get("name")
-                                            .and_then(|n| n.as_str())
-                                            .map(|s| s.to_string()),
-                                        ..Default::default()
-                                    },
-                                ));
-                            }
-                            _ => {
-                                return Err(NodeError::ValidationError(format!(
-                                    "Unsupported message role: {}",
-                                    role
-                                )));
-                            }
-                        }
-                    }
-                }
-                _ => {
-                    return Err(NodeError::ValidationError(
-                        "Input must be a string (prompt) or array of message objects".to_string(),
-                    ));
-                }
+        async fn prep(
+            &mut self,
+            store: &SharedStore<S>,
+            _context: &ExecutionContext,
+        ) -> Result<Self::PrepResult, Self::Error> {
+            let mut payload = json!({ "text": StructuredLogNode::render(&self.text, store)? });
+            if let Some(username) = &self.username {
+                payload["username"] = json!(username);
             }
-
-            if messages.is_empty() {
-                return Err(NodeError::ValidationError(
-                    "No valid messages found in input".to_string(),
-                ));
+            if let Some(emoji) = &self.icon_emoji {
+                payload["icon_emoji"] = json!(emoji);
             }
-
-            Ok(messages)
+            Ok(payload)
         }
 
-        /// Make the actual API request using async-openai SDK
-        async fn make_api_request(
+        async fn exec(
             &mut self,
-            messages: Vec<ChatCompletionRequestMessage>,
-        ) -> Result<String, NodeError> {
-            // Extract config values to avoid borrowing issues
-            let model = self.config.model.clone();
-            let max_tokens = self.config.max_tokens;
-            let temperature = self.config.temperature;
-            let top_p = self.config.top_p;
-            let frequency_penalty = self.config.frequency_penalty;
-            let presence_penalty = self.config.presence_penalty;
-            let timeout_secs = self.config.timeout;
-            let stream = self.config.stream;
+            payload: Self::PrepResult,
+            context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            let url = secret(&self.webhook_url)?;
+            send_json(&self.client, self.client.post(url), &payload, context).await?;
+            Ok(Ok(()))
+        }
 
-            let _client = self.get_client()?;
+        async fn post(
+            &mut self,
+            store: &mut SharedStore<S>,
+            _payload: Self::PrepResult,
+            delivery: Self::ExecResult,
+            _context: &ExecutionContext,
+        ) -> Result<Action, Self::Error> {
+            self.options.finish(store, "SlackWebhookNode", delivery)
+        }
 
-            // Build the request using builder pattern correctly
-            let mut request_builder = CreateChatCompletionRequestArgs::default();
-            request_builder.model(model);
-            request_builder.messages(messages);
-            request_builder.stream(stream); // Set streaming option
+        async fn exec_fallback(
+            &mut self,
+            _payload: Self::PrepResult,
+            error: Self::Error,
+            _context: &ExecutionContext,
+        ) -> Result<Self::ExecResult, Self::Error> {
+            Ok(Err(error.to_string()))
+        }
 
-            if let Some(max_tokens) = max_tokens {
-                request_builder.max_tokens(max_tokens);
-            }
+        fn name(&self) -> &str {
+            "SlackWebhookNode"
+        }
 
-            if let Some(temperature) = temperature {
-                request_builder.temperature(temperature);
-            }
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
 
-            if let Some(top_p) = top_p {
-                request_builder.top_p(top_p);
-            }
+        fn intent(&self, payload: &Self::PrepResult) -> Option<Value> {
+            Some(payload.clone())
+        }
 
-            if let Some(frequency_penalty) = frequency_penalty {
-                request_builder.frequency_penalty(frequency_penalty);
-            }
+        fn max_retries(&self) -> usize {
+            self.options.max_retries
+        }
 
-            if let Some(presence_penalty) = presence_penalty {
-                request_builder.presence_penalty(presence_penalty);
-            }
+        fn retry_delay(&self) -> Duration {
+            self.options.retry_delay
+        }
+    }
 
-            let request = request_builder.build().map_err(|e| {
-                NodeError::ExecutionError(format!("Failed to build request: {}", e))
-            })?;
+    /// A node sending a JSON notification to an arbitrary webhook
+    ///
+    /// Every string in the body template is rendered against the store.
+    pub struct WebhookNotifyNode {
+        client: HttpClient,
+        url: String,
+        method: reqwest::Method,
+        headers: Vec<(String, String)>,
+        bearer_token: Option<Secret>,
+        body: Value,
+        options: DeliveryOptions,
+    }
 
-            if stream {
-                // Handle streaming response
-                self.make_streaming_request(request, timeout_secs).await
-            } else {
-                // Handle non-streaming response
-                self.make_regular_request(request, timeout_secs).await
+    impl WebhookNotifyNode {
+        /// Create a node POSTing `body` to `url`
+        pub fn new(url: impl Into<String>, body: Value) -> Self {
+            Self {
+                client: HttpClient::shared(),
+                url: url.into(),
+                method: reqwest::Method::POST,
+                headers: Vec::new(),
+                bearer_token: None,
+                body,
+                options: DeliveryOptions::default(),
             }
         }
 
-        /// Make a regular (non-streaming) API request
-        async fn make_regular_request(
-            &mut self,
-            request: async_openai::types::CreateChatCompletionRequest,
-            timeout_secs: Option<u64>,
-        ) -> Result<String, NodeError> {
-            let client = self.get_client()?;
-
-            // Make the request with timeout
-            let response =
-                if let Some(timeout_secs) = timeout_secs {
-                    tokio::time::timeout(
-                        Duration::from_secs(timeout_secs),
-                        client.chat().create(request),
-                    )
-                    .await
-                    .map_err(|_| NodeError::ExecutionError("Request timeout".to_string()))?
-                    .map_err(|e| NodeError::ExecutionError(format!("API request failed: {}", e)))?
-                } else {
-                    client.chat().create(request).await.map_err(|e| {
-                        NodeError::ExecutionError(format!("API request failed: {}", e))
-                    })?
-                };
-
-            // Extract the response content
-            let content = response
-                .choices
-                .first()
-                .and_then(|choice| choice.message.content.as_ref())
-                .ok_or_else(|| {
-                    NodeError::ExecutionError("No response content received".to_string())
-                })?
-                .clone();
-
-            Ok(content)
+        /// Use another HTTP method, such as PUT
+        pub fn with_method(mut self, method: reqwest::Method) -> Self {
+            self.method = method;
+            self
         }
 
-        /// Make a streaming API request and accumulate the response
-        async fn make_streaming_request(
-            &mut self,
-            request: async_openai::types::CreateChatCompletionRequest,
-            timeout_secs: Option<u64>,
-        ) -> Result<String, NodeError> {
-            let client = self.get_client()?;
-
-            // Make the streaming request with timeout
-            let stream_result =
-                if let Some(timeout_secs) = timeout_secs {
-                    tokio::time::timeout(
-                        Duration::from_secs(timeout_secs),
-                        client.chat().create_stream(request),
-                    )
-                    .await
-                    .map_err(|_| NodeError::ExecutionError("Request timeout".to_string()))?
-                    .map_err(|e| NodeError::ExecutionError(format!("API request failed: {}", e)))?
-                } else {
-                    client.chat().create_stream(request).await.map_err(|e| {
-                        NodeError::ExecutionError(format!("API request failed: {}", e))
-                    })?
-                };
-
-            // Process the stream and accumulate content
-            let mut accumulated_content = String::new();
-            let mut stream = stream_result;
-
-            while let Some(result) = stream.next().await {
-                match result {
-                    Ok(response) => {
-                        // Extract content from the streaming response
-                        if let Some(choice) = response.choices.first() {
-                            if let Some(delta) = &choice.delta.content {
-                                accumulated_content.push_str(delta);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        return Err(NodeError::ExecutionError(format!(
-                            "Stream processing error: {}",
-                            e
-                        )));
-                    }
-                }
-            }
+        /// Add a request header
+        pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+            self.headers.push((name.into(), value.into()));
+            self
+        }
 
-            if accumulated_content.is_empty() {
-                return Err(NodeError::ExecutionError(
-                    "No content received from streaming response".to_string(),
-                ));
-            }
+        /// Authenticate with a bearer token
+        pub fn with_bearer_token(mut self, token: Secret) -> Self {
+            self.bearer_token = Some(token);
+            self
+        }
 
-            Ok(accumulated_content)
+        /// Send through `client` instead of the shared one
+        pub fn with_http_client(mut self, client: HttpClient) -> Self {
+            self.client = client;
+            self
         }
     }
 
+    delivery_builders!(WebhookNotifyNode);
+
     #[async_trait]
-    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for ApiRequestNode {
-        type PrepResult = Vec<ChatCompletionRequestMessage>; // The messages to send
-        type ExecResult = String; // The API response
+    impl<S: StorageBackend + Send + Sync> NodeBackend<S> for WebhookNotifyNode {
+        type PrepResult = Value;
+        type ExecResult = Delivery;
         type Error = NodeError;
 
         async fn prep(
@@ -1052,73 +8332,66 @@ pub mod llm {
             store: &SharedStore<S>,
             _context: &ExecutionContext,
         ) -> Result<Self::PrepResult, Self::Error> {
-            match store.get(&self.input_key) {
-                Ok(Some(value)) => self.parse_messages(&value),
-                Ok(None) => Err(NodeError::PrepError(format!(
-                    "Input key '{}' not found in store",
-                    self.input_key
-                ))),
-                Err(e) => Err(NodeError::StorageError(e.to_string())),
-            }
+            render_value(&self.body, store)
         }
 
         async fn exec(
             &mut self,
-            prep_result: Self::PrepResult,
+            body: Self::PrepResult,
             context: &ExecutionContext,
         ) -> Result<Self::ExecResult, Self::Error> {
-            // Check if this is a retry and log it
-            if context.current_retry > 0 {
-                eprintln!(
-                    "ApiRequestNode retry attempt {} for {} messages",
-                    context.current_retry,
-                    prep_result.len()
-                );
+            let mut request = self.client.request(self.method.clone(), &self.url);
+            for (name, value) in &self.headers {
+                request = request.header(name, value);
             }
-
-            // Make the actual API request
-            self.make_api_request(prep_result).await
+            if let Some(token) = &self.bearer_token {
+                request = request.bearer_auth(secret(token)?);
+            }
+            send_json(&self.client, request, &body, context).await?;
+            Ok(Ok(()))
         }
 
         async fn post(
             &mut self,
             store: &mut SharedStore<S>,
-            _prep_result: Self::PrepResult,
-            exec_result: Self::ExecResult,
+            _body: Self::PrepResult,
+            delivery: Self::ExecResult,
             _context: &ExecutionContext,
         ) -> Result<Action, Self::Error> {
-            match store.set(
-                self.output_key.clone(),
-                serde_json::Value::String(exec_result),
-            ) {
-                Ok(_) => Ok(self.action.clone()),
-                Err(e) => Err(NodeError::StorageError(e.to_string())),
-            }
+            self.options.finish(store, "WebhookNotifyNode", delivery)
         }
 
         async fn exec_fallback(
             &mut self,
-            _prep_result: Self::PrepResult,
+            _body: Self::PrepResult,
             error: Self::Error,
             _context: &ExecutionContext,
         ) -> Result<Self::ExecResult, Self::Error> {
-            // For API failures, return a user-friendly error message
-            Ok(format!(
-                "API request failed: {}. Please check your configuration and try again.",
-                error
-            ))
+            Ok(Err(error.to_string()))
         }
 
         fn name(&self) -> &str {
-            "ApiRequestNode"
+            "WebhookNotifyNode"
+        }
+
+        fn is_side_effecting(&self) -> bool {
+            true
+        }
+
+        fn intent(&self, body: &Self::PrepResult) -> Option<Value> {
+            Some(json!({
+                "method": self.method.as_str(),
+                "url": self.url,
+                "body": body,
+            }))
         }
 
         fn max_retries(&self) -> usize {
-            self.max_retries
+            self.options.max_retries
         }
 
         fn retry_delay(&self) -> Duration {
-            self.retry_delay
+            self.options.retry_delay
         }
     }
 }
@@ -1129,8 +8402,78 @@ pub mod llm {
 
 // Re-export basic nodes
 #[cfg(feature = "builtin-nodes")]
-pub use basic::{ConditionalNode, DelayNode, GetValueNode, LogNode, SetValueNode};
+pub use basic::{
+    AccumulateNode, AsyncConditionalNode, AwaitKeysNode, ConditionFuture, ConditionalNode,
+    CounterNode, Decision, DelayNode, GetValueNode, LogNode, QueryNode, SetValueNode,
+    StructuredLogNode, SwitchNode,
+};
+
+// Re-export messaging nodes
+#[cfg(feature = "builtin-nodes")]
+pub use messaging::{ReceiveMessageNode, SendMessageNode};
+
+// Re-export report nodes
+#[cfg(feature = "builtin-nodes")]
+pub use report::{RenderReportNode, ReportFormat};
+
+// Re-export record transform nodes
+#[cfg(feature = "builtin-nodes")]
+pub use records::{
+    Aggregate, AggregateFn, DedupeNode, Record, RecordOp, SampleNode, TransformRecordsNode,
+};
+
+// Re-export JSON Patch nodes
+#[cfg(feature = "builtin-nodes")]
+pub use patch::{JsonDiffNode, JsonPatchNode, PatchError, PatchOperation};
+
+// Re-export task queue nodes
+#[cfg(feature = "builtin-nodes")]
+pub use tasks::{DequeueTaskNode, EnqueueTaskNode};
+
+// Re-export graph memory nodes
+#[cfg(feature = "builtin-nodes")]
+pub use graph::{QueryGraphNode, UpsertFactNode};
 
 // Re-export LLM components
 #[cfg(feature = "builtin-llm")]
-pub use llm::{ApiConfig, ApiRequestNode, MockLlmNode};
+pub use llm::{ApiConfig, ApiConfigError, ApiRequestNode, AuthStyle, MockLlmNode, Provider};
+
+// Re-export Gemini components
+#[cfg(feature = "builtin-llm")]
+pub use gemini::{GeminiConfig, GeminiRequestNode};
+
+// Re-export local inference nodes
+#[cfg(feature = "local-inference")]
+pub use local::LocalLlmNode;
+
+// Re-export reasoning nodes
+#[cfg(feature = "builtin-llm")]
+pub use reasoning::ReasoningNode;
+
+// Re-export ensemble nodes
+#[cfg(feature = "builtin-llm")]
+pub use ensemble::EnsembleLlmNode;
+
+// Re-export cascade nodes
+#[cfg(feature = "builtin-llm")]
+pub use cascade::CascadeLlmNode;
+
+// Re-export tree search nodes
+#[cfg(feature = "builtin-llm")]
+pub use search::TreeSearchNode;
+
+// Re-export prompt template nodes
+#[cfg(feature = "prompt-library")]
+pub use prompt::PromptTemplateNode;
+
+// Re-export regex nodes
+#[cfg(feature = "regex-nodes")]
+pub use text::{RegexExtractNode, RegexValidateNode};
+
+// Re-export text normalization nodes
+#[cfg(feature = "normalize-text")]
+pub use normalize::{NormalizeTextNode, TextFormat};
+
+// Re-export notification nodes
+#[cfg(feature = "notify")]
+pub use notify::{SendEmailNode, SlackWebhookNode, SmtpSecurity, WebhookNotifyNode};
@@ -0,0 +1,352 @@
+//! Circuit breaker wrapper for nodes calling flaky external services
+//!
+//! A [`CircuitBreaker`] counts consecutive exec failures. Once the failure
+//! threshold is reached the circuit *opens*: wrapped nodes stop calling the
+//! service and return a fallback action instead. After the reset timeout a
+//! single probe call is let through (*half-open*); its outcome either closes
+//! the circuit again or re-opens it.
+//!
+//! Clones of a breaker share state, so one breaker can guard every node (and
+//! every flow instance) that talks to the same endpoint.
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::InMemoryNode;
+//! # use pocketflow_rs::node::builtin::LogNode;
+//! # use pocketflow_rs::node::circuit_breaker::{CircuitBreaker, CircuitBreakerNode};
+//! # use std::time::Duration;
+//! let breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+//! let node: InMemoryNode<_> = Node::new(
+//!     CircuitBreakerNode::new(LogNode::new("call api", Action::simple("done")), breaker.clone())
+//!         .with_fallback_action(Action::simple("use_cache")),
+//! );
+//! ```
+
+use super::{ExecutionContext, KeySpec, NodeBackend};
+use crate::{Action, SharedStore, StorageBackend};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Action returned by a [`CircuitBreakerNode`] while its circuit is open, unless overridden
+pub const DEFAULT_FALLBACK_ACTION: &str = "circuit_open";
+
+/// Externally visible state of a circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through; failures are being counted
+    Closed,
+    /// Calls are short-circuited until the reset timeout elapses
+    Open,
+    /// A probe call is allowed to test whether the service recovered
+    HalfOpen,
+}
+
+#[derive(Debug)]
+enum BreakerState {
+    Closed { failures: usize },
+    Open { until: Instant },
+    HalfOpen { probe_in_flight: bool },
+}
+
+/// Failure counter and state machine shared by circuit breaker nodes
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    state: Arc<Mutex<BreakerState>>,
+    failure_threshold: usize,
+    reset_timeout: Duration,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker that opens after `failure_threshold` consecutive
+    /// failures and probes again after `reset_timeout`
+    pub fn new(failure_threshold: usize, reset_timeout: Duration) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BreakerState::Closed { failures: 0 })),
+            failure_threshold: failure_threshold.max(1),
+            reset_timeout,
+        }
+    }
+
+    /// Get the current state
+    pub fn state(&self) -> CircuitState {
+        match *self.lock() {
+            BreakerState::Closed { .. } => CircuitState::Closed,
+            BreakerState::Open { until } if Instant::now() >= until => CircuitState::HalfOpen,
+            BreakerState::Open { .. } => CircuitState::Open,
+            BreakerState::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Number of consecutive failures while closed
+    pub fn failures(&self) -> usize {
+        match *self.lock() {
+            BreakerState::Closed { failures } => failures,
+            _ => self.failure_threshold,
+        }
+    }
+
+    /// Ask to make a call; `false` means the call should be short-circuited
+    ///
+    /// In the half-open state only one caller gets `true` until its outcome is
+    /// recorded.
+    pub fn allow_request(&self) -> bool {
+        let mut state = self.lock();
+        match *state {
+            BreakerState::Closed { .. } => true,
+            BreakerState::Open { until } if Instant::now() >= until => {
+                *state = BreakerState::HalfOpen {
+                    probe_in_flight: true,
+                };
+                true
+            }
+            BreakerState::Open { .. } => false,
+            BreakerState::HalfOpen {
+                ref mut probe_in_flight,
+            } => !std::mem::replace(probe_in_flight, true),
+        }
+    }
+
+    /// Record a successful call, closing the circuit
+    pub fn record_success(&self) {
+        *self.lock() = BreakerState::Closed { failures: 0 };
+    }
+
+    /// Record a failed call, opening the circuit once the threshold is reached
+    pub fn record_failure(&self) {
+        let mut state = self.lock();
+        let failures = match *state {
+            BreakerState::Closed { failures } => failures + 1,
+            // A failed probe (or a late failure while open) re-opens immediately
+            _ => self.failure_threshold,
+        };
+        *state = if failures >= self.failure_threshold {
+            BreakerState::Open {
+                until: Instant::now() + self.reset_timeout,
+            }
+        } else {
+            BreakerState::Closed { failures }
+        };
+    }
+
+    /// Force the circuit closed
+    pub fn reset(&self) {
+        self.record_success();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Node wrapper that stops calling its inner node while the circuit is open
+///
+/// Every exec attempt (including retries) asks the breaker first. A
+/// short-circuited attempt skips the inner node's exec and post and the node
+/// returns the fallback action, so flows can route around a degraded service.
+pub struct CircuitBreakerNode<B> {
+    inner: B,
+    breaker: CircuitBreaker,
+    fallback_action: Action,
+}
+
+impl<B> CircuitBreakerNode<B> {
+    /// Wrap a node with a breaker
+    pub fn new(inner: B, breaker: CircuitBreaker) -> Self {
+        Self {
+            inner,
+            breaker,
+            fallback_action: Action::simple(DEFAULT_FALLBACK_ACTION),
+        }
+    }
+
+    /// Set the action returned while the circuit is open
+    pub fn with_fallback_action(mut self, action: Action) -> Self {
+        self.fallback_action = action;
+        self
+    }
+
+    /// Get the breaker guarding this node
+    pub fn breaker(&self) -> &CircuitBreaker {
+        &self.breaker
+    }
+
+    /// Get the wrapped node
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<S, B> NodeBackend<S> for CircuitBreakerNode<B>
+where
+    S: StorageBackend + Send + Sync,
+    B: NodeBackend<S>,
+    B::PrepResult: Sync,
+{
+    type PrepResult = B::PrepResult;
+    /// `None` when the call was short-circuited
+    type ExecResult = Option<B::ExecResult>;
+    type Error = B::Error;
+
+    async fn prep(
+        &mut self,
+        store: &SharedStore<S>,
+        context: &ExecutionContext,
+    ) -> Result<Self::PrepResult, Self::Error> {
+        self.inner.prep(store, context).await
+    }
+
+    async fn exec(
+        &mut self,
+        prep_result: Self::PrepResult,
+        context: &ExecutionContext,
+    ) -> Result<Self::ExecResult, Self::Error> {
+        if !self.breaker.allow_request() {
+            return Ok(None);
+        }
+        match self.inner.exec(prep_result, context).await {
+            Ok(result) => {
+                self.breaker.record_success();
+                Ok(Some(result))
+            }
+            Err(error) => {
+                self.breaker.record_failure();
+                Err(error)
+            }
+        }
+    }
+
+    async fn post(
+        &mut self,
+        store: &mut SharedStore<S>,
+        prep_result: Self::PrepResult,
+        exec_result: Self::ExecResult,
+        context: &ExecutionContext,
+    ) -> Result<Action, Self::Error> {
+        match exec_result {
+            Some(result) => self.inner.post(store, prep_result, result, context).await,
+            None => Ok(self.fallback_action.clone()),
+        }
+    }
+
+    async fn exec_fallback(
+        &mut self,
+        prep_result: Self::PrepResult,
+        error: Self::Error,
+        context: &ExecutionContext,
+    ) -> Result<Self::ExecResult, Self::Error> {
+        self.inner
+            .exec_fallback(prep_result, error, context)
+            .await
+            .map(Some)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_retryable(&self, error: &Self::Error) -> bool {
+        self.inner.is_retryable(error)
+    }
+
+    fn is_side_effecting(&self) -> bool {
+        self.inner.is_side_effecting()
+    }
+
+    fn intent(&self, prep_result: &Self::PrepResult) -> Option<serde_json::Value> {
+        self.inner.intent(prep_result)
+    }
+
+    fn reads(&self) -> Vec<KeySpec> {
+        self.inner.reads()
+    }
+
+    fn writes(&self) -> Option<Vec<KeySpec>> {
+        self.inner.writes()
+    }
+
+    fn max_retries(&self) -> usize {
+        self.inner.max_retries()
+    }
+
+    fn retry_delay(&self) -> Duration {
+        self.inner.retry_delay()
+    }
+}
+
+#[cfg(all(test, feature = "storage-memory"))]
+mod tests {
+    use super::*;
+    use crate::node::{FunctionNode, Node};
+    use crate::storage::InMemoryStorage;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    fn flaky(
+        healthy: Arc<AtomicBool>,
+        calls: Arc<AtomicUsize>,
+    ) -> FunctionNode<InMemoryStorage, (), ()> {
+        FunctionNode::new(
+            "flaky".to_string(),
+            |_store: &SharedStore<InMemoryStorage>, _ctx| (),
+            move |_, _ctx| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                if healthy.load(Ordering::SeqCst) {
+                    Ok(())
+                } else {
+                    Err("service unavailable".into())
+                }
+            },
+            |_store, _prep, _result, _ctx| Ok(Action::simple("ok")),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_and_recovers() {
+        let healthy = Arc::new(AtomicBool::new(false));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(20));
+        let mut store = SharedStore::new();
+
+        // Two failing runs trip the breaker
+        for _ in 0..2 {
+            let mut node = Node::new(CircuitBreakerNode::new(
+                flaky(healthy.clone(), calls.clone()).with_retries(0),
+                breaker.clone(),
+            ));
+            assert!(node.run(&mut store).await.is_err());
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // While open, the service isn't called and the fallback action is returned
+        let mut node = Node::new(
+            CircuitBreakerNode::new(flaky(healthy.clone(), calls.clone()), breaker.clone())
+                .with_fallback_action(Action::simple("degraded")),
+        );
+        let calls_before = calls.load(Ordering::SeqCst);
+        assert_eq!(node.run(&mut store).await.unwrap().name(), "degraded");
+        assert_eq!(calls.load(Ordering::SeqCst), calls_before);
+
+        // After the reset timeout a successful probe closes the circuit
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        healthy.store(true, Ordering::SeqCst);
+        assert_eq!(node.run(&mut store).await.unwrap().name(), "ok");
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.failures(), 0);
+    }
+
+    #[test]
+    fn test_half_open_allows_single_probe() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        breaker.record_failure();
+        assert!(breaker.allow_request());
+        assert!(!breaker.allow_request());
+
+        breaker.record_failure();
+        assert!(breaker.allow_request());
+        breaker.record_success();
+        assert!(breaker.allow_request());
+        assert!(breaker.allow_request());
+    }
+}
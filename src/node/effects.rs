@@ -0,0 +1,320 @@
+//! Idempotent external effects
+//!
+//! Retries and resumed flows run `exec` again, which must not send the same
+//! email or charge the same card twice. An [`EffectLedger`] records each
+//! effect that succeeded under an idempotency key chosen by the node, such as
+//! `"email:order-123"`, together with what the effect returned.
+//! [`EffectLedger::run_effect_once`] runs an effect only if its key isn't
+//! recorded yet, and otherwise hands back the recorded result.
+//!
+//! Like a [`MemoTable`](super::MemoTable), the ledger can live in any
+//! [`StorageBackend`]; back it with a file, Redis or a database for it to
+//! survive restarts. Clones share entries, so a node keeps a clone and the
+//! flow's owner keeps another. Concurrent calls with the same key may both
+//! run the effect, so give each key a single owner.
+//!
+//! ```rust
+//! # use pocketflow_rs::node::effects::EffectLedger;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let ledger = EffectLedger::in_memory();
+//! for _attempt in 0..2 {
+//!     let message_id: String = ledger
+//!         .run_effect_once("email:order-123", || async {
+//!             // Send the email here
+//!             Ok::<_, std::io::Error>("msg-1".to_string())
+//!         })
+//!         .await
+//!         .unwrap();
+//!     assert_eq!(message_id, "msg-1");
+//! }
+//! assert_eq!(ledger.effects().unwrap().len(), 1);
+//! # }
+//! ```
+
+use crate::StorageBackend;
+use crate::env::RuntimeEnv;
+use crate::storage::InMemoryStorage;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::UNIX_EPOCH;
+
+/// Key prefix ledger entries are stored under by default
+pub const DEFAULT_EFFECT_PREFIX: &str = "effect:";
+
+/// Error running an effect through a ledger
+#[derive(Debug, thiserror::Error)]
+pub enum EffectError<E> {
+    /// The effect itself failed; nothing was recorded
+    #[error("Effect '{key}' failed: {error}")]
+    Failed { key: String, error: E },
+    /// The ledger could not be read or written, or holds an entry that isn't
+    /// a record or a result of another type; a failed write leaves a
+    /// succeeded effect unrecorded
+    #[error("Effect ledger error for '{key}': {message}")]
+    Ledger { key: String, message: String },
+}
+
+/// An effect that succeeded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectRecord {
+    pub key: String,
+    /// What the effect returned, such as a message or payment ID
+    pub result: Value,
+    /// Time the effect was recorded, in milliseconds since the Unix epoch
+    pub recorded_at: u64,
+}
+
+/// Succeeded effects by idempotency key, shared by every clone
+pub struct EffectLedger<M = InMemoryStorage> {
+    storage: Arc<Mutex<M>>,
+    prefix: String,
+    env: RuntimeEnv,
+}
+
+impl<M> Clone for EffectLedger<M> {
+    fn clone(&self) -> Self {
+        Self {
+            storage: self.storage.clone(),
+            prefix: self.prefix.clone(),
+            env: self.env.clone(),
+        }
+    }
+}
+
+impl EffectLedger<InMemoryStorage> {
+    /// Create a ledger kept in memory
+    pub fn in_memory() -> Self {
+        Self::new(InMemoryStorage::new())
+    }
+}
+
+impl<M: StorageBackend> EffectLedger<M> {
+    /// Create a ledger stored in `storage`
+    pub fn new(storage: M) -> Self {
+        Self {
+            storage: Arc::new(Mutex::new(storage)),
+            prefix: DEFAULT_EFFECT_PREFIX.to_string(),
+            env: RuntimeEnv::default(),
+        }
+    }
+
+    /// Store entries under another key prefix
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Take `recorded_at` timestamps from `env`'s clock
+    pub fn with_env(mut self, env: RuntimeEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// The recorded effect with idempotency key `key`
+    ///
+    /// An entry that isn't an [`EffectRecord`] is an [`EffectError::Ledger`]
+    /// rather than `None`, so it can't be mistaken for an effect that never ran.
+    pub fn get(&self, key: &str) -> Result<Option<EffectRecord>, EffectError<M::Error>> {
+        self.read(key).map_err(|message| EffectError::Ledger {
+            key: key.to_string(),
+            message,
+        })
+    }
+
+    /// Whether an effect with idempotency key `key` succeeded
+    pub fn contains(&self, key: &str) -> Result<bool, EffectError<M::Error>> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    /// Record that the effect with idempotency key `key` succeeded, for
+    /// effects performed outside [`EffectLedger::run_effect_once`]
+    pub fn record(&self, key: &str, result: Value) -> Result<EffectRecord, M::Error> {
+        let record = EffectRecord {
+            key: key.to_string(),
+            result,
+            recorded_at: self
+                .env
+                .system_time()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64),
+        };
+        let entry = serde_json::to_value(&record).expect("EffectRecord serializes to JSON");
+        self.lock().set(self.entry_key(key), entry)?;
+        Ok(record)
+    }
+
+    /// Forget an effect so it runs again, returning whether it was recorded
+    pub fn forget(&self, key: &str) -> Result<bool, M::Error> {
+        Ok(self.lock().remove(&self.entry_key(key))?.is_some())
+    }
+
+    /// Every recorded effect, oldest first
+    pub fn effects(&self) -> Result<Vec<EffectRecord>, M::Error> {
+        let storage = self.lock();
+        let mut effects = Vec::new();
+        for key in storage.keys()? {
+            if !key.starts_with(&self.prefix) {
+                continue;
+            }
+            if let Some(record) = storage
+                .get(&key)?
+                .and_then(|entry| serde_json::from_value::<EffectRecord>(entry).ok())
+            {
+                effects.push(record);
+            }
+        }
+        effects.sort_by(|a, b| (a.recorded_at, &a.key).cmp(&(b.recorded_at, &b.key)));
+        Ok(effects)
+    }
+
+    /// Remove every entry of this ledger
+    pub fn clear(&self) -> Result<(), M::Error> {
+        let mut storage = self.lock();
+        for key in storage.keys()? {
+            if key.starts_with(&self.prefix) {
+                storage.remove(&key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `effect` unless the ledger shows it already succeeded under `key`,
+    /// returning its result either way
+    ///
+    /// Only successes are recorded, so a failed effect runs again on the next
+    /// call.
+    pub async fn run_effect_once<T, E, F, Fut>(
+        &self,
+        key: &str,
+        effect: F,
+    ) -> Result<T, EffectError<E>>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+    {
+        let ledger_error = |message: String| EffectError::Ledger {
+            key: key.to_string(),
+            message,
+        };
+        if let Some(record) = self.read(key).map_err(ledger_error)? {
+            return serde_json::from_value(record.result).map_err(|e| ledger_error(e.to_string()));
+        }
+        let result = effect().await.map_err(|error| EffectError::Failed {
+            key: key.to_string(),
+            error,
+        })?;
+        let value = serde_json::to_value(&result).map_err(|e| ledger_error(e.to_string()))?;
+        self.record(key, value)
+            .map_err(|e| ledger_error(e.to_string()))?;
+        Ok(result)
+    }
+
+    /// The record under `key`, or a description of why it can't be read
+    fn read(&self, key: &str) -> Result<Option<EffectRecord>, String> {
+        let entry = self
+            .lock()
+            .get(&self.entry_key(key))
+            .map_err(|e| e.to_string())?;
+        entry
+            .map(|entry| {
+                serde_json::from_value(entry)
+                    .map_err(|e| format!("entry is not an effect record: {}", e))
+            })
+            .transpose()
+    }
+
+    fn entry_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, M> {
+        self.storage.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::ManualClock;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_run_effect_once_skips_recorded_effects() {
+        let ledger = EffectLedger::in_memory();
+        let sends = AtomicUsize::new(0);
+        let send = |fail: bool| {
+            let sends = &sends;
+            move || async move {
+                sends.fetch_add(1, Ordering::SeqCst);
+                if fail {
+                    Err("smtp timeout")
+                } else {
+                    Ok(format!("msg-{}", sends.load(Ordering::SeqCst)))
+                }
+            }
+        };
+
+        // A failure isn't recorded, so the retry sends
+        let failed = ledger.run_effect_once::<String, _, _, _>("email:1", send(true));
+        assert!(matches!(failed.await, Err(EffectError::Failed { .. })));
+        assert_eq!(
+            ledger
+                .run_effect_once("email:1", send(false))
+                .await
+                .unwrap(),
+            "msg-2"
+        );
+        // A resumed run gets the recorded message ID without sending again
+        let resumed = ledger.clone();
+        assert_eq!(
+            resumed
+                .run_effect_once("email:1", send(false))
+                .await
+                .unwrap(),
+            "msg-2"
+        );
+        assert_eq!(sends.load(Ordering::SeqCst), 2);
+        assert_eq!(ledger.get("email:1").unwrap().unwrap().result, "msg-2");
+
+        // A recorded result of another type is a ledger error
+        let mismatched = ledger.run_effect_once::<u64, &str, _, _>("email:1", || async { Ok(1) });
+        assert!(matches!(mismatched.await, Err(EffectError::Ledger { .. })));
+
+        // So is an entry that isn't a record, and the effect doesn't run over it
+        ledger
+            .lock()
+            .set("effect:email:3".to_string(), json!("garbage"))
+            .unwrap();
+        assert!(matches!(
+            ledger.get("email:3"),
+            Err(EffectError::Ledger { .. })
+        ));
+        let garbled = ledger.run_effect_once("email:3", send(false));
+        assert!(matches!(garbled.await, Err(EffectError::Ledger { .. })));
+        assert_eq!(sends.load(Ordering::SeqCst), 2);
+        ledger.forget("email:3").unwrap();
+
+        ledger.record("email:2", Value::Null).unwrap();
+        assert_eq!(ledger.effects().unwrap().len(), 2);
+        assert!(ledger.forget("email:1").unwrap());
+        assert!(!ledger.contains("email:1").unwrap());
+        ledger.clear().unwrap();
+        assert!(ledger.effects().unwrap().is_empty());
+    }
+    #[test]
+    fn test_record_takes_time_from_env() {
+        let clock = Arc::new(ManualClock::new(UNIX_EPOCH + Duration::from_secs(10)));
+        let ledger =
+            EffectLedger::in_memory().with_env(RuntimeEnv::new().with_clock(clock.clone()));
+
+        assert_eq!(ledger.record("a", Value::Null).unwrap().recorded_at, 10_000);
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(ledger.record("b", Value::Null).unwrap().recorded_at, 10_005);
+    }
+}
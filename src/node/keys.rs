@@ -0,0 +1,146 @@
+//! Store keys a node reads and writes
+//!
+//! Nodes can list the keys they read ([`NodeBackend::reads`]) and write
+//! ([`NodeBackend::writes`]), optionally with a JSON schema for each. Flow
+//! validation then checks along every path that a key is written before a
+//! node reads it and that the writer's schema fits the reader's, so a typo'd
+//! key name fails `validate` instead of surfacing as a missing value mid-run.
+//!
+//! ```rust
+//! # use pocketflow_rs::node::keys::KeySpec;
+//! # use serde_json::json;
+//! let spec = KeySpec::new("summary").with_schema(json!({"type": "string"}));
+//! assert_eq!(spec.key, "summary");
+//! ```
+//!
+//! [`NodeBackend::reads`]: super::NodeBackend::reads
+//! [`NodeBackend::writes`]: super::NodeBackend::writes
+
+use serde_json::Value;
+
+/// A store key, optionally with the JSON schema of its value
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySpec {
+    pub key: String,
+    pub schema: Option<Value>,
+}
+
+impl KeySpec {
+    /// A key whose value may have any shape
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            schema: None,
+        }
+    }
+
+    /// Constrain the value to `schema`
+    pub fn with_schema(mut self, schema: Value) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+}
+
+impl From<&str> for KeySpec {
+    fn from(key: &str) -> Self {
+        Self::new(key)
+    }
+}
+
+impl From<String> for KeySpec {
+    fn from(key: String) -> Self {
+        Self::new(key)
+    }
+}
+
+/// Types a schema allows, or `None` if it doesn't restrict them
+fn schema_types(schema: &Value) -> Option<Vec<&str>> {
+    match schema.get("type")? {
+        Value::String(ty) => Some(vec![ty.as_str()]),
+        Value::Array(types) => Some(types.iter().filter_map(Value::as_str).collect()),
+        _ => None,
+    }
+}
+
+/// Describe why a value matching `written` may not match `read`, if it can't
+///
+/// Only `type`, `properties`, `required` and `items` are compared; anything
+/// else is assumed compatible.
+pub(crate) fn schema_mismatch(written: &Value, read: &Value) -> Option<String> {
+    if let (Some(written_types), Some(read_types)) = (schema_types(written), schema_types(read)) {
+        let allowed = |ty: &str| {
+            read_types.contains(&ty) || (ty == "integer" && read_types.contains(&"number"))
+        };
+        if let Some(ty) = written_types.into_iter().find(|ty| !allowed(ty)) {
+            return Some(format!("'{}' is not one of {:?}", ty, read_types));
+        }
+    }
+
+    let written_properties = written.get("properties").and_then(Value::as_object);
+    let written_required = |name: &str| {
+        written
+            .get("required")
+            .and_then(Value::as_array)
+            .is_some_and(|required| required.iter().any(|r| r == name))
+    };
+    for name in read
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        // A writer listing its properties must provide every required one
+        if written_properties.is_some() && !written_required(name) {
+            return Some(format!("property '{}' may be missing", name));
+        }
+    }
+    if let (Some(written_properties), Some(read_properties)) = (
+        written_properties,
+        read.get("properties").and_then(Value::as_object),
+    ) {
+        for (name, read_property) in read_properties {
+            if let Some(written_property) = written_properties.get(name)
+                && let Some(mismatch) = schema_mismatch(written_property, read_property)
+            {
+                return Some(format!("property '{}': {}", name, mismatch));
+            }
+        }
+    }
+
+    if let (Some(written_items), Some(read_items)) = (written.get("items"), read.get("items")) {
+        return schema_mismatch(written_items, read_items).map(|m| format!("items: {}", m));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_schema_mismatch() {
+        let person = json!({
+            "type": "object",
+            "properties": {"name": {"type": "string"}, "age": {"type": "integer"}},
+            "required": ["name", "age"]
+        });
+        let reader = json!({
+            "type": "object",
+            "properties": {"age": {"type": "number"}},
+            "required": ["age"]
+        });
+        assert_eq!(schema_mismatch(&person, &reader), None);
+        assert_eq!(schema_mismatch(&json!({}), &reader), None);
+        assert!(schema_mismatch(&json!({"type": "string"}), &reader).is_some());
+
+        let partial = json!({"type": "object", "properties": {"name": {"type": "string"}}});
+        assert_eq!(
+            schema_mismatch(&partial, &reader).as_deref(),
+            Some("property 'age' may be missing")
+        );
+        let lists = |ty| json!({"type": "array", "items": {"type": ty}});
+        assert!(schema_mismatch(&lists("string"), &lists("integer")).is_some());
+    }
+}
@@ -0,0 +1,328 @@
+//! Content-addressed memoization of node executions
+//!
+//! A [`MemoizedNode`] hashes its inner node's prep result together with the
+//! node name and an optional config fingerprint. If a [`MemoTable`] already
+//! holds an exec result for that input, exec is skipped and the stored result
+//! is passed to post; otherwise the inner exec runs and its result is saved.
+//! Re-running a pipeline whose upstream inputs haven't changed then only pays
+//! for prep and post.
+//!
+//! The memo table can live in any [`StorageBackend`], so results survive
+//! restarts when it is backed by a file, Redis or a database. Entries keep
+//! the full input and are only used when it matches exactly, so a hash
+//! collision costs a cache miss, never a wrong result. Errors reading or
+//! writing the table are treated as misses.
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::node::memo::{MemoTable, MemoizedNode};
+//! # use pocketflow_rs::node::builtin::LogNode;
+//! # use pocketflow_rs::InMemoryNode;
+//! let memo = MemoTable::in_memory();
+//! let node: InMemoryNode<_> = Node::new(
+//!     MemoizedNode::new(LogNode::new("summarize", Action::simple("done")), memo.clone())
+//!         .with_config(serde_json::json!({"model": "gpt-4o", "prompt_version": 3})),
+//! );
+//! ```
+
+use super::{ExecutionContext, KeySpec, NodeBackend};
+use crate::storage::InMemoryStorage;
+use crate::{Action, SharedStore, StorageBackend};
+use async_trait::async_trait;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Key prefix memo entries are stored under by default
+pub const DEFAULT_MEMO_PREFIX: &str = "memo:";
+
+/// Stored exec results, shared by every clone
+pub struct MemoTable<M = InMemoryStorage> {
+    storage: Arc<Mutex<M>>,
+    prefix: String,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+impl<M> Clone for MemoTable<M> {
+    fn clone(&self) -> Self {
+        Self {
+            storage: self.storage.clone(),
+            prefix: self.prefix.clone(),
+            hits: self.hits.clone(),
+            misses: self.misses.clone(),
+        }
+    }
+}
+
+impl MemoTable<InMemoryStorage> {
+    /// Create a table kept in memory
+    pub fn in_memory() -> Self {
+        Self::new(InMemoryStorage::new())
+    }
+}
+
+impl<M: StorageBackend> MemoTable<M> {
+    /// Create a table stored in `storage`
+    pub fn new(storage: M) -> Self {
+        Self {
+            storage: Arc::new(Mutex::new(storage)),
+            prefix: DEFAULT_MEMO_PREFIX.to_string(),
+            hits: Arc::default(),
+            misses: Arc::default(),
+        }
+    }
+
+    /// Store entries under another key prefix
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Number of executions answered from the table
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of executions that had to run
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Remove every entry of this table
+    pub fn clear(&self) -> Result<(), M::Error> {
+        let mut storage = self.lock();
+        for key in storage.keys()? {
+            if key.starts_with(&self.prefix) {
+                storage.remove(&key)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, M> {
+        self.storage.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The stored output for `input`, if there is one
+    fn lookup(&self, key: &str, input: &Value) -> Option<Value> {
+        let entry = self.lock().get(key).ok()??;
+        (entry.get("input") == Some(input)).then(|| entry.get("output").cloned())?
+    }
+
+    fn store(&self, key: String, input: Value, output: Value) {
+        let entry = json!({"input": input, "output": output});
+        let _ = self.lock().set(key, entry);
+    }
+}
+
+/// Node wrapper that skips exec when the same input was already executed
+///
+/// Each exec attempt checks the table first; only successful inner execs are
+/// stored, so failures and fallback results are never replayed.
+pub struct MemoizedNode<B, M = InMemoryStorage> {
+    inner: B,
+    table: MemoTable<M>,
+    config: Value,
+}
+
+impl<B, M: StorageBackend> MemoizedNode<B, M> {
+    /// Wrap a node, memoizing its exec results in `table`
+    pub fn new(inner: B, table: MemoTable<M>) -> Self {
+        Self {
+            inner,
+            table,
+            config: Value::Null,
+        }
+    }
+
+    /// Include the node's settings in the hash, so changing them (a model or
+    /// prompt version, say) invalidates earlier results
+    pub fn with_config(mut self, config: impl Serialize) -> Self {
+        self.config = serde_json::to_value(config).unwrap_or(Value::Null);
+        self
+    }
+
+    /// Get the memo table
+    pub fn table(&self) -> &MemoTable<M> {
+        &self.table
+    }
+
+    /// Get the wrapped node
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+}
+
+/// 64-bit FNV-1a, stable across builds and platforms
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[async_trait]
+impl<S, B, M> NodeBackend<S> for MemoizedNode<B, M>
+where
+    S: StorageBackend + Send + Sync,
+    B: NodeBackend<S>,
+    B::PrepResult: Serialize,
+    B::ExecResult: Serialize + DeserializeOwned,
+    M: StorageBackend + 'static,
+{
+    type PrepResult = B::PrepResult;
+    type ExecResult = B::ExecResult;
+    type Error = B::Error;
+
+    async fn prep(
+        &mut self,
+        store: &SharedStore<S>,
+        context: &ExecutionContext,
+    ) -> Result<Self::PrepResult, Self::Error> {
+        self.inner.prep(store, context).await
+    }
+
+    async fn exec(
+        &mut self,
+        prep_result: Self::PrepResult,
+        context: &ExecutionContext,
+    ) -> Result<Self::ExecResult, Self::Error> {
+        // Inputs that can't be serialized are never memoized
+        let Ok(prep) = serde_json::to_value(&prep_result) else {
+            return self.inner.exec(prep_result, context).await;
+        };
+        let input = json!({"node": self.inner.name(), "config": self.config, "prep": prep});
+        let key = format!(
+            "{}{}:{:016x}",
+            self.table.prefix,
+            self.inner.name(),
+            fnv1a(input.to_string().as_bytes())
+        );
+
+        if let Some(result) = self
+            .table
+            .lookup(&key, &input)
+            .and_then(|output| serde_json::from_value(output).ok())
+        {
+            self.table.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(result);
+        }
+        self.table.misses.fetch_add(1, Ordering::Relaxed);
+        let result = self.inner.exec(prep_result, context).await?;
+        if let Ok(output) = serde_json::to_value(&result) {
+            self.table.store(key, input, output);
+        }
+        Ok(result)
+    }
+
+    async fn post(
+        &mut self,
+        store: &mut SharedStore<S>,
+        prep_result: Self::PrepResult,
+        exec_result: Self::ExecResult,
+        context: &ExecutionContext,
+    ) -> Result<Action, Self::Error> {
+        self.inner
+            .post(store, prep_result, exec_result, context)
+            .await
+    }
+
+    async fn exec_fallback(
+        &mut self,
+        prep_result: Self::PrepResult,
+        error: Self::Error,
+        context: &ExecutionContext,
+    ) -> Result<Self::ExecResult, Self::Error> {
+        self.inner.exec_fallback(prep_result, error, context).await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_retryable(&self, error: &Self::Error) -> bool {
+        self.inner.is_retryable(error)
+    }
+
+    fn is_side_effecting(&self) -> bool {
+        self.inner.is_side_effecting()
+    }
+
+    fn intent(&self, prep_result: &Self::PrepResult) -> Option<Value> {
+        self.inner.intent(prep_result)
+    }
+
+    fn reads(&self) -> Vec<KeySpec> {
+        self.inner.reads()
+    }
+
+    fn writes(&self) -> Option<Vec<KeySpec>> {
+        self.inner.writes()
+    }
+
+    fn max_retries(&self) -> usize {
+        self.inner.max_retries()
+    }
+
+    fn retry_delay(&self) -> Duration {
+        self.inner.retry_delay()
+    }
+}
+
+#[cfg(all(test, feature = "storage-memory"))]
+mod tests {
+    use super::*;
+    use crate::node::{FunctionNode, Node};
+    use std::sync::atomic::AtomicUsize;
+
+    #[tokio::test]
+    async fn test_memoized_node_skips_repeated_execs() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let table = MemoTable::in_memory();
+        let square = |config: i64| {
+            let calls = calls.clone();
+            Node::new(
+                MemoizedNode::new(
+                    FunctionNode::new(
+                        "square".to_string(),
+                        |store: &SharedStore<InMemoryStorage>, _ctx| {
+                            store.get("n").unwrap().unwrap().as_i64().unwrap()
+                        },
+                        move |n, _ctx| {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            Ok(n * n)
+                        },
+                        |store, _prep, squared, _ctx| {
+                            store.set("squared".to_string(), json!(squared))?;
+                            Ok(Action::simple("done"))
+                        },
+                    ),
+                    table.clone(),
+                )
+                .with_config(config),
+            )
+        };
+        let mut store = SharedStore::new();
+
+        store.set("n".to_string(), json!(7)).unwrap();
+        square(1).run(&mut store).await.unwrap();
+        square(1).run(&mut store).await.unwrap();
+        assert_eq!(store.get("squared").unwrap(), Some(json!(49)));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!((table.hits(), table.misses()), (1, 1));
+
+        // A new input or config runs again
+        store.set("n".to_string(), json!(8)).unwrap();
+        square(1).run(&mut store).await.unwrap();
+        square(2).run(&mut store).await.unwrap();
+        assert_eq!(store.get("squared").unwrap(), Some(json!(64)));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        table.clear().unwrap();
+        square(2).run(&mut store).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
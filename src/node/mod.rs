@@ -78,6 +78,11 @@
 //! - **Retry Management**: Current attempt, max retries, delays
 //! - **Unique Identification**: Execution IDs for tracking and correlation
 //! - **Metadata Storage**: Additional context data for complex flows
+//! - **Parameters**: Per-execution configuration supplied by the flow
+//! - **Variables**: Transient [`FlowVars`] shared by the nodes of one run and
+//!   never persisted
+//! - **Scoped Tasks**: `spawn_scoped` and [`TaskGroup`] run concurrent work
+//!   that is aborted when the node run ends, instead of detached tokio tasks
 //! - **Flow Coordination**: Cross-node communication and state
 //!
 //! ## Built-in Node Types
@@ -86,17 +91,42 @@
 //!
 //! ### Basic Nodes (feature: `builtin-nodes`)
 //! - **LogNode**: Simple logging with configurable output
+//! - **StructuredLogNode**: Leveled `tracing` events with store interpolation and a `__log` record
 //! - **SetValueNode**: Write values to shared store
 //! - **GetValueNode**: Read and validate shared store values  
-//! - **DelayNode**: Configurable execution delays
+//! - **DelayNode**: Delays for a duration, until a stored time or a cron-aligned
+//!   boundary
+//! - **AwaitKeysNode**: Waits (with a timeout) until a set of keys exist
+//! - **CounterNode** / **AccumulateNode**: Loop counters with a threshold and
+//!   bounded history arrays
 //! - **ConditionalNode**: Branching logic based on store state
+//! - **SwitchNode**: Multi-way branching on a store value, with a default
+//! - **AsyncConditionalNode**: Branching on an async condition, with an optional
+//!   audit trail of decisions
 //!
 //! ### LLM Nodes (feature: `builtin-llm`)
 //! - **ApiRequestNode**: Configurable HTTP API calls with streaming support
+//! - **GeminiRequestNode**: Google Gemini API calls with the same input/output conventions
+//! - **ReasoningNode**: Step-by-step reasoning with a scratchpad in the store
+//! - **EnsembleLlmNode**: Concurrent samples aggregated by vote, LLM judge or closure
+//! - **CascadeLlmNode**: Cheap model first, escalating when a validator or judge scores
+//!   its answer too low
+//! - **TreeSearchNode**: Tree-of-thought beam search over candidate reasoning steps
 //! - **MockLlmNode**: Testing and development placeholder
+//! - **LocalLlmNode**: GGUF models run in-process (feature: `local-inference`)
 //!
 //! ## Advanced Features
 //!
+//! ### CircuitBreakerNode
+//! Wrap nodes that call flaky services in a [`CircuitBreakerNode`]; once a shared
+//! [`CircuitBreaker`] sees enough consecutive failures, calls are skipped and a
+//! fallback action is returned until the service recovers.
+//!
+//! ### EffectLedger
+//! Side-effecting `exec`s can run each external effect through an
+//! [`EffectLedger`] with an idempotency key, so retries and resumed flows
+//! don't send the same email or charge the same card twice.
+//!
 //! ### FunctionNode
 //! For rapid prototyping, create nodes from closures:
 //!
@@ -114,11 +144,16 @@
 //! ).with_retries(3).with_retry_delay(Duration::from_millis(100));
 //! ```
 //!
+//! ### Outputs
+//! An [`Outputs`] writes the fields of a JSON result to declared store keys,
+//! so one extraction node can fill several keys from its `post`.
+//!
 //! ### Error Handling
 //! Comprehensive error system supporting:
 //! - **Automatic Retries**: Configurable retry counts and delays
 //! - **Graceful Fallbacks**: Custom error recovery strategies
-//! - **Error Classification**: Different error types for different handling
+//! - **Error Classification**: Different error types for different handling;
+//!   fatal errors (`NodeError::fatal`, validation errors) skip remaining retries
 //! - **Context Preservation**: Error context carried through execution
 //!
 //! ## Design Principles
@@ -130,10 +165,14 @@
 //! 5. **Performance**: Minimal allocations, efficient async execution
 //! 6. **Extensibility**: Easy to implement custom node types
 
+use crate::env::RuntimeEnv;
+use crate::shared_store::{Intent, IntentStatus};
+use crate::tenant::TenantContext;
 use crate::{Action, PocketFlowError, PocketFlowResult, SharedStore, StorageBackend};
 use async_trait::async_trait;
-use std::time::Duration;
-use tokio::time::sleep;
+use scope::{ScopeGuard, TaskScope};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 // Type aliases to reduce complexity warnings
 type PrepFn<S, P> = Box<dyn Fn(&SharedStore<S>, &ExecutionContext) -> P + Send + Sync>;
@@ -164,6 +203,35 @@ pub enum NodeError {
     ValidationError(String),
     #[error("Preparation error: {0}")]
     PrepError(String),
+    /// An error that retrying cannot fix, such as rejected credentials
+    #[error("Fatal error: {0}")]
+    Fatal(String),
+    /// The node wrote a store key its access policy does not allow
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+}
+
+impl NodeError {
+    /// Create an error that is not worth retrying
+    pub fn fatal(message: impl Into<String>) -> Self {
+        NodeError::Fatal(message.into())
+    }
+
+    /// Create an execution error that may succeed on retry
+    pub fn retryable(message: impl Into<String>) -> Self {
+        NodeError::ExecutionError(message.into())
+    }
+
+    /// Whether retrying the failed phase could succeed
+    ///
+    /// Fatal, validation and permission errors are not retryable; everything
+    /// else is.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(
+            self,
+            NodeError::Fatal(_) | NodeError::ValidationError(_) | NodeError::PermissionDenied(_)
+        )
+    }
 }
 
 impl From<String> for NodeError {
@@ -178,6 +246,12 @@ impl From<&str> for NodeError {
     }
 }
 
+impl From<ScopedTaskError> for NodeError {
+    fn from(error: ScopedTaskError) -> Self {
+        NodeError::ExecutionError(error.to_string())
+    }
+}
+
 /// Represents the execution context for a node, containing the current retry count
 /// and other execution metadata.
 #[derive(Debug, Clone)]
@@ -192,20 +266,133 @@ pub struct ExecutionContext {
     pub execution_id: String,
     /// Additional metadata for the execution
     pub metadata: std::collections::HashMap<String, serde_json::Value>,
+    /// Per-execution parameters supplied by the flow (static node params merged
+    /// with the parameters of the action that routed to this node)
+    pub params: std::collections::HashMap<String, serde_json::Value>,
+    /// Point in time by which the enclosing flow must finish, if it has a
+    /// `max_duration`
+    pub deadline: Option<Instant>,
+    /// Clock, randomness and ID generation for this execution
+    pub env: RuntimeEnv,
+    /// Tenant the execution is made for, if the flow serves several
+    pub tenant: Option<TenantContext>,
+    /// Variables of the enclosing flow run
+    pub vars: FlowVars,
+    /// ID of the node in its flow, if it runs in one
+    pub node_id: Option<String>,
+    /// Log the operation of a side-effecting node before `exec`, see
+    /// [`NodeBackend::intent`]
+    pub log_intents: bool,
+    /// ID tying together everything done for one request, across flows and
+    /// services; builtin nodes send it as the [`REQUEST_ID_HEADER`]
+    pub correlation_id: Option<String>,
+    /// Tasks spawned with `spawn_scoped` or `task_group` during the node run
+    scope: TaskScope,
 }
 
+/// HTTP header carrying [`ExecutionContext::correlation_id`]
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
 impl ExecutionContext {
     /// Create a new execution context
     pub fn new(max_retries: usize, retry_delay: Duration) -> Self {
+        let env = RuntimeEnv::default();
         Self {
             current_retry: 0,
             max_retries,
             retry_delay,
-            execution_id: uuid::Uuid::new_v4().to_string(),
+            execution_id: env.next_id(),
             metadata: std::collections::HashMap::new(),
+            params: std::collections::HashMap::new(),
+            deadline: None,
+            env,
+            tenant: None,
+            vars: FlowVars::new(),
+            node_id: None,
+            log_intents: false,
+            correlation_id: None,
+            scope: TaskScope::default(),
         }
     }
 
+    /// Set the runtime environment, drawing a new execution ID from it
+    pub fn with_env(mut self, env: RuntimeEnv) -> Self {
+        self.execution_id = env.next_id();
+        self.env = env;
+        self
+    }
+
+    /// Set the execution parameters, replacing any existing ones
+    pub fn with_params(
+        mut self,
+        params: std::collections::HashMap<String, serde_json::Value>,
+    ) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Set the deadline
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Set the tenant
+    pub fn with_tenant(mut self, tenant: Option<TenantContext>) -> Self {
+        self.tenant = tenant;
+        self
+    }
+
+    /// Get the tenant, if there is one
+    pub fn tenant(&self) -> Option<&TenantContext> {
+        self.tenant.as_ref()
+    }
+
+    /// Share the variables of a flow run
+    pub fn with_vars(mut self, vars: FlowVars) -> Self {
+        self.vars = vars;
+        self
+    }
+
+    /// Get the variables of the flow run, which live until the run ends and
+    /// are never written to the store
+    pub fn vars(&self) -> &FlowVars {
+        &self.vars
+    }
+
+    /// Set the ID of the node in its flow
+    pub fn with_node_id(mut self, node_id: impl Into<String>) -> Self {
+        self.node_id = Some(node_id.into());
+        self
+    }
+
+    /// Set the correlation ID of the request being served
+    pub fn with_correlation_id(mut self, correlation_id: Option<String>) -> Self {
+        self.correlation_id = correlation_id;
+        self
+    }
+
+    /// Log the operation of a side-effecting node in [`SystemKeys::INTENTS`]
+    /// before `exec` and mark it completed or failed afterwards
+    ///
+    /// [`SystemKeys::INTENTS`]: crate::shared_store::SystemKeys::INTENTS
+    pub fn with_intent_log(mut self, enabled: bool) -> Self {
+        self.log_intents = enabled;
+        self
+    }
+
+    /// Time left until the deadline, if there is one
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(self.env.now()))
+    }
+
+    /// Check whether the deadline has passed
+    pub fn deadline_exceeded(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| self.env.now() >= deadline)
+    }
+
     /// Check if more retries are available
     pub fn can_retry(&self) -> bool {
         self.current_retry < self.max_retries
@@ -216,6 +403,11 @@ impl ExecutionContext {
         self.current_retry += 1;
     }
 
+    /// Get the runtime environment
+    pub fn env(&self) -> &RuntimeEnv {
+        &self.env
+    }
+
     /// Get the execution ID
     pub fn execution_id(&self) -> &str {
         &self.execution_id
@@ -240,6 +432,16 @@ impl ExecutionContext {
     pub fn metadata(&self) -> &std::collections::HashMap<String, serde_json::Value> {
         &self.metadata
     }
+
+    /// Get parameter value by key
+    pub fn get_param(&self, key: &str) -> Option<&serde_json::Value> {
+        self.params.get(key)
+    }
+
+    /// Get all parameters
+    pub fn params(&self) -> &std::collections::HashMap<String, serde_json::Value> {
+        &self.params
+    }
 }
 
 /// Core trait for implementing custom node backends.
@@ -320,6 +522,44 @@ pub trait NodeBackend<S: StorageBackend>: Send + Sync {
         std::any::type_name::<Self>()
     }
 
+    /// Whether a failed exec() should be retried
+    ///
+    /// By default `NodeError`s are classified by [`NodeError::is_retryable`] and
+    /// any other error type is retried. Non-retryable errors go straight to
+    /// `exec_fallback`.
+    fn is_retryable(&self, error: &Self::Error) -> bool {
+        (error as &(dyn std::error::Error + 'static))
+            .downcast_ref::<NodeError>()
+            .is_none_or(NodeError::is_retryable)
+    }
+
+    /// Whether exec() acts on the outside world (calls paid APIs, sends
+    /// messages, writes files), so a dry run should stub it instead
+    fn is_side_effecting(&self) -> bool {
+        false
+    }
+
+    /// Describe the operation `exec` will perform with `prep_result`, such as
+    /// the recipient and subject of an email, for the intent log
+    ///
+    /// Only called for side-effecting nodes run with an intent log. The
+    /// description should identify the external resource well enough to
+    /// check or undo it after a crash.
+    fn intent(&self, _prep_result: &Self::PrepResult) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Store keys the node reads, checked by flow validation
+    fn reads(&self) -> Vec<KeySpec> {
+        Vec::new()
+    }
+
+    /// Store keys the node writes, or `None` if it doesn't declare them (it
+    /// may then write any key)
+    fn writes(&self) -> Option<Vec<KeySpec>> {
+        None
+    }
+
     /// Get maximum number of retries for this node
     fn max_retries(&self) -> usize {
         1 // Default: no retries
@@ -338,9 +578,32 @@ where
     S: StorageBackend,
 {
     backend: B,
+    last_stats: Option<NodeRunStats>,
     _phantom: std::marker::PhantomData<S>,
 }
 
+/// Timing and retry statistics for one run of a node
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeRunStats {
+    /// Number of times `exec` was called (0 if prep failed)
+    pub attempts: usize,
+    pub prep_duration: Duration,
+    /// Time spent in `exec`, including retry delays and the fallback
+    pub exec_duration: Duration,
+    pub post_duration: Duration,
+    /// Phase the run failed in, if it failed
+    pub failed_phase: Option<NodePhase>,
+}
+
+/// One of the three phases of a node run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodePhase {
+    Prep,
+    Exec,
+    Post,
+}
+
 impl<B, S> Node<B, S>
 where
     B: NodeBackend<S>,
@@ -350,33 +613,142 @@ where
     pub fn new(backend: B) -> Self {
         Self {
             backend,
+            last_stats: None,
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Statistics of the most recent run, including a failed one
+    pub fn last_run_stats(&self) -> Option<&NodeRunStats> {
+        self.last_stats.as_ref()
+    }
+
     /// Run the complete node execution cycle: prep -> exec -> post
     pub async fn run(&mut self, store: &mut SharedStore<S>) -> PocketFlowResult<Action> {
-        let context = ExecutionContext::new(self.backend.max_retries(), self.backend.retry_delay());
+        self.run_with_params(store, std::collections::HashMap::new())
+            .await
+    }
+
+    /// Run the complete node execution cycle with per-execution parameters,
+    /// which are exposed to every phase through `ExecutionContext::params`
+    pub async fn run_with_params(
+        &mut self,
+        store: &mut SharedStore<S>,
+        params: std::collections::HashMap<String, serde_json::Value>,
+    ) -> PocketFlowResult<Action> {
+        self.run_with_deadline(store, params, None).await
+    }
+
+    /// Run with per-execution parameters and a deadline exposed through
+    /// `ExecutionContext::deadline`; retries stop once the deadline has passed
+    pub async fn run_with_deadline(
+        &mut self,
+        store: &mut SharedStore<S>,
+        params: std::collections::HashMap<String, serde_json::Value>,
+        deadline: Option<Instant>,
+    ) -> PocketFlowResult<Action> {
+        let context = ExecutionContext::new(0, Duration::ZERO)
+            .with_params(params)
+            .with_deadline(deadline);
+        self.run_with_context(store, context).await
+    }
+
+    /// Run with a prepared context, e.g. one carrying a flow's
+    /// [`RuntimeEnv`]; retry settings are taken from the backend
+    pub async fn run_with_context(
+        &mut self,
+        store: &mut SharedStore<S>,
+        mut context: ExecutionContext,
+    ) -> PocketFlowResult<Action> {
+        context.max_retries = self.backend.max_retries();
+        context.retry_delay = self.backend.retry_delay();
+        let mut stats = NodeRunStats::default();
+        let result = self.run_phases(store, context, &mut stats).await;
+        self.last_stats = Some(stats);
+        result
+    }
+
+    /// Run prep, exec and post, recording timings in `stats`
+    async fn run_phases(
+        &mut self,
+        store: &mut SharedStore<S>,
+        mut context: ExecutionContext,
+        stats: &mut NodeRunStats,
+    ) -> PocketFlowResult<Action> {
+        // Scoped tasks end with the run, also when it is dropped midway
+        context.scope = TaskScope::default();
+        let _scope = ScopeGuard(context.scope.clone());
 
         // Prep phase
-        let prep_result = self
-            .backend
-            .prep(store, &context)
-            .await
-            .map_err(|e| PocketFlowError::ExecutionError(format!("Prep failed: {}", e)))?;
+        let started = context.env.now();
+        let prep_result = self.backend.prep(store, &context).await;
+        stats.prep_duration = context.env.now() - started;
+        let prep_result = prep_result.map_err(|e| {
+            stats.failed_phase = Some(NodePhase::Prep);
+            PocketFlowError::ExecutionError(format!("Prep failed: {}", e))
+        })?;
+
+        // Write-ahead intent for side-effecting nodes
+        let intent_id = if context.log_intents && self.backend.is_side_effecting() {
+            let intent = Intent {
+                id: context.execution_id.clone(),
+                node_id: context
+                    .node_id
+                    .clone()
+                    .unwrap_or_else(|| self.backend.name().to_string()),
+                operation: self
+                    .backend
+                    .intent(&prep_result)
+                    .unwrap_or(serde_json::Value::Null),
+                status: IntentStatus::Pending,
+                started_at: epoch_millis(&context.env),
+                finished_at: None,
+                correlation_id: context.correlation_id.clone(),
+            };
+            let id = intent.id.clone();
+            store.record_intent(intent).map_err(|e| {
+                stats.failed_phase = Some(NodePhase::Prep);
+                PocketFlowError::ExecutionError(format!("Logging intent failed: {}", e))
+            })?;
+            Some(id)
+        } else {
+            None
+        };
 
         // Exec phase with retries
+        let started = context.env.now();
         let exec_result = self
-            .exec_with_retries(prep_result.clone(), context.clone())
-            .await
-            .map_err(|e| PocketFlowError::ExecutionError(format!("Exec failed: {}", e)))?;
+            .exec_with_retries(prep_result.clone(), context.clone(), &mut stats.attempts)
+            .await;
+        stats.exec_duration = context.env.now() - started;
+        if let Some(id) = intent_id {
+            let status = match exec_result {
+                Ok(_) => IntentStatus::Completed,
+                Err(_) => IntentStatus::Failed,
+            };
+            store
+                .finish_intent(&id, status, epoch_millis(&context.env))
+                .map_err(|e| {
+                    stats.failed_phase = Some(NodePhase::Exec);
+                    PocketFlowError::ExecutionError(format!("Logging intent failed: {}", e))
+                })?;
+        }
+        let exec_result = exec_result.map_err(|e| {
+            stats.failed_phase = Some(NodePhase::Exec);
+            PocketFlowError::ExecutionError(format!("Exec failed: {}", e))
+        })?;
 
         // Post phase
+        let started = context.env.now();
         let action = self
             .backend
             .post(store, prep_result, exec_result, &context)
-            .await
-            .map_err(|e| PocketFlowError::ExecutionError(format!("Post failed: {}", e)))?;
+            .await;
+        stats.post_duration = context.env.now() - started;
+        let action = action.map_err(|e| {
+            stats.failed_phase = Some(NodePhase::Post);
+            PocketFlowError::ExecutionError(format!("Post failed: {}", e))
+        })?;
 
         Ok(action)
     }
@@ -386,20 +758,25 @@ where
         &mut self,
         prep_result: B::PrepResult,
         mut context: ExecutionContext,
+        attempts: &mut usize,
     ) -> Result<B::ExecResult, B::Error> {
         loop {
+            *attempts += 1;
             match self.backend.exec(prep_result.clone(), &context).await {
                 Ok(result) => return Ok(result),
                 Err(error) => {
-                    if context.can_retry() {
+                    if context.can_retry()
+                        && !context.deadline_exceeded()
+                        && self.backend.is_retryable(&error)
+                    {
                         // Wait before retry
                         if context.retry_delay > Duration::ZERO {
-                            sleep(context.retry_delay).await;
+                            context.env.sleep(context.retry_delay).await;
                         }
                         context.next_retry();
                         continue;
                     } else {
-                        // All retries exhausted, try fallback
+                        // All retries exhausted or the error is fatal, try fallback
                         match self
                             .backend
                             .exec_fallback(prep_result, error, &context)
@@ -430,6 +807,17 @@ where
 // Convenience type aliases for common node types
 pub type InMemoryNode<B> = Node<B, crate::storage::InMemoryStorage>;
 
+/// Wall-clock milliseconds since the Unix epoch, as journal timestamps use
+fn epoch_millis(env: &RuntimeEnv) -> u64 {
+    env.system_time()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Channel that nodes producing incremental output (e.g. LLM token deltas)
+/// send each chunk to as it arrives
+pub type TokenSender = tokio::sync::mpsc::UnboundedSender<String>;
+
 /// Builder for creating nodes with custom configuration
 pub struct NodeBuilder<B> {
     backend: B,
@@ -463,6 +851,9 @@ where
     post_fn: PostFn<S, P, E>,
     max_retries: usize,
     retry_delay: Duration,
+    side_effecting: bool,
+    reads: Vec<KeySpec>,
+    writes: Option<Vec<KeySpec>>,
 }
 
 impl<S, P, E> FunctionNode<S, P, E>
@@ -501,6 +892,9 @@ where
             post_fn: Box::new(post_fn),
             max_retries: 1,
             retry_delay: Duration::from_secs(0),
+            side_effecting: false,
+            reads: Vec::new(),
+            writes: None,
         }
     }
 
@@ -515,6 +909,25 @@ where
         self.retry_delay = delay;
         self
     }
+
+    /// Mark the exec function as having side effects, see
+    /// [`NodeBackend::is_side_effecting`]
+    pub fn side_effecting(mut self) -> Self {
+        self.side_effecting = true;
+        self
+    }
+
+    /// Declare the keys prep reads, see [`NodeBackend::reads`]
+    pub fn with_reads<K: Into<KeySpec>>(mut self, keys: impl IntoIterator<Item = K>) -> Self {
+        self.reads = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Declare the keys post writes, see [`NodeBackend::writes`]
+    pub fn with_writes<K: Into<KeySpec>>(mut self, keys: impl IntoIterator<Item = K>) -> Self {
+        self.writes = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
 }
 
 #[async_trait]
@@ -541,7 +954,12 @@ where
         prep_result: Self::PrepResult,
         context: &ExecutionContext,
     ) -> Result<Self::ExecResult, Self::Error> {
-        (self.exec_fn)(prep_result, context).map_err(|e| NodeError::ExecutionError(e.to_string()))
+        // Keep the classification of NodeErrors returned by the closure
+        (self.exec_fn)(prep_result, context).map_err(|e| {
+            e.downcast::<NodeError>()
+                .map(|e| *e)
+                .unwrap_or_else(|e| NodeError::ExecutionError(e.to_string()))
+        })
     }
 
     async fn post(
@@ -559,6 +977,18 @@ where
         &self.name
     }
 
+    fn is_side_effecting(&self) -> bool {
+        self.side_effecting
+    }
+
+    fn reads(&self) -> Vec<KeySpec> {
+        self.reads.clone()
+    }
+
+    fn writes(&self) -> Option<Vec<KeySpec>> {
+        self.writes.clone()
+    }
+
     fn max_retries(&self) -> usize {
         self.max_retries
     }
@@ -569,6 +999,23 @@ where
 }
 
 pub mod builtin;
+pub mod circuit_breaker;
+pub mod effects;
+pub mod keys;
+pub mod memo;
+pub mod outputs;
+pub mod registry;
+pub mod scope;
+pub mod vars;
+
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerNode, CircuitState};
+pub use effects::{EffectError, EffectLedger, EffectRecord};
+pub use keys::KeySpec;
+pub use memo::{MemoTable, MemoizedNode};
+pub use outputs::Outputs;
+pub use registry::NodeRegistry;
+pub use scope::{ScopedTask, ScopedTaskError, TaskGroup};
+pub use vars::FlowVars;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,123 @@
+//! Writing several store keys from one result
+//!
+//! An [`Outputs`] maps the fields of a JSON object (typically an LLM
+//! extraction) to store keys, so a single node can populate `title`, `summary`
+//! and `tags` at once. Backends call [`Outputs::write`] from `post` and return
+//! [`Outputs::keys`] from `writes` so flow validation sees every key.
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::node::Outputs;
+//! # use serde_json::json;
+//! let outputs = Outputs::new().field("title").field_as("tags", "labels");
+//! let mut store = SharedStore::new();
+//! outputs
+//!     .write(&mut store, &json!({"title": "Hi", "tags": ["a"], "extra": 1}))
+//!     .unwrap();
+//! assert_eq!(store.get("labels").unwrap(), Some(json!(["a"])));
+//! assert!(!store.contains_key("extra").unwrap());
+//! ```
+
+use super::{KeySpec, NodeError};
+use crate::{SharedStore, StorageBackend};
+use serde_json::Value;
+
+/// Fields of a JSON object written to store keys
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outputs {
+    fields: Vec<(String, KeySpec)>,
+}
+
+impl Outputs {
+    /// Create a mapping with no fields
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write `field` under a key of the same name
+    pub fn field(self, field: impl Into<String>) -> Self {
+        let field = field.into();
+        let key = KeySpec::new(field.clone());
+        self.field_as(field, key)
+    }
+
+    /// Write `field` under `key`, which may carry a schema for validation
+    pub fn field_as(mut self, field: impl Into<String>, key: impl Into<KeySpec>) -> Self {
+        self.fields.push((field.into(), key.into()));
+        self
+    }
+
+    /// Whether no fields are mapped
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// The store keys written
+    pub fn keys(&self) -> Vec<KeySpec> {
+        self.fields.iter().map(|(_, key)| key.clone()).collect()
+    }
+
+    /// Pick the mapped fields out of `value` as `(key, value)` pairs
+    ///
+    /// Fails if `value` isn't an object or lacks a mapped field.
+    pub fn extract(&self, value: &Value) -> Result<Vec<(String, Value)>, String> {
+        let Value::Object(object) = value else {
+            return Err(format!("expected a JSON object, got {}", value));
+        };
+        self.fields
+            .iter()
+            .map(|(field, key)| {
+                object
+                    .get(field)
+                    .map(|value| (key.key.clone(), value.clone()))
+                    .ok_or_else(|| format!("missing field '{}'", field))
+            })
+            .collect()
+    }
+
+    /// Write the mapped fields of `value` to the store
+    ///
+    /// Nothing is written unless every field is present.
+    pub fn write<S: StorageBackend>(
+        &self,
+        store: &mut SharedStore<S>,
+        value: &Value,
+    ) -> Result<(), NodeError> {
+        for (key, value) in self.extract(value).map_err(NodeError::ValidationError)? {
+            store
+                .set(key, value)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: Into<String>> FromIterator<F> for Outputs {
+    fn from_iter<I: IntoIterator<Item = F>>(fields: I) -> Self {
+        fields.into_iter().fold(Self::new(), Outputs::field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract() {
+        let outputs: Outputs = ["title", "summary"].into_iter().collect();
+        assert_eq!(
+            outputs.extract(&json!({"title": "T", "summary": null})),
+            Ok(vec![
+                ("title".to_string(), json!("T")),
+                ("summary".to_string(), Value::Null)
+            ])
+        );
+        assert_eq!(
+            outputs.extract(&json!({"title": "T"})),
+            Err("missing field 'summary'".to_string())
+        );
+        assert!(outputs.extract(&json!("T")).is_err());
+        assert_eq!(outputs.keys()[1], KeySpec::new("summary"));
+    }
+}
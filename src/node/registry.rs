@@ -0,0 +1,209 @@
+//! Named node factories for flows assembled from text
+//!
+//! A [`NodeRegistry`] maps names to functions producing fresh nodes, so a
+//! flow definition can refer to nodes by name (see
+//! [`BasicFlow::from_mermaid`](crate::flow::BasicFlow::from_mermaid)).
+//!
+//! Nodes registered with [`NodeRegistry::register_configurable`] are built
+//! from a serializable config, which a deployed flow can patch at runtime
+//! with [`BasicFlow::override_node_config`](crate::flow::BasicFlow::override_node_config).
+
+use super::{ExecutionContext, KeySpec, Node, NodeBackend, NodeError, NodeRunStats};
+use crate::flow::NodeRunner;
+use crate::{Action, SharedStore, StorageBackend};
+use async_trait::async_trait;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Factory producing a fresh, type-erased node
+pub type NodeFactory<S> = Arc<dyn Fn() -> Box<dyn NodeRunner<S>> + Send + Sync>;
+
+/// Named node factories
+pub struct NodeRegistry<S: StorageBackend> {
+    factories: HashMap<String, NodeFactory<S>>,
+}
+
+impl<S: StorageBackend> Clone for NodeRegistry<S> {
+    fn clone(&self) -> Self {
+        Self {
+            factories: self.factories.clone(),
+        }
+    }
+}
+
+impl<S: StorageBackend> Default for NodeRegistry<S> {
+    fn default() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+}
+
+impl<S: StorageBackend> std::fmt::Debug for NodeRegistry<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeRegistry")
+            .field("nodes", &self.names())
+            .finish()
+    }
+}
+
+impl<S: StorageBackend> NodeRegistry<S> {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new instance of a registered node
+    pub fn create(&self, name: &str) -> Option<Box<dyn NodeRunner<S>>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+
+    /// Check whether a node is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Get the names of all registered nodes, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.factories.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> NodeRegistry<S> {
+    /// Register a node factory under a name, replacing any existing one
+    pub fn register<B, F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        B: NodeBackend<S> + Send + Sync + 'static,
+        B::Error: Send + Sync + 'static,
+        F: Fn() -> Node<B, S> + Send + Sync + 'static,
+    {
+        self.factories.insert(
+            name.into(),
+            Arc::new(move || Box::new(factory()) as Box<dyn NodeRunner<S>>),
+        );
+    }
+
+    /// Register a node built from a serializable config, starting from
+    /// `default`
+    ///
+    /// Nodes created from the registry report their config through
+    /// [`NodeRunner::config`] and are rebuilt by [`NodeRunner::configure`],
+    /// so model names, temperatures or retry counts can be changed without
+    /// code changes.
+    pub fn register_configurable<C, B, F>(
+        &mut self,
+        name: impl Into<String>,
+        default: C,
+        factory: F,
+    ) where
+        C: Serialize + DeserializeOwned + 'static,
+        B: NodeBackend<S> + Send + Sync + 'static,
+        B::Error: Send + Sync + 'static,
+        F: Fn(C) -> Node<B, S> + Send + Sync + 'static,
+    {
+        let build: ConfigFactory<S> = Arc::new(move |config: &Value| {
+            let config = C::deserialize(config)
+                .map_err(|e| NodeError::ValidationError(format!("Invalid node config: {}", e)))?;
+            Ok(Box::new(factory(config)) as Box<dyn NodeRunner<S>>)
+        });
+        let default = serde_json::to_value(default).expect("Node config serializes to JSON");
+        self.factories.insert(
+            name.into(),
+            Arc::new(move || {
+                let node = build(&default).expect("Default node config deserializes");
+                Box::new(ConfigurableNode {
+                    node,
+                    config: default.clone(),
+                    build: build.clone(),
+                }) as Box<dyn NodeRunner<S>>
+            }),
+        );
+    }
+}
+
+/// Builds a node from a JSON config
+type ConfigFactory<S> =
+    Arc<dyn Fn(&Value) -> Result<Box<dyn NodeRunner<S>>, NodeError> + Send + Sync>;
+
+/// A registry-built node that remembers its config so it can be rebuilt
+struct ConfigurableNode<S: StorageBackend> {
+    node: Box<dyn NodeRunner<S>>,
+    config: Value,
+    build: ConfigFactory<S>,
+}
+
+#[async_trait]
+impl<S: StorageBackend + Send + Sync> NodeRunner<S> for ConfigurableNode<S> {
+    async fn run(&mut self, store: &mut SharedStore<S>) -> Result<Action, NodeError> {
+        self.node.run(store).await
+    }
+
+    async fn run_with_params(
+        &mut self,
+        store: &mut SharedStore<S>,
+        params: HashMap<String, Value>,
+    ) -> Result<Action, NodeError> {
+        self.node.run_with_params(store, params).await
+    }
+
+    async fn run_with_context(
+        &mut self,
+        store: &mut SharedStore<S>,
+        context: ExecutionContext,
+    ) -> Result<Action, NodeError> {
+        self.node.run_with_context(store, context).await
+    }
+
+    fn last_run_stats(&self) -> Option<NodeRunStats> {
+        self.node.last_run_stats()
+    }
+
+    fn is_side_effecting(&self) -> bool {
+        self.node.is_side_effecting()
+    }
+
+    fn reads(&self) -> Vec<KeySpec> {
+        self.node.reads()
+    }
+
+    fn writes(&self) -> Option<Vec<KeySpec>> {
+        self.node.writes()
+    }
+
+    fn config(&self) -> Option<Value> {
+        Some(self.config.clone())
+    }
+
+    fn configure(&mut self, config: Value) -> Result<(), NodeError> {
+        self.node = (self.build)(&config)?;
+        self.config = config;
+        Ok(())
+    }
+}
+
+/// Apply a JSON merge patch (RFC 7386): objects merge recursively, `null`
+/// removes a field and anything else replaces the target
+pub(crate) fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let Value::Object(target) = target else {
+        unreachable!()
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
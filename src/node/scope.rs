@@ -0,0 +1,338 @@
+//! Structured concurrency inside node phases
+//!
+//! Work started with `tokio::spawn` outlives the node that started it: when
+//! the flow passes its deadline or the run is dropped, the detached task
+//! keeps running. Tasks spawned with [`ExecutionContext::spawn_scoped`] or
+//! through a [`TaskGroup`] from [`ExecutionContext::task_group`] belong to the
+//! node run instead. They are aborted when their [`ScopedTask`] or group is
+//! dropped, when the context's deadline passes, and at the latest when the
+//! node run ends, including when the run itself is dropped or aborted.
+//!
+//! ```rust
+//! # use pocketflow_rs::ExecutionContext;
+//! # use std::time::Duration;
+//! # #[tokio::main]
+//! # async fn main() {
+//! # let context = ExecutionContext::new(0, Duration::ZERO);
+//! // Inside `exec`: fetch pages two at a time
+//! let mut group = context.task_group().with_limit(2);
+//! for page in 1..=5 {
+//!     group.spawn(async move { format!("page {page}") });
+//! }
+//! let pages = group.join_all().await.unwrap();
+//! assert_eq!(pages[4], "page 5");
+//! # }
+//! ```
+//!
+//! A panicking task resumes the panic in whoever awaits it, as if the work
+//! had run inline.
+
+use super::ExecutionContext;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+use tokio::sync::Semaphore;
+use tokio::task::{AbortHandle, Id, JoinError, JoinHandle, JoinSet};
+use tokio::time::Instant;
+
+/// Why a scoped task produced no result
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ScopedTaskError {
+    /// The task was aborted before it finished
+    #[error("Scoped task was cancelled")]
+    Cancelled,
+    /// The execution deadline passed before the task finished
+    #[error("Scoped task ran past the execution deadline")]
+    DeadlineExceeded,
+}
+
+/// Abort handles of the tasks spawned during one node run
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TaskScope {
+    tasks: Arc<Mutex<Vec<AbortHandle>>>,
+}
+
+impl TaskScope {
+    fn register(&self, task: AbortHandle) {
+        let mut tasks = self.lock();
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task);
+    }
+
+    /// Abort every task still running
+    pub(crate) fn abort_all(&self) {
+        for task in self.lock().drain(..) {
+            task.abort();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<AbortHandle>> {
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Aborts the tasks of a scope when dropped
+pub(crate) struct ScopeGuard(pub(crate) TaskScope);
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        self.0.abort_all();
+    }
+}
+
+/// Run `task`, giving up at `deadline`
+async fn within<F: Future>(
+    deadline: Option<Instant>,
+    task: F,
+) -> Result<F::Output, ScopedTaskError> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, task)
+            .await
+            .map_err(|_| ScopedTaskError::DeadlineExceeded),
+        None => Ok(task.await),
+    }
+}
+
+/// The outcome of a joined task, resuming its panic
+fn settle<T>(joined: Result<Result<T, ScopedTaskError>, JoinError>) -> Result<T, ScopedTaskError> {
+    match joined {
+        Ok(result) => result,
+        Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+        Err(_) => Err(ScopedTaskError::Cancelled),
+    }
+}
+
+impl ExecutionContext {
+    /// Spawn `task` as part of this node run
+    ///
+    /// Await the returned [`ScopedTask`] for the task's output. Dropping it
+    /// aborts the task.
+    pub fn spawn_scoped<F>(&self, task: F) -> ScopedTask<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let handle = tokio::spawn(within(self.task_deadline(), task));
+        self.scope.register(handle.abort_handle());
+        ScopedTask { handle }
+    }
+
+    /// Start a group of concurrent tasks belonging to this node run
+    pub fn task_group<T: Send + 'static>(&self) -> TaskGroup<T> {
+        TaskGroup {
+            tasks: JoinSet::new(),
+            order: Vec::new(),
+            limit: None,
+            deadline: self.task_deadline(),
+            scope: self.scope.clone(),
+        }
+    }
+
+    fn task_deadline(&self) -> Option<Instant> {
+        self.remaining().map(|remaining| Instant::now() + remaining)
+    }
+}
+
+/// A task spawned with [`ExecutionContext::spawn_scoped`], aborted when
+/// dropped
+#[derive(Debug)]
+pub struct ScopedTask<T> {
+    handle: JoinHandle<Result<T, ScopedTaskError>>,
+}
+
+impl<T> ScopedTask<T> {
+    /// Abort the task
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    /// Whether the task has stopped running
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+impl<T> Future for ScopedTask<T> {
+    type Output = Result<T, ScopedTaskError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.get_mut().handle).poll(cx).map(settle)
+    }
+}
+
+impl<T> Drop for ScopedTask<T> {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Concurrent tasks of a node run, optionally limited in number, aborted
+/// when the group is dropped
+pub struct TaskGroup<T> {
+    tasks: JoinSet<Result<T, ScopedTaskError>>,
+    /// IDs of the spawned tasks, in spawn order
+    order: Vec<Id>,
+    limit: Option<Arc<Semaphore>>,
+    deadline: Option<Instant>,
+    scope: TaskScope,
+}
+
+impl<T: Send + 'static> TaskGroup<T> {
+    /// Run at most `max_concurrent` tasks at once; the others wait their turn
+    pub fn with_limit(mut self, max_concurrent: usize) -> Self {
+        self.limit = Some(Arc::new(Semaphore::new(max_concurrent.max(1))));
+        self
+    }
+
+    /// Spawn `task` into the group
+    pub fn spawn<F>(&mut self, task: F)
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let limit = self.limit.clone();
+        let handle = self.tasks.spawn(within(self.deadline, async move {
+            let _permit = match limit {
+                Some(limit) => Some(
+                    limit
+                        .acquire_owned()
+                        .await
+                        .expect("task group semaphore is never closed"),
+                ),
+                None => None,
+            };
+            task.await
+        }));
+        self.order.push(handle.id());
+        self.scope.register(handle);
+    }
+
+    /// Number of tasks not joined yet
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Whether every task has been joined
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Output of the next task to finish, `None` once every task is joined
+    pub async fn join_next(&mut self) -> Option<Result<T, ScopedTaskError>> {
+        Some(settle(self.tasks.join_next().await?))
+    }
+
+    /// Outputs of the tasks not joined yet, in spawn order
+    ///
+    /// The first task to fail aborts the rest.
+    pub async fn join_all(mut self) -> Result<Vec<T>, ScopedTaskError> {
+        let mut outputs = HashMap::new();
+        while let Some(joined) = self.tasks.join_next_with_id().await {
+            let id = match &joined {
+                Ok((id, _)) => *id,
+                Err(error) => error.id(),
+            };
+            let output = settle(joined.map(|(_, output)| output))?;
+            outputs.insert(id, output);
+        }
+        Ok(self
+            .order
+            .iter()
+            .filter_map(|id| outputs.remove(id))
+            .collect())
+    }
+
+    /// Abort every task of the group
+    pub fn abort_all(&mut self) {
+        self.tasks.abort_all();
+    }
+}
+
+#[cfg(all(test, feature = "storage-memory"))]
+mod tests {
+    use super::*;
+    use crate::node::{Node, NodeBackend, NodeError};
+    use crate::storage::InMemoryStorage;
+    use crate::{Action, SharedStore};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Starts a slow scoped task and waits for it
+    struct SlowNode {
+        finished: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl NodeBackend<InMemoryStorage> for SlowNode {
+        type PrepResult = ();
+        type ExecResult = ();
+        type Error = NodeError;
+
+        async fn prep(
+            &mut self,
+            _store: &SharedStore<InMemoryStorage>,
+            _context: &ExecutionContext,
+        ) -> Result<(), NodeError> {
+            Ok(())
+        }
+
+        async fn exec(&mut self, _prep: (), context: &ExecutionContext) -> Result<(), NodeError> {
+            let finished = self.finished.clone();
+            let task = context.spawn_scoped(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                finished.store(true, Ordering::SeqCst);
+            });
+            // Even a detached handle is aborted when the run ends
+            std::mem::forget(task);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok(())
+        }
+
+        async fn post(
+            &mut self,
+            _store: &mut SharedStore<InMemoryStorage>,
+            _prep: (),
+            _exec: (),
+            _context: &ExecutionContext,
+        ) -> Result<Action, NodeError> {
+            Ok(Action::simple("done"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scoped_tasks_end_with_the_node_run() {
+        let context = ExecutionContext::new(0, Duration::ZERO);
+        let (running, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let mut group = context.task_group().with_limit(2);
+        for n in 0..6u64 {
+            let (running, peak) = (running.clone(), peak.clone());
+            group.spawn(async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10 - n)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                n * n
+            });
+        }
+        assert_eq!(group.join_all().await.unwrap(), vec![0, 1, 4, 9, 16, 25]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+
+        // Tasks stop at the deadline
+        let context = context.with_deadline(Some(std::time::Instant::now()));
+        let late = context.spawn_scoped(tokio::time::sleep(Duration::from_secs(5)));
+        assert_eq!(late.await, Err(ScopedTaskError::DeadlineExceeded));
+
+        // Dropping a run mid-exec aborts the tasks it spawned
+        let finished = Arc::new(AtomicBool::new(false));
+        let mut node = Node::new(SlowNode {
+            finished: finished.clone(),
+        });
+        let mut store = SharedStore::new();
+        let run = tokio::time::timeout(Duration::from_millis(10), node.run(&mut store));
+        assert!(run.await.is_err());
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+}
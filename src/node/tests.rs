@@ -14,6 +14,71 @@ async fn test_log_node() {
     assert_eq!(result.unwrap().name(), "test_action");
 }
 
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_structured_log_node() {
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    store
+        .set("user".to_string(), json!({"name": "Ada", "id": 7}))
+        .unwrap();
+    store.set("count".to_string(), json!(3)).unwrap();
+
+    let mut log_node = Node::new(
+        StructuredLogNode::new(
+            "{user.name} has {count} items ({missing})",
+            Action::simple("logged"),
+        )
+        .with_level(tracing::Level::WARN)
+        .with_field("user.id")
+        .with_store_log(),
+    );
+    assert_eq!(log_node.run(&mut store).await.unwrap().name(), "logged");
+    log_node.run(&mut store).await.unwrap();
+
+    let log = store.get("__log").unwrap().unwrap();
+    let entries = log.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["message"], "Ada has 3 items ({missing})");
+    assert_eq!(entries[0]["level"], "WARN");
+    assert_eq!(entries[0]["fields"], json!({"user.id": 7}));
+    assert_eq!(entries[0]["node"], "StructuredLogNode");
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_structured_log_node_redacts() {
+    use crate::redact::Redactor;
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    store
+        .set(
+            "user".to_string(),
+            json!({"email": "ada@example.com", "password": "hunter2"}),
+        )
+        .unwrap();
+
+    let mut log_node = Node::new(
+        StructuredLogNode::new(
+            "login {user.email} / {user.password}",
+            Action::simple("logged"),
+        )
+        .with_field("user")
+        .with_store_log()
+        .with_redactor(Redactor::pii().with_key("password")),
+    );
+    log_node.run(&mut store).await.unwrap();
+
+    let log = store.get("__log").unwrap().unwrap();
+    assert_eq!(log[0]["message"], "login [REDACTED] / [REDACTED]");
+    assert_eq!(
+        log[0]["fields"],
+        json!({"user": {"email": "[REDACTED]", "password": "[REDACTED]"}})
+    );
+}
+
 #[cfg(feature = "builtin-nodes")]
 #[tokio::test]
 async fn test_set_value_node() {
@@ -97,6 +162,141 @@ async fn test_conditional_node() {
     assert_eq!(result.unwrap().name(), "false_action");
 }
 
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_counter_and_accumulate_nodes() {
+    use serde_json::json;
+
+    let mut counter = Node::new(
+        CounterNode::new("attempts", Action::simple("continue"))
+            .with_threshold(3, Action::simple("done"))
+            .reset_on_threshold(),
+    );
+    let mut history = Node::new(
+        AccumulateNode::new("observation", "history", Action::simple("next")).with_max_len(2),
+    );
+    let mut store = SharedStore::new();
+    let mut actions = Vec::new();
+    for step in 1..=3 {
+        store
+            .set("observation".to_string(), json!(format!("step {}", step)))
+            .unwrap();
+        history.run(&mut store).await.unwrap();
+        actions.push(counter.run(&mut store).await.unwrap().name());
+    }
+    assert_eq!(actions, ["continue", "continue", "done"]);
+    assert!(store.get("attempts").unwrap().is_none());
+    assert_eq!(
+        store.get("history").unwrap(),
+        Some(json!(["step 2", "step 3"]))
+    );
+
+    store.set("attempts".to_string(), json!("many")).unwrap();
+    assert!(counter.run(&mut store).await.is_err());
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_await_keys_node() {
+    use crate::storage::ConcurrentInMemoryStorage;
+    use serde_json::json;
+
+    let storage = ConcurrentInMemoryStorage::new();
+    let mut store = SharedStore::with_storage(storage.clone());
+    let mut node = Node::new(
+        AwaitKeysNode::new(["left", "right"], Duration::from_secs(5))
+            .with_poll_interval(Duration::from_millis(5)),
+    );
+    // Another writer fills in the branches while the node waits
+    let writer = tokio::spawn(async move {
+        let mut store = SharedStore::with_storage(storage);
+        store.set("left".to_string(), json!(1)).unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        store.set("right".to_string(), json!(2)).unwrap();
+    });
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "ready");
+    writer.await.unwrap();
+
+    let mut node = Node::new(
+        AwaitKeysNode::new(["left", "missing"], Duration::from_millis(30))
+            .with_poll_interval(Duration::from_millis(5)),
+    );
+    let started = Instant::now();
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "timeout");
+    assert!(started.elapsed() >= Duration::from_millis(30));
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_switch_node() {
+    use serde_json::json;
+
+    let mut node = Node::new(
+        SwitchNode::on_key("category", Action::simple("triage"))
+            .case("billing", Action::simple("billing"))
+            .case("support", Action::simple("support"))
+            .case("sales", Action::simple("sales"))
+            .ignore_case(),
+    );
+    let mut store = SharedStore::new();
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "triage");
+    store
+        .set("category".to_string(), json!(" Support\n"))
+        .unwrap();
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "support");
+    store.set("category".to_string(), json!("refunds")).unwrap();
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "triage");
+
+    // The value can also be computed from the store
+    let mut node = Node::new(
+        SwitchNode::on(
+            |store: &SharedStore<_>| {
+                let score = store.get("score").ok()??.as_u64()?;
+                Some(if score >= 8 { "high" } else { "low" }.to_string())
+            },
+            Action::simple("unknown"),
+        )
+        .case("high", Action::simple("escalate")),
+    );
+    store.set("score".to_string(), json!(9)).unwrap();
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "escalate");
+    store.set("score".to_string(), json!(2)).unwrap();
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "unknown");
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_async_conditional_node() {
+    use crate::node::builtin::Decision;
+    use serde_json::json;
+
+    // Stands in for an inventory service
+    let mut node = Node::new(
+        AsyncConditionalNode::new(
+            |inputs| async move {
+                tokio::task::yield_now().await;
+                let quantity = inputs["quantity"].as_u64().unwrap_or(0);
+                Ok(Decision::new(quantity <= 5)
+                    .with_rationale(format!("{} requested, 5 in stock", quantity)))
+            },
+            Action::simple("ship"),
+            Action::simple("backorder"),
+        )
+        .with_inputs(["quantity"])
+        .with_rationale_key("decisions"),
+    );
+    let mut store = SharedStore::new();
+    store.set("quantity".to_string(), json!(3)).unwrap();
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "ship");
+    store.set("quantity".to_string(), json!(8)).unwrap();
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "backorder");
+
+    let decisions = store.get("decisions").unwrap().unwrap();
+    assert_eq!(decisions[0]["outcome"], true);
+    assert_eq!(decisions[1]["action"], "backorder");
+    assert_eq!(decisions[1]["rationale"], "8 requested, 5 in stock");
+}
+
 #[cfg(feature = "builtin-nodes")]
 #[tokio::test]
 async fn test_delay_node() {
@@ -117,6 +317,44 @@ async fn test_delay_node() {
     assert_eq!(result.unwrap().name(), "delay_complete");
 }
 
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_delay_node_until_time() {
+    use crate::env::ManualClock;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    let clock = Arc::new(ManualClock::default());
+    let env = RuntimeEnv::deterministic(1).with_clock(clock.clone());
+    let context = || ExecutionContext::new(0, Duration::ZERO).with_env(env.clone());
+    let mut store = SharedStore::new();
+
+    store.set("send_at".to_string(), json!(5_000)).unwrap();
+    let mut node = Node::new(DelayNode::until_key("send_at", Action::simple("send")));
+    node.run_with_context(&mut store, context()).await.unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(5));
+
+    // A recorded wake time from an interrupted run is reused
+    store
+        .set("wake".to_string(), json!("1970-01-01T00:00:35Z"))
+        .unwrap();
+    let mut node = Node::new(
+        DelayNode::new(Duration::from_secs(60), Action::simple("next")).with_wake_key("wake"),
+    );
+    node.run_with_context(&mut store, context()).await.unwrap();
+    assert_eq!(clock.elapsed(), Duration::from_secs(35));
+    assert!(store.get("wake").unwrap().is_none());
+
+    #[cfg(feature = "cron")]
+    {
+        let mut node =
+            Node::new(DelayNode::cron("0 */15 * * * *", Action::simple("tick")).unwrap());
+        node.run_with_context(&mut store, context()).await.unwrap();
+        assert_eq!(clock.elapsed(), Duration::from_secs(15 * 60));
+        assert!(DelayNode::cron("every day", Action::simple("tick")).is_err());
+    }
+}
+
 #[cfg(feature = "builtin-llm")]
 #[tokio::test]
 async fn test_mock_llm_node() {
@@ -263,6 +501,155 @@ async fn test_node_error_handling() {
     );
 }
 
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_message_nodes_between_concurrent_flows() {
+    use crate::bus::AgentBus;
+    use serde_json::json;
+
+    let bus = AgentBus::new();
+
+    // Subscriptions are taken when the nodes are built, before either flow runs
+    let mut hinter = FlowBuilder::new()
+        .start_node("hint")
+        .node(
+            "hint",
+            Node::new(
+                SendMessageNode::new(&bus, "guesser", "hint", Action::simple("sent"))
+                    .with_sender("hinter"),
+            ),
+        )
+        .node(
+            "await_guess",
+            Node::new(ReceiveMessageNode::new(
+                &bus,
+                "hinter",
+                "guess",
+                Action::simple("guessed"),
+            )),
+        )
+        .node(
+            "stop",
+            Node::new(SendMessageNode::new(
+                &bus,
+                "verdict",
+                "verdict",
+                Action::simple("complete"),
+            )),
+        )
+        .route("hint", "sent", "await_guess")
+        .route("await_guess", "guessed", "stop")
+        .build();
+
+    let mut guesser = FlowBuilder::new()
+        .start_node("await_hint")
+        .node(
+            "await_hint",
+            Node::new(ReceiveMessageNode::new(
+                &bus,
+                "guesser",
+                "hint",
+                Action::simple("hinted"),
+            )),
+        )
+        .node(
+            "guess",
+            Node::new(SendMessageNode::new(
+                &bus,
+                "hinter",
+                "guess",
+                Action::simple("sent"),
+            )),
+        )
+        .node(
+            "await_verdict",
+            Node::new(
+                ReceiveMessageNode::new(&bus, "verdict", "verdict", Action::simple("retry"))
+                    .with_route(json!("correct"), Action::simple("complete"))
+                    .with_timeout(Duration::from_secs(5)),
+            ),
+        )
+        .route("await_hint", "hinted", "guess")
+        .route("guess", "sent", "await_verdict")
+        .build();
+
+    let mut hinter_store = SharedStore::new();
+    hinter_store
+        .set("hint".to_string(), json!("red fruit"))
+        .unwrap();
+    hinter_store
+        .set("verdict".to_string(), json!("correct"))
+        .unwrap();
+    let mut guesser_store = SharedStore::new();
+    guesser_store
+        .set("guess".to_string(), json!("apple"))
+        .unwrap();
+
+    let (hinter_result, guesser_result) = tokio::join!(
+        hinter.execute(&mut hinter_store),
+        guesser.execute(&mut guesser_store)
+    );
+
+    assert_eq!(hinter_result.unwrap().final_action.name(), "complete");
+    assert_eq!(guesser_result.unwrap().final_action.name(), "complete");
+    assert_eq!(hinter_store.get("guess").unwrap(), Some(json!("apple")));
+    assert_eq!(guesser_store.get("hint").unwrap(), Some(json!("red fruit")));
+    assert_eq!(
+        guesser_store.get("verdict").unwrap(),
+        Some(json!("correct"))
+    );
+}
+
+#[cfg(feature = "storage-memory")]
+#[tokio::test]
+async fn test_fatal_errors_skip_retries() {
+    use crate::node::NodeError;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let failing_node = |error: fn() -> NodeError, attempts: Arc<AtomicUsize>| {
+        FunctionNode::new(
+            "FailingNode".to_string(),
+            |_store: &SharedStore<InMemoryStorage>, _context: &ExecutionContext| (),
+            move |_input: (),
+                  _context: &ExecutionContext|
+                  -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(error().into())
+            },
+            |_store: &mut SharedStore<InMemoryStorage>,
+             _prep: (),
+             _result: (),
+             _context: &ExecutionContext|
+             -> Result<Action, Box<dyn std::error::Error + Send + Sync>> {
+                Ok(Action::simple("done"))
+            },
+        )
+        .with_retries(3)
+    };
+
+    let mut store = SharedStore::new();
+
+    let fatal_attempts = Arc::new(AtomicUsize::new(0));
+    let mut node = Node::new(failing_node(
+        || NodeError::fatal("invalid API key"),
+        fatal_attempts.clone(),
+    ));
+    assert!(node.run(&mut store).await.is_err());
+    assert_eq!(fatal_attempts.load(Ordering::SeqCst), 1);
+
+    let retryable_attempts = Arc::new(AtomicUsize::new(0));
+    let mut node = Node::new(failing_node(
+        || NodeError::retryable("connection reset"),
+        retryable_attempts.clone(),
+    ));
+    assert!(node.run(&mut store).await.is_err());
+    assert!(retryable_attempts.load(Ordering::SeqCst) > 1);
+
+    assert!(!NodeError::ValidationError("bad input".to_string()).is_retryable());
+    assert!(NodeError::StorageError("busy".to_string()).is_retryable());
+}
+
 #[tokio::test]
 async fn test_execution_context() {
     let context = ExecutionContext::new(3, Duration::from_millis(100));
@@ -288,17 +675,9 @@ async fn test_execution_context() {
 #[tokio::test]
 async fn test_api_request_node_creation() {
     let config = ApiConfig {
-        api_key: "test_key".to_string(),
-        base_url: None,
-        org_id: None,
-        model: "gpt-3.5-turbo".to_string(),
+        api_key: "test_key".into(),
         max_tokens: Some(100),
-        temperature: Some(0.7),
-        timeout: Some(30),
-        top_p: None,
-        frequency_penalty: None,
-        presence_penalty: None,
-        stream: false,
+        ..Default::default()
     };
 
     let api_node = ApiRequestNode::new("prompt", "response", Action::simple("next"))
@@ -337,3 +716,1649 @@ async fn test_api_request_node_prep_error() {
     assert!(result.is_err());
     assert!(result.unwrap_err().to_string().contains("not found"));
 }
+
+#[cfg(feature = "builtin-llm")]
+#[test]
+fn test_api_config_from_vars() {
+    use crate::node::builtin::ApiConfigError;
+    use std::collections::HashMap;
+
+    let vars = |pairs: &[(&str, &str)]| {
+        let map: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key: &str| map.get(key).cloned()
+    };
+
+    let config = ApiConfig::from_vars(vars(&[
+        ("POCKETFLOW_PROVIDER", "groq"),
+        ("GROQ_API_KEY", "gsk-test"),
+        ("POCKETFLOW_TEMPERATURE", "0.2"),
+    ]))
+    .unwrap();
+    assert_eq!(config.provider, Provider::Groq);
+    assert_eq!(config.api_key.expose().unwrap(), "gsk-test");
+    // The key never shows up in debug output
+    assert!(!format!("{:?}", config).contains("gsk-test"));
+    assert_eq!(
+        config.base_url.as_deref(),
+        Some("https://api.groq.com/openai/v1")
+    );
+    assert_eq!(config.model, Provider::Groq.default_model());
+    assert_eq!(config.temperature, Some(0.2));
+
+    let azure = ApiConfig::from_vars(vars(&[
+        ("POCKETFLOW_PROVIDER", "azure"),
+        ("POCKETFLOW_API_KEY", "key"),
+        ("AZURE_OPENAI_ENDPOINT", "https://example.openai.azure.com"),
+        ("AZURE_OPENAI_DEPLOYMENT", "gpt4o-prod"),
+    ]))
+    .unwrap();
+    assert_eq!(
+        azure.provider.auth_style(),
+        crate::node::builtin::AuthStyle::ApiKeyHeader
+    );
+    assert_eq!(azure.deployment.as_deref(), Some("gpt4o-prod"));
+    assert!(azure.api_version.is_some());
+
+    assert!(matches!(
+        ApiConfig::from_vars(vars(&[
+            ("POCKETFLOW_PROVIDER", "azure-openai"),
+            ("POCKETFLOW_API_KEY", "key")
+        ])),
+        Err(ApiConfigError::MissingVar("AZURE_OPENAI_ENDPOINT"))
+    ));
+    assert!(matches!(
+        ApiConfig::from_vars(vars(&[("POCKETFLOW_PROVIDER", "nope")])),
+        Err(ApiConfigError::UnknownProvider(_))
+    ));
+    assert!(matches!(
+        ApiConfig::from_vars(vars(&[("POCKETFLOW_PROVIDER", "deepseek")])),
+        Err(ApiConfigError::MissingApiKey("DEEPSEEK_API_KEY"))
+    ));
+    assert!(matches!(
+        ApiConfig::from_vars(vars(&[
+            ("OPENAI_API_KEY", "sk"),
+            ("POCKETFLOW_MAX_TOKENS", "lots")
+        ])),
+        Err(ApiConfigError::InvalidValue {
+            key: "POCKETFLOW_MAX_TOKENS",
+            ..
+        })
+    ));
+}
+
+#[cfg(feature = "builtin-llm")]
+#[test]
+fn test_azure_config_uses_deployment_urls() {
+    use async_openai::config::Config;
+
+    let config = ApiConfig::azure(
+        "https://my-resource.openai.azure.com/",
+        "azure-key",
+        "gpt4o-prod",
+    )
+    .with_api_version("2024-06-01");
+    let azure = config
+        .azure_config(&config.api_key.expose().unwrap())
+        .unwrap();
+    assert_eq!(
+        azure.url("/chat/completions"),
+        "https://my-resource.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions"
+    );
+    assert_eq!(azure.query(), vec![("api-version", "2024-06-01")]);
+    assert_eq!(azure.headers().get("api-key").unwrap(), "azure-key");
+
+    // Without an explicit deployment the model name is used
+    let config = ApiConfig::default()
+        .with_provider(Provider::AzureOpenAi)
+        .with_base_url("https://my-resource.openai.azure.com")
+        .with_model("gpt-4o");
+    assert_eq!(config.azure_deployment(), "gpt-4o");
+    assert!(
+        config
+            .azure_config("azure-key")
+            .unwrap()
+            .url("")
+            .ends_with("/deployments/gpt-4o")
+    );
+
+    assert!(
+        ApiConfig::default()
+            .with_provider(Provider::AzureOpenAi)
+            .azure_config("azure-key")
+            .is_err()
+    );
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_api_key_is_resolved_per_request() {
+    use crate::llm::{LlmError, OpenAiProvider};
+    use crate::secrets::Secret;
+
+    // An unset key fails the request, not the construction
+    let provider = OpenAiProvider::new(ApiConfig::new(Secret::env(
+        "POCKETFLOW_TEST_UNSET_OPENAI_KEY",
+    )))
+    .unwrap();
+    let error = provider
+        .chat(ChatRequest::new(vec![ChatMessage::user("Hi")]))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, LlmError::Rejected(_)));
+    assert!(
+        error
+            .to_string()
+            .contains("POCKETFLOW_TEST_UNSET_OPENAI_KEY")
+    );
+    assert!(format!("{:?}", provider).contains("env:POCKETFLOW_TEST_UNSET_OPENAI_KEY"));
+}
+
+/// Serve one canned HTTP response, returning the request it received
+#[cfg(any(feature = "builtin-llm", feature = "notify"))]
+async fn serve_once(
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+) -> (String, tokio::task::JoinHandle<String>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some(header_end) = text.find("\r\n\r\n") {
+                let length = text[..header_end]
+                    .lines()
+                    .find_map(|l| {
+                        l.to_ascii_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if request.len() >= header_end + 4 + length {
+                    break;
+                }
+            }
+        }
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&request).to_string()
+    });
+    (base_url, handle)
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_api_config_proxy_and_tls() {
+    use crate::http::HttpError;
+    use crate::llm::{ChatRequest, LlmError, OpenAiProvider};
+    use serde_json::json;
+
+    let body = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 0,
+        "model": "gpt-test",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "via proxy"}, "finish_reason": "stop"}]
+    });
+    let (proxy, server) = serve_once("200 OK", "application/json", body.to_string()).await;
+    assert!(ApiConfig::new("key").http_config().is_none());
+    let config = ApiConfig::new("key")
+        .with_base_url("http://api.internal.example/v1")
+        .with_proxy(proxy);
+    let provider = OpenAiProvider::new(config).unwrap();
+    let reply = provider
+        .chat(ChatRequest::new(vec![ChatMessage::user("Hi")]))
+        .await
+        .unwrap();
+    assert_eq!(reply.content, "via proxy");
+    // A proxied request names the absolute target URL
+    let request = server.await.unwrap();
+    assert!(request.starts_with("POST http://api.internal.example/v1/chat/completions "));
+
+    let config = ApiConfig::from_vars(|key| match key {
+        "OPENAI_API_KEY" => Some("sk".to_string()),
+        "POCKETFLOW_CA_CERTS" => Some("/nonexistent/corp-root.pem".to_string()),
+        "POCKETFLOW_ACCEPT_INVALID_CERTS" => Some("true".to_string()),
+        _ => None,
+    })
+    .unwrap();
+    assert!(config.accept_invalid_certs);
+    let http = config.http_config().unwrap();
+    assert!(matches!(
+        crate::http::HttpClient::new(http),
+        Err(HttpError::Certificate { .. })
+    ));
+    let provider = OpenAiProvider::new(config).unwrap();
+    let error = provider
+        .chat(ChatRequest::new(vec![ChatMessage::user("Hi")]))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, LlmError::InvalidRequest(message) if message.contains("corp-root.pem"))
+    );
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_gemini_request_node() {
+    use serde_json::json;
+
+    let body = json!({
+        "candidates": [{"content": {"role": "model", "parts": [{"text": "Hello"}, {"text": " there"}]}}]
+    });
+    let (base_url, server) = serve_once("200 OK", "application/json", body.to_string()).await;
+    let config = GeminiConfig::new("gemini-key")
+        .with_base_url(base_url)
+        .with_model("gemini-test")
+        .with_max_output_tokens(64)
+        .with_safety_setting("HARM_CATEGORY_HARASSMENT", "BLOCK_ONLY_HIGH");
+    let mut node = Node::new(
+        GeminiRequestNode::new("messages", "reply", Action::simple("next"))
+            .with_config(config)
+            .with_system_message("Be brief"),
+    );
+    let mut store = SharedStore::new();
+    store
+        .set(
+            "messages".to_string(),
+            json!([
+                {"role": "system", "content": "Answer in English"},
+                {"role": "user", "content": "Hi"},
+                {"role": "assistant", "content": "Hello!"},
+                {"role": "user", "content": "Greet me again"}
+            ]),
+        )
+        .unwrap();
+
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "next");
+    assert_eq!(store.get("reply").unwrap(), Some(json!("Hello there")));
+
+    let request = server.await.unwrap();
+    assert!(request.starts_with("POST /models/gemini-test:generateContent "));
+    assert!(
+        request
+            .to_ascii_lowercase()
+            .contains("x-goog-api-key: gemini-key")
+    );
+    let sent: serde_json::Value =
+        serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+    assert_eq!(
+        sent["systemInstruction"]["parts"][0]["text"],
+        "Be brief\n\nAnswer in English"
+    );
+    assert_eq!(sent["contents"][1]["role"], "model");
+    assert_eq!(sent["generationConfig"]["maxOutputTokens"], 64);
+    assert_eq!(sent["safetySettings"][0]["threshold"], "BLOCK_ONLY_HIGH");
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_gemini_streaming_and_errors() {
+    use crate::node::NodeBackend;
+    use serde_json::json;
+
+    let chunk = |text: &str| {
+        format!(
+            "data: {}\r\n\r\n",
+            json!({"candidates": [{"content": {"parts": [{"text": text}]}}]})
+        )
+    };
+    let (base_url, server) =
+        serve_once("200 OK", "text/event-stream", chunk("Hel") + &chunk("lo")).await;
+    let (sender, mut tokens) = tokio::sync::mpsc::unbounded_channel();
+    let mut node = GeminiRequestNode::new("prompt", "reply", Action::simple("next"))
+        .with_config(
+            GeminiConfig::new("key")
+                .with_base_url(base_url)
+                .with_stream(true),
+        )
+        .with_token_sender(sender);
+    let context = ExecutionContext::new(0, Duration::ZERO);
+    let mut store: SharedStore<InMemoryStorage> = SharedStore::new();
+    store.set("prompt".to_string(), json!("Say hello")).unwrap();
+
+    let prompt = node.prep(&store, &context).await.unwrap();
+    let reply = NodeBackend::<InMemoryStorage>::exec(&mut node, prompt, &context)
+        .await
+        .unwrap();
+    assert_eq!(reply, "Hello");
+    assert_eq!(tokens.recv().await.unwrap(), "Hel");
+    assert_eq!(tokens.recv().await.unwrap(), "lo");
+    assert!(
+        server
+            .await
+            .unwrap()
+            .contains(":streamGenerateContent?alt=sse")
+    );
+
+    // Invalid requests are not retried
+    let error = json!({"error": {"code": 400, "message": "API key not valid", "status": "INVALID_ARGUMENT"}});
+    let (base_url, _server) =
+        serve_once("400 Bad Request", "application/json", error.to_string()).await;
+    let mut node = GeminiRequestNode::new("prompt", "reply", Action::simple("next"))
+        .with_config(GeminiConfig::new("bad").with_base_url(base_url));
+    let prompt = node.prep(&store, &context).await.unwrap();
+    let error = NodeBackend::<InMemoryStorage>::exec(&mut node, prompt, &context)
+        .await
+        .unwrap_err();
+    assert!(!error.is_retryable());
+    assert!(error.to_string().contains("API key not valid"));
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_api_request_node_uses_tenant_config() {
+    use crate::tenant::{TenantContext, TenantQuota};
+    use crate::{Flow, FlowBuilder, StorageBackend};
+    use serde_json::json;
+
+    let body = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 0,
+        "model": "gpt-test",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi acme"}, "finish_reason": "stop"}]
+    });
+    let (base_url, server) = serve_once("200 OK", "application/json", body.to_string()).await;
+    let mut config = ApiConfig::new("acme-key");
+    config.base_url = Some(base_url);
+    let acme = TenantContext::new("acme")
+        .with_api_config(config)
+        .with_quota(TenantQuota::new().with_request_budget(1));
+    let mut flow = FlowBuilder::new()
+        .start_node("ask")
+        .tenant(acme.clone())
+        .node(
+            "ask",
+            Node::new(
+                ApiRequestNode::new("prompt", "reply", Action::simple("end"))
+                    .with_config(ApiConfig::new("node-key"))
+                    .with_retries(0),
+            ),
+        )
+        .build();
+    let mut store = SharedStore::with_storage(acme.scope(InMemoryStorage::new()));
+    store.set("prompt".to_string(), json!("Hello")).unwrap();
+    flow.execute(&mut store).await.unwrap();
+
+    assert_eq!(store.get("reply").unwrap(), Some(json!("Hi acme")));
+    assert!(
+        store
+            .storage()
+            .inner()
+            .contains_key("tenant:acme:reply")
+            .unwrap()
+    );
+    let request = server.await.unwrap().to_ascii_lowercase();
+    assert!(request.contains("authorization: bearer acme-key"));
+
+    // The request budget is spent, so the next run never reaches the API
+    flow.execute(&mut store).await.unwrap();
+    assert!(
+        store
+            .get("reply")
+            .unwrap()
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .contains("exceeded its request budget")
+    );
+    assert_eq!(acme.requests_made(), 1);
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_api_request_node_with_providers() {
+    use crate::llm::{ClaudeProvider, LlmRouter, OllamaProvider};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    // Claude answers through a router after an unreachable provider fails
+    let body = json!({"content": [{"type": "text", "text": "Bonjour"}], "model": "claude-test"});
+    let (base_url, server) = serve_once("200 OK", "application/json", body.to_string()).await;
+    let router = LlmRouter::new()
+        .with_provider(OllamaProvider::new().with_base_url("http://127.0.0.1:9"))
+        .with_provider(ClaudeProvider::new("ant-key").with_base_url(base_url));
+    let mut node = Node::new(
+        ApiRequestNode::new("prompt", "reply", Action::simple("next"))
+            .with_provider(Arc::new(router))
+            .with_system_message("Answer in French"),
+    );
+    let mut store = SharedStore::new();
+    store.set("prompt".to_string(), json!("Hello")).unwrap();
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("reply").unwrap(), Some(json!("Bonjour")));
+
+    let request = server.await.unwrap();
+    assert!(request.starts_with("POST /messages "));
+    assert!(request.to_ascii_lowercase().contains("x-api-key: ant-key"));
+    let sent: serde_json::Value =
+        serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+    assert_eq!(sent["system"], "Answer in French");
+    assert_eq!(
+        sent["messages"],
+        json!([{"role": "user", "content": "Hello"}])
+    );
+
+    // Ollama streams newline-delimited JSON
+    let chunks = [
+        json!({"message": {"role": "assistant", "content": "Hi"}, "done": false}),
+        json!({"message": {"role": "assistant", "content": " there"}, "done": false}),
+        json!({"message": {"role": "assistant", "content": ""}, "done": true}),
+    ]
+    .map(|chunk| chunk.to_string() + "\n")
+    .concat();
+    let (base_url, server) = serve_once("200 OK", "application/x-ndjson", chunks).await;
+    let (sender, mut tokens) = tokio::sync::mpsc::unbounded_channel();
+    let mut node = Node::new(
+        ApiRequestNode::new("prompt", "reply", Action::simple("next"))
+            .with_config(ApiConfig::default().with_stream(true))
+            .with_provider(Arc::new(OllamaProvider::new().with_base_url(base_url)))
+            .with_token_sender(sender),
+    );
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("reply").unwrap(), Some(json!("Hi there")));
+    assert_eq!(tokens.recv().await.unwrap(), "Hi");
+    assert_eq!(tokens.recv().await.unwrap(), " there");
+    assert!(server.await.unwrap().starts_with("POST /api/chat "));
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_api_request_node_response_metadata() {
+    use crate::llm::ClaudeProvider;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    let body = json!({
+        "content": [
+            {"type": "text", "text": "Partial"},
+            {"type": "tool_use", "id": "t1", "name": "search", "input": {"q": "rust"}}
+        ],
+        "model": "claude-test",
+        "stop_reason": "max_tokens",
+        "usage": {"input_tokens": 12, "output_tokens": 30}
+    });
+    let (base_url, server) = serve_once("200 OK", "application/json", body.to_string()).await;
+    let mut node = Node::new(
+        ApiRequestNode::new("prompt", "reply", Action::simple("next"))
+            .with_provider(Arc::new(
+                ClaudeProvider::new("ant-key").with_base_url(base_url),
+            ))
+            .with_response_key("reply_meta"),
+    );
+    let mut store = SharedStore::new();
+    store.set("prompt".to_string(), json!("Hello")).unwrap();
+    node.run(&mut store).await.unwrap();
+    server.await.unwrap();
+
+    assert_eq!(store.get("reply").unwrap(), Some(json!("Partial")));
+    let meta = store.get("reply_meta").unwrap().unwrap();
+    assert_eq!(meta["content"], "Partial");
+    assert_eq!(meta["provider"], "claude");
+    assert_eq!(meta["model"], "claude-test");
+    assert_eq!(meta["finish_reason"], "max_tokens");
+    assert_eq!(
+        meta["usage"],
+        json!({"requests": 1, "input_tokens": 12, "output_tokens": 30})
+    );
+    assert_eq!(meta["tool_calls"][0]["name"], "search");
+    assert!(meta["latency_ms"].is_u64());
+    assert_eq!(meta["raw"], body);
+}
+
+/// Provider replying with canned answers and recording each request
+#[cfg(feature = "builtin-llm")]
+struct ScriptedProvider {
+    replies: std::sync::Mutex<std::collections::VecDeque<String>>,
+    requests: std::sync::Mutex<Vec<crate::llm::ChatRequest>>,
+}
+
+#[cfg(feature = "builtin-llm")]
+impl ScriptedProvider {
+    fn new(replies: &[&str]) -> std::sync::Arc<Self> {
+        std::sync::Arc::new(Self {
+            replies: std::sync::Mutex::new(replies.iter().map(|r| r.to_string()).collect()),
+            requests: Default::default(),
+        })
+    }
+}
+
+#[cfg(feature = "builtin-llm")]
+#[async_trait::async_trait]
+impl LlmProvider for ScriptedProvider {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    async fn chat(
+        &self,
+        request: crate::llm::ChatRequest,
+    ) -> Result<crate::llm::ChatResponse, crate::llm::LlmError> {
+        self.requests.lock().unwrap().push(request);
+        let content = self
+            .replies
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| crate::llm::LlmError::Request("no more replies".to_string()))?;
+        Ok(crate::llm::ChatResponse::new(content, "scripted"))
+    }
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_api_request_node_json_mode() {
+    use crate::llm::ResponseFormat;
+    use serde_json::json;
+
+    let provider =
+        ScriptedProvider::new(&["Sure! Here it is", "```json\n{\"city\": \"Paris\"}\n```"]);
+    let mut node = Node::new(
+        ApiRequestNode::new("prompt", "place", Action::simple("next"))
+            .with_config(ApiConfig::default().with_json_mode().with_seed(7))
+            .with_provider(provider.clone())
+            .with_retries(1)
+            .with_retry_delay(Duration::ZERO),
+    );
+    let mut store = SharedStore::new();
+    store
+        .set("prompt".to_string(), json!("Where is the Louvre?"))
+        .unwrap();
+    node.run(&mut store).await.unwrap();
+
+    assert_eq!(store.get("place").unwrap(), Some(json!({"city": "Paris"})));
+    let requests = provider.requests.lock().unwrap();
+    assert_eq!(
+        requests[0].response_format,
+        Some(ResponseFormat::JsonObject)
+    );
+    assert_eq!(requests[0].seed, Some(7));
+    // The retry explains why the first reply was rejected
+    let retry = requests[1].messages.last().unwrap();
+    assert!(
+        retry
+            .content
+            .starts_with("Your previous reply was rejected")
+    );
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_api_request_node_outputs() {
+    use crate::node::Outputs;
+    use serde_json::json;
+
+    let provider = ScriptedProvider::new(&[
+        r#"{"title": "Rust"}"#,
+        r#"{"title": "Rust", "summary": "A language", "tags": ["systems"]}"#,
+    ]);
+    let node = ApiRequestNode::new("prompt", "extraction", Action::simple("next"))
+        .with_config(ApiConfig::default().with_json_mode())
+        .with_provider(provider.clone())
+        .with_outputs(
+            Outputs::new()
+                .field("title")
+                .field("summary")
+                .field_as("tags", "labels"),
+        )
+        .with_retries(1)
+        .with_retry_delay(Duration::ZERO);
+    let writes = <ApiRequestNode as NodeBackend<crate::InMemoryStorage>>::writes(&node).unwrap();
+    assert_eq!(writes.len(), 4);
+    let mut node = Node::new(node);
+    let mut store = SharedStore::new();
+    store
+        .set("prompt".to_string(), json!("Describe Rust"))
+        .unwrap();
+    node.run(&mut store).await.unwrap();
+
+    assert_eq!(store.get("title").unwrap(), Some(json!("Rust")));
+    assert_eq!(store.get("summary").unwrap(), Some(json!("A language")));
+    assert_eq!(store.get("labels").unwrap(), Some(json!(["systems"])));
+    assert_eq!(
+        store.get("extraction").unwrap().unwrap()["tags"],
+        json!(["systems"])
+    );
+    let requests = provider.requests.lock().unwrap();
+    assert!(
+        requests[1]
+            .messages
+            .last()
+            .unwrap()
+            .content
+            .contains("missing field 'summary'")
+    );
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_reasoning_node() {
+    use serde_json::json;
+
+    let provider = ScriptedProvider::new(&[
+        "Step 1: 17 * 3 = 51",
+        "Step 2: 51 + 4 = 55\nFINAL ANSWER: 55",
+    ]);
+    let mut node = Node::new(ReasoningNode::new(
+        provider.clone(),
+        "problem",
+        "answer",
+        Action::simple("solved"),
+    ));
+    let mut store = SharedStore::new();
+    store
+        .set("problem".to_string(), json!("What is 17 * 3 + 4?"))
+        .unwrap();
+
+    let action = node.run(&mut store).await.unwrap();
+    assert_eq!(action, Action::simple("solved"));
+    assert_eq!(store.get("answer").unwrap(), Some(json!("55")));
+    assert_eq!(
+        store.get("scratchpad").unwrap(),
+        Some(json!([
+            "Step 1: 17 * 3 = 51",
+            "Step 2: 51 + 4 = 55\nFINAL ANSWER: 55"
+        ]))
+    );
+    // The second step sees the first thought
+    assert_eq!(
+        provider.requests.lock().unwrap()[1].messages[2].content,
+        "Step 1: 17 * 3 = 51"
+    );
+
+    // Without a declared answer the step limit ends the run
+    let provider = ScriptedProvider::new(&["Still thinking", "Almost there"]);
+    let mut node = Node::new(
+        ReasoningNode::new(provider, "problem", "answer", Action::simple("solved"))
+            .with_scratchpad_key("notes")
+            .with_max_steps(1)
+            .with_exhausted_action(Action::simple("think_more")),
+    );
+    let action = node.run(&mut store).await.unwrap();
+    assert_eq!(action, Action::simple("think_more"));
+    assert_eq!(store.get("answer").unwrap(), Some(json!("Still thinking")));
+
+    // Running again continues the scratchpad
+    node.run(&mut store).await.unwrap();
+    assert_eq!(
+        store.get("notes").unwrap(),
+        Some(json!(["Still thinking", "Almost there"]))
+    );
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_ensemble_llm_node() {
+    use serde_json::json;
+
+    // Majority vote ignores case and punctuation; failed samples are dropped
+    let provider = ScriptedProvider::new(&["Positive", "negative", "positive."]);
+    let mut node = Node::new(
+        EnsembleLlmNode::new(provider.clone(), "prompt", "label", Action::simple("next"))
+            .with_samples(4)
+            .with_temperatures(vec![0.2, 0.9])
+            .with_samples_key("samples"),
+    );
+    let mut store = SharedStore::new();
+    store
+        .set("prompt".to_string(), json!("Sentiment of: great film"))
+        .unwrap();
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("label").unwrap(), Some(json!("Positive")));
+    assert_eq!(
+        store.get("samples").unwrap(),
+        Some(json!(["Positive", "negative", "positive."]))
+    );
+    let temperatures: Vec<_> = provider
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|r| r.temperature)
+        .collect();
+    assert_eq!(temperatures, [Some(0.2), Some(0.9), Some(0.2), Some(0.9)]);
+
+    // An LLM judge picks by number
+    let judge = ScriptedProvider::new(&["Candidate 2 is best"]);
+    let mut node = Node::new(
+        EnsembleLlmNode::new(
+            ScriptedProvider::new(&["4", "5", "4"]),
+            "prompt",
+            "answer",
+            Action::simple("next"),
+        )
+        .with_samples(3)
+        .with_judge_provider(judge.clone()),
+    );
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("answer").unwrap(), Some(json!("5")));
+    assert!(
+        judge.requests.lock().unwrap()[0].messages[1]
+            .content
+            .contains("[3] 4")
+    );
+
+    // A closure can aggregate
+    let mut node = Node::new(
+        EnsembleLlmNode::new(
+            ScriptedProvider::new(&["short", "the longest", "longer"]),
+            "prompt",
+            "answer",
+            Action::simple("next"),
+        )
+        .with_samples(3)
+        .with_aggregator(|samples| samples.iter().max_by_key(|s| s.len()).cloned()),
+    );
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("answer").unwrap(), Some(json!("the longest")));
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_cascade_llm_node() {
+    use serde_json::json;
+
+    // A low score escalates to the stronger model
+    let cheap = ScriptedProvider::new(&["Not sure"]);
+    let strong = ScriptedProvider::new(&["Paris"]);
+    let mut node = Node::new(
+        CascadeLlmNode::new(cheap.clone(), "prompt", "answer", Action::simple("next"))
+            .with_model("mini")
+            .with_escalation_model(strong.clone(), "large")
+            .with_validator(|answer| if answer.contains("sure") { 0.2 } else { 1.0 }),
+    );
+    let mut store = SharedStore::new();
+    store
+        .set("prompt".to_string(), json!("Capital of France?"))
+        .unwrap();
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("answer").unwrap(), Some(json!("Paris")));
+    assert_eq!(
+        store.get("cascade_tier").unwrap(),
+        Some(json!({"tier": 1, "provider": "scripted", "model": "large", "scores": [0.2]}))
+    );
+    assert_eq!(
+        cheap.requests.lock().unwrap()[0].model.as_deref(),
+        Some("mini")
+    );
+
+    // A good grade from the judge keeps the cheap answer
+    let cheap = ScriptedProvider::new(&["Paris"]);
+    let strong = ScriptedProvider::new(&[]);
+    let judge = ScriptedProvider::new(&["8/10"]);
+    let mut node = Node::new(
+        CascadeLlmNode::new(cheap, "prompt", "answer", Action::simple("next"))
+            .with_escalation(strong.clone())
+            .with_judge_provider(judge.clone()),
+    );
+    node.run(&mut store).await.unwrap();
+    assert_eq!(
+        store.get("cascade_tier").unwrap().unwrap()["scores"],
+        json!([0.8])
+    );
+    assert!(strong.requests.lock().unwrap().is_empty());
+    assert!(
+        judge.requests.lock().unwrap()[0].messages[1]
+            .content
+            .contains("Capital of France?")
+    );
+
+    // A failing tier is escalated past
+    let mut node = Node::new(
+        CascadeLlmNode::new(
+            ScriptedProvider::new(&[]),
+            "prompt",
+            "answer",
+            Action::simple("next"),
+        )
+        .with_escalation(ScriptedProvider::new(&["Lyon"]))
+        .with_retries(0),
+    );
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("answer").unwrap(), Some(json!("Lyon")));
+    assert_eq!(
+        store.get("cascade_tier").unwrap().unwrap()["scores"],
+        json!([null])
+    );
+}
+
+#[cfg(feature = "builtin-llm")]
+#[tokio::test]
+async fn test_tree_search_node() {
+    use serde_json::json;
+
+    // Two candidates per level, keeping the best one
+    let provider = ScriptedProvider::new(&[
+        "Try the sum",
+        "Try the product",
+        "The product is 12",
+        "FINAL ANSWER: 12",
+        "never asked",
+    ]);
+    let mut node = Node::new(
+        TreeSearchNode::new(
+            provider.clone(),
+            "problem",
+            "answer",
+            Action::simple("next"),
+        )
+        .with_branching(2)
+        .with_beam_width(1)
+        .with_max_depth(3)
+        .with_path_key("path")
+        .with_heuristic(|steps| {
+            let last = steps.last().unwrap();
+            if last.contains("FINAL") {
+                1.0
+            } else if last.contains("product") {
+                0.6
+            } else {
+                0.1
+            }
+        }),
+    );
+    let mut store = SharedStore::new();
+    store
+        .set("problem".to_string(), json!("3 and 4 multiplied?"))
+        .unwrap();
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("answer").unwrap(), Some(json!("12")));
+    assert_eq!(
+        store.get("path").unwrap(),
+        Some(json!({"steps": ["Try the product", "FINAL ANSWER: 12"], "score": 1.0}))
+    );
+    // The second level continued the kept path, and the finished beam stopped
+    // the search
+    let requests = provider.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 4);
+    assert_eq!(requests[2].messages[2].content, "Try the product");
+
+    // An LLM judge scores by default
+    let provider = ScriptedProvider::new(&["FINAL ANSWER: 7", "FINAL ANSWER: 12", "2", "9"]);
+    let mut node = Node::new(
+        TreeSearchNode::new(provider, "problem", "answer", Action::simple("next"))
+            .with_branching(2),
+    );
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("answer").unwrap(), Some(json!("12")));
+}
+
+#[cfg(feature = "prompt-library")]
+#[tokio::test]
+async fn test_prompt_template_node() {
+    use crate::llm::Prompt;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("greet.prompt"),
+        "---\nmodel: tiny\nvariables: [name]\n---\nSay hello to {name}.",
+    )
+    .unwrap();
+    let library = Arc::new(
+        PromptLibrary::load_dir(dir.path())
+            .unwrap()
+            .with_prompt(Prompt::new("static", "No variables"))
+            .with_hot_reload(true),
+    );
+
+    // Without a provider the rendered prompt is stored
+    let mut render = Node::new(
+        PromptTemplateNode::new(library.clone(), "greet", "prompt", Action::simple("next"))
+            .with_variable("name", "user"),
+    );
+    let mut store = SharedStore::new();
+    store.set("user".to_string(), json!("Ada")).unwrap();
+    render.run(&mut store).await.unwrap();
+    assert_eq!(
+        store.get("prompt").unwrap(),
+        Some(json!("Say hello to Ada."))
+    );
+
+    // Edited prompt files apply to the next run
+    std::fs::write(
+        dir.path().join("greet.prompt"),
+        "---\nmodel: tiny\nvariables: [name]\n---\nPlease greet {name} warmly.",
+    )
+    .unwrap();
+    let provider = ScriptedProvider::new(&["Hello, Ada!"]);
+    let mut call = Node::new(
+        PromptTemplateNode::new(library.clone(), "greet", "reply", Action::simple("next"))
+            .with_variable("name", "user")
+            .with_provider(provider.clone()),
+    );
+    call.run(&mut store).await.unwrap();
+    assert_eq!(store.get("reply").unwrap(), Some(json!("Hello, Ada!")));
+    let request = provider.requests.lock().unwrap()[0].clone();
+    assert_eq!(request.model.as_deref(), Some("tiny"));
+    assert_eq!(request.messages[0].content, "Please greet Ada warmly.");
+
+    // Missing variables and unknown prompts fail the node
+    let mut missing = Node::new(PromptTemplateNode::new(
+        library.clone(),
+        "greet",
+        "prompt",
+        Action::simple("next"),
+    ));
+    assert!(missing.run(&mut store).await.is_err());
+    let mut unknown = Node::new(PromptTemplateNode::new(
+        library,
+        "nope",
+        "prompt",
+        Action::simple("next"),
+    ));
+    assert!(unknown.run(&mut store).await.is_err());
+}
+
+#[cfg(feature = "notify")]
+#[tokio::test]
+async fn test_notification_nodes() {
+    use crate::node::builtin::{SendEmailNode, SlackWebhookNode, WebhookNotifyNode};
+    use crate::secrets::Secret;
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    store
+        .set(
+            "order".to_string(),
+            json!({"id": 42, "email": "buyer@example.com"}),
+        )
+        .unwrap();
+
+    let (url, request) = serve_once("200 OK", "text/plain", "ok".to_string()).await;
+    let mut webhook = Node::new(WebhookNotifyNode::new(
+        format!("{}/hooks", url),
+        json!({"event": "shipped", "text": "Order {order.id} shipped", "ids": ["{order.id}"]}),
+    ));
+    let context =
+        ExecutionContext::new(0, Duration::ZERO).with_correlation_id(Some("req-42".to_string()));
+    assert_eq!(
+        webhook.run_with_context(&mut store, context).await.unwrap(),
+        Action::simple("sent")
+    );
+    let request = request.await.unwrap();
+    assert!(request.starts_with("POST /hooks"));
+    assert!(
+        request
+            .to_ascii_lowercase()
+            .contains("x-request-id: req-42")
+    );
+    assert!(request.contains(r#""text":"Order 42 shipped""#));
+    assert!(request.contains(r#""ids":["42"]"#));
+
+    // A rejected payload is not retried and routes to the failure action
+    let (url, request) = serve_once(
+        "400 Bad Request",
+        "text/plain",
+        "invalid_payload".to_string(),
+    )
+    .await;
+    let mut slack = Node::new(
+        SlackWebhookNode::new(Secret::new(url), "Order {order.id} failed")
+            .with_retries(3)
+            .with_failed_action("alert_failed")
+            .with_error_key("notify_error"),
+    );
+    assert_eq!(
+        slack.run(&mut store).await.unwrap(),
+        Action::simple("alert_failed")
+    );
+    assert!(
+        request
+            .await
+            .unwrap()
+            .contains(r#""text":"Order 42 failed""#)
+    );
+    assert_eq!(slack.last_run_stats().unwrap().attempts, 1);
+    let error = store.get("notify_error").unwrap().unwrap();
+    assert!(error.as_str().unwrap().contains("invalid_payload"));
+
+    // An invalid sender fails before any connection is made
+    let mut email = Node::new(
+        SendEmailNode::new("localhost", "not an address", "Order {order.id}", "Thanks!")
+            .to("{order.email}"),
+    );
+    assert_eq!(
+        email.run(&mut store).await.unwrap(),
+        Action::simple("failed")
+    );
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_render_report_node() {
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    store.set("title".to_string(), json!("Q3 <sales>")).unwrap();
+    store
+        .set(
+            "rows".to_string(),
+            json!([{"region": "EU", "total": 12}, {"region": "US|CA", "total": 30}]),
+        )
+        .unwrap();
+    store
+        .set("share".to_string(), json!({"EU": 12, "US": 30}))
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out/report.md");
+    let template = "# {title}\n\n{table:rows}\n{chart:share}\n{missing}";
+    let mut node =
+        Node::new(RenderReportNode::new(template, Action::simple("done")).with_output_file(&path));
+    assert_eq!(node.run(&mut store).await.unwrap(), Action::simple("done"));
+
+    let report = store.get("report").unwrap().unwrap();
+    let report = report.as_str().unwrap();
+    assert!(report.starts_with("# Q3 <sales>\n"));
+    assert!(report.contains("| region | total |\n| --- | --- |\n| EU | 12 |\n| US\\|CA | 30 |\n"));
+    assert!(report.contains("```mermaid\npie title share\n    \"EU\" : 12\n"));
+    assert!(report.ends_with("{missing}"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), report);
+
+    let html = RenderReportNode::render("<h1>{title}</h1>{table:rows}", ReportFormat::Html, &store)
+        .unwrap();
+    assert!(html.starts_with("<h1>Q3 &lt;sales&gt;</h1><table>"));
+    assert!(html.contains("<tr><td>US|CA</td><td>30</td></tr>"));
+
+    // Charts need numeric values
+    store
+        .set("share".to_string(), json!({"EU": "n/a"}))
+        .unwrap();
+    let mut chart = Node::new(RenderReportNode::new(
+        "{chart:share}",
+        Action::simple("done"),
+    ));
+    assert!(chart.run(&mut store).await.is_err());
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_sample_node() {
+    use serde_json::{Value, json};
+
+    // 60 support tickets, 30 billing and 10 sales
+    let tickets: Vec<Value> = (0..100)
+        .map(|id| {
+            let queue = match id % 10 {
+                0..=5 => "support",
+                6..=8 => "billing",
+                _ => "sales",
+            };
+            json!({"id": id, "queue": queue})
+        })
+        .collect();
+    let mut store = SharedStore::new();
+    store.set("tickets".to_string(), json!(tickets)).unwrap();
+
+    let sample = || SampleNode::new("tickets", "spot_check", 10, Action::simple("review"));
+    let mut node = Node::new(sample().stratify_by("queue").with_seed(7));
+    node.run(&mut store).await.unwrap();
+    let first = store.get("spot_check").unwrap().unwrap();
+    let items = first.as_array().unwrap();
+    let count = |queue: &str| items.iter().filter(|item| item["queue"] == queue).count();
+    assert_eq!(
+        (count("support"), count("billing"), count("sales")),
+        (6, 3, 1)
+    );
+    let ids: Vec<u64> = items
+        .iter()
+        .map(|item| item["id"].as_u64().unwrap())
+        .collect();
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // The same seed draws the same sample
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("spot_check").unwrap().unwrap(), first);
+
+    let mut node = Node::new(SampleNode::new(
+        "tickets",
+        "all",
+        500,
+        Action::simple("review"),
+    ));
+    node.run(&mut store).await.unwrap();
+    assert_eq!(
+        store.get("all").unwrap().unwrap().as_array().unwrap().len(),
+        100
+    );
+}
+
+#[cfg(feature = "regex-nodes")]
+#[tokio::test]
+async fn test_regex_nodes() {
+    use regex::Regex;
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    store
+        .set(
+            "reply".to_string(),
+            json!("Order #1042 shipped to ada@example.com, order #1043 pending"),
+        )
+        .unwrap();
+
+    let order =
+        Regex::new(r"#(?P<order_id>\d+) (?P<status>shipped|pending)(?P<to> to \S+)?").unwrap();
+    let mut node = Node::new(RegexExtractNode::new(
+        "reply",
+        order.clone(),
+        Action::simple("found"),
+    ));
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "found");
+    assert_eq!(store.get("order_id").unwrap(), Some(json!("1042")));
+    assert_eq!(store.get("status").unwrap(), Some(json!("shipped")));
+
+    let mut node = Node::new(
+        RegexExtractNode::new("reply", order, Action::simple("found")).find_all("orders"),
+    );
+    node.run(&mut store).await.unwrap();
+    let orders = store.get("orders").unwrap().unwrap();
+    assert_eq!(
+        orders[1],
+        json!({"order_id": "1043", "status": "pending", "to": null})
+    );
+
+    let mut node = Node::new(
+        RegexExtractNode::new(
+            "reply",
+            Regex::new(r"\d{4}").unwrap(),
+            Action::simple("found"),
+        )
+        .find_all("numbers"),
+    );
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("numbers").unwrap(), Some(json!(["1042", "1043"])));
+
+    let refund = Regex::new(r"refund (?P<amount>\d+)").unwrap();
+    let mut node = Node::new(RegexExtractNode::new(
+        "reply",
+        refund.clone(),
+        Action::simple("found"),
+    ));
+    assert!(node.run(&mut store).await.is_err());
+    let mut node = Node::new(
+        RegexExtractNode::new("reply", refund, Action::simple("found"))
+            .with_no_match_action(Action::simple("none")),
+    );
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "none");
+
+    let email = Regex::new(r"[\w.]+@[\w.]+").unwrap();
+    let mut contains = Node::new(RegexValidateNode::new("reply", email.clone()));
+    assert_eq!(contains.run(&mut store).await.unwrap().name(), "valid");
+    let mut exact = Node::new(RegexValidateNode::new("reply", email.clone()).full_match());
+    assert_eq!(exact.run(&mut store).await.unwrap().name(), "invalid");
+    store
+        .set("email".to_string(), json!("ada@example.com"))
+        .unwrap();
+    let mut exact = Node::new(RegexValidateNode::new("email", email.clone()).full_match());
+    assert_eq!(exact.run(&mut store).await.unwrap().name(), "valid");
+    let mut missing = Node::new(RegexValidateNode::new("phone", email));
+    assert_eq!(missing.run(&mut store).await.unwrap().name(), "invalid");
+}
+
+#[cfg(feature = "normalize-text")]
+#[tokio::test]
+async fn test_normalize_text_node() {
+    use serde_json::json;
+
+    let markdown = "# Setup\n\nInstall   the **CLI** from [the site](https://example.com):\n\n```sh\ncargo install  pf\n  pf --help\n```\n\n- one\n- two &amp; three\n";
+    let html = "<html><head><title>Doc</title><style>p { color: red }</style></head>\n<body><h2>Intro</h2><p>Fish &amp; chips,<br>\n  &quot;fresh&quot;</p><!-- nav --><script>track()</script><pre>a  b\n c</pre><table><tr><td>x</td><td>1</td></tr></table></body></html>";
+    let mut store = SharedStore::new();
+    store.set("doc".to_string(), json!(markdown)).unwrap();
+    store.set("page".to_string(), json!(html)).unwrap();
+
+    let mut node = Node::new(NormalizeTextNode::new(
+        "doc",
+        "doc_text",
+        TextFormat::Markdown,
+        Action::simple("chunk"),
+    ));
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "chunk");
+    assert_eq!(
+        store.get("doc_text").unwrap().unwrap(),
+        json!(
+            "Setup\n\nInstall the CLI from the site:\n\ncargo install  pf\n  pf --help\n\none\n\ntwo & three"
+        )
+    );
+
+    let mut node = Node::new(
+        NormalizeTextNode::new(
+            "page",
+            "page_text",
+            TextFormat::Html,
+            Action::simple("chunk"),
+        )
+        .with_headings(false)
+        .with_code_blocks(false),
+    );
+    node.run(&mut store).await.unwrap();
+    assert_eq!(
+        store.get("page_text").unwrap().unwrap(),
+        json!("Fish & chips, \"fresh\"\n\nx 1")
+    );
+
+    let preserved =
+        NormalizeTextNode::new("page", "out", TextFormat::Html, Action::simple("chunk"))
+            .with_collapse_whitespace(false);
+    assert_eq!(
+        preserved.normalize(html),
+        "Intro\n\nFish & chips,\n\n  \"fresh\"\n\na  b\n c\n\nx 1"
+    );
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_dedupe_node() {
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    store
+        .set(
+            "chunks".to_string(),
+            json!([
+                {"id": 1, "text": "Reset your password"},
+                {"id": 2, "text": "Billing  FAQ"},
+                {"id": 3, "text": "reset your password "},
+                {"id": 4, "text": "How do I reset my password?"},
+                {"id": 5, "text": "Billing FAQ"}
+            ]),
+        )
+        .unwrap();
+    store
+        .set("tags".to_string(), json!(["a", "b", "a", 1, 1]))
+        .unwrap();
+
+    let mut node = Node::new(DedupeNode::new("tags", "tags", Action::simple("index")));
+    node.run(&mut store).await.unwrap();
+    assert_eq!(store.get("tags").unwrap(), Some(json!(["a", "b", 1])));
+
+    let mut node = Node::new(
+        DedupeNode::new("chunks", "unique", Action::simple("index"))
+            .by_field("text")
+            .normalized()
+            .with_duplicates_key("dropped"),
+    );
+    assert_eq!(node.run(&mut store).await.unwrap().name(), "index");
+    let ids = |store: &SharedStore<_>, key: &str| -> Vec<u64> {
+        let items = store.get(key).unwrap().unwrap();
+        items
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_u64().unwrap())
+            .collect()
+    };
+    assert_eq!(ids(&store, "unique"), vec![1, 2, 4]);
+    assert_eq!(ids(&store, "dropped"), vec![3, 5]);
+
+    #[cfg(feature = "builtin-llm")]
+    {
+        /// Embeds texts mentioning passwords close together
+        struct TopicEmbedder;
+
+        #[async_trait::async_trait]
+        impl LlmProvider for TopicEmbedder {
+            fn name(&self) -> &str {
+                "topics"
+            }
+
+            async fn chat(
+                &self,
+                _request: crate::llm::ChatRequest,
+            ) -> Result<crate::llm::ChatResponse, crate::llm::LlmError> {
+                unimplemented!()
+            }
+
+            async fn embeddings(
+                &self,
+                request: crate::llm::EmbeddingRequest,
+            ) -> Result<Vec<Vec<f32>>, crate::llm::LlmError> {
+                Ok(request
+                    .inputs
+                    .iter()
+                    .map(|text| match (text.contains("password"), text.len() % 2) {
+                        (true, parity) => vec![1.0, 0.1 * parity as f32],
+                        (false, _) => vec![0.0, 1.0],
+                    })
+                    .collect())
+            }
+        }
+
+        let mut node = Node::new(
+            DedupeNode::new("chunks", "unique", Action::simple("index"))
+                .by_field("text")
+                .with_embeddings(std::sync::Arc::new(TopicEmbedder), 0.95),
+        );
+        node.run(&mut store).await.unwrap();
+        assert_eq!(ids(&store, "unique"), vec![1, 2]);
+    }
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_task_queue_nodes() {
+    use crate::shared_store::{TaskQueue, TaskStatus};
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    store
+        .set(
+            "steps".to_string(),
+            json!([
+                {"step": "draft", "priority": 1},
+                {"step": "research", "priority": 2},
+                {"step": "review"}
+            ]),
+        )
+        .unwrap();
+    let mut enqueue = Node::new(
+        EnqueueTaskNode::new("plan", "steps", Action::simple("execute"))
+            .each()
+            .with_priority_field("priority"),
+    );
+    assert_eq!(enqueue.run(&mut store).await.unwrap().name(), "execute");
+
+    let mut dequeue = Node::new(DequeueTaskNode::new("plan", "current").complete_previous());
+    let mut order = Vec::new();
+    while dequeue.run(&mut store).await.unwrap().name() == "task" {
+        let current = store.get("current").unwrap().unwrap();
+        assert_eq!(current["status"], "in_progress");
+        order.push(current["payload"]["step"].clone());
+    }
+    assert_eq!(
+        order,
+        vec![json!("research"), json!("draft"), json!("review")]
+    );
+    assert!(!store.contains_key("current").unwrap());
+
+    let queue = TaskQueue::new("plan");
+    assert_eq!(queue.remaining(&store).unwrap(), 0);
+    assert!(
+        queue
+            .tasks(&store)
+            .unwrap()
+            .iter()
+            .all(|task| task.status == TaskStatus::Done)
+    );
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_graph_memory_nodes() {
+    use crate::shared_store::Direction;
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    store
+        .set(
+            "facts".to_string(),
+            json!([
+                {"subject": "ada", "relation": "works_at", "object": "acme"},
+                {"subject": "acme", "relation": "located_in", "object": "berlin"},
+                {"subject": "ada", "properties": {"role": "engineer"}}
+            ]),
+        )
+        .unwrap();
+    let mut upsert = Node::new(UpsertFactNode::new(
+        "memory",
+        "facts",
+        Action::simple("recall"),
+    ));
+    assert_eq!(upsert.run(&mut store).await.unwrap().name(), "recall");
+
+    store.set("who".to_string(), json!("ada")).unwrap();
+    let mut query = Node::new(
+        QueryGraphNode::new("memory", "who", "context", Action::simple("answer"))
+            .with_direction(Direction::Outgoing)
+            .with_depth(2)
+            .with_not_found_action(Action::simple("unknown")),
+    );
+    assert_eq!(query.run(&mut store).await.unwrap().name(), "answer");
+    let context = store.get("context").unwrap().unwrap();
+    assert_eq!(
+        context["nodes"][0],
+        json!({"id": "ada", "properties": {"role": "engineer"}})
+    );
+    assert_eq!(context["nodes"][2]["id"], "berlin");
+    assert_eq!(context["edges"].as_array().unwrap().len(), 2);
+
+    store.set("who".to_string(), json!("bob")).unwrap();
+    assert_eq!(query.run(&mut store).await.unwrap().name(), "unknown");
+
+    // Nothing is written unless every fact is valid
+    store
+        .set(
+            "facts".to_string(),
+            json!([{"subject": "bob"}, {"subject": "bob", "relation": "knows"}]),
+        )
+        .unwrap();
+    assert!(upsert.run(&mut store).await.is_err());
+    assert_eq!(query.run(&mut store).await.unwrap().name(), "unknown");
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_transform_records_node() {
+    use crate::node::builtin::Aggregate;
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    store
+        .set(
+            "orders".to_string(),
+            json!([
+                {"id": 1, "customer": "a", "amount": 10, "status": "paid"},
+                {"id": 2, "customer": "b", "amount": 25, "status": "paid"},
+                {"id": 3, "customer": "a", "amount": 5, "status": "paid"},
+                {"id": 4, "customer": "c", "amount": 99, "status": "refunded"},
+                {"id": 5, "customer": "z", "amount": 7, "status": "paid"}
+            ]),
+        )
+        .unwrap();
+    store
+        .set(
+            "customers".to_string(),
+            json!([
+                {"customer": "a", "country": "DE"},
+                {"customer": "b", "country": "FR"},
+                {"customer": "c", "country": "DE"}
+            ]),
+        )
+        .unwrap();
+
+    let mut node = Node::new(
+        TransformRecordsNode::new("orders", "revenue", Action::simple("done"))
+            .filter("status", ComparisonOperator::Equal, json!("paid"))
+            .join("customers", "customer")
+            .group_by(
+                ["country"],
+                [
+                    Aggregate::sum("total", "amount"),
+                    Aggregate::count("orders"),
+                    Aggregate::collect("ids", "id"),
+                ],
+            )
+            .sort_by("total", true),
+    );
+    assert_eq!(node.run(&mut store).await.unwrap(), Action::simple("done"));
+    assert_eq!(
+        store.get("revenue").unwrap(),
+        Some(json!([
+            {"country": "FR", "total": 25, "orders": 1, "ids": [2]},
+            {"country": "DE", "total": 15, "orders": 2, "ids": [1, 3]}
+        ]))
+    );
+
+    // Left joins keep unmatched records; field ops reshape them
+    let mut node = Node::new(
+        TransformRecordsNode::new("orders", "shaped", Action::simple("done"))
+            .filter("amount", ComparisonOperator::LessThan, json!(10))
+            .left_join("customers", "customer")
+            .select(["id", "country"])
+            .rename("country", "region")
+            .set("checked", json!(true))
+            .limit(2),
+    );
+    node.run(&mut store).await.unwrap();
+    assert_eq!(
+        store.get("shaped").unwrap(),
+        Some(json!([
+            {"id": 3, "region": "DE", "checked": true},
+            {"id": 5, "checked": true}
+        ]))
+    );
+
+    // Non-array input is rejected
+    store.set("orders".to_string(), json!({"id": 1})).unwrap();
+    assert!(node.run(&mut store).await.is_err());
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_query_node() {
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    store
+        .set(
+            "orders".to_string(),
+            json!([{"id": 1, "total": 80}, {"id": 2, "total": 120}, {"id": 3, "total": 250}]),
+        )
+        .unwrap();
+
+    let mut node = Node::new(
+        QueryNode::new(
+            "$.orders[?(@.total > 100)].id",
+            "big_orders",
+            Action::simple("next"),
+        )
+        .unwrap()
+        .collect_all(),
+    );
+    assert_eq!(node.run(&mut store).await.unwrap(), Action::simple("next"));
+    assert_eq!(store.get("big_orders").unwrap(), Some(json!([2, 3])));
+
+    let mut first = Node::new(
+        QueryNode::new("$.orders[-1].total", "last_total", Action::simple("next")).unwrap(),
+    );
+    first.run(&mut store).await.unwrap();
+    assert_eq!(store.get("last_total").unwrap(), Some(json!(250)));
+
+    let mut missing =
+        Node::new(QueryNode::new("$.orders[9].id", "id", Action::simple("next")).unwrap());
+    assert!(missing.run(&mut store).await.is_err());
+    let mut defaulted = Node::new(
+        QueryNode::new("$.orders[9].id", "id", Action::simple("next"))
+            .unwrap()
+            .with_default(json!(null)),
+    );
+    defaulted.run(&mut store).await.unwrap();
+    assert_eq!(store.get("id").unwrap(), Some(json!(null)));
+
+    assert!(QueryNode::new("orders", "id", Action::simple("next")).is_err());
+}
+
+#[cfg(feature = "builtin-nodes")]
+#[tokio::test]
+async fn test_json_diff_and_patch_nodes() {
+    use crate::node::builtin::patch::{PatchOperation, apply_patch};
+    use serde_json::json;
+
+    let mut store = SharedStore::new();
+    let original = json!({"settings": {"theme": "dark", "tags": ["a", "b", "c"]}, "owner": "ann"});
+    let edited = json!({"settings": {"theme": "light", "tags": ["a", "x"], "font/size": 12}, "owner": "ann"});
+    store.set("config".to_string(), original.clone()).unwrap();
+    store.set("draft".to_string(), edited.clone()).unwrap();
+
+    let mut differ = Node::new(
+        JsonDiffNode::new("config", "draft", "changes", Action::simple("review"))
+            .with_unchanged_action("skip"),
+    );
+    assert_eq!(
+        differ.run(&mut store).await.unwrap(),
+        Action::simple("review")
+    );
+    let changes = store.get("changes").unwrap().unwrap();
+    assert!(
+        changes
+            .as_array()
+            .unwrap()
+            .contains(&json!({"op": "add", "path": "/settings/font~1size", "value": 12}))
+    );
+
+    // The computed patch reproduces the edit and passes a path policy
+    let mut patcher = Node::new(
+        JsonPatchNode::new("config", "changes", Action::simple("applied")).allow_path("/settings"),
+    );
+    assert_eq!(
+        patcher.run(&mut store).await.unwrap(),
+        Action::simple("applied")
+    );
+    assert_eq!(store.get("config").unwrap(), Some(edited.clone()));
+    assert_eq!(
+        differ.run(&mut store).await.unwrap(),
+        Action::simple("skip")
+    );
+
+    // Policy violations and failed tests leave the document untouched
+    store
+        .set(
+            "changes".to_string(),
+            json!([{"op": "replace", "path": "/settings/theme", "value": "blue"},
+                   {"op": "remove", "path": "/owner"}]),
+        )
+        .unwrap();
+    let mut guarded = Node::new(
+        JsonPatchNode::new("config", "changes", Action::simple("applied"))
+            .allow_path("/settings")
+            .with_rejected_action("rejected")
+            .with_error_key("patch_error"),
+    );
+    assert_eq!(
+        guarded.run(&mut store).await.unwrap(),
+        Action::simple("rejected")
+    );
+    assert_eq!(store.get("config").unwrap(), Some(edited.clone()));
+    assert!(
+        store
+            .get("patch_error")
+            .unwrap()
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .contains("/owner")
+    );
+
+    let mut document = edited.clone();
+    let patch = vec![
+        PatchOperation::Move {
+            from: "/owner".into(),
+            path: "/settings/owner".into(),
+        },
+        PatchOperation::Test {
+            path: "/settings/theme".into(),
+            value: json!("dark"),
+        },
+    ];
+    assert!(apply_patch(&mut document, &patch).is_err());
+    assert_eq!(document, edited);
+}
@@ -0,0 +1,79 @@
+//! Variables scoped to one flow run
+//!
+//! [`FlowVars`] hold transient values such as loop counters and cursors. Every
+//! node of a run sees the same variables through [`ExecutionContext::vars`],
+//! from any phase, but they are never written to the shared store, so they
+//! don't end up in checkpoints, journals or persisted storage. A new run (or a
+//! resumed one) starts with none.
+//!
+//! ```rust
+//! # use pocketflow_rs::node::FlowVars;
+//! # use serde_json::json;
+//! let vars = FlowVars::new();
+//! vars.set("cursor", json!(10));
+//! assert_eq!(vars.update("cursor", |cursor| json!(cursor.as_i64().unwrap_or(0) + 5)), json!(15));
+//! assert_eq!(vars.get("cursor"), Some(json!(15)));
+//! ```
+//!
+//! [`ExecutionContext::vars`]: super::ExecutionContext::vars
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Variables shared by the nodes of one flow run
+///
+/// Clones share the same variables.
+#[derive(Debug, Clone, Default)]
+pub struct FlowVars {
+    values: Arc<Mutex<HashMap<String, Value>>>,
+}
+
+impl FlowVars {
+    /// Create an empty set of variables
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Value>> {
+        self.values.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Get a variable
+    pub fn get(&self, key: &str) -> Option<Value> {
+        self.lock().get(key).cloned()
+    }
+
+    /// Set a variable, returning its previous value
+    pub fn set(&self, key: impl Into<String>, value: Value) -> Option<Value> {
+        self.lock().insert(key.into(), value)
+    }
+
+    /// Replace a variable (`null` if unset) with `f` of it, returning the new value
+    pub fn update(&self, key: &str, f: impl FnOnce(&Value) -> Value) -> Value {
+        let mut values = self.lock();
+        let value = f(values.get(key).unwrap_or(&Value::Null));
+        values.insert(key.to_string(), value.clone());
+        value
+    }
+
+    /// Remove a variable, returning its value
+    pub fn remove(&self, key: &str) -> Option<Value> {
+        self.lock().remove(key)
+    }
+
+    /// Check whether a variable is set
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.lock().contains_key(key)
+    }
+
+    /// Copy of all variables
+    pub fn snapshot(&self) -> HashMap<String, Value> {
+        self.lock().clone()
+    }
+
+    /// Remove all variables
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+}
@@ -0,0 +1,281 @@
+//! Model prices for dollar estimates of LLM usage
+//!
+//! A [`PricingCatalog`] knows what a model charges per million input and
+//! output tokens, and what embedding models charge per million input tokens.
+//! [`PricingCatalog::default`] bundles list prices of common OpenAI, Anthropic
+//! and Google models; a JSON file can override them or add private models and
+//! negotiated rates. Models are looked up by exact name, then by the longest
+//! known name they extend with a `-` or `:` suffix, so dated snapshots such as
+//! `gpt-4o-2024-08-06` get the price of `gpt-4o`.
+//!
+//! [`PricingCatalog::priced`] fills in [`TokenUsage::cost`], which the store's
+//! usage total, experiment summaries, the TUI dashboard and tenant cost
+//! budgets then add up. Costs are estimates: they ignore cached-input
+//! discounts, batch pricing and price changes after this release.
+//!
+//! ```rust
+//! # use pocketflow_rs::pricing::{ModelPrice, PricingCatalog};
+//! # use pocketflow_rs::TokenUsage;
+//! let catalog =
+//!     PricingCatalog::default().with_model_price("my-finetune", ModelPrice::new(3.0, 12.0));
+//! let usage = TokenUsage {
+//!     requests: 1,
+//!     input_tokens: 1_000_000,
+//!     output_tokens: 500_000,
+//!     ..TokenUsage::default()
+//! };
+//! assert_eq!(catalog.priced("my-finetune", usage).cost, 9.0);
+//! assert_eq!(catalog.cost("gpt-4o-2024-08-06", &usage), Some(7.5));
+//! assert_eq!(catalog.cost("llama3:8b", &usage), None);
+//! ```
+//!
+//! An override file has the same shape as the catalog:
+//!
+//! ```json
+//! {
+//!   "models": { "gpt-4o": { "input": 2.0, "output": 8.0 } },
+//!   "embeddings": { "my-embedder": 0.05 }
+//! }
+//! ```
+
+use crate::shared_store::TokenUsage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Error loading a pricing file
+#[derive(Debug, thiserror::Error)]
+pub enum PricingError {
+    #[error("Cannot read pricing file {}: {message}", .path.display())]
+    Read { path: PathBuf, message: String },
+    #[error("Invalid pricing file {}: {message}", .path.display())]
+    Parse { path: PathBuf, message: String },
+}
+
+/// Price of a chat model in US dollars per million tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    /// Price per million input and output tokens
+    pub fn new(input: f64, output: f64) -> Self {
+        Self { input, output }
+    }
+}
+
+/// Bundled chat model prices, per million input and output tokens
+const MODEL_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("gpt-4-turbo", 10.0, 30.0),
+    ("gpt-4", 30.0, 60.0),
+    ("gpt-3.5-turbo", 0.5, 1.5),
+    ("o1", 15.0, 60.0),
+    ("o1-mini", 1.1, 4.4),
+    ("o3", 2.0, 8.0),
+    ("o3-mini", 1.1, 4.4),
+    ("o4-mini", 1.1, 4.4),
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-opus", 15.0, 75.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("gemini-2.5-pro", 1.25, 10.0),
+    ("gemini-2.5-flash", 0.3, 2.5),
+    ("gemini-2.0-flash", 0.1, 0.4),
+    ("gemini-1.5-pro", 1.25, 5.0),
+    ("gemini-1.5-flash", 0.075, 0.3),
+];
+
+/// Bundled embedding model prices, per million input tokens
+const EMBEDDING_PRICES: &[(&str, f64)] = &[
+    ("text-embedding-3-small", 0.02),
+    ("text-embedding-3-large", 0.13),
+    ("text-embedding-ada-002", 0.1),
+];
+
+/// Prices of chat and embedding models
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PricingCatalog {
+    /// Chat model prices by model name
+    pub models: HashMap<String, ModelPrice>,
+    /// Embedding model prices in US dollars per million input tokens
+    pub embeddings: HashMap<String, f64>,
+}
+
+impl Default for PricingCatalog {
+    /// The bundled prices
+    fn default() -> Self {
+        Self {
+            models: MODEL_PRICES
+                .iter()
+                .map(|(model, input, output)| (model.to_string(), ModelPrice::new(*input, *output)))
+                .collect(),
+            embeddings: EMBEDDING_PRICES
+                .iter()
+                .map(|(model, price)| (model.to_string(), *price))
+                .collect(),
+        }
+    }
+}
+
+impl PricingCatalog {
+    /// Create a catalog without any prices
+    pub fn new() -> Self {
+        Self {
+            models: HashMap::new(),
+            embeddings: HashMap::new(),
+        }
+    }
+
+    /// The bundled prices overridden by a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, PricingError> {
+        let mut catalog = Self::default();
+        catalog.merge(Self::read(path.as_ref())?);
+        Ok(catalog)
+    }
+
+    /// Read a catalog from a JSON file, without the bundled prices
+    pub fn read(path: &Path) -> Result<Self, PricingError> {
+        let text = std::fs::read_to_string(path).map_err(|e| PricingError::Read {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        serde_json::from_str(&text).map_err(|e| PricingError::Parse {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Set or replace a chat model's price
+    pub fn with_model_price(mut self, model: impl Into<String>, price: ModelPrice) -> Self {
+        self.models.insert(model.into(), price);
+        self
+    }
+
+    /// Set or replace an embedding model's price per million input tokens
+    pub fn with_embedding_price(mut self, model: impl Into<String>, price: f64) -> Self {
+        self.embeddings.insert(model.into(), price);
+        self
+    }
+
+    /// Take over every price of `overrides`
+    pub fn merge(&mut self, overrides: PricingCatalog) {
+        self.models.extend(overrides.models);
+        self.embeddings.extend(overrides.embeddings);
+    }
+
+    /// Price of a chat model
+    pub fn model_price(&self, model: &str) -> Option<ModelPrice> {
+        lookup(&self.models, model).copied()
+    }
+
+    /// Price of an embedding model per million input tokens
+    pub fn embedding_price(&self, model: &str) -> Option<f64> {
+        lookup(&self.embeddings, model).copied()
+    }
+
+    /// Estimated cost in US dollars of the tokens in `usage`, `None` for an
+    /// unknown model
+    pub fn cost(&self, model: &str, usage: &TokenUsage) -> Option<f64> {
+        let price = self.model_price(model)?;
+        Some(
+            (usage.input_tokens as f64 * price.input + usage.output_tokens as f64 * price.output)
+                / 1_000_000.0,
+        )
+    }
+
+    /// Estimated cost in US dollars of embedding `tokens` input tokens
+    pub fn embedding_cost(&self, model: &str, tokens: u64) -> Option<f64> {
+        Some(tokens as f64 * self.embedding_price(model)? / 1_000_000.0)
+    }
+
+    /// `usage` with its cost set, or unchanged for an unknown model
+    pub fn priced(&self, model: &str, mut usage: TokenUsage) -> TokenUsage {
+        if let Some(cost) = self.cost(model, &usage) {
+            usage.cost = cost;
+        }
+        usage
+    }
+
+    /// Priced usage of a chat response, if the provider reported usage
+    ///
+    /// Responses without a model name are priced as `default_model`.
+    #[cfg(feature = "builtin-llm")]
+    pub fn response_usage(
+        &self,
+        response: &crate::llm::ChatResponse,
+        default_model: &str,
+    ) -> Option<TokenUsage> {
+        let model = response.model.as_deref().unwrap_or(default_model);
+        Some(self.priced(model, response.usage?))
+    }
+}
+
+/// The entry for `model`, or for the longest name it extends
+fn lookup<'a, T>(prices: &'a HashMap<String, T>, model: &str) -> Option<&'a T> {
+    if let Some(price) = prices.get(model) {
+        return Some(price);
+    }
+    prices
+        .iter()
+        .filter(|(name, _)| {
+            model
+                .strip_prefix(name.as_str())
+                .is_some_and(|rest| rest.starts_with(['-', ':']))
+        })
+        .max_by_key(|(name, _)| name.len())
+        .map(|(_, price)| price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pricing_catalog_overrides() {
+        let usage = TokenUsage {
+            requests: 2,
+            input_tokens: 2_000_000,
+            output_tokens: 1_000_000,
+            ..TokenUsage::default()
+        };
+        let catalog = PricingCatalog::default();
+        assert_eq!(catalog.cost("gpt-4o-mini-2024-07-18", &usage), Some(0.9));
+        assert_eq!(
+            catalog.cost("claude-3-5-sonnet-20241022", &usage),
+            Some(21.0)
+        );
+        assert_eq!(catalog.cost("gpt-4omni", &usage), None);
+        assert_eq!(
+            catalog.embedding_cost("text-embedding-3-small", 500_000),
+            Some(0.01)
+        );
+
+        let path = std::env::temp_dir().join(format!("pf-pricing-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"models": {"gpt-4o": {"input": 1.0, "output": 4.0}}, "embeddings": {"mine": 1.0}}"#,
+        )
+        .unwrap();
+        let catalog = PricingCatalog::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(catalog.priced("gpt-4o", usage).cost, 6.0);
+        assert_eq!(catalog.embedding_price("mine"), Some(1.0));
+        // Prices not overridden are kept
+        assert!(catalog.model_price("claude-3-haiku").is_some());
+        assert!(matches!(
+            PricingCatalog::from_file(&path),
+            Err(PricingError::Read { .. })
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+use super::{ClaimedJob, FlowJob, JobQueue, JobResult, WorkQueueError};
+use crate::env::RuntimeEnv;
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default)]
+struct QueueState {
+    pending: VecDeque<FlowJob>,
+    in_flight: HashMap<String, (ClaimedJob, Instant)>,
+    results: HashMap<String, JobResult>,
+}
+
+/// Work queue held in process memory
+///
+/// Clones share the same queue, so it can be handed to several workers running
+/// as tasks in one process.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryJobQueue {
+    state: Arc<Mutex<QueueState>>,
+    env: RuntimeEnv,
+}
+
+impl InMemoryJobQueue {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the clock that lease deadlines are measured on and the generator
+    /// for lease tokens
+    pub fn with_env(mut self, env: RuntimeEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Number of jobs waiting to be claimed
+    pub fn pending_len(&self) -> usize {
+        self.lock().pending.len()
+    }
+
+    /// Number of jobs currently claimed by a worker
+    pub fn in_flight_len(&self) -> usize {
+        self.lock().in_flight.len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take ownership of a claim, failing if its lease is no longer current
+    fn take_claim(state: &mut QueueState, claim: &ClaimedJob) -> Result<(), WorkQueueError> {
+        match state.in_flight.get(&claim.job.id) {
+            Some((current, _)) if current.lease == claim.lease => {
+                state.in_flight.remove(&claim.job.id);
+                Ok(())
+            }
+            _ => Err(WorkQueueError::LeaseLost(claim.job.id.clone())),
+        }
+    }
+}
+
+#[async_trait]
+impl JobQueue for InMemoryJobQueue {
+    async fn enqueue(&self, job: FlowJob) -> Result<(), WorkQueueError> {
+        self.lock().pending.push_back(job);
+        Ok(())
+    }
+
+    async fn claim(
+        &self,
+        visibility_timeout: Duration,
+    ) -> Result<Option<ClaimedJob>, WorkQueueError> {
+        let mut state = self.lock();
+        let now = self.env.now();
+
+        // Expired leases go back to the front of the queue
+        let expired: Vec<String> = state
+            .in_flight
+            .iter()
+            .filter(|(_, (_, deadline))| *deadline <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in expired {
+            if let Some((claim, _)) = state.in_flight.remove(&id) {
+                state.pending.push_front(claim.job);
+            }
+        }
+
+        let Some(mut job) = state.pending.pop_front() else {
+            return Ok(None);
+        };
+        job.attempts += 1;
+        let claim = ClaimedJob {
+            job,
+            lease: self.env.next_id(),
+        };
+        state.in_flight.insert(
+            claim.job.id.clone(),
+            (claim.clone(), now + visibility_timeout),
+        );
+        Ok(Some(claim))
+    }
+
+    async fn complete(&self, claim: &ClaimedJob, result: JobResult) -> Result<(), WorkQueueError> {
+        let mut state = self.lock();
+        Self::take_claim(&mut state, claim)?;
+        state.results.insert(claim.job.id.clone(), result);
+        Ok(())
+    }
+
+    async fn release(&self, claim: &ClaimedJob) -> Result<(), WorkQueueError> {
+        let mut state = self.lock();
+        Self::take_claim(&mut state, claim)?;
+        state.pending.push_back(claim.job.clone());
+        Ok(())
+    }
+
+    async fn result(&self, job_id: &str) -> Result<Option<JobResult>, WorkQueueError> {
+        Ok(self.lock().results.get(job_id).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::ManualClock;
+    use crate::queue::JobStatus;
+
+    #[tokio::test]
+    async fn test_expired_lease_is_reclaimed() {
+        let clock = Arc::new(ManualClock::default());
+        let queue = InMemoryJobQueue::new()
+            .with_env(RuntimeEnv::deterministic(1).with_clock(clock.clone()));
+        queue
+            .enqueue(FlowJob::new("flow", HashMap::new()))
+            .await
+            .unwrap();
+
+        let first = queue.claim(Duration::from_secs(30)).await.unwrap().unwrap();
+        assert_eq!(first.job.attempts, 1);
+        assert!(
+            queue
+                .claim(Duration::from_secs(60))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Once the lease runs out another worker gets the job
+        clock.advance(Duration::from_secs(30));
+        let second = queue.claim(Duration::from_secs(60)).await.unwrap().unwrap();
+        assert_eq!(second.job.id, first.job.id);
+        assert_eq!(second.job.attempts, 2);
+        assert_ne!(second.lease, first.lease);
+        assert!(
+            queue
+                .claim(Duration::from_secs(60))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // The stale worker can no longer complete the job
+        let result = JobResult {
+            job_id: first.job.id.clone(),
+            status: JobStatus::Completed,
+            output: HashMap::new(),
+            final_action: None,
+            error: None,
+            attempts: first.job.attempts,
+        };
+        assert!(matches!(
+            queue.complete(&first, result.clone()).await,
+            Err(WorkQueueError::LeaseLost(_))
+        ));
+        queue.complete(&second, result).await.unwrap();
+        assert_eq!(queue.in_flight_len(), 0);
+        assert_eq!(queue.pending_len(), 0);
+    }
+}
@@ -0,0 +1,611 @@
+//! Distributed work queue for flow execution
+//!
+//! Flows are submitted as [`FlowJob`]s (a registered flow name plus the initial
+//! shared store contents) to a [`JobQueue`]. Worker processes run a [`FlowWorker`]
+//! that claims jobs, executes the named flow from its [`FlowRegistry`] and writes a
+//! [`JobResult`] back to the queue.
+//!
+//! Claims are leases: a claimed job is hidden from other workers for the
+//! visibility timeout. If the worker crashes or the lease expires before the job
+//! completes, the job becomes claimable again and counts as another attempt.
+//!
+//! Backends:
+//! - [`InMemoryJobQueue`]: single process, mainly for tests and local development
+//! - `RedisJobQueue`: Redis (feature `storage-redis`)
+//! - `PostgresJobQueue`: Postgres (feature `storage-postgres`)
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::queue::{FlowRegistry, FlowWorkQueue, InMemoryJobQueue, JobStatus};
+//! # use std::collections::HashMap;
+//! # tokio_test::block_on(async {
+//! let mut registry = FlowRegistry::new();
+//! registry.register("greet", || {
+//!     FlowBuilder::new()
+//!         .start_node("set")
+//!         .node("set", Node::new(SetValueNode::new(
+//!             "greeting".to_string(),
+//!             JsonValue::from("hello"),
+//!             Action::simple("complete"),
+//!         )))
+//!         .build()
+//! });
+//!
+//! let queue = FlowWorkQueue::new(InMemoryJobQueue::new());
+//! let job_id = queue.submit("greet", HashMap::new()).await.unwrap();
+//!
+//! let worker = queue.worker(registry);
+//! worker.process_next().await.unwrap();
+//!
+//! let result = queue.result(&job_id).await.unwrap().unwrap();
+//! assert_eq!(result.status, JobStatus::Completed);
+//! assert_eq!(result.output["greeting"], "hello");
+//! # });
+//! ```
+
+use crate::env::RuntimeEnv;
+use crate::flow::{BasicFlow, Flow};
+use crate::shared_store::SharedStore;
+use crate::storage::InMemoryStorage;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use thiserror::Error;
+
+mod memory;
+pub use memory::InMemoryJobQueue;
+
+#[cfg(feature = "storage-redis")]
+mod redis;
+#[cfg(feature = "storage-redis")]
+pub use redis::RedisJobQueue;
+
+#[cfg(feature = "storage-postgres")]
+mod postgres;
+#[cfg(feature = "storage-postgres")]
+pub use postgres::PostgresJobQueue;
+
+/// Default number of attempts before a job is marked as failed
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Error types for work queue operations
+#[derive(Debug, Error)]
+pub enum WorkQueueError {
+    #[error("Queue backend error: {0}")]
+    Backend(String),
+    #[error("JSON serialization error: {0}")]
+    JsonSerialization(#[from] serde_json::Error),
+    #[error("Lease for job '{0}' expired or is held by another worker")]
+    LeaseLost(String),
+    #[error("Timed out waiting for job '{0}'")]
+    Timeout(String),
+}
+
+/// A flow execution request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowJob {
+    /// Unique job ID
+    pub id: String,
+    /// Name of the flow in the worker's [`FlowRegistry`]
+    pub flow: String,
+    /// Initial shared store contents
+    pub input: HashMap<String, Value>,
+    /// Number of times the job has been claimed
+    pub attempts: u32,
+    /// Maximum number of attempts before the job is marked as failed
+    pub max_attempts: u32,
+    /// Submission time in milliseconds since the Unix epoch
+    pub created_at: u64,
+    /// Error from the most recent failed attempt, kept while the job waits to
+    /// be retried
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl FlowJob {
+    /// Create a new job for a registered flow
+    pub fn new(flow: impl Into<String>, input: HashMap<String, Value>) -> Self {
+        Self::new_with_env(flow, input, &RuntimeEnv::default())
+    }
+
+    /// Create a new job, taking its ID and submission time from `env`
+    pub fn new_with_env(
+        flow: impl Into<String>,
+        input: HashMap<String, Value>,
+        env: &RuntimeEnv,
+    ) -> Self {
+        Self {
+            id: env.next_id(),
+            flow: flow.into(),
+            input,
+            attempts: 0,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            created_at: now_millis(env),
+            last_error: None,
+        }
+    }
+
+    /// Set the maximum number of attempts
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Check whether the job may be retried after a failed attempt
+    pub fn can_retry(&self) -> bool {
+        self.attempts < self.max_attempts
+    }
+}
+
+/// A job claimed by a worker, together with the lease that proves ownership
+#[derive(Debug, Clone)]
+pub struct ClaimedJob {
+    /// The claimed job, with `attempts` including this claim
+    pub job: FlowJob,
+    /// Lease token; completing or releasing with a stale token fails
+    pub lease: String,
+}
+
+/// Final status of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// The flow ran to completion
+    Completed,
+    /// The flow failed on its last attempt, or could not be run at all
+    Failed,
+}
+
+/// The outcome of a job, written back to the queue by the worker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobResult {
+    /// ID of the job
+    pub job_id: String,
+    /// Final status
+    pub status: JobStatus,
+    /// Shared store contents after the flow finished
+    pub output: HashMap<String, Value>,
+    /// Name of the action that terminated the flow
+    pub final_action: Option<String>,
+    /// Error message for failed jobs
+    pub error: Option<String>,
+    /// Number of attempts made
+    pub attempts: u32,
+}
+
+impl JobResult {
+    fn failed(job: &FlowJob, error: String) -> Self {
+        Self {
+            job_id: job.id.clone(),
+            status: JobStatus::Failed,
+            output: HashMap::new(),
+            final_action: None,
+            error: Some(error),
+            attempts: job.attempts,
+        }
+    }
+}
+
+/// Trait implemented by work queue backends
+#[async_trait]
+pub trait JobQueue: Send + Sync {
+    /// Add a job to the back of the queue
+    async fn enqueue(&self, job: FlowJob) -> Result<(), WorkQueueError>;
+
+    /// Claim the next available job, hiding it from other workers for
+    /// `visibility_timeout`. Jobs whose lease has expired are claimable again.
+    async fn claim(
+        &self,
+        visibility_timeout: Duration,
+    ) -> Result<Option<ClaimedJob>, WorkQueueError>;
+
+    /// Record the final result of a claimed job and remove it from the queue
+    async fn complete(&self, claim: &ClaimedJob, result: JobResult) -> Result<(), WorkQueueError>;
+
+    /// Give up a claimed job so it can be retried by any worker, keeping the
+    /// claim's `last_error`
+    async fn release(&self, claim: &ClaimedJob) -> Result<(), WorkQueueError>;
+
+    /// Get the result of a finished job
+    async fn result(&self, job_id: &str) -> Result<Option<JobResult>, WorkQueueError>;
+}
+
+/// Factory producing a fresh flow instance for each job
+pub type FlowFactory = Arc<dyn Fn() -> BasicFlow<InMemoryStorage> + Send + Sync>;
+
+/// Named flow factories known to a worker
+#[derive(Clone, Default)]
+pub struct FlowRegistry {
+    factories: HashMap<String, FlowFactory>,
+}
+
+impl std::fmt::Debug for FlowRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlowRegistry")
+            .field("flows", &self.names())
+            .finish()
+    }
+}
+
+impl FlowRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a flow factory under a name, replacing any existing one
+    pub fn register<F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn() -> BasicFlow<InMemoryStorage> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.into(), Arc::new(factory));
+    }
+
+    /// Create a new instance of a registered flow
+    pub fn create(&self, name: &str) -> Option<BasicFlow<InMemoryStorage>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+
+    /// Check whether a flow is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Get the names of all registered flows, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.factories.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+/// Client-side entry point for submitting flows and reading their results
+#[derive(Debug)]
+pub struct FlowWorkQueue<Q: JobQueue> {
+    queue: Arc<Q>,
+    poll_interval: Duration,
+    env: RuntimeEnv,
+}
+
+impl<Q: JobQueue> Clone for FlowWorkQueue<Q> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: Arc::clone(&self.queue),
+            poll_interval: self.poll_interval,
+            env: self.env.clone(),
+        }
+    }
+}
+
+impl<Q: JobQueue> FlowWorkQueue<Q> {
+    /// Create a work queue on top of a backend
+    pub fn new(queue: Q) -> Self {
+        Self {
+            queue: Arc::new(queue),
+            poll_interval: Duration::from_millis(100),
+            env: RuntimeEnv::default(),
+        }
+    }
+
+    /// Set the clock and ID generator used for submitted jobs
+    pub fn with_env(mut self, env: RuntimeEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Set how often `wait_for_result` and idle workers poll the backend
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Get the underlying backend
+    pub fn backend(&self) -> &Arc<Q> {
+        &self.queue
+    }
+
+    /// Submit a registered flow with its initial store contents, returning the job ID
+    pub async fn submit(
+        &self,
+        flow: impl Into<String>,
+        input: HashMap<String, Value>,
+    ) -> Result<String, WorkQueueError> {
+        self.submit_job(FlowJob::new_with_env(flow, input, &self.env))
+            .await
+    }
+
+    /// Submit a fully configured job, returning its ID
+    pub async fn submit_job(&self, job: FlowJob) -> Result<String, WorkQueueError> {
+        let id = job.id.clone();
+        self.queue.enqueue(job).await?;
+        Ok(id)
+    }
+
+    /// Get the result of a job if it has finished
+    pub async fn result(&self, job_id: &str) -> Result<Option<JobResult>, WorkQueueError> {
+        self.queue.result(job_id).await
+    }
+
+    /// Poll until a job finishes or `timeout` elapses
+    pub async fn wait_for_result(
+        &self,
+        job_id: &str,
+        timeout: Duration,
+    ) -> Result<JobResult, WorkQueueError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Some(result) = self.queue.result(job_id).await? {
+                return Ok(result);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(WorkQueueError::Timeout(job_id.to_string()));
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Create a worker that executes jobs from this queue
+    pub fn worker(&self, registry: FlowRegistry) -> FlowWorker<Q> {
+        FlowWorker {
+            queue: Arc::clone(&self.queue),
+            registry,
+            visibility_timeout: Duration::from_secs(300),
+            poll_interval: self.poll_interval,
+        }
+    }
+}
+
+/// Claims jobs from a queue and executes them
+///
+/// The visibility timeout should comfortably exceed the longest expected flow
+/// run; a job that outlives its lease may be picked up by another worker.
+#[derive(Debug)]
+pub struct FlowWorker<Q: JobQueue> {
+    queue: Arc<Q>,
+    registry: FlowRegistry,
+    visibility_timeout: Duration,
+    poll_interval: Duration,
+}
+
+impl<Q: JobQueue> FlowWorker<Q> {
+    /// Set how long a claimed job stays hidden from other workers
+    pub fn with_visibility_timeout(mut self, visibility_timeout: Duration) -> Self {
+        self.visibility_timeout = visibility_timeout;
+        self
+    }
+
+    /// Get the registry of flows this worker can run
+    pub fn registry(&self) -> &FlowRegistry {
+        &self.registry
+    }
+
+    /// Claim and execute a single job. Returns `false` if the queue was empty.
+    pub async fn process_next(&self) -> Result<bool, WorkQueueError> {
+        let Some(claim) = self.queue.claim(self.visibility_timeout).await? else {
+            return Ok(false);
+        };
+        let job = &claim.job;
+
+        // A job whose lease kept expiring has used up its attempts
+        if job.attempts > job.max_attempts {
+            let error = format!("Job exceeded {} attempts", job.max_attempts);
+            self.queue
+                .complete(&claim, JobResult::failed(job, error))
+                .await?;
+            return Ok(true);
+        }
+
+        let Some(flow) = self.registry.create(&job.flow) else {
+            let error = format!("Flow '{}' is not registered", job.flow);
+            self.queue
+                .complete(&claim, JobResult::failed(job, error))
+                .await?;
+            return Ok(true);
+        };
+
+        match Self::execute(flow, job).await {
+            Ok(result) => self.queue.complete(&claim, result).await?,
+            Err(error) if job.can_retry() => {
+                let mut retry = claim.clone();
+                retry.job.last_error = Some(error);
+                self.queue.release(&retry).await?
+            }
+            Err(error) => {
+                self.queue
+                    .complete(&claim, JobResult::failed(job, error))
+                    .await?
+            }
+        }
+        Ok(true)
+    }
+
+    /// Process jobs until `shutdown` resolves, sleeping between polls when idle
+    ///
+    /// A job that is already running when `shutdown` resolves is finished first.
+    pub async fn run_until<F>(&self, shutdown: F) -> Result<(), WorkQueueError>
+    where
+        F: Future<Output = ()>,
+    {
+        tokio::pin!(shutdown);
+        loop {
+            // Only check for shutdown between jobs so a running job isn't cancelled
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => return Ok(()),
+                _ = std::future::ready(()) => {}
+            }
+            if !self.process_next().await? {
+                tokio::select! {
+                    _ = &mut shutdown => return Ok(()),
+                    _ = tokio::time::sleep(self.poll_interval) => {}
+                }
+            }
+        }
+    }
+
+    async fn execute(
+        mut flow: BasicFlow<InMemoryStorage>,
+        job: &FlowJob,
+    ) -> Result<JobResult, String> {
+        let mut store = SharedStore::new();
+        for (key, value) in &job.input {
+            store
+                .set(key.clone(), value.clone())
+                .map_err(|e| e.to_string())?;
+        }
+
+        let execution = flow.execute(&mut store).await.map_err(|e| e.to_string())?;
+
+        let mut output = HashMap::new();
+        for key in store.keys().map_err(|e| e.to_string())? {
+            if let Some(value) = store.get(&key).map_err(|e| e.to_string())? {
+                output.insert(key, value);
+            }
+        }
+
+        Ok(JobResult {
+            job_id: job.id.clone(),
+            status: JobStatus::Completed,
+            output,
+            final_action: Some(execution.final_action.name()),
+            error: None,
+            attempts: job.attempts,
+        })
+    }
+}
+
+/// Wall-clock milliseconds since the Unix epoch on `env`'s clock
+pub(crate) fn now_millis(env: &RuntimeEnv) -> u64 {
+    env.system_time()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+#[cfg(all(test, feature = "builtin-nodes"))]
+mod tests {
+    use super::*;
+    use crate::Action;
+    use crate::flow::FlowBuilder;
+    use crate::node::Node;
+    use crate::node::builtin::SetValueNode;
+    use serde_json::json;
+
+    fn registry() -> FlowRegistry {
+        let mut registry = FlowRegistry::new();
+        registry.register("greet", || {
+            FlowBuilder::new()
+                .start_node("set")
+                .node(
+                    "set",
+                    Node::new(SetValueNode::new(
+                        "greeting".to_string(),
+                        json!("hello"),
+                        Action::simple("complete"),
+                    )),
+                )
+                .build()
+        });
+        // Fails at runtime because its start node doesn't exist
+        registry.register("broken", || {
+            FlowBuilder::new().start_node("missing").build()
+        });
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_worker_completes_job() {
+        let queue = FlowWorkQueue::new(InMemoryJobQueue::new());
+        let job_id = queue
+            .submit("greet", HashMap::from([("name".to_string(), json!("Ada"))]))
+            .await
+            .unwrap();
+
+        let worker = queue.worker(registry());
+        assert!(worker.process_next().await.unwrap());
+        assert!(!worker.process_next().await.unwrap());
+
+        let result = queue
+            .wait_for_result(&job_id, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(result.status, JobStatus::Completed);
+        assert_eq!(result.output["greeting"], json!("hello"));
+        assert_eq!(result.output["name"], json!("Ada"));
+        assert_eq!(result.final_action.as_deref(), Some("complete"));
+        assert_eq!(result.attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_worker_retries_then_fails() {
+        let queue = FlowWorkQueue::new(InMemoryJobQueue::new());
+        let job_id = queue
+            .submit_job(FlowJob::new("broken", HashMap::new()).with_max_attempts(2))
+            .await
+            .unwrap();
+        let unknown_id = queue.submit("missing", HashMap::new()).await.unwrap();
+
+        let worker = queue.worker(registry());
+        while worker.process_next().await.unwrap() {}
+
+        let result = queue.result(&job_id).await.unwrap().unwrap();
+        assert_eq!(result.status, JobStatus::Failed);
+        assert_eq!(result.attempts, 2);
+        assert!(result.error.unwrap().contains("missing"));
+
+        // Unknown flows fail immediately without retries
+        let unknown = queue.result(&unknown_id).await.unwrap().unwrap();
+        assert_eq!(unknown.status, JobStatus::Failed);
+        assert_eq!(unknown.attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_released_job_keeps_last_error() {
+        let queue =
+            FlowWorkQueue::new(InMemoryJobQueue::new()).with_env(RuntimeEnv::deterministic(1));
+        let job_id = queue.submit("broken", HashMap::new()).await.unwrap();
+        assert_eq!(job_id, "exec-1");
+
+        let worker = queue.worker(registry());
+        assert!(worker.process_next().await.unwrap());
+        assert!(queue.result(&job_id).await.unwrap().is_none());
+
+        // The failed attempt's error travels with the job to the next worker
+        let claim = queue
+            .backend()
+            .claim(Duration::from_secs(60))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(claim.job.attempts, 2);
+        assert!(claim.job.last_error.unwrap().contains("missing"));
+    }
+
+    #[tokio::test]
+    async fn test_worker_run_until_shutdown() {
+        let queue = FlowWorkQueue::new(InMemoryJobQueue::new())
+            .with_poll_interval(Duration::from_millis(10));
+        let worker = queue.worker(registry());
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            worker
+                .run_until(async {
+                    stop_rx.await.ok();
+                })
+                .await
+        });
+
+        let job_id = queue.submit("greet", HashMap::new()).await.unwrap();
+        let result = queue
+            .wait_for_result(&job_id, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(result.status, JobStatus::Completed);
+
+        stop_tx.send(()).unwrap();
+        handle.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,260 @@
+use super::{ClaimedJob, FlowJob, JobQueue, JobResult, JobStatus, WorkQueueError, now_millis};
+use crate::env::RuntimeEnv;
+use async_trait::async_trait;
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, DbErr, Statement};
+use std::time::Duration;
+
+const CREATE_TABLE: &str = r"
+CREATE TABLE IF NOT EXISTS pocketflow_jobs (
+    id TEXT PRIMARY KEY,
+    queue TEXT NOT NULL,
+    flow TEXT NOT NULL,
+    input TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    created_at BIGINT NOT NULL,
+    queued_at BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    lease TEXT,
+    visible_at TIMESTAMPTZ,
+    result TEXT,
+    last_error TEXT
+)";
+
+/// Tables created before `last_error` existed gain the column on migrate
+const ADD_LAST_ERROR: &str = r"
+ALTER TABLE pocketflow_jobs ADD COLUMN IF NOT EXISTS last_error TEXT";
+
+const CREATE_INDEX: &str = r"
+CREATE INDEX IF NOT EXISTS pocketflow_jobs_claim_idx
+    ON pocketflow_jobs (queue, status, queued_at)";
+
+/// Leases the oldest claimable job; `SKIP LOCKED` keeps concurrent workers from
+/// blocking on each other's candidate rows. Times are milliseconds since the
+/// Unix epoch on the queue's clock.
+const CLAIM_SQL: &str = r"
+UPDATE pocketflow_jobs
+SET status = 'running',
+    attempts = attempts + 1,
+    lease = $1,
+    visible_at = to_timestamp($2::double precision / 1000)
+WHERE id = (
+    SELECT id FROM pocketflow_jobs
+    WHERE queue = $3
+      AND (status = 'pending'
+           OR (status = 'running' AND visible_at <= to_timestamp($4::double precision / 1000)))
+    ORDER BY queued_at
+    LIMIT 1
+    FOR UPDATE SKIP LOCKED
+)
+RETURNING id, flow, input, attempts, max_attempts, created_at, last_error";
+
+/// Work queue backed by a Postgres table
+///
+/// All queues share the `pocketflow_jobs` table and are told apart by name. Call
+/// [`PostgresJobQueue::migrate`] once to create the table.
+#[derive(Debug, Clone)]
+pub struct PostgresJobQueue {
+    connection: DatabaseConnection,
+    queue: String,
+    env: RuntimeEnv,
+}
+
+impl PostgresJobQueue {
+    /// Connect to Postgres using the default queue name
+    pub async fn new(database_url: &str) -> Result<Self, WorkQueueError> {
+        Self::new_with_queue(database_url, "pocketflow").await
+    }
+
+    /// Connect to Postgres using a custom queue name
+    pub async fn new_with_queue(database_url: &str, queue: &str) -> Result<Self, WorkQueueError> {
+        let connection = Database::connect(database_url).await.map_err(backend_err)?;
+        Ok(Self::from_connection(connection, queue))
+    }
+
+    /// Use an existing connection (e.g. the one from `DatabaseStorage`)
+    pub fn from_connection(connection: DatabaseConnection, queue: &str) -> Self {
+        Self {
+            connection,
+            queue: queue.to_string(),
+            env: RuntimeEnv::default(),
+        }
+    }
+
+    /// Set the clock that lease deadlines are measured on and the generator
+    /// for lease tokens
+    pub fn with_env(mut self, env: RuntimeEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Create the jobs table if it doesn't exist
+    pub async fn migrate(&self) -> Result<(), WorkQueueError> {
+        self.connection
+            .execute_unprepared(CREATE_TABLE)
+            .await
+            .map_err(backend_err)?;
+        self.connection
+            .execute_unprepared(ADD_LAST_ERROR)
+            .await
+            .map_err(backend_err)?;
+        self.connection
+            .execute_unprepared(CREATE_INDEX)
+            .await
+            .map_err(backend_err)?;
+        Ok(())
+    }
+
+    /// Get the queue name
+    pub fn queue(&self) -> &str {
+        &self.queue
+    }
+
+    fn statement(sql: &str, values: Vec<sea_orm::Value>) -> Statement {
+        Statement::from_sql_and_values(DbBackend::Postgres, sql, values)
+    }
+}
+
+#[async_trait]
+impl JobQueue for PostgresJobQueue {
+    async fn enqueue(&self, job: FlowJob) -> Result<(), WorkQueueError> {
+        let input = serde_json::to_string(&job.input)?;
+        self.connection
+            .execute(Self::statement(
+                "INSERT INTO pocketflow_jobs \
+                 (id, queue, flow, input, attempts, max_attempts, created_at, queued_at, status) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'pending')",
+                vec![
+                    job.id.into(),
+                    self.queue.clone().into(),
+                    job.flow.into(),
+                    input.into(),
+                    (job.attempts as i32).into(),
+                    (job.max_attempts as i32).into(),
+                    (job.created_at as i64).into(),
+                    (now_millis(&self.env) as i64).into(),
+                ],
+            ))
+            .await
+            .map_err(backend_err)?;
+        Ok(())
+    }
+
+    async fn claim(
+        &self,
+        visibility_timeout: Duration,
+    ) -> Result<Option<ClaimedJob>, WorkQueueError> {
+        let now = now_millis(&self.env);
+        let deadline = now + visibility_timeout.as_millis() as u64;
+        let lease = self.env.next_id();
+        let row = self
+            .connection
+            .query_one(Self::statement(
+                CLAIM_SQL,
+                vec![
+                    lease.clone().into(),
+                    (deadline as f64).into(),
+                    self.queue.clone().into(),
+                    (now as f64).into(),
+                ],
+            ))
+            .await
+            .map_err(backend_err)?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let input: String = row.try_get("", "input").map_err(backend_err)?;
+        let attempts: i32 = row.try_get("", "attempts").map_err(backend_err)?;
+        let max_attempts: i32 = row.try_get("", "max_attempts").map_err(backend_err)?;
+        let created_at: i64 = row.try_get("", "created_at").map_err(backend_err)?;
+        let job = FlowJob {
+            id: row.try_get("", "id").map_err(backend_err)?,
+            flow: row.try_get("", "flow").map_err(backend_err)?,
+            input: serde_json::from_str(&input)?,
+            attempts: attempts as u32,
+            max_attempts: max_attempts as u32,
+            created_at: created_at as u64,
+            last_error: row.try_get("", "last_error").map_err(backend_err)?,
+        };
+        Ok(Some(ClaimedJob { job, lease }))
+    }
+
+    async fn complete(&self, claim: &ClaimedJob, result: JobResult) -> Result<(), WorkQueueError> {
+        let status = match result.status {
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+        };
+        let payload = serde_json::to_string(&result)?;
+        let updated = self
+            .connection
+            .execute(Self::statement(
+                "UPDATE pocketflow_jobs \
+                 SET status = $1, result = $2, lease = NULL, visible_at = NULL \
+                 WHERE id = $3 AND lease = $4 AND status = 'running'",
+                vec![
+                    status.into(),
+                    payload.into(),
+                    claim.job.id.clone().into(),
+                    claim.lease.clone().into(),
+                ],
+            ))
+            .await
+            .map_err(backend_err)?;
+
+        if updated.rows_affected() == 1 {
+            Ok(())
+        } else {
+            Err(WorkQueueError::LeaseLost(claim.job.id.clone()))
+        }
+    }
+
+    async fn release(&self, claim: &ClaimedJob) -> Result<(), WorkQueueError> {
+        let updated = self
+            .connection
+            .execute(Self::statement(
+                "UPDATE pocketflow_jobs \
+                 SET status = 'pending', lease = NULL, visible_at = NULL, queued_at = $1, \
+                     last_error = $2 \
+                 WHERE id = $3 AND lease = $4 AND status = 'running'",
+                vec![
+                    (now_millis(&self.env) as i64).into(),
+                    claim.job.last_error.clone().into(),
+                    claim.job.id.clone().into(),
+                    claim.lease.clone().into(),
+                ],
+            ))
+            .await
+            .map_err(backend_err)?;
+
+        if updated.rows_affected() == 1 {
+            Ok(())
+        } else {
+            Err(WorkQueueError::LeaseLost(claim.job.id.clone()))
+        }
+    }
+
+    async fn result(&self, job_id: &str) -> Result<Option<JobResult>, WorkQueueError> {
+        let row = self
+            .connection
+            .query_one(Self::statement(
+                "SELECT result FROM pocketflow_jobs \
+                 WHERE id = $1 AND queue = $2 AND result IS NOT NULL",
+                vec![job_id.into(), self.queue.clone().into()],
+            ))
+            .await
+            .map_err(backend_err)?;
+
+        match row {
+            Some(row) => {
+                let payload: String = row.try_get("", "result").map_err(backend_err)?;
+                Ok(Some(serde_json::from_str(&payload)?))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+fn backend_err(error: DbErr) -> WorkQueueError {
+    WorkQueueError::Backend(error.to_string())
+}
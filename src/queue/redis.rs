@@ -0,0 +1,218 @@
+use super::{ClaimedJob, FlowJob, JobQueue, JobResult, WorkQueueError, now_millis};
+use crate::env::RuntimeEnv;
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncCommands, Client, Script};
+use std::time::Duration;
+
+/// Requeues expired leases, then pops the oldest pending job and leases it
+const CLAIM_SCRIPT: &str = r"
+local expired = redis.call('ZRANGEBYSCORE', KEYS[3], '-inf', ARGV[1])
+for _, id in ipairs(expired) do
+  redis.call('ZREM', KEYS[3], id)
+  redis.call('HDEL', KEYS[4], id)
+  redis.call('RPUSH', KEYS[1], id)
+end
+while true do
+  local id = redis.call('RPOP', KEYS[1])
+  if not id then return false end
+  local job = redis.call('HGET', KEYS[2], id)
+  if job then
+    local attempts = redis.call('HINCRBY', KEYS[5], id, 1)
+    redis.call('ZADD', KEYS[3], ARGV[2], id)
+    redis.call('HSET', KEYS[4], id, ARGV[3])
+    return {job, attempts}
+  end
+end
+";
+
+/// Stores the result of a job if the caller still holds its lease
+const COMPLETE_SCRIPT: &str = r"
+if redis.call('HGET', KEYS[3], ARGV[1]) ~= ARGV[2] then return 0 end
+redis.call('ZREM', KEYS[2], ARGV[1])
+redis.call('HDEL', KEYS[3], ARGV[1])
+redis.call('HDEL', KEYS[1], ARGV[1])
+redis.call('HDEL', KEYS[5], ARGV[1])
+redis.call('HSET', KEYS[4], ARGV[1], ARGV[3])
+return 1
+";
+
+/// Stores the released payload and puts the job back at the end of the queue if
+/// the caller still holds its lease
+const RELEASE_SCRIPT: &str = r"
+if redis.call('HGET', KEYS[3], ARGV[1]) ~= ARGV[2] then return 0 end
+redis.call('ZREM', KEYS[2], ARGV[1])
+redis.call('HDEL', KEYS[3], ARGV[1])
+redis.call('HSET', KEYS[4], ARGV[1], ARGV[3])
+redis.call('LPUSH', KEYS[1], ARGV[1])
+return 1
+";
+
+/// Work queue backed by Redis
+///
+/// Uses a list of pending job IDs, a hash of job payloads, a sorted set of lease
+/// deadlines and a hash of results, all under `<prefix>:queue:`. Claims, completions
+/// and releases run as Lua scripts so they are atomic across workers.
+#[derive(Clone)]
+pub struct RedisJobQueue {
+    connection: MultiplexedConnection,
+    key_prefix: String,
+    env: RuntimeEnv,
+}
+
+impl std::fmt::Debug for RedisJobQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisJobQueue")
+            .field("key_prefix", &self.key_prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RedisJobQueue {
+    /// Connect to Redis with the default prefix
+    pub async fn new(redis_url: &str) -> Result<Self, WorkQueueError> {
+        Self::new_with_prefix(redis_url, "pocketflow").await
+    }
+
+    /// Connect to Redis with a custom key prefix
+    pub async fn new_with_prefix(
+        redis_url: &str,
+        key_prefix: &str,
+    ) -> Result<Self, WorkQueueError> {
+        let client = Client::open(redis_url).map_err(backend_err)?;
+        let connection = client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(backend_err)?;
+        Ok(Self {
+            connection,
+            key_prefix: key_prefix.to_string(),
+            env: RuntimeEnv::default(),
+        })
+    }
+
+    /// Set the clock that lease deadlines are measured on and the generator
+    /// for lease tokens
+    pub fn with_env(mut self, env: RuntimeEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Get the key prefix
+    pub fn prefix(&self) -> &str {
+        &self.key_prefix
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}:queue:{}", self.key_prefix, name)
+    }
+}
+
+#[async_trait]
+impl JobQueue for RedisJobQueue {
+    async fn enqueue(&self, job: FlowJob) -> Result<(), WorkQueueError> {
+        let payload = serde_json::to_string(&job)?;
+        let mut conn = self.connection.clone();
+        redis::pipe()
+            .atomic()
+            .hset(self.key("jobs"), &job.id, payload)
+            .hset(self.key("attempts"), &job.id, job.attempts)
+            .lpush(self.key("pending"), &job.id)
+            .exec_async(&mut conn)
+            .await
+            .map_err(backend_err)
+    }
+
+    async fn claim(
+        &self,
+        visibility_timeout: Duration,
+    ) -> Result<Option<ClaimedJob>, WorkQueueError> {
+        let now = now_millis(&self.env);
+        let deadline = now + visibility_timeout.as_millis() as u64;
+        let lease = self.env.next_id();
+
+        let mut conn = self.connection.clone();
+        let claimed: Option<(String, u32)> = Script::new(CLAIM_SCRIPT)
+            .key(self.key("pending"))
+            .key(self.key("jobs"))
+            .key(self.key("in_flight"))
+            .key(self.key("leases"))
+            .key(self.key("attempts"))
+            .arg(now)
+            .arg(deadline)
+            .arg(&lease)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(backend_err)?;
+
+        match claimed {
+            Some((payload, attempts)) => {
+                let mut job: FlowJob = serde_json::from_str(&payload)?;
+                job.attempts = attempts;
+                Ok(Some(ClaimedJob { job, lease }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn complete(&self, claim: &ClaimedJob, result: JobResult) -> Result<(), WorkQueueError> {
+        let payload = serde_json::to_string(&result)?;
+        let mut conn = self.connection.clone();
+        let completed: i32 = Script::new(COMPLETE_SCRIPT)
+            .key(self.key("jobs"))
+            .key(self.key("in_flight"))
+            .key(self.key("leases"))
+            .key(self.key("results"))
+            .key(self.key("attempts"))
+            .arg(&claim.job.id)
+            .arg(&claim.lease)
+            .arg(payload)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(backend_err)?;
+
+        if completed == 1 {
+            Ok(())
+        } else {
+            Err(WorkQueueError::LeaseLost(claim.job.id.clone()))
+        }
+    }
+
+    async fn release(&self, claim: &ClaimedJob) -> Result<(), WorkQueueError> {
+        let payload = serde_json::to_string(&claim.job)?;
+        let mut conn = self.connection.clone();
+        let released: i32 = Script::new(RELEASE_SCRIPT)
+            .key(self.key("pending"))
+            .key(self.key("in_flight"))
+            .key(self.key("leases"))
+            .key(self.key("jobs"))
+            .arg(&claim.job.id)
+            .arg(&claim.lease)
+            .arg(payload)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(backend_err)?;
+
+        if released == 1 {
+            Ok(())
+        } else {
+            Err(WorkQueueError::LeaseLost(claim.job.id.clone()))
+        }
+    }
+
+    async fn result(&self, job_id: &str) -> Result<Option<JobResult>, WorkQueueError> {
+        let mut conn = self.connection.clone();
+        let payload: Option<String> = conn
+            .hget(self.key("results"), job_id)
+            .await
+            .map_err(backend_err)?;
+        match payload {
+            Some(payload) => Ok(Some(serde_json::from_str(&payload)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+fn backend_err(error: redis::RedisError) -> WorkQueueError {
+    WorkQueueError::Backend(error.to_string())
+}
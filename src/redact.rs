@@ -0,0 +1,302 @@
+//! Scrubbing sensitive data before it leaves the process
+//!
+//! A [`Redactor`] masks store values before they are logged, journaled or
+//! shown: whole values under keys matching a pattern (`*token*`, `password`),
+//! and spans inside text such as email addresses, long numbers, literal terms
+//! and, with the `redaction` feature, regular expressions. Key patterns also
+//! apply to field names inside nested objects, so an API payload's
+//! `headers.authorization` is masked wherever it ends up.
+//!
+//! ```rust
+//! # use pocketflow_rs::redact::Redactor;
+//! # use serde_json::json;
+//! let redactor = Redactor::pii().with_key("*token*");
+//! assert_eq!(
+//!     redactor.redact_entry("request", &json!({"to": "jane@example.com", "api_token": "sk-1"})),
+//!     json!({"to": "[REDACTED]", "api_token": "[REDACTED]"})
+//! );
+//! assert_eq!(redactor.redact_entry("auth_token", &json!(42)), json!("[REDACTED]"));
+//! ```
+
+use serde_json::Value;
+
+/// Masks sensitive keys and text spans in store values
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    keys: Vec<String>,
+    emails: bool,
+    min_digits: Option<usize>,
+    terms: Vec<String>,
+    #[cfg(feature = "redaction")]
+    patterns: Vec<regex::Regex>,
+    replacement: String,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            emails: false,
+            min_digits: None,
+            terms: Vec::new(),
+            #[cfg(feature = "redaction")]
+            patterns: Vec::new(),
+            replacement: "[REDACTED]".to_string(),
+        }
+    }
+}
+
+impl Redactor {
+    /// Create a redactor that masks nothing until configured
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redact email addresses and numbers of 7 or more digits (phone, card and
+    /// account numbers)
+    pub fn pii() -> Self {
+        Self::new().with_emails().with_numbers(7)
+    }
+
+    /// Mask the whole value of keys matching `pattern`
+    ///
+    /// Matching ignores case and `*` stands for any run of characters.
+    pub fn with_key(mut self, pattern: impl Into<String>) -> Self {
+        self.keys.push(pattern.into().to_lowercase());
+        self
+    }
+
+    /// Redact email addresses
+    pub fn with_emails(mut self) -> Self {
+        self.emails = true;
+        self
+    }
+
+    /// Redact runs of at least `min_digits` digits, allowing single spaces or
+    /// dashes between them
+    pub fn with_numbers(mut self, min_digits: usize) -> Self {
+        self.min_digits = Some(min_digits.max(1));
+        self
+    }
+
+    /// Redact every occurrence of a literal term
+    pub fn with_term(mut self, term: impl Into<String>) -> Self {
+        let term = term.into();
+        if !term.is_empty() {
+            self.terms.push(term);
+        }
+        self
+    }
+
+    /// Redact every match of a regular expression
+    #[cfg(feature = "redaction")]
+    pub fn with_pattern(mut self, pattern: regex::Regex) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Set the text substituted for redacted values (default: `[REDACTED]`)
+    pub fn with_replacement(mut self, replacement: impl Into<String>) -> Self {
+        self.replacement = replacement.into();
+        self
+    }
+
+    /// Whether a key's whole value is masked
+    pub fn is_sensitive_key(&self, key: &str) -> bool {
+        let key = key.to_lowercase();
+        self.keys.iter().any(|pattern| glob_match(pattern, &key))
+    }
+
+    /// Redact the value stored under `key`
+    pub fn redact_entry(&self, key: &str, value: &Value) -> Value {
+        if self.is_sensitive_key(key) {
+            Value::String(self.replacement.clone())
+        } else {
+            self.redact_value(value)
+        }
+    }
+
+    /// Redact the strings and sensitive fields inside a value
+    pub fn redact_value(&self, value: &Value) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.redact(text)),
+            Value::Array(items) => items.iter().map(|item| self.redact_value(item)).collect(),
+            Value::Object(fields) => fields
+                .iter()
+                .map(|(key, value)| (key.clone(), self.redact_entry(key, value)))
+                .collect(),
+            other => other.clone(),
+        }
+    }
+
+    /// Apply the configured text redactions
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for term in &self.terms {
+            text = text.replace(term.as_str(), &self.replacement);
+        }
+        #[cfg(feature = "redaction")]
+        for pattern in &self.patterns {
+            text = pattern
+                .replace_all(&text, regex::NoExpand(&self.replacement))
+                .into_owned();
+        }
+        let chars: Vec<char> = text.chars().collect();
+        let mut spans = Vec::new();
+        if self.emails {
+            spans.extend(email_spans(&chars));
+        }
+        if let Some(min_digits) = self.min_digits {
+            spans.extend(number_spans(&chars, min_digits));
+        }
+        if spans.is_empty() {
+            return text;
+        }
+
+        spans.sort_unstable();
+        let mut redacted = String::with_capacity(text.len());
+        let mut position = 0;
+        for (start, end) in spans {
+            if start < position {
+                // Overlaps a span already replaced
+                position = position.max(end);
+                continue;
+            }
+            redacted.extend(&chars[position..start]);
+            redacted.push_str(&self.replacement);
+            position = end;
+        }
+        redacted.extend(&chars[position..]);
+        redacted
+    }
+}
+
+/// Match `text` against a pattern where `*` stands for any run of characters
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(first) = parts.next() else {
+        return text.is_empty();
+    };
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Character spans of email addresses
+fn email_spans(chars: &[char]) -> Vec<(usize, usize)> {
+    let is_local = |c: char| c.is_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_alphanumeric() || c == '.' || c == '-';
+    let mut spans = Vec::new();
+    for (at, _) in chars.iter().enumerate().filter(|(_, c)| **c == '@') {
+        let start = (0..at)
+            .rev()
+            .take_while(|&i| is_local(chars[i]))
+            .last()
+            .unwrap_or(at);
+        let mut end = (at + 1..chars.len())
+            .take_while(|&i| is_domain(chars[i]))
+            .last()
+            .map_or(at + 1, |i| i + 1);
+        // Trailing punctuation isn't part of the domain
+        while end > at + 1 && matches!(chars[end - 1], '.' | '-') {
+            end -= 1;
+        }
+        let domain = &chars[at + 1..end];
+        let dot = domain.iter().position(|&c| c == '.');
+        if start < at && dot.is_some_and(|dot| dot > 0 && dot + 1 < domain.len()) {
+            spans.push((start, end));
+        }
+    }
+    spans
+}
+
+/// Character spans of digit runs with at least `min_digits` digits
+fn number_spans(chars: &[char], min_digits: usize) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut end = i;
+        let mut digits = 0;
+        while i < chars.len() {
+            if chars[i].is_ascii_digit() {
+                digits += 1;
+                i += 1;
+                end = i;
+            } else if matches!(chars[i], ' ' | '-')
+                && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())
+            {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        if digits >= min_digits {
+            spans.push((start, end));
+        }
+        i = end.max(start + 1);
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact_entry() {
+        let redactor = Redactor::new()
+            .with_key("password")
+            .with_key("*_key")
+            .with_key("auth*")
+            .with_term("Project X");
+        assert!(redactor.is_sensitive_key("PASSWORD"));
+        assert!(redactor.is_sensitive_key("openai_api_key"));
+        assert!(!redactor.is_sensitive_key("keyring"));
+        assert!(!redactor.is_sensitive_key("passwords"));
+
+        let payload = json!({
+            "headers": {"Authorization": "Bearer sk-1", "accept": "json"},
+            "messages": ["about Project X", 3],
+            "api_key": {"nested": true}
+        });
+        assert_eq!(
+            redactor.redact_entry("request", &payload),
+            json!({
+                "headers": {"Authorization": "[REDACTED]", "accept": "json"},
+                "messages": ["about [REDACTED]", 3],
+                "api_key": "[REDACTED]"
+            })
+        );
+        assert_eq!(
+            redactor.redact_entry("password", &json!(null)),
+            json!("[REDACTED]")
+        );
+    }
+
+    #[cfg(feature = "redaction")]
+    #[test]
+    fn test_redact_pattern() {
+        let redactor = Redactor::new()
+            .with_pattern(regex::Regex::new(r"sk-[A-Za-z0-9]+").unwrap())
+            .with_replacement("$1***");
+        assert_eq!(redactor.redact("key sk-abc123 used"), "key $1*** used");
+    }
+}
@@ -0,0 +1,221 @@
+use super::proto::{
+    EXEC_PATH, ExecRequest, ExecResponse, POST_PATH, PREP_PATH, PostRequest, PostResponse,
+    PrepRequest, PrepResponse,
+};
+use super::{decode, decode_map, encode, encode_map};
+use crate::node::{ExecutionContext, NodeBackend, NodeError};
+use crate::{Action, SharedStore, StorageBackend};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+
+/// Node whose prep, exec and post phases run in a remote service
+///
+/// The store stays local: `prep` sends the values of the input keys, and
+/// `post` applies the writes returned by the server. Retries and deadlines
+/// are handled here, so the server only sees individual calls.
+#[derive(Debug, Clone)]
+pub struct RemoteNode {
+    channel: Channel,
+    node: String,
+    input_keys: Vec<String>,
+    output_key: Option<String>,
+    action: String,
+    max_retries: usize,
+    retry_delay: Duration,
+}
+
+impl RemoteNode {
+    /// Create a node calling `node` on the server behind `channel`
+    pub fn new(channel: Channel, node: impl Into<String>) -> Self {
+        Self {
+            channel,
+            node: node.into(),
+            input_keys: Vec::new(),
+            output_key: None,
+            action: "default".to_string(),
+            max_retries: 1,
+            retry_delay: Duration::from_secs(0),
+        }
+    }
+
+    /// Create a node for a server URL such as `http://gpu-host:50051`
+    ///
+    /// The connection is made on the first call.
+    pub fn connect_lazy(
+        endpoint: impl Into<String>,
+        node: impl Into<String>,
+    ) -> Result<Self, tonic::transport::Error> {
+        let channel = Endpoint::from_shared(endpoint.into())?.connect_lazy();
+        Ok(Self::new(channel, node))
+    }
+
+    /// Set the store keys sent to the server
+    pub fn with_input_keys<I, K>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.input_keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Also store the exec result under this key
+    pub fn with_output_key(mut self, key: impl Into<String>) -> Self {
+        self.output_key = Some(key.into());
+        self
+    }
+
+    /// Set the action returned when the server does not choose one
+    pub fn with_action(mut self, action: impl Into<String>) -> Self {
+        self.action = action.into();
+        self
+    }
+
+    /// Set maximum retries
+    pub fn with_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set retry delay
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    async fn call<Req, Resp>(
+        &self,
+        path: &'static str,
+        message: Req,
+        context: &ExecutionContext,
+    ) -> Result<Resp, NodeError>
+    where
+        Req: prost::Message + Send + Sync + 'static,
+        Resp: prost::Message + Default + Send + Sync + 'static,
+    {
+        let mut grpc = tonic::client::Grpc::new(self.channel.clone());
+        grpc.ready()
+            .await
+            .map_err(|e| NodeError::retryable(format!("Remote node unavailable: {e}")))?;
+
+        let mut request = tonic::Request::new(message);
+        if let Some(remaining) = context.remaining() {
+            request.set_timeout(remaining);
+        }
+        let codec = tonic::codec::ProstCodec::default();
+        grpc.unary(request, PathAndQuery::from_static(path), codec)
+            .await
+            .map(tonic::Response::into_inner)
+            .map_err(|status| status_to_error(&self.node, status))
+    }
+}
+
+/// Convert a failed call into a node error, keeping retryability
+fn status_to_error(node: &str, status: Status) -> NodeError {
+    let message = format!("Remote node '{node}': {}", status.message());
+    match status.code() {
+        Code::InvalidArgument => NodeError::ValidationError(message),
+        Code::PermissionDenied => NodeError::PermissionDenied(message),
+        Code::NotFound
+        | Code::AlreadyExists
+        | Code::FailedPrecondition
+        | Code::OutOfRange
+        | Code::Unimplemented
+        | Code::Unauthenticated => NodeError::fatal(message),
+        _ => NodeError::retryable(message),
+    }
+}
+
+#[async_trait]
+impl<S: StorageBackend + Send + Sync> NodeBackend<S> for RemoteNode {
+    type PrepResult = (HashMap<String, Value>, Value);
+    type ExecResult = Value;
+    type Error = NodeError;
+
+    async fn prep(
+        &mut self,
+        store: &SharedStore<S>,
+        context: &ExecutionContext,
+    ) -> Result<Self::PrepResult, Self::Error> {
+        let mut inputs = HashMap::new();
+        for key in &self.input_keys {
+            if let Some(value) = store
+                .get(key)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?
+            {
+                inputs.insert(key.clone(), value);
+            }
+        }
+
+        let request = PrepRequest {
+            node: self.node.clone(),
+            inputs: encode_map(&inputs)?,
+            params: encode(&context.params)?,
+        };
+        let response: PrepResponse = self.call(PREP_PATH, request, context).await?;
+        Ok((inputs, decode(&response.prep_result)?))
+    }
+
+    async fn exec(
+        &mut self,
+        (_, prep_result): Self::PrepResult,
+        context: &ExecutionContext,
+    ) -> Result<Self::ExecResult, Self::Error> {
+        let request = ExecRequest {
+            node: self.node.clone(),
+            prep_result: encode(&prep_result)?,
+            attempt: context.current_retry as u32,
+        };
+        let response: ExecResponse = self.call(EXEC_PATH, request, context).await?;
+        Ok(decode(&response.exec_result)?)
+    }
+
+    async fn post(
+        &mut self,
+        store: &mut SharedStore<S>,
+        (inputs, prep_result): Self::PrepResult,
+        exec_result: Self::ExecResult,
+        context: &ExecutionContext,
+    ) -> Result<Action, Self::Error> {
+        let request = PostRequest {
+            node: self.node.clone(),
+            inputs: encode_map(&inputs)?,
+            prep_result: encode(&prep_result)?,
+            exec_result: encode(&exec_result)?,
+        };
+        let response: PostResponse = self.call(POST_PATH, request, context).await?;
+
+        let mut writes: Vec<(String, Value)> = decode_map(&response.writes)?.into_iter().collect();
+        if let Some(key) = &self.output_key {
+            writes.push((key.clone(), exec_result));
+        }
+        for (key, value) in writes {
+            store
+                .set(key, value)
+                .map_err(|e| NodeError::StorageError(e.to_string()))?;
+        }
+
+        if response.action.is_empty() {
+            Ok(Action::simple(self.action.clone()))
+        } else {
+            Ok(Action::simple(response.action))
+        }
+    }
+
+    fn is_side_effecting(&self) -> bool {
+        true
+    }
+
+    fn max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    fn retry_delay(&self) -> Duration {
+        self.retry_delay
+    }
+}
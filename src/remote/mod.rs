@@ -0,0 +1,144 @@
+//! Remote node execution over gRPC (feature `grpc`)
+//!
+//! Heavy nodes, such as GPU inference or large ML models, can run in their
+//! own services while the flow engine orchestrates them. The protocol in
+//! `proto/remote_node.proto` mirrors the node contract with one call per
+//! phase; values travel as JSON.
+//!
+//! - [`RemoteNode`] is a [`NodeBackend`](crate::node::NodeBackend) forwarding
+//!   each phase to a server. The store never leaves the engine: the client
+//!   sends the input keys it is configured with and applies the writes the
+//!   server returns.
+//! - [`RemoteNodeServer`] hosts [`RemoteNodeHandler`]s by name.
+//!
+//! ```rust,no_run
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::BasicFlow;
+//! # use pocketflow_rs::remote::RemoteNode;
+//! # fn build() -> Result<(), tonic::transport::Error> {
+//! let classify = RemoteNode::connect_lazy("http://gpu-host:50051", "classify")?
+//!     .with_input_keys(["image_url"])
+//!     .with_output_key("label")
+//!     .with_retries(3);
+//! let flow: BasicFlow<InMemoryStorage> = FlowBuilder::new()
+//!     .start_node("classify")
+//!     .node("classify", Node::new(classify))
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+pub mod proto;
+mod server;
+
+pub use client::RemoteNode;
+pub use server::{PostOutcome, RemoteNodeHandler, RemoteNodeServer};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Error raised when a value cannot cross the wire as JSON
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid JSON payload: {0}")]
+struct PayloadError(#[from] serde_json::Error);
+
+impl From<PayloadError> for crate::node::NodeError {
+    fn from(error: PayloadError) -> Self {
+        crate::node::NodeError::ValidationError(error.to_string())
+    }
+}
+
+impl From<PayloadError> for tonic::Status {
+    fn from(error: PayloadError) -> Self {
+        tonic::Status::invalid_argument(error.to_string())
+    }
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<String, PayloadError> {
+    Ok(serde_json::to_string(value)?)
+}
+
+fn decode<T: DeserializeOwned>(text: &str) -> Result<T, PayloadError> {
+    Ok(serde_json::from_str(text)?)
+}
+
+fn encode_map(values: &HashMap<String, Value>) -> Result<HashMap<String, String>, PayloadError> {
+    values
+        .iter()
+        .map(|(key, value)| Ok((key.clone(), encode(value)?)))
+        .collect()
+}
+
+fn decode_map(values: &HashMap<String, String>) -> Result<HashMap<String, Value>, PayloadError> {
+    values
+        .iter()
+        .map(|(key, text)| Ok((key.clone(), decode(text)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{Node, NodeError};
+    use crate::{Action, SharedStore};
+    use serde_json::json;
+
+    struct Double;
+
+    #[async_trait::async_trait]
+    impl RemoteNodeHandler for Double {
+        async fn exec(&self, prep_result: Value, _attempt: u32) -> Result<Value, NodeError> {
+            let x = prep_result["x"]
+                .as_i64()
+                .ok_or_else(|| NodeError::ValidationError("x must be an integer".into()))?;
+            Ok(json!(x * 2))
+        }
+
+        async fn post(
+            &self,
+            _inputs: &HashMap<String, Value>,
+            _prep_result: Value,
+            exec_result: Value,
+        ) -> Result<PostOutcome, NodeError> {
+            Ok(PostOutcome::new()
+                .with_write("doubled", exec_result)
+                .with_action("done"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_node_round_trip() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = RemoteNodeServer::new().register("double", Double);
+        tokio::spawn(server.serve_with_listener(listener));
+
+        let endpoint = format!("http://{addr}");
+        let mut node = Node::new(
+            RemoteNode::connect_lazy(endpoint.clone(), "double")
+                .unwrap()
+                .with_input_keys(["x"])
+                .with_output_key("result")
+                .with_retries(3),
+        );
+        let mut store = SharedStore::new();
+        store.set("x".to_string(), json!(21)).unwrap();
+
+        let action = node.run(&mut store).await.unwrap();
+        assert_eq!(action, Action::simple("done"));
+        assert_eq!(store.get("doubled").unwrap(), Some(json!(42)));
+        assert_eq!(store.get("result").unwrap(), Some(json!(42)));
+
+        // Bad input is rejected without retrying
+        store.set("x".to_string(), json!("twenty")).unwrap();
+        assert!(node.run(&mut store).await.is_err());
+        assert_eq!(node.last_run_stats().unwrap().attempts, 1);
+
+        // Unknown nodes fail fatally
+        let mut missing = Node::new(RemoteNode::connect_lazy(endpoint, "missing").unwrap());
+        assert!(missing.run(&mut store).await.is_err());
+    }
+}
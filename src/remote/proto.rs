@@ -0,0 +1,65 @@
+//! Messages of `proto/remote_node.proto`, written out by hand so the crate
+//! builds without `protoc`
+
+use std::collections::HashMap;
+
+/// Fully qualified name of the gRPC service
+pub const SERVICE_NAME: &str = "pocketflow.remote.v1.RemoteNodeService";
+/// Path of the `Prep` method
+pub const PREP_PATH: &str = "/pocketflow.remote.v1.RemoteNodeService/Prep";
+/// Path of the `Exec` method
+pub const EXEC_PATH: &str = "/pocketflow.remote.v1.RemoteNodeService/Exec";
+/// Path of the `Post` method
+pub const POST_PATH: &str = "/pocketflow.remote.v1.RemoteNodeService/Post";
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PrepRequest {
+    #[prost(string, tag = "1")]
+    pub node: String,
+    #[prost(map = "string, string", tag = "2")]
+    pub inputs: HashMap<String, String>,
+    #[prost(string, tag = "3")]
+    pub params: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PrepResponse {
+    #[prost(string, tag = "1")]
+    pub prep_result: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExecRequest {
+    #[prost(string, tag = "1")]
+    pub node: String,
+    #[prost(string, tag = "2")]
+    pub prep_result: String,
+    #[prost(uint32, tag = "3")]
+    pub attempt: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExecResponse {
+    #[prost(string, tag = "1")]
+    pub exec_result: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PostRequest {
+    #[prost(string, tag = "1")]
+    pub node: String,
+    #[prost(map = "string, string", tag = "2")]
+    pub inputs: HashMap<String, String>,
+    #[prost(string, tag = "3")]
+    pub prep_result: String,
+    #[prost(string, tag = "4")]
+    pub exec_result: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PostResponse {
+    #[prost(map = "string, string", tag = "1")]
+    pub writes: HashMap<String, String>,
+    #[prost(string, tag = "2")]
+    pub action: String,
+}
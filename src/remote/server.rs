@@ -0,0 +1,286 @@
+use super::proto::{
+    EXEC_PATH, ExecRequest, ExecResponse, POST_PATH, PREP_PATH, PostRequest, PostResponse,
+    PrepRequest, PrepResponse, SERVICE_NAME,
+};
+use super::{decode, decode_map, encode, encode_map};
+use crate::node::NodeError;
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::Status;
+use tonic::body::Body;
+use tonic::codegen::{Body as HttpBody, BoxFuture, Context, Poll, Service, StdError, http};
+use tonic::server::NamedService;
+use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
+
+/// Server-side implementation of a remote node
+///
+/// Mirrors [`NodeBackend`](crate::node::NodeBackend), but without direct store
+/// access: `prep` receives the input values sent by the client and `post`
+/// returns the writes to apply.
+#[async_trait]
+pub trait RemoteNodeHandler: Send + Sync {
+    /// Prepare the exec input; by default, the inputs as a JSON object
+    async fn prep(
+        &self,
+        inputs: &HashMap<String, Value>,
+        _params: &HashMap<String, Value>,
+    ) -> Result<Value, NodeError> {
+        Ok(Value::Object(
+            inputs.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        ))
+    }
+
+    /// Perform the main computation; `attempt` is the 0-based retry attempt
+    async fn exec(&self, prep_result: Value, attempt: u32) -> Result<Value, NodeError>;
+
+    /// Choose the store writes and next action; by default, neither
+    async fn post(
+        &self,
+        _inputs: &HashMap<String, Value>,
+        _prep_result: Value,
+        _exec_result: Value,
+    ) -> Result<PostOutcome, NodeError> {
+        Ok(PostOutcome::new())
+    }
+}
+
+/// Store writes and next action returned by [`RemoteNodeHandler::post`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PostOutcome {
+    pub writes: HashMap<String, Value>,
+    /// Next action; `None` leaves the choice to the client
+    pub action: Option<String>,
+}
+
+impl PostOutcome {
+    /// Create an outcome without writes or action
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write a value to the client's store
+    pub fn with_write(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.writes.insert(key.into(), value);
+        self
+    }
+
+    /// Set the next action
+    pub fn with_action(mut self, action: impl Into<String>) -> Self {
+        self.action = Some(action.into());
+        self
+    }
+}
+
+/// gRPC service hosting named [`RemoteNodeHandler`]s
+///
+/// ```rust,no_run
+/// # use pocketflow_rs::node::NodeError;
+/// # use pocketflow_rs::remote::{RemoteNodeHandler, RemoteNodeServer};
+/// # use serde_json::Value;
+/// struct Embed;
+///
+/// #[async_trait::async_trait]
+/// impl RemoteNodeHandler for Embed {
+///     async fn exec(&self, input: Value, _attempt: u32) -> Result<Value, NodeError> {
+///         Ok(input)
+///     }
+/// }
+///
+/// # async fn run() -> Result<(), tonic::transport::Error> {
+/// RemoteNodeServer::new()
+///     .register("embed", Embed)
+///     .serve("0.0.0.0:50051".parse().unwrap())
+///     .await
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct RemoteNodeServer {
+    handlers: Arc<HashMap<String, Arc<dyn RemoteNodeHandler>>>,
+}
+
+impl std::fmt::Debug for RemoteNodeServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut nodes: Vec<&String> = self.handlers.keys().collect();
+        nodes.sort();
+        f.debug_struct("RemoteNodeServer")
+            .field("nodes", &nodes)
+            .finish()
+    }
+}
+
+impl RemoteNodeServer {
+    /// Create a server without handlers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Host a handler under a node name, replacing any existing one
+    pub fn register(
+        mut self,
+        node: impl Into<String>,
+        handler: impl RemoteNodeHandler + 'static,
+    ) -> Self {
+        Arc::make_mut(&mut self.handlers).insert(node.into(), Arc::new(handler));
+        self
+    }
+
+    /// Serve on an address until the server fails
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+        Server::builder().add_service(self).serve(addr).await
+    }
+
+    /// Serve on an already bound listener until the server fails
+    pub async fn serve_with_listener(
+        self,
+        listener: tokio::net::TcpListener,
+    ) -> Result<(), tonic::transport::Error> {
+        Server::builder()
+            .add_service(self)
+            .serve_with_incoming(TcpIncoming::from(listener))
+            .await
+    }
+
+    fn handler(&self, node: &str) -> Option<Arc<dyn RemoteNodeHandler>> {
+        self.handlers.get(node).cloned()
+    }
+
+    async fn prep(&self, request: PrepRequest) -> Result<PrepResponse, Status> {
+        let handler = self
+            .handler(&request.node)
+            .ok_or_else(|| unknown_node(&request.node))?;
+        let inputs = decode_map(&request.inputs)?;
+        let params = if request.params.is_empty() {
+            HashMap::new()
+        } else {
+            let params: Map<String, Value> = decode(&request.params)?;
+            params.into_iter().collect()
+        };
+        let prep_result = handler.prep(&inputs, &params).await?;
+        Ok(PrepResponse {
+            prep_result: encode(&prep_result)?,
+        })
+    }
+
+    async fn exec(&self, request: ExecRequest) -> Result<ExecResponse, Status> {
+        let handler = self
+            .handler(&request.node)
+            .ok_or_else(|| unknown_node(&request.node))?;
+        let exec_result = handler
+            .exec(decode(&request.prep_result)?, request.attempt)
+            .await?;
+        Ok(ExecResponse {
+            exec_result: encode(&exec_result)?,
+        })
+    }
+
+    async fn post(&self, request: PostRequest) -> Result<PostResponse, Status> {
+        let handler = self
+            .handler(&request.node)
+            .ok_or_else(|| unknown_node(&request.node))?;
+        let outcome = handler
+            .post(
+                &decode_map(&request.inputs)?,
+                decode(&request.prep_result)?,
+                decode(&request.exec_result)?,
+            )
+            .await?;
+        Ok(PostResponse {
+            writes: encode_map(&outcome.writes)?,
+            action: outcome.action.unwrap_or_default(),
+        })
+    }
+}
+
+fn unknown_node(node: &str) -> Status {
+    Status::not_found(format!("Unknown node '{node}'"))
+}
+
+impl From<NodeError> for Status {
+    fn from(error: NodeError) -> Self {
+        match error {
+            NodeError::ValidationError(_) => Status::invalid_argument(error.to_string()),
+            NodeError::Fatal(_) => Status::failed_precondition(error.to_string()),
+            NodeError::PermissionDenied(_) => Status::permission_denied(error.to_string()),
+            _ => Status::internal(error.to_string()),
+        }
+    }
+}
+
+/// Adapts an async function to the unary service expected by tonic
+struct UnaryFn<F>(Option<F>);
+
+impl<F, Req, Resp, Fut> Service<tonic::Request<Req>> for UnaryFn<F>
+where
+    F: FnOnce(Req) -> Fut,
+    Fut: Future<Output = Result<Resp, Status>> + Send + 'static,
+{
+    type Response = tonic::Response<Resp>;
+    type Error = Status;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: tonic::Request<Req>) -> Self::Future {
+        match self.0.take() {
+            Some(handle) => {
+                let response = handle(request.into_inner());
+                Box::pin(async move { response.await.map(tonic::Response::new) })
+            }
+            None => Box::pin(async { Err(Status::internal("Unary handler called twice")) }),
+        }
+    }
+}
+
+fn unary<B, Req, Resp, F, Fut>(
+    request: http::Request<B>,
+    handle: F,
+) -> BoxFuture<http::Response<Body>, Infallible>
+where
+    B: HttpBody + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+    Req: prost::Message + Default + Send + 'static,
+    Resp: prost::Message + Send + 'static,
+    F: FnOnce(Req) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Resp, Status>> + Send + 'static,
+{
+    Box::pin(async move {
+        let mut grpc = tonic::server::Grpc::new(tonic::codec::ProstCodec::<Resp, Req>::default());
+        Ok(grpc.unary(UnaryFn(Some(handle)), request).await)
+    })
+}
+
+impl<B> Service<http::Request<B>> for RemoteNodeServer
+where
+    B: HttpBody + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let server = self.clone();
+        match request.uri().path() {
+            PREP_PATH => unary(request, move |req| async move { server.prep(req).await }),
+            EXEC_PATH => unary(request, move |req| async move { server.exec(req).await }),
+            POST_PATH => unary(request, move |req| async move { server.post(req).await }),
+            _ => Box::pin(async { Ok(Status::unimplemented("Unknown method").into_http()) }),
+        }
+    }
+}
+
+impl NamedService for RemoteNodeServer {
+    const NAME: &'static str = SERVICE_NAME;
+}
@@ -0,0 +1,396 @@
+//! Runtime facade with graceful shutdown
+//!
+//! [`PocketFlowRuntime`] owns the long-running parts of an application: flows
+//! started in the background, supervised agents, work-queue workers (feature
+//! `work-queue`) and HTTP servers (feature `server`). A single call to
+//! [`PocketFlowRuntime::shutdown`] stops accepting new work, signals every
+//! service to wind down, waits up to a grace period for in-flight work, and
+//! aborts whatever is still running.
+//!
+//! Work-queue workers finish their current job before stopping; a job that is
+//! aborted at the end of the grace period keeps its lease and is picked up
+//! again by another worker once the lease expires.
+//!
+//! ```rust
+//! # use pocketflow_rs::runtime::PocketFlowRuntime;
+//! # use std::time::Duration;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let runtime = PocketFlowRuntime::new();
+//! let signal = runtime.shutdown_signal();
+//! runtime
+//!     .spawn("ticker", async move {
+//!         while !signal.is_triggered() {
+//!             tokio::time::sleep(Duration::from_millis(5)).await;
+//!         }
+//!     })
+//!     .unwrap();
+//!
+//! let report = runtime.shutdown(Duration::from_secs(1)).await;
+//! assert_eq!(report.finished, vec!["ticker".to_string()]);
+//! assert!(report.aborted.is_empty());
+//! # }
+//! ```
+
+use crate::flow::{BasicFlow, Flow, FlowError, FlowExecutionResult};
+use crate::shared_store::SharedStore;
+use crate::storage::StorageBackend;
+use crate::supervisor::{AgentHandle, AgentSpec, FlowSupervisor, SupervisorError};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::{oneshot, watch};
+use tokio::task::{Id, JoinSet};
+
+/// Errors from runtime operations
+#[derive(Debug, thiserror::Error)]
+pub enum RuntimeError {
+    #[error("Runtime is shutting down")]
+    ShuttingDown,
+    #[error(transparent)]
+    Supervisor(#[from] SupervisorError),
+}
+
+/// Outcome of a flow started with [`PocketFlowRuntime::spawn_flow`]
+#[derive(Debug)]
+pub struct FlowRun<S: StorageBackend> {
+    /// Result of the flow execution
+    pub result: Result<FlowExecutionResult, FlowError>,
+    /// The store after execution
+    pub store: SharedStore<S>,
+}
+
+/// What happened to each task during [`PocketFlowRuntime::shutdown`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Tasks running at shutdown that finished within the grace period
+    pub finished: Vec<String>,
+    /// Tasks that panicked before finishing
+    pub panicked: Vec<String>,
+    /// Tasks that were still running when the grace period ran out
+    pub aborted: Vec<String>,
+}
+
+/// Resolves once the runtime starts shutting down
+#[derive(Debug, Clone)]
+pub struct ShutdownSignal {
+    receiver: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+    /// Whether shutdown has started
+    pub fn is_triggered(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Wait until shutdown starts
+    pub async fn wait(&self) {
+        let mut receiver = self.receiver.clone();
+        // An error means the runtime is gone, which counts as shutting down
+        let _ = receiver.wait_for(|triggered| *triggered).await;
+    }
+}
+
+#[derive(Default)]
+struct RuntimeState {
+    accepting: bool,
+    tasks: JoinSet<()>,
+    names: HashMap<Id, String>,
+}
+
+/// Owns background flows, agents, workers and servers
+pub struct PocketFlowRuntime {
+    state: Mutex<RuntimeState>,
+    shutdown: watch::Sender<bool>,
+    supervisor: FlowSupervisor,
+}
+
+impl std::fmt::Debug for PocketFlowRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PocketFlowRuntime")
+            .field("accepting", &self.is_accepting())
+            .field("tasks", &self.task_names())
+            .field("supervisor", &self.supervisor)
+            .finish()
+    }
+}
+
+impl Default for PocketFlowRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PocketFlowRuntime {
+    /// Create a runtime that accepts work
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(RuntimeState {
+                accepting: true,
+                ..RuntimeState::default()
+            }),
+            shutdown: watch::channel(false).0,
+            supervisor: FlowSupervisor::new(),
+        }
+    }
+
+    /// Get a signal that resolves when shutdown starts
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        ShutdownSignal {
+            receiver: self.shutdown.subscribe(),
+        }
+    }
+
+    /// Whether new work is still accepted
+    pub fn is_accepting(&self) -> bool {
+        self.lock().accepting
+    }
+
+    /// Names of the tasks currently owned by the runtime
+    pub fn task_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.lock().names.values().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Get the supervisor running this runtime's agents
+    pub fn supervisor(&self) -> &FlowSupervisor {
+        &self.supervisor
+    }
+
+    /// Run a task until it completes or the runtime shuts down
+    ///
+    /// Long-running tasks should watch [`PocketFlowRuntime::shutdown_signal`]
+    /// and return once it fires; otherwise they are aborted after the grace period.
+    pub fn spawn<F>(&self, name: impl Into<String>, task: F) -> Result<(), RuntimeError>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut state = self.lock();
+        if !state.accepting {
+            return Err(RuntimeError::ShuttingDown);
+        }
+        // Forget tasks that already finished so the set doesn't grow unbounded
+        while let Some(joined) = state.tasks.try_join_next_with_id() {
+            let id = match joined {
+                Ok((id, ())) => id,
+                Err(e) => e.id(),
+            };
+            state.names.remove(&id);
+        }
+        let id = state.tasks.spawn(task).id();
+        state.names.insert(id, name.into());
+        Ok(())
+    }
+
+    /// Execute a flow in the background
+    ///
+    /// The returned receiver yields the result and the final store. It is never
+    /// resolved if the run is aborted at shutdown.
+    pub fn spawn_flow<S>(
+        &self,
+        name: impl Into<String>,
+        mut flow: BasicFlow<S>,
+        mut store: SharedStore<S>,
+    ) -> Result<oneshot::Receiver<FlowRun<S>>, RuntimeError>
+    where
+        S: StorageBackend + Send + Sync + 'static,
+        S::Error: Send + Sync + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        self.spawn(name, async move {
+            let result = flow.execute(&mut store).await;
+            let _ = sender.send(FlowRun { result, store });
+        })?;
+        Ok(receiver)
+    }
+
+    /// Start a supervised agent
+    pub fn spawn_agent(&self, spec: AgentSpec) -> Result<AgentHandle, RuntimeError> {
+        // Hold the lock so shutdown can't start between the check and the spawn
+        let state = self.lock();
+        if !state.accepting {
+            return Err(RuntimeError::ShuttingDown);
+        }
+        Ok(self.supervisor.spawn(spec)?)
+    }
+
+    /// Run a work-queue worker until shutdown, letting its current job finish
+    #[cfg(feature = "work-queue")]
+    pub fn spawn_worker<Q>(
+        &self,
+        name: impl Into<String>,
+        worker: crate::queue::FlowWorker<Q>,
+    ) -> Result<(), RuntimeError>
+    where
+        Q: crate::queue::JobQueue + 'static,
+    {
+        let signal = self.shutdown_signal();
+        self.spawn(name, async move {
+            let _ = worker.run_until(signal.wait()).await;
+        })
+    }
+
+    /// Serve a router until shutdown, letting open requests complete
+    #[cfg(feature = "server")]
+    pub fn serve(
+        &self,
+        name: impl Into<String>,
+        listener: tokio::net::TcpListener,
+        router: axum::Router,
+    ) -> Result<(), RuntimeError> {
+        let signal = self.shutdown_signal();
+        self.spawn(name, async move {
+            let _ = axum::serve(listener, router)
+                .with_graceful_shutdown(async move { signal.wait().await })
+                .await;
+        })
+    }
+
+    /// Stop accepting work, wait up to `grace_period` for running tasks and
+    /// agents, then abort the rest
+    pub async fn shutdown(&self, grace_period: Duration) -> ShutdownReport {
+        let (mut tasks, mut names) = {
+            let mut state = self.lock();
+            state.accepting = false;
+            (
+                std::mem::take(&mut state.tasks),
+                std::mem::take(&mut state.names),
+            )
+        };
+        self.shutdown.send_replace(true);
+
+        // Agents get the same grace period as tasks
+        for agent in self.supervisor.names() {
+            if let Some(handle) = self.supervisor.handle(&agent) {
+                let id = tasks
+                    .spawn(async move {
+                        handle.wait().await;
+                    })
+                    .id();
+                names.insert(id, agent);
+            }
+        }
+
+        let mut report = ShutdownReport::default();
+        let deadline = tokio::time::Instant::now() + grace_period;
+        loop {
+            match tokio::time::timeout_at(deadline, tasks.join_next_with_id()).await {
+                Ok(Some(joined)) => record(&mut report, &mut names, joined),
+                Ok(None) => break,
+                Err(_) => {
+                    tasks.abort_all();
+                    while let Some(joined) = tasks.join_next_with_id().await {
+                        record(&mut report, &mut names, joined);
+                    }
+                    break;
+                }
+            }
+        }
+        self.supervisor.shutdown();
+
+        report.finished.sort();
+        report.panicked.sort();
+        report.aborted.sort();
+        report
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RuntimeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn record(
+    report: &mut ShutdownReport,
+    names: &mut HashMap<Id, String>,
+    joined: Result<(Id, ()), tokio::task::JoinError>,
+) {
+    let (id, bucket) = match joined {
+        Ok((id, ())) => (id, &mut report.finished),
+        Err(e) if e.is_cancelled() => (e.id(), &mut report.aborted),
+        Err(e) => (e.id(), &mut report.panicked),
+    };
+    if let Some(name) = names.remove(&id) {
+        bucket.push(name);
+    }
+}
+
+#[cfg(all(test, feature = "builtin-nodes"))]
+mod tests {
+    use super::*;
+    use crate::Action;
+    use crate::flow::FlowBuilder;
+    use crate::node::Node;
+    use crate::node::builtin::{DelayNode, SetValueNode};
+    use crate::storage::InMemoryStorage;
+    use crate::supervisor::AgentStatus;
+    use serde_json::json;
+
+    fn sleeper() -> BasicFlow<InMemoryStorage> {
+        FlowBuilder::new()
+            .start_node("sleep")
+            .node(
+                "sleep",
+                Node::new(DelayNode::new(
+                    Duration::from_secs(3600),
+                    Action::simple("complete"),
+                )),
+            )
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_then_aborts() {
+        let runtime = PocketFlowRuntime::new();
+
+        let flow = FlowBuilder::new()
+            .start_node("set")
+            .node(
+                "set",
+                Node::new(SetValueNode::new(
+                    "answer",
+                    json!(42),
+                    Action::simple("complete"),
+                )),
+            )
+            .build();
+        let run = runtime
+            .spawn_flow("quick", flow, SharedStore::new())
+            .unwrap()
+            .await
+            .unwrap();
+        assert!(run.result.is_ok());
+        assert_eq!(run.store.get("answer").unwrap(), Some(json!(42)));
+
+        let signal = runtime.shutdown_signal();
+        runtime
+            .spawn("graceful", async move {
+                signal.wait().await;
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            })
+            .unwrap();
+        runtime
+            .spawn_flow("stuck", sleeper(), SharedStore::new())
+            .unwrap();
+        let agent = runtime
+            .spawn_agent(AgentSpec::new("agent", sleeper))
+            .unwrap();
+
+        let report = runtime.shutdown(Duration::from_millis(50)).await;
+        assert_eq!(report.finished, vec!["graceful"]);
+        assert_eq!(report.aborted, vec!["agent", "stuck"]);
+        assert!(report.panicked.is_empty());
+        assert_eq!(agent.status(), AgentStatus::Stopped);
+
+        assert!(!runtime.is_accepting());
+        assert!(matches!(
+            runtime.spawn("late", async {}),
+            Err(RuntimeError::ShuttingDown)
+        ));
+        assert!(matches!(
+            runtime.spawn_agent(AgentSpec::new("late", sleeper)),
+            Err(RuntimeError::ShuttingDown)
+        ));
+    }
+}
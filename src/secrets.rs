@@ -0,0 +1,228 @@
+//! Credentials resolved on use
+//!
+//! A [`Secret`] wraps a [`SecretProvider`] and is resolved each time a request
+//! needs it, so keys rotated in the environment or on disk are picked up
+//! without rebuilding nodes. Its `Debug` output names the source
+//! (`Secret(env:OPENAI_API_KEY)`) and never the value.
+//!
+//! ```rust
+//! # use pocketflow_rs::secrets::Secret;
+//! let key = Secret::new("sk-live-123");
+//! assert_eq!(format!("{:?}", key), "Secret(<redacted>)");
+//! assert_eq!(key.expose().unwrap(), "sk-live-123");
+//!
+//! let from_env = Secret::env("SOME_UNSET_VARIABLE");
+//! assert_eq!(format!("{:?}", from_env), "Secret(env:SOME_UNSET_VARIABLE)");
+//! assert!(from_env.expose().is_err());
+//! ```
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Errors raised while resolving a secret
+#[derive(Debug, thiserror::Error)]
+pub enum SecretError {
+    #[error("Secret not set: {0}")]
+    NotSet(String),
+    #[error("Failed to read secret from {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Secret unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// A source of a secret value
+pub trait SecretProvider: Send + Sync {
+    /// Resolve the current value
+    fn resolve(&self) -> Result<String, SecretError>;
+
+    /// Where the value comes from, shown instead of it in `Debug` output
+    fn source(&self) -> String {
+        "<redacted>".to_string()
+    }
+}
+
+/// A value held in memory
+pub struct StaticSecret(String);
+
+impl SecretProvider for StaticSecret {
+    fn resolve(&self) -> Result<String, SecretError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// The first set, non-empty environment variable of a list
+pub struct EnvSecret {
+    vars: Vec<String>,
+}
+
+impl SecretProvider for EnvSecret {
+    fn resolve(&self) -> Result<String, SecretError> {
+        self.vars
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|value| !value.is_empty()))
+            .ok_or_else(|| SecretError::NotSet(self.source()))
+    }
+
+    fn source(&self) -> String {
+        format!("env:{}", self.vars.join("|"))
+    }
+}
+
+/// The trimmed contents of a file, re-read on every resolution
+pub struct FileSecret {
+    path: PathBuf,
+}
+
+impl SecretProvider for FileSecret {
+    fn resolve(&self) -> Result<String, SecretError> {
+        let contents = std::fs::read_to_string(&self.path).map_err(|source| SecretError::Io {
+            path: self.path.clone(),
+            source,
+        })?;
+        let value = contents.trim();
+        if value.is_empty() {
+            return Err(SecretError::NotSet(self.source()));
+        }
+        Ok(value.to_string())
+    }
+
+    fn source(&self) -> String {
+        format!("file:{}", self.path.display())
+    }
+}
+
+struct FnSecret<F> {
+    name: String,
+    resolve: F,
+}
+
+impl<F> SecretProvider for FnSecret<F>
+where
+    F: Fn() -> Result<String, SecretError> + Send + Sync,
+{
+    fn resolve(&self) -> Result<String, SecretError> {
+        (self.resolve)()
+    }
+
+    fn source(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// A credential resolved from its provider on use
+///
+/// Cloning shares the provider.
+#[derive(Clone)]
+pub struct Secret(Arc<dyn SecretProvider>);
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Secret({})", self.0.source())
+    }
+}
+
+impl Secret {
+    /// A fixed value held in memory
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(Arc::new(StaticSecret(value.into())))
+    }
+
+    /// Read from an environment variable on each use
+    pub fn env(var: impl Into<String>) -> Self {
+        Self::env_any([var.into()])
+    }
+
+    /// Read from the first set of several environment variables on each use
+    pub fn env_any<I, V>(vars: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<String>,
+    {
+        Self(Arc::new(EnvSecret {
+            vars: vars.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    /// Read from a file on each use, e.g. a mounted Kubernetes secret
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self(Arc::new(FileSecret { path: path.into() }))
+    }
+
+    /// Resolve with a closure; `name` is shown in `Debug` output
+    pub fn from_fn<F>(name: impl Into<String>, resolve: F) -> Self
+    where
+        F: Fn() -> Result<String, SecretError> + Send + Sync + 'static,
+    {
+        Self(Arc::new(FnSecret {
+            name: name.into(),
+            resolve,
+        }))
+    }
+
+    /// Use a custom provider, such as a vault client
+    pub fn from_provider(provider: Arc<dyn SecretProvider>) -> Self {
+        Self(provider)
+    }
+
+    /// Resolve the current value
+    pub fn expose(&self) -> Result<String, SecretError> {
+        self.0.resolve()
+    }
+
+    /// Where the value comes from
+    pub fn source(&self) -> String {
+        self.0.source()
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_file_secret_picks_up_rotation() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("api_key");
+        let secret = Secret::file(&path);
+        assert!(matches!(secret.expose(), Err(SecretError::Io { .. })));
+
+        std::fs::write(&path, "first\n").unwrap();
+        assert_eq!(secret.expose().unwrap(), "first");
+        std::fs::write(&path, "second").unwrap();
+        assert_eq!(secret.clone().expose().unwrap(), "second");
+        assert_eq!(
+            format!("{:?}", secret),
+            format!("Secret(file:{})", path.display())
+        );
+    }
+
+    #[test]
+    fn test_custom_secret() {
+        let secret = Secret::from_fn("vault:openai", || Ok("from-vault".to_string()));
+        assert_eq!(secret.expose().unwrap(), "from-vault");
+        assert_eq!(format!("{:?}", secret), "Secret(vault:openai)");
+        assert!(
+            Secret::env_any(["POCKETFLOW_TEST_UNSET_A", "POCKETFLOW_TEST_UNSET_B"])
+                .expose()
+                .unwrap_err()
+                .to_string()
+                .contains("POCKETFLOW_TEST_UNSET_A|POCKETFLOW_TEST_UNSET_B")
+        );
+    }
+}
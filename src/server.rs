@@ -0,0 +1,539 @@
+//! HTTP endpoints for flows (feature `server`)
+//!
+//! [`serve_flow`] turns a flow factory into an axum [`Router`] with a single POST
+//! route. Each request gets a fresh flow and shared store:
+//!
+//! - A JSON object body is written into the store key by key; any other JSON
+//!   value is stored under `"input"`. Bodies setting reserved `__pf::` keys are
+//!   rejected with 400.
+//! - The flow runs to completion and the configured output keys (or the whole
+//!   store, minus the reserved keys the engine wrote) are returned as a JSON
+//!   object.
+//! - Requests sent with `Accept: text/event-stream` get server-sent events
+//!   instead: the flow's [`FlowEvent`]s as they happen (`node_started`,
+//!   `node_completed`, ...), a `token` event for each chunk the flow's nodes send
+//!   to their [`TokenSender`], and a final `result` or `error` event.
+//! - A WebSocket connection to the same path (`GET`) sends the input as its
+//!   first text message and receives the same events as JSON text messages,
+//!   each tagged with a `type` field, ending with `result` or `error`.
+//!
+//! [`serve_health`] adds `GET /livez` and `GET /readyz` probes backed by a
+//! [`RuntimeHealth`]; `/readyz` answers 503 while any check fails.
+//!
+//! Routers for several flows can be combined with [`Router::merge`]:
+//!
+//! ```rust,no_run
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::BasicFlow;
+//! # use pocketflow_rs::server::{FlowEndpoint, serve_endpoint, serve_flow};
+//! # fn summarize_flow() -> BasicFlow<InMemoryStorage> { FlowBuilder::new().build() }
+//! # fn chat_flow(_tokens: pocketflow_rs::node::TokenSender) -> BasicFlow<InMemoryStorage> { FlowBuilder::new().build() }
+//! # async fn run() -> std::io::Result<()> {
+//! let app = serve_flow("/summarize", summarize_flow).merge(serve_endpoint(
+//!     "/chat",
+//!     FlowEndpoint::streaming(chat_flow).output_keys(["reply"]),
+//! ));
+//!
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
+//! axum::serve(listener, app).await
+//! # }
+//! ```
+
+use crate::flow::{BasicFlow, Flow, FlowEvent, FlowEventSender};
+use crate::health::RuntimeHealth;
+use crate::node::TokenSender;
+use crate::shared_store::{SharedStore, SystemKeys};
+use crate::storage::InMemoryStorage;
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::{get, post};
+use serde_json::{Map, Value, json};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Store key used for request bodies that aren't JSON objects
+pub const INPUT_KEY: &str = "input";
+
+type EndpointFactory = Arc<dyn Fn(TokenSender) -> BasicFlow<InMemoryStorage> + Send + Sync>;
+
+/// A flow exposed over HTTP, together with how its output is returned
+#[derive(Clone)]
+pub struct FlowEndpoint {
+    factory: EndpointFactory,
+    output_keys: Option<Vec<String>>,
+}
+
+impl std::fmt::Debug for FlowEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlowEndpoint")
+            .field("output_keys", &self.output_keys)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FlowEndpoint {
+    /// Create an endpoint from a factory producing a fresh flow per request
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn() -> BasicFlow<InMemoryStorage> + Send + Sync + 'static,
+    {
+        Self::streaming(move |_tokens| factory())
+    }
+
+    /// Create an endpoint whose flows stream output through a [`TokenSender`]
+    ///
+    /// Pass the sender to nodes such as `ApiRequestNode::with_token_sender`;
+    /// chunks are forwarded to SSE clients as `token` events.
+    pub fn streaming<F>(factory: F) -> Self
+    where
+        F: Fn(TokenSender) -> BasicFlow<InMemoryStorage> + Send + Sync + 'static,
+    {
+        Self {
+            factory: Arc::new(factory),
+            output_keys: None,
+        }
+    }
+
+    /// Only return these store keys in the response (default: the whole store
+    /// except reserved [`SystemKeys`])
+    pub fn output_keys<I, K>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.output_keys = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Run a fresh flow on the given input and collect its output
+    async fn run(
+        &self,
+        input: Value,
+        tokens: TokenSender,
+        events: Option<FlowEventSender>,
+    ) -> Result<Value, String> {
+        let mut flow = (self.factory)(tokens);
+        if let Some(events) = events {
+            flow.set_event_sender(events);
+        }
+        let mut store = SharedStore::new();
+
+        match input {
+            Value::Object(fields) => {
+                for (key, value) in fields {
+                    store.set(key, value).map_err(|e| e.to_string())?;
+                }
+            }
+            Value::Null => {}
+            other => store
+                .set(INPUT_KEY.to_string(), other)
+                .map_err(|e| e.to_string())?,
+        }
+
+        flow.execute(&mut store).await.map_err(|e| e.to_string())?;
+
+        let keys = match &self.output_keys {
+            Some(keys) => keys.clone(),
+            None => {
+                let mut keys = store.keys().map_err(|e| e.to_string())?;
+                keys.retain(|key| !SystemKeys::is_reserved(key));
+                keys
+            }
+        };
+        let mut output = Map::new();
+        for key in keys {
+            if let Some(value) = store.get(&key).map_err(|e| e.to_string())? {
+                output.insert(key, value);
+            }
+        }
+        Ok(Value::Object(output))
+    }
+}
+
+/// Create a router exposing a flow as `POST path`
+pub fn serve_flow<F>(path: &str, factory: F) -> Router
+where
+    F: Fn() -> BasicFlow<InMemoryStorage> + Send + Sync + 'static,
+{
+    serve_endpoint(path, FlowEndpoint::new(factory))
+}
+
+/// Create a router exposing a configured endpoint as `POST path`
+pub fn serve_endpoint(path: &str, endpoint: FlowEndpoint) -> Router {
+    Router::new()
+        .route(path, post(handle_request).get(handle_websocket))
+        .with_state(Arc::new(endpoint))
+}
+
+/// Create a router with Kubernetes-style probes: `GET /livez` answers 200
+/// while the process serves requests, `GET /readyz` runs the health checks
+/// and answers 200 or 503 with the [`HealthReport`](crate::health::HealthReport)
+pub fn serve_health(health: RuntimeHealth) -> Router {
+    Router::new()
+        .route("/livez", get(|| async { "ok" }))
+        .route("/readyz", get(handle_readiness))
+        .with_state(Arc::new(health))
+}
+
+async fn handle_readiness(State(health): State<Arc<RuntimeHealth>>) -> Response {
+    let report = health.check().await;
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report)).into_response()
+}
+
+/// Progress of a streamed flow run
+enum StreamItem {
+    Event(FlowEvent),
+    Done(Result<Value, String>),
+}
+
+/// Run the endpoint's flow in the background, forwarding its events and tokens
+fn spawn_run(endpoint: Arc<FlowEndpoint>, input: Value) -> mpsc::UnboundedReceiver<StreamItem> {
+    let (item_tx, item_rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let (token_tx, mut token_rx) = mpsc::unbounded_channel::<String>();
+        let run = endpoint.run(input, token_tx, Some(event_tx));
+        tokio::pin!(run);
+
+        let outcome = loop {
+            tokio::select! {
+                Some(event) = event_rx.recv() => {
+                    let _ = item_tx.send(StreamItem::Event(event));
+                }
+                Some(text) = token_rx.recv() => {
+                    let _ = item_tx.send(StreamItem::Event(FlowEvent::TokenDelta { text }));
+                }
+                outcome = &mut run => break outcome,
+            }
+        };
+        while let Ok(text) = token_rx.try_recv() {
+            let _ = item_tx.send(StreamItem::Event(FlowEvent::TokenDelta { text }));
+        }
+        while let Ok(event) = event_rx.try_recv() {
+            let _ = item_tx.send(StreamItem::Event(event));
+        }
+        let _ = item_tx.send(StreamItem::Done(outcome));
+    });
+
+    item_rx
+}
+
+async fn handle_request(
+    State(endpoint): State<Arc<FlowEndpoint>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let input = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(input) => input,
+            Err(e) => {
+                return error_response(StatusCode::BAD_REQUEST, format!("Invalid JSON: {}", e));
+            }
+        }
+    };
+
+    if let Some(key) = reserved_input_key(&input) {
+        return error_response(StatusCode::BAD_REQUEST, reserved_input_error(key));
+    }
+
+    if wants_event_stream(&headers) {
+        return stream_response(endpoint, input);
+    }
+
+    // Nobody listens for tokens on a plain request
+    let (tokens, _) = mpsc::unbounded_channel();
+    match endpoint.run(input, tokens, None).await {
+        Ok(output) => Json(output).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+fn stream_response(endpoint: Arc<FlowEndpoint>, input: Value) -> Response {
+    let stream = futures::stream::unfold(spawn_run(endpoint, input), |mut rx| async move {
+        let event = match rx.recv().await? {
+            StreamItem::Event(FlowEvent::TokenDelta { text }) => {
+                Event::default().event("token").data(text)
+            }
+            StreamItem::Event(event) => {
+                let data = serde_json::to_value(&event).unwrap_or(Value::Null);
+                let name = data["type"].as_str().unwrap_or("event").to_string();
+                Event::default().event(name).data(data.to_string())
+            }
+            StreamItem::Done(Ok(output)) => {
+                Event::default().event("result").data(output.to_string())
+            }
+            StreamItem::Done(Err(e)) => Event::default()
+                .event("error")
+                .data(json!({ "error": e }).to_string()),
+        };
+        Some((Ok::<_, Infallible>(event), rx))
+    });
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn handle_websocket(
+    State(endpoint): State<Arc<FlowEndpoint>>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(move |socket| run_websocket(endpoint, socket))
+}
+
+async fn run_websocket(endpoint: Arc<FlowEndpoint>, mut socket: WebSocket) {
+    // The first text message carries the flow input
+    let input = loop {
+        match socket.recv().await {
+            Some(Ok(Message::Text(text))) => break serde_json::from_str::<Value>(&text),
+            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+            Some(Ok(_)) => continue,
+        }
+    };
+    let input = match input {
+        Ok(input) => match reserved_input_key(&input) {
+            Some(key) => Err(reserved_input_error(key)),
+            None => Ok(input),
+        },
+        Err(e) => Err(format!("Invalid JSON: {}", e)),
+    };
+    let input = match input {
+        Ok(input) => input,
+        Err(message) => {
+            let message = json!({ "type": "error", "error": message });
+            let _ = socket.send(Message::Text(message.to_string().into())).await;
+            let _ = socket.send(Message::Close(None)).await;
+            return;
+        }
+    };
+
+    let mut items = spawn_run(endpoint, input);
+    while let Some(item) = items.recv().await {
+        let message = match item {
+            StreamItem::Event(event) => serde_json::to_value(&event).unwrap_or(Value::Null),
+            StreamItem::Done(Ok(output)) => json!({ "type": "result", "output": output }),
+            StreamItem::Done(Err(e)) => json!({ "type": "error", "error": e }),
+        };
+        if socket
+            .send(Message::Text(message.to_string().into()))
+            .await
+            .is_err()
+        {
+            // Client went away; the flow keeps running to completion in the background
+            return;
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+/// A key of an object input that only the engine may write
+fn reserved_input_key(input: &Value) -> Option<&str> {
+    input
+        .as_object()?
+        .keys()
+        .map(String::as_str)
+        .find(|key| SystemKeys::is_reserved(key))
+}
+
+fn reserved_input_error(key: &str) -> String {
+    format!("Key '{}' is reserved for the engine", key)
+}
+
+fn wants_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"))
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Action;
+    use crate::flow::FlowBuilder;
+    use crate::node::{ExecutionContext, FunctionNode, Node};
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use futures::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite;
+    use tower::ServiceExt;
+
+    /// Upper-cases `text` into `shout`, streaming each word as a token
+    fn shout_flow(tokens: TokenSender) -> BasicFlow<InMemoryStorage> {
+        FlowBuilder::new()
+            .start_node("shout")
+            .node(
+                "shout",
+                Node::new(FunctionNode::new(
+                    "ShoutNode".to_string(),
+                    |store: &SharedStore<_>, _context: &ExecutionContext| -> String {
+                        store
+                            .get("text")
+                            .ok()
+                            .flatten()
+                            .and_then(|v| v.as_str().map(String::from))
+                            .unwrap_or_default()
+                    },
+                    move |text: String,
+                          _context: &ExecutionContext|
+                          -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+                        let shout = text.to_uppercase();
+                        for word in shout.split_inclusive(' ') {
+                            let _ = tokens.send(word.to_string());
+                        }
+                        Ok(shout)
+                    },
+                    |store: &mut SharedStore<_>,
+                     _prep: String,
+                     shout: String,
+                     _context: &ExecutionContext|
+                     -> Result<Action, Box<dyn std::error::Error + Send + Sync>> {
+                        store.set("shout".to_string(), Value::String(shout))?;
+                        Ok(Action::simple("complete"))
+                    },
+                )),
+            )
+            .build()
+    }
+
+    fn post_json(body: &str) -> Request<Body> {
+        Request::post("/shout")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_serve_flow_returns_output_keys() {
+        let app = serve_endpoint(
+            "/shout",
+            FlowEndpoint::streaming(shout_flow).output_keys(["shout"]),
+        );
+
+        let response = app
+            .clone()
+            .oneshot(post_json(r#"{"text": "hello world"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let output: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(output, json!({"shout": "HELLO WORLD"}));
+
+        let response = app.clone().oneshot(post_json("{not json")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(post_json(r#"{"__pf::last_error": "forged"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_serve_flow_hides_system_keys() {
+        let app = serve_endpoint("/shout", FlowEndpoint::streaming(shout_flow));
+
+        let response = app.oneshot(post_json(r#"{"text": "hi"}"#)).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let output: Value = serde_json::from_slice(&body).unwrap();
+        // The flow recorded its execution path, which isn't returned
+        assert_eq!(output, json!({"text": "hi", "shout": "HI"}));
+    }
+
+    #[tokio::test]
+    async fn test_serve_health_probes() {
+        let app = serve_health(
+            RuntimeHealth::new()
+                .with_storage("store", InMemoryStorage::new())
+                .with_check("redis", || async { Err::<(), _>("connection refused") }),
+        );
+        let get = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+
+        let response = app.clone().oneshot(get("/livez")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(get("/readyz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["ready"], json!(false));
+        assert_eq!(report["checks"][0]["healthy"], json!(true));
+        assert_eq!(report["checks"][1]["error"], json!("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_flow_streams_tokens() {
+        let app = serve_endpoint(
+            "/shout",
+            FlowEndpoint::streaming(shout_flow).output_keys(["shout"]),
+        );
+
+        let mut request = post_json(r#"{"text": "hello world"}"#);
+        request
+            .headers_mut()
+            .insert(header::ACCEPT, "text/event-stream".parse().unwrap());
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let token_events = body.matches("event: token").count();
+        assert_eq!(token_events, 2);
+        assert!(body.contains("data: HELLO "));
+        assert!(body.contains("event: node_started\ndata: {"));
+        assert!(body.contains("event: flow_completed"));
+        assert!(body.contains("event: result\ndata: {\"shout\":\"HELLO WORLD\"}"));
+    }
+
+    #[tokio::test]
+    async fn test_serve_flow_over_websocket() {
+        let app = serve_endpoint(
+            "/shout",
+            FlowEndpoint::streaming(shout_flow).output_keys(["shout"]),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/shout", addr))
+            .await
+            .unwrap();
+        socket
+            .send(tungstenite::Message::text(r#"{"text": "hi there"}"#))
+            .await
+            .unwrap();
+
+        let mut messages = Vec::new();
+        while let Some(Ok(message)) = socket.next().await {
+            if let tungstenite::Message::Text(text) = message {
+                messages.push(serde_json::from_str::<Value>(&text).unwrap());
+            }
+        }
+
+        let types: Vec<&str> = messages.iter().filter_map(|m| m["type"].as_str()).collect();
+        assert!(types.contains(&"node_started"));
+        assert!(types.contains(&"node_completed"));
+        assert_eq!(types.iter().filter(|t| **t == "token_delta").count(), 2);
+        assert_eq!(
+            messages.last().unwrap(),
+            &json!({"type": "result", "output": {"shout": "HI THERE"}})
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Per-node write permissions on store keys
+//!
+//! A [`KeyAccessPolicy`] grants a node read-only or read-write access to key
+//! prefixes. Policies are declared with `FlowBuilder::key_access`; while the
+//! node runs, writes to keys it may not change are refused and the flow fails
+//! the node with `NodeError::PermissionDenied`, even if the node ignored the
+//! refused write. This keeps an LLM or tool node from clobbering control-plane
+//! keys such as conversation history or budgets.
+//!
+//! The longest matching prefix decides, and keys matching no grant are
+//! read-only, so a policy lists what a node may write:
+//!
+//! ```rust
+//! # use pocketflow_rs::shared_store::{KeyAccess, KeyAccessPolicy};
+//! let policy = KeyAccessPolicy::new()
+//!     .read_write("draft.")
+//!     .read_only("draft.approved");
+//! assert!(policy.can_write("draft.text"));
+//! assert!(!policy.can_write("draft.approved"));
+//! assert_eq!(policy.access("history"), KeyAccess::ReadOnly);
+//! ```
+//!
+//! Policies only restrict writes: reads are never refused.
+
+/// Access a node has to a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAccess {
+    /// The node may read but not write or remove the key
+    ReadOnly,
+    /// The node may read, write and remove the key
+    ReadWrite,
+}
+
+/// Access grants for key prefixes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyAccessPolicy {
+    grants: Vec<(String, KeyAccess)>,
+}
+
+impl KeyAccessPolicy {
+    /// Create a policy granting write access to nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a policy granting write access to every key
+    pub fn allow_all() -> Self {
+        Self::new().read_write("")
+    }
+
+    /// Grant access to keys starting with `prefix`
+    pub fn grant(mut self, prefix: impl Into<String>, access: KeyAccess) -> Self {
+        let prefix = prefix.into();
+        self.grants.retain(|(existing, _)| *existing != prefix);
+        self.grants.push((prefix, access));
+        self
+    }
+
+    /// Allow reading but not writing keys starting with `prefix`
+    pub fn read_only(self, prefix: impl Into<String>) -> Self {
+        self.grant(prefix, KeyAccess::ReadOnly)
+    }
+
+    /// Allow writing keys starting with `prefix`
+    pub fn read_write(self, prefix: impl Into<String>) -> Self {
+        self.grant(prefix, KeyAccess::ReadWrite)
+    }
+
+    /// The access granted to `key` by its longest matching prefix
+    pub fn access(&self, key: &str) -> KeyAccess {
+        self.grants
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(KeyAccess::ReadOnly, |(_, access)| *access)
+    }
+
+    /// Check whether `key` may be written or removed
+    pub fn can_write(&self, key: &str) -> bool {
+        self.access(key) == KeyAccess::ReadWrite
+    }
+}
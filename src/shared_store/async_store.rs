@@ -1,13 +1,25 @@
+use crate::env::RuntimeEnv;
+use crate::shared_store::SystemKeys;
+use crate::shared_store::expiry::{self, ExpiryState, is_expiry_key, ttl_key};
 use crate::storage::AsyncStorageBackend;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::error::Error;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 /// An async version of SharedStore for use with AsyncStorageBackend implementations
+///
+/// Keys given a time-to-live with [`AsyncSharedStore::set_with_ttl`] expire
+/// exactly as in [`SharedStore`](crate::shared_store::SharedStore): the deadline
+/// is kept in the backend under [`SystemKeys::TTL_PREFIX`], expired keys are
+/// hidden from reads, and [`AsyncSharedStore::purge_expired`] removes them.
 pub struct AsyncSharedStore<S: AsyncStorageBackend> {
     storage: Arc<Mutex<S>>,
+    env: RuntimeEnv,
+    expiry: Arc<ExpiryState>,
 }
 
 impl<S: AsyncStorageBackend> AsyncSharedStore<S> {
@@ -15,37 +27,119 @@ impl<S: AsyncStorageBackend> AsyncSharedStore<S> {
     pub fn new(storage: S) -> Self {
         Self {
             storage: Arc::new(Mutex::new(storage)),
+            env: RuntimeEnv::default(),
+            expiry: Arc::default(),
         }
     }
 
-    /// Store a value with the given key
+    /// Use `env`'s clock for expiry deadlines
+    pub fn with_env(mut self, env: RuntimeEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Store a value with the given key, clearing any expiry
     pub async fn set(&self, key: String, value: Value) -> Result<(), S::Error> {
         let mut storage = self.storage.lock().await;
+        self.clear_expiry(&mut storage, &key).await?;
         storage.set(key, value).await
     }
 
-    /// Retrieve a value by key
-    pub async fn get(&self, key: &str) -> Result<Option<Value>, S::Error> {
+    /// Store a value that expires after the given time-to-live
+    pub async fn set_with_ttl(
+        &self,
+        key: String,
+        value: Value,
+        ttl: Duration,
+    ) -> Result<(), S::Error> {
+        let deadline = expiry::now_ms(&self.env).saturating_add(ttl.as_millis() as u64);
+        let mut storage = self.storage.lock().await;
+        storage
+            .set_many(vec![
+                (SystemKeys::TTL_MARKER.to_string(), Value::Bool(true)),
+                (ttl_key(&key), Value::from(deadline)),
+                (key, value),
+            ])
+            .await?;
+        self.expiry.mark_in_use();
+        Ok(())
+    }
+
+    /// Remaining time-to-live of a key, or `None` if it has no expiry
+    ///
+    /// Returns `Some(Duration::ZERO)` for keys that have expired but not been purged.
+    pub async fn ttl(&self, key: &str) -> Result<Option<Duration>, S::Error> {
         let storage = self.storage.lock().await;
-        storage.get(key).await
+        let now = expiry::now_ms(&self.env);
+        Ok(self
+            .deadline(&storage, key)
+            .await?
+            .map(|deadline| Duration::from_millis(deadline.saturating_sub(now))))
     }
 
-    /// Remove a value by key, returning it if it existed
+    /// Remove the expiry from a key, making it permanent
+    pub async fn persist(&self, key: &str) -> Result<bool, S::Error> {
+        let mut storage = self.storage.lock().await;
+        if !self.uses_ttl(&storage).await? {
+            return Ok(false);
+        }
+        Ok(storage.remove(&ttl_key(key)).await?.is_some())
+    }
+
+    /// Remove every expired key from the backend, returning the removed keys
+    pub async fn purge_expired(&self) -> Result<Vec<String>, S::Error> {
+        let mut storage = self.storage.lock().await;
+        let keys = storage.keys().await?;
+        let now = expiry::now_ms(&self.env);
+        let mut expired: Vec<String> = self
+            .deadlines(&storage, &keys)
+            .await?
+            .into_iter()
+            .filter(|(_, deadline)| *deadline <= now)
+            .map(|(key, _)| key)
+            .collect();
+        expired.sort();
+
+        for key in &expired {
+            storage.remove(key).await?;
+            storage.remove(&ttl_key(key)).await?;
+        }
+        Ok(expired)
+    }
+
+    /// Retrieve a value by key, `None` if it has expired
+    pub async fn get(&self, key: &str) -> Result<Option<Value>, S::Error> {
+        Ok(self.get_many(&[key]).await?.pop().flatten())
+    }
+
+    /// Remove a value by key, returning it if it existed and hadn't expired
     pub async fn remove(&self, key: &str) -> Result<Option<Value>, S::Error> {
         let mut storage = self.storage.lock().await;
-        storage.remove(key).await
+        let expired = self.is_expired(&storage, key).await?;
+        self.clear_expiry(&mut storage, key).await?;
+        let value = storage.remove(key).await?;
+        Ok(value.filter(|_| !expired))
     }
 
-    /// Check if a key exists
+    /// Check if a key exists and hasn't expired
     pub async fn contains_key(&self, key: &str) -> Result<bool, S::Error> {
         let storage = self.storage.lock().await;
+        if self.is_expired(&storage, key).await? {
+            return Ok(false);
+        }
         storage.contains_key(key).await
     }
 
-    /// Get all keys
+    /// Get all keys, skipping expired keys and their deadlines
     pub async fn keys(&self) -> Result<Vec<String>, S::Error> {
         let storage = self.storage.lock().await;
-        storage.keys().await
+        let mut keys = storage.keys().await?;
+        let deadlines = self.deadlines(&storage, &keys).await?;
+        let now = expiry::now_ms(&self.env);
+        keys.retain(|key| {
+            !is_expiry_key(key) && deadlines.get(key).is_none_or(|deadline| *deadline > now)
+        });
+        Ok(keys)
     }
 
     /// Clear all data
@@ -54,16 +148,107 @@ impl<S: AsyncStorageBackend> AsyncSharedStore<S> {
         storage.clear().await
     }
 
-    /// Get the number of stored items
+    /// Get the number of stored items, skipping expired keys and their deadlines
     pub async fn len(&self) -> Result<usize, S::Error> {
-        let storage = self.storage.lock().await;
-        storage.len().await
+        {
+            let storage = self.storage.lock().await;
+            if !self.uses_ttl(&storage).await? {
+                return storage.len().await;
+            }
+        }
+        Ok(self.keys().await?.len())
     }
 
     /// Check if the storage is empty
     pub async fn is_empty(&self) -> Result<bool, S::Error> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// Retrieve several values at once, in the order of `keys`; missing and
+    /// expired keys yield `None`
+    pub async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Value>>, S::Error> {
         let storage = self.storage.lock().await;
-        storage.is_empty().await
+        if !self.uses_ttl(&storage).await? {
+            return storage.get_many(keys).await;
+        }
+        // Values and deadlines in one batched read
+        let ttl_keys: Vec<String> = keys.iter().map(|key| ttl_key(key)).collect();
+        let mut batch = keys.to_vec();
+        batch.extend(ttl_keys.iter().map(String::as_str));
+        let mut values = storage.get_many(&batch).await?;
+        let deadlines = values.split_off(keys.len());
+        let now = expiry::now_ms(&self.env);
+        for (value, deadline) in values.iter_mut().zip(deadlines) {
+            if expiry::has_passed(deadline.as_ref(), now) {
+                *value = None;
+            }
+        }
+        Ok(values)
+    }
+
+    /// Store several values at once, clearing their expiry
+    pub async fn set_many(&self, entries: Vec<(String, Value)>) -> Result<(), S::Error> {
+        let mut storage = self.storage.lock().await;
+        for (key, _) in &entries {
+            self.clear_expiry(&mut storage, key).await?;
+        }
+        storage.set_many(entries).await
+    }
+
+    /// Persist writes the backend has buffered, e.g. at a node boundary
+    pub async fn flush(&self) -> Result<(), S::Error> {
+        let mut storage = self.storage.lock().await;
+        storage.flush().await
+    }
+
+    /// Get all key-value pairs, skipping expired keys and their deadlines
+    pub async fn entries(&self) -> Result<Vec<(String, Value)>, S::Error> {
+        let storage = self.storage.lock().await;
+        let mut entries = storage.entries().await?;
+        self.expiry
+            .observe(entries.iter().map(|(key, _)| key.as_str()));
+        let now = expiry::now_ms(&self.env);
+        let expired: Vec<String> = entries
+            .iter()
+            .filter_map(|(key, deadline)| {
+                let key = key.strip_prefix(SystemKeys::TTL_PREFIX)?;
+                let expired = deadline.as_u64()? <= now;
+                expired.then(|| key.to_string())
+            })
+            .collect();
+        entries.retain(|(key, _)| !is_expiry_key(key) && !expired.contains(key));
+        Ok(entries)
+    }
+
+    /// Keep only the entries for which `keep` returns `true`
+    ///
+    /// Expired keys are removed as well, without being passed to `keep`.
+    pub async fn retain<F>(&self, mut keep: F) -> Result<(), S::Error>
+    where
+        F: FnMut(&str, &Value) -> bool + Send,
+    {
+        let mut storage = self.storage.lock().await;
+        let keys = storage.keys().await?;
+        let deadlines = self.deadlines(&storage, &keys).await?;
+        let now = expiry::now_ms(&self.env);
+        storage
+            .retain(&mut |key, value| {
+                // Deadlines are dropped below, along with their keys
+                if is_expiry_key(key) {
+                    return true;
+                }
+                if deadlines.get(key).is_some_and(|deadline| *deadline <= now) {
+                    return false;
+                }
+                keep(key, value)
+            })
+            .await?;
+        for key in deadlines.keys() {
+            if !storage.contains_key(key).await? {
+                storage.remove(&ttl_key(key)).await?;
+            }
+        }
+        Ok(())
     }
 
     /// Store a serializable value (convenience method)
@@ -111,12 +296,71 @@ impl<S: AsyncStorageBackend> AsyncSharedStore<S> {
     pub fn storage(&self) -> &Arc<Mutex<S>> {
         &self.storage
     }
+
+    /// Whether any key may carry an expiry, checking the backend on first use
+    async fn uses_ttl(&self, storage: &S) -> Result<bool, S::Error> {
+        if let Some(in_use) = self.expiry.known() {
+            return Ok(in_use);
+        }
+        let in_use = storage.contains_key(SystemKeys::TTL_MARKER).await?;
+        self.expiry.settle(in_use);
+        Ok(in_use)
+    }
+
+    /// Expiry deadline of a key
+    async fn deadline(&self, storage: &S, key: &str) -> Result<Option<u64>, S::Error> {
+        if !self.uses_ttl(storage).await? {
+            return Ok(None);
+        }
+        Ok(storage
+            .get(&ttl_key(key))
+            .await?
+            .and_then(|deadline| deadline.as_u64()))
+    }
+
+    /// Whether `key` has a deadline that has passed
+    async fn is_expired(&self, storage: &S, key: &str) -> Result<bool, S::Error> {
+        let now = expiry::now_ms(&self.env);
+        Ok(self
+            .deadline(storage, key)
+            .await?
+            .is_some_and(|deadline| deadline <= now))
+    }
+
+    /// Expiry deadlines of the keys whose deadline keys are among `keys`
+    async fn deadlines(
+        &self,
+        storage: &S,
+        keys: &[String],
+    ) -> Result<HashMap<String, u64>, S::Error> {
+        let ttl_keys: Vec<&str> = keys
+            .iter()
+            .map(String::as_str)
+            .filter(|key| key.starts_with(SystemKeys::TTL_PREFIX))
+            .collect();
+        if ttl_keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+        self.expiry.mark_in_use();
+        let deadlines = storage.get_many(&ttl_keys).await?;
+        Ok(expiry::deadlines(ttl_keys.into_iter().zip(deadlines)))
+    }
+
+    /// Drop the expiry of a key about to be overwritten
+    async fn clear_expiry(&self, storage: &mut S, key: &str) -> Result<(), S::Error> {
+        if self.uses_ttl(storage).await? {
+            storage.remove(&ttl_key(key)).await?;
+        }
+        Ok(())
+    }
 }
 
 impl<S: AsyncStorageBackend> Clone for AsyncSharedStore<S> {
     fn clone(&self) -> Self {
         Self {
             storage: Arc::clone(&self.storage),
+            env: self.env.clone(),
+            expiry: Arc::clone(&self.expiry),
         }
     }
 }
@@ -209,6 +453,55 @@ mod tests {
         let retrieved: Option<TestData> = store.get_deserializable("struct_test").await?;
         assert_eq!(retrieved, Some(test_data));
 
+        // Bulk operations fall back to the per-key methods
+        store
+            .set_many(vec![
+                ("a".to_string(), json!(1)),
+                ("b".to_string(), json!(2)),
+            ])
+            .await?;
+        assert_eq!(
+            store.get_many(&["b", "missing"]).await?,
+            vec![Some(json!(2)), None]
+        );
+        store.retain(|key, _| key == "a").await?;
+        assert_eq!(store.entries().await?, vec![("a".to_string(), json!(1))]);
+
+        Ok(())
+    }
+    #[cfg(feature = "storage-memory")]
+    #[tokio::test]
+    async fn test_async_shared_store_ttl() -> Result<(), Box<dyn Error + Send + Sync>> {
+        use crate::env::ManualClock;
+
+        let clock = Arc::new(ManualClock::new(std::time::SystemTime::now()));
+        let store = AsyncSharedStore::new(MockAsyncStorage::new())
+            .with_env(RuntimeEnv::new().with_clock(clock.clone()));
+
+        store.set("permanent".to_string(), json!(1)).await?;
+        assert_eq!(store.len().await?, 1);
+        store
+            .set_with_ttl(
+                "session".to_string(),
+                json!("token"),
+                Duration::from_secs(60),
+            )
+            .await?;
+        assert_eq!(store.get("session").await?, Some(json!("token")));
+        assert_eq!(store.len().await?, 2);
+
+        clock.advance(Duration::from_secs(61));
+        assert_eq!(store.get("session").await?, None);
+        assert!(!store.contains_key("session").await?);
+        assert_eq!(store.keys().await?, vec!["permanent".to_string()]);
+        assert_eq!(
+            store.entries().await?,
+            vec![("permanent".to_string(), json!(1))]
+        );
+        assert_eq!(store.ttl("session").await?, Some(Duration::ZERO));
+
+        assert_eq!(store.purge_expired().await?, vec!["session".to_string()]);
+        assert_eq!(store.ttl("session").await?, None);
         Ok(())
     }
 }
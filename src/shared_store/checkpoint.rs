@@ -0,0 +1,253 @@
+//! Store checkpoints, diffs between them and time travel
+//!
+//! A [`CheckpointManager`] keeps snapshots of a store's entries, reserved
+//! keys included. Take them by hand with [`CheckpointManager::checkpoint`], or
+//! give the manager to a flow with `FlowBuilder::checkpoints` to snapshot the
+//! store after every step. [`CheckpointManager::diff`] lists the keys that
+//! changed between two checkpoints, and [`CheckpointManager::time_travel`]
+//! opens a read-only store as it was at a checkpoint, to answer questions like
+//! "what did the agent know at step 7?".
+//!
+//! Clones share checkpoints, so a manager can be cloned into a flow and kept
+//! for inspection.
+//!
+//! ```rust
+//! # use pocketflow_rs::shared_store::{CheckpointManager, KeyChange, SharedStore};
+//! # use serde_json::json;
+//! let checkpoints = CheckpointManager::new();
+//! let mut store = SharedStore::new();
+//! store.set("plan".to_string(), json!("draft")).unwrap();
+//! let before = checkpoints.checkpoint("drafted", &store).unwrap();
+//! store.set("plan".to_string(), json!("final")).unwrap();
+//! let after = checkpoints.checkpoint("revised", &store).unwrap();
+//!
+//! assert_eq!(
+//!     checkpoints.diff(&before, &after).unwrap(),
+//!     vec![KeyChange::Changed {
+//!         key: "plan".to_string(),
+//!         before: json!("draft"),
+//!         after: json!("final"),
+//!     }]
+//! );
+//! let past = checkpoints.time_travel(&before).unwrap();
+//! assert_eq!(past.get("plan").unwrap(), Some(json!("draft")));
+//! ```
+
+use crate::shared_store::SharedStore;
+use crate::storage::{InMemoryStorage, StorageBackend};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Error looking up a checkpoint
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CheckpointError {
+    #[error("Unknown checkpoint '{0}'")]
+    NotFound(String),
+}
+
+/// A snapshot of a store's entries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub id: String,
+    /// What the checkpoint was taken after, such as the node that just ran
+    pub label: String,
+    /// Number of flow steps executed when a flow took the checkpoint
+    pub step: Option<usize>,
+    pub entries: BTreeMap<String, Value>,
+}
+
+/// How a key differs between two checkpoints
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum KeyChange {
+    Added {
+        key: String,
+        value: Value,
+    },
+    Removed {
+        key: String,
+        value: Value,
+    },
+    Changed {
+        key: String,
+        before: Value,
+        after: Value,
+    },
+}
+
+impl KeyChange {
+    /// The key that changed
+    pub fn key(&self) -> &str {
+        match self {
+            KeyChange::Added { key, .. }
+            | KeyChange::Removed { key, .. }
+            | KeyChange::Changed { key, .. } => key,
+        }
+    }
+}
+
+/// A read-only store as it was at a checkpoint
+///
+/// Dereferences to a [`SharedStore`] for reading only.
+#[derive(Debug)]
+pub struct CheckpointView {
+    checkpoint: Checkpoint,
+    store: SharedStore<InMemoryStorage>,
+}
+
+impl CheckpointView {
+    /// The checkpoint the view shows
+    pub fn checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint
+    }
+}
+
+impl std::ops::Deref for CheckpointView {
+    type Target = SharedStore<InMemoryStorage>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.store
+    }
+}
+
+#[derive(Debug, Default)]
+struct Checkpoints {
+    taken: Vec<Checkpoint>,
+    next_id: u64,
+}
+
+/// Snapshots of a store, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointManager {
+    checkpoints: Arc<Mutex<Checkpoints>>,
+    max_checkpoints: Option<usize>,
+}
+
+impl CheckpointManager {
+    /// Create a manager keeping every checkpoint
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep only the most recent `max` checkpoints
+    pub fn with_max_checkpoints(mut self, max: usize) -> Self {
+        self.max_checkpoints = Some(max.max(1));
+        self
+    }
+
+    /// Snapshot `store`, returning the checkpoint's ID
+    pub fn checkpoint<S: StorageBackend>(
+        &self,
+        label: impl Into<String>,
+        store: &SharedStore<S>,
+    ) -> Result<String, S::Error> {
+        self.record(label.into(), None, store)
+    }
+
+    /// Snapshot `store` after a flow step
+    pub(crate) fn record<S: StorageBackend>(
+        &self,
+        label: String,
+        step: Option<usize>,
+        store: &SharedStore<S>,
+    ) -> Result<String, S::Error> {
+        let entries = store.iter()?.collect();
+        let mut checkpoints = self.lock();
+        checkpoints.next_id += 1;
+        let id = format!("checkpoint-{}", checkpoints.next_id);
+        checkpoints.taken.push(Checkpoint {
+            id: id.clone(),
+            label,
+            step,
+            entries,
+        });
+        if let Some(max) = self.max_checkpoints {
+            let excess = checkpoints.taken.len().saturating_sub(max);
+            checkpoints.taken.drain(..excess);
+        }
+        Ok(id)
+    }
+
+    /// Copy of every checkpoint kept, oldest first
+    pub fn checkpoints(&self) -> Vec<Checkpoint> {
+        self.lock().taken.clone()
+    }
+
+    /// The checkpoint with the given ID
+    pub fn get(&self, id: &str) -> Option<Checkpoint> {
+        self.lock()
+            .taken
+            .iter()
+            .find(|checkpoint| checkpoint.id == id)
+            .cloned()
+    }
+
+    /// The latest checkpoint a flow took after `step` steps
+    pub fn at_step(&self, step: usize) -> Option<Checkpoint> {
+        self.lock()
+            .taken
+            .iter()
+            .rev()
+            .find(|checkpoint| checkpoint.step == Some(step))
+            .cloned()
+    }
+
+    /// Drop every checkpoint
+    pub fn clear(&self) {
+        self.lock().taken.clear();
+    }
+
+    /// Keys added, removed or changed from checkpoint `a` to checkpoint `b`,
+    /// sorted by key
+    pub fn diff(&self, a: &str, b: &str) -> Result<Vec<KeyChange>, CheckpointError> {
+        let (before, after) = (self.require(a)?.entries, self.require(b)?.entries);
+        let mut changes = Vec::new();
+        for (key, old) in &before {
+            match after.get(key) {
+                None => changes.push(KeyChange::Removed {
+                    key: key.clone(),
+                    value: old.clone(),
+                }),
+                Some(new) if new != old => changes.push(KeyChange::Changed {
+                    key: key.clone(),
+                    before: old.clone(),
+                    after: new.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (key, new) in after {
+            if !before.contains_key(&key) {
+                changes.push(KeyChange::Added { key, value: new });
+            }
+        }
+        changes.sort_by(|x, y| x.key().cmp(y.key()));
+        Ok(changes)
+    }
+
+    /// A read-only store with the entries of a checkpoint
+    pub fn time_travel(&self, id: &str) -> Result<CheckpointView, CheckpointError> {
+        let checkpoint = self.require(id)?;
+        let mut storage = InMemoryStorage::new();
+        for (key, value) in &checkpoint.entries {
+            storage
+                .set(key.clone(), value.clone())
+                .expect("In-memory storage accepts every write");
+        }
+        Ok(CheckpointView {
+            checkpoint,
+            store: SharedStore::with_storage(storage),
+        })
+    }
+
+    fn require(&self, id: &str) -> Result<Checkpoint, CheckpointError> {
+        self.get(id)
+            .ok_or_else(|| CheckpointError::NotFound(id.to_string()))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Checkpoints> {
+        self.checkpoints.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,38 @@
+use std::error::Error;
+
+/// Errors returned by SharedStore write operations
+///
+/// Wraps the storage backend's own error type and adds the failures the store
+/// layer itself can produce, such as rejected writes.
+#[derive(Debug, thiserror::Error)]
+pub enum SharedStoreError<E: Error + 'static> {
+    /// Error reported by the storage backend
+    #[error(transparent)]
+    Storage(E),
+
+    /// A value was rejected by the validator registered for its key
+    #[error("Validation failed for key '{key}': {message}")]
+    ValidationFailed { key: String, message: String },
+
+    /// The key lies in the namespace reserved for the engine, see `SystemKeys`
+    #[error("Key '{0}' is reserved for the engine")]
+    ReservedKey(String),
+
+    /// The running node's key access policy does not allow writing the key
+    #[error("Node '{node_id}' may not write key '{key}'")]
+    PermissionDenied { key: String, node_id: String },
+
+    /// A schema could not be compiled into a validator
+    #[error("Invalid schema for key '{key}': {message}")]
+    InvalidSchema { key: String, message: String },
+
+    /// A JSONPath query could not be parsed
+    #[error(transparent)]
+    InvalidQuery(crate::shared_store::JsonPathError),
+}
+
+impl<E: Error + 'static> From<E> for SharedStoreError<E> {
+    fn from(error: E) -> Self {
+        SharedStoreError::Storage(error)
+    }
+}
@@ -0,0 +1,90 @@
+//! Expiry bookkeeping shared by `SharedStore`, `AsyncSharedStore` and
+//! `SharedStoreHandle`
+
+use crate::env::RuntimeEnv;
+use crate::shared_store::SystemKeys;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::UNIX_EPOCH;
+
+const UNKNOWN: u8 = 0;
+const UNUSED: u8 = 1;
+const IN_USE: u8 = 2;
+
+/// Whether a store's backend holds expiry deadlines
+///
+/// Starts unknown and is settled by checking for [`SystemKeys::TTL_MARKER`]
+/// once. Only moves from unused to in use afterwards, when the store sets a
+/// TTL itself or sees a deadline key while listing.
+#[derive(Debug, Default)]
+pub(crate) struct ExpiryState(AtomicU8);
+
+impl ExpiryState {
+    /// `Some(in_use)` once the backend has been checked
+    pub(crate) fn known(&self) -> Option<bool> {
+        match self.0.load(Ordering::Acquire) {
+            UNKNOWN => None,
+            state => Some(state == IN_USE),
+        }
+    }
+
+    /// Record the result of checking the backend for the marker
+    pub(crate) fn settle(&self, in_use: bool) {
+        if in_use {
+            self.mark_in_use();
+        } else {
+            let _ = self
+                .0
+                .compare_exchange(UNKNOWN, UNUSED, Ordering::AcqRel, Ordering::Acquire);
+        }
+    }
+
+    pub(crate) fn mark_in_use(&self) {
+        self.0.store(IN_USE, Ordering::Release);
+    }
+
+    /// Mark in use if `keys` include a deadline key
+    pub(crate) fn observe<'a>(&self, mut keys: impl Iterator<Item = &'a str>) {
+        if keys.any(|key| key.starts_with(SystemKeys::TTL_PREFIX)) {
+            self.mark_in_use();
+        }
+    }
+}
+
+/// Key holding the expiry deadline of `key`
+pub(crate) fn ttl_key(key: &str) -> String {
+    format!("{}{}", SystemKeys::TTL_PREFIX, key)
+}
+
+/// Whether `key` holds expiry bookkeeping rather than data
+pub(crate) fn is_expiry_key(key: &str) -> bool {
+    key == SystemKeys::TTL_MARKER || key.starts_with(SystemKeys::TTL_PREFIX)
+}
+
+/// Current wall-clock time of `env`, in milliseconds since the Unix epoch
+pub(crate) fn now_ms(env: &RuntimeEnv) -> u64 {
+    env.system_time()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Whether the stored deadline has passed at `now`
+pub(crate) fn has_passed(deadline: Option<&Value>, now: u64) -> bool {
+    deadline
+        .and_then(Value::as_u64)
+        .is_some_and(|deadline| deadline <= now)
+}
+
+/// Deadlines by the key they expire, from deadline keys and their values
+pub(crate) fn deadlines<'a>(
+    entries: impl IntoIterator<Item = (&'a str, Option<Value>)>,
+) -> HashMap<String, u64> {
+    entries
+        .into_iter()
+        .filter_map(|(ttl_key, deadline)| {
+            let key = ttl_key.strip_prefix(SystemKeys::TTL_PREFIX)?;
+            Some((key.to_string(), deadline?.as_u64()?))
+        })
+        .collect()
+}
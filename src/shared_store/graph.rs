@@ -0,0 +1,377 @@
+//! Graph-shaped agent memory kept in the shared store
+//!
+//! A [`KnowledgeGraph`] names a store key holding entities (graph nodes with
+//! properties) and the relations between them (directed, labelled edges with
+//! properties). Agents record facts such as `ada -works_at-> acme` as they
+//! learn them and later pull the neighbourhood of an entity into a prompt.
+//! The graph is an ordinary JSON value, so it persists with whatever
+//! [`StorageBackend`] the store uses, and every change is a single
+//! [`SharedStore::update`].
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::shared_store::{Direction, KnowledgeGraph};
+//! # use serde_json::json;
+//! let graph = KnowledgeGraph::new("memory");
+//! let mut store = SharedStore::new();
+//! graph.upsert_node(&mut store, "ada", json!({"role": "engineer"})).unwrap();
+//! graph.upsert_edge(&mut store, "ada", "works_at", "acme", json!({"since": 2021})).unwrap();
+//!
+//! let employers = graph.neighbors(&store, "ada", Some("works_at"), Direction::Outgoing).unwrap();
+//! assert_eq!(employers[0].id, "acme");
+//! ```
+
+use super::{SharedStore, SharedStoreError};
+use crate::StorageBackend;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An entity of a [`KnowledgeGraph`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub properties: Map<String, Value>,
+}
+
+/// A directed, labelled relation between two entities
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub relation: String,
+    pub to: String,
+    #[serde(default)]
+    pub properties: Map<String, Value>,
+}
+
+/// Which edges of an entity a query follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Edges starting at the entity
+    Outgoing,
+    /// Edges ending at the entity
+    Incoming,
+    #[default]
+    Both,
+}
+
+/// Part of a graph returned by [`KnowledgeGraph::subgraph`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Subgraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Stored form of a graph
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct GraphState {
+    #[serde(default)]
+    nodes: BTreeMap<String, Map<String, Value>>,
+    #[serde(default)]
+    edges: Vec<GraphEdge>,
+}
+
+impl GraphState {
+    fn node(&self, id: &str) -> Option<GraphNode> {
+        self.nodes.get(id).map(|properties| GraphNode {
+            id: id.to_string(),
+            properties: properties.clone(),
+        })
+    }
+
+    /// Edges of `id` that `relation` and `direction` select, with the entity
+    /// at their other end
+    fn edges_of<'a>(
+        &'a self,
+        id: &'a str,
+        relation: Option<&'a str>,
+        direction: Direction,
+    ) -> impl Iterator<Item = (&'a GraphEdge, &'a str)> + 'a {
+        self.edges.iter().filter_map(move |edge| {
+            if relation.is_some_and(|relation| relation != edge.relation) {
+                return None;
+            }
+            let outgoing = edge.from == id && direction != Direction::Incoming;
+            let incoming = edge.to == id && direction != Direction::Outgoing;
+            match (outgoing, incoming) {
+                (true, _) => Some((edge, edge.to.as_str())),
+                (false, true) => Some((edge, edge.from.as_str())),
+                (false, false) => None,
+            }
+        })
+    }
+}
+
+/// Merge `properties` into `target`; `null` removes a property
+fn merge(target: &mut Map<String, Value>, properties: &Value) {
+    let Value::Object(properties) = properties else {
+        return;
+    };
+    for (key, value) in properties {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            target.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// A knowledge graph stored under one key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnowledgeGraph {
+    key: String,
+}
+
+impl KnowledgeGraph {
+    /// Use the graph under `key`; it's created by the first upsert
+    pub fn new(key: impl Into<String>) -> Self {
+        Self { key: key.into() }
+    }
+
+    /// The store key holding the graph
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Create an entity or merge properties into it
+    ///
+    /// `properties` is an object; `null` properties are removed.
+    pub fn upsert_node<S: StorageBackend>(
+        &self,
+        store: &mut SharedStore<S>,
+        id: &str,
+        properties: Value,
+    ) -> Result<(), SharedStoreError<S::Error>> {
+        self.modify(store, |graph| {
+            merge(graph.nodes.entry(id.to_string()).or_default(), &properties);
+            Some(())
+        })?;
+        Ok(())
+    }
+
+    /// Create a relation or merge properties into it, creating missing
+    /// entities
+    ///
+    /// There is at most one edge per `from`, `relation` and `to`.
+    pub fn upsert_edge<S: StorageBackend>(
+        &self,
+        store: &mut SharedStore<S>,
+        from: &str,
+        relation: &str,
+        to: &str,
+        properties: Value,
+    ) -> Result<(), SharedStoreError<S::Error>> {
+        self.modify(store, |graph| {
+            graph.nodes.entry(from.to_string()).or_default();
+            graph.nodes.entry(to.to_string()).or_default();
+            let existing = graph
+                .edges
+                .iter()
+                .position(|edge| edge.from == from && edge.relation == relation && edge.to == to);
+            let index = existing.unwrap_or_else(|| {
+                graph.edges.push(GraphEdge {
+                    from: from.to_string(),
+                    relation: relation.to_string(),
+                    to: to.to_string(),
+                    properties: Map::new(),
+                });
+                graph.edges.len() - 1
+            });
+            merge(&mut graph.edges[index].properties, &properties);
+            Some(())
+        })?;
+        Ok(())
+    }
+
+    /// Remove an entity and its relations, returning whether it existed
+    pub fn remove_node<S: StorageBackend>(
+        &self,
+        store: &mut SharedStore<S>,
+        id: &str,
+    ) -> Result<bool, SharedStoreError<S::Error>> {
+        let removed = self.modify(store, |graph| {
+            graph.nodes.remove(id)?;
+            graph.edges.retain(|edge| edge.from != id && edge.to != id);
+            Some(())
+        })?;
+        Ok(removed.is_some())
+    }
+
+    /// Remove a relation, returning whether it existed
+    pub fn remove_edge<S: StorageBackend>(
+        &self,
+        store: &mut SharedStore<S>,
+        from: &str,
+        relation: &str,
+        to: &str,
+    ) -> Result<bool, SharedStoreError<S::Error>> {
+        let removed = self.modify(store, |graph| {
+            let index = graph
+                .edges
+                .iter()
+                .position(|edge| edge.from == from && edge.relation == relation && edge.to == to)?;
+            graph.edges.remove(index);
+            Some(())
+        })?;
+        Ok(removed.is_some())
+    }
+
+    /// An entity
+    pub fn node<S: StorageBackend>(
+        &self,
+        store: &SharedStore<S>,
+        id: &str,
+    ) -> Result<Option<GraphNode>, SharedStoreError<S::Error>> {
+        Ok(self.state(store)?.node(id))
+    }
+
+    /// Entities one relation away from `id`, optionally only along
+    /// `relation`, in edge order without repeats
+    pub fn neighbors<S: StorageBackend>(
+        &self,
+        store: &SharedStore<S>,
+        id: &str,
+        relation: Option<&str>,
+        direction: Direction,
+    ) -> Result<Vec<GraphNode>, SharedStoreError<S::Error>> {
+        Ok(self
+            .subgraph(store, id, relation, direction, 1)?
+            .nodes
+            .into_iter()
+            .filter(|node| node.id != id)
+            .collect())
+    }
+
+    /// Entities within `depth` relations of `id` and the relations between
+    /// them that were followed, breadth first
+    ///
+    /// Empty if `id` isn't in the graph.
+    pub fn subgraph<S: StorageBackend>(
+        &self,
+        store: &SharedStore<S>,
+        id: &str,
+        relation: Option<&str>,
+        direction: Direction,
+        depth: usize,
+    ) -> Result<Subgraph, SharedStoreError<S::Error>> {
+        let graph = self.state(store)?;
+        let Some(start) = graph.node(id) else {
+            return Ok(Subgraph::default());
+        };
+        let mut seen = BTreeSet::from([id.to_string()]);
+        let mut subgraph = Subgraph {
+            nodes: vec![start],
+            edges: Vec::new(),
+        };
+        let mut frontier = vec![id.to_string()];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for current in &frontier {
+                for (edge, other) in graph.edges_of(current, relation, direction) {
+                    if !subgraph.edges.contains(edge) {
+                        subgraph.edges.push(edge.clone());
+                    }
+                    if seen.insert(other.to_string()) {
+                        subgraph.nodes.extend(graph.node(other));
+                        next.push(other.to_string());
+                    }
+                }
+            }
+            frontier = next;
+        }
+        Ok(subgraph)
+    }
+
+    fn state<S: StorageBackend>(
+        &self,
+        store: &SharedStore<S>,
+    ) -> Result<GraphState, SharedStoreError<S::Error>> {
+        store.get_structure(&self.key, "knowledge graph")
+    }
+
+    fn modify<S: StorageBackend, T>(
+        &self,
+        store: &mut SharedStore<S>,
+        f: impl FnMut(&mut GraphState) -> Option<T>,
+    ) -> Result<Option<T>, SharedStoreError<S::Error>> {
+        store.update_structure(&self.key, "knowledge graph", f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_subgraph() {
+        let graph = KnowledgeGraph::new("kg");
+        let mut store = SharedStore::new();
+        graph
+            .upsert_edge(&mut store, "ada", "works_at", "acme", json!({}))
+            .unwrap();
+        graph
+            .upsert_edge(&mut store, "acme", "located_in", "berlin", json!({}))
+            .unwrap();
+        graph
+            .upsert_edge(
+                &mut store,
+                "bob",
+                "works_at",
+                "acme",
+                json!({"since": 2020}),
+            )
+            .unwrap();
+        graph
+            .upsert_edge(
+                &mut store,
+                "bob",
+                "works_at",
+                "acme",
+                json!({"since": null, "team": "ops"}),
+            )
+            .unwrap();
+        graph
+            .upsert_node(&mut store, "ada", json!({"role": "engineer"}))
+            .unwrap();
+
+        let ids = |nodes: &[GraphNode]| nodes.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
+        let coworkers = graph
+            .neighbors(&store, "acme", Some("works_at"), Direction::Incoming)
+            .unwrap();
+        assert_eq!(ids(&coworkers), vec!["ada", "bob"]);
+        assert!(
+            graph
+                .neighbors(&store, "ada", None, Direction::Incoming)
+                .unwrap()
+                .is_empty()
+        );
+
+        let subgraph = graph
+            .subgraph(&store, "ada", None, Direction::Both, 2)
+            .unwrap();
+        assert_eq!(ids(&subgraph.nodes), vec!["ada", "acme", "berlin", "bob"]);
+        assert_eq!(subgraph.edges.len(), 3);
+        assert_eq!(
+            subgraph.edges[2].properties,
+            json!({"team": "ops"}).as_object().cloned().unwrap()
+        );
+        assert_eq!(subgraph.nodes[0].properties["role"], "engineer");
+
+        assert!(graph.remove_node(&mut store, "acme").unwrap());
+        assert!(
+            graph
+                .subgraph(&store, "ada", None, Direction::Both, 2)
+                .unwrap()
+                .edges
+                .is_empty()
+        );
+        assert_eq!(
+            graph
+                .subgraph(&store, "acme", None, Direction::Both, 1)
+                .unwrap(),
+            Subgraph::default()
+        );
+    }
+}
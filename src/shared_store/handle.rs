@@ -0,0 +1,227 @@
+use crate::env::RuntimeEnv;
+use crate::shared_store::SharedStore;
+use crate::shared_store::expiry::{self, is_expiry_key, ttl_key};
+use crate::storage::ConcurrentInMemoryStorage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+
+/// A cheaply cloneable handle to a concurrent in-memory shared store.
+///
+/// Unlike `SharedStore`, every operation takes `&self`, so clones of the handle
+/// can be moved into spawned tasks and read or write the same data in parallel.
+///
+/// Reads honour expiry deadlines set through [`SharedStore::set_with_ttl`] like
+/// `SharedStore` does, and writes clear them. The handle is a raw view for
+/// tooling such as the TUI, though: it doesn't run validators and lets
+/// reserved `__pf::` keys be written, so nodes should go through a
+/// `SharedStore` instead.
+#[derive(Debug, Clone, Default)]
+pub struct SharedStoreHandle {
+    storage: ConcurrentInMemoryStorage,
+    env: RuntimeEnv,
+}
+
+impl SharedStoreHandle {
+    /// Create a new handle with empty concurrent storage
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a handle sharing the given concurrent storage
+    pub fn from_storage(storage: ConcurrentInMemoryStorage) -> Self {
+        Self {
+            storage,
+            env: RuntimeEnv::default(),
+        }
+    }
+
+    /// Use `env`'s clock to check expiry deadlines
+    pub fn with_env(mut self, env: RuntimeEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Store a value with the given key, clearing any expiry
+    pub fn set(&self, key: String, value: Value) {
+        self.storage.delete(&ttl_key(&key));
+        self.storage.insert(key, value);
+    }
+
+    /// Retrieve a value by key, `None` if it has expired
+    pub fn get(&self, key: &str) -> Option<Value> {
+        if self.is_expired(key) {
+            return None;
+        }
+        self.storage.get_value(key)
+    }
+
+    /// Remove a value by key, returning it if it existed and hadn't expired
+    pub fn remove(&self, key: &str) -> Option<Value> {
+        let expired = self.is_expired(key);
+        self.storage.delete(&ttl_key(key));
+        self.storage.delete(key).filter(|_| !expired)
+    }
+
+    /// Atomically read-modify-write a single key, returning the new value
+    ///
+    /// `f` receives `None` for an expired key, and the key loses its expiry.
+    pub fn update<F>(&self, key: &str, f: F) -> Value
+    where
+        F: FnOnce(Option<&Value>) -> Value,
+    {
+        let expired = self.is_expired(key);
+        let value = self
+            .storage
+            .update(key, |current| f(current.filter(|_| !expired)));
+        self.storage.delete(&ttl_key(key));
+        value
+    }
+
+    /// Check if a key exists and hasn't expired
+    pub fn contains_key(&self, key: &str) -> bool {
+        !self.is_expired(key) && self.storage.has_key(key)
+    }
+
+    /// Get all keys, skipping expired keys and their deadlines
+    pub fn keys(&self) -> Vec<String> {
+        let now = expiry::now_ms(&self.env);
+        let mut keys = self.storage.all_keys();
+        keys.retain(|key| {
+            !is_expiry_key(key)
+                && !expiry::has_passed(self.storage.get_value(&ttl_key(key)).as_ref(), now)
+        });
+        keys
+    }
+
+    /// Clear all data
+    pub fn clear(&self) {
+        self.storage.clear_all();
+    }
+
+    /// Get the number of stored items, skipping expired keys and their deadlines
+    pub fn len(&self) -> usize {
+        self.keys().len()
+    }
+
+    /// Check if the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Store a serializable value (convenience method)
+    pub fn set_serializable<T: Serialize>(
+        &self,
+        key: String,
+        value: &T,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.set(key, serde_json::to_value(value)?);
+        Ok(())
+    }
+
+    /// Retrieve and deserialize a value (convenience method)
+    pub fn get_deserializable<T>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, Box<dyn Error + Send + Sync>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        match self.get(key) {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the underlying concurrent storage
+    pub fn storage(&self) -> &ConcurrentInMemoryStorage {
+        &self.storage
+    }
+
+    /// Create a `SharedStore` view over the same data, for running nodes and flows
+    pub fn to_shared_store(&self) -> SharedStore<ConcurrentInMemoryStorage> {
+        SharedStore::with_storage(self.storage.clone()).with_env(self.env.clone())
+    }
+
+    /// Whether `key` has a deadline that has passed
+    fn is_expired(&self, key: &str) -> bool {
+        let deadline = self.storage.get_value(&ttl_key(key));
+        expiry::has_passed(deadline.as_ref(), expiry::now_ms(&self.env))
+    }
+}
+
+impl SharedStore<ConcurrentInMemoryStorage> {
+    /// Creates a new SharedStore with concurrent in-memory storage
+    pub fn concurrent() -> Self {
+        Self::with_storage(ConcurrentInMemoryStorage::new())
+    }
+
+    /// Get a cloneable handle sharing this store's data
+    pub fn handle(&self) -> SharedStoreHandle {
+        SharedStoreHandle::from_storage(self.storage().clone()).with_env(self.env().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_handle_parallel_writers() {
+        let handle = SharedStoreHandle::new();
+
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let handle = handle.clone();
+                tokio::spawn(async move {
+                    for _ in 0..100 {
+                        handle.update("counter", |v| {
+                            json!(v.and_then(|v| v.as_u64()).unwrap_or(0) + 1)
+                        });
+                    }
+                    handle.set(format!("worker_{}", i), json!(true));
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(handle.get("counter"), Some(json!(800)));
+        assert_eq!(handle.len(), 9);
+    }
+
+    #[test]
+    fn test_handle_shares_data_with_shared_store() {
+        let mut store = SharedStore::concurrent();
+        let handle = store.handle();
+
+        store.set("from_store".to_string(), json!(1)).unwrap();
+        handle.set("from_handle".to_string(), json!(2));
+
+        assert_eq!(handle.get("from_store"), Some(json!(1)));
+        assert_eq!(store.get("from_handle").unwrap(), Some(json!(2)));
+    }
+
+    #[test]
+    fn test_handle_honours_expiry() {
+        let mut store = SharedStore::concurrent();
+        let handle = store.handle();
+
+        store
+            .set_with_ttl("stale".to_string(), json!(1), Duration::ZERO)
+            .unwrap();
+        store.set("kept".to_string(), json!(2)).unwrap();
+
+        assert_eq!(handle.get("stale"), None);
+        assert!(!handle.contains_key("stale"));
+        assert_eq!(handle.keys(), vec!["kept".to_string()]);
+        assert_eq!(handle.len(), 1);
+
+        // Writing through the handle clears the expiry
+        handle.set("stale".to_string(), json!(3));
+        assert_eq!(store.get("stale").unwrap(), Some(json!(3)));
+    }
+}
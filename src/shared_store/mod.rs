@@ -3,12 +3,31 @@
 //! This module provides both synchronous and asynchronous shared store implementations
 //! for data communication between nodes in PocketFlow workflows.
 
+pub mod access;
 pub mod async_store;
+pub mod checkpoint;
+pub mod error;
+mod expiry;
+pub mod graph;
+pub mod handle;
+pub mod query;
+pub mod session;
 pub mod sync;
+pub mod system;
+pub mod tasks;
 
 // Re-export the main types for convenience
+pub use access::{KeyAccess, KeyAccessPolicy};
 pub use async_store::AsyncSharedStore;
-pub use sync::{InMemorySharedStore, SharedStore};
+pub use checkpoint::{Checkpoint, CheckpointError, CheckpointManager, CheckpointView, KeyChange};
+pub use error::SharedStoreError;
+pub use graph::{Direction, GraphEdge, GraphNode, KnowledgeGraph, Subgraph};
+pub use handle::SharedStoreHandle;
+pub use query::{JsonPath, JsonPathError};
+pub use session::{Session, SessionError, SessionManager};
+pub use sync::{InMemorySharedStore, KeyValidator, SharedStore};
+pub use system::{ExperimentTag, Intent, IntentStatus, NodeFailure, SystemKeys, TokenUsage};
+pub use tasks::{Task, TaskQueue, TaskStatus};
 
 #[cfg(test)]
 mod tests {
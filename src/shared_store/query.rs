@@ -0,0 +1,631 @@
+//! JSONPath queries over store values
+//!
+//! The store acts as the root object, so the first name after `$` is a store
+//! key: `$.orders[?(@.total > 100)].id` selects the `id` of every order above
+//! 100. Supported syntax:
+//!
+//! - `.name`, `['name']`, `['a','b']` and `.*` / `[*]` for object members
+//! - `[0]`, `[-1]`, `[0,2]` and slices `[start:end:step]` for array items
+//! - `..name`, `..*` and `..[...]` for descendants
+//! - `[?(...)]` filters with `@`-relative paths, literals (numbers, quoted
+//!   strings, `true`, `false`, `null`), `==`, `!=`, `<`, `<=`, `>`, `>=`,
+//!   `&&`, `||`, `!` and parentheses; a bare path tests for existence
+
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Error raised for malformed JSONPath expressions
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid JSONPath at position {position}: {message}")]
+pub struct JsonPathError {
+    /// Character offset of the problem
+    pub position: usize,
+    pub message: String,
+}
+
+/// A parsed JSONPath expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Child(Selector),
+    Descendant(Selector),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Names(Vec<String>),
+    Indices(Vec<i64>),
+    Wildcard,
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: i64,
+    },
+    Filter(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Exists(Vec<Segment>),
+    Compare(Operand, CompareOp, Operand),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Path(Vec<Segment>),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl JsonPath {
+    /// Parse an expression starting with `$`
+    pub fn parse(source: &str) -> Result<Self, JsonPathError> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        parser.skip_whitespace();
+        parser.expect('$')?;
+        let segments = parser.segments()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("Unexpected character"));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            segments,
+        })
+    }
+
+    /// The expression as written
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Select the values matching the expression, in document order
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        select(&self.segments, root)
+    }
+
+    /// The store key the expression starts with, when it names exactly one
+    pub(crate) fn root_key(&self) -> Option<&str> {
+        match self.segments.first() {
+            Some(Segment::Child(Selector::Names(names))) if names.len() == 1 => Some(&names[0]),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = JsonPathError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Self::parse(source)
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+// ============================================================================
+// EVALUATION
+// ============================================================================
+
+fn select<'a>(segments: &[Segment], root: &'a Value) -> Vec<&'a Value> {
+    let mut current = vec![root];
+    for segment in segments {
+        let mut next = Vec::new();
+        for value in current {
+            match segment {
+                Segment::Child(selector) => selector.select(value, &mut next),
+                Segment::Descendant(selector) => {
+                    let mut stack = vec![value];
+                    while let Some(node) = stack.pop() {
+                        selector.select(node, &mut next);
+                        match node {
+                            Value::Array(items) => stack.extend(items.iter().rev()),
+                            Value::Object(map) => stack.extend(map.values().rev()),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(map) => map.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Selector {
+    fn select<'a>(&self, value: &'a Value, out: &mut Vec<&'a Value>) {
+        match self {
+            Selector::Names(names) => {
+                if let Value::Object(map) = value {
+                    out.extend(names.iter().filter_map(|name| map.get(name)));
+                }
+            }
+            Selector::Indices(indices) => {
+                if let Value::Array(items) = value {
+                    let len = items.len() as i64;
+                    for &index in indices {
+                        let index = if index < 0 { len + index } else { index };
+                        if (0..len).contains(&index) {
+                            out.push(&items[index as usize]);
+                        }
+                    }
+                }
+            }
+            Selector::Wildcard => out.extend(children(value)),
+            Selector::Slice { start, end, step } => {
+                if let Value::Array(items) = value {
+                    let len = items.len() as i64;
+                    let bound = |index: i64| {
+                        if index < 0 {
+                            (len + index).max(0)
+                        } else {
+                            index.min(len)
+                        }
+                    };
+                    let start = start.map_or(0, bound);
+                    let end = end.map_or(len, bound);
+                    let mut index = start;
+                    while index < end {
+                        out.push(&items[index as usize]);
+                        index += step;
+                    }
+                }
+            }
+            Selector::Filter(expr) => {
+                out.extend(children(value).into_iter().filter(|child| expr.test(child)));
+            }
+        }
+    }
+}
+
+impl Expr {
+    fn test(&self, current: &Value) -> bool {
+        match self {
+            Expr::Exists(path) => !select(path, current).is_empty(),
+            Expr::Compare(left, op, right) => {
+                let (left, right) = (left.resolve(current), right.resolve(current));
+                match (left, right) {
+                    (Some(left), Some(right)) => op.holds(left, right),
+                    (None, None) => matches!(op, CompareOp::Eq | CompareOp::Le | CompareOp::Ge),
+                    _ => *op == CompareOp::Ne,
+                }
+            }
+            Expr::And(left, right) => left.test(current) && right.test(current),
+            Expr::Or(left, right) => left.test(current) || right.test(current),
+            Expr::Not(inner) => !inner.test(current),
+        }
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, current: &'a Value) -> Option<&'a Value> {
+        match self {
+            Operand::Path(path) => select(path, current).into_iter().next(),
+            Operand::Literal(value) => Some(value),
+        }
+    }
+}
+
+impl CompareOp {
+    fn holds(self, left: &Value, right: &Value) -> bool {
+        let ordering = match (left, right) {
+            (Value::Number(a), Value::Number(b)) => a
+                .as_f64()
+                .zip(b.as_f64())
+                .and_then(|(a, b)| a.partial_cmp(&b)),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (a, b) if a == b => Some(Ordering::Equal),
+            _ => None,
+        };
+        match self {
+            CompareOp::Eq => ordering == Some(Ordering::Equal),
+            CompareOp::Ne => ordering != Some(Ordering::Equal),
+            CompareOp::Lt => ordering == Some(Ordering::Less),
+            CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            CompareOp::Gt => ordering == Some(Ordering::Greater),
+            CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+// ============================================================================
+// PARSING
+// ============================================================================
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: impl Into<String>) -> JsonPathError {
+        JsonPathError {
+            position: self.pos,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let len = s.chars().count();
+        if self.chars[self.pos..]
+            .iter()
+            .take(len)
+            .copied()
+            .eq(s.chars())
+        {
+            self.pos += len;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), JsonPathError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected '{}'", c)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, JsonPathError> {
+        let mut segments = Vec::new();
+        loop {
+            match self.peek() {
+                Some('.') => {
+                    self.pos += 1;
+                    if self.eat('.') {
+                        let selector = if self.peek() == Some('[') {
+                            self.bracket()?
+                        } else if self.eat('*') {
+                            Selector::Wildcard
+                        } else {
+                            Selector::Names(vec![self.name()?])
+                        };
+                        segments.push(Segment::Descendant(selector));
+                    } else if self.eat('*') {
+                        segments.push(Segment::Child(Selector::Wildcard));
+                    } else {
+                        segments.push(Segment::Child(Selector::Names(vec![self.name()?])));
+                    }
+                }
+                Some('[') => segments.push(Segment::Child(self.bracket()?)),
+                _ => return Ok(segments),
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, JsonPathError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ':'))
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error("Expected a member name"));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn bracket(&mut self) -> Result<Selector, JsonPathError> {
+        self.expect('[')?;
+        self.skip_whitespace();
+        let selector = match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Selector::Wildcard
+            }
+            Some('?') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                Selector::Filter(Box::new(self.or_expr()?))
+            }
+            Some('\'' | '"') => {
+                let mut names = vec![self.string()?];
+                while self.list_separator() {
+                    names.push(self.string()?);
+                }
+                Selector::Names(names)
+            }
+            _ => {
+                let first = self.optional_integer()?;
+                self.skip_whitespace();
+                if self.eat(':') {
+                    self.skip_whitespace();
+                    let end = self.optional_integer()?;
+                    self.skip_whitespace();
+                    let step = if self.eat(':') {
+                        self.skip_whitespace();
+                        self.optional_integer()?.unwrap_or(1)
+                    } else {
+                        1
+                    };
+                    if step < 1 {
+                        return Err(self.error("Slice step must be positive"));
+                    }
+                    Selector::Slice {
+                        start: first,
+                        end,
+                        step,
+                    }
+                } else {
+                    let first = first.ok_or_else(|| self.error("Expected a selector"))?;
+                    let mut indices = vec![first];
+                    while self.list_separator() {
+                        indices.push(
+                            self.optional_integer()?
+                                .ok_or_else(|| self.error("Expected an index"))?,
+                        );
+                    }
+                    Selector::Indices(indices)
+                }
+            }
+        };
+        self.skip_whitespace();
+        self.expect(']')?;
+        Ok(selector)
+    }
+
+    fn list_separator(&mut self) -> bool {
+        self.skip_whitespace();
+        let found = self.eat(',');
+        self.skip_whitespace();
+        found
+    }
+
+    fn optional_integer(&mut self) -> Result<Option<i64>, JsonPathError> {
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Ok(None);
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse()
+            .map(Some)
+            .map_err(|_| self.error(format!("Invalid integer '{}'", text)))
+    }
+
+    fn string(&mut self) -> Result<String, JsonPathError> {
+        let quote = self.peek().ok_or_else(|| self.error("Expected a string"))?;
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated string")),
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| self.error("Unterminated string"))?;
+                    text.push(escaped);
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn or_expr(&mut self) -> Result<Expr, JsonPathError> {
+        let mut expr = self.and_expr()?;
+        while self.eat_str("||") {
+            self.skip_whitespace();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, JsonPathError> {
+        let mut expr = self.unary_expr()?;
+        while self.eat_str("&&") {
+            self.skip_whitespace();
+            expr = Expr::And(Box::new(expr), Box::new(self.unary_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary_expr(&mut self) -> Result<Expr, JsonPathError> {
+        let expr = if self.peek() == Some('!') && self.chars.get(self.pos + 1) != Some(&'=') {
+            self.pos += 1;
+            self.skip_whitespace();
+            Expr::Not(Box::new(self.unary_expr()?))
+        } else if self.eat('(') {
+            self.skip_whitespace();
+            let expr = self.or_expr()?;
+            self.expect(')')?;
+            expr
+        } else {
+            let left = self.operand()?;
+            self.skip_whitespace();
+            match self.compare_op() {
+                Some(op) => {
+                    self.skip_whitespace();
+                    Expr::Compare(left, op, self.operand()?)
+                }
+                None => match left {
+                    Operand::Path(path) => Expr::Exists(path),
+                    Operand::Literal(_) => {
+                        return Err(self.error("Expected a comparison operator"));
+                    }
+                },
+            }
+        };
+        self.skip_whitespace();
+        Ok(expr)
+    }
+
+    fn compare_op(&mut self) -> Option<CompareOp> {
+        [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ]
+        .into_iter()
+        .find_map(|(token, op)| self.eat_str(token).then_some(op))
+    }
+
+    fn operand(&mut self) -> Result<Operand, JsonPathError> {
+        match self.peek() {
+            Some('@') => {
+                self.pos += 1;
+                Ok(Operand::Path(self.segments()?))
+            }
+            Some('\'' | '"') => Ok(Operand::Literal(Value::String(self.string()?))),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                serde_json::from_str::<serde_json::Number>(&text)
+                    .map(|n| Operand::Literal(Value::Number(n)))
+                    .map_err(|_| self.error(format!("Invalid number '{}'", text)))
+            }
+            _ => {
+                for (token, value) in [
+                    ("true", Value::Bool(true)),
+                    ("false", Value::Bool(false)),
+                    ("null", Value::Null),
+                ] {
+                    if self.eat_str(token) {
+                        return Ok(Operand::Literal(value));
+                    }
+                }
+                Err(self.error("Expected '@' or a literal"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn query(path: &str, root: &Value) -> Vec<Value> {
+        JsonPath::parse(path)
+            .unwrap()
+            .select(root)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_json_path_selectors() {
+        let root = json!({
+            "orders": [
+                {"id": 1, "total": 50, "tags": ["new"], "customer": {"name": "Ann"}},
+                {"id": 2, "total": 150, "customer": {"name": "Bob"}},
+                {"id": 3, "total": 300, "status": "open"}
+            ],
+            "meta": {"name": "Q3"}
+        });
+
+        assert_eq!(
+            query("$.orders[?(@.total > 100)].id", &root),
+            vec![json!(2), json!(3)]
+        );
+        assert_eq!(
+            query("$.orders[0].customer.name", &root),
+            vec![json!("Ann")]
+        );
+        assert_eq!(query("$['orders'][-1].id", &root), vec![json!(3)]);
+        assert_eq!(query("$.orders[0:2].id", &root), vec![json!(1), json!(2)]);
+        assert_eq!(query("$.orders[::2].id", &root), vec![json!(1), json!(3)]);
+        assert_eq!(query("$.orders[0,2].id", &root), vec![json!(1), json!(3)]);
+        assert_eq!(query("$.orders[*].customer.name", &root).len(), 2);
+        assert_eq!(query("$..name", &root).len(), 3);
+        assert_eq!(query("$.orders[?(@.status)].id", &root), vec![json!(3)]);
+        assert_eq!(
+            query(
+                "$.orders[?(@.total >= 150 && !(@.status == 'open'))].id",
+                &root
+            ),
+            vec![json!(2)]
+        );
+        assert_eq!(
+            query(
+                r#"$.orders[?(@.customer.name == "Ann" || @.id == 3)].id"#,
+                &root
+            ),
+            vec![json!(1), json!(3)]
+        );
+        assert!(query("$.missing.path", &root).is_empty());
+
+        assert!(JsonPath::parse("orders").is_err());
+        assert!(JsonPath::parse("$.orders[").is_err());
+        assert!(JsonPath::parse("$.orders[?(@.total >)]").is_err());
+        assert_eq!(JsonPath::parse("$.a b").unwrap_err().position, 4);
+    }
+}
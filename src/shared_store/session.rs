@@ -0,0 +1,282 @@
+//! Persistent conversation sessions over an async storage backend
+//!
+//! A [`SessionManager`] keeps one store per session ID in any
+//! [`AsyncStorageBackend`], such as a database. Each session is saved as a
+//! single JSON object under `session:{id}`, so loading and saving are one
+//! read or write on every backend.
+//!
+//! [`SessionManager::load_session`] waits until no other request holds the
+//! same session, so concurrent requests for one conversation run one after
+//! the other instead of overwriting each other's state. The lock is released
+//! when the [`Session`] is saved or dropped. Locks are per manager, so share
+//! one manager (it is cheap to clone) across all request handlers of a
+//! process.
+
+use crate::shared_store::{AsyncSharedStore, SharedStore};
+use crate::storage::{AsyncStorageBackend, InMemoryStorage, StorageBackend};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// Key prefix sessions are stored under by default
+pub const DEFAULT_SESSION_PREFIX: &str = "session:";
+
+/// Error raised while loading or saving a session
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError<E: std::error::Error + 'static> {
+    /// Error reported by the storage backend
+    #[error(transparent)]
+    Storage(E),
+
+    /// The stored session is not a JSON object
+    #[error("Session '{0}' is corrupt: expected a JSON object")]
+    Corrupt(String),
+}
+
+/// A loaded session, holding its lock until saved or dropped
+///
+/// Changes to [`Session::store`] are only persisted by
+/// [`SessionManager::save_session`]; dropping the session discards them.
+pub struct Session {
+    id: String,
+    is_new: bool,
+    /// The session's values
+    pub store: SharedStore<InMemoryStorage>,
+    _lock: OwnedMutexGuard<()>,
+}
+
+impl std::fmt::Debug for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Session")
+            .field("id", &self.id)
+            .field("is_new", &self.is_new)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Session {
+    /// The session ID
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Whether nothing was stored for this ID when it was loaded
+    pub fn is_new(&self) -> bool {
+        self.is_new
+    }
+}
+
+/// Loads and saves per-session stores with per-session locking
+pub struct SessionManager<B: AsyncStorageBackend> {
+    storage: AsyncSharedStore<B>,
+    prefix: String,
+    locks: Arc<std::sync::Mutex<HashMap<String, Weak<Mutex<()>>>>>,
+}
+
+impl<B: AsyncStorageBackend> Clone for SessionManager<B> {
+    fn clone(&self) -> Self {
+        Self {
+            storage: self.storage.clone(),
+            prefix: self.prefix.clone(),
+            locks: self.locks.clone(),
+        }
+    }
+}
+
+impl<B: AsyncStorageBackend> SessionManager<B> {
+    /// Create a manager storing sessions in `backend`
+    pub fn new(backend: B) -> Self {
+        Self {
+            storage: AsyncSharedStore::new(backend),
+            prefix: DEFAULT_SESSION_PREFIX.to_string(),
+            locks: Arc::default(),
+        }
+    }
+
+    /// Store sessions under another key prefix
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Generate a new random session ID
+    pub fn new_session_id() -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+
+    fn key(&self, id: &str) -> String {
+        format!("{}{}", self.prefix, id)
+    }
+
+    fn lock_for(&self, id: &str) -> Arc<Mutex<()>> {
+        let mut locks = self.locks.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(lock) = locks.get(id).and_then(Weak::upgrade) {
+            return lock;
+        }
+        locks.retain(|_, lock| lock.strong_count() > 0);
+        let lock = Arc::new(Mutex::new(()));
+        locks.insert(id.to_string(), Arc::downgrade(&lock));
+        lock
+    }
+
+    /// Load a session, waiting for any other holder of it to finish
+    ///
+    /// Unknown IDs give an empty, new session.
+    pub async fn load_session(&self, id: &str) -> Result<Session, SessionError<B::Error>> {
+        let lock = self.lock_for(id).lock_owned().await;
+        let values = match self
+            .storage
+            .get(&self.key(id))
+            .await
+            .map_err(SessionError::Storage)?
+        {
+            Some(Value::Object(values)) => Some(values),
+            Some(_) => return Err(SessionError::Corrupt(id.to_string())),
+            None => None,
+        };
+
+        // Written straight to the backend so engine-owned keys round-trip
+        let mut storage = InMemoryStorage::new();
+        let is_new = values.is_none();
+        storage
+            .set_many(values.unwrap_or_default().into_iter().collect())
+            .expect("in-memory storage does not fail");
+        Ok(Session {
+            id: id.to_string(),
+            is_new,
+            store: SharedStore::with_storage(storage),
+            _lock: lock,
+        })
+    }
+
+    /// Persist a session's values and release its lock
+    pub async fn save_session(&self, session: Session) -> Result<(), SessionError<B::Error>> {
+        let values: Map<String, Value> = session
+            .store
+            .iter()
+            .expect("in-memory storage does not fail")
+            .collect();
+        self.storage
+            .set(self.key(&session.id), Value::Object(values))
+            .await
+            .map_err(SessionError::Storage)
+    }
+
+    /// Delete a session, waiting for any holder of it to finish
+    ///
+    /// Returns whether the session existed.
+    pub async fn delete_session(&self, id: &str) -> Result<bool, SessionError<B::Error>> {
+        let _lock = self.lock_for(id).lock_owned().await;
+        Ok(self
+            .storage
+            .remove(&self.key(id))
+            .await
+            .map_err(SessionError::Storage)?
+            .is_some())
+    }
+
+    /// Check whether a session has been saved
+    pub async fn session_exists(&self, id: &str) -> Result<bool, SessionError<B::Error>> {
+        self.storage
+            .contains_key(&self.key(id))
+            .await
+            .map_err(SessionError::Storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorageError;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::time::Duration;
+
+    /// Minimal async backend over a map
+    #[derive(Default)]
+    struct MapBackend(HashMap<String, Value>);
+
+    #[async_trait]
+    impl AsyncStorageBackend for MapBackend {
+        type Error = InMemoryStorageError;
+
+        async fn set(&mut self, key: String, value: Value) -> Result<(), Self::Error> {
+            self.0.insert(key, value);
+            Ok(())
+        }
+
+        async fn get(&self, key: &str) -> Result<Option<Value>, Self::Error> {
+            Ok(self.0.get(key).cloned())
+        }
+
+        async fn remove(&mut self, key: &str) -> Result<Option<Value>, Self::Error> {
+            Ok(self.0.remove(key))
+        }
+
+        async fn contains_key(&self, key: &str) -> Result<bool, Self::Error> {
+            Ok(self.0.contains_key(key))
+        }
+
+        async fn keys(&self) -> Result<Vec<String>, Self::Error> {
+            Ok(self.0.keys().cloned().collect())
+        }
+
+        async fn clear(&mut self) -> Result<(), Self::Error> {
+            self.0.clear();
+            Ok(())
+        }
+
+        async fn len(&self) -> Result<usize, Self::Error> {
+            Ok(self.0.len())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_manager() {
+        let manager = SessionManager::new(MapBackend::default());
+        let id = SessionManager::<MapBackend>::new_session_id();
+
+        let mut session = manager.load_session(&id).await.unwrap();
+        assert!(session.is_new());
+        session
+            .store
+            .set("history".to_string(), json!(["hi"]))
+            .unwrap();
+        manager.save_session(session).await.unwrap();
+        assert!(manager.session_exists(&id).await.unwrap());
+
+        // A second request for the same session waits for the first to save
+        let first = manager.load_session(&id).await.unwrap();
+        let second = {
+            let manager = manager.clone();
+            let id = id.clone();
+            tokio::spawn(async move {
+                let mut session = manager.load_session(&id).await.unwrap();
+                let mut history = session.store.get("history").unwrap().unwrap();
+                history.as_array_mut().unwrap().push(json!("second"));
+                session.store.set("history".to_string(), history).unwrap();
+                manager.save_session(session).await.unwrap();
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!second.is_finished());
+        let mut first = first;
+        let mut history = first.store.get("history").unwrap().unwrap();
+        history.as_array_mut().unwrap().push(json!("first"));
+        first.store.set("history".to_string(), history).unwrap();
+        manager.save_session(first).await.unwrap();
+        second.await.unwrap();
+
+        let session = manager.load_session(&id).await.unwrap();
+        assert!(!session.is_new());
+        assert_eq!(
+            session.store.get("history").unwrap(),
+            Some(json!(["hi", "first", "second"]))
+        );
+        drop(session);
+
+        assert!(manager.delete_session(&id).await.unwrap());
+        assert!(manager.load_session(&id).await.unwrap().is_new());
+    }
+}
@@ -1,11 +1,68 @@
+use crate::env::RuntimeEnv;
+use crate::shared_store::expiry::{self, ExpiryState, is_expiry_key, ttl_key};
+use crate::shared_store::{
+    ExperimentTag, Intent, IntentStatus, JsonPath, KeyAccessPolicy, NodeFailure, SharedStoreError,
+    SystemKeys, TokenUsage,
+};
 use crate::storage::{InMemoryStorage, StorageBackend};
 use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A validation hook for a single key, returning a description of the problem
+/// when the value is rejected
+pub type KeyValidator = Arc<dyn Fn(&Value) -> Result<(), String> + Send + Sync>;
 
 /// SharedStore provides a type-safe interface for data communication between nodes
 /// in PocketFlow workflows. It can use different storage backends for flexibility.
-#[derive(Debug)]
+///
+/// Keys can be given a time-to-live with [`SharedStore::set_with_ttl`]. The
+/// deadline is stored in the backend under [`SystemKeys::TTL_PREFIX`] followed by
+/// the key, as wall-clock time from the store's [`RuntimeEnv`], so it survives
+/// restarts and every store over the same backend sees it. Expired keys are
+/// hidden from reads immediately and physically removed by
+/// [`SharedStore::purge_expired`] or the next write to the same key. Deadline keys
+/// are hidden from `keys`, `iter` and `len`. Until some store sets a TTL on the
+/// backend, which leaves [`SystemKeys::TTL_MARKER`] there, reads and writes skip
+/// the deadline lookups; a store checks for the marker on first use.
+///
+/// Validators can be registered per key with [`SharedStore::validate_key_with`] (or
+/// `SharedStore::validate_key` for JSON Schema, feature `schema-validation`).
+/// Writes that don't conform are rejected with [`SharedStoreError::ValidationFailed`].
+///
+/// Keys under [`SystemKeys::PREFIX`] are written by the engine only; user writes
+/// to them fail with [`SharedStoreError::ReservedKey`].
+///
+/// While a flow runs a node that has a [`KeyAccessPolicy`], writes to keys the
+/// node may not change fail with [`SharedStoreError::PermissionDenied`], and
+/// removals of them are skipped.
 pub struct SharedStore<S: StorageBackend> {
     storage: S,
+    env: RuntimeEnv,
+    expiry: ExpiryState,
+    validators: HashMap<String, KeyValidator>,
+    access: Option<AccessScope>,
+}
+
+/// The policy of the node currently running against a store
+struct AccessScope {
+    node_id: String,
+    policy: Arc<KeyAccessPolicy>,
+    /// First key the node was refused
+    denied: Option<String>,
+}
+
+impl<S: StorageBackend + fmt::Debug> fmt::Debug for SharedStore<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedStore")
+            .field("storage", &self.storage)
+            .field("env", &self.env)
+            .field("validators", &self.validators.keys().collect::<Vec<_>>())
+            .field("access", &self.access.as_ref().map(|scope| &scope.node_id))
+            .finish()
+    }
 }
 
 /// Type alias for the default in-memory SharedStore
@@ -14,7 +71,36 @@ pub type InMemorySharedStore = SharedStore<InMemoryStorage>;
 impl<S: StorageBackend> SharedStore<S> {
     /// Creates a new SharedStore with the provided storage backend
     pub fn with_storage(storage: S) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            env: RuntimeEnv::default(),
+            expiry: ExpiryState::default(),
+            validators: HashMap::new(),
+            access: None,
+        }
+    }
+
+    /// Use `env`'s clock for expiry deadlines
+    pub fn with_env(mut self, env: RuntimeEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Environment whose clock the store checks deadlines against
+    pub(crate) fn env(&self) -> &RuntimeEnv {
+        &self.env
+    }
+
+    /// Get a reference to the underlying storage backend
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Persist writes the backend has buffered, see [`StorageBackend::flush`]
+    ///
+    /// Flows call this after every node.
+    pub fn flush(&mut self) -> Result<(), S::Error> {
+        self.storage.flush()
     }
 
     /// Sets a value in the SharedStore.
@@ -23,8 +109,367 @@ impl<S: StorageBackend> SharedStore<S> {
     ///
     /// * `key` - The key (String) to associate with the value.
     /// * `value` - The `serde_json::Value` to store.
-    pub fn set(&mut self, key: String, value: Value) -> Result<(), S::Error> {
-        self.storage.set(key, value)
+    ///
+    /// Returns [`SharedStoreError::ValidationFailed`] if a validator is registered
+    /// for the key and rejects the value.
+    pub fn set(&mut self, key: String, value: Value) -> Result<(), SharedStoreError<S::Error>> {
+        self.check_access(&key)?;
+        self.check_value(&key, &value)?;
+        self.clear_expiry(&key)?;
+        Ok(self.storage.set(key, value)?)
+    }
+
+    /// Sets a value that expires after the given time-to-live.
+    ///
+    /// Once expired, the key is treated as absent by `get`, `contains_key`,
+    /// `keys` and `len`.
+    pub fn set_with_ttl(
+        &mut self,
+        key: String,
+        value: Value,
+        ttl: Duration,
+    ) -> Result<(), SharedStoreError<S::Error>> {
+        self.check_access(&key)?;
+        self.check_value(&key, &value)?;
+        let deadline = self.now_ms().saturating_add(ttl.as_millis() as u64);
+        self.storage.set_many(vec![
+            (SystemKeys::TTL_MARKER.to_string(), Value::Bool(true)),
+            (ttl_key(&key), Value::from(deadline)),
+            (key, value),
+        ])?;
+        self.expiry.mark_in_use();
+        Ok(())
+    }
+
+    /// Returns the remaining time-to-live of a key, or `None` if it has no expiry.
+    ///
+    /// Returns `Some(Duration::ZERO)` for keys that have expired but not been purged.
+    pub fn ttl(&self, key: &str) -> Result<Option<Duration>, S::Error> {
+        let now = self.now_ms();
+        Ok(self
+            .deadline(key)?
+            .map(|deadline| Duration::from_millis(deadline.saturating_sub(now))))
+    }
+
+    /// Removes the expiry from a key, making it permanent.
+    pub fn persist(&mut self, key: &str) -> Result<bool, S::Error> {
+        if !self.uses_ttl()? {
+            return Ok(false);
+        }
+        Ok(self.storage.remove(&ttl_key(key))?.is_some())
+    }
+
+    /// Removes every expired key from the backend, returning the removed keys.
+    pub fn purge_expired(&mut self) -> Result<Vec<String>, S::Error> {
+        let now = self.now_ms();
+        let mut expired: Vec<String> = self
+            .deadlines(&self.storage.keys()?)?
+            .into_iter()
+            .filter(|(_, deadline)| *deadline <= now)
+            .map(|(key, _)| key)
+            .collect();
+        expired.sort();
+
+        for key in &expired {
+            self.storage.remove(key)?;
+            self.storage.remove(&ttl_key(key))?;
+        }
+        Ok(expired)
+    }
+
+    /// Current wall-clock time of the store's environment, in milliseconds
+    fn now_ms(&self) -> u64 {
+        expiry::now_ms(&self.env)
+    }
+
+    /// Whether any key may carry an expiry, checking the backend on first use
+    fn uses_ttl(&self) -> Result<bool, S::Error> {
+        if let Some(in_use) = self.expiry.known() {
+            return Ok(in_use);
+        }
+        let in_use = self.storage.contains_key(SystemKeys::TTL_MARKER)?;
+        self.expiry.settle(in_use);
+        Ok(in_use)
+    }
+
+    /// Expiry deadline of a key
+    fn deadline(&self, key: &str) -> Result<Option<u64>, S::Error> {
+        if !self.uses_ttl()? {
+            return Ok(None);
+        }
+        Ok(self
+            .storage
+            .get(&ttl_key(key))?
+            .and_then(|deadline| deadline.as_u64()))
+    }
+
+    /// Expiry deadlines of the keys whose deadline keys are among `keys`
+    fn deadlines(&self, keys: &[String]) -> Result<HashMap<String, u64>, S::Error> {
+        let ttl_keys: Vec<&str> = keys
+            .iter()
+            .map(String::as_str)
+            .filter(|key| key.starts_with(SystemKeys::TTL_PREFIX))
+            .collect();
+        if ttl_keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+        self.expiry.mark_in_use();
+        let deadlines = self.storage.get_many(&ttl_keys)?;
+        Ok(expiry::deadlines(ttl_keys.into_iter().zip(deadlines)))
+    }
+
+    /// Drop the expiry of a key about to be overwritten
+    fn clear_expiry(&mut self, key: &str) -> Result<(), S::Error> {
+        if !self.uses_ttl()? {
+            return Ok(());
+        }
+        let ttl_key = ttl_key(key);
+        if self.storage.contains_key(&ttl_key)? {
+            self.storage.remove(&ttl_key)?;
+        }
+        Ok(())
+    }
+
+    /// Registers a validator for a key. Subsequent writes to the key are rejected
+    /// when the validator returns an error. Replaces any existing validator.
+    pub fn validate_key_with<F>(&mut self, key: impl Into<String>, validator: F)
+    where
+        F: Fn(&Value) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.validators.insert(key.into(), Arc::new(validator));
+    }
+
+    /// Registers a JSON Schema that all values written to a key must conform to.
+    ///
+    /// ```rust
+    /// # use pocketflow_rs::prelude::*;
+    /// # use serde_json::json;
+    /// let mut store = SharedStore::new();
+    /// store
+    ///     .validate_key("customer", json!({"type": "object", "required": ["id"]}))
+    ///     .unwrap();
+    ///
+    /// assert!(store.set("customer".to_string(), json!({"id": 7})).is_ok());
+    /// assert!(store.set("customer".to_string(), json!({"name": "x"})).is_err());
+    /// ```
+    #[cfg(feature = "schema-validation")]
+    pub fn validate_key(
+        &mut self,
+        key: impl Into<String>,
+        schema: Value,
+    ) -> Result<(), SharedStoreError<S::Error>> {
+        let key = key.into();
+        let validator =
+            jsonschema::validator_for(&schema).map_err(|e| SharedStoreError::InvalidSchema {
+                key: key.clone(),
+                message: e.to_string(),
+            })?;
+        self.validate_key_with(key, move |value| {
+            let errors: Vec<String> = validator
+                .iter_errors(value)
+                .map(|e| {
+                    let path = e.instance_path.to_string();
+                    if path.is_empty() {
+                        e.to_string()
+                    } else {
+                        format!("{} (at {})", e, path)
+                    }
+                })
+                .collect();
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors.join("; "))
+            }
+        });
+        Ok(())
+    }
+
+    /// Removes the validator registered for a key, returning whether one existed.
+    pub fn remove_validator(&mut self, key: &str) -> bool {
+        self.validators.remove(key).is_some()
+    }
+
+    /// Checks whether a validator is registered for a key.
+    pub fn has_validator(&self, key: &str) -> bool {
+        self.validators.contains_key(key)
+    }
+
+    fn check_value(&self, key: &str, value: &Value) -> Result<(), SharedStoreError<S::Error>> {
+        if SystemKeys::is_reserved(key) {
+            return Err(SharedStoreError::ReservedKey(key.to_string()));
+        }
+        match self.validators.get(key) {
+            Some(validator) => {
+                validator(value).map_err(|message| SharedStoreError::ValidationFailed {
+                    key: key.to_string(),
+                    message,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Restrict writes to what `policy` grants `node_id`, until
+    /// [`SharedStore::end_access_scope`]
+    pub(crate) fn begin_access_scope(&mut self, node_id: &str, policy: Arc<KeyAccessPolicy>) {
+        self.access = Some(AccessScope {
+            node_id: node_id.to_string(),
+            policy,
+            denied: None,
+        });
+    }
+
+    /// Lift the current node's policy, returning the first key it was refused
+    pub(crate) fn end_access_scope(&mut self) -> Option<String> {
+        self.access.take().and_then(|scope| scope.denied)
+    }
+
+    /// Whether the current node may write `key`
+    fn can_write(&self, key: &str) -> bool {
+        self.access
+            .as_ref()
+            .is_none_or(|scope| scope.policy.can_write(key))
+    }
+
+    /// Record a refused write to `key`
+    fn deny(&mut self, key: &str) {
+        if let Some(scope) = &mut self.access {
+            scope.denied.get_or_insert_with(|| key.to_string());
+        }
+    }
+
+    fn check_access(&mut self, key: &str) -> Result<(), SharedStoreError<S::Error>> {
+        if self.can_write(key) {
+            return Ok(());
+        }
+        self.deny(key);
+        let node_id = self.access.as_ref().map(|scope| scope.node_id.clone());
+        Err(SharedStoreError::PermissionDenied {
+            key: key.to_string(),
+            node_id: node_id.unwrap_or_default(),
+        })
+    }
+
+    /// Writes a reserved key, bypassing the namespace check and validators
+    pub(crate) fn set_system(&mut self, key: &str, value: Value) -> Result<(), S::Error> {
+        self.storage.set(key.to_string(), value)
+    }
+
+    /// Reads a reserved key, treating a value of the wrong shape as absent
+    fn get_system<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>, S::Error> {
+        Ok(self
+            .get(key)?
+            .and_then(|value| serde_json::from_value(value).ok()))
+    }
+
+    /// Node IDs visited by the most recent flow run on this store
+    pub fn execution_path(&self) -> Result<Vec<String>, S::Error> {
+        Ok(self
+            .get_system(SystemKeys::EXECUTION_PATH)?
+            .unwrap_or_default())
+    }
+
+    /// Message of the error that ended the most recent failed flow run
+    ///
+    /// Not cleared by later successful runs.
+    pub fn last_error(&self) -> Result<Option<String>, S::Error> {
+        self.get_system(SystemKeys::LAST_ERROR)
+    }
+
+    /// LLM usage recorded so far
+    pub fn usage(&self) -> Result<TokenUsage, S::Error> {
+        Ok(self.get_system(SystemKeys::USAGE)?.unwrap_or_default())
+    }
+
+    /// Adds to the recorded LLM usage, returning the new total
+    pub fn record_usage(&mut self, usage: TokenUsage) -> Result<TokenUsage, S::Error> {
+        let mut total = self.usage()?;
+        total += usage;
+        let value = serde_json::to_value(total).expect("TokenUsage serializes to JSON");
+        self.set_system(SystemKeys::USAGE, value)?;
+        Ok(total)
+    }
+
+    /// Experiment variant of the current run, if it is part of an experiment
+    pub fn experiment(&self) -> Result<Option<ExperimentTag>, S::Error> {
+        self.get_system(SystemKeys::EXPERIMENT)
+    }
+
+    /// Node failures recorded by flows with error routing, oldest first
+    pub fn errors(&self) -> Result<Vec<NodeFailure>, S::Error> {
+        Ok(self.get_system(SystemKeys::ERRORS)?.unwrap_or_default())
+    }
+
+    /// Appends to the recorded node failures
+    pub(crate) fn record_error(&mut self, failure: NodeFailure) -> Result<(), S::Error> {
+        let mut errors = self.errors()?;
+        errors.push(failure);
+        let value = serde_json::to_value(errors).expect("NodeFailure serializes to JSON");
+        self.set_system(SystemKeys::ERRORS, value)
+    }
+
+    /// Logged intents of side-effecting nodes, oldest first
+    pub fn intents(&self) -> Result<Vec<Intent>, S::Error> {
+        Ok(self.get_system(SystemKeys::INTENTS)?.unwrap_or_default())
+    }
+
+    /// Intents whose node never reported back, typically because the process
+    /// died mid-operation; each needs compensation or review
+    pub fn incomplete_intents(&self) -> Result<Vec<Intent>, S::Error> {
+        let mut intents = self.intents()?;
+        intents.retain(|intent| intent.status == IntentStatus::Pending);
+        Ok(intents)
+    }
+
+    /// Marks a pending intent as dealt with, returning whether there was one
+    pub fn resolve_intent(&mut self, id: &str) -> Result<bool, S::Error> {
+        let now = self.now_ms();
+        self.finish_intent(id, IntentStatus::Resolved, now)
+    }
+
+    /// Drops intents that are no longer pending from the log
+    pub fn prune_intents(&mut self) -> Result<(), S::Error> {
+        let intents = self.incomplete_intents()?;
+        self.write_intents(&intents)
+    }
+
+    /// Appends a pending intent
+    pub(crate) fn record_intent(&mut self, intent: Intent) -> Result<(), S::Error> {
+        let mut intents = self.intents()?;
+        intents.push(intent);
+        self.write_intents(&intents)
+    }
+
+    /// Moves a pending intent to `status`, returning whether there was one
+    pub(crate) fn finish_intent(
+        &mut self,
+        id: &str,
+        status: IntentStatus,
+        finished_at: u64,
+    ) -> Result<bool, S::Error> {
+        let mut intents = self.intents()?;
+        let Some(intent) = intents
+            .iter_mut()
+            .find(|intent| intent.id == id && intent.status == IntentStatus::Pending)
+        else {
+            return Ok(false);
+        };
+        intent.status = status;
+        intent.finished_at = Some(finished_at);
+        self.write_intents(&intents)?;
+        Ok(true)
+    }
+
+    fn write_intents(&mut self, intents: &[Intent]) -> Result<(), S::Error> {
+        let value = serde_json::to_value(intents).expect("Intent serializes to JSON");
+        self.set_system(SystemKeys::INTENTS, value)
+    }
+
+    /// Whether `key` has a deadline that has passed
+    fn is_expired(&self, key: &str) -> Result<bool, S::Error> {
+        let now = self.now_ms();
+        Ok(self.deadline(key)?.is_some_and(|deadline| deadline <= now))
     }
 
     /// Gets a value from the SharedStore.
@@ -38,7 +483,54 @@ impl<S: StorageBackend> SharedStore<S> {
     /// A `Result<Option<Value>, S::Error>` which is `Ok(Some(Value))` if the key exists,
     /// `Ok(None)` if it doesn't, or `Err` if there was a storage error.
     pub fn get(&self, key: &str) -> Result<Option<Value>, S::Error> {
-        self.storage.get(key)
+        Ok(self.get_many(&[key])?.pop().flatten())
+    }
+
+    /// Atomically replaces the value of `key` with `f` of it, returning the
+    /// value stored afterwards.
+    ///
+    /// `f` receives the current value (`None` if absent or expired) and returns
+    /// the new one, or `None` to leave the key unchanged. The new value is
+    /// checked like a `set`; a rejected value leaves the key unchanged. With a
+    /// backend whose [`StorageBackend::update`] is atomic, such as
+    /// `ConcurrentInMemoryStorage`, no other write to the key can interleave.
+    pub fn update<F>(
+        &mut self,
+        key: &str,
+        mut f: F,
+    ) -> Result<Option<Value>, SharedStoreError<S::Error>>
+    where
+        F: FnMut(Option<&Value>) -> Option<Value>,
+    {
+        self.check_access(key)?;
+        if SystemKeys::is_reserved(key) {
+            return Err(SharedStoreError::ReservedKey(key.to_string()));
+        }
+        let expired = self.is_expired(key)?;
+        let validator = self.validators.get(key);
+        let mut rejected = None;
+        let mut written = false;
+        let stored = self.storage.update(key, &mut |current| {
+            written = false;
+            rejected = None;
+            let value = f(current.filter(|_| !expired))?;
+            if let Some(Err(message)) = validator.map(|validator| validator(&value)) {
+                rejected = Some(message);
+                return None;
+            }
+            written = true;
+            Some(value)
+        })?;
+        if let Some(message) = rejected {
+            return Err(SharedStoreError::ValidationFailed {
+                key: key.to_string(),
+                message,
+            });
+        }
+        if written {
+            self.clear_expiry(key)?;
+        }
+        Ok(stored.filter(|_| written || !expired))
     }
 
     /// Removes a value from the SharedStore, returning it if it existed.
@@ -51,33 +543,190 @@ impl<S: StorageBackend> SharedStore<S> {
     ///
     /// A `Result<Option<Value>, S::Error>` which is `Ok(Some(Value))` if the key existed,
     /// `Ok(None)` if it didn't, or `Err` if there was a storage error.
+    ///
+    /// Keys the running node may not write are left in place and `Ok(None)`
+    /// is returned.
     pub fn remove(&mut self, key: &str) -> Result<Option<Value>, S::Error> {
-        self.storage.remove(key)
+        if !self.can_write(key) {
+            self.deny(key);
+            return Ok(None);
+        }
+        let expired = self.is_expired(key)?;
+        self.clear_expiry(key)?;
+        let value = self.storage.remove(key)?;
+        Ok(if expired { None } else { value })
     }
 
     /// Checks if a key exists in the SharedStore.
     pub fn contains_key(&self, key: &str) -> Result<bool, S::Error> {
+        if self.is_expired(key)? {
+            return Ok(false);
+        }
         self.storage.contains_key(key)
     }
 
     /// Gets all keys from the SharedStore.
     pub fn keys(&self) -> Result<Vec<String>, S::Error> {
-        self.storage.keys()
+        let mut keys = self.storage.keys()?;
+        let deadlines = self.deadlines(&keys)?;
+        let now = self.now_ms();
+        keys.retain(|key| {
+            !is_expiry_key(key) && deadlines.get(key).is_none_or(|deadline| *deadline > now)
+        });
+        Ok(keys)
+    }
+
+    /// Gets several values at once, in the order of `keys`.
+    ///
+    /// Uses the backend's batched read, so a networked backend makes a single
+    /// round trip. Missing and expired keys yield `None`.
+    pub fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Value>>, S::Error> {
+        if !self.uses_ttl()? {
+            return self.storage.get_many(keys);
+        }
+        // Values and deadlines in one batched read
+        let ttl_keys: Vec<String> = keys.iter().map(|key| ttl_key(key)).collect();
+        let mut batch = keys.to_vec();
+        batch.extend(ttl_keys.iter().map(String::as_str));
+        let mut values = self.storage.get_many(&batch)?;
+        let deadlines = values.split_off(keys.len());
+        let now = self.now_ms();
+        for (value, deadline) in values.iter_mut().zip(deadlines) {
+            if expiry::has_passed(deadline.as_ref(), now) {
+                *value = None;
+            }
+        }
+        Ok(values)
+    }
+
+    /// Sets several values at once.
+    ///
+    /// Every value is validated before anything is written, so a rejected value
+    /// leaves the store unchanged. Written keys lose any expiry, as with `set`.
+    pub fn set_many(
+        &mut self,
+        entries: Vec<(String, Value)>,
+    ) -> Result<(), SharedStoreError<S::Error>> {
+        for (key, value) in &entries {
+            self.check_access(key)?;
+            self.check_value(key, value)?;
+        }
+        for (key, _) in &entries {
+            self.clear_expiry(key)?;
+        }
+        Ok(self.storage.set_many(entries)?)
+    }
+
+    /// Evaluates a JSONPath expression with the store as the root object.
+    ///
+    /// The first name after `$` is a store key, so `$.orders[0].id` reads only
+    /// `orders`; expressions starting with a wildcard or descendant segment
+    /// scan every entry. See [`crate::shared_store::query`] for the syntax.
+    pub fn query(&self, path: &str) -> Result<Vec<Value>, SharedStoreError<S::Error>> {
+        let path = JsonPath::parse(path).map_err(SharedStoreError::InvalidQuery)?;
+        Ok(self.query_path(&path)?)
+    }
+
+    /// Like [`SharedStore::query`], returning only the first match.
+    pub fn query_first(&self, path: &str) -> Result<Option<Value>, SharedStoreError<S::Error>> {
+        Ok(self.query(path)?.into_iter().next())
+    }
+
+    /// Evaluates a pre-parsed JSONPath expression.
+    pub fn query_path(&self, path: &JsonPath) -> Result<Vec<Value>, S::Error> {
+        let root = match path.root_key() {
+            Some(key) => Value::Object(
+                self.get(key)?
+                    .map(|value| (key.to_string(), value))
+                    .into_iter()
+                    .collect(),
+            ),
+            None => Value::Object(self.iter()?.collect()),
+        };
+        Ok(path.select(&root).into_iter().cloned().collect())
+    }
+
+    /// Iterates over all key-value pairs, skipping expired keys.
+    ///
+    /// The entries are fetched from the backend up front.
+    pub fn iter(&self) -> Result<std::vec::IntoIter<(String, Value)>, S::Error> {
+        let mut entries = self.storage.entries()?;
+        let now = self.now_ms();
+        let expired: Vec<String> = entries
+            .iter()
+            .filter_map(|(key, deadline)| {
+                let key = key.strip_prefix(SystemKeys::TTL_PREFIX)?;
+                let expired = deadline.as_u64()? <= now;
+                expired.then(|| key.to_string())
+            })
+            .collect();
+        self.expiry
+            .observe(entries.iter().map(|(key, _)| key.as_str()));
+        entries.retain(|(key, _)| !is_expiry_key(key) && !expired.contains(key));
+        Ok(entries.into_iter())
+    }
+
+    /// Keeps only the entries for which `keep` returns `true`.
+    ///
+    /// Expired keys are removed as well, without being passed to `keep`.
+    /// Keys the running node may not write are kept.
+    pub fn retain<F>(&mut self, mut keep: F) -> Result<(), S::Error>
+    where
+        F: FnMut(&str, &Value) -> bool,
+    {
+        let now = self.now_ms();
+        let deadlines = self.deadlines(&self.storage.keys()?)?;
+        let policy = self.access.as_ref().map(|scope| scope.policy.clone());
+        let mut denied = None;
+        self.storage.retain(&mut |key, value| {
+            // Deadlines are dropped below, along with their keys
+            if is_expiry_key(key) {
+                return true;
+            }
+            if deadlines.get(key).is_some_and(|deadline| *deadline <= now) {
+                return false;
+            }
+            if keep(key, value) {
+                return true;
+            }
+            if policy.as_ref().is_some_and(|policy| !policy.can_write(key)) {
+                denied.get_or_insert_with(|| key.to_string());
+                return true;
+            }
+            false
+        })?;
+        if let Some(key) = denied {
+            self.deny(&key);
+        }
+        for key in deadlines.keys() {
+            if !self.storage.contains_key(key)? {
+                self.storage.remove(&ttl_key(key))?;
+            }
+        }
+        Ok(())
     }
 
     /// Clears all data from the SharedStore.
+    ///
+    /// Keys the running node may not write are kept.
     pub fn clear(&mut self) -> Result<(), S::Error> {
+        if self.access.is_some() {
+            return self.retain(|_, _| false);
+        }
         self.storage.clear()
     }
 
     /// Gets the number of items in the SharedStore.
     pub fn len(&self) -> Result<usize, S::Error> {
-        self.storage.len()
+        if !self.uses_ttl()? {
+            return self.storage.len();
+        }
+        Ok(self.keys()?.len())
     }
 
     /// Checks if the SharedStore is empty.
     pub fn is_empty(&self) -> Result<bool, S::Error> {
-        self.storage.is_empty()
+        Ok(self.len()? == 0)
     }
 
     /// Convenience method to set a serializable value
@@ -87,8 +736,7 @@ impl<S: StorageBackend> SharedStore<S> {
         value: T,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let json_value = serde_json::to_value(value)?;
-        self.storage
-            .set(key, json_value)
+        self.set(key, json_value)
             .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
     }
 
@@ -97,7 +745,7 @@ impl<S: StorageBackend> SharedStore<S> {
         &self,
         key: &str,
     ) -> Result<Option<T>, Box<dyn std::error::Error + Send + Sync>> {
-        match self.storage.get(key) {
+        match self.get(key) {
             Ok(Some(value)) => {
                 let deserialized = serde_json::from_value(value)?;
                 Ok(Some(deserialized))
@@ -106,6 +754,70 @@ impl<S: StorageBackend> SharedStore<S> {
             Err(e) => Err(Box::new(e) as Box<dyn std::error::Error + Send + Sync>),
         }
     }
+
+    /// Reads a structure kept under `key` by a store helper such as
+    /// `TaskQueue`, empty if the key is absent
+    pub(crate) fn get_structure<T: serde::de::DeserializeOwned + Default>(
+        &self,
+        key: &str,
+        kind: &str,
+    ) -> Result<T, SharedStoreError<S::Error>> {
+        parse_structure(key, kind, self.get(key)?.as_ref())
+    }
+
+    /// Applies `f` to a structure kept under `key` in one
+    /// [`SharedStore::update`]; `f` returns `None` to leave it unchanged
+    pub(crate) fn update_structure<T, R>(
+        &mut self,
+        key: &str,
+        kind: &str,
+        mut f: impl FnMut(&mut T) -> Option<R>,
+    ) -> Result<Option<R>, SharedStoreError<S::Error>>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + Default,
+    {
+        let mut output = None;
+        let mut invalid = None;
+        self.update(key, |current| {
+            output = None;
+            invalid = None;
+            let mut structure = match parse_structure(key, kind, current) {
+                Ok(structure) => structure,
+                Err(e) => {
+                    invalid = Some(e);
+                    return None;
+                }
+            };
+            output = Some(f(&mut structure)?);
+            serde_json::to_value(&structure).ok()
+        })?;
+        match invalid {
+            Some(e) => Err(e),
+            None => Ok(output),
+        }
+    }
+}
+
+/// Deserialize a helper's structure, reporting a value of the wrong shape as
+/// a validation failure
+fn parse_structure<T, E>(
+    key: &str,
+    kind: &str,
+    value: Option<&Value>,
+) -> Result<T, SharedStoreError<E>>
+where
+    T: serde::de::DeserializeOwned + Default,
+    E: std::error::Error,
+{
+    match value {
+        Some(value) => {
+            serde_json::from_value(value.clone()).map_err(|e| SharedStoreError::ValidationFailed {
+                key: key.to_string(),
+                message: format!("not a {}: {}", kind, e),
+            })
+        }
+        None => Ok(T::default()),
+    }
 }
 
 // Convenience constructors for common storage backends
@@ -218,6 +930,254 @@ mod tests {
         assert_eq!(store.len().unwrap(), 0);
     }
 
+    #[test]
+    fn test_shared_store_ttl_expiry_and_purge() {
+        let mut store = SharedStore::concurrent();
+        store.set("permanent".to_string(), json!(true)).unwrap();
+        // No deadlines are written until a TTL is set
+        assert!(
+            !store
+                .storage()
+                .contains_key(SystemKeys::TTL_MARKER)
+                .unwrap()
+        );
+        // A store that has already checked the backend notices TTLs set later
+        let other = store.handle().to_shared_store();
+        assert_eq!(other.len().unwrap(), 1);
+
+        store
+            .set_with_ttl("stale".to_string(), json!("old"), Duration::ZERO)
+            .unwrap();
+        store
+            .set_with_ttl("fresh".to_string(), json!("new"), Duration::from_secs(60))
+            .unwrap();
+
+        // Expired keys are hidden before they are purged
+        assert_eq!(store.get("stale").unwrap(), None);
+        assert!(!store.contains_key("stale").unwrap());
+        assert_eq!(store.len().unwrap(), 2);
+        assert!(store.ttl("fresh").unwrap().unwrap() > Duration::from_secs(30));
+        assert_eq!(store.ttl("permanent").unwrap(), None);
+        assert_eq!(other.keys().unwrap().len(), 2);
+        assert_eq!(other.get("stale").unwrap(), None);
+
+        assert_eq!(store.purge_expired().unwrap(), vec!["stale".to_string()]);
+        // "fresh", its deadline, the marker and "permanent"
+        assert_eq!(store.storage().len().unwrap(), 4);
+
+        // A plain set clears any previous expiry
+        store
+            .set_with_ttl("fresh".to_string(), json!("new"), Duration::ZERO)
+            .unwrap();
+        store.set("fresh".to_string(), json!("kept")).unwrap();
+        assert_eq!(store.get("fresh").unwrap(), Some(json!("kept")));
+        assert_eq!(store.ttl("fresh").unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "storage-file")]
+    fn test_shared_store_ttl_survives_reopening() {
+        use crate::env::ManualClock;
+
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("ttl.json");
+        let clock = Arc::new(ManualClock::new(std::time::SystemTime::now()));
+        let env = RuntimeEnv::new().with_clock(clock.clone());
+
+        let mut store =
+            SharedStore::with_storage(FileStorage::new(&file_path).unwrap()).with_env(env.clone());
+        store
+            .set_with_ttl(
+                "session".to_string(),
+                json!("token"),
+                Duration::from_secs(60),
+            )
+            .unwrap();
+        drop(store);
+
+        // A new store over the reopened file sees the same deadline
+        let mut reopened =
+            SharedStore::with_storage(FileStorage::new(&file_path).unwrap()).with_env(env);
+        assert_eq!(reopened.get("session").unwrap(), Some(json!("token")));
+        assert_eq!(reopened.keys().unwrap(), vec!["session".to_string()]);
+        clock.advance(Duration::from_secs(61));
+        assert_eq!(reopened.get("session").unwrap(), None);
+        assert_eq!(reopened.len().unwrap(), 0);
+        assert_eq!(
+            reopened.purge_expired().unwrap(),
+            vec!["session".to_string()]
+        );
+        assert_eq!(
+            reopened.storage().keys().unwrap(),
+            vec![SystemKeys::TTL_MARKER.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_shared_store_rejects_invalid_writes() {
+        let mut store = InMemorySharedStore::new();
+        store.validate_key_with("score", |value| {
+            if value.as_f64().is_some_and(|n| (0.0..=1.0).contains(&n)) {
+                Ok(())
+            } else {
+                Err("expected a number between 0 and 1".to_string())
+            }
+        });
+
+        store.set("score".to_string(), json!(0.5)).unwrap();
+        let err = store.set("score".to_string(), json!(7)).unwrap_err();
+        assert!(
+            matches!(err, SharedStoreError::ValidationFailed { ref key, .. } if key == "score")
+        );
+        assert!(err.to_string().contains("between 0 and 1"));
+
+        // The rejected write left the previous value untouched
+        assert_eq!(store.get("score").unwrap(), Some(json!(0.5)));
+
+        assert!(store.remove_validator("score"));
+        store.set("score".to_string(), json!(7)).unwrap();
+    }
+
+    #[test]
+    fn test_shared_store_bulk_operations() {
+        let mut store = InMemorySharedStore::new();
+        store.validate_key_with("count", |value| {
+            value
+                .as_u64()
+                .map(|_| ())
+                .ok_or_else(|| "expected a count".to_string())
+        });
+        store
+            .set_with_ttl("stale".to_string(), json!(0), Duration::ZERO)
+            .unwrap();
+
+        // One invalid value rejects the whole batch
+        let err = store
+            .set_many(vec![
+                ("a".to_string(), json!(1)),
+                ("count".to_string(), json!("many")),
+            ])
+            .unwrap_err();
+        assert!(matches!(err, SharedStoreError::ValidationFailed { .. }));
+        assert_eq!(store.get("a").unwrap(), None);
+
+        store
+            .set_many(vec![
+                ("a".to_string(), json!(1)),
+                ("b".to_string(), json!(2)),
+                ("count".to_string(), json!(2)),
+            ])
+            .unwrap();
+        assert_eq!(
+            store.get_many(&["b", "stale", "missing"]).unwrap(),
+            vec![Some(json!(2)), None, None]
+        );
+
+        let mut entries: Vec<_> = store.iter().unwrap().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            entries,
+            vec![
+                ("a".to_string(), json!(1)),
+                ("b".to_string(), json!(2)),
+                ("count".to_string(), json!(2)),
+            ]
+        );
+
+        // Retain also drops expired keys from the backend
+        store.retain(|key, _| key != "b").unwrap();
+        let mut keys = store.storage().keys().unwrap();
+        keys.sort();
+        assert_eq!(keys, vec![SystemKeys::TTL_MARKER, "a", "count"]);
+        assert_eq!(store.ttl("stale").unwrap(), None);
+    }
+
+    #[test]
+    fn test_shared_store_reserved_keys_and_usage() {
+        let mut store = InMemorySharedStore::new();
+        let err = store
+            .set_many(vec![
+                ("ok".to_string(), json!(1)),
+                (SystemKeys::USAGE.to_string(), json!({})),
+            ])
+            .unwrap_err();
+        assert!(matches!(err, SharedStoreError::ReservedKey(ref key) if key == SystemKeys::USAGE));
+        assert!(store.is_empty().unwrap());
+
+        assert_eq!(store.usage().unwrap(), TokenUsage::default());
+        let usage = TokenUsage {
+            requests: 1,
+            input_tokens: 10,
+            output_tokens: 5,
+            ..TokenUsage::default()
+        };
+        store.record_usage(usage).unwrap();
+        let total = store.record_usage(usage).unwrap();
+        assert_eq!(total.requests, 2);
+        assert_eq!(total.total_tokens(), 30);
+        assert_eq!(store.usage().unwrap(), total);
+        assert!(store.execution_path().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_shared_store_query() {
+        let mut store = InMemorySharedStore::new();
+        store
+            .set(
+                "orders".to_string(),
+                json!([{"id": 1, "total": 80}, {"id": 2, "total": 120}]),
+            )
+            .unwrap();
+        store
+            .set("user".to_string(), json!({"id": 7, "name": "Ann"}))
+            .unwrap();
+
+        assert_eq!(
+            store.query("$.orders[?(@.total > 100)].id").unwrap(),
+            vec![json!(2)]
+        );
+        assert_eq!(
+            store.query_first("$.user.name").unwrap(),
+            Some(json!("Ann"))
+        );
+        assert_eq!(store.query_first("$.missing[0]").unwrap(), None);
+        assert_eq!(store.query("$..id").unwrap().len(), 3);
+        assert!(matches!(
+            store.query("orders"),
+            Err(SharedStoreError::InvalidQuery(_))
+        ));
+    }
+
+    #[cfg(feature = "schema-validation")]
+    #[test]
+    fn test_shared_store_json_schema_validation() {
+        let mut store = InMemorySharedStore::new();
+        store
+            .validate_key(
+                "customer",
+                json!({
+                    "type": "object",
+                    "properties": {"id": {"type": "integer"}},
+                    "required": ["id"]
+                }),
+            )
+            .unwrap();
+
+        store
+            .set("customer".to_string(), json!({"id": 1, "name": "Ada"}))
+            .unwrap();
+        let err = store
+            .set("customer".to_string(), json!({"id": "one"}))
+            .unwrap_err();
+        assert!(err.to_string().contains("/id"));
+
+        let invalid = store.validate_key("other", json!({"type": 12}));
+        assert!(matches!(
+            invalid,
+            Err(SharedStoreError::InvalidSchema { .. })
+        ));
+    }
+
     #[cfg(feature = "storage-file")]
     #[test]
     fn test_file_shared_store() {
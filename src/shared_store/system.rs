@@ -0,0 +1,138 @@
+use crate::node::NodePhase;
+use serde::{Deserialize, Serialize};
+
+/// Keys reserved for values written by the engine
+///
+/// Every reserved key starts with [`SystemKeys::PREFIX`]. `SharedStore` rejects
+/// user writes to them with [`SharedStoreError::ReservedKey`]; read them
+/// through the typed accessors such as `SharedStore::execution_path`.
+///
+/// [`SharedStoreError::ReservedKey`]: crate::shared_store::SharedStoreError::ReservedKey
+pub struct SystemKeys;
+
+impl SystemKeys {
+    /// Prefix shared by all reserved keys
+    pub const PREFIX: &'static str = "__pf::";
+    /// Node IDs visited by the most recent flow run
+    pub const EXECUTION_PATH: &'static str = "__pf::execution_path";
+    /// Message of the error that ended the most recent failed flow run
+    pub const LAST_ERROR: &'static str = "__pf::last_error";
+    /// Accumulated LLM usage, see [`TokenUsage`]
+    pub const USAGE: &'static str = "__pf::usage";
+    /// Summary of the most recent nested flow run
+    pub const NESTED_FLOW_RESULT: &'static str = "__pf::nested_flow_result";
+    /// Experiment variant of the current run, see [`ExperimentTag`]
+    pub const EXPERIMENT: &'static str = "__pf::experiment";
+    /// Node failures routed by flows with error routing, oldest first, see
+    /// [`NodeFailure`]
+    pub const ERRORS: &'static str = "__pf::errors";
+    /// Operations of side-effecting nodes, logged before they run, see
+    /// [`Intent`]
+    pub const INTENTS: &'static str = "__pf::intents";
+    /// Prefix of the keys holding expiry deadlines, followed by the key that
+    /// expires; each holds milliseconds since the Unix epoch
+    pub const TTL_PREFIX: &'static str = "__pf::ttl::";
+    /// Present once any key has been given an expiry, so stores over a
+    /// backend without deadlines skip looking them up
+    pub const TTL_MARKER: &'static str = "__pf::ttl";
+
+    /// Check whether a key lies in the reserved namespace
+    pub fn is_reserved(key: &str) -> bool {
+        key.starts_with(Self::PREFIX)
+    }
+}
+
+/// LLM usage accumulated over a store's lifetime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// Number of completed requests
+    pub requests: u64,
+    /// Tokens sent to the model
+    pub input_tokens: u64,
+    /// Tokens generated by the model
+    pub output_tokens: u64,
+    /// Estimated cost in US dollars, 0 unless priced with a
+    /// [`PricingCatalog`](crate::pricing::PricingCatalog)
+    #[serde(default, skip_serializing_if = "is_free")]
+    pub cost: f64,
+}
+
+fn is_free(cost: &f64) -> bool {
+    *cost == 0.0
+}
+
+impl TokenUsage {
+    /// Total tokens in both directions
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.requests += other.requests;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Experiment variant a run was assigned to, written by `Experiment::run`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentTag {
+    pub experiment: String,
+    pub variant: String,
+    /// Settings of the variant, such as prompt version, model or temperature
+    pub config: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A failed node run, recorded by flows with error routing enabled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeFailure {
+    pub node_id: String,
+    pub phase: NodePhase,
+    pub message: String,
+    /// Number of times `exec` was retried before giving up
+    pub retries: usize,
+    /// Time of the failure in milliseconds since the Unix epoch
+    pub timestamp: u64,
+    /// Correlation ID of the run, see `ExecutionContext::correlation_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
+
+/// Outcome of a logged [`Intent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentStatus {
+    /// `exec` started but never reported back, e.g. because the process
+    /// crashed; the operation may or may not have happened
+    Pending,
+    /// `exec` succeeded
+    Completed,
+    /// `exec` returned an error
+    Failed,
+    /// A pending intent was reviewed or compensated, see
+    /// `SharedStore::resolve_intent`
+    Resolved,
+}
+
+/// An operation a side-effecting node was about to perform, logged before
+/// `exec` by flows with an intent log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intent {
+    /// Execution ID of the node run
+    pub id: String,
+    pub node_id: String,
+    /// What the node was about to do, as described by `NodeBackend::intent`
+    pub operation: serde_json::Value,
+    pub status: IntentStatus,
+    /// Time `exec` started, in milliseconds since the Unix epoch
+    pub started_at: u64,
+    /// Time the intent stopped being pending, in milliseconds since the Unix
+    /// epoch
+    pub finished_at: Option<u64>,
+    /// Correlation ID of the run, see `ExecutionContext::correlation_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
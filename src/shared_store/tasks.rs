@@ -0,0 +1,225 @@
+//! Priority task lists kept in the shared store
+//!
+//! A [`TaskQueue`] names a store key holding a list of tasks, such as the
+//! steps a planner agent hands to an executor. Tasks are taken highest
+//! priority first (oldest first among equals), marked in progress, and later
+//! marked done; done tasks stay in the list as a record of the work. Every
+//! change is a single [`SharedStore::update`], so workers sharing a backend
+//! whose `update` is atomic — `ConcurrentInMemoryStorage`, `FileStorage` or
+//! `RedisStorage`, see [`StorageBackend::update`] — never take the same task,
+//! and a persistent backend keeps the list across restarts.
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::shared_store::TaskQueue;
+//! # use serde_json::json;
+//! let queue = TaskQueue::new("plan");
+//! let mut store = SharedStore::new();
+//! queue.push(&mut store, json!("write tests"), 1).unwrap();
+//! let urgent = queue.push(&mut store, json!("fix build"), 5).unwrap();
+//!
+//! let task = queue.pop(&mut store).unwrap().unwrap();
+//! assert_eq!((task.id, task.payload), (urgent, json!("fix build")));
+//! assert!(queue.mark_done(&mut store, urgent).unwrap());
+//! assert_eq!(queue.remaining(&store).unwrap(), 1);
+//! ```
+
+use super::{SharedStore, SharedStoreError};
+use crate::StorageBackend;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Reverse;
+
+/// Progress of a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Pending,
+    /// Taken by [`TaskQueue::pop`]
+    InProgress,
+    Done,
+}
+
+/// A task in a [`TaskQueue`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    /// Identifier, increasing in the order tasks were pushed
+    pub id: u64,
+    pub priority: i64,
+    pub status: TaskStatus,
+    pub payload: Value,
+}
+
+/// Stored form of a queue
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QueueState {
+    last_id: u64,
+    tasks: Vec<Task>,
+}
+
+impl QueueState {
+    /// The pending task taken next
+    fn next_mut(&mut self) -> Option<&mut Task> {
+        self.tasks
+            .iter_mut()
+            .filter(|task| task.status == TaskStatus::Pending)
+            .max_by_key(|task| (task.priority, Reverse(task.id)))
+    }
+}
+
+/// A priority task list stored under one key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskQueue {
+    key: String,
+}
+
+impl TaskQueue {
+    /// Use the list under `key`; it's created by the first push
+    pub fn new(key: impl Into<String>) -> Self {
+        Self { key: key.into() }
+    }
+
+    /// The store key holding the list
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Add a pending task, returning its id
+    pub fn push<S: StorageBackend>(
+        &self,
+        store: &mut SharedStore<S>,
+        payload: Value,
+        priority: i64,
+    ) -> Result<u64, SharedStoreError<S::Error>> {
+        let id = self.modify(store, |state| {
+            let id = state.last_id + 1;
+            state.last_id = id;
+            state.tasks.push(Task {
+                id,
+                priority,
+                status: TaskStatus::Pending,
+                payload: payload.clone(),
+            });
+            Some(id)
+        })?;
+        Ok(id.unwrap_or_default())
+    }
+
+    /// Take the highest priority pending task, marking it in progress
+    pub fn pop<S: StorageBackend>(
+        &self,
+        store: &mut SharedStore<S>,
+    ) -> Result<Option<Task>, SharedStoreError<S::Error>> {
+        self.modify(store, |state| {
+            let task = state.next_mut()?;
+            task.status = TaskStatus::InProgress;
+            Some(task.clone())
+        })
+    }
+
+    /// The task [`TaskQueue::pop`] would take, without taking it
+    pub fn peek<S: StorageBackend>(
+        &self,
+        store: &SharedStore<S>,
+    ) -> Result<Option<Task>, SharedStoreError<S::Error>> {
+        Ok(self.state(store)?.next_mut().cloned())
+    }
+
+    /// Mark a task done, returning `false` if there is no such unfinished task
+    pub fn mark_done<S: StorageBackend>(
+        &self,
+        store: &mut SharedStore<S>,
+        id: u64,
+    ) -> Result<bool, SharedStoreError<S::Error>> {
+        let done = self.modify(store, |state| {
+            let task = state
+                .tasks
+                .iter_mut()
+                .find(|task| task.id == id && task.status != TaskStatus::Done)?;
+            task.status = TaskStatus::Done;
+            Some(())
+        })?;
+        Ok(done.is_some())
+    }
+
+    /// All tasks, in the order they were pushed
+    pub fn tasks<S: StorageBackend>(
+        &self,
+        store: &SharedStore<S>,
+    ) -> Result<Vec<Task>, SharedStoreError<S::Error>> {
+        Ok(self.state(store)?.tasks)
+    }
+
+    /// Number of tasks not yet done
+    pub fn remaining<S: StorageBackend>(
+        &self,
+        store: &SharedStore<S>,
+    ) -> Result<usize, SharedStoreError<S::Error>> {
+        Ok(self
+            .state(store)?
+            .tasks
+            .iter()
+            .filter(|task| task.status != TaskStatus::Done)
+            .count())
+    }
+
+    fn state<S: StorageBackend>(
+        &self,
+        store: &SharedStore<S>,
+    ) -> Result<QueueState, SharedStoreError<S::Error>> {
+        store.get_structure(&self.key, "task queue")
+    }
+
+    fn modify<S: StorageBackend, T>(
+        &self,
+        store: &mut SharedStore<S>,
+        f: impl FnMut(&mut QueueState) -> Option<T>,
+    ) -> Result<Option<T>, SharedStoreError<S::Error>> {
+        store.update_structure(&self.key, "task queue", f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ConcurrentInMemoryStorage;
+    use serde_json::json;
+
+    #[test]
+    fn test_workers_never_take_the_same_task() {
+        let storage = ConcurrentInMemoryStorage::new();
+        let queue = TaskQueue::new("tasks");
+        let mut store = SharedStore::with_storage(storage.clone());
+        for i in 0..100 {
+            queue.push(&mut store, json!(i), i % 3).unwrap();
+        }
+
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let (queue, storage) = (queue.clone(), storage.clone());
+                std::thread::spawn(move || {
+                    let mut store = SharedStore::with_storage(storage);
+                    let mut taken = Vec::new();
+                    while let Some(task) = queue.pop(&mut store).unwrap() {
+                        queue.mark_done(&mut store, task.id).unwrap();
+                        taken.push(task.id);
+                    }
+                    taken
+                })
+            })
+            .collect();
+        let mut taken: Vec<u64> = workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect();
+        taken.sort_unstable();
+        assert_eq!(taken, (1..=100).collect::<Vec<_>>());
+        assert_eq!(queue.remaining(&store).unwrap(), 0);
+
+        store.set("tasks".to_string(), json!([1, 2])).unwrap();
+        assert!(matches!(
+            queue.pop(&mut store),
+            Err(SharedStoreError::ValidationFailed { .. })
+        ));
+    }
+}
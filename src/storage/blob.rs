@@ -0,0 +1,337 @@
+//! Out-of-band storage for binary and large values
+//!
+//! Images, audio and documents shouldn't be stored as base64 strings in the
+//! shared store. Instead, put the bytes in a [`BlobStore`] and keep the small
+//! [`BlobRef`] it returns in the shared store:
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::storage::blob::{BlobRef, BlobStore, InMemoryBlobStore};
+//! # tokio_test::block_on(async {
+//! let blobs = InMemoryBlobStore::new();
+//! let mut store = SharedStore::new();
+//!
+//! let blob = blobs.put_bytes(vec![0x89, 0x50, 0x4e, 0x47], Some("image/png")).await.unwrap();
+//! store.set("image".to_string(), blob.to_value()).unwrap();
+//!
+//! let blob = BlobRef::from_value(&store.get("image").unwrap().unwrap()).unwrap();
+//! let bytes = blobs.get_bytes(&blob).await.unwrap().unwrap();
+//! assert_eq!(bytes.len(), 4);
+//! # });
+//! ```
+
+use super::InMemoryStorageError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+/// Marker key identifying a blob reference inside a JSON value
+pub const BLOB_REF_MARKER: &str = "$blob";
+
+/// A reference to bytes held in a [`BlobStore`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobRef {
+    /// Identifier of the blob within its store
+    pub id: String,
+    /// Size of the blob in bytes
+    pub size: u64,
+    /// Optional MIME type (e.g. `image/png`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+impl BlobRef {
+    /// Create a new blob reference with a fresh unique ID
+    pub fn new(size: u64, content_type: Option<&str>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            size,
+            content_type: content_type.map(|s| s.to_string()),
+        }
+    }
+
+    /// Convert to the JSON value stored in the shared store: `{"$blob": {...}}`
+    pub fn to_value(&self) -> Value {
+        let mut wrapper = serde_json::Map::new();
+        wrapper.insert(
+            BLOB_REF_MARKER.to_string(),
+            serde_json::to_value(self).unwrap_or(Value::Null),
+        );
+        Value::Object(wrapper)
+    }
+
+    /// Parse a blob reference from a shared store value, if it is one
+    pub fn from_value(value: &Value) -> Option<Self> {
+        serde_json::from_value(value.get(BLOB_REF_MARKER)?.clone()).ok()
+    }
+
+    /// Check whether a shared store value is a blob reference
+    pub fn is_blob_ref(value: &Value) -> bool {
+        Self::from_value(value).is_some()
+    }
+}
+
+/// Trait defining the interface for out-of-band binary storage
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// Error type returned by blob operations
+    type Error: Error + Send + Sync + 'static;
+
+    /// Store bytes and return a reference to them
+    async fn put_bytes(
+        &self,
+        bytes: Vec<u8>,
+        content_type: Option<&str>,
+    ) -> Result<BlobRef, Self::Error>;
+
+    /// Retrieve the bytes for a reference, or `None` if the blob doesn't exist
+    async fn get_bytes(&self, blob: &BlobRef) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Delete a blob, returning whether it existed
+    async fn delete(&self, blob: &BlobRef) -> Result<bool, Self::Error>;
+}
+
+/// Blob store that keeps bytes in memory, mainly for tests and short-lived flows
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryBlobStore {
+    blobs: Arc<RwLock<HashMap<String, Vec<u8>>>>,
+}
+
+impl InMemoryBlobStore {
+    /// Create a new in-memory blob store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl BlobStore for InMemoryBlobStore {
+    type Error = InMemoryStorageError;
+
+    async fn put_bytes(
+        &self,
+        bytes: Vec<u8>,
+        content_type: Option<&str>,
+    ) -> Result<BlobRef, Self::Error> {
+        let blob = BlobRef::new(bytes.len() as u64, content_type);
+        self.blobs
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(blob.id.clone(), bytes);
+        Ok(blob)
+    }
+
+    async fn get_bytes(&self, blob: &BlobRef) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self
+            .blobs
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&blob.id)
+            .cloned())
+    }
+
+    async fn delete(&self, blob: &BlobRef) -> Result<bool, Self::Error> {
+        Ok(self
+            .blobs
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&blob.id)
+            .is_some())
+    }
+}
+
+/// Blob store that writes each blob to its own file in a directory
+#[cfg(feature = "storage-file")]
+#[derive(Debug, Clone)]
+pub struct FileBlobStore {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(feature = "storage-file")]
+impl FileBlobStore {
+    /// Create a new file blob store rooted at `dir`, creating it if needed
+    pub fn new<P: AsRef<std::path::Path>>(dir: P) -> std::io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Get the directory blobs are written to
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+
+    fn blob_path(&self, blob: &BlobRef) -> Option<std::path::PathBuf> {
+        // IDs are generated by us; reject anything that could escape the directory
+        if blob.id.is_empty()
+            || !blob
+                .id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return None;
+        }
+        Some(self.dir.join(format!("{}.blob", blob.id)))
+    }
+}
+
+#[cfg(feature = "storage-file")]
+#[async_trait]
+impl BlobStore for FileBlobStore {
+    type Error = std::io::Error;
+
+    async fn put_bytes(
+        &self,
+        bytes: Vec<u8>,
+        content_type: Option<&str>,
+    ) -> Result<BlobRef, Self::Error> {
+        let blob = BlobRef::new(bytes.len() as u64, content_type);
+        let path = self
+            .blob_path(&blob)
+            .ok_or_else(|| std::io::Error::other("invalid blob id"))?;
+        let tmp_path = path.with_extension("blob.tmp");
+        tokio::fs::write(&tmp_path, &bytes).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+        Ok(blob)
+    }
+
+    async fn get_bytes(&self, blob: &BlobRef) -> Result<Option<Vec<u8>>, Self::Error> {
+        let Some(path) = self.blob_path(blob) else {
+            return Ok(None);
+        };
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn delete(&self, blob: &BlobRef) -> Result<bool, Self::Error> {
+        let Some(path) = self.blob_path(blob) else {
+            return Ok(false);
+        };
+        match tokio::fs::remove_file(path).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Blob store backed by S3 or any other `object_store` implementation
+#[cfg(feature = "storage-s3")]
+#[derive(Debug, Clone)]
+pub struct ObjectStoreBlobStore {
+    store: Arc<dyn object_store::ObjectStore>,
+    prefix: object_store::path::Path,
+}
+
+#[cfg(feature = "storage-s3")]
+impl ObjectStoreBlobStore {
+    /// Create a new blob store writing objects under `prefix`
+    pub fn new(store: Arc<dyn object_store::ObjectStore>, prefix: &str) -> Self {
+        Self {
+            store,
+            prefix: object_store::path::Path::from(prefix),
+        }
+    }
+
+    fn blob_path(&self, blob: &BlobRef) -> object_store::path::Path {
+        self.prefix
+            .child(object_store::path::PathPart::from(blob.id.as_str()))
+    }
+}
+
+#[cfg(feature = "storage-s3")]
+#[async_trait]
+impl BlobStore for ObjectStoreBlobStore {
+    type Error = super::ObjectStoreStorageError;
+
+    async fn put_bytes(
+        &self,
+        bytes: Vec<u8>,
+        content_type: Option<&str>,
+    ) -> Result<BlobRef, Self::Error> {
+        let blob = BlobRef::new(bytes.len() as u64, content_type);
+        self.store
+            .put(
+                &self.blob_path(&blob),
+                object_store::PutPayload::from(bytes),
+            )
+            .await?;
+        Ok(blob)
+    }
+
+    async fn get_bytes(&self, blob: &BlobRef) -> Result<Option<Vec<u8>>, Self::Error> {
+        match self.store.get(&self.blob_path(blob)).await {
+            Ok(result) => Ok(Some(result.bytes().await?.to_vec())),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, blob: &BlobRef) -> Result<bool, Self::Error> {
+        let path = self.blob_path(blob);
+        match self.store.head(&path).await {
+            Ok(_) => {
+                self.store.delete(&path).await?;
+                Ok(true)
+            }
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_in_memory_blob_store_roundtrip() {
+        let blobs = InMemoryBlobStore::new();
+        let blob = blobs
+            .put_bytes(b"hello".to_vec(), Some("text/plain"))
+            .await
+            .unwrap();
+
+        let value = blob.to_value();
+        assert!(BlobRef::is_blob_ref(&value));
+        assert!(!BlobRef::is_blob_ref(&json!({"id": "x"})));
+
+        let parsed = BlobRef::from_value(&value).unwrap();
+        assert_eq!(parsed, blob);
+        assert_eq!(parsed.size, 5);
+        assert_eq!(
+            blobs.get_bytes(&parsed).await.unwrap(),
+            Some(b"hello".to_vec())
+        );
+
+        assert!(blobs.delete(&parsed).await.unwrap());
+        assert_eq!(blobs.get_bytes(&parsed).await.unwrap(), None);
+    }
+
+    #[cfg(feature = "storage-file")]
+    #[tokio::test]
+    async fn test_file_blob_store_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let blobs = FileBlobStore::new(temp_dir.path().join("blobs")).unwrap();
+
+        let blob = blobs.put_bytes(vec![1, 2, 3], None).await.unwrap();
+        assert_eq!(blobs.get_bytes(&blob).await.unwrap(), Some(vec![1, 2, 3]));
+
+        let escaped = BlobRef {
+            id: "../escape".to_string(),
+            size: 0,
+            content_type: None,
+        };
+        assert_eq!(blobs.get_bytes(&escaped).await.unwrap(), None);
+
+        assert!(blobs.delete(&blob).await.unwrap());
+        assert!(!blobs.delete(&blob).await.unwrap());
+    }
+}
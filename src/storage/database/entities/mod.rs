@@ -1 +0,0 @@
-pub mod key_value_store;
@@ -0,0 +1,24 @@
+use crate::storage::database::DatabaseSchema;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        DatabaseSchema::default().create_prefix_index(manager).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let schema = DatabaseSchema::default();
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(schema.prefix_index_name())
+                    .table(schema.table())
+                    .to_owned(),
+            )
+            .await
+    }
+}
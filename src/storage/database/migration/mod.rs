@@ -1,12 +1,16 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20250531_000001_create_key_value_store;
+mod m20250601_000001_create_prefix_index;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20250531_000001_create_key_value_store::Migration)]
+        vec![
+            Box::new(m20250531_000001_create_key_value_store::Migration),
+            Box::new(m20250601_000001_create_prefix_index::Migration),
+        ]
     }
 }
@@ -0,0 +1,136 @@
+//! Change notifications for Postgres-backed `DatabaseStorage`
+//!
+//! Every write made through a Postgres `DatabaseStorage` issues a `NOTIFY` on a
+//! channel (see [`DEFAULT_NOTIFY_CHANNEL`]). A [`ChangeListener`] `LISTEN`s on that
+//! channel, which lets a flow in one process block until a node in another
+//! process writes the key it is waiting for.
+
+use sea_orm::sqlx::postgres::PgListener;
+use sea_orm::{DbErr, RuntimeErr};
+use serde::{Deserialize, Serialize};
+
+/// Channel used for change notifications unless configured otherwise
+pub const DEFAULT_NOTIFY_CHANNEL: &str = "pocketflow_changes";
+
+/// The kind of write that produced a change notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOp {
+    /// A key was inserted or updated
+    Set,
+    /// A key was removed
+    Remove,
+    /// All keys under the prefix were removed
+    Clear,
+}
+
+/// A change notification published by a `DatabaseStorage` write
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageChange {
+    /// Prefix of the storage that made the change
+    pub prefix: String,
+    /// Key that changed (without prefix), or `None` for a clear
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// The kind of change
+    pub op: ChangeOp,
+}
+
+impl StorageChange {
+    /// Check whether this change can affect the given key
+    pub fn affects(&self, key: &str) -> bool {
+        self.op == ChangeOp::Clear || self.key.as_deref() == Some(key)
+    }
+}
+
+/// Receives change notifications for one storage prefix
+///
+/// Created with `DatabaseStorage::subscribe`. The listener holds its own
+/// Postgres connection; notifications sent while it exists are buffered until
+/// [`ChangeListener::recv`] is called.
+pub struct ChangeListener {
+    listener: PgListener,
+    prefix: String,
+}
+
+impl std::fmt::Debug for ChangeListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChangeListener")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ChangeListener {
+    pub(crate) async fn connect(
+        pool: &sea_orm::sqlx::PgPool,
+        channel: &str,
+        prefix: &str,
+    ) -> Result<Self, DbErr> {
+        let mut listener = PgListener::connect_with(pool).await.map_err(conn_err)?;
+        listener.listen(channel).await.map_err(conn_err)?;
+        Ok(Self {
+            listener,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    /// Wait for the next change made to this listener's prefix
+    ///
+    /// Notifications from other prefixes, or with payloads that weren't written
+    /// by `DatabaseStorage`, are skipped.
+    pub async fn recv(&mut self) -> Result<StorageChange, DbErr> {
+        loop {
+            let notification = self.listener.recv().await.map_err(conn_err)?;
+            if let Ok(change) = serde_json::from_str::<StorageChange>(notification.payload())
+                && change.prefix == self.prefix
+            {
+                return Ok(change);
+            }
+        }
+    }
+
+    /// Wait for the next change that can affect the given key
+    pub async fn recv_for_key(&mut self, key: &str) -> Result<StorageChange, DbErr> {
+        loop {
+            let change = self.recv().await?;
+            if change.affects(key) {
+                return Ok(change);
+            }
+        }
+    }
+}
+
+fn conn_err(error: sea_orm::sqlx::Error) -> DbErr {
+    DbErr::Conn(RuntimeErr::SqlxError(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_change_payload_roundtrip() {
+        let change = StorageChange {
+            prefix: "pocketflow".to_string(),
+            key: Some("job:1".to_string()),
+            op: ChangeOp::Set,
+        };
+        let payload = serde_json::to_string(&change).unwrap();
+        assert_eq!(
+            payload,
+            r#"{"prefix":"pocketflow","key":"job:1","op":"set"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<StorageChange>(&payload).unwrap(),
+            change
+        );
+
+        assert!(change.affects("job:1"));
+        assert!(!change.affects("job:2"));
+
+        let clear: StorageChange =
+            serde_json::from_str(r#"{"prefix":"pocketflow","op":"clear"}"#).unwrap();
+        assert!(clear.affects("anything"));
+    }
+}
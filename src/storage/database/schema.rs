@@ -0,0 +1,187 @@
+//! Table layout of a [`DatabaseStorage`](super::DatabaseStorage)
+//!
+//! By default every storage shares the `key_value_store` table created by
+//! [`Migrator`](super::Migrator), with values serialized to text. A
+//! [`DatabaseSchema`] picks another table, so applications sharing a database
+//! keep their data apart, stores values in a native `JSONB` column on
+//! Postgres so SQL can query inside them, and can leave out the index on the
+//! prefix column. `DatabaseStorage::migrate` creates the table it describes.
+
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement};
+use sea_orm_migration::prelude::*;
+
+/// Table the default schema stores entries in
+pub const DEFAULT_TABLE_NAME: &str = "key_value_store";
+
+/// Columns of a key-value table
+#[derive(DeriveIden, Clone, Copy)]
+pub(crate) enum KeyValueColumn {
+    Key,
+    Value,
+    Prefix,
+    CreatedAt,
+    UpdatedAt,
+}
+
+/// Table name, value column type and indexes of a key-value table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseSchema {
+    table_name: String,
+    jsonb_values: bool,
+    prefix_index: bool,
+}
+
+impl Default for DatabaseSchema {
+    fn default() -> Self {
+        Self {
+            table_name: DEFAULT_TABLE_NAME.to_string(),
+            jsonb_values: false,
+            prefix_index: true,
+        }
+    }
+}
+
+impl DatabaseSchema {
+    /// Create the default schema
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store entries in another table
+    pub fn with_table_name(mut self, table_name: impl Into<String>) -> Self {
+        self.table_name = table_name.into();
+        self
+    }
+
+    /// Store values in a `JSONB` column on Postgres; other databases keep
+    /// serialized text
+    pub fn with_jsonb_values(mut self) -> Self {
+        self.jsonb_values = true;
+        self
+    }
+
+    /// Index the prefix column, which listing and clearing filter on; the
+    /// default schema does
+    pub fn with_prefix_index(mut self) -> Self {
+        self.prefix_index = true;
+        self
+    }
+
+    /// Leave the prefix column unindexed
+    pub fn without_prefix_index(mut self) -> Self {
+        self.prefix_index = false;
+        self
+    }
+
+    /// Get the table name
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Check whether values are stored as `JSONB` on Postgres
+    pub fn jsonb_values(&self) -> bool {
+        self.jsonb_values
+    }
+
+    /// Check whether the prefix column is indexed
+    pub fn prefix_index(&self) -> bool {
+        self.prefix_index
+    }
+
+    /// Name of the prefix index
+    pub fn prefix_index_name(&self) -> String {
+        format!("idx_{}_prefix", self.table_name)
+    }
+
+    pub(crate) fn table(&self) -> Alias {
+        Alias::new(&self.table_name)
+    }
+
+    /// Whether values are stored as `JSONB` on `backend`
+    pub(crate) fn uses_jsonb(&self, backend: DbBackend) -> bool {
+        self.jsonb_values && backend == DbBackend::Postgres
+    }
+
+    /// Create the table and its indexes unless they exist
+    pub(crate) async fn create(&self, connection: &DatabaseConnection) -> Result<(), DbErr> {
+        let manager = SchemaManager::new(connection);
+        let mut value = ColumnDef::new(KeyValueColumn::Value);
+        if self.uses_jsonb(manager.get_database_backend()) {
+            value.json_binary();
+        } else {
+            value.text();
+        }
+        manager
+            .create_table(
+                Table::create()
+                    .table(self.table())
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(KeyValueColumn::Key)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(value.not_null())
+                    .col(ColumnDef::new(KeyValueColumn::Prefix).string())
+                    .col(
+                        ColumnDef::new(KeyValueColumn::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(KeyValueColumn::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        if self.prefix_index {
+            self.create_prefix_index(&manager).await?;
+        }
+        Ok(())
+    }
+
+    /// Create the prefix index unless it exists
+    pub(crate) async fn create_prefix_index(
+        &self,
+        manager: &SchemaManager<'_>,
+    ) -> Result<(), DbErr> {
+        if self.has_prefix_index(manager).await? {
+            return Ok(());
+        }
+        manager
+            .create_index(
+                Index::create()
+                    .name(self.prefix_index_name())
+                    .table(self.table())
+                    .col(KeyValueColumn::Prefix)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    /// Whether the prefix index exists where `CREATE INDEX` can't check
+    async fn has_prefix_index(&self, manager: &SchemaManager<'_>) -> Result<bool, DbErr> {
+        // MySQL has no CREATE INDEX IF NOT EXISTS
+        if manager.get_database_backend() != DbBackend::MySql {
+            return Ok(false);
+        }
+        let existing = manager
+            .get_connection()
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::MySql,
+                "SELECT 1 FROM information_schema.statistics \
+                 WHERE table_schema = DATABASE() AND table_name = ? AND index_name = ?",
+                [
+                    self.table_name.clone().into(),
+                    self.prefix_index_name().into(),
+                ],
+            ))
+            .await?;
+        Ok(existing.is_some())
+    }
+}
@@ -0,0 +1,420 @@
+//! At-rest encryption for any storage backend
+//!
+//! [`EncryptedStorage`] wraps a sync or async backend and encrypts every value
+//! with AES-256-GCM before handing it on, so file, Redis or database
+//! deployments never persist user data in the clear. Keys stay readable so
+//! listing and prefix scans keep working.
+//!
+//! The encryption key is a [`Secret`] holding 32 bytes in standard base64
+//! (see [`EncryptedStorage::generate_key`]). Like other secrets it is resolved
+//! on each operation. Each value gets a random nonce and is bound to its key,
+//! so a ciphertext copied to another key fails to decrypt.
+//!
+//! ```rust
+//! # use pocketflow_rs::storage::{EncryptedStorage, InMemoryStorage, StorageBackend};
+//! # use serde_json::json;
+//! let key = EncryptedStorage::<InMemoryStorage>::generate_key();
+//! let mut storage = EncryptedStorage::new(InMemoryStorage::new(), key);
+//! storage.set("email".to_string(), json!("ada@example.com")).unwrap();
+//!
+//! assert_eq!(storage.get("email").unwrap(), Some(json!("ada@example.com")));
+//! let stored = storage.inner().get("email").unwrap().unwrap();
+//! assert!(stored.as_str().unwrap().starts_with("pfenc:v1:"));
+//! ```
+
+use crate::secrets::Secret;
+use crate::storage::{AsyncStorageBackend, StorageBackend};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::Value;
+use std::error::Error;
+
+/// Prefix of every stored ciphertext
+const PREFIX: &str = "pfenc:v1:";
+
+/// Length of an AES-GCM nonce in bytes
+const NONCE_LEN: usize = 12;
+
+/// Error types for encrypted storage operations
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptedStorageError<E: Error + 'static> {
+    /// Error reported by the inner backend
+    #[error(transparent)]
+    Storage(E),
+    /// The key could not be resolved or is not 32 base64-encoded bytes
+    #[error("Invalid encryption key: {0}")]
+    InvalidKey(String),
+    /// A value was not written by `EncryptedStorage`, or was written with
+    /// another encryption key
+    #[error("Failed to decrypt value of key '{0}'")]
+    Decryption(String),
+    /// A value could not be encrypted
+    #[error("Failed to encrypt value of key '{0}'")]
+    Encryption(String),
+}
+
+impl<E: Error + 'static> From<E> for EncryptedStorageError<E> {
+    fn from(error: E) -> Self {
+        EncryptedStorageError::Storage(error)
+    }
+}
+
+/// Storage wrapper encrypting values before they reach the inner backend
+#[derive(Debug)]
+pub struct EncryptedStorage<B> {
+    inner: B,
+    key: Secret,
+}
+
+impl<B> EncryptedStorage<B> {
+    /// Wrap `inner`, encrypting with the base64-encoded 256-bit `key`
+    pub fn new(inner: B, key: impl Into<Secret>) -> Self {
+        Self {
+            inner,
+            key: key.into(),
+        }
+    }
+
+    /// Generate a random key in the format expected by [`EncryptedStorage::new`]
+    pub fn generate_key() -> String {
+        BASE64.encode(Aes256Gcm::generate_key(OsRng))
+    }
+
+    /// The wrapped backend, holding ciphertexts
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Unwrap the backend
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    fn cipher<E: Error>(&self) -> Result<Aes256Gcm, EncryptedStorageError<E>> {
+        let encoded = self
+            .key
+            .expose()
+            .map_err(|e| EncryptedStorageError::InvalidKey(e.to_string()))?;
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| EncryptedStorageError::InvalidKey(e.to_string()))?;
+        Aes256Gcm::new_from_slice(&bytes).map_err(|_| {
+            EncryptedStorageError::InvalidKey(format!("expected 32 bytes, got {}", bytes.len()))
+        })
+    }
+
+    fn encrypt<E: Error>(
+        &self,
+        key: &str,
+        value: &Value,
+    ) -> Result<Value, EncryptedStorageError<E>> {
+        let cipher = self.cipher()?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let plaintext = serde_json::to_vec(value).expect("JSON values serialize");
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: key.as_bytes(),
+                },
+            )
+            .map_err(|_| EncryptedStorageError::Encryption(key.to_string()))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(Value::String(format!(
+            "{}{}",
+            PREFIX,
+            BASE64.encode(sealed)
+        )))
+    }
+
+    fn decrypt<E: Error>(
+        &self,
+        key: &str,
+        value: Value,
+    ) -> Result<Value, EncryptedStorageError<E>> {
+        let failed = || EncryptedStorageError::Decryption(key.to_string());
+        let sealed = value
+            .as_str()
+            .and_then(|text| text.strip_prefix(PREFIX))
+            .and_then(|encoded| BASE64.decode(encoded).ok())
+            .filter(|sealed| sealed.len() > NONCE_LEN)
+            .ok_or_else(failed)?;
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher()?
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: key.as_bytes(),
+                },
+            )
+            .map_err(|_| failed())?;
+        serde_json::from_slice(&plaintext).map_err(|_| failed())
+    }
+
+    fn decrypt_opt<E: Error>(
+        &self,
+        key: &str,
+        value: Option<Value>,
+    ) -> Result<Option<Value>, EncryptedStorageError<E>> {
+        value.map(|value| self.decrypt(key, value)).transpose()
+    }
+}
+
+impl<B: StorageBackend> StorageBackend for EncryptedStorage<B> {
+    type Error = EncryptedStorageError<B::Error>;
+
+    fn set(&mut self, key: String, value: Value) -> Result<(), Self::Error> {
+        let value = self.encrypt(&key, &value)?;
+        Ok(self.inner.set(key, value)?)
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Value>, Self::Error> {
+        let value = self.inner.get(key)?;
+        self.decrypt_opt(key, value)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<Option<Value>, Self::Error> {
+        let value = self.inner.remove(key)?;
+        self.decrypt_opt(key, value)
+    }
+
+    fn contains_key(&self, key: &str) -> Result<bool, Self::Error> {
+        Ok(self.inner.contains_key(key)?)
+    }
+
+    fn keys(&self) -> Result<Vec<String>, Self::Error> {
+        Ok(self.inner.keys()?)
+    }
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        Ok(self.inner.clear()?)
+    }
+
+    fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.inner.len()?)
+    }
+
+    fn health_check(&self) -> Result<(), Self::Error> {
+        Ok(self.inner.health_check()?)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(self.inner.flush()?)
+    }
+
+    fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Value>>, Self::Error> {
+        keys.iter()
+            .zip(self.inner.get_many(keys)?)
+            .map(|(key, value)| self.decrypt_opt(key, value))
+            .collect()
+    }
+
+    fn set_many(&mut self, entries: Vec<(String, Value)>) -> Result<(), Self::Error> {
+        let mut sealed = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            let value = self.encrypt(&key, &value)?;
+            sealed.push((key, value));
+        }
+        Ok(self.inner.set_many(sealed)?)
+    }
+
+    fn entries(&self) -> Result<Vec<(String, Value)>, Self::Error> {
+        let mut opened = Vec::new();
+        for (key, value) in self.inner.entries()? {
+            let value = self.decrypt(&key, value)?;
+            opened.push((key, value));
+        }
+        Ok(opened)
+    }
+
+    fn update(
+        &mut self,
+        key: &str,
+        f: &mut dyn FnMut(Option<&Value>) -> Option<Value>,
+    ) -> Result<Option<Value>, Self::Error> {
+        // Decrypt and encrypt inside the inner update, so it stays as atomic
+        // as the inner backend makes it
+        let codec = EncryptedStorage::new((), self.key.clone());
+        let mut failure = None;
+        let mut written = None;
+        let stored = self.inner.update(key, &mut |current| {
+            failure = None;
+            written = None;
+            let current = match codec.decrypt_opt(key, current.cloned()) {
+                Ok(current) => current,
+                Err(e) => {
+                    failure = Some(e);
+                    return None;
+                }
+            };
+            let value = f(current.as_ref())?;
+            match codec.encrypt(key, &value) {
+                Ok(sealed) => {
+                    written = Some(value);
+                    Some(sealed)
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    None
+                }
+            }
+        })?;
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        match written {
+            Some(value) => Ok(Some(value)),
+            None => self.decrypt_opt(key, stored),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<B: AsyncStorageBackend> AsyncStorageBackend for EncryptedStorage<B> {
+    type Error = EncryptedStorageError<B::Error>;
+
+    async fn set(&mut self, key: String, value: Value) -> Result<(), Self::Error> {
+        let value = self.encrypt(&key, &value)?;
+        Ok(self.inner.set(key, value).await?)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Value>, Self::Error> {
+        let value = self.inner.get(key).await?;
+        self.decrypt_opt(key, value)
+    }
+
+    async fn remove(&mut self, key: &str) -> Result<Option<Value>, Self::Error> {
+        let value = self.inner.remove(key).await?;
+        self.decrypt_opt(key, value)
+    }
+
+    async fn contains_key(&self, key: &str) -> Result<bool, Self::Error> {
+        Ok(self.inner.contains_key(key).await?)
+    }
+
+    async fn keys(&self) -> Result<Vec<String>, Self::Error> {
+        Ok(self.inner.keys().await?)
+    }
+
+    async fn clear(&mut self) -> Result<(), Self::Error> {
+        Ok(self.inner.clear().await?)
+    }
+
+    async fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.inner.len().await?)
+    }
+
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        Ok(self.inner.health_check().await?)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(self.inner.flush().await?)
+    }
+
+    async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Value>>, Self::Error> {
+        let values = self.inner.get_many(keys).await?;
+        keys.iter()
+            .zip(values)
+            .map(|(key, value)| self.decrypt_opt(key, value))
+            .collect()
+    }
+
+    async fn set_many(&mut self, entries: Vec<(String, Value)>) -> Result<(), Self::Error> {
+        let mut sealed = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            let value = self.encrypt(&key, &value)?;
+            sealed.push((key, value));
+        }
+        Ok(self.inner.set_many(sealed).await?)
+    }
+
+    async fn entries(&self) -> Result<Vec<(String, Value)>, Self::Error> {
+        let mut opened = Vec::new();
+        for (key, value) in self.inner.entries().await? {
+            let value = self.decrypt(&key, value)?;
+            opened.push((key, value));
+        }
+        Ok(opened)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InMemoryStorage;
+    use serde_json::json;
+
+    #[test]
+    fn test_encrypted_storage() {
+        let key = EncryptedStorage::<InMemoryStorage>::generate_key();
+        let mut storage = EncryptedStorage::new(InMemoryStorage::new(), key.clone());
+        let profile = json!({"name": "Ada", "cards": ["4111-1111"]});
+        storage.set("profile".to_string(), profile.clone()).unwrap();
+        storage
+            .set_many(vec![("note".to_string(), json!("private"))])
+            .unwrap();
+
+        // Nothing readable reaches the inner backend, and nonces differ
+        let stored = storage.inner().get("profile").unwrap().unwrap();
+        assert!(!stored.to_string().contains("Ada"));
+        storage.set("again".to_string(), profile.clone()).unwrap();
+        assert_ne!(storage.inner().get("again").unwrap(), Some(stored.clone()));
+
+        assert_eq!(storage.get("profile").unwrap(), Some(profile.clone()));
+        assert_eq!(
+            storage.get_many(&["note", "missing"]).unwrap(),
+            vec![Some(json!("private")), None]
+        );
+        assert_eq!(storage.entries().unwrap().len(), 3);
+        assert_eq!(storage.remove("note").unwrap(), Some(json!("private")));
+        assert_eq!(
+            storage
+                .update("note", &mut |current| {
+                    assert_eq!(current, None);
+                    Some(json!("sealed"))
+                })
+                .unwrap(),
+            Some(json!("sealed"))
+        );
+        assert!(
+            !storage
+                .inner()
+                .get("note")
+                .unwrap()
+                .unwrap()
+                .to_string()
+                .contains("sealed")
+        );
+        assert_eq!(
+            storage.update("note", &mut |_| None).unwrap(),
+            Some(json!("sealed"))
+        );
+        assert_eq!(storage.remove("note").unwrap(), Some(json!("sealed")));
+
+        // Ciphertexts are bound to their key and to the encryption key
+        let mut inner = storage.into_inner();
+        inner.set("moved".to_string(), stored).unwrap();
+        let storage = EncryptedStorage::new(inner, key);
+        assert!(matches!(
+            storage.get("moved"),
+            Err(EncryptedStorageError::Decryption(ref key)) if key == "moved"
+        ));
+        let other = EncryptedStorage::new(
+            storage.into_inner(),
+            EncryptedStorage::<InMemoryStorage>::generate_key(),
+        );
+        assert!(other.get("profile").is_err());
+        let invalid = EncryptedStorage::new(other.into_inner(), "c2hvcnQ=");
+        assert!(matches!(
+            invalid.get("profile"),
+            Err(EncryptedStorageError::InvalidKey(_))
+        ));
+    }
+}
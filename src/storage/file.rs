@@ -1,15 +1,63 @@
 use super::StorageBackend;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::fs;
-use std::io;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
 /// File-based storage backend that persists data to JSON files
-#[derive(Debug, Clone)]
+///
+/// Every write is persisted atomically (temp file + rename) while holding an
+/// exclusive advisory lock on a sidecar `<file>.lock` file. The on-disk state is
+/// re-read under the lock before applying changes, so several processes sharing
+/// the same path don't overwrite each other's keys.
+///
+/// In write-behind mode mutations are only applied in memory and queued until
+/// [`FileStorage::flush`] is called, which flows do after every node. Queued
+/// writes are not persisted on drop; dropping them logs a warning (feature
+/// `builtin-nodes`).
+///
+/// A clone starts without queued writes, so only the storage that made a
+/// write persists it.
+#[derive(Debug)]
 pub struct FileStorage {
     file_path: PathBuf,
     data: HashMap<String, Value>,
+    write_behind: bool,
+    pending: Vec<PendingOp>,
+}
+
+impl Clone for FileStorage {
+    fn clone(&self) -> Self {
+        Self {
+            file_path: self.file_path.clone(),
+            data: self.data.clone(),
+            write_behind: self.write_behind,
+            pending: Vec::new(),
+        }
+    }
+}
+
+/// A mutation waiting to be persisted in write-behind mode
+#[derive(Debug, Clone)]
+enum PendingOp {
+    Set(String, Value),
+    Remove(String),
+    Clear,
+}
+
+impl PendingOp {
+    fn apply(self, data: &mut HashMap<String, Value>) {
+        match self {
+            PendingOp::Set(key, value) => {
+                data.insert(key, value);
+            }
+            PendingOp::Remove(key) => {
+                data.remove(&key);
+            }
+            PendingOp::Clear => data.clear(),
+        }
+    }
 }
 
 /// Error type for file storage operations
@@ -55,34 +103,175 @@ impl FileStorage {
     /// Create a new file storage with the specified file path
     pub fn new<P: AsRef<Path>>(file_path: P) -> Result<Self, FileStorageError> {
         let file_path = file_path.as_ref().to_path_buf();
-        let data = if file_path.exists() {
-            let content = fs::read_to_string(&file_path)?;
-            if content.trim().is_empty() {
-                HashMap::new()
-            } else {
-                serde_json::from_str(&content)?
-            }
-        } else {
-            HashMap::new()
+        let data = {
+            let lock = Self::open_lock_file(&file_path)?;
+            lock.lock_shared()?;
+            Self::read_from_file(&file_path)?
         };
 
-        Ok(Self { file_path, data })
+        Ok(Self {
+            file_path,
+            data,
+            write_behind: false,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Enable or disable write-behind mode
+    ///
+    /// Disabling write-behind does not flush queued writes; call
+    /// [`FileStorage::flush`] first if needed.
+    pub fn with_write_behind(mut self, enabled: bool) -> Self {
+        self.write_behind = enabled;
+        self
+    }
+
+    /// Check if write-behind mode is enabled
+    pub fn is_write_behind(&self) -> bool {
+        self.write_behind
+    }
+
+    /// Check if there are queued writes that haven't been persisted yet
+    pub fn has_pending_writes(&self) -> bool {
+        !self.pending.is_empty()
     }
 
-    /// Save the current data to file
-    fn save_to_file(&self) -> Result<(), FileStorageError> {
-        let json_data = serde_json::to_string_pretty(&self.data)?;
-        fs::write(&self.file_path, json_data)?;
+    /// Get the path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Persist all queued writes to disk
+    pub fn flush(&mut self) -> Result<(), FileStorageError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let ops = std::mem::take(&mut self.pending);
+        self.commit(|data| ops.into_iter().for_each(|op| op.apply(data)))
+    }
+
+    /// Reload data from disk, picking up changes made by other processes
+    ///
+    /// Queued writes are re-applied on top of the reloaded state.
+    pub fn reload(&mut self) -> Result<(), FileStorageError> {
+        let lock = Self::open_lock_file(&self.file_path)?;
+        lock.lock_shared()?;
+        let mut data = Self::read_from_file(&self.file_path)?;
+        for op in self.pending.iter().cloned() {
+            op.apply(&mut data);
+        }
+        self.data = data;
+        Ok(())
+    }
+
+    /// Apply a mutation, persisting it immediately unless write-behind is enabled
+    fn write(&mut self, op: PendingOp) -> Result<(), FileStorageError> {
+        self.write_all(vec![op])
+    }
+
+    /// Apply mutations, persisting them in one write unless write-behind is enabled
+    fn write_all(&mut self, ops: Vec<PendingOp>) -> Result<(), FileStorageError> {
+        if self.write_behind {
+            for op in ops {
+                op.clone().apply(&mut self.data);
+                self.pending.push(op);
+            }
+            Ok(())
+        } else {
+            self.commit(|data| ops.into_iter().for_each(|op| op.apply(data)))
+        }
+    }
+
+    /// Update the on-disk state under an exclusive lock
+    fn commit(
+        &mut self,
+        update: impl FnOnce(&mut HashMap<String, Value>),
+    ) -> Result<(), FileStorageError> {
+        let lock = Self::open_lock_file(&self.file_path)?;
+        lock.lock()?;
+
+        let mut data = Self::read_from_file(&self.file_path)?;
+        update(&mut data);
+        Self::write_atomic(&self.file_path, &data)?;
+        self.data = data;
         Ok(())
     }
+
+    fn open_lock_file(file_path: &Path) -> Result<File, FileStorageError> {
+        let mut lock_name = file_path
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_default();
+        lock_name.push(".lock");
+        let lock_path = file_path.with_file_name(lock_name);
+        Ok(OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path)?)
+    }
+
+    fn read_from_file(file_path: &Path) -> Result<HashMap<String, Value>, FileStorageError> {
+        if !file_path.exists() {
+            return Ok(HashMap::new());
+        }
+        let content = fs::read_to_string(file_path)?;
+        if content.trim().is_empty() {
+            Ok(HashMap::new())
+        } else {
+            Ok(serde_json::from_str(&content)?)
+        }
+    }
+
+    /// Write to a temp file in the same directory, then rename it over the target
+    fn write_atomic(
+        file_path: &Path,
+        data: &HashMap<String, Value>,
+    ) -> Result<(), FileStorageError> {
+        let json_data = serde_json::to_string_pretty(data)?;
+
+        let mut tmp_name = file_path
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_default();
+        tmp_name.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        let tmp_path = file_path.with_file_name(tmp_name);
+
+        let result = (|| {
+            let mut tmp_file = File::create(&tmp_path)?;
+            tmp_file.write_all(json_data.as_bytes())?;
+            tmp_file.sync_all()?;
+            fs::rename(&tmp_path, file_path)
+        })();
+
+        if result.is_err() {
+            fs::remove_file(&tmp_path).ok();
+        }
+        Ok(result?)
+    }
+}
+
+impl Drop for FileStorage {
+    fn drop(&mut self) {
+        // Dropping queued writes is a bug in the caller, not something to
+        // paper over with a flush whose error nobody sees
+        if self.pending.is_empty() {
+            return;
+        }
+        #[cfg(feature = "builtin-nodes")]
+        tracing::warn!(
+            path = %self.file_path.display(),
+            writes = self.pending.len(),
+            "FileStorage dropped with unflushed writes"
+        );
+    }
 }
 
 impl StorageBackend for FileStorage {
     type Error = FileStorageError;
 
     fn set(&mut self, key: String, value: Value) -> Result<(), Self::Error> {
-        self.data.insert(key, value);
-        self.save_to_file()
+        self.write(PendingOp::Set(key, value))
     }
 
     fn get(&self, key: &str) -> Result<Option<Value>, Self::Error> {
@@ -90,8 +279,15 @@ impl StorageBackend for FileStorage {
     }
 
     fn remove(&mut self, key: &str) -> Result<Option<Value>, Self::Error> {
-        let result = self.data.remove(key);
-        self.save_to_file()?;
+        if self.write_behind {
+            let result = self.data.get(key).cloned();
+            self.write(PendingOp::Remove(key.to_string()))?;
+            return Ok(result);
+        }
+        // The previous value is the one on disk, which another process may
+        // have changed since this storage last read it
+        let mut result = None;
+        self.commit(|data| result = data.remove(key))?;
         Ok(result)
     }
 
@@ -104,13 +300,72 @@ impl StorageBackend for FileStorage {
     }
 
     fn clear(&mut self) -> Result<(), Self::Error> {
-        self.data.clear();
-        self.save_to_file()
+        self.write(PendingOp::Clear)
     }
 
     fn len(&self) -> Result<usize, Self::Error> {
         Ok(self.data.len())
     }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        FileStorage::flush(self)
+    }
+
+    fn update(
+        &mut self,
+        key: &str,
+        f: &mut dyn FnMut(Option<&Value>) -> Option<Value>,
+    ) -> Result<Option<Value>, Self::Error> {
+        if self.write_behind {
+            let current = self.data.get(key).cloned();
+            return match f(current.as_ref()) {
+                Some(value) => {
+                    self.write(PendingOp::Set(key.to_string(), value.clone()))?;
+                    Ok(Some(value))
+                }
+                None => Ok(current),
+            };
+        }
+        // Read the value on disk under the same lock as the write, so another
+        // process can't write the key in between
+        let mut result = None;
+        self.commit(|data| {
+            if let Some(value) = f(data.get(key)) {
+                data.insert(key.to_string(), value);
+            }
+            result = data.get(key).cloned();
+        })?;
+        Ok(result)
+    }
+
+    fn set_many(&mut self, entries: Vec<(String, Value)>) -> Result<(), Self::Error> {
+        let ops = entries
+            .into_iter()
+            .map(|(key, value)| PendingOp::Set(key, value))
+            .collect();
+        self.write_all(ops)
+    }
+
+    fn entries(&self) -> Result<Vec<(String, Value)>, Self::Error> {
+        Ok(self
+            .data
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&str, &Value) -> bool) -> Result<(), Self::Error> {
+        let ops: Vec<PendingOp> = self
+            .data
+            .iter()
+            .filter(|(key, value)| !keep(key, value))
+            .map(|(key, _)| PendingOp::Remove(key.clone()))
+            .collect();
+        if ops.is_empty() {
+            return Ok(());
+        }
+        self.write_all(ops)
+    }
 }
 
 #[cfg(test)]
@@ -168,4 +423,116 @@ mod tests {
         // Clean up
         fs::remove_file(&file_path).ok();
     }
+
+    #[test]
+    fn test_file_storage_atomic_write_leaves_no_temp_files() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("atomic.json");
+
+        let mut storage = FileStorage::new(&file_path).unwrap();
+        storage.set("key".to_string(), json!("value")).unwrap();
+        storage.remove("key").unwrap();
+        storage.set("key2".to_string(), json!(2)).unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["atomic.json", "atomic.json.lock"]);
+    }
+
+    #[test]
+    fn test_file_storage_shared_path_keeps_both_writers() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("shared.json");
+
+        let mut writer_a = FileStorage::new(&file_path).unwrap();
+        let mut writer_b = FileStorage::new(&file_path).unwrap();
+
+        writer_a.set("a".to_string(), json!(1)).unwrap();
+        writer_b.set("b".to_string(), json!(2)).unwrap();
+
+        // writer_b merged writer_a's key when it committed
+        assert_eq!(writer_b.get("a").unwrap(), Some(json!(1)));
+
+        writer_a.reload().unwrap();
+        assert_eq!(writer_a.get("b").unwrap(), Some(json!(2)));
+
+        let reopened = FileStorage::new(&file_path).unwrap();
+        assert_eq!(reopened.len().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_file_storage_update_reads_under_the_lock() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("counter.json");
+
+        let mut writer_a = FileStorage::new(&file_path).unwrap();
+        let mut writer_b = FileStorage::new(&file_path).unwrap();
+        let mut increment =
+            |current: Option<&Value>| Some(json!(current.and_then(Value::as_u64).unwrap_or(0) + 1));
+
+        writer_a.update("count", &mut increment).unwrap();
+        // writer_b hasn't reloaded, but still sees writer_a's increment
+        assert_eq!(
+            writer_b.update("count", &mut increment).unwrap(),
+            Some(json!(2))
+        );
+        assert_eq!(
+            writer_b.update("count", &mut |_| None).unwrap(),
+            Some(json!(2))
+        );
+    }
+
+    #[test]
+    fn test_file_storage_write_behind_flush() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("write_behind.json");
+
+        let mut storage = FileStorage::new(&file_path)
+            .unwrap()
+            .with_write_behind(true);
+        storage.set("key".to_string(), json!("value")).unwrap();
+
+        assert_eq!(storage.get("key").unwrap(), Some(json!("value")));
+        assert!(storage.has_pending_writes());
+        assert!(!file_path.exists());
+
+        storage.flush().unwrap();
+        assert!(!storage.has_pending_writes());
+
+        let reopened = FileStorage::new(&file_path).unwrap();
+        assert_eq!(reopened.get("key").unwrap(), Some(json!("value")));
+    }
+
+    #[test]
+    fn test_file_storage_clone_does_not_replay_writes() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("clone.json");
+
+        let mut storage = FileStorage::new(&file_path)
+            .unwrap()
+            .with_write_behind(true);
+        storage.set("key".to_string(), json!("old")).unwrap();
+        let clone = storage.clone();
+        assert!(!clone.has_pending_writes());
+        storage.flush().unwrap();
+
+        // Another writer changes the key after the flush
+        let mut other = FileStorage::new(&file_path).unwrap();
+        other.set("key".to_string(), json!("new")).unwrap();
+        drop(clone);
+        assert_eq!(
+            FileStorage::new(&file_path).unwrap().get("key").unwrap(),
+            Some(json!("new"))
+        );
+
+        // Removing returns the value on disk, not the stale in-memory one
+        let mut stale = FileStorage::new(&file_path).unwrap();
+        other.set("key".to_string(), json!("newest")).unwrap();
+        assert_eq!(stale.remove("key").unwrap(), Some(json!("newest")));
+        other.reload().unwrap();
+        assert_eq!(other.get("key").unwrap(), None);
+    }
 }
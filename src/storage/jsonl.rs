@@ -0,0 +1,482 @@
+use super::StorageBackend;
+use crate::redact::Redactor;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A single entry in the append-only log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JsonlEvent {
+    /// A key was set to a value
+    Set { key: String, value: Value, ts: u64 },
+    /// A key was removed
+    Remove { key: String, ts: u64 },
+    /// All keys were cleared
+    Clear { ts: u64 },
+}
+
+impl JsonlEvent {
+    /// Timestamp of the event in milliseconds since the Unix epoch
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            JsonlEvent::Set { ts, .. }
+            | JsonlEvent::Remove { ts, .. }
+            | JsonlEvent::Clear { ts } => *ts,
+        }
+    }
+
+    /// The key this event affects, if any
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            JsonlEvent::Set { key, .. } | JsonlEvent::Remove { key, .. } => Some(key),
+            JsonlEvent::Clear { .. } => None,
+        }
+    }
+
+    fn apply(&self, data: &mut HashMap<String, Value>) {
+        match self {
+            JsonlEvent::Set { key, value, .. } => {
+                data.insert(key.clone(), value.clone());
+            }
+            JsonlEvent::Remove { key, .. } => {
+                data.remove(key);
+            }
+            JsonlEvent::Clear { .. } => data.clear(),
+        }
+    }
+}
+
+/// Append-only storage backend that records every set/remove/clear as a JSON line
+///
+/// State is rebuilt by replaying the log on open. The log can be compacted
+/// explicitly with [`JsonlStorage::compact`] or automatically once it grows past
+/// a configured number of entries.
+///
+/// With [`JsonlStorage::with_redactor`] values are scrubbed before they are
+/// written to the log. Reads in the same process still see the raw values, but
+/// a store reopened from the log gets the redacted ones.
+#[derive(Debug)]
+pub struct JsonlStorage {
+    file_path: PathBuf,
+    data: HashMap<String, Value>,
+    log_entries: usize,
+    auto_compact_threshold: Option<usize>,
+    redactor: Option<Redactor>,
+}
+
+/// Error type for JSONL storage operations
+#[derive(Debug)]
+pub enum JsonlStorageError {
+    /// I/O error
+    Io(io::Error),
+    /// JSON serialization/deserialization error
+    Json(serde_json::Error),
+    /// A line in the middle of the log could not be parsed
+    Corrupted { line: usize, message: String },
+}
+
+impl std::fmt::Display for JsonlStorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonlStorageError::Io(e) => write!(f, "I/O error: {}", e),
+            JsonlStorageError::Json(e) => write!(f, "JSON error: {}", e),
+            JsonlStorageError::Corrupted { line, message } => {
+                write!(f, "Corrupted log at line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonlStorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonlStorageError::Io(e) => Some(e),
+            JsonlStorageError::Json(e) => Some(e),
+            JsonlStorageError::Corrupted { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for JsonlStorageError {
+    fn from(error: io::Error) -> Self {
+        JsonlStorageError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for JsonlStorageError {
+    fn from(error: serde_json::Error) -> Self {
+        JsonlStorageError::Json(error)
+    }
+}
+
+impl JsonlStorage {
+    /// Open (or create) a JSONL store at the given path and replay its log
+    pub fn new<P: AsRef<Path>>(file_path: P) -> Result<Self, JsonlStorageError> {
+        let file_path = file_path.as_ref().to_path_buf();
+        let mut data = HashMap::new();
+        let (events, torn_at) = Self::read_log(&file_path)?;
+        for event in &events {
+            event.apply(&mut data);
+        }
+
+        // Cut the torn line off so later appends don't land after garbage,
+        // keeping the history before it
+        if let Some(len) = torn_at {
+            let file = OpenOptions::new().write(true).open(&file_path)?;
+            file.set_len(len)?;
+            file.sync_data()?;
+        }
+
+        Ok(Self {
+            file_path,
+            data,
+            log_entries: events.len(),
+            auto_compact_threshold: None,
+            redactor: None,
+        })
+    }
+
+    /// Compact the log automatically once it holds more than `max_entries` lines
+    pub fn with_auto_compact(mut self, max_entries: usize) -> Self {
+        self.auto_compact_threshold = Some(max_entries);
+        self
+    }
+
+    /// Scrub values before they are written to the log
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Get the path of the log file
+    pub fn path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Number of entries currently in the log
+    pub fn log_len(&self) -> usize {
+        self.log_entries
+    }
+
+    /// Read every event in the log, oldest first
+    pub fn events(&self) -> Result<Vec<JsonlEvent>, JsonlStorageError> {
+        Ok(Self::read_log(&self.file_path)?.0)
+    }
+
+    /// Read the events that touched a specific key, oldest first
+    pub fn history(&self, key: &str) -> Result<Vec<JsonlEvent>, JsonlStorageError> {
+        Ok(self
+            .events()?
+            .into_iter()
+            .filter(|e| e.key().is_none_or(|k| k == key))
+            .collect())
+    }
+
+    /// Rewrite the log so it only contains one `set` entry per live key
+    pub fn compact(&mut self) -> Result<(), JsonlStorageError> {
+        let ts = now_millis();
+        let mut tmp_name = self
+            .file_path
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_default();
+        tmp_name.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        let tmp_path = self.file_path.with_file_name(tmp_name);
+
+        let result = (|| -> Result<(), JsonlStorageError> {
+            let mut tmp_file = File::create(&tmp_path)?;
+            let mut keys: Vec<&String> = self.data.keys().collect();
+            keys.sort();
+            for key in keys {
+                let event = JsonlEvent::Set {
+                    key: key.clone(),
+                    value: self.data[key].clone(),
+                    ts,
+                };
+                writeln!(tmp_file, "{}", self.serialize(&event)?)?;
+            }
+            tmp_file.sync_all()?;
+            fs::rename(&tmp_path, &self.file_path)?;
+            Ok(())
+        })();
+
+        if result.is_err() {
+            fs::remove_file(&tmp_path).ok();
+        }
+        result?;
+
+        self.log_entries = self.data.len();
+        Ok(())
+    }
+
+    fn append(&mut self, event: JsonlEvent) -> Result<(), JsonlStorageError> {
+        self.append_all(vec![event])
+    }
+
+    /// Append events with a single write and sync
+    fn append_all(&mut self, events: Vec<JsonlEvent>) -> Result<(), JsonlStorageError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut lines = String::new();
+        for event in &events {
+            lines.push_str(&self.serialize(event)?);
+            lines.push('\n');
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file_path)?;
+        file.write_all(lines.as_bytes())?;
+        file.sync_data()?;
+
+        for event in &events {
+            event.apply(&mut self.data);
+        }
+        self.log_entries += events.len();
+
+        if let Some(threshold) = self.auto_compact_threshold
+            && self.log_entries > threshold
+        {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Serialize an event as a log line, redacting its value
+    fn serialize(&self, event: &JsonlEvent) -> Result<String, JsonlStorageError> {
+        match (&self.redactor, event) {
+            (Some(redactor), JsonlEvent::Set { key, value, ts }) => {
+                Ok(serde_json::to_string(&JsonlEvent::Set {
+                    key: key.clone(),
+                    value: redactor.redact_entry(key, value),
+                    ts: *ts,
+                })?)
+            }
+            _ => Ok(serde_json::to_string(event)?),
+        }
+    }
+
+    /// Read the log, returning the events and, if a torn final line was
+    /// skipped, the length in bytes of the log before it
+    fn read_log(file_path: &Path) -> Result<(Vec<JsonlEvent>, Option<u64>), JsonlStorageError> {
+        if !file_path.exists() {
+            return Ok((Vec::new(), None));
+        }
+
+        let mut reader = BufReader::new(File::open(file_path)?);
+        let mut events = Vec::new();
+        let mut line = Vec::new();
+        let mut line_number = 0;
+        let mut offset = 0;
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            if read == 0 {
+                break;
+            }
+            line_number += 1;
+            let line_start = offset;
+            offset += read as u64;
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            match serde_json::from_slice(&line) {
+                Ok(event) => events.push(event),
+                // A torn final line means the process died mid-append; ignore it
+                Err(_) if reader.fill_buf()?.is_empty() => return Ok((events, Some(line_start))),
+                Err(e) => {
+                    return Err(JsonlStorageError::Corrupted {
+                        line: line_number,
+                        message: e.to_string(),
+                    });
+                }
+            }
+        }
+        Ok((events, None))
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl StorageBackend for JsonlStorage {
+    type Error = JsonlStorageError;
+
+    fn set(&mut self, key: String, value: Value) -> Result<(), Self::Error> {
+        self.append(JsonlEvent::Set {
+            key,
+            value,
+            ts: now_millis(),
+        })
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Value>, Self::Error> {
+        Ok(self.data.get(key).cloned())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<Option<Value>, Self::Error> {
+        let existing = self.data.get(key).cloned();
+        if existing.is_some() {
+            self.append(JsonlEvent::Remove {
+                key: key.to_string(),
+                ts: now_millis(),
+            })?;
+        }
+        Ok(existing)
+    }
+
+    fn contains_key(&self, key: &str) -> Result<bool, Self::Error> {
+        Ok(self.data.contains_key(key))
+    }
+
+    fn keys(&self) -> Result<Vec<String>, Self::Error> {
+        Ok(self.data.keys().cloned().collect())
+    }
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.append(JsonlEvent::Clear { ts: now_millis() })
+    }
+
+    fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.data.len())
+    }
+
+    fn set_many(&mut self, entries: Vec<(String, Value)>) -> Result<(), Self::Error> {
+        let ts = now_millis();
+        self.append_all(
+            entries
+                .into_iter()
+                .map(|(key, value)| JsonlEvent::Set { key, value, ts })
+                .collect(),
+        )
+    }
+
+    fn entries(&self) -> Result<Vec<(String, Value)>, Self::Error> {
+        Ok(self
+            .data
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&str, &Value) -> bool) -> Result<(), Self::Error> {
+        let ts = now_millis();
+        let events = self
+            .data
+            .iter()
+            .filter(|(key, value)| !keep(key, value))
+            .map(|(key, _)| JsonlEvent::Remove {
+                key: key.clone(),
+                ts,
+            })
+            .collect();
+        self.append_all(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_jsonl_storage_replay() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("events.jsonl");
+
+        {
+            let mut storage = JsonlStorage::new(&file_path).unwrap();
+            storage.set("a".to_string(), json!(1)).unwrap();
+            storage.set("b".to_string(), json!(2)).unwrap();
+            storage.set("a".to_string(), json!(3)).unwrap();
+            storage.remove("b").unwrap();
+        }
+
+        let storage = JsonlStorage::new(&file_path).unwrap();
+        assert_eq!(storage.get("a").unwrap(), Some(json!(3)));
+        assert_eq!(storage.get("b").unwrap(), None);
+        assert_eq!(storage.log_len(), 4);
+
+        let history = storage.history("a").unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(matches!(&history[0], JsonlEvent::Set { value, .. } if *value == json!(1)));
+    }
+
+    #[test]
+    fn test_jsonl_storage_compaction() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("compact.jsonl");
+
+        let mut storage = JsonlStorage::new(&file_path).unwrap().with_auto_compact(5);
+        for i in 0..6 {
+            storage.set("counter".to_string(), json!(i)).unwrap();
+        }
+
+        // The sixth write pushed the log over the threshold
+        assert_eq!(storage.log_len(), 1);
+        let content = fs::read_to_string(&file_path).unwrap();
+        assert_eq!(content.lines().count(), 1);
+
+        let reopened = JsonlStorage::new(&file_path).unwrap();
+        assert_eq!(reopened.get("counter").unwrap(), Some(json!(5)));
+    }
+
+    #[test]
+    fn test_jsonl_storage_ignores_torn_last_line() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("torn.jsonl");
+
+        {
+            let mut storage = JsonlStorage::new(&file_path).unwrap();
+            storage.set("key".to_string(), json!("draft")).unwrap();
+            storage.set("key".to_string(), json!("value")).unwrap();
+        }
+        let mut file = OpenOptions::new().append(true).open(&file_path).unwrap();
+        write!(file, "{{\"op\":\"set\",\"key\":\"partial").unwrap();
+
+        let mut storage = JsonlStorage::new(&file_path).unwrap();
+        assert_eq!(storage.len().unwrap(), 1);
+        assert_eq!(storage.get("key").unwrap(), Some(json!("value")));
+        // Only the torn line was dropped, not the history
+        assert_eq!(storage.history("key").unwrap().len(), 2);
+
+        // Appending after recovery must not leave the torn line mid-log
+        storage.set("next".to_string(), json!(true)).unwrap();
+        let reopened = JsonlStorage::new(&file_path).unwrap();
+        assert_eq!(reopened.len().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_jsonl_storage_redacts_log() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("redacted.jsonl");
+
+        let mut storage = JsonlStorage::new(&file_path)
+            .unwrap()
+            .with_redactor(Redactor::pii().with_key("api_key"));
+        storage.set("api_key".to_string(), json!("sk-123")).unwrap();
+        storage
+            .set("contact".to_string(), json!({"email": "ada@example.com"}))
+            .unwrap();
+        assert_eq!(storage.get("api_key").unwrap(), Some(json!("sk-123")));
+
+        let log = fs::read_to_string(&file_path).unwrap();
+        assert!(!log.contains("sk-123") && !log.contains("ada@example.com"));
+        let reopened = JsonlStorage::new(&file_path).unwrap();
+        assert_eq!(
+            reopened.get("contact").unwrap(),
+            Some(json!({"email": "[REDACTED]"}))
+        );
+    }
+}
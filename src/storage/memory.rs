@@ -1,7 +1,12 @@
 use super::StorageBackend;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+const DEFAULT_SHARD_COUNT: usize = 16;
 
 /// Simple in-memory storage backend using HashMap
 #[derive(Debug, Clone, Default)]
@@ -75,6 +80,221 @@ impl StorageBackend for InMemoryStorage {
     fn len(&self) -> Result<usize, Self::Error> {
         Ok(self.data.len())
     }
+
+    fn set_many(&mut self, entries: Vec<(String, Value)>) -> Result<(), Self::Error> {
+        self.data.extend(entries);
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<(String, Value)>, Self::Error> {
+        Ok(self
+            .data
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&str, &Value) -> bool) -> Result<(), Self::Error> {
+        self.data.retain(|key, value| keep(key, value));
+        Ok(())
+    }
+}
+
+/// Thread-safe in-memory storage backend with interior mutability
+///
+/// Data is split across `RwLock`-protected shards so readers and writers of
+/// different keys don't contend. Cloning is cheap and every clone shares the
+/// same data, which makes it suitable for nodes running on parallel tasks.
+#[derive(Debug, Clone)]
+pub struct ConcurrentInMemoryStorage {
+    shards: Arc<Vec<RwLock<HashMap<String, Value>>>>,
+}
+
+impl Default for ConcurrentInMemoryStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConcurrentInMemoryStorage {
+    /// Create a new concurrent storage with the default number of shards
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARD_COUNT)
+    }
+
+    /// Create a new concurrent storage with a specific number of shards
+    pub fn with_shards(shard_count: usize) -> Self {
+        let shards = (0..shard_count.max(1))
+            .map(|_| RwLock::new(HashMap::new()))
+            .collect();
+        Self {
+            shards: Arc::new(shards),
+        }
+    }
+
+    fn shard_index(&self, key: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() as usize) % self.shards.len()
+    }
+
+    // A panic while holding a shard lock can't leave a HashMap half-updated,
+    // so poisoned locks are safe to recover.
+    fn read_shard(&self, key: &str) -> RwLockReadGuard<'_, HashMap<String, Value>> {
+        self.shards[self.shard_index(key)]
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_shard(&self, key: &str) -> RwLockWriteGuard<'_, HashMap<String, Value>> {
+        self.shards[self.shard_index(key)]
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Store a value through a shared reference
+    pub fn insert(&self, key: String, value: Value) -> Option<Value> {
+        self.write_shard(&key).insert(key, value)
+    }
+
+    /// Retrieve a value by key
+    pub fn get_value(&self, key: &str) -> Option<Value> {
+        self.read_shard(key).get(key).cloned()
+    }
+
+    /// Remove a value through a shared reference
+    pub fn delete(&self, key: &str) -> Option<Value> {
+        self.write_shard(key).remove(key)
+    }
+
+    /// Atomically read-modify-write a single key, returning the new value
+    pub fn update<F>(&self, key: &str, f: F) -> Value
+    where
+        F: FnOnce(Option<&Value>) -> Value,
+    {
+        let mut shard = self.write_shard(key);
+        let new_value = f(shard.get(key));
+        shard.insert(key.to_string(), new_value.clone());
+        new_value
+    }
+
+    /// Check if a key exists
+    pub fn has_key(&self, key: &str) -> bool {
+        self.read_shard(key).contains_key(key)
+    }
+
+    /// Get all keys (a snapshot; concurrent writers may change it immediately)
+    pub fn all_keys(&self) -> Vec<String> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                shard
+                    .read()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Remove all data through a shared reference
+    pub fn clear_all(&self) {
+        for shard in self.shards.iter() {
+            shard.write().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+    }
+
+    /// Get all key-value pairs (a snapshot, taken one shard at a time)
+    pub fn all_entries(&self) -> Vec<(String, Value)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                shard
+                    .read()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Keep only the entries for which `keep` returns `true`, one shard at a time
+    pub fn retain_entries<F>(&self, mut keep: F)
+    where
+        F: FnMut(&str, &Value) -> bool,
+    {
+        for shard in self.shards.iter() {
+            shard
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .retain(|key, value| keep(key, value));
+        }
+    }
+
+    /// Get the number of stored items
+    pub fn count(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap_or_else(|e| e.into_inner()).len())
+            .sum()
+    }
+}
+
+impl StorageBackend for ConcurrentInMemoryStorage {
+    type Error = InMemoryStorageError;
+
+    fn set(&mut self, key: String, value: Value) -> Result<(), Self::Error> {
+        self.insert(key, value);
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Value>, Self::Error> {
+        Ok(self.get_value(key))
+    }
+
+    fn remove(&mut self, key: &str) -> Result<Option<Value>, Self::Error> {
+        Ok(self.delete(key))
+    }
+
+    fn contains_key(&self, key: &str) -> Result<bool, Self::Error> {
+        Ok(self.has_key(key))
+    }
+
+    fn keys(&self) -> Result<Vec<String>, Self::Error> {
+        Ok(self.all_keys())
+    }
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.clear_all();
+        Ok(())
+    }
+
+    fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.count())
+    }
+
+    fn entries(&self) -> Result<Vec<(String, Value)>, Self::Error> {
+        Ok(self.all_entries())
+    }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&str, &Value) -> bool) -> Result<(), Self::Error> {
+        self.retain_entries(keep);
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        key: &str,
+        f: &mut dyn FnMut(Option<&Value>) -> Option<Value>,
+    ) -> Result<Option<Value>, Self::Error> {
+        let mut shard = self.write_shard(key);
+        if let Some(value) = f(shard.get(key)) {
+            shard.insert(key.to_string(), value);
+        }
+        Ok(shard.get(key).cloned())
+    }
 }
 
 #[cfg(test)]
@@ -124,4 +344,54 @@ mod tests {
         assert_eq!(storage.len().unwrap(), 0);
         assert!(storage.keys().unwrap().is_empty());
     }
+
+    #[test]
+    fn test_concurrent_storage_clones_share_data() {
+        let storage = ConcurrentInMemoryStorage::with_shards(4);
+        let clone = storage.clone();
+
+        clone.insert("key".to_string(), json!("value"));
+        assert_eq!(storage.get_value("key"), Some(json!("value")));
+
+        let counter = storage.update("counter", |v| {
+            json!(v.and_then(|v| v.as_i64()).unwrap_or(0) + 1)
+        });
+        assert_eq!(counter, json!(1));
+        assert_eq!(clone.count(), 2);
+
+        clone.clear_all();
+        assert!(storage.all_keys().is_empty());
+    }
+
+    #[test]
+    fn test_bulk_operations() {
+        let mut memory = InMemoryStorage::new();
+        let mut concurrent = ConcurrentInMemoryStorage::with_shards(4);
+        for storage in [
+            &mut memory as &mut dyn StorageBackend<Error = InMemoryStorageError>,
+            &mut concurrent,
+        ] {
+            storage
+                .set_many((0..6).map(|i| (format!("key{}", i), json!(i))).collect())
+                .unwrap();
+            assert_eq!(
+                storage.get_many(&["key4", "missing", "key1"]).unwrap(),
+                vec![Some(json!(4)), None, Some(json!(1))]
+            );
+
+            storage
+                .retain(&mut |_, value| value.as_i64().unwrap() % 2 == 0)
+                .unwrap();
+            let mut entries = storage.entries().unwrap();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            assert_eq!(
+                entries,
+                vec![
+                    ("key0".to_string(), json!(0)),
+                    ("key2".to_string(), json!(2)),
+                    ("key4".to_string(), json!(4)),
+                ]
+            );
+        }
+    }
 }
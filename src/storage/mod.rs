@@ -4,8 +4,14 @@
 //!
 //! - Memory storage (always available)
 //! - File storage (feature: `storage-file`)
+//! - Append-only JSONL storage (feature: `storage-jsonl`)
 //! - Redis storage (feature: `storage-redis`)
+//! - S3 / object store storage (feature: `storage-s3`)
 //! - Database storage (feature: `storage-database`)
+//! - Encryption at rest for any of these (feature: `storage-encrypted`)
+//!
+//! Binary and large values can be kept out of these backends with a
+//! [`blob::BlobStore`], storing only a small [`blob::BlobRef`] in the shared store.
 
 use serde_json::Value;
 use std::error::Error;
@@ -14,6 +20,9 @@ use std::error::Error;
 // STORAGE TRAITS
 // ============================================================================
 
+/// Key looked up by the default [`StorageBackend::health_check`]
+pub const HEALTH_CHECK_KEY: &str = "__pf::health_check";
+
 /// Trait defining the interface for storage backends used by SharedStore
 pub trait StorageBackend: Send + Sync {
     /// Error type returned by storage operations
@@ -44,6 +53,84 @@ pub trait StorageBackend: Send + Sync {
     fn is_empty(&self) -> Result<bool, Self::Error> {
         Ok(self.len()? == 0)
     }
+
+    /// Check that the backend can serve requests, e.g. for a readiness probe
+    ///
+    /// The default looks up a key. Backends behind a connection should
+    /// override this with a cheap round trip such as a ping.
+    fn health_check(&self) -> Result<(), Self::Error> {
+        self.contains_key(HEALTH_CHECK_KEY).map(|_| ())
+    }
+
+    /// Persist writes the backend has buffered
+    ///
+    /// Flows call this after every node. The default does nothing; backends
+    /// with write-behind override it.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Retrieve several values, in the order of `keys`
+    ///
+    /// Backends with a round trip per call should override this with a
+    /// single batched request.
+    fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Value>>, Self::Error> {
+        keys.iter().map(|key| self.get(key)).collect()
+    }
+
+    /// Store several values
+    fn set_many(&mut self, entries: Vec<(String, Value)>) -> Result<(), Self::Error> {
+        for (key, value) in entries {
+            self.set(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Get all key-value pairs
+    fn entries(&self) -> Result<Vec<(String, Value)>, Self::Error> {
+        let keys = self.keys()?;
+        let key_refs: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let values = self.get_many(&key_refs)?;
+        Ok(keys
+            .into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .collect())
+    }
+
+    /// Keep only the entries for which `keep` returns `true`
+    fn retain(&mut self, keep: &mut dyn FnMut(&str, &Value) -> bool) -> Result<(), Self::Error> {
+        for (key, value) in self.entries()? {
+            if !keep(&key, &value) {
+                self.remove(&key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the value of `key` with `f` of it as one operation, returning
+    /// the value stored afterwards
+    ///
+    /// `f` returns `None` to leave the key unchanged. The default reads then
+    /// writes, which is atomic only as long as the data is reachable through
+    /// this backend alone. Backends shared between stores or processes
+    /// override it: `ConcurrentInMemoryStorage` under its shard lock,
+    /// `FileStorage` under its file lock (unless write-behind is enabled) and
+    /// `RedisStorage` with `WATCH`/`MULTI`.
+    fn update(
+        &mut self,
+        key: &str,
+        f: &mut dyn FnMut(Option<&Value>) -> Option<Value>,
+    ) -> Result<Option<Value>, Self::Error> {
+        let current = self.get(key)?;
+        match f(current.as_ref()) {
+            Some(value) => {
+                self.set(key.to_string(), value.clone())?;
+                Ok(Some(value))
+            }
+            None => Ok(current),
+        }
+    }
 }
 
 /// Async version of StorageBackend for I/O-bound operations
@@ -77,15 +164,76 @@ pub trait AsyncStorageBackend: Send + Sync {
     async fn is_empty(&self) -> Result<bool, Self::Error> {
         Ok(self.len().await? == 0)
     }
+
+    /// Check that the backend can serve requests, e.g. for a readiness probe
+    ///
+    /// The default looks up a key. Backends behind a connection should
+    /// override this with a cheap round trip such as a ping.
+    async fn health_check(&self) -> Result<(), Self::Error> {
+        self.contains_key(HEALTH_CHECK_KEY).await.map(|_| ())
+    }
+
+    /// Persist writes the backend has buffered
+    ///
+    /// The default does nothing; backends with write-behind override it.
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Retrieve several values, in the order of `keys`
+    async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Value>>, Self::Error> {
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            values.push(self.get(key).await?);
+        }
+        Ok(values)
+    }
+
+    /// Store several values
+    async fn set_many(&mut self, entries: Vec<(String, Value)>) -> Result<(), Self::Error> {
+        for (key, value) in entries {
+            self.set(key, value).await?;
+        }
+        Ok(())
+    }
+
+    /// Get all key-value pairs
+    async fn entries(&self) -> Result<Vec<(String, Value)>, Self::Error> {
+        let keys = self.keys().await?;
+        let key_refs: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let values = self.get_many(&key_refs).await?;
+        Ok(keys
+            .into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .collect())
+    }
+
+    /// Keep only the entries for which `keep` returns `true`
+    // The lifetimes are spelled out so async_trait doesn't tie them to the future
+    async fn retain(
+        &mut self,
+        keep: &mut (dyn for<'k, 'v> FnMut(&'k str, &'v Value) -> bool + Send),
+    ) -> Result<(), Self::Error> {
+        for (key, value) in self.entries().await? {
+            if !keep(&key, &value) {
+                self.remove(&key).await?;
+            }
+        }
+        Ok(())
+    }
 }
 
 // ============================================================================
 // STORAGE IMPLEMENTATIONS (feature-gated)
 // ============================================================================
 
+// Out-of-band blob storage - always available
+pub mod blob;
+
 // Memory storage - always available
 mod memory;
-pub use memory::{InMemoryStorage, InMemoryStorageError};
+pub use memory::{ConcurrentInMemoryStorage, InMemoryStorage, InMemoryStorageError};
 
 // File storage
 #[cfg(feature = "storage-file")]
@@ -93,14 +241,35 @@ mod file;
 #[cfg(feature = "storage-file")]
 pub use file::{FileStorage, FileStorageError};
 
+// Append-only JSONL storage
+#[cfg(feature = "storage-jsonl")]
+mod jsonl;
+#[cfg(feature = "storage-jsonl")]
+pub use jsonl::{JsonlEvent, JsonlStorage, JsonlStorageError};
+
 // Redis storage
 #[cfg(feature = "storage-redis")]
 mod redis;
 #[cfg(feature = "storage-redis")]
 pub use redis::{RedisStorage, RedisStorageError};
 
+// S3 / object store storage
+#[cfg(feature = "storage-s3")]
+mod object_store;
+#[cfg(feature = "storage-s3")]
+pub use object_store::{ObjectStoreStorage, ObjectStoreStorageError};
+
+// Encrypted storage wrapper
+#[cfg(feature = "storage-encrypted")]
+mod encrypted;
+#[cfg(feature = "storage-encrypted")]
+pub use encrypted::{EncryptedStorage, EncryptedStorageError};
+
 // Database storage
 #[cfg(feature = "storage-database")]
 mod database;
 #[cfg(feature = "storage-database")]
-pub use database::DatabaseStorage;
+pub use database::{
+    ChangeListener, ChangeOp, DEFAULT_BATCH_SIZE, DEFAULT_NOTIFY_CHANNEL, DEFAULT_TABLE_NAME,
+    DatabaseSchema, DatabaseStorage, StorageChange,
+};
@@ -0,0 +1,257 @@
+use crate::storage::AsyncStorageBackend;
+use futures::TryStreamExt;
+use object_store::path::{Path, PathPart};
+use object_store::{ObjectStore, PutPayload};
+use serde_json::Value;
+use std::sync::Arc;
+use thiserror::Error;
+
+const OBJECT_EXTENSION: &str = ".json";
+
+/// Error types for object store operations
+#[derive(Debug, Error)]
+pub enum ObjectStoreStorageError {
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+    #[error("JSON serialization error: {0}")]
+    JsonSerialization(#[from] serde_json::Error),
+}
+
+/// Object store backend (S3, GCS, Azure, local, in-memory) that implements
+/// AsyncStorageBackend
+///
+/// Each key is stored as its own JSON object at `<prefix>/<key>.json`, with the
+/// key percent-encoded so arbitrary key names map to a single path segment.
+#[derive(Debug, Clone)]
+pub struct ObjectStoreStorage {
+    store: Arc<dyn ObjectStore>,
+    prefix: Option<Path>,
+}
+
+impl ObjectStoreStorage {
+    /// Create a new storage on top of any object_store implementation
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            store,
+            prefix: None,
+        }
+    }
+
+    /// Create an S3 storage for the given bucket, reading credentials and region
+    /// from the standard `AWS_*` environment variables
+    pub fn s3_from_env(bucket: &str) -> Result<Self, ObjectStoreStorageError> {
+        let store = object_store::aws::AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .build()?;
+        Ok(Self::new(Arc::new(store)))
+    }
+
+    /// Store all objects under the given prefix (e.g. `"flows/session-1"`)
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        let prefix = Path::from(prefix);
+        self.prefix = if prefix.as_ref().is_empty() {
+            None
+        } else {
+            Some(prefix)
+        };
+        self
+    }
+
+    /// Get the key prefix, if any
+    pub fn prefix(&self) -> Option<&Path> {
+        self.prefix.as_ref()
+    }
+
+    /// Get the underlying object store
+    pub fn store(&self) -> &Arc<dyn ObjectStore> {
+        &self.store
+    }
+
+    /// Get the object path for a key
+    fn object_path(&self, key: &str) -> Path {
+        let part = PathPart::from(format!("{}{}", key, OBJECT_EXTENSION));
+        match &self.prefix {
+            Some(prefix) => prefix.child(part),
+            None => Path::from_iter([part]),
+        }
+    }
+
+    /// Recover the original key from an object path under the prefix
+    fn key_from_path(&self, path: &Path) -> Option<String> {
+        let mut parts: Vec<PathPart<'_>> = match &self.prefix {
+            Some(prefix) => path.prefix_match(prefix)?.collect(),
+            None => path.parts().collect(),
+        };
+        // Only direct children belong to this store
+        if parts.len() != 1 {
+            return None;
+        }
+        let encoded = parts.pop()?;
+        let encoded = encoded.as_ref().strip_suffix(OBJECT_EXTENSION)?;
+        percent_encoding::percent_decode_str(encoded)
+            .decode_utf8()
+            .ok()
+            .map(|key| key.into_owned())
+    }
+
+    /// List the paths of all objects belonging to this store
+    async fn list_paths(&self) -> Result<Vec<Path>, ObjectStoreStorageError> {
+        let objects: Vec<_> = self.store.list(self.prefix.as_ref()).try_collect().await?;
+        Ok(objects
+            .into_iter()
+            .map(|meta| meta.location)
+            .filter(|location| self.key_from_path(location).is_some())
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncStorageBackend for ObjectStoreStorage {
+    type Error = ObjectStoreStorageError;
+
+    async fn set(&mut self, key: String, value: Value) -> Result<(), Self::Error> {
+        let body = serde_json::to_vec(&value)?;
+        self.store
+            .put(&self.object_path(&key), PutPayload::from(body))
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Value>, Self::Error> {
+        match self.store.get(&self.object_path(key)).await {
+            Ok(result) => {
+                let bytes = result.bytes().await?;
+                Ok(Some(serde_json::from_slice(&bytes)?))
+            }
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn remove(&mut self, key: &str) -> Result<Option<Value>, Self::Error> {
+        let existing = self.get(key).await?;
+        if existing.is_some() {
+            match self.store.delete(&self.object_path(key)).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(existing)
+    }
+
+    async fn contains_key(&self, key: &str) -> Result<bool, Self::Error> {
+        match self.store.head(&self.object_path(key)).await {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn keys(&self) -> Result<Vec<String>, Self::Error> {
+        Ok(self
+            .list_paths()
+            .await?
+            .iter()
+            .filter_map(|path| self.key_from_path(path))
+            .collect())
+    }
+
+    async fn clear(&mut self) -> Result<(), Self::Error> {
+        for path in self.list_paths().await? {
+            match self.store.delete(&path).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    async fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.list_paths().await?.len())
+    }
+
+    async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Value>>, Self::Error> {
+        // Object stores have no batch read, so issue the requests concurrently
+        futures::future::try_join_all(keys.iter().map(|key| self.get(key))).await
+    }
+
+    async fn set_many(&mut self, entries: Vec<(String, Value)>) -> Result<(), Self::Error> {
+        let this = &*self;
+        let puts = entries.iter().map(|(key, value)| async move {
+            let body = serde_json::to_vec(value)?;
+            this.store
+                .put(&this.object_path(key), PutPayload::from(body))
+                .await?;
+            Ok::<_, ObjectStoreStorageError>(())
+        });
+        futures::future::try_join_all(puts).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_object_store_storage_basic_operations() -> Result<(), ObjectStoreStorageError> {
+        let mut storage = ObjectStoreStorage::new(Arc::new(InMemory::new())).with_prefix("flows");
+
+        storage.set("user/name".to_string(), json!("Ada")).await?;
+        storage.set("count".to_string(), json!(3)).await?;
+
+        assert_eq!(storage.get("user/name").await?, Some(json!("Ada")));
+        assert!(storage.contains_key("count").await?);
+        assert!(!storage.contains_key("missing").await?);
+
+        let mut keys = storage.keys().await?;
+        keys.sort();
+        assert_eq!(keys, vec!["count", "user/name"]);
+
+        assert_eq!(storage.remove("count").await?, Some(json!(3)));
+        assert_eq!(storage.len().await?, 1);
+
+        storage.clear().await?;
+        assert!(storage.is_empty().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_object_store_storage_prefix_isolation() -> Result<(), ObjectStoreStorageError> {
+        let shared: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let mut first = ObjectStoreStorage::new(Arc::clone(&shared)).with_prefix("a");
+        let mut second = ObjectStoreStorage::new(shared).with_prefix("b");
+
+        first.set("key".to_string(), json!(1)).await?;
+        second.set("key".to_string(), json!(2)).await?;
+
+        assert_eq!(first.get("key").await?, Some(json!(1)));
+        assert_eq!(second.get("key").await?, Some(json!(2)));
+
+        first.clear().await?;
+        assert_eq!(second.len().await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_object_store_storage_bulk_operations() -> Result<(), ObjectStoreStorageError> {
+        let mut storage = ObjectStoreStorage::new(Arc::new(InMemory::new()));
+
+        storage
+            .set_many(vec![
+                ("a".to_string(), json!(1)),
+                ("b".to_string(), json!(2)),
+            ])
+            .await?;
+        assert_eq!(
+            storage.get_many(&["b", "missing", "a"]).await?,
+            vec![Some(json!(2)), None, Some(json!(1))]
+        );
+
+        storage.retain(&mut |key, _| key == "a").await?;
+        assert_eq!(storage.entries().await?, vec![("a".to_string(), json!(1))]);
+        Ok(())
+    }
+}
@@ -87,13 +87,7 @@ impl StorageBackend for RedisStorage {
 
             match result {
                 Some(json_string) => {
-                    let value: Value = serde_json::from_str(&json_string).map_err(|e| {
-                        redis::RedisError::from((
-                            redis::ErrorKind::TypeError,
-                            "JSON parse error",
-                            e.to_string(),
-                        ))
-                    })?;
+                    let value: Value = serde_json::from_str(&json_string).map_err(json_error)?;
                     Ok(Some(value))
                 }
                 None => Ok(None),
@@ -162,6 +156,96 @@ impl StorageBackend for RedisStorage {
             Ok(full_keys.len())
         })
     }
+
+    fn health_check(&self) -> Result<(), Self::Error> {
+        self.with_connection(|conn| redis::cmd("PING").query::<String>(conn))
+            .map(|_| ())
+    }
+
+    fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Value>>, Self::Error> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let full_keys: Vec<String> = keys.iter().map(|key| self.get_full_key(key)).collect();
+
+        // One MGET instead of a GET per key
+        let results: Vec<Option<String>> = self.with_connection(|conn| conn.mget(&full_keys))?;
+        results
+            .into_iter()
+            .map(|json_string| {
+                json_string
+                    .map(|json_string| serde_json::from_str(&json_string))
+                    .transpose()
+                    .map_err(RedisStorageError::from)
+            })
+            .collect()
+    }
+
+    fn set_many(&mut self, entries: Vec<(String, Value)>) -> Result<(), Self::Error> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let items = entries
+            .iter()
+            .map(|(key, value)| Ok((self.get_full_key(key), serde_json::to_string(value)?)))
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+
+        self.with_connection(|conn| {
+            let _: () = conn.mset(&items)?;
+            Ok(())
+        })
+    }
+
+    fn update(
+        &mut self,
+        key: &str,
+        f: &mut dyn FnMut(Option<&Value>) -> Option<Value>,
+    ) -> Result<Option<Value>, Self::Error> {
+        let full_key = self.get_full_key(key);
+
+        // WATCH the key and start over if another client writes it before EXEC
+        self.with_connection(|conn| {
+            redis::transaction(conn, &[&full_key], |conn, pipe| {
+                let current: Option<String> = conn.get(&full_key)?;
+                let current = current
+                    .map(|json_string| serde_json::from_str::<Value>(&json_string))
+                    .transpose()
+                    .map_err(json_error)?;
+                let Some(value) = f(current.as_ref()) else {
+                    return Ok(Some(current));
+                };
+                let json_string = serde_json::to_string(&value).map_err(json_error)?;
+                let written: Option<()> = pipe.set(&full_key, json_string).ignore().query(conn)?;
+                Ok(written.map(|()| Some(value)))
+            })
+        })
+    }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&str, &Value) -> bool) -> Result<(), Self::Error> {
+        let removed: Vec<String> = self
+            .entries()?
+            .into_iter()
+            .filter(|(key, value)| !keep(key, value))
+            .map(|(key, _)| self.get_full_key(&key))
+            .collect();
+        if removed.is_empty() {
+            return Ok(());
+        }
+
+        self.with_connection(|conn| {
+            let _: u32 = conn.del(&removed)?;
+            Ok(())
+        })
+    }
+}
+
+/// Report a value that isn't valid JSON as a Redis type error
+fn json_error(error: serde_json::Error) -> redis::RedisError {
+    redis::RedisError::from((
+        redis::ErrorKind::TypeError,
+        "JSON parse error",
+        error.to_string(),
+    ))
 }
 
 #[cfg(test)]
@@ -250,4 +334,49 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[ignore] // Requires Redis server
+    fn test_redis_storage_bulk_operations() -> Result<(), RedisStorageError> {
+        let mut storage = setup_redis()?;
+        storage.clear()?;
+
+        storage.set_many(vec![
+            ("a".to_string(), json!(1)),
+            ("b".to_string(), json!(2)),
+            ("c".to_string(), json!(3)),
+        ])?;
+        assert_eq!(
+            storage.get_many(&["c", "missing", "a"])?,
+            vec![Some(json!(3)), None, Some(json!(1))]
+        );
+
+        storage.retain(&mut |key, _| key != "b")?;
+        let mut entries = storage.entries()?;
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            entries,
+            vec![("a".to_string(), json!(1)), ("c".to_string(), json!(3))]
+        );
+
+        storage.clear()?;
+        Ok(())
+    }
+
+    #[test]
+    #[ignore] // Requires Redis server
+    fn test_redis_storage_update() -> Result<(), RedisStorageError> {
+        let mut storage = setup_redis()?;
+        let mut other = setup_redis()?;
+        storage.clear()?;
+        let mut increment =
+            |current: Option<&Value>| Some(json!(current.and_then(Value::as_u64).unwrap_or(0) + 1));
+
+        storage.update("count", &mut increment)?;
+        assert_eq!(other.update("count", &mut increment)?, Some(json!(2)));
+        assert_eq!(storage.update("count", &mut |_| None)?, Some(json!(2)));
+
+        storage.clear()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,524 @@
+//! Supervision of long-running agent flows
+//!
+//! A [`FlowSupervisor`] spawns each agent flow as its own task and watches it.
+//! When a run fails (or panics) the agent is restarted with a fresh flow and
+//! store according to its [`RestartPolicy`]. Every agent has an
+//! [`AgentHandle`] for querying its status, restart count and last error.
+//!
+//! ```rust
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::supervisor::{AgentSpec, AgentStatus, FlowSupervisor, RestartPolicy};
+//! # use std::time::Duration;
+//! # #[tokio::main]
+//! # async fn main() {
+//! let supervisor = FlowSupervisor::new();
+//! let handle = supervisor
+//!     .spawn(
+//!         AgentSpec::new("worker", || FlowBuilder::new().start_node("missing").build())
+//!             .with_restart_policy(RestartPolicy::backoff(
+//!                 Duration::from_millis(1),
+//!                 Duration::from_millis(10),
+//!             ))
+//!             .with_max_restarts(2),
+//!     )
+//!     .unwrap();
+//!
+//! let state = handle.wait().await;
+//! assert_eq!(state.status, AgentStatus::Failed);
+//! assert_eq!(state.restarts, 2);
+//! assert!(state.last_error.is_some());
+//! # }
+//! ```
+
+use crate::flow::{BasicFlow, Flow};
+use crate::shared_store::SharedStore;
+use crate::storage::InMemoryStorage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+type AgentFactory = Arc<dyn Fn() -> BasicFlow<InMemoryStorage> + Send + Sync>;
+
+/// Errors from supervisor operations
+#[derive(Debug, thiserror::Error)]
+pub enum SupervisorError {
+    #[error("Agent already running: {0}")]
+    AlreadyRunning(String),
+    #[error("Agent not found: {0}")]
+    NotFound(String),
+}
+
+/// When a failed agent is restarted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestartPolicy {
+    /// Leave the agent failed
+    #[default]
+    Never,
+    /// Restart immediately
+    Always,
+    /// Restart after a delay that doubles on each restart, up to `max`
+    Backoff { initial: Duration, max: Duration },
+}
+
+impl RestartPolicy {
+    /// Create a backoff policy
+    pub fn backoff(initial: Duration, max: Duration) -> Self {
+        Self::Backoff { initial, max }
+    }
+
+    /// Delay before the restart following `restarts` previous restarts,
+    /// or `None` if the policy doesn't restart
+    pub fn delay(&self, restarts: usize) -> Option<Duration> {
+        match self {
+            Self::Never => None,
+            Self::Always => Some(Duration::ZERO),
+            Self::Backoff { initial, max } => {
+                let factor = 2u32.saturating_pow(restarts.min(31) as u32);
+                Some(initial.saturating_mul(factor).min(*max))
+            }
+        }
+    }
+}
+
+/// Lifecycle state of a supervised agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentStatus {
+    /// The flow is executing
+    Running,
+    /// The last run failed and the agent is waiting to restart
+    Restarting,
+    /// The flow reached a terminal action
+    Completed,
+    /// The flow failed and will not be restarted
+    Failed,
+    /// The agent was stopped through the supervisor
+    Stopped,
+}
+
+impl AgentStatus {
+    /// Whether the agent has finished for good
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Stopped)
+    }
+}
+
+/// Snapshot of an agent's state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentState {
+    /// Current status
+    pub status: AgentStatus,
+    /// Number of restarts so far
+    pub restarts: usize,
+    /// Error from the most recent failed run
+    pub last_error: Option<String>,
+    /// Final action of the run, once completed
+    pub final_action: Option<String>,
+}
+
+/// Description of an agent to supervise
+#[derive(Clone)]
+pub struct AgentSpec {
+    name: String,
+    factory: AgentFactory,
+    inputs: HashMap<String, Value>,
+    restart_policy: RestartPolicy,
+    max_restarts: Option<usize>,
+}
+
+impl std::fmt::Debug for AgentSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentSpec")
+            .field("name", &self.name)
+            .field("inputs", &self.inputs)
+            .field("restart_policy", &self.restart_policy)
+            .field("max_restarts", &self.max_restarts)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AgentSpec {
+    /// Create a spec from a factory producing a fresh flow for every (re)start
+    pub fn new<F>(name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn() -> BasicFlow<InMemoryStorage> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            factory: Arc::new(factory),
+            inputs: HashMap::new(),
+            restart_policy: RestartPolicy::default(),
+            max_restarts: None,
+        }
+    }
+
+    /// Seed the agent's store with a value on every (re)start
+    pub fn with_input(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.inputs.insert(key.into(), value);
+        self
+    }
+
+    /// Set the restart policy
+    pub fn with_restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// Give up after this many restarts
+    pub fn with_max_restarts(mut self, max_restarts: usize) -> Self {
+        self.max_restarts = Some(max_restarts);
+        self
+    }
+
+    /// Get the agent name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Handle for observing a supervised agent
+#[derive(Debug, Clone)]
+pub struct AgentHandle {
+    name: String,
+    state: watch::Receiver<AgentState>,
+}
+
+impl AgentHandle {
+    /// Get the agent name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get a snapshot of the agent's state
+    pub fn state(&self) -> AgentState {
+        self.state.borrow().clone()
+    }
+
+    /// Get the agent's current status
+    pub fn status(&self) -> AgentStatus {
+        self.state.borrow().status
+    }
+
+    /// Get the error from the agent's most recent failed run
+    pub fn last_error(&self) -> Option<String> {
+        self.state.borrow().last_error.clone()
+    }
+
+    /// Get the number of restarts so far
+    pub fn restarts(&self) -> usize {
+        self.state.borrow().restarts
+    }
+
+    /// Wait until the agent has completed, failed for good or been stopped
+    pub async fn wait(&self) -> AgentState {
+        let mut receiver = self.state.clone();
+        if let Ok(state) = receiver.wait_for(|state| state.status.is_finished()).await {
+            return state.clone();
+        }
+        // The supervisor is gone; report the last state it published
+        receiver.borrow().clone()
+    }
+}
+
+struct SupervisedAgent {
+    handle: AgentHandle,
+    state: watch::Sender<AgentState>,
+    task: JoinHandle<()>,
+}
+
+/// Apply a state change unless the agent has been stopped in the meantime
+fn update(state: &watch::Sender<AgentState>, change: impl FnOnce(&mut AgentState)) {
+    state.send_if_modified(|state| {
+        if state.status == AgentStatus::Stopped {
+            return false;
+        }
+        change(state);
+        true
+    });
+}
+
+/// Aborts the wrapped task when dropped, so stopping an agent also stops its run
+struct AbortOnDrop(JoinHandle<Result<String, String>>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Spawns agent flows and restarts them on failure
+///
+/// Clones share the same set of agents.
+#[derive(Clone, Default)]
+pub struct FlowSupervisor {
+    agents: Arc<Mutex<HashMap<String, SupervisedAgent>>>,
+}
+
+impl std::fmt::Debug for FlowSupervisor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlowSupervisor")
+            .field("agents", &self.names())
+            .finish()
+    }
+}
+
+impl FlowSupervisor {
+    /// Create a supervisor with no agents
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start supervising an agent
+    ///
+    /// Fails if an agent with the same name is still running; finished agents
+    /// are replaced.
+    pub fn spawn(&self, spec: AgentSpec) -> Result<AgentHandle, SupervisorError> {
+        let mut agents = self.lock();
+        if let Some(existing) = agents.get(&spec.name)
+            && !existing.handle.status().is_finished()
+        {
+            return Err(SupervisorError::AlreadyRunning(spec.name));
+        }
+
+        let (state, receiver) = watch::channel(AgentState {
+            status: AgentStatus::Running,
+            restarts: 0,
+            last_error: None,
+            final_action: None,
+        });
+        let handle = AgentHandle {
+            name: spec.name.clone(),
+            state: receiver,
+        };
+        let task = tokio::spawn(Self::supervise(spec.clone(), state.clone()));
+        agents.insert(
+            spec.name,
+            SupervisedAgent {
+                handle: handle.clone(),
+                state,
+                task,
+            },
+        );
+        Ok(handle)
+    }
+
+    /// Get the handle of an agent
+    pub fn handle(&self, name: &str) -> Option<AgentHandle> {
+        self.lock().get(name).map(|agent| agent.handle.clone())
+    }
+
+    /// Get a snapshot of an agent's state
+    pub fn state(&self, name: &str) -> Option<AgentState> {
+        self.lock().get(name).map(|agent| agent.handle.state())
+    }
+
+    /// Get a snapshot of every agent's state
+    pub fn states(&self) -> HashMap<String, AgentState> {
+        self.lock()
+            .iter()
+            .map(|(name, agent)| (name.clone(), agent.handle.state()))
+            .collect()
+    }
+
+    /// Names of all supervised agents
+    pub fn names(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    /// Stop an agent, aborting its current run
+    pub fn stop(&self, name: &str) -> Result<(), SupervisorError> {
+        let agents = self.lock();
+        let agent = agents
+            .get(name)
+            .ok_or_else(|| SupervisorError::NotFound(name.to_string()))?;
+        agent.task.abort();
+        agent.state.send_modify(|state| {
+            if !state.status.is_finished() {
+                state.status = AgentStatus::Stopped;
+            }
+        });
+        Ok(())
+    }
+
+    /// Stop every agent
+    pub fn shutdown(&self) {
+        for name in self.names() {
+            let _ = self.stop(&name);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, SupervisedAgent>> {
+        self.agents.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn supervise(spec: AgentSpec, state: watch::Sender<AgentState>) {
+        let mut restarts = 0;
+        loop {
+            let flow = (spec.factory)();
+            let inputs = spec.inputs.clone();
+            // Each run gets its own task so a panicking node counts as a failure
+            let mut run = AbortOnDrop(tokio::spawn(async move {
+                let mut flow = flow;
+                let mut store = SharedStore::new();
+                for (key, value) in inputs {
+                    store.set(key, value).map_err(|e| e.to_string())?;
+                }
+                flow.execute(&mut store)
+                    .await
+                    .map(|result| result.final_action.name())
+                    .map_err(|e| e.to_string())
+            }));
+            let outcome = match (&mut run.0).await {
+                Ok(outcome) => outcome,
+                Err(e) if e.is_panic() => Err("Agent panicked".to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+
+            let error = match outcome {
+                Ok(final_action) => {
+                    update(&state, |state| {
+                        state.status = AgentStatus::Completed;
+                        state.final_action = Some(final_action);
+                    });
+                    return;
+                }
+                Err(error) => error,
+            };
+
+            let delay = spec
+                .restart_policy
+                .delay(restarts)
+                .filter(|_| spec.max_restarts.is_none_or(|max| restarts < max));
+            let Some(delay) = delay else {
+                update(&state, |state| {
+                    state.status = AgentStatus::Failed;
+                    state.last_error = Some(error);
+                });
+                return;
+            };
+
+            update(&state, |state| {
+                state.status = AgentStatus::Restarting;
+                state.last_error = Some(error);
+            });
+            tokio::time::sleep(delay).await;
+            restarts += 1;
+            update(&state, |state| {
+                state.status = AgentStatus::Running;
+                state.restarts = restarts;
+            });
+        }
+    }
+}
+
+#[cfg(all(test, feature = "builtin-nodes"))]
+mod tests {
+    use super::*;
+    use crate::Action;
+    use crate::flow::FlowBuilder;
+    use crate::node::Node;
+    use crate::node::builtin::{DelayNode, SetValueNode};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn working_flow() -> BasicFlow<InMemoryStorage> {
+        FlowBuilder::new()
+            .start_node("set")
+            .node(
+                "set",
+                Node::new(SetValueNode::new(
+                    "done",
+                    json!(true),
+                    Action::simple("complete"),
+                )),
+            )
+            .build()
+    }
+
+    fn broken_flow() -> BasicFlow<InMemoryStorage> {
+        FlowBuilder::new().start_node("missing").build()
+    }
+
+    #[test]
+    fn test_backoff_delay_is_capped() {
+        let policy = RestartPolicy::backoff(Duration::from_millis(10), Duration::from_millis(50));
+        assert_eq!(policy.delay(0), Some(Duration::from_millis(10)));
+        assert_eq!(policy.delay(2), Some(Duration::from_millis(40)));
+        assert_eq!(policy.delay(3), Some(Duration::from_millis(50)));
+        assert_eq!(policy.delay(100), Some(Duration::from_millis(50)));
+        assert_eq!(RestartPolicy::Never.delay(0), None);
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_restarts_until_success() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let supervisor = FlowSupervisor::new();
+        let handle = supervisor
+            .spawn(
+                AgentSpec::new("flaky", move || {
+                    if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                        broken_flow()
+                    } else {
+                        working_flow()
+                    }
+                })
+                .with_restart_policy(RestartPolicy::Always),
+            )
+            .unwrap();
+
+        let state = handle.wait().await;
+        assert_eq!(state.status, AgentStatus::Completed);
+        assert_eq!(state.restarts, 2);
+        assert_eq!(state.final_action.as_deref(), Some("complete"));
+        assert!(state.last_error.is_some());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        let never = supervisor
+            .spawn(AgentSpec::new("fragile", broken_flow))
+            .unwrap()
+            .wait()
+            .await;
+        assert_eq!(never.status, AgentStatus::Failed);
+        assert_eq!(never.restarts, 0);
+        assert_eq!(supervisor.states().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_stops_running_agent() {
+        let supervisor = FlowSupervisor::new();
+        let spec = AgentSpec::new("sleeper", || {
+            FlowBuilder::new()
+                .start_node("sleep")
+                .node(
+                    "sleep",
+                    Node::new(DelayNode::new(
+                        Duration::from_secs(3600),
+                        Action::simple("complete"),
+                    )),
+                )
+                .build()
+        });
+        let handle = supervisor.spawn(spec.clone()).unwrap();
+        assert_eq!(handle.status(), AgentStatus::Running);
+        assert!(matches!(
+            supervisor.spawn(spec.clone()),
+            Err(SupervisorError::AlreadyRunning(_))
+        ));
+
+        supervisor.stop("sleeper").unwrap();
+        assert_eq!(handle.wait().await.status, AgentStatus::Stopped);
+        assert!(matches!(
+            supervisor.stop("unknown"),
+            Err(SupervisorError::NotFound(_))
+        ));
+
+        // A finished agent can be spawned again under the same name
+        supervisor.spawn(spec).unwrap();
+        supervisor.shutdown();
+    }
+}
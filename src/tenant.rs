@@ -0,0 +1,491 @@
+//! Per-tenant key namespaces, credentials and quotas
+//!
+//! A [`TenantContext`] identifies the customer a run is made for. Set it on a
+//! flow with `FlowBuilder::tenant` and every node sees it through
+//! [`ExecutionContext::tenant`]. It carries:
+//!
+//! - a key prefix, applied to a whole store by wrapping its backend in a
+//!   [`TenantStorage`] (see [`TenantContext::scope`]), so tenants sharing one
+//!   Redis or database never see each other's keys;
+//! - credentials, as named [`Secret`]s and (with `builtin-llm`) an `ApiConfig`
+//!   that `ApiRequestNode` uses instead of its own;
+//! - a [`TenantQuota`] limiting how many requests may start per time window
+//!   and how many requests, tokens and dollars may be spent in total.
+//!
+//! Clones share quota counters, so one context per tenant should be kept for
+//! the lifetime of the service and cloned into each run.
+//!
+//! ```rust
+//! # use pocketflow_rs::tenant::{TenantContext, TenantQuota};
+//! # use pocketflow_rs::{InMemoryStorage, SharedStore, StorageBackend};
+//! # use std::time::Duration;
+//! let acme = TenantContext::new("acme")
+//!     .with_quota(TenantQuota::new().with_rate_limit(10, Duration::from_secs(60)));
+//! let mut store = SharedStore::with_storage(acme.scope(InMemoryStorage::new()));
+//! store.set("answer".to_string(), 42.into()).unwrap();
+//! assert_eq!(
+//!     store.storage().inner().get("tenant:acme:answer").unwrap(),
+//!     Some(42.into())
+//! );
+//! ```
+
+use crate::node::{ExecutionContext, NodeError};
+use crate::secrets::Secret;
+use crate::shared_store::TokenUsage;
+use crate::storage::StorageBackend;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A quota that stopped a tenant's request
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum QuotaError {
+    /// Too many requests in the current window
+    #[error("Tenant '{tenant}' is rate limited, retry after {retry_after:?}")]
+    RateLimited {
+        tenant: String,
+        retry_after: Duration,
+    },
+    /// The tenant's request or token budget is used up
+    #[error("Tenant '{tenant}' exceeded its {resource} budget of {limit}")]
+    BudgetExceeded {
+        tenant: String,
+        resource: &'static str,
+        limit: u64,
+    },
+    /// The tenant's estimated spend reached its cost budget
+    #[error("Tenant '{tenant}' exceeded its cost budget of ${limit}")]
+    CostBudgetExceeded { tenant: String, limit: f64 },
+}
+
+impl From<QuotaError> for NodeError {
+    fn from(error: QuotaError) -> Self {
+        match error {
+            QuotaError::RateLimited { .. } => NodeError::retryable(error.to_string()),
+            QuotaError::BudgetExceeded { .. } | QuotaError::CostBudgetExceeded { .. } => {
+                NodeError::fatal(error.to_string())
+            }
+        }
+    }
+}
+
+/// Limits on a tenant's requests and token spend; unlimited by default
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TenantQuota {
+    /// At most this many requests may start per window
+    pub rate_limit: Option<(usize, Duration)>,
+    /// Total requests allowed
+    pub request_budget: Option<u64>,
+    /// Total tokens allowed, as reported through [`TenantContext::record_tokens`]
+    pub token_budget: Option<u64>,
+    /// Total estimated cost allowed in US dollars, as reported through
+    /// [`TenantContext::record_usage`]
+    pub cost_budget: Option<f64>,
+}
+
+impl TenantQuota {
+    /// Create an unlimited quota
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow at most `requests` requests to start per `window`
+    pub fn with_rate_limit(mut self, requests: usize, window: Duration) -> Self {
+        self.rate_limit = Some((requests, window));
+        self
+    }
+
+    /// Limit the total number of requests
+    pub fn with_request_budget(mut self, requests: u64) -> Self {
+        self.request_budget = Some(requests);
+        self
+    }
+
+    /// Limit the total number of tokens
+    pub fn with_token_budget(mut self, tokens: u64) -> Self {
+        self.token_budget = Some(tokens);
+        self
+    }
+
+    /// Limit the total estimated cost in US dollars
+    pub fn with_cost_budget(mut self, dollars: f64) -> Self {
+        self.cost_budget = Some(dollars);
+        self
+    }
+}
+
+/// Counters shared by all clones of a context
+#[derive(Debug, Default)]
+struct QuotaState {
+    /// Start times of the requests in the current window
+    recent: VecDeque<Instant>,
+    requests: u64,
+    tokens: u64,
+    cost: f64,
+}
+
+/// The tenant a run is made for
+#[derive(Debug, Clone)]
+pub struct TenantContext {
+    id: String,
+    key_prefix: String,
+    quota: TenantQuota,
+    secrets: HashMap<String, Secret>,
+    #[cfg(feature = "builtin-llm")]
+    api_config: Option<crate::node::builtin::ApiConfig>,
+    state: Arc<Mutex<QuotaState>>,
+}
+
+impl TenantContext {
+    /// Create a context for `id`, with keys under `tenant:{id}:`
+    pub fn new(id: impl Into<String>) -> Self {
+        let id = id.into();
+        Self {
+            key_prefix: format!("tenant:{}:", id),
+            id,
+            quota: TenantQuota::default(),
+            secrets: HashMap::new(),
+            #[cfg(feature = "builtin-llm")]
+            api_config: None,
+            state: Arc::default(),
+        }
+    }
+
+    /// Store the tenant's keys under another prefix
+    pub fn with_key_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.key_prefix = prefix.into();
+        self
+    }
+
+    /// Set the tenant's quota
+    pub fn with_quota(mut self, quota: TenantQuota) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    /// Add a named credential
+    pub fn with_secret(mut self, name: impl Into<String>, secret: impl Into<Secret>) -> Self {
+        self.secrets.insert(name.into(), secret.into());
+        self
+    }
+
+    /// Set the API configuration LLM nodes use for this tenant
+    #[cfg(feature = "builtin-llm")]
+    pub fn with_api_config(mut self, config: crate::node::builtin::ApiConfig) -> Self {
+        self.api_config = Some(config);
+        self
+    }
+
+    /// The tenant ID
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The prefix of the tenant's store keys
+    pub fn key_prefix(&self) -> &str {
+        &self.key_prefix
+    }
+
+    /// The tenant's quota
+    pub fn quota(&self) -> &TenantQuota {
+        &self.quota
+    }
+
+    /// Get a named credential
+    pub fn secret(&self, name: &str) -> Option<&Secret> {
+        self.secrets.get(name)
+    }
+
+    /// The tenant's API configuration, if one was set
+    #[cfg(feature = "builtin-llm")]
+    pub fn api_config(&self) -> Option<&crate::node::builtin::ApiConfig> {
+        self.api_config.as_ref()
+    }
+
+    /// The key `key` is stored under for this tenant
+    pub fn key(&self, key: &str) -> String {
+        format!("{}{}", self.key_prefix, key)
+    }
+
+    /// Wrap a backend so every key is stored under the tenant's prefix
+    pub fn scope<S: StorageBackend>(&self, storage: S) -> TenantStorage<S> {
+        TenantStorage::new(storage, self.key_prefix.clone())
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, QuotaState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Number of requests started so far
+    pub fn requests_made(&self) -> u64 {
+        self.state().requests
+    }
+
+    /// Number of tokens recorded so far
+    pub fn tokens_used(&self) -> u64 {
+        self.state().tokens
+    }
+
+    /// Estimated cost recorded so far in US dollars
+    pub fn cost_spent(&self) -> f64 {
+        self.state().cost
+    }
+
+    /// Add tokens spent by a request to the tenant's total
+    pub fn record_tokens(&self, tokens: u64) {
+        self.state().tokens += tokens;
+    }
+
+    /// Add the tokens and estimated cost of a request to the tenant's totals
+    pub fn record_usage(&self, usage: &TokenUsage) {
+        let mut state = self.state();
+        state.tokens += usage.total_tokens();
+        state.cost += usage.cost;
+    }
+
+    /// Start a request at `now` if the quota allows it
+    pub fn try_acquire(&self, now: Instant) -> Result<(), QuotaError> {
+        let mut state = self.state();
+        let budgets = [
+            ("request", self.quota.request_budget, state.requests),
+            ("token", self.quota.token_budget, state.tokens),
+        ];
+        for (resource, limit, used) in budgets {
+            if let Some(limit) = limit
+                && used >= limit
+            {
+                return Err(QuotaError::BudgetExceeded {
+                    tenant: self.id.clone(),
+                    resource,
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = self.quota.cost_budget
+            && state.cost >= limit
+        {
+            return Err(QuotaError::CostBudgetExceeded {
+                tenant: self.id.clone(),
+                limit,
+            });
+        }
+
+        if let Some((max_requests, window)) = self.quota.rate_limit {
+            while state
+                .recent
+                .front()
+                .is_some_and(|started| now.saturating_duration_since(*started) >= window)
+            {
+                state.recent.pop_front();
+            }
+            if state.recent.len() >= max_requests {
+                let oldest = state.recent.front().copied().unwrap_or(now);
+                return Err(QuotaError::RateLimited {
+                    tenant: self.id.clone(),
+                    retry_after: window.saturating_sub(now.saturating_duration_since(oldest)),
+                });
+            }
+            state.recent.push_back(now);
+        }
+        state.requests += 1;
+        Ok(())
+    }
+
+    /// Start a request, waiting for the rate limit if needed
+    ///
+    /// Waits through the context's [`RuntimeEnv`](crate::env::RuntimeEnv), and
+    /// gives up with [`QuotaError::RateLimited`] if the wait would pass the
+    /// context's deadline. Exhausted budgets fail at once.
+    pub async fn acquire(&self, context: &ExecutionContext) -> Result<(), QuotaError> {
+        loop {
+            let retry_after = match self.try_acquire(context.env().now()) {
+                Err(QuotaError::RateLimited { retry_after, .. }) => retry_after,
+                result => return result,
+            };
+            if context
+                .remaining()
+                .is_some_and(|remaining| remaining < retry_after)
+            {
+                return Err(QuotaError::RateLimited {
+                    tenant: self.id.clone(),
+                    retry_after,
+                });
+            }
+            context.env().sleep(retry_after).await;
+        }
+    }
+}
+
+/// A backend storing every key under a tenant's prefix
+///
+/// Only the tenant's own keys are visible: `keys`, `len` and `clear` ignore
+/// everything outside the prefix.
+#[derive(Debug, Clone)]
+pub struct TenantStorage<S> {
+    inner: S,
+    prefix: String,
+}
+
+impl<S: StorageBackend> TenantStorage<S> {
+    /// Wrap `inner`, storing keys under `prefix`
+    pub fn new(inner: S, prefix: impl Into<String>) -> Self {
+        Self {
+            inner,
+            prefix: prefix.into(),
+        }
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwrap the backend
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+impl<S: StorageBackend> StorageBackend for TenantStorage<S> {
+    type Error = S::Error;
+
+    fn set(&mut self, key: String, value: Value) -> Result<(), Self::Error> {
+        let key = self.key(&key);
+        self.inner.set(key, value)
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Value>, Self::Error> {
+        self.inner.get(&self.key(key))
+    }
+
+    fn remove(&mut self, key: &str) -> Result<Option<Value>, Self::Error> {
+        let key = self.key(key);
+        self.inner.remove(&key)
+    }
+
+    fn contains_key(&self, key: &str) -> Result<bool, Self::Error> {
+        self.inner.contains_key(&self.key(key))
+    }
+
+    fn keys(&self) -> Result<Vec<String>, Self::Error> {
+        Ok(self
+            .inner
+            .keys()?
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string))
+            .collect())
+    }
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        for key in self.keys()? {
+            self.remove(&key)?;
+        }
+        Ok(())
+    }
+
+    fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.keys()?.len())
+    }
+
+    fn health_check(&self) -> Result<(), Self::Error> {
+        self.inner.health_check()
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush()
+    }
+
+    fn update(
+        &mut self,
+        key: &str,
+        f: &mut dyn FnMut(Option<&Value>) -> Option<Value>,
+    ) -> Result<Option<Value>, Self::Error> {
+        let key = self.key(key);
+        self.inner.update(&key, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::RuntimeEnv;
+    use crate::shared_store::SharedStore;
+    use crate::storage::ConcurrentInMemoryStorage;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_tenant_scoping_and_quotas() {
+        // Two tenants sharing one backend see only their own keys
+        let backend = ConcurrentInMemoryStorage::new();
+        let acme = TenantContext::new("acme").with_quota(
+            TenantQuota::new()
+                .with_rate_limit(2, Duration::from_secs(60))
+                .with_token_budget(100),
+        );
+        let globex = TenantContext::new("globex");
+        let mut acme_store = SharedStore::with_storage(acme.scope(backend.clone()));
+        let mut globex_store = SharedStore::with_storage(globex.scope(backend.clone()));
+        acme_store.set("plan".to_string(), json!("pro")).unwrap();
+        globex_store.set("plan".to_string(), json!("free")).unwrap();
+        assert_eq!(acme_store.get("plan").unwrap(), Some(json!("pro")));
+        assert_eq!(globex_store.keys().unwrap(), vec!["plan".to_string()]);
+        acme_store.clear().unwrap();
+        assert_eq!(
+            backend.keys().unwrap(),
+            vec!["tenant:globex:plan".to_string()]
+        );
+
+        // The third request in a window waits for the first to expire
+        let env = RuntimeEnv::deterministic(1);
+        let context = ExecutionContext::new(0, Duration::ZERO).with_env(env.clone());
+        let start = env.now();
+        for _ in 0..4 {
+            acme.acquire(&context).await.unwrap();
+        }
+        assert_eq!(env.now() - start, Duration::from_secs(60));
+        assert_eq!(acme.requests_made(), 4);
+
+        // A wait past the deadline is refused
+        let hurried = context.clone().with_deadline(Some(env.now()));
+        assert!(matches!(
+            acme.acquire(&hurried).await,
+            Err(QuotaError::RateLimited { .. })
+        ));
+
+        // Spent budgets fail at once and are fatal to nodes
+        acme.record_tokens(100);
+        let error = acme.acquire(&context).await.unwrap_err();
+        assert!(matches!(
+            error,
+            QuotaError::BudgetExceeded {
+                resource: "token",
+                limit: 100,
+                ..
+            }
+        ));
+        assert!(!NodeError::from(error).is_retryable());
+        assert_eq!(globex.try_acquire(env.now()), Ok(()));
+
+        let capped =
+            TenantContext::new("initech").with_quota(TenantQuota::new().with_cost_budget(1.0));
+        capped.record_usage(&TokenUsage {
+            requests: 1,
+            input_tokens: 10,
+            output_tokens: 5,
+            cost: 1.5,
+        });
+        assert_eq!(capped.tokens_used(), 15);
+        assert_eq!(
+            capped.try_acquire(env.now()),
+            Err(QuotaError::CostBudgetExceeded {
+                tenant: "initech".to_string(),
+                limit: 1.0,
+            })
+        );
+    }
+}
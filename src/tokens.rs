@@ -0,0 +1,219 @@
+//! Counting, truncating and chunking text by tokens
+//!
+//! A [`TokenCounter`] counts the tokens of a text for some model, and on top
+//! of that cuts a text down to a number of tokens or splits it into chunks of
+//! at most that many, breaking at paragraphs, lines, sentences or words where
+//! it can. [`EstimateCounter`] assumes about four characters per token; with
+//! the `tiktoken` feature, [`TiktokenCounter`] uses the model's BPE
+//! vocabulary and is what [`for_model`] picks.
+//!
+//! ```rust
+//! # use pocketflow_rs::tokens::{self, TokenCounter};
+//! let counter = tokens::for_model("gpt-4o");
+//! let text = "First paragraph, long enough to matter.\n\nSecond one.";
+//! assert!(counter.count(text) > 0);
+//! assert!(counter.count(counter.truncate(text, 5)) <= 5);
+//! for chunk in counter.split(text, 10) {
+//!     assert!(counter.count(chunk) <= 10);
+//! }
+//! ```
+
+use std::sync::Arc;
+
+/// Counts the tokens of a text
+pub trait TokenCounter: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+
+    /// Length in bytes of the longest start of `text` within `max_tokens`
+    ///
+    /// The default implementation searches over character boundaries with
+    /// [`TokenCounter::count`], assuming longer text never counts fewer
+    /// tokens.
+    fn prefix_len(&self, text: &str, max_tokens: usize) -> usize {
+        if self.count(text) <= max_tokens {
+            return text.len();
+        }
+        let boundaries: Vec<usize> = text.char_indices().map(|(index, _)| index).collect();
+        // The first `low` characters fit, the first `high` don't
+        let (mut low, mut high) = (0, boundaries.len());
+        while high - low > 1 {
+            let middle = (low + high) / 2;
+            if self.count(&text[..boundaries[middle]]) <= max_tokens {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        boundaries[low]
+    }
+
+    /// The longest start of `text` within `max_tokens`
+    fn truncate<'a>(&self, text: &'a str, max_tokens: usize) -> &'a str {
+        &text[..self.prefix_len(text, max_tokens)]
+    }
+
+    /// Split `text` into chunks of at most `max_tokens`, trimmed of
+    /// surrounding whitespace
+    ///
+    /// A chunk ends at the last paragraph break that fits, else the last line
+    /// break, sentence end or space, as long as that keeps at least half of
+    /// what would fit. A single character over the limit becomes its own
+    /// chunk.
+    fn split<'a>(&self, text: &'a str, max_tokens: usize) -> Vec<&'a str> {
+        let mut chunks = Vec::new();
+        let mut rest = text.trim();
+        while !rest.is_empty() {
+            let fit = self.prefix_len(rest, max_tokens);
+            let end = if fit == rest.len() {
+                fit
+            } else if fit == 0 {
+                rest.chars().next().map_or(0, char::len_utf8)
+            } else {
+                break_point(&rest[..fit]).unwrap_or(fit)
+            };
+            let chunk = rest[..end].trim();
+            if !chunk.is_empty() {
+                chunks.push(chunk);
+            }
+            rest = rest[end..].trim_start();
+        }
+        chunks
+    }
+}
+
+/// Where to end a chunk within `text`, preferring stronger breaks
+fn break_point(text: &str) -> Option<usize> {
+    let min = text.len() / 2;
+    ["\n\n", "\n", ". ", "! ", "? ", " "]
+        .iter()
+        .find_map(|separator| {
+            let end = text.rfind(separator)? + separator.len();
+            (end > min).then_some(end)
+        })
+}
+
+/// Approximates four characters per token
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EstimateCounter;
+
+impl TokenCounter for EstimateCounter {
+    fn count(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+
+    fn prefix_len(&self, text: &str, max_tokens: usize) -> usize {
+        text.char_indices()
+            .nth(max_tokens.saturating_mul(4))
+            .map_or(text.len(), |(index, _)| index)
+    }
+}
+
+/// Counts tokens with a tiktoken BPE vocabulary (feature `tiktoken`)
+#[cfg(feature = "tiktoken")]
+#[derive(Clone, Copy)]
+pub struct TiktokenCounter {
+    bpe: &'static tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenCounter {
+    /// The vocabulary used by `model`, falling back to `cl100k_base`
+    pub fn for_model(model: &str) -> Self {
+        use tiktoken_rs::tokenizer::{Tokenizer, get_tokenizer};
+        let bpe = match get_tokenizer(model) {
+            Some(Tokenizer::O200kBase) => tiktoken_rs::o200k_base_singleton(),
+            _ => tiktoken_rs::cl100k_base_singleton(),
+        };
+        Self { bpe }
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenCounter for TiktokenCounter {
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+
+    /// Ends after the last whole character of the first `max_tokens` tokens
+    fn prefix_len(&self, text: &str, max_tokens: usize) -> usize {
+        let mut tokens = self.bpe.encode_with_special_tokens(text);
+        if tokens.len() <= max_tokens {
+            return text.len();
+        }
+        tokens.truncate(max_tokens);
+        let mut end: usize = self
+            .bpe
+            ._decode_native_and_split(tokens)
+            .map(|bytes| bytes.len())
+            .sum();
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        end
+    }
+}
+
+/// The best counter available for `model`: its tokenizer with the `tiktoken`
+/// feature, an estimate otherwise
+pub fn for_model(model: &str) -> Arc<dyn TokenCounter> {
+    #[cfg(feature = "tiktoken")]
+    return Arc::new(TiktokenCounter::for_model(model));
+    #[cfg(not(feature = "tiktoken"))]
+    {
+        let _ = model;
+        Arc::new(EstimateCounter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One token per word, to exercise the default `prefix_len`
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn count(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+    }
+
+    #[test]
+    fn test_truncate_and_split() {
+        assert_eq!(EstimateCounter.truncate("abcdefghij", 2), "abcdefgh");
+        assert_eq!(EstimateCounter.truncate("héllo", 1), "héll");
+        assert_eq!(WordCounter.truncate("one two three four", 2), "one two ");
+        assert_eq!(WordCounter.truncate("short", 2), "short");
+
+        let text = "One two three. Four five six seven.\n\nEight nine ten eleven twelve.";
+        assert_eq!(
+            WordCounter.split(text, 8),
+            vec![
+                "One two three. Four five six seven.",
+                "Eight nine ten eleven twelve."
+            ]
+        );
+        assert_eq!(
+            WordCounter.split(text, 4),
+            vec![
+                "One two three.",
+                "Four five six seven.",
+                "Eight nine ten eleven",
+                "twelve."
+            ]
+        );
+        assert_eq!(EstimateCounter.split("abcdefgh", 1), vec!["abcd", "efgh"]);
+        // Nothing fits: one character per chunk
+        assert_eq!(WordCounter.split("ab", 0), vec!["a", "b"]);
+        assert!(EstimateCounter.split(" \n ", 4).is_empty());
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_tiktoken_counter() {
+        assert_eq!(TiktokenCounter::for_model("gpt-4o").count("hello world"), 2);
+        assert_eq!(TiktokenCounter::for_model("gpt-4").count("hello world"), 2);
+        let counter = for_model("gpt-4o");
+        assert_eq!(counter.truncate("hello world again", 2), "hello world");
+    }
+}
@@ -0,0 +1,553 @@
+//! Terminal dashboard for running flows (feature `tui`)
+//!
+//! A [`Dashboard`] consumes the [`FlowEvent`] stream of a flow and renders
+//! live progress, per-node latencies, streamed output, the contents of the
+//! store and the recorded token usage. Attach it to a flow with
+//! `BasicFlow::subscribe` and run it next to the flow:
+//!
+//! ```rust,no_run
+//! # use pocketflow_rs::prelude::*;
+//! # use pocketflow_rs::flow::BasicFlow;
+//! # use pocketflow_rs::tui::Dashboard;
+//! # async fn demo(mut flow: BasicFlow<ConcurrentInMemoryStorage>) -> std::io::Result<()> {
+//! let storage = ConcurrentInMemoryStorage::new();
+//! let events = flow.subscribe();
+//! let dashboard = Dashboard::new("ingest")
+//!     .with_store(SharedStoreHandle::from_storage(storage.clone()));
+//!
+//! let mut store = SharedStore::with_storage(storage);
+//! tokio::spawn(async move { flow.execute(&mut store).await });
+//! dashboard.run(events).await?; // `q` quits, arrow keys browse the store
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The store browser reads through a [`SharedStoreHandle`], so share a
+//! [`ConcurrentInMemoryStorage`](crate::storage::ConcurrentInMemoryStorage)
+//! between the handle and the flow's store to see its keys live.
+
+use crate::flow::{FlowEvent, FlowEventReceiver};
+use crate::redact::Redactor;
+use crate::shared_store::{SharedStoreHandle, SystemKeys, TokenUsage};
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Row, Table, Wrap};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+
+/// Number of event log lines kept by default
+const DEFAULT_LOG_LINES: usize = 200;
+
+/// Overall state of the watched run
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunStatus {
+    /// No event received yet
+    Waiting,
+    /// A node is running at the given step
+    Running { step: usize },
+    /// The flow reached a terminal action
+    Completed { final_action: String, steps: usize },
+    /// The flow stopped with an error
+    Failed { error: String },
+}
+
+/// State of a single node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeState {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Progress and latency of a node across its runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeProgress {
+    pub node_id: String,
+    pub state: NodeState,
+    /// Completed runs
+    pub runs: u32,
+    /// Duration of the most recent completed run
+    pub last_ms: Option<u64>,
+    /// Total duration of all completed runs
+    pub total_ms: u64,
+    /// Action returned by the most recent completed run
+    pub last_action: Option<String>,
+    /// Error of the most recent failed run
+    pub error: Option<String>,
+}
+
+impl NodeProgress {
+    /// Mean duration of the completed runs
+    pub fn average_ms(&self) -> Option<u64> {
+        (self.runs > 0).then(|| self.total_ms / u64::from(self.runs))
+    }
+}
+
+/// Live view of a flow run
+#[derive(Debug)]
+pub struct Dashboard {
+    title: String,
+    status: RunStatus,
+    nodes: Vec<NodeProgress>,
+    log: VecDeque<String>,
+    max_log_lines: usize,
+    output: String,
+    store: Option<SharedStoreHandle>,
+    entries: Vec<(String, Value)>,
+    redactor: Option<Redactor>,
+    selected: ListState,
+    usage: TokenUsage,
+    tick_rate: Duration,
+}
+
+impl Dashboard {
+    /// Create a dashboard titled after the watched flow
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            status: RunStatus::Waiting,
+            nodes: Vec::new(),
+            log: VecDeque::new(),
+            max_log_lines: DEFAULT_LOG_LINES,
+            output: String::new(),
+            store: None,
+            entries: Vec::new(),
+            redactor: None,
+            selected: ListState::default(),
+            usage: TokenUsage::default(),
+            tick_rate: Duration::from_millis(100),
+        }
+    }
+
+    /// Browse the keys of a store and show its recorded token usage
+    pub fn with_store(mut self, store: SharedStoreHandle) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Scrub store values before they are shown
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Set how often the screen is redrawn (default 100ms)
+    pub fn with_tick_rate(mut self, tick_rate: Duration) -> Self {
+        self.tick_rate = tick_rate;
+        self
+    }
+
+    /// Set how many event log lines are kept (default 200)
+    pub fn with_max_log_lines(mut self, max_log_lines: usize) -> Self {
+        self.max_log_lines = max_log_lines;
+        self
+    }
+
+    /// Overall state of the run
+    pub fn status(&self) -> &RunStatus {
+        &self.status
+    }
+
+    /// Progress of every node seen so far, in order of first execution
+    pub fn nodes(&self) -> &[NodeProgress] {
+        &self.nodes
+    }
+
+    /// Progress of a node
+    pub fn node(&self, node_id: &str) -> Option<&NodeProgress> {
+        self.nodes.iter().find(|node| node.node_id == node_id)
+    }
+
+    /// Text streamed by nodes through `FlowEvent::TokenDelta`
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Token usage read from the store at the last refresh
+    pub fn usage(&self) -> TokenUsage {
+        self.usage
+    }
+
+    /// Update the view with an event
+    pub fn apply(&mut self, event: &FlowEvent) {
+        match event {
+            FlowEvent::NodeStarted { node_id, step } => {
+                self.status = RunStatus::Running { step: *step };
+                self.node_mut(node_id).state = NodeState::Running;
+                self.push_log(format!("#{} {} started", step, node_id));
+            }
+            FlowEvent::NodeCompleted {
+                node_id,
+                action,
+                duration_ms,
+            } => {
+                let node = self.node_mut(node_id);
+                node.state = NodeState::Completed;
+                node.runs += 1;
+                node.last_ms = Some(*duration_ms);
+                node.total_ms += duration_ms;
+                node.last_action = Some(action.clone());
+                self.push_log(format!("{} -> {} ({}ms)", node_id, action, duration_ms));
+            }
+            FlowEvent::NodeFailed { node_id, error } => {
+                let node = self.node_mut(node_id);
+                node.state = NodeState::Failed;
+                node.error = Some(error.clone());
+                self.push_log(format!("{} failed: {}", node_id, error));
+            }
+            FlowEvent::TokenDelta { text } => self.output.push_str(text),
+            FlowEvent::FlowCompleted {
+                final_action,
+                steps,
+            } => {
+                self.status = RunStatus::Completed {
+                    final_action: final_action.clone(),
+                    steps: *steps,
+                };
+                self.push_log(format!("flow completed with '{}'", final_action));
+            }
+            FlowEvent::FlowFailed { error } => {
+                self.status = RunStatus::Failed {
+                    error: error.clone(),
+                };
+                self.push_log(format!("flow failed: {}", error));
+            }
+        }
+    }
+
+    /// Re-read the store's keys and token usage
+    pub fn refresh_store(&mut self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let mut keys = store.keys();
+        keys.sort();
+        self.entries = keys
+            .into_iter()
+            .filter_map(|key| store.get(&key).map(|value| (key, value)))
+            .map(|(key, value)| match &self.redactor {
+                Some(redactor) => {
+                    let value = redactor.redact_entry(&key, &value);
+                    (key, value)
+                }
+                None => (key, value),
+            })
+            .collect();
+        self.usage = store
+            .get(SystemKeys::USAGE)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default();
+
+        match self.selected.selected() {
+            _ if self.entries.is_empty() => self.selected.select(None),
+            None => self.selected.select(Some(0)),
+            Some(index) if index >= self.entries.len() => {
+                self.selected.select(Some(self.entries.len() - 1))
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Handle a key press, returning `false` when the dashboard should close
+    pub fn handle_key(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Down | KeyCode::Char('j') if !self.entries.is_empty() => {
+                let next = self
+                    .selected
+                    .selected()
+                    .map_or(0, |index| (index + 1).min(self.entries.len() - 1));
+                self.selected.select(Some(next));
+            }
+            KeyCode::Up | KeyCode::Char('k') if !self.entries.is_empty() => {
+                let previous = self
+                    .selected
+                    .selected()
+                    .map_or(0, |index| index.saturating_sub(1));
+                self.selected.select(Some(previous));
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Draw the dashboard into a frame
+    pub fn render(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(10),
+        ])
+        .areas(frame.area());
+        let [nodes, store] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(body);
+        let [log, output] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(footer);
+
+        self.render_header(frame, header);
+        self.render_nodes(frame, nodes);
+        self.render_store(frame, store);
+        self.render_log(frame, log);
+        frame.render_widget(
+            Paragraph::new(self.output.as_str())
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(" Output ")),
+            output,
+        );
+    }
+
+    /// Render until `q` is pressed, applying events from `events` as they
+    /// arrive, and return the final state
+    ///
+    /// Takes over the terminal (raw mode, alternate screen) and restores it
+    /// before returning.
+    pub async fn run(mut self, mut events: FlowEventReceiver) -> io::Result<Self> {
+        let mut terminal = ratatui::init();
+        let result = async {
+            loop {
+                while let Ok(event) = events.try_recv() {
+                    self.apply(&event);
+                }
+                self.refresh_store();
+                terminal.draw(|frame| self.render(frame))?;
+
+                while event::poll(Duration::ZERO)? {
+                    if let Event::Key(key) = event::read()?
+                        && key.kind == KeyEventKind::Press
+                        && !self.handle_key(key.code)
+                    {
+                        return Ok(());
+                    }
+                }
+                tokio::time::sleep(self.tick_rate).await;
+            }
+        }
+        .await;
+        ratatui::restore();
+        result.map(|()| self)
+    }
+
+    fn node_mut(&mut self, node_id: &str) -> &mut NodeProgress {
+        let index = match self.nodes.iter().position(|node| node.node_id == node_id) {
+            Some(index) => index,
+            None => {
+                self.nodes.push(NodeProgress {
+                    node_id: node_id.to_string(),
+                    state: NodeState::Running,
+                    runs: 0,
+                    last_ms: None,
+                    total_ms: 0,
+                    last_action: None,
+                    error: None,
+                });
+                self.nodes.len() - 1
+            }
+        };
+        &mut self.nodes[index]
+    }
+
+    fn push_log(&mut self, line: String) {
+        self.log.push_back(line);
+        while self.log.len() > self.max_log_lines {
+            self.log.pop_front();
+        }
+    }
+
+    fn render_header(&self, frame: &mut Frame, area: Rect) {
+        let (status, color) = match &self.status {
+            RunStatus::Waiting => ("waiting".to_string(), Color::Gray),
+            RunStatus::Running { step } => (format!("running step {}", step), Color::Yellow),
+            RunStatus::Completed {
+                final_action,
+                steps,
+            } => (
+                format!("completed '{}' after {} steps", final_action, steps),
+                Color::Green,
+            ),
+            RunStatus::Failed { error } => (format!("failed: {}", error), Color::Red),
+        };
+        let mut usage = format!(
+            "  tokens {} in / {} out, {} requests",
+            self.usage.input_tokens, self.usage.output_tokens, self.usage.requests
+        );
+        if self.usage.cost > 0.0 {
+            usage.push_str(&format!(", ~${:.4}", self.usage.cost));
+        }
+        let line = Line::from(vec![
+            Span::styled(status, Style::new().fg(color).add_modifier(Modifier::BOLD)),
+            Span::raw(usage),
+        ]);
+        frame.render_widget(
+            Paragraph::new(line).block(Block::bordered().title(format!(" {} ", self.title))),
+            area,
+        );
+    }
+
+    fn render_nodes(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.nodes.iter().map(|node| {
+            let (state, color) = match node.state {
+                NodeState::Running => ("running", Color::Yellow),
+                NodeState::Completed => ("done", Color::Green),
+                NodeState::Failed => ("failed", Color::Red),
+            };
+            let ms = |value: Option<u64>| value.map_or("-".to_string(), |ms| format!("{}ms", ms));
+            Row::new(vec![
+                node.node_id.clone(),
+                state.to_string(),
+                node.runs.to_string(),
+                ms(node.last_ms),
+                ms(node.average_ms()),
+                node.last_action.clone().unwrap_or_default(),
+            ])
+            .style(Style::new().fg(color))
+        });
+        let widths = [
+            Constraint::Fill(2),
+            Constraint::Length(8),
+            Constraint::Length(5),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Fill(1),
+        ];
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new(["node", "state", "runs", "last", "avg", "action"])
+                    .style(Style::new().add_modifier(Modifier::BOLD)),
+            )
+            .block(Block::bordered().title(" Nodes "));
+        frame.render_widget(table, area);
+    }
+
+    fn render_store(&mut self, frame: &mut Frame, area: Rect) {
+        let [keys, value] =
+            Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(area);
+
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|(key, _)| ListItem::new(key.as_str()))
+            .collect();
+        let title = if self.store.is_some() {
+            " Store "
+        } else {
+            " Store (not attached) "
+        };
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, keys, &mut self.selected);
+
+        let selected = self
+            .selected
+            .selected()
+            .and_then(|index| self.entries.get(index))
+            .map(|(_, value)| serde_json::to_string_pretty(value).unwrap_or_default())
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(selected)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(" Value ")),
+            value,
+        );
+    }
+
+    fn render_log(&self, frame: &mut Frame, area: Rect) {
+        // Show the most recent lines that fit
+        let visible = area.height.saturating_sub(2) as usize;
+        let lines: Vec<Line> = self
+            .log
+            .iter()
+            .skip(self.log.len().saturating_sub(visible))
+            .map(|line| Line::raw(line.as_str()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Events ")),
+            area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use serde_json::json;
+
+    #[test]
+    fn test_dashboard_tracks_events_and_renders() {
+        let store = SharedStoreHandle::new();
+        store.set("answer".to_string(), json!(42));
+        store.set(
+            SystemKeys::USAGE.to_string(),
+            json!({"requests": 1, "input_tokens": 12, "output_tokens": 7, "cost": 0.0012}),
+        );
+        let mut dashboard = Dashboard::new("demo").with_store(store);
+
+        for event in [
+            FlowEvent::NodeStarted {
+                node_id: "fetch".to_string(),
+                step: 1,
+            },
+            FlowEvent::NodeCompleted {
+                node_id: "fetch".to_string(),
+                action: "next".to_string(),
+                duration_ms: 30,
+            },
+            FlowEvent::NodeStarted {
+                node_id: "fetch".to_string(),
+                step: 2,
+            },
+            FlowEvent::NodeCompleted {
+                node_id: "fetch".to_string(),
+                action: "end".to_string(),
+                duration_ms: 10,
+            },
+            FlowEvent::TokenDelta {
+                text: "Hello".to_string(),
+            },
+            FlowEvent::FlowCompleted {
+                final_action: "end".to_string(),
+                steps: 2,
+            },
+        ] {
+            dashboard.apply(&event);
+        }
+        dashboard.refresh_store();
+
+        let fetch = dashboard.node("fetch").unwrap();
+        assert_eq!(fetch.runs, 2);
+        assert_eq!(fetch.average_ms(), Some(20));
+        assert_eq!(fetch.last_action.as_deref(), Some("end"));
+        assert!(matches!(
+            dashboard.status(),
+            RunStatus::Completed { steps: 2, .. }
+        ));
+        assert_eq!(dashboard.usage().total_tokens(), 19);
+        assert_eq!(dashboard.output(), "Hello");
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| dashboard.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("completed 'end' after 2 steps"));
+        assert!(screen.contains("~$0.0012"));
+        assert!(screen.contains("fetch"));
+        assert!(screen.contains("answer"));
+
+        assert!(dashboard.handle_key(KeyCode::Down));
+        assert!(!dashboard.handle_key(KeyCode::Char('q')));
+    }
+}
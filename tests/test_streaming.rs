@@ -14,17 +14,10 @@ async fn test_api_request_node_streaming() {
 
     // Create API config with streaming enabled
     let api_config = ApiConfig {
-        api_key: "test_key".to_string(),
-        base_url: None,
-        org_id: None,
-        model: "gpt-3.5-turbo".to_string(),
+        api_key: "test_key".into(),
         max_tokens: Some(100),
-        temperature: Some(0.7),
-        top_p: None,
-        frequency_penalty: None,
-        presence_penalty: None,
-        timeout: Some(30),
         stream: true, // Enable streaming
+        ..Default::default()
     };
 
     // Create the API request node
@@ -59,9 +52,9 @@ async fn test_api_request_node_streaming() {
             )
             .await
             {
-                Ok(result) => {
+                Ok((result, _)) => {
                     println!("Streaming response received: {}", result);
-                    assert!(!result.is_empty());
+                    assert!(!result.is_null());
                 }
                 Err(e) => {
                     // Expected to fail without proper API credentials
@@ -90,17 +83,10 @@ async fn test_api_request_node_non_streaming() {
 
     // Create API config with streaming disabled
     let api_config = ApiConfig {
-        api_key: "test_key".to_string(),
-        base_url: None,
-        org_id: None,
-        model: "gpt-3.5-turbo".to_string(),
+        api_key: "test_key".into(),
         max_tokens: Some(100),
-        temperature: Some(0.7),
-        top_p: None,
-        frequency_penalty: None,
-        presence_penalty: None,
-        timeout: Some(30),
         stream: false, // Disable streaming
+        ..Default::default()
     };
 
     // Create the API request node
@@ -135,9 +121,9 @@ async fn test_api_request_node_non_streaming() {
             )
             .await
             {
-                Ok(result) => {
+                Ok((result, _)) => {
                     println!("Non-streaming response received: {}", result);
-                    assert!(!result.is_empty());
+                    assert!(!result.is_null());
                 }
                 Err(e) => {
                     // Expected to fail without proper API credentials